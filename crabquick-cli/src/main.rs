@@ -1,6 +1,21 @@
 //! CrabQuick command-line interface
 
-use crabquick::Engine;
+mod readline;
+mod repl;
+
+use crabquick::{Engine, EngineError, ErrorKind, LoadedScript};
+
+/// Heap size used for scripts and REPL sessions (64 KB)
+const HEAP_SIZE: usize = 65536;
+
+/// Exit status when the script runs to completion
+const EXIT_SUCCESS: i32 = 0;
+/// Exit status for an uncaught exception, bad arguments or unreadable files
+const EXIT_RUNTIME_ERROR: i32 = 1;
+/// Exit status when the script fails to compile
+const EXIT_COMPILE_ERROR: i32 = 2;
+/// Exit status when the script runs out of heap or stack
+const EXIT_RESOURCE_LIMIT: i32 = 3;
 
 fn main() {
     // Parse command-line arguments
@@ -8,30 +23,66 @@ fn main() {
 
     if args.len() < 2 {
         print_usage();
-        std::process::exit(1);
+        std::process::exit(EXIT_RUNTIME_ERROR);
     }
 
     // Handle different command-line options
     match args[1].as_str() {
         "--help" | "-h" => {
             print_usage();
-            std::process::exit(0);
+            std::process::exit(EXIT_SUCCESS);
         }
         "--version" | "-v" => {
             println!("CrabQuick v{}", env!("CARGO_PKG_VERSION"));
-            std::process::exit(0);
+            std::process::exit(EXIT_SUCCESS);
         }
         "-e" | "--eval" => {
             if args.len() < 3 {
                 eprintln!("Error: -e requires a script argument");
                 print_usage();
-                std::process::exit(1);
+                std::process::exit(EXIT_RUNTIME_ERROR);
             }
             eval_script(&args[2]);
         }
+        "--inspect" => {
+            if args.len() < 3 {
+                eprintln!("Error: --inspect requires a file argument");
+                print_usage();
+                std::process::exit(EXIT_RUNTIME_ERROR);
+            }
+            inspect_script(&args[2]);
+        }
+        "--dump-bytecode" => {
+            if args.len() < 3 {
+                eprintln!("Error: --dump-bytecode requires a file argument");
+                print_usage();
+                std::process::exit(EXIT_RUNTIME_ERROR);
+            }
+            dump_bytecode(&args[2]);
+        }
+        "--emit-hash" => {
+            if args.len() < 3 {
+                eprintln!("Error: --emit-hash requires a file argument");
+                print_usage();
+                std::process::exit(EXIT_RUNTIME_ERROR);
+            }
+            emit_hash(&args[2]);
+        }
+        "--heap-census" => {
+            if args.len() < 3 {
+                eprintln!("Error: --heap-census requires a file argument");
+                print_usage();
+                std::process::exit(EXIT_RUNTIME_ERROR);
+            }
+            let source = std::fs::read_to_string(&args[2]).unwrap_or_else(|e| {
+                eprintln!("Error reading file '{}': {}", args[2], e);
+                std::process::exit(EXIT_RUNTIME_ERROR);
+            });
+            let mut engine = run_script(&source);
+            print_heap_census(&mut engine);
+        }
         "--repl" => {
-            eprintln!("REPL mode not yet implemented");
-            std::process::exit(1);
+            repl::run_repl(HEAP_SIZE);
         }
         filename => {
             // Try to read and execute a script file
@@ -39,7 +90,7 @@ fn main() {
                 Ok(source) => eval_script(&source),
                 Err(e) => {
                     eprintln!("Error reading file '{}': {}", filename, e);
-                    std::process::exit(1);
+                    std::process::exit(EXIT_RUNTIME_ERROR);
                 }
             }
         }
@@ -50,11 +101,26 @@ fn print_usage() {
     println!("CrabQuick v{} - A minimal JavaScript engine", env!("CARGO_PKG_VERSION"));
     println!();
     println!("Usage:");
-    println!("  crabquick -e <script>     Evaluate JavaScript code");
-    println!("  crabquick <script.js>     Execute JavaScript file");
-    println!("  crabquick --repl          Start interactive REPL (not yet implemented)");
-    println!("  crabquick --help          Show this help message");
-    println!("  crabquick --version       Show version information");
+    println!("  crabquick -e <script>      Evaluate JavaScript code");
+    println!("  crabquick <script.js>      Execute JavaScript file");
+    println!("  crabquick --inspect <file> List the functions in a .js or .cqb file");
+    println!("  crabquick --dump-bytecode <script.js>");
+    println!("                             Compile without running and print the bytecode");
+    println!("  crabquick --emit-hash <script.js>");
+    println!("                             Compile without running and print the bytecode's fingerprint");
+    println!("  crabquick --heap-census <script.js>");
+    println!("                             Run a script, then count what's left on the heap");
+    println!("  crabquick --repl           Start interactive REPL");
+    println!("  crabquick --help           Show this help message");
+    println!("  crabquick --version        Show version information");
+    println!();
+    println!("Scripts can end with a chosen status by calling exit(code).");
+    println!();
+    println!("Exit status:");
+    println!("  0  success");
+    println!("  1  uncaught exception (or bad arguments)");
+    println!("  2  compile error");
+    println!("  3  out of memory or stack");
     println!();
     println!("Examples:");
     println!("  crabquick -e \"1 + 2\"");
@@ -63,20 +129,155 @@ fn print_usage() {
 }
 
 fn eval_script(source: &str) {
+    run_script(source);
+}
+
+/// Runs a script and prints its result, exiting on failure
+fn run_script(source: &str) -> Engine {
+    let script = LoadedScript::compile(source).unwrap_or_else(|e| {
+        eprintln!("{}", EngineError::from(e));
+        std::process::exit(EXIT_COMPILE_ERROR);
+    });
+
     // Create engine with 64 KB memory (enough for most scripts)
-    let mut engine = Engine::new(65536);
+    let mut engine = Engine::new(HEAP_SIZE);
+    if engine.enable_exit().is_err() {
+        eprintln!("Error: out of memory");
+        std::process::exit(EXIT_RESOURCE_LIMIT);
+    }
 
-    // Execute the script
-    match engine.eval_as_string(source) {
-        Ok(result) => {
+    match engine.run(&script) {
+        Ok(value) => {
             // Only print non-undefined results
-            if result != "undefined" {
-                println!("{}", result);
+            if !value.is_undefined() {
+                println!("{}", engine.value_to_string(value));
             }
         }
-        Err(error) => {
-            eprintln!("Error: {}", error);
-            std::process::exit(1);
+        Err(error) => std::process::exit(report_error(&engine, &error)),
+    }
+    engine
+}
+
+/// Collects garbage and prints the blocks still on the heap by kind
+fn print_heap_census(engine: &mut Engine) {
+    engine.gc();
+    let census = engine.heap_census();
+    println!("{:<18} {:>8} {:>10}", "kind", "blocks", "bytes");
+    for (tag, entry) in &census {
+        println!("{:<18} {:>8} {:>10}", format!("{:?}", tag), entry.count, entry.bytes);
+    }
+    let blocks: usize = census.iter().map(|(_, entry)| entry.count).sum();
+    let bytes: usize = census.iter().map(|(_, entry)| entry.bytes).sum();
+    println!("{:<18} {:>8} {:>10}", "total", blocks, bytes);
+
+    #[cfg(feature = "alloc-origin")]
+    {
+        println!();
+        println!("{:<18} {:>8} {:>10}", "String origin", "blocks", "bytes");
+        for (origin, entry) in engine.heap_census_by_origin(crabquick::MemTag::String) {
+            println!("{:<18} {:>8} {:>10}", engine.alloc_origin_name(origin), entry.count, entry.bytes);
         }
     }
 }
+
+/// Prints an error thrown out of a script and returns the exit status for it
+///
+/// `exit(code)` requests end quietly with the script's own status.
+fn report_error(engine: &Engine, error: &EngineError) -> i32 {
+    if let Some(code) = engine.exit_code(error) {
+        return code;
+    }
+
+    eprintln!("{}", error);
+    if let EngineError::Parse { .. } = error {
+        return EXIT_COMPILE_ERROR;
+    }
+    match engine.error_kind(error) {
+        Some(ErrorKind::OutOfMemory | ErrorKind::StackOverflow) => EXIT_RESOURCE_LIMIT,
+        _ => EXIT_RUNTIME_ERROR,
+    }
+}
+
+fn inspect_script(filename: &str) {
+    // .js files are compiled first; anything else is taken as bytecode
+    let script = if filename.ends_with(".js") {
+        let source = std::fs::read_to_string(filename).unwrap_or_else(|e| {
+            eprintln!("Error reading file '{}': {}", filename, e);
+            std::process::exit(EXIT_RUNTIME_ERROR);
+        });
+        LoadedScript::compile(&source).unwrap_or_else(|e| {
+            eprintln!("{}", EngineError::from(e));
+            std::process::exit(EXIT_RUNTIME_ERROR);
+        })
+    } else {
+        let bytecode = std::fs::read(filename).unwrap_or_else(|e| {
+            eprintln!("Error reading file '{}': {}", filename, e);
+            std::process::exit(EXIT_RUNTIME_ERROR);
+        });
+        LoadedScript::owned(bytecode)
+    };
+
+    let manifest = script.manifest().unwrap_or_else(|e| {
+        eprintln!("Error: invalid bytecode: {:?}", e);
+        std::process::exit(EXIT_RUNTIME_ERROR);
+    });
+
+    let constants = manifest.constants();
+    println!("{} bytes, {} functions", manifest.bytecode_size(), manifest.function_count());
+    println!("constants: {} ({} numbers, {} other)", constants.total(), constants.numbers, constants.values);
+    println!("globals: {}", manifest.referenced_globals().join(", "));
+    println!();
+
+    for (index, func) in manifest.functions().iter().enumerate() {
+        let name = match (&func.name, func.parent) {
+            (Some(name), _) => name.clone(),
+            (None, None) => String::from("<script>"),
+            (None, Some(_)) => String::from("<anonymous>"),
+        };
+        let parent = func.parent.map(|p| format!(" in #{}", p)).unwrap_or_default();
+        let arrow = if func.is_arrow { " (arrow)" } else { "" };
+        println!("#{} {}{}{}", index, name, arrow, parent);
+        println!(
+            "    params {}, locals {}, code {} bytes / {} instructions, constants {}, atoms {}",
+            func.param_count,
+            func.local_count,
+            func.code_size,
+            func.instruction_count,
+            func.constants.total(),
+            func.atoms.len(),
+        );
+        if !func.globals.is_empty() {
+            println!("    globals: {}", func.globals.join(", "));
+        }
+    }
+}
+
+fn dump_bytecode(filename: &str) {
+    let source = std::fs::read_to_string(filename).unwrap_or_else(|e| {
+        eprintln!("Error reading file '{}': {}", filename, e);
+        std::process::exit(EXIT_RUNTIME_ERROR);
+    });
+    let script = LoadedScript::compile(&source).unwrap_or_else(|e| {
+        eprintln!("{}", EngineError::from(e));
+        std::process::exit(EXIT_COMPILE_ERROR);
+    });
+
+    print!("{}", crabquick::compiler::debug::disassemble(script.bytecode()));
+}
+
+/// Prints the fingerprint of a script's bytecode as 16 hex digits
+///
+/// Compiling is deterministic, so the same source, compiler version and
+/// features always print the same hash.
+fn emit_hash(filename: &str) {
+    let source = std::fs::read_to_string(filename).unwrap_or_else(|e| {
+        eprintln!("Error reading file '{}': {}", filename, e);
+        std::process::exit(EXIT_RUNTIME_ERROR);
+    });
+    let script = LoadedScript::compile(&source).unwrap_or_else(|e| {
+        eprintln!("{}", EngineError::from(e));
+        std::process::exit(EXIT_COMPILE_ERROR);
+    });
+
+    println!("{:016x}", script.fingerprint());
+}
@@ -1,13 +1,26 @@
 //! Readline integration
+//!
+//! Uses rustyline for line editing and history when the `repl` feature is
+//! enabled, and falls back to plain buffered stdin otherwise.
 
 #[cfg(feature = "repl")]
-use rustyline::Editor;
+use rustyline::{error::ReadlineError, DefaultEditor};
+
+/// Result of reading one line of input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    /// A line of input (without the trailing newline)
+    Line(String),
+    /// The user pressed Ctrl-C; pending input should be discarded
+    Interrupted,
+    /// End of input (Ctrl-D or closed stdin)
+    Eof,
+}
 
 /// Readline wrapper
 pub struct Readline {
-    // TODO: Wrap rustyline editor
     #[cfg(feature = "repl")]
-    _editor: Editor<()>,
+    editor: Option<DefaultEditor>,
 }
 
 impl Readline {
@@ -15,8 +28,9 @@ impl Readline {
     pub fn new() -> Self {
         #[cfg(feature = "repl")]
         {
+            // Fall back to plain stdin if the terminal cannot be set up
             Readline {
-                _editor: Editor::<()>::new().unwrap(),
+                editor: DefaultEditor::new().ok(),
             }
         }
         #[cfg(not(feature = "repl"))]
@@ -25,10 +39,25 @@ impl Readline {
         }
     }
 
-    /// Reads a line from stdin
-    pub fn read_line(&mut self, _prompt: &str) -> Option<String> {
-        // TODO: Use rustyline if feature enabled
-        None
+    /// Reads a line from stdin, displaying `prompt` first
+    pub fn read_line(&mut self, prompt: &str) -> Input {
+        #[cfg(feature = "repl")]
+        {
+            if let Some(editor) = self.editor.as_mut() {
+                return match editor.readline(prompt) {
+                    Ok(line) => {
+                        if !line.trim().is_empty() {
+                            let _ = editor.add_history_entry(line.as_str());
+                        }
+                        Input::Line(line)
+                    }
+                    Err(ReadlineError::Interrupted) => Input::Interrupted,
+                    Err(_) => Input::Eof,
+                };
+            }
+        }
+
+        read_stdin_line(prompt)
     }
 }
 
@@ -37,3 +66,21 @@ impl Default for Readline {
         Self::new()
     }
 }
+
+/// Reads a line from plain stdin without any line editing
+fn read_stdin_line(prompt: &str) -> Input {
+    use std::io::{BufRead, Write};
+
+    print!("{}", prompt);
+    let _ = std::io::stdout().flush();
+
+    let mut line = String::new();
+    match std::io::stdin().lock().read_line(&mut line) {
+        Ok(0) | Err(_) => Input::Eof,
+        Ok(_) => {
+            let trimmed = line.trim_end_matches(['\n', '\r']).len();
+            line.truncate(trimmed);
+            Input::Line(line)
+        }
+    }
+}
@@ -1,9 +1,139 @@
 //! REPL implementation
+//!
+//! All lines are evaluated against a single `Engine`, so globals defined on
+//! one line (`var x = 5`) remain visible on the next (`x + 1`).
 
-/// Runs the interactive REPL
-pub fn run_repl() {
-    // TODO: Initialize readline
-    // TODO: Read-eval-print loop
-    // TODO: Handle multi-line input
-    println!("REPL not yet implemented");
+use crabquick::Engine;
+
+use crate::readline::{Input, Readline};
+
+/// Prompt shown for a new statement
+const PROMPT: &str = "> ";
+/// Prompt shown while an unterminated statement is being continued
+const CONTINUATION_PROMPT: &str = "... ";
+
+/// Runs the interactive REPL until `.exit` or end of input
+pub fn run_repl(heap_size: usize) {
+    println!("CrabQuick v{}", env!("CARGO_PKG_VERSION"));
+    println!("Type \".exit\" or press Ctrl-D to quit.");
+
+    let mut engine = Engine::new(heap_size);
+    let _ = engine.enable_exit();
+    let mut readline = Readline::new();
+    let mut buffer = String::new();
+
+    loop {
+        let prompt = if buffer.is_empty() { PROMPT } else { CONTINUATION_PROMPT };
+
+        let line = match readline.read_line(prompt) {
+            Input::Line(line) => line,
+            Input::Interrupted => {
+                // Ctrl-C abandons the current (possibly multi-line) input
+                buffer.clear();
+                continue;
+            }
+            Input::Eof => break,
+        };
+
+        if buffer.is_empty() {
+            match line.trim() {
+                "" => continue,
+                ".exit" => break,
+                _ => {}
+            }
+        }
+
+        buffer.push_str(&line);
+        buffer.push('\n');
+
+        if is_incomplete(&buffer) {
+            continue;
+        }
+
+        match engine.eval(&buffer) {
+            Ok(result) => println!("{}", engine.value_to_string(result)),
+            Err(error) => {
+                if let Some(code) = engine.exit_code(&error) {
+                    std::process::exit(code);
+                }
+                eprintln!("{}", error);
+            }
+        }
+        buffer.clear();
+    }
+}
+
+/// Returns true if `source` has unclosed brackets, strings or block comments
+///
+/// This is a lexical approximation used to decide whether to keep reading
+/// lines; the parser still reports any real syntax errors.
+fn is_incomplete(source: &str) -> bool {
+    let bytes = source.as_bytes();
+    let mut depth: i32 = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth -= 1,
+            quote @ (b'"' | b'\'' | b'`') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    } else if bytes[i] == b'\n' && quote != b'`' {
+                        // Unterminated single-line string: let the parser report it
+                        break;
+                    }
+                    i += 1;
+                }
+                if i >= bytes.len() {
+                    return quote == b'`';
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                match source[i + 2..].find("*/") {
+                    Some(end) => i += end + 3,
+                    None => return true,
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    depth > 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_input() {
+        assert!(!is_incomplete("1 + 2\n"));
+        assert!(!is_incomplete("function f() { return 1; }\n"));
+        assert!(!is_incomplete("var s = '{';\n"));
+        assert!(!is_incomplete("var x = 1; // {\n"));
+        assert!(!is_incomplete("/* ( */ 1\n"));
+    }
+
+    #[test]
+    fn test_incomplete_input() {
+        assert!(is_incomplete("function f() {\n"));
+        assert!(is_incomplete("foo(1,\n"));
+        assert!(is_incomplete("var a = [1,\n"));
+        assert!(is_incomplete("/* comment\n"));
+    }
+
+    #[test]
+    fn test_extra_closing_is_complete() {
+        // Let the parser report the syntax error instead of waiting forever
+        assert!(!is_incomplete("}\n"));
+    }
 }
@@ -0,0 +1,172 @@
+//! Exit status contract of the `crabquick` binary
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn eval(source: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_crabquick"))
+        .args(["-e", source])
+        .output()
+        .expect("failed to run crabquick")
+}
+
+fn status(source: &str) -> i32 {
+    eval(source).status.code().expect("terminated by a signal")
+}
+
+#[test]
+fn test_success() {
+    let output = eval("1 + 2");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "3");
+}
+
+#[test]
+fn test_runtime_error() {
+    let output = eval("throw new TypeError('bad')");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("TypeError: bad"));
+}
+
+#[test]
+fn test_compile_error() {
+    let output = eval("var a = 1;\nvar = ;");
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("SyntaxError: "), "{}", stderr);
+    assert!(stderr.trim_end().ends_with(" at line 2, column 5"), "{}", stderr);
+}
+
+#[test]
+fn test_resource_limits() {
+    assert_eq!(status("function f() { return f(); } f()"), 3);
+    assert_eq!(status("var a = []; for (var i = 0; i < 100000; i = i + 1) { a[i] = { x: i }; }"), 3);
+    // Running out of memory inside a builtin is a resource limit too
+    assert_eq!(status("var a = []; for (var i = 0; i < 1000000; i++) a.push('s' + i);"), 3);
+    let output = eval("var o = {}; for (var i = 0; i < 500; i++) o['k' + i] = i; var ks = []; for (;;) ks.push(Object.keys(o));");
+    assert_eq!(output.status.code(), Some(3));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Out of memory"));
+}
+
+#[test]
+fn test_script_exit() {
+    let output = eval("console.log('before'); exit(7); console.log('after')");
+    assert_eq!(output.status.code(), Some(7));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "before");
+    assert!(output.stderr.is_empty());
+
+    assert_eq!(status("exit()"), 0);
+    assert_eq!(status("try { exit(4) } catch (e) { exit(5) }"), 4);
+}
+
+#[test]
+fn test_repl_undefined_variables() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_crabquick"))
+        .arg("--repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run crabquick");
+    child.stdin.take().unwrap().write_all(b"x\nvar y\ny\ntypeof x\nvar count = 1\ncout\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    let all = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    let lines: Vec<&str> = all.lines().filter(|line| !line.starts_with("CrabQuick") && !line.starts_with("Type")).collect();
+    assert!(lines.contains(&"ReferenceError: x is not defined at line 1"), "{}", all);
+    assert!(lines.contains(&"ReferenceError: cout is not defined (did you mean 'count'?) at line 1"), "{}", all);
+    // `var y` and `y` both show undefined, and so does `typeof x`
+    assert_eq!(lines.iter().filter(|&&line| line == "undefined").count(), 4, "{}", all);
+}
+
+#[test]
+fn test_unreadable_file() {
+    let output = Command::new(env!("CARGO_BIN_EXE_crabquick"))
+        .arg("does-not-exist.js")
+        .output()
+        .expect("failed to run crabquick");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn test_heap_census() {
+    let dir = std::env::temp_dir().join(format!("crabquick-census-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("census.js");
+    std::fs::write(&script, "var kept = []; for (var i = 0; i < 50; i++) kept.push('item ' + i);").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_crabquick"))
+        .arg("--heap-census")
+        .arg(&script)
+        .output()
+        .expect("failed to run crabquick");
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let strings: usize = stdout.lines()
+        .find_map(|line| line.strip_prefix("String "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|count| count.parse().ok())
+        .unwrap_or_else(|| panic!("no String row in {}", stdout));
+    assert!(strings >= 50, "{}", stdout);
+    assert!(stdout.lines().any(|line| line.starts_with("total ")), "{}", stdout);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_dump_bytecode() {
+    let dir = std::env::temp_dir().join(format!("crabquick-dump-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("dump.js");
+    let broken = dir.join("broken.js");
+    std::fs::write(&script, "exit(9); var f = function (a) { return a + 1; };").unwrap();
+    std::fs::write(&broken, "var = ;").unwrap();
+
+    let dump = |path: &std::path::Path| {
+        Command::new(env!("CARGO_BIN_EXE_crabquick"))
+            .arg("--dump-bytecode")
+            .arg(path)
+            .output()
+            .expect("failed to run crabquick")
+    };
+
+    let output = dump(&script);
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("function #0 <script>"), "{}", stdout);
+    assert!(stdout.contains("function #1 (in #0): params 1"), "{}", stdout);
+
+    assert_eq!(dump(&broken).status.code(), Some(2));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_emit_hash() {
+    let dir = std::env::temp_dir().join(format!("crabquick-hash-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let files = [
+        ("a.js", "exit(9); var x = 1.5;"),
+        ("b.js", "exit(9); var x = 1.5;"),
+        ("c.js", "exit(9); var x = 2.5;"),
+        ("broken.js", "var = ;"),
+    ];
+    for (name, source) in files {
+        std::fs::write(dir.join(name), source).unwrap();
+    }
+    let hash = |name: &str| {
+        Command::new(env!("CARGO_BIN_EXE_crabquick"))
+            .arg("--emit-hash")
+            .arg(dir.join(name))
+            .output()
+            .expect("failed to run crabquick")
+    };
+
+    // Nothing runs, and the same source always hashes the same
+    let output = hash("a.js");
+    assert_eq!(output.status.code(), Some(0));
+    let a = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    assert!(a.len() == 16 && a.chars().all(|c| c.is_ascii_hexdigit()), "{}", a);
+    assert_eq!(String::from_utf8_lossy(&hash("b.js").stdout).trim(), a);
+    assert_ne!(String::from_utf8_lossy(&hash("c.js").stdout).trim(), a);
+
+    assert_eq!(hash("broken.js").status.code(), Some(2));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
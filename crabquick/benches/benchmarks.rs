@@ -26,5 +26,103 @@ fn bench_value_operations(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, bench_context_creation, bench_value_operations);
+fn bench_property_access(c: &mut Criterion) {
+    use criterion::BatchSize;
+    use crabquick::Engine;
+
+    // Dominated by GetField on well-known names ("length", "push")
+    let source = "function f(a) { var n = 0; var i = 0; \
+                  while (i < 1000) { n = n + a.length; a.push; i = i + 1; } return n; } \
+                  f([1, 2, 3])";
+
+    c.bench_function("get_field_well_known_1000", |b| {
+        b.iter_batched(
+            || Engine::new(65536),
+            |mut engine| engine.eval(source),
+            BatchSize::SmallInput,
+        );
+    });
+}
+
+fn bench_eval_throughput(c: &mut Criterion) {
+    use criterion::BatchSize;
+    use crabquick::Engine;
+
+    // Many tiny scripts against one engine, as a host polling scripts would
+    c.bench_function("eval_tiny_script_x32", |b| {
+        b.iter_batched(
+            || Engine::new(65536),
+            |mut engine| {
+                for _ in 0..32 {
+                    let _ = engine.eval("1 + 2");
+                }
+                engine
+            },
+            BatchSize::SmallInput,
+        );
+    });
+}
+
+fn bench_f64_batches(c: &mut Criterion) {
+    use criterion::BatchSize;
+    use crabquick::runtime::conversion::to_number;
+    use crabquick::value::JSAtom;
+
+    // A batch of sensor samples, mostly fractional so most are boxed
+    let samples: Vec<f64> = (0..1000).map(|i| f64::from(i) * 0.25).collect();
+
+    c.bench_function("f64_batch_to_array_naive_1000", |b| {
+        b.iter_batched(
+            || Context::new(65536),
+            |mut ctx| {
+                let arr = ctx.new_array().unwrap();
+                for (i, &sample) in samples.iter().enumerate() {
+                    let value = ctx.new_number(sample).unwrap();
+                    ctx.set_array_element(arr, i as u32, value).unwrap();
+                }
+                ctx
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    c.bench_function("f64_batch_to_array_bulk_1000", |b| {
+        b.iter_batched(
+            || Context::new(65536),
+            |mut ctx| {
+                ctx.new_array_from_f64_slice(&samples).unwrap();
+                ctx
+            },
+            BatchSize::SmallInput,
+        );
+    });
+
+    let mut ctx = Context::new(65536);
+    let arr = ctx.new_array_from_f64_slice(&samples).unwrap();
+
+    c.bench_function("array_to_f64_batch_naive_1000", |b| {
+        b.iter(|| {
+            let len = ctx.array_length(arr).unwrap();
+            (0..len)
+                .map(|i| {
+                    let value = ctx.get_property(arr, JSAtom::from_index(i).unwrap()).unwrap();
+                    to_number(&ctx, value)
+                })
+                .collect::<Vec<f64>>()
+        });
+    });
+
+    c.bench_function("array_to_f64_batch_bulk_1000", |b| {
+        b.iter(|| ctx.array_to_f64_vec(arr).unwrap());
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_context_creation,
+    bench_value_operations,
+    bench_property_access,
+    bench_eval_throughput,
+    bench_f64_batches
+);
 criterion_main!(benches);
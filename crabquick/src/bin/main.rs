@@ -85,9 +85,8 @@ fn execute_code(engine: &mut Engine, source: &str) {
                 println!("{}", result_str);
             }
         }
-        Err(_error) => {
-            // Try to get error message
-            eprintln!("Error executing JavaScript");
+        Err(error) => {
+            eprintln!("Error: {}", error);
             process::exit(1);
         }
     }
@@ -107,8 +106,8 @@ fn execute_file(engine: &mut Engine, filename: &str) {
             // Execution succeeded
             // Result is typically undefined for script files
         }
-        Err(_error) => {
-            eprintln!("Error executing JavaScript file '{}'", filename);
+        Err(error) => {
+            eprintln!("Error executing JavaScript file '{}': {}", filename, error);
             process::exit(1);
         }
     }
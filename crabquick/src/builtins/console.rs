@@ -4,78 +4,38 @@
 
 use crate::context::Context;
 use crate::value::JSValue;
+use crate::runtime::display::format_value;
 use alloc::string::String;
 use alloc::vec::Vec;
 
 /// Format and print values to stdout (or designated output)
 ///
-/// This is a helper function used by all console methods
-fn format_values(ctx: &Context, args: &[JSValue]) -> String {
+/// This is a helper function used by all console methods. Each argument is
+/// rendered by `runtime::display::format_value` and joined with spaces.
+pub(crate) fn format_values(ctx: &Context, args: &[JSValue]) -> String {
     let mut result = String::new();
 
     for (i, arg) in args.iter().enumerate() {
         if i > 0 {
             result.push(' ');
         }
-
-        // Convert value to string representation
-        let s = value_to_display_string(ctx, *arg);
-        result.push_str(&s);
+        result.push_str(&format_value(ctx, *arg));
     }
 
     result
 }
 
-/// Convert a JSValue to a display string
-fn value_to_display_string(ctx: &Context, value: JSValue) -> String {
-    if value.is_null() {
-        return String::from("null");
-    }
-
-    if value.is_undefined() {
-        return String::from("undefined");
-    }
-
-    if let Some(b) = value.to_bool() {
-        return if b { String::from("true") } else { String::from("false") };
-    }
-
-    if let Some(n) = ctx.get_number(value) {
-        // Format number
-        if n.is_nan() {
-            return String::from("NaN");
-        }
-        if n.is_infinite() {
-            return if n > 0.0 {
-                String::from("Infinity")
-            } else {
-                String::from("-Infinity")
-            };
-        }
-        // Use alloc::format! for no_std compatibility
-        return alloc::format!("{}", n);
-    }
-
-    if let Some(s) = ctx.get_string(value) {
-        return String::from(s);
-    }
-
-    // Object
-    if value.is_object() {
-        return String::from("[object Object]");
-    }
-
-    String::from("undefined")
-}
-
 /// console.log() - Logs messages to the console
 ///
 /// # Arguments
 ///
 /// * `ctx` - JavaScript execution context
 /// * `args` - Arguments to log
-pub fn console_log(ctx: &Context, args: &[JSValue]) {
+pub fn console_log(ctx: &mut Context, args: &[JSValue]) {
     let message = format_values(ctx, args);
+    if ctx.capture_console_line(&message) {
+        return;
+    }
 
     #[cfg(any(test, feature = "std"))]
     {
@@ -96,8 +56,11 @@ pub fn console_log(ctx: &Context, args: &[JSValue]) {
 ///
 /// * `ctx` - JavaScript execution context
 /// * `args` - Arguments to log as error
-pub fn console_error(ctx: &Context, args: &[JSValue]) {
+pub fn console_error(ctx: &mut Context, args: &[JSValue]) {
     let message = format_values(ctx, args);
+    if ctx.capture_console_line(&message) {
+        return;
+    }
 
     #[cfg(any(test, feature = "std"))]
     {
@@ -116,12 +79,15 @@ pub fn console_error(ctx: &Context, args: &[JSValue]) {
 ///
 /// * `ctx` - JavaScript execution context
 /// * `args` - Arguments to log as warning
-pub fn console_warn(ctx: &Context, args: &[JSValue]) {
-    let message = format_values(ctx, args);
+pub fn console_warn(ctx: &mut Context, args: &[JSValue]) {
+    let message = alloc::format!("Warning: {}", format_values(ctx, args));
+    if ctx.capture_console_line(&message) {
+        return;
+    }
 
     #[cfg(any(test, feature = "std"))]
     {
-        eprintln!("Warning: {}", message);
+        eprintln!("{}", message);
     }
 
     #[cfg(not(any(test, feature = "std")))]
@@ -136,7 +102,7 @@ pub fn console_warn(ctx: &Context, args: &[JSValue]) {
 ///
 /// * `ctx` - JavaScript execution context
 /// * `args` - Arguments to log as info
-pub fn console_info(ctx: &Context, args: &[JSValue]) {
+pub fn console_info(ctx: &mut Context, args: &[JSValue]) {
     // console.info is typically the same as console.log
     console_log(ctx, args);
 }
@@ -150,7 +116,7 @@ mod tests {
         let mut ctx = Context::new(4096);
 
         let msg = ctx.new_string("Hello, world!").unwrap();
-        console_log(&ctx, &[msg]);
+        console_log(&mut ctx, &[msg]);
         // Output is printed, can't easily test without capturing stdout
     }
 
@@ -167,18 +133,12 @@ mod tests {
     }
 
     #[test]
-    fn test_value_to_display_string() {
+    fn test_format_values_primitives() {
         let mut ctx = Context::new(4096);
 
-        assert_eq!(value_to_display_string(&ctx, JSValue::null()), "null");
-        assert_eq!(value_to_display_string(&ctx, JSValue::undefined()), "undefined");
-        assert_eq!(value_to_display_string(&ctx, JSValue::bool(true)), "true");
-        assert_eq!(value_to_display_string(&ctx, JSValue::bool(false)), "false");
-
         let num = ctx.new_number(3.14).unwrap();
-        assert_eq!(value_to_display_string(&ctx, num), "3.14");
-
         let str_val = ctx.new_string("test").unwrap();
-        assert_eq!(value_to_display_string(&ctx, str_val), "test");
+        let args = [JSValue::null(), JSValue::undefined(), JSValue::bool(false), num, str_val];
+        assert_eq!(format_values(&ctx, &args), "null undefined false 3.14 test");
     }
 }
@@ -0,0 +1,21 @@
+//! Date built-in functions
+//!
+//! Only `Date.now()` so far, for timing. There is no OS clock to assume on
+//! the targets this crate runs on, so it reads the clock the host sets with
+//! `Context::set_clock`; with the `std` feature a context starts out with
+//! the system clock. The milliseconds are returned as a number like any
+//! other, boxed once they outgrow an inline integer.
+
+use crate::builtins::error::{create_error, ErrorType};
+use crate::context::Context;
+use crate::value::JSValue;
+
+/// `Date.now()` - milliseconds from the host's clock
+///
+/// Throws an `Error` if the host hasn't set a clock.
+pub fn date_now(ctx: &mut Context, _this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let Some(now) = ctx.now_ms() else {
+        return Err(create_error(ctx, ErrorType::Error, Some("Date.now() needs a clock, and the host hasn't set one"))?);
+    };
+    ctx.new_number(now as f64).map_err(|_| JSValue::exception())
+}
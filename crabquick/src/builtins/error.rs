@@ -4,7 +4,7 @@
 //! URIError, EvalError and Error.prototype methods
 
 use crate::context::Context;
-use crate::value::{JSValue, JSAtom};
+use crate::value::{atoms, JSValue};
 use crate::object::PropertyFlags;
 
 /// Error types
@@ -20,6 +20,17 @@ pub enum ErrorType {
 }
 
 impl ErrorType {
+    /// All error types, in declaration order
+    pub const ALL: [ErrorType; 7] = [
+        ErrorType::Error,
+        ErrorType::TypeError,
+        ErrorType::ReferenceError,
+        ErrorType::SyntaxError,
+        ErrorType::RangeError,
+        ErrorType::URIError,
+        ErrorType::EvalError,
+    ];
+
     pub fn name(&self) -> &'static str {
         match self {
             ErrorType::Error => "Error",
@@ -31,31 +42,223 @@ impl ErrorType {
             ErrorType::EvalError => "EvalError",
         }
     }
+
+    /// Returns the category for errors created by this constructor
+    ///
+    /// Plain `Error` and `URIError` have no dedicated category.
+    pub fn kind(&self) -> Option<ErrorKind> {
+        match self {
+            ErrorType::TypeError => Some(ErrorKind::Type),
+            ErrorType::ReferenceError => Some(ErrorKind::Reference),
+            ErrorType::SyntaxError => Some(ErrorKind::Syntax),
+            ErrorType::RangeError => Some(ErrorKind::Range),
+            ErrorType::EvalError => Some(ErrorKind::Eval),
+            ErrorType::Error | ErrorType::URIError => None,
+        }
+    }
+}
+
+/// Stable category of a failure raised by the engine
+///
+/// Every error object the engine throws carries its kind in a hidden slot,
+/// so hosts can classify failures without matching on message text. The
+/// numeric codes are part of the public API and never change.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ErrorKind {
+    /// Source failed to parse or compile
+    Syntax = 0,
+    /// Operation applied to a value of the wrong type
+    Type = 1,
+    /// Numeric value out of its allowed range
+    Range = 2,
+    /// Reference to an unknown binding
+    Reference = 3,
+    /// Error raised by the `EvalError` constructor
+    Eval = 4,
+    /// Engine invariant violated (bad bytecode, internal failure)
+    Internal = 5,
+    /// The heap is exhausted
+    OutOfMemory = 6,
+    /// Execution exceeded its time or step budget
+    Timeout = 7,
+    /// Execution was interrupted by the host
+    Interrupted = 8,
+    /// Value stack or call stack exhausted
+    StackOverflow = 9,
+    /// The script called `exit()`; see `Context::exit_code`
+    ExitRequested = 10,
+}
+
+impl ErrorKind {
+    /// All kinds, in code order
+    pub const ALL: [ErrorKind; 11] = [
+        ErrorKind::Syntax,
+        ErrorKind::Type,
+        ErrorKind::Range,
+        ErrorKind::Reference,
+        ErrorKind::Eval,
+        ErrorKind::Internal,
+        ErrorKind::OutOfMemory,
+        ErrorKind::Timeout,
+        ErrorKind::Interrupted,
+        ErrorKind::StackOverflow,
+        ErrorKind::ExitRequested,
+    ];
+
+    /// Returns the stable numeric code for this kind
+    #[inline]
+    pub const fn code(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the kind for a numeric code
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.get(code as usize).copied()
+    }
+
+    /// Returns the JS-visible constructor name used for errors of this kind
+    ///
+    /// Kinds without a standard constructor use `InternalError`, matching
+    /// QuickJS. Stack exhaustion is a `RangeError`, as in other engines.
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorKind::Syntax => "SyntaxError",
+            ErrorKind::Type => "TypeError",
+            ErrorKind::Range | ErrorKind::StackOverflow => "RangeError",
+            ErrorKind::Reference => "ReferenceError",
+            ErrorKind::Eval => "EvalError",
+            ErrorKind::Internal
+            | ErrorKind::OutOfMemory
+            | ErrorKind::Timeout
+            | ErrorKind::Interrupted
+            | ErrorKind::ExitRequested => "InternalError",
+        }
+    }
+
+    /// Returns the error type whose prototype errors of this kind inherit from
+    ///
+    /// `InternalError` kinds have no constructor of their own and inherit
+    /// from `Error.prototype`.
+    pub fn error_type(self) -> ErrorType {
+        match self {
+            ErrorKind::Syntax => ErrorType::SyntaxError,
+            ErrorKind::Type => ErrorType::TypeError,
+            ErrorKind::Range | ErrorKind::StackOverflow => ErrorType::RangeError,
+            ErrorKind::Reference => ErrorType::ReferenceError,
+            ErrorKind::Eval => ErrorType::EvalError,
+            ErrorKind::Internal
+            | ErrorKind::OutOfMemory
+            | ErrorKind::Timeout
+            | ErrorKind::Interrupted
+            | ErrorKind::ExitRequested => ErrorType::Error,
+        }
+    }
 }
 
 /// Creates an error object
 pub fn create_error(ctx: &mut Context, error_type: ErrorType, message: Option<&str>) -> Result<JSValue, JSValue> {
-    let err = ctx.new_object().map_err(|_| JSValue::exception())?;
+    let message = match message {
+        Some(msg) => Some(ctx.new_string(msg).map_err(|_| JSValue::exception())?),
+        None => None,
+    };
+    new_error_object(ctx, error_type, error_type.name(), message, error_type.kind())
+}
+
+/// Creates an error object of the given kind, as thrown by the engine itself
+pub fn create_error_with_kind(ctx: &mut Context, kind: ErrorKind, message: &str) -> Result<JSValue, JSValue> {
+    let message = ctx.new_string(message).map_err(|_| JSValue::exception())?;
+    new_error_object(ctx, kind.error_type(), kind.name(), Some(message), Some(kind))
+}
+
+/// Creates the error thrown by `exit(code)`
+///
+/// Exit requests can't be caught by scripts; they unwind to the host, which
+/// reads the status back with `Context::exit_code`.
+pub fn create_exit_request(ctx: &mut Context, code: i32) -> Result<JSValue, JSValue> {
+    let err = create_error_with_kind(ctx, ErrorKind::ExitRequested, &alloc::format!("exit({})", code))?;
+    ctx.add_property(err, atoms::EXIT_CODE, JSValue::from_int(code), PropertyFlags::empty())
+        .map_err(|_| JSValue::exception())?;
+    Ok(err)
+}
+
+/// Builds an error object with `name`, optional `message`, `stack` and kind slot
+///
+/// The object inherits from the prototype registered for `error_type`.
+/// `stack` holds just the `"name: message"` header line; the VM adds a
+/// `lineNumber` when the error is thrown.
+pub fn new_error_object(
+    ctx: &mut Context,
+    error_type: ErrorType,
+    name: &str,
+    message: Option<JSValue>,
+    kind: Option<ErrorKind>,
+) -> Result<JSValue, JSValue> {
+    let proto = ctx.get_error_prototype(error_type);
+    new_error_object_with_proto(ctx, proto, name, message, kind)
+}
 
-    // Set name property
-    let name_atom = JSAtom::from_id(1); // Simplified: should use proper atom for "name"
-    let name_val = ctx.new_string(error_type.name()).map_err(|_| JSValue::exception())?;
-    ctx.add_property(err, name_atom, name_val, PropertyFlags::default())
+/// Like [`new_error_object`], but inheriting from `proto`, such as the
+/// prototype of an error class the host registered
+pub fn new_error_object_with_proto(
+    ctx: &mut Context,
+    proto: JSValue,
+    name: &str,
+    message: Option<JSValue>,
+    kind: Option<ErrorKind>,
+) -> Result<JSValue, JSValue> {
+    let err = if proto.is_null() {
+        ctx.new_object()
+    } else {
+        ctx.new_object_with_proto(proto)
+    }
+    .map_err(|_| JSValue::exception())?;
+
+    // Like other engines, these don't show up in Object.keys or JSON
+    let mut hidden = PropertyFlags::default();
+    hidden.set_enumerable(false);
+
+    let name_val = ctx.new_string(name).map_err(|_| JSValue::exception())?;
+    ctx.add_property(err, atoms::NAME, name_val, hidden)
         .map_err(|_| JSValue::exception())?;
 
-    // Set message property
     if let Some(msg) = message {
-        let msg_atom = JSAtom::from_id(2); // Simplified: should use proper atom for "message"
-        let msg_val = ctx.new_string(msg).map_err(|_| JSValue::exception())?;
-        ctx.add_property(err, msg_atom, msg_val, PropertyFlags::default())
+        ctx.add_property(err, atoms::MESSAGE, msg, hidden)
             .map_err(|_| JSValue::exception())?;
     }
 
-    // TODO: Add stack trace
+    let stack = match message.and_then(|msg| ctx.get_string(msg)) {
+        Some(msg) if !msg.is_empty() => alloc::format!("{}: {}", name, msg),
+        _ => alloc::string::String::from(name),
+    };
+    let stack_val = ctx.new_string(&stack).map_err(|_| JSValue::exception())?;
+    ctx.add_property(err, atoms::STACK, stack_val, hidden)
+        .map_err(|_| JSValue::exception())?;
+
+    if let (Some(kind), Some(obj)) = (kind, ctx.get_object_mut(err)) {
+        obj.set_error_kind_code(kind.code());
+    }
 
     Ok(err)
 }
 
+/// Returns the builtin error type nearest up `proto`'s prototype chain
+///
+/// Registered error classes report the kind of the builtin they extend.
+pub fn builtin_error_type(ctx: &Context, proto: JSValue) -> ErrorType {
+    let mut current = proto;
+    for _ in 0..=ctx.max_prototype_depth() {
+        if let Some(&error_type) = ErrorType::ALL.iter().find(|&&t| ctx.get_error_prototype(t) == current) {
+            return error_type;
+        }
+        match ctx.get_object(current) {
+            Some(obj) if !obj.prototype().is_null() => current = obj.prototype(),
+            _ => break,
+        }
+    }
+    ErrorType::Error
+}
+
 /// Error() constructor
 pub fn error_constructor(ctx: &mut Context, message: Option<&str>) -> Result<JSValue, JSValue> {
     create_error(ctx, ErrorType::Error, message)
@@ -93,9 +296,8 @@ pub fn eval_error_constructor(ctx: &mut Context, message: Option<&str>) -> Resul
 
 /// Error.prototype.toString() - Returns string representation
 pub fn to_string(ctx: &mut Context, error: JSValue) -> Result<JSValue, JSValue> {
-    // Simplified: just return "[ErrorType: message]"
-    let name_atom = JSAtom::from_id(1);
-    let msg_atom = JSAtom::from_id(2);
+    let name_atom = atoms::NAME;
+    let msg_atom = atoms::MESSAGE;
 
     let name = ctx.get_property(error, name_atom)
         .and_then(|v| ctx.get_string(v))
@@ -134,6 +336,63 @@ mod tests {
         assert!(err.is_object());
     }
 
+    #[test]
+    fn test_error_kind_codes_are_stable() {
+        for (i, kind) in ErrorKind::ALL.iter().enumerate() {
+            assert_eq!(kind.code() as usize, i);
+            assert_eq!(ErrorKind::from_code(kind.code()), Some(*kind));
+        }
+        assert_eq!(ErrorKind::Syntax.code(), 0);
+        assert_eq!(ErrorKind::StackOverflow.code(), 9);
+        assert_eq!(ErrorKind::ExitRequested.code(), 10);
+        assert_eq!(ErrorKind::from_code(11), None);
+    }
+
+    #[test]
+    fn test_error_kind_matches_constructor_names() {
+        for error_type in [
+            ErrorType::TypeError,
+            ErrorType::ReferenceError,
+            ErrorType::SyntaxError,
+            ErrorType::RangeError,
+            ErrorType::EvalError,
+        ] {
+            assert_eq!(error_type.kind().unwrap().name(), error_type.name());
+        }
+        assert_eq!(ErrorType::Error.kind(), None);
+        assert_eq!(ErrorKind::StackOverflow.name(), "RangeError");
+        assert_eq!(ErrorKind::OutOfMemory.name(), "InternalError");
+    }
+
+    #[test]
+    fn test_create_error_sets_kind() {
+        let mut ctx = Context::new(8192);
+
+        let err = create_error(&mut ctx, ErrorType::RangeError, Some("bad length")).unwrap();
+        assert_eq!(ctx.error_kind(err), Some(ErrorKind::Range));
+
+        let err = create_error_with_kind(&mut ctx, ErrorKind::StackOverflow, "too deep").unwrap();
+        assert_eq!(ctx.error_kind(err), Some(ErrorKind::StackOverflow));
+        let name = ctx.get_property(err, atoms::NAME).unwrap();
+        assert_eq!(ctx.get_string(name), Some("RangeError"));
+
+        let err = create_error(&mut ctx, ErrorType::Error, None).unwrap();
+        assert_eq!(ctx.error_kind(err), None);
+        assert_eq!(ctx.error_kind(JSValue::from_int(1)), None);
+    }
+
+    #[test]
+    fn test_error_kind_prototypes() {
+        assert_eq!(ErrorKind::StackOverflow.error_type(), ErrorType::RangeError);
+        assert_eq!(ErrorKind::Reference.error_type(), ErrorType::ReferenceError);
+        assert_eq!(ErrorKind::Timeout.error_type(), ErrorType::Error);
+
+        let mut ctx = Context::new(8192);
+        let err = create_error_with_kind(&mut ctx, ErrorKind::Type, "bad").unwrap();
+        let stack = ctx.get_property(err, atoms::STACK).unwrap();
+        assert_eq!(ctx.get_string(stack), Some("TypeError: bad"));
+    }
+
     #[test]
     fn test_error_types() {
         assert_eq!(ErrorType::Error.name(), "Error");
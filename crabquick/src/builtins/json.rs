@@ -2,25 +2,63 @@
 //!
 //! Implements JSON.parse and JSON.stringify
 
+use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
+use crate::builtins::error::ErrorKind;
 use crate::context::Context;
-use crate::value::JSValue;
+use crate::util::{format_number_with, NumberFormat};
+use crate::value::{JSAtom, JSValue};
 use crate::object::PropertyFlags;
 
 /// JSON.parse() - Parses a JSON string and returns a JavaScript value
+///
+/// Malformed input throws a SyntaxError naming the byte position of the
+/// problem, and nesting deeper than the context's nesting budget a
+/// RangeError. Running out of heap throws an out-of-memory error rather than
+/// panicking, leaving whatever was built so far for the GC.
 pub fn parse(ctx: &mut Context, json_str: &str) -> Result<JSValue, JSValue> {
     let mut parser = JsonParser::new(json_str);
-    parser.parse(ctx)
+    parser.parse(ctx).map_err(|err| err.into_value(ctx))
 }
 
 /// JSON.stringify() - Converts a JavaScript value to a JSON string
-pub fn stringify(ctx: &Context, value: JSValue) -> Result<String, JSValue> {
-    let mut result = String::new();
-    stringify_value(ctx, value, &mut result)?;
-    Ok(result)
+///
+/// Returns `None` for values with no JSON form (undefined and functions).
+/// Cyclic structures throw a TypeError, and values nested deeper than the
+/// context's nesting budget a RangeError.
+pub fn stringify(ctx: &mut Context, value: JSValue) -> Result<Option<String>, JSValue> {
+    let mut stringifier = Stringifier { ctx, out: String::new(), stack: Vec::new() };
+    match stringifier.write_value(value) {
+        Ok(true) => Ok(Some(stringifier.out)),
+        Ok(false) => Ok(None),
+        Err(err) => Err(err.into_value(ctx)),
+    }
+}
+
+/// Failure inside parse or stringify, turned into a thrown value at the top
+enum JsonError {
+    Syntax(String),
+    Cycle,
+    TooDeep,
+    TooManyNames,
+    OutOfMemory,
+}
+
+impl JsonError {
+    fn into_value(self, ctx: &mut Context) -> JSValue {
+        match self {
+            JsonError::Syntax(msg) => ctx.new_error(ErrorKind::Syntax, &msg),
+            JsonError::Cycle => ctx.new_error(ErrorKind::Type, "Converting circular structure to JSON"),
+            JsonError::TooDeep => ctx.nesting_error(),
+            JsonError::TooManyNames => ctx.new_error(ErrorKind::Range, "Too many distinct property names"),
+            JsonError::OutOfMemory => ctx.new_error(ErrorKind::OutOfMemory, "Out of memory"),
+        }
+    }
 }
 
+type JsonResult<T> = Result<T, JsonError>;
+
 // ========== JSON Parser ==========
 
 struct JsonParser<'a> {
@@ -33,371 +71,406 @@ impl<'a> JsonParser<'a> {
         JsonParser { input, pos: 0 }
     }
 
-    fn parse(&mut self, ctx: &mut Context) -> Result<JSValue, JSValue> {
-        self.skip_whitespace();
+    fn parse(&mut self, ctx: &mut Context) -> JsonResult<JSValue> {
         let result = self.parse_value(ctx)?;
         self.skip_whitespace();
         if self.pos < self.input.len() {
-            return Err(ctx.new_string("Unexpected characters after JSON")
-                .unwrap_or(JSValue::exception()));
+            return Err(self.unexpected());
         }
         Ok(result)
     }
 
-    fn parse_value(&mut self, ctx: &mut Context) -> Result<JSValue, JSValue> {
+    fn parse_value(&mut self, ctx: &mut Context) -> JsonResult<JSValue> {
         self.skip_whitespace();
 
         match self.peek() {
-            Some('"') => self.parse_string(ctx),
-            Some('{') => self.parse_object(ctx),
-            Some('[') => self.parse_array(ctx),
-            Some('t') => self.parse_true(ctx),
-            Some('f') => self.parse_false(ctx),
-            Some('n') => self.parse_null(ctx),
-            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(ctx),
-            _ => Err(ctx.new_string("Unexpected character in JSON")
-                .unwrap_or(JSValue::exception())),
+            Some(b'"') => {
+                let s = self.parse_string()?;
+                ctx.new_string(&s).map_err(|_| JsonError::OutOfMemory)
+            }
+            Some(b'{') => self.nested(ctx, Self::parse_object),
+            Some(b'[') => self.nested(ctx, Self::parse_array),
+            Some(b't') => self.parse_literal("true", JSValue::bool(true)),
+            Some(b'f') => self.parse_literal("false", JSValue::bool(false)),
+            Some(b'n') => self.parse_literal("null", JSValue::null()),
+            Some(c) if c == b'-' || c.is_ascii_digit() => self.parse_number(ctx),
+            _ => Err(self.unexpected()),
         }
     }
 
-    fn parse_string(&mut self, ctx: &mut Context) -> Result<JSValue, JSValue> {
-        self.expect('"')?;
+    fn nested(
+        &mut self,
+        ctx: &mut Context,
+        parse: fn(&mut Self, &mut Context) -> JsonResult<JSValue>,
+    ) -> JsonResult<JSValue> {
+        if !ctx.enter_nesting() {
+            return Err(JsonError::TooDeep);
+        }
+        let result = parse(self, ctx);
+        ctx.leave_nesting();
+        result
+    }
+
+    fn parse_string(&mut self) -> JsonResult<String> {
+        self.expect(b'"')?;
         let mut s = String::new();
 
-        while let Some(c) = self.peek() {
-            if c == '"' {
-                self.advance();
-                return ctx.new_string(&s).map_err(|_| JSValue::exception());
-            } else if c == '\\' {
-                self.advance();
-                match self.peek() {
-                    Some('"') => { s.push('"'); self.advance(); }
-                    Some('\\') => { s.push('\\'); self.advance(); }
-                    Some('/') => { s.push('/'); self.advance(); }
-                    Some('b') => { s.push('\x08'); self.advance(); }
-                    Some('f') => { s.push('\x0c'); self.advance(); }
-                    Some('n') => { s.push('\n'); self.advance(); }
-                    Some('r') => { s.push('\r'); self.advance(); }
-                    Some('t') => { s.push('\t'); self.advance(); }
-                    Some('u') => {
-                        self.advance();
-                        let hex = self.take_n(4);
-                        if let Ok(code) = u32::from_str_radix(&hex, 16) {
-                            if let Some(ch) = char::from_u32(code) {
-                                s.push(ch);
-                            }
+        loop {
+            // Copy the run of plain characters in one go
+            let start = self.pos;
+            while let Some(c) = self.peek() {
+                if c == b'"' || c == b'\\' || c < 0x20 {
+                    break;
+                }
+                self.pos += 1;
+            }
+            s.push_str(&self.input[start..self.pos]);
+
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escaped = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\x08',
+                        Some(b'f') => '\x0c',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            self.pos += 1;
+                            s.push(self.parse_unicode_escape()?);
+                            continue;
                         }
-                    }
-                    _ => return Err(ctx.new_string("Invalid escape sequence")
-                        .unwrap_or(JSValue::exception())),
+                        _ => return Err(self.unexpected()),
+                    };
+                    self.pos += 1;
+                    s.push(escaped);
                 }
-            } else {
-                s.push(c);
-                self.advance();
+                // Unescaped control character or end of input
+                _ => return Err(self.unexpected()),
             }
         }
+    }
+
+    /// Parses the XXXX of a `\uXXXX` escape, pairing surrogates
+    ///
+    /// Lone surrogates can't be held in a Rust string and become U+FFFD.
+    fn parse_unicode_escape(&mut self) -> JsonResult<char> {
+        let high = self.parse_hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return Ok(char::from_u32(high).unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+
+        if self.input[self.pos..].starts_with("\\u") {
+            let save = self.pos;
+            self.pos += 2;
+            let low = self.parse_hex4()?;
+            if (0xDC00..0xE000).contains(&low) {
+                let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                return Ok(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            self.pos = save;
+        }
+        Ok(char::REPLACEMENT_CHARACTER)
+    }
 
-        Err(ctx.new_string("Unterminated string").unwrap_or(JSValue::exception()))
+    fn parse_hex4(&mut self) -> JsonResult<u32> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.peek()
+                .and_then(|c| (c as char).to_digit(16))
+                .ok_or_else(|| self.unexpected())?;
+            code = code * 16 + digit;
+            self.pos += 1;
+        }
+        Ok(code)
     }
 
-    fn parse_number(&mut self, ctx: &mut Context) -> Result<JSValue, JSValue> {
+    fn parse_number(&mut self, ctx: &mut Context) -> JsonResult<JSValue> {
         let start = self.pos;
 
         // Optional minus
-        if self.peek() == Some('-') {
-            self.advance();
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
         }
 
-        // Integer part
-        if self.peek() == Some('0') {
-            self.advance();
-        } else {
-            while let Some(c) = self.peek() {
-                if c.is_ascii_digit() {
-                    self.advance();
-                } else {
-                    break;
-                }
-            }
+        // Integer part: a single 0 or a run not starting with 0
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(c) if c.is_ascii_digit() => self.skip_digits(),
+            _ => return Err(self.unexpected()),
         }
 
         // Fractional part
-        if self.peek() == Some('.') {
-            self.advance();
-            while let Some(c) = self.peek() {
-                if c.is_ascii_digit() {
-                    self.advance();
-                } else {
-                    break;
-                }
-            }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            self.expect_digits()?;
         }
 
         // Exponent part
-        if self.peek() == Some('e') || self.peek() == Some('E') {
-            self.advance();
-            if self.peek() == Some('+') || self.peek() == Some('-') {
-                self.advance();
-            }
-            while let Some(c) = self.peek() {
-                if c.is_ascii_digit() {
-                    self.advance();
-                } else {
-                    break;
-                }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
             }
+            self.expect_digits()?;
         }
 
         let num_str = &self.input[start..self.pos];
-        match num_str.parse::<f64>() {
-            Ok(n) => {
-                if n.fract() == 0.0 && n >= i32::MIN as f64 && n <= i32::MAX as f64 {
-                    Ok(JSValue::from_int(n as i32))
-                } else {
-                    ctx.new_number(n).map_err(|_| JSValue::exception())
-                }
-            }
-            Err(_) => Err(ctx.new_string("Invalid number").unwrap_or(JSValue::exception())),
+        let n: f64 = num_str.parse().map_err(|_| self.unexpected_at(start))?;
+        let is_int = libm::floor(n) == n && n >= i32::MIN as f64 && n <= i32::MAX as f64;
+        if is_int && !(n == 0.0 && n.is_sign_negative()) {
+            Ok(JSValue::from_int(n as i32))
+        } else {
+            ctx.new_number(n).map_err(|_| JsonError::OutOfMemory)
         }
     }
 
-    fn parse_object(&mut self, ctx: &mut Context) -> Result<JSValue, JSValue> {
-        use crate::runtime::init::string_to_atom;
+    fn parse_object(&mut self, ctx: &mut Context) -> JsonResult<JSValue> {
+        self.expect(b'{')?;
+        let obj = ctx.new_object().map_err(|_| JsonError::OutOfMemory)?;
 
-        self.expect('{')?;
         self.skip_whitespace();
-
-        let obj = ctx.new_object().map_err(|_| JSValue::exception())?;
-
-        if self.peek() == Some('}') {
-            self.advance();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
             return Ok(obj);
         }
 
         loop {
             self.skip_whitespace();
-
-            // Parse key (must be string)
-            if self.peek() != Some('"') {
-                return Err(ctx.new_string("Expected string key").unwrap_or(JSValue::exception()));
+            if self.peek() != Some(b'"') {
+                return Err(self.unexpected());
             }
-            let key_val = self.parse_string(ctx)?;
-            let key_str = ctx.get_string(key_val)
-                .ok_or(JSValue::exception())?;
-            let key_atom = string_to_atom(key_str);
+            let key = self.parse_string()?;
+            let key_atom = ctx.try_intern_atom(&key).ok_or(JsonError::TooManyNames)?;
 
             self.skip_whitespace();
-            self.expect(':')?;
-            self.skip_whitespace();
-
-            // Parse value
+            self.expect(b':')?;
             let value = self.parse_value(ctx)?;
 
-            // Add to object
             ctx.add_property(obj, key_atom, value, PropertyFlags::default())
-                .map_err(|_| JSValue::exception())?;
+                .map_err(|_| JsonError::OutOfMemory)?;
 
             self.skip_whitespace();
             match self.peek() {
-                Some(',') => { self.advance(); }
-                Some('}') => { self.advance(); return Ok(obj); }
-                _ => return Err(ctx.new_string("Expected ',' or '}'").unwrap_or(JSValue::exception())),
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(obj);
+                }
+                _ => return Err(self.unexpected()),
             }
         }
     }
 
-    fn parse_array(&mut self, ctx: &mut Context) -> Result<JSValue, JSValue> {
-        use crate::runtime::init::string_to_atom;
+    fn parse_array(&mut self, ctx: &mut Context) -> JsonResult<JSValue> {
+        self.expect(b'[')?;
+        let arr = ctx.new_array().map_err(|_| JsonError::OutOfMemory)?;
 
-        self.expect('[')?;
         self.skip_whitespace();
-
-        let arr = ctx.new_object().map_err(|_| JSValue::exception())?;
-        let mut index = 0;
-
-        if self.peek() == Some(']') {
-            self.advance();
-            let length_atom = string_to_atom("length");
-            ctx.add_property(arr, length_atom, JSValue::from_int(0), PropertyFlags::default())
-                .map_err(|_| JSValue::exception())?;
+        if self.peek() == Some(b']') {
+            self.pos += 1;
             return Ok(arr);
         }
 
+        let mut index = 0;
         loop {
-            self.skip_whitespace();
             let value = self.parse_value(ctx)?;
-
-            let idx_atom = string_to_atom(&alloc::format!("{}", index));
-            ctx.add_property(arr, idx_atom, value, PropertyFlags::default())
-                .map_err(|_| JSValue::exception())?;
+            ctx.set_array_element(arr, index, value)
+                .map_err(|_| JsonError::OutOfMemory)?;
             index += 1;
 
             self.skip_whitespace();
             match self.peek() {
-                Some(',') => { self.advance(); }
-                Some(']') => {
-                    self.advance();
-                    let length_atom = string_to_atom("length");
-                    ctx.add_property(arr, length_atom, JSValue::from_int(index), PropertyFlags::default())
-                        .map_err(|_| JSValue::exception())?;
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
                     return Ok(arr);
                 }
-                _ => return Err(ctx.new_string("Expected ',' or ']'").unwrap_or(JSValue::exception())),
+                _ => return Err(self.unexpected()),
             }
         }
     }
 
-    fn parse_true(&mut self, ctx: &mut Context) -> Result<JSValue, JSValue> {
-        if self.take_n(4) == "true" {
-            Ok(JSValue::bool(true))
+    fn parse_literal(&mut self, word: &str, value: JSValue) -> JsonResult<JSValue> {
+        if self.input[self.pos..].starts_with(word) {
+            self.pos += word.len();
+            Ok(value)
         } else {
-            Err(ctx.new_string("Expected 'true'").unwrap_or(JSValue::exception()))
+            // Point at the first character that doesn't match
+            let matched = self.input[self.pos..]
+                .bytes()
+                .zip(word.bytes())
+                .take_while(|(a, b)| a == b)
+                .count();
+            Err(self.unexpected_at(self.pos + matched))
         }
     }
 
-    fn parse_false(&mut self, ctx: &mut Context) -> Result<JSValue, JSValue> {
-        if self.take_n(5) == "false" {
-            Ok(JSValue::bool(false))
-        } else {
-            Err(ctx.new_string("Expected 'false'").unwrap_or(JSValue::exception()))
-        }
-    }
-
-    fn parse_null(&mut self, ctx: &mut Context) -> Result<JSValue, JSValue> {
-        if self.take_n(4) == "null" {
-            Ok(JSValue::null())
-        } else {
-            Err(ctx.new_string("Expected 'null'").unwrap_or(JSValue::exception()))
-        }
-    }
-
-    fn peek(&self) -> Option<char> {
-        self.input[self.pos..].chars().next()
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
     }
 
-    fn advance(&mut self) {
-        if let Some(c) = self.peek() {
-            self.pos += c.len_utf8();
-        }
-    }
-
-    fn expect(&mut self, expected: char) -> Result<(), JSValue> {
+    fn expect(&mut self, expected: u8) -> JsonResult<()> {
         if self.peek() == Some(expected) {
-            self.advance();
+            self.pos += 1;
             Ok(())
         } else {
-            Err(JSValue::exception())
+            Err(self.unexpected())
         }
     }
 
-    fn take_n(&mut self, n: usize) -> String {
-        let mut s = String::new();
-        for _ in 0..n {
-            if let Some(c) = self.peek() {
-                s.push(c);
-                self.advance();
+    fn skip_digits(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_digit() {
+                break;
             }
+            self.pos += 1;
         }
-        s
     }
 
-    fn skip_whitespace(&mut self) {
-        while let Some(c) = self.peek() {
-            if c.is_ascii_whitespace() {
-                self.advance();
-            } else {
-                break;
+    fn expect_digits(&mut self) -> JsonResult<()> {
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() => {
+                self.skip_digits();
+                Ok(())
             }
+            _ => Err(self.unexpected()),
         }
     }
-}
-
-// ========== JSON Stringify ==========
 
-fn stringify_value(ctx: &Context, value: JSValue, result: &mut String) -> Result<(), JSValue> {
-    // null
-    if value.is_null() {
-        result.push_str("null");
-        return Ok(());
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
     }
 
-    // undefined -> null in JSON
-    if value.is_undefined() {
-        result.push_str("null");
-        return Ok(());
+    fn unexpected(&self) -> JsonError {
+        self.unexpected_at(self.pos)
     }
 
-    // boolean
-    if let Some(b) = value.to_bool() {
-        result.push_str(if b { "true" } else { "false" });
-        return Ok(());
+    fn unexpected_at(&self, pos: usize) -> JsonError {
+        match self.input.get(pos..).and_then(|rest| rest.chars().next()) {
+            Some(c) => JsonError::Syntax(format!("Unexpected token '{}' in JSON at position {}", c, pos)),
+            None => JsonError::Syntax(String::from("Unexpected end of JSON input")),
+        }
     }
+}
 
-    // integer
-    if let Some(i) = value.to_int() {
-        result.push_str(&alloc::format!("{}", i));
-        return Ok(());
-    }
+// ========== JSON Stringify ==========
+
+struct Stringifier<'a> {
+    ctx: &'a Context,
+    out: String,
+    /// Objects currently being written, for cycle detection
+    stack: Vec<JSValue>,
+}
 
-    // float
-    if let Some(f) = ctx.get_number(value) {
-        if f.is_nan() || f.is_infinite() {
-            result.push_str("null");
+impl Stringifier<'_> {
+    /// Writes `value`, returning false (and writing nothing) if it has no
+    /// JSON form
+    fn write_value(&mut self, value: JSValue) -> JsonResult<bool> {
+        let ctx = self.ctx;
+
+        if value.is_null() {
+            self.out.push_str("null");
+        } else if value.is_undefined() {
+            return Ok(false);
+        } else if let Some(b) = value.to_bool() {
+            self.out.push_str(if b { "true" } else { "false" });
+        } else if let Some(i) = value.to_int() {
+            self.out.push_str(&format!("{}", i));
+        } else if let Some(f) = ctx.get_number(value) {
+            self.out.push_str(&format_number_with(f, NumberFormat::Json));
+        } else if let Some(s) = ctx.get_string(value) {
+            write_string(s, &mut self.out);
+        } else if ctx.get_object(value).is_some() {
+            if self.stack.contains(&value) {
+                return Err(JsonError::Cycle);
+            }
+            if !ctx.enter_nesting() {
+                return Err(JsonError::TooDeep);
+            }
+            self.stack.push(value);
+            let result = match ctx.array_length(value) {
+                Some(len) => self.write_array(value, len),
+                None => self.write_object(value),
+            };
+            self.stack.pop();
+            ctx.leave_nesting();
+            result?;
         } else {
-            result.push_str(&alloc::format!("{}", f));
+            // Functions and other non-object heap values
+            return Ok(false);
         }
-        return Ok(());
+        Ok(true)
     }
 
-    // string
-    if let Some(s) = ctx.get_string(value) {
-        stringify_string(s, result);
-        return Ok(());
+    fn write_array(&mut self, arr: JSValue, len: u32) -> JsonResult<()> {
+        self.out.push('[');
+        for index in 0..len {
+            if index > 0 {
+                self.out.push(',');
+            }
+            let elem = JSAtom::from_index(index)
+                .and_then(|key| self.ctx.get_property(arr, key))
+                .unwrap_or(JSValue::undefined());
+            // Holes, undefined and functions become null inside arrays
+            if !self.write_value(elem)? {
+                self.out.push_str("null");
+            }
+        }
+        self.out.push(']');
+        Ok(())
     }
 
-    // object or array
-    if value.is_object() {
-        // Check if it's array-like (has numeric "length" property)
-        let length_atom = crate::runtime::init::string_to_atom("length");
-        if let Some(len_val) = ctx.get_property(value, length_atom) {
-            if let Some(len) = len_val.to_int() {
-                // Array-like
-                result.push('[');
-                for i in 0..len {
-                    if i > 0 {
-                        result.push(',');
-                    }
-                    let idx_atom = crate::runtime::init::string_to_atom(&alloc::format!("{}", i));
-                    let elem = ctx.get_property(value, idx_atom).unwrap_or(JSValue::undefined());
-                    stringify_value(ctx, elem, result)?;
-                }
-                result.push(']');
-                return Ok(());
+    fn write_object(&mut self, obj: JSValue) -> JsonResult<()> {
+        let ctx = self.ctx;
+        self.out.push('{');
+        let mut first = true;
+        for key in ctx.own_enumerable_keys(obj) {
+            let Some(name) = ctx.atom_name(key) else {
+                continue;
+            };
+            let value = ctx.get_property(obj, key).unwrap_or(JSValue::undefined());
+
+            let mark = self.out.len();
+            if !first {
+                self.out.push(',');
+            }
+            write_string(&name, &mut self.out);
+            self.out.push(':');
+            if self.write_value(value)? {
+                first = false;
+            } else {
+                // Members without a JSON form are left out entirely
+                self.out.truncate(mark);
             }
         }
-
-        // Regular object - for now just output empty object
-        // (full object property enumeration would require more API support)
-        result.push_str("{}");
-        return Ok(());
+        self.out.push('}');
+        Ok(())
     }
-
-    // Function or other -> null
-    result.push_str("null");
-    Ok(())
 }
 
-fn stringify_string(s: &str, result: &mut String) {
+fn write_string(s: &str, result: &mut String) {
     result.push('"');
     for c in s.chars() {
         match c {
             '"' => result.push_str("\\\""),
             '\\' => result.push_str("\\\\"),
+            '\x08' => result.push_str("\\b"),
+            '\x0c' => result.push_str("\\f"),
             '\n' => result.push_str("\\n"),
             '\r' => result.push_str("\\r"),
             '\t' => result.push_str("\\t"),
             c if c < '\x20' => {
-                result.push_str(&alloc::format!("\\u{:04x}", c as u32));
+                result.push_str(&format!("\\u{:04x}", c as u32));
             }
             c => result.push(c),
         }
@@ -409,6 +482,10 @@ fn stringify_string(s: &str, result: &mut String) {
 mod tests {
     use super::*;
 
+    fn json(ctx: &mut Context, value: JSValue) -> String {
+        stringify(ctx, value).unwrap().unwrap()
+    }
+
     #[test]
     fn test_parse_simple() {
         let mut ctx = Context::new(4096);
@@ -436,13 +513,89 @@ mod tests {
         assert_eq!(ctx.get_string(result), Some("hello"));
     }
 
+    #[test]
+    fn test_parse_nested() {
+        let mut ctx = Context::new(16384);
+
+        let result = parse(&mut ctx, r#" { "a": [1, 2.5, "x\né😀"], "b": { "c": null } } "#).unwrap();
+        let key = ctx.intern_atom("a");
+        let a = ctx.get_property(result, key).unwrap();
+        assert_eq!(ctx.array_length(a), Some(3));
+        let s = ctx.get_array_element(a, 2).unwrap();
+        assert_eq!(ctx.get_string(s), Some("x\n\u{e9}\u{1f600}"));
+
+        // Round trip
+        assert_eq!(json(&mut ctx, result), r#"{"a":[1,2.5,"x\né😀"],"b":{"c":null}}"#);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let mut ctx = Context::new(65536);
+
+        for (input, message) in [
+            ("[1, 2", "Unexpected end of JSON input"),
+            ("[1,]", "Unexpected token ']' in JSON at position 3"),
+            ("{'a': 1}", "Unexpected token ''' in JSON at position 1"),
+            ("tru", "Unexpected end of JSON input"),
+            ("nul!", "Unexpected token '!' in JSON at position 3"),
+            ("01", "Unexpected token '1' in JSON at position 1"),
+            ("1.", "Unexpected end of JSON input"),
+            ("\"a\nb\"", "Unexpected token '\n' in JSON at position 2"),
+            ("1 2", "Unexpected token '2' in JSON at position 2"),
+        ] {
+            let err = parse(&mut ctx, input).unwrap_err();
+            assert_eq!(ctx.error_kind(err), Some(ErrorKind::Syntax), "{}", input);
+            let msg = ctx.get_property(err, crate::value::atoms::MESSAGE).unwrap();
+            assert_eq!(ctx.get_string(msg), Some(message), "{}", input);
+        }
+
+        let deep = "[".repeat(ctx.max_nesting_depth() + 1);
+        let err = parse(&mut ctx, &deep).unwrap_err();
+        assert_eq!(ctx.error_kind(err), Some(ErrorKind::Range));
+    }
+
+    #[test]
+    fn test_parse_out_of_memory() {
+        let mut ctx = Context::new(2048);
+        let input = alloc::format!("[{}1]", "1,".repeat(1000));
+        assert!(parse(&mut ctx, &input).is_err());
+    }
+
     #[test]
     fn test_stringify_simple() {
-        let ctx = Context::new(4096);
+        let mut ctx = Context::new(4096);
+
+        assert_eq!(json(&mut ctx, JSValue::null()), "null");
+        assert_eq!(json(&mut ctx, JSValue::bool(true)), "true");
+        assert_eq!(json(&mut ctx, JSValue::bool(false)), "false");
+        assert_eq!(json(&mut ctx, JSValue::from_int(42)), "42");
+        assert_eq!(stringify(&mut ctx, JSValue::undefined()).unwrap(), None);
+
+        let n = ctx.new_number(1e21).unwrap();
+        assert_eq!(json(&mut ctx, n), "1e+21");
+        let n = ctx.new_number(f64::NAN).unwrap();
+        assert_eq!(json(&mut ctx, n), "null");
 
-        assert_eq!(stringify(&ctx, JSValue::null()).unwrap(), "null");
-        assert_eq!(stringify(&ctx, JSValue::bool(true)).unwrap(), "true");
-        assert_eq!(stringify(&ctx, JSValue::bool(false)).unwrap(), "false");
-        assert_eq!(stringify(&ctx, JSValue::from_int(42)).unwrap(), "42");
+        let s = ctx.new_string("a\"b\\c\u{1}").unwrap();
+        assert_eq!(json(&mut ctx, s), r#""a\"b\\c\u0001""#);
+    }
+
+    #[test]
+    fn test_stringify_cycle() {
+        let mut ctx = Context::new(8192);
+
+        let obj = ctx.new_object().unwrap();
+        let key = ctx.intern_atom("self");
+        ctx.add_property(obj, key, obj, PropertyFlags::default()).unwrap();
+
+        let err = stringify(&mut ctx, obj).unwrap_err();
+        assert_eq!(ctx.error_kind(err), Some(ErrorKind::Type));
+
+        // Shared (non-cyclic) references are fine
+        let inner = ctx.new_array().unwrap();
+        let outer = ctx.new_array().unwrap();
+        ctx.set_array_element(outer, 0, inner).unwrap();
+        ctx.set_array_element(outer, 1, inner).unwrap();
+        assert_eq!(json(&mut ctx, outer), "[[],[]]");
     }
 }
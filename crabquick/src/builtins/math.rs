@@ -2,7 +2,7 @@
 //!
 //! Implements Math.abs, Math.floor, Math.ceil, Math.round, Math.trunc,
 //! Math.min, Math.max, Math.pow, Math.sqrt, Math.sin, Math.cos, Math.tan,
-//! Math.log, Math.exp, Math.random, and Math constants
+//! Math.sign, Math.log, Math.exp, Math.atan2, Math.random, and Math constants
 
 // Math constants
 pub const PI: f64 = core::f64::consts::PI;
@@ -14,6 +14,9 @@ pub const LOG10E: f64 = core::f64::consts::LOG10_E;
 pub const SQRT2: f64 = core::f64::consts::SQRT_2;
 pub const SQRT1_2: f64 = core::f64::consts::FRAC_1_SQRT_2;
 
+/// Seed used by Math.random until the host calls `Context::seed_random`
+pub const DEFAULT_RANDOM_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
 /// Math.abs() - Returns absolute value
 #[inline]
 pub fn abs(x: f64) -> f64 {
@@ -33,9 +36,13 @@ pub fn ceil(x: f64) -> f64 {
 }
 
 /// Math.round() - Returns value rounded to nearest integer
+///
+/// Halves round toward +Infinity, and results in [-0.5, 0) are -0.
 #[inline]
 pub fn round(x: f64) -> f64 {
-    libm::round(x)
+    let r = libm::floor(x);
+    let r = if x - r >= 0.5 { r + 1.0 } else { r };
+    libm::copysign(r, x)
 }
 
 /// Math.trunc() - Returns integer part of x
@@ -45,18 +52,56 @@ pub fn trunc(x: f64) -> f64 {
 }
 
 /// Math.min() - Returns smallest of given numbers
+///
+/// Infinity for no arguments, NaN if any argument is NaN; -0 is below +0.
 pub fn min(args: &[f64]) -> f64 {
-    args.iter().fold(f64::INFINITY, |a, &b| if a < b { a } else { b })
+    args.iter().fold(f64::INFINITY, |a, &b| {
+        if a.is_nan() || b.is_nan() {
+            f64::NAN
+        } else if a < b || (a == b && a.is_sign_negative()) {
+            a
+        } else {
+            b
+        }
+    })
 }
 
 /// Math.max() - Returns largest of given numbers
+///
+/// -Infinity for no arguments, NaN if any argument is NaN; +0 is above -0.
 pub fn max(args: &[f64]) -> f64 {
-    args.iter().fold(f64::NEG_INFINITY, |a, &b| if a > b { a } else { b })
+    args.iter().fold(f64::NEG_INFINITY, |a, &b| {
+        if a.is_nan() || b.is_nan() {
+            f64::NAN
+        } else if a > b || (a == b && a.is_sign_positive()) {
+            a
+        } else {
+            b
+        }
+    })
+}
+
+/// Math.sign() - Returns 1 or -1 by the sign of x, or x itself for zeros and NaN
+#[inline]
+pub fn sign(x: f64) -> f64 {
+    if x > 0.0 {
+        1.0
+    } else if x < 0.0 {
+        -1.0
+    } else {
+        x
+    }
 }
 
 /// Math.pow() - Returns base raised to exponent power
+///
+/// Unlike C's pow, a NaN exponent always gives NaN (even for base 1), as
+/// does a base of +/-1 with an infinite exponent.
 #[inline]
 pub fn pow(base: f64, exponent: f64) -> f64 {
+    if exponent.is_nan() || (libm::fabs(base) == 1.0 && exponent.is_infinite()) {
+        return f64::NAN;
+    }
     libm::pow(base, exponent)
 }
 
@@ -132,17 +177,19 @@ pub fn exp(x: f64) -> f64 {
     libm::exp(x)
 }
 
-/// Math.random() - Returns pseudo-random number between 0 and 1
+/// Math.random() - Returns pseudo-random number in [0, 1)
 ///
-/// Simplified PRNG using linear congruential generator
+/// Advances `state` with xorshift64* and uses the top 53 bits of the output.
+/// A zero state would never change, so it is replaced by the default seed.
 pub fn random(state: &mut u64) -> f64 {
-    // Linear congruential generator: x = (a * x + c) mod m
-    const A: u64 = 1103515245;
-    const C: u64 = 12345;
-    const M: u64 = 1u64 << 31;
-
-    *state = (A.wrapping_mul(*state).wrapping_add(C)) % M;
-    (*state as f64) / (M as f64)
+    let mut x = if *state == 0 { DEFAULT_RANDOM_SEED } else { *state };
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    *state = x;
+
+    let bits = x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11;
+    bits as f64 / (1u64 << 53) as f64
 }
 
 #[cfg(test)]
@@ -171,6 +218,10 @@ mod tests {
     fn test_round() {
         assert_eq!(round(3.5), 4.0);
         assert_eq!(round(3.4), 3.0);
+        assert_eq!(round(-2.5), -2.0);
+        assert!(round(-0.5) == 0.0 && round(-0.5).is_sign_negative());
+        assert!(round(0.4).is_sign_positive());
+        assert!(round(f64::NAN).is_nan());
     }
 
     #[test]
@@ -183,12 +234,28 @@ mod tests {
     fn test_min_max() {
         assert_eq!(min(&[1.0, 2.0, 3.0]), 1.0);
         assert_eq!(max(&[1.0, 2.0, 3.0]), 3.0);
+        assert_eq!(min(&[]), f64::INFINITY);
+        assert_eq!(max(&[]), f64::NEG_INFINITY);
+        assert!(max(&[1.0, f64::NAN, 3.0]).is_nan());
+        assert!(min(&[0.0, -0.0]).is_sign_negative());
+        assert!(max(&[-0.0, 0.0]).is_sign_positive());
+    }
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(sign(-3.0), -1.0);
+        assert_eq!(sign(2.0), 1.0);
+        assert!(sign(-0.0).is_sign_negative());
+        assert!(sign(f64::NAN).is_nan());
     }
 
     #[test]
     fn test_pow_sqrt() {
         assert_eq!(pow(2.0, 3.0), 8.0);
         assert_eq!(sqrt(9.0), 3.0);
+        assert!(pow(1.0, f64::NAN).is_nan());
+        assert!(pow(-1.0, f64::INFINITY).is_nan());
+        assert_eq!(pow(f64::NAN, 0.0), 1.0);
     }
 
     #[test]
@@ -206,5 +273,9 @@ mod tests {
         assert!(r1 >= 0.0 && r1 < 1.0);
         assert!(r2 >= 0.0 && r2 < 1.0);
         assert_ne!(r1, r2); // Should be different
+
+        // Same seed, same sequence
+        let mut again = 12345;
+        assert_eq!(random(&mut again), r1);
     }
 }
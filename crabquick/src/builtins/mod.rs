@@ -6,15 +6,23 @@ pub mod string;
 pub mod number;
 pub mod boolean;
 pub mod function;
+#[cfg(feature = "builtin-math")]
 pub mod math;
+#[cfg(feature = "builtin-console")]
 pub mod console;
 pub mod error;
 pub mod native_functions;
+#[cfg(feature = "test-api")]
+pub mod test_api;
+#[cfg(feature = "builtin-typed-array")]
+pub mod typed_array;
+#[cfg(feature = "builtin-date")]
+pub mod date;
 
 // Legacy modules (stubs for future implementation)
+#[cfg(feature = "builtin-json")]
 pub mod json;
 pub mod regexp;
-pub mod typed_array;
 
 // Re-exports for convenience
 pub use object::{object_constructor, object_keys, object_values, object_entries, object_assign, object_create};
@@ -22,5 +30,6 @@ pub use array::{array_constructor, is_array as array_is_array, array_push, array
 pub use string::{string_constructor, string_length};
 pub use number::{number_constructor, is_nan as number_is_nan, is_finite as number_is_finite};
 pub use boolean::{boolean_constructor, to_boolean};
+#[cfg(feature = "builtin-console")]
 pub use console::{console_log, console_error, console_warn, console_info};
-pub use error::{error_constructor, type_error_constructor, ErrorType};
+pub use error::{error_constructor, type_error_constructor, ErrorType, ErrorKind};
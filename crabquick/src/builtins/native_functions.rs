@@ -5,197 +5,122 @@
 //! convert the result back to JavaScript values.
 
 use crate::context::Context;
-use crate::value::JSValue;
-use crate::builtins::{math, console, array, string, object, number};
+use crate::value::{atoms, JSValue};
+use crate::builtins::{array, string, object, number};
+#[cfg(feature = "builtin-math")]
+use crate::builtins::math;
+#[cfg(feature = "builtin-console")]
+use crate::builtins::console;
 use alloc::string::ToString;
 
 // ========== Math Functions ==========
 
-/// Math.abs() wrapper
-pub fn math_abs(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    // Get first argument or return NaN
-    let arg = args.get(0).copied().unwrap_or(JSValue::undefined());
+/// Converts argument `index` to a number, NaN when it's missing
+#[cfg(feature = "builtin-math")]
+fn number_arg(ctx: &Context, args: &[JSValue], index: usize) -> f64 {
+    use crate::runtime::conversion::to_number;
 
-    // Convert to number
-    let num = if let Some(i) = arg.to_int() {
-        i as f64
-    } else if let Some(f) = ctx.get_number(arg) {
-        f
-    } else {
-        f64::NAN
-    };
-
-    // Calculate absolute value
-    let result = math::abs(num);
-
-    // Return as JSValue
-    ctx.new_number(result)
-        .map_err(|_| ctx.new_string("Out of memory").unwrap_or(JSValue::undefined()))
+    args.get(index).map_or(f64::NAN, |arg| to_number(ctx, *arg))
 }
 
-/// Math.floor() wrapper
-pub fn math_floor(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    let arg = args.get(0).copied().unwrap_or(JSValue::undefined());
-
-    let num = if let Some(i) = arg.to_int() {
-        i as f64
-    } else if let Some(f) = ctx.get_number(arg) {
-        f
-    } else {
-        f64::NAN
-    };
-
-    let result = math::floor(num);
-
+/// Boxes the result of a Math function
+#[cfg(feature = "builtin-math")]
+fn math_result(ctx: &mut Context, result: f64) -> Result<JSValue, JSValue> {
     ctx.new_number(result)
         .map_err(|_| ctx.new_string("Out of memory").unwrap_or(JSValue::undefined()))
 }
 
-/// Math.ceil() wrapper
-pub fn math_ceil(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    let arg = args.get(0).copied().unwrap_or(JSValue::undefined());
-
-    let num = if let Some(i) = arg.to_int() {
-        i as f64
-    } else if let Some(f) = ctx.get_number(arg) {
-        f
-    } else {
-        f64::NAN
+/// Defines a wrapper for a Math function of one argument
+#[cfg(feature = "builtin-math")]
+macro_rules! math_unary {
+    ($($name:ident => $func:path, $js:literal;)*) => {
+        $(
+            #[doc = concat!("Math.", $js, "() wrapper")]
+            pub fn $name(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+                let x = number_arg(ctx, args, 0);
+                math_result(ctx, $func(x))
+            }
+        )*
     };
-
-    let result = math::ceil(num);
-
-    ctx.new_number(result)
-        .map_err(|_| ctx.new_string("Out of memory").unwrap_or(JSValue::undefined()))
 }
 
-/// Math.round() wrapper
-pub fn math_round(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    let arg = args.get(0).copied().unwrap_or(JSValue::undefined());
-
-    let num = if let Some(i) = arg.to_int() {
-        i as f64
-    } else if let Some(f) = ctx.get_number(arg) {
-        f
-    } else {
-        f64::NAN
-    };
-
-    let result = math::round(num);
-
-    ctx.new_number(result)
-        .map_err(|_| ctx.new_string("Out of memory").unwrap_or(JSValue::undefined()))
+#[cfg(feature = "builtin-math")]
+math_unary! {
+    math_abs => math::abs, "abs";
+    math_floor => math::floor, "floor";
+    math_ceil => math::ceil, "ceil";
+    math_round => math::round, "round";
+    math_trunc => math::trunc, "trunc";
+    math_sign => math::sign, "sign";
+    math_sqrt => math::sqrt, "sqrt";
+    math_log => math::log, "log";
+    math_exp => math::exp, "exp";
+    math_sin => math::sin, "sin";
+    math_cos => math::cos, "cos";
 }
 
 /// Math.min() wrapper
+#[cfg(feature = "builtin-math")]
 pub fn math_min(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    // Convert all arguments to f64
-    let mut nums = alloc::vec::Vec::new();
-    for arg in args {
-        let num = if let Some(i) = arg.to_int() {
-            i as f64
-        } else if let Some(f) = ctx.get_number(*arg) {
-            f
-        } else {
-            f64::NAN
-        };
-        nums.push(num);
-    }
-
-    let result = math::min(&nums);
-
-    ctx.new_number(result)
-        .map_err(|_| ctx.new_string("Out of memory").unwrap_or(JSValue::undefined()))
+    let nums: alloc::vec::Vec<f64> = (0..args.len()).map(|i| number_arg(ctx, args, i)).collect();
+    math_result(ctx, math::min(&nums))
 }
 
 /// Math.max() wrapper
+#[cfg(feature = "builtin-math")]
 pub fn math_max(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    // Convert all arguments to f64
-    let mut nums = alloc::vec::Vec::new();
-    for arg in args {
-        let num = if let Some(i) = arg.to_int() {
-            i as f64
-        } else if let Some(f) = ctx.get_number(*arg) {
-            f
-        } else {
-            f64::NAN
-        };
-        nums.push(num);
-    }
-
-    let result = math::max(&nums);
-
-    ctx.new_number(result)
-        .map_err(|_| ctx.new_string("Out of memory").unwrap_or(JSValue::undefined()))
+    let nums: alloc::vec::Vec<f64> = (0..args.len()).map(|i| number_arg(ctx, args, i)).collect();
+    math_result(ctx, math::max(&nums))
 }
 
 /// Math.pow() wrapper
+#[cfg(feature = "builtin-math")]
 pub fn math_pow(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    let base = if args.is_empty() {
-        f64::NAN
-    } else if let Some(i) = args[0].to_int() {
-        i as f64
-    } else if let Some(f) = ctx.get_number(args[0]) {
-        f
-    } else {
-        f64::NAN
-    };
-
-    let exp = if args.len() < 2 {
-        f64::NAN
-    } else if let Some(i) = args[1].to_int() {
-        i as f64
-    } else if let Some(f) = ctx.get_number(args[1]) {
-        f
-    } else {
-        f64::NAN
-    };
-
-    let result = libm::pow(base, exp);
-
-    ctx.new_number(result)
-        .map_err(|_| ctx.new_string("Out of memory").unwrap_or(JSValue::undefined()))
+    let base = number_arg(ctx, args, 0);
+    let exponent = number_arg(ctx, args, 1);
+    math_result(ctx, math::pow(base, exponent))
 }
 
-/// Math.sqrt() wrapper
-pub fn math_sqrt(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    let num = if args.is_empty() {
-        f64::NAN
-    } else if let Some(i) = args[0].to_int() {
-        i as f64
-    } else if let Some(f) = ctx.get_number(args[0]) {
-        f
-    } else {
-        f64::NAN
-    };
-
-    let result = libm::sqrt(num);
+/// Math.atan2() wrapper
+#[cfg(feature = "builtin-math")]
+pub fn math_atan2(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let y = number_arg(ctx, args, 0);
+    let x = number_arg(ctx, args, 1);
+    math_result(ctx, math::atan2(y, x))
+}
 
-    ctx.new_number(result)
-        .map_err(|_| ctx.new_string("Out of memory").unwrap_or(JSValue::undefined()))
+/// Math.random() wrapper
+#[cfg(feature = "builtin-math")]
+pub fn math_random(ctx: &mut Context, _this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let result = ctx.next_random();
+    math_result(ctx, result)
 }
 
 // ========== Console Functions ==========
 
 /// console.log() wrapper
+#[cfg(feature = "builtin-console")]
 pub fn console_log_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     console::console_log(ctx, args);
     Ok(JSValue::undefined())
 }
 
 /// console.error() wrapper
+#[cfg(feature = "builtin-console")]
 pub fn console_error_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     console::console_error(ctx, args);
     Ok(JSValue::undefined())
 }
 
 /// console.warn() wrapper
+#[cfg(feature = "builtin-console")]
 pub fn console_warn_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     console::console_warn(ctx, args);
     Ok(JSValue::undefined())
 }
 
 /// console.info() wrapper
+#[cfg(feature = "builtin-console")]
 pub fn console_info_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     console::console_info(ctx, args);
     Ok(JSValue::undefined())
@@ -203,6 +128,19 @@ pub fn console_info_native(ctx: &mut Context, _this: JSValue, args: &[JSValue])
 
 // ========== Array.prototype Methods ==========
 
+/// Converts argument `index` with ToIntegerOrInfinity, saturated to an i32
+///
+/// Returns None when the argument is missing or undefined. String and array
+/// lengths fit in an i32, so saturating keeps out-of-range positions out of
+/// range.
+fn integer_arg(ctx: &Context, args: &[JSValue], index: usize) -> Option<i32> {
+    use crate::runtime::conversion::to_integer_or_infinity;
+
+    args.get(index)
+        .filter(|arg| !arg.is_undefined())
+        .map(|arg| to_integer_or_infinity(ctx, *arg) as i32)
+}
+
 /// Array.prototype.push() wrapper
 pub fn array_push_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     let len = array::array_push(ctx, this, args)?;
@@ -229,10 +167,8 @@ pub fn array_unshift_native(ctx: &mut Context, this: JSValue, args: &[JSValue])
 
 /// Array.prototype.slice() wrapper
 pub fn array_slice_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
-
-    let start = args.get(0).map(|v| to_int32(ctx, *v));
-    let end = args.get(1).map(|v| to_int32(ctx, *v));
+    let start = integer_arg(ctx, args, 0);
+    let end = integer_arg(ctx, args, 1);
 
     array::array_slice(ctx, this, start, end)
 }
@@ -244,10 +180,10 @@ pub fn array_concat_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -
 
 /// Array.prototype.indexOf() wrapper
 pub fn array_index_of_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
+    use crate::runtime::conversion::to_integer_or_infinity;
 
     let search_element = args.get(0).copied().unwrap_or(JSValue::undefined());
-    let from_index = args.get(1).map(|v| to_int32(ctx, *v));
+    let from_index = args.get(1).map_or(0.0, |v| to_integer_or_infinity(ctx, *v));
 
     let index = array::array_index_of(ctx, this, search_element, from_index)?;
     ctx.new_number(index as f64)
@@ -256,10 +192,8 @@ pub fn array_index_of_native(ctx: &mut Context, this: JSValue, args: &[JSValue])
 
 /// Array.prototype.includes() wrapper
 pub fn array_includes_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
-
     let search_element = args.get(0).copied().unwrap_or(JSValue::undefined());
-    let from_index = args.get(1).map(|v| to_int32(ctx, *v));
+    let from_index = integer_arg(ctx, args, 1);
 
     let result = array::array_includes(ctx, this, search_element, from_index)?;
     Ok(JSValue::bool(result))
@@ -267,15 +201,26 @@ pub fn array_includes_native(ctx: &mut Context, this: JSValue, args: &[JSValue])
 
 /// Array.prototype.splice() wrapper
 pub fn array_splice_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
-
-    let start = args.get(0).map(|v| to_int32(ctx, *v)).unwrap_or(0);
-    let delete_count = args.get(1).map(|v| to_int32(ctx, *v));
+    use crate::runtime::conversion::to_integer_or_infinity;
+
+    let start = integer_arg(ctx, args, 0).unwrap_or(0);
+    // With no arguments nothing is removed; with only `start`, everything after it is
+    let delete_count = match args.get(1) {
+        Some(count) => Some(to_integer_or_infinity(ctx, *count) as i32),
+        None if args.is_empty() => Some(0),
+        None => None,
+    };
     let items = if args.len() > 2 { &args[2..] } else { &[] };
 
     array::array_splice(ctx, this, start, delete_count, items)
 }
 
+/// Array.prototype.at() wrapper
+pub fn array_at_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let index = integer_arg(ctx, args, 0).unwrap_or(0);
+    array::array_at(ctx, this, index)
+}
+
 /// Array.prototype.join() wrapper
 pub fn array_join_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     // Get separator from first argument, default to ","
@@ -355,10 +300,10 @@ pub fn array_every_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) ->
 
 /// Array.prototype.lastIndexOf() wrapper
 pub fn array_last_index_of_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
+    use crate::runtime::conversion::to_integer_or_infinity;
 
     let search = args.get(0).copied().unwrap_or(JSValue::undefined());
-    let from_index = args.get(1).map(|v| to_int32(ctx, *v));
+    let from_index = args.get(1).map(|v| to_integer_or_infinity(ctx, *v));
 
     let result = array::array_last_index_of(ctx, this, search, from_index)?;
     Ok(JSValue::from_int(result))
@@ -384,50 +329,81 @@ pub fn array_to_string_native(ctx: &mut Context, this: JSValue, _args: &[JSValue
 
 // ========== String.prototype Methods ==========
 
+/// Coerces the receiver of a String.prototype method to a string
+///
+/// Throws a TypeError naming `method` for undefined and null.
+fn this_string(ctx: &mut Context, this: JSValue, method: &str) -> Result<JSValue, JSValue> {
+    use crate::runtime::conversion::{require_object_coercible, to_string};
+
+    let this = require_object_coercible(ctx, this, method)?;
+    if ctx.get_string(this).is_some() {
+        return Ok(this);
+    }
+    let s = to_string(ctx, this);
+    ctx.new_string(&s).map_err(|_| JSValue::exception())
+}
+
+/// String.prototype.at() wrapper
+#[cfg(feature = "builtin-string-extras")]
+pub fn string_at_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let this = this_string(ctx, this, "String.prototype.at")?;
+    let index = integer_arg(ctx, args, 0).unwrap_or(0);
+    string::at(ctx, this, index)
+}
+
+/// `String()` wrapper
+///
+/// There are no String wrapper objects, so `new String(...)`, which passes
+/// a fresh object inheriting from `String.prototype`, throws a `TypeError`.
+pub fn string_constructor_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let string_proto = ctx.get_global_property(atoms::STRING)
+        .and_then(|ctor| ctx.get_property(ctor, atoms::PROTOTYPE));
+    if string_proto.is_some_and(|proto| ctx.get_object(this).is_some_and(|obj| obj.prototype() == proto)) {
+        return Err(ctx.new_error(crate::builtins::error::ErrorKind::Type, "String is not a constructor"));
+    }
+    string::string_constructor(ctx, args.first().copied())
+}
+
 /// String.prototype.charAt() wrapper
 pub fn string_char_at_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
-
-    let index = args.get(0).map(|v| to_int32(ctx, *v)).unwrap_or(0);
+    let this = this_string(ctx, this, "String.prototype.charAt")?;
+    let index = integer_arg(ctx, args, 0).unwrap_or(0);
     string::char_at(ctx, this, index)
 }
 
 /// String.prototype.charCodeAt() wrapper
 pub fn string_char_code_at_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
-
-    let index = args.get(0).map(|v| to_int32(ctx, *v)).unwrap_or(0);
+    let this = this_string(ctx, this, "String.prototype.charCodeAt")?;
+    let index = integer_arg(ctx, args, 0).unwrap_or(0);
     let code = string::char_code_at(ctx, this, index)?;
-    ctx.new_number(code as f64)
+    let code = if code < 0 { f64::NAN } else { code as f64 };
+    ctx.new_number(code)
         .map_err(|_| JSValue::exception())
 }
 
 /// String.prototype.slice() wrapper
 pub fn string_slice_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
-
-    let start = args.get(0).map(|v| to_int32(ctx, *v)).unwrap_or(0);
-    let end = args.get(1).map(|v| to_int32(ctx, *v));
+    let this = this_string(ctx, this, "String.prototype.slice")?;
+    let start = integer_arg(ctx, args, 0).unwrap_or(0);
+    let end = integer_arg(ctx, args, 1);
 
     string::slice(ctx, this, start, end)
 }
 
 /// String.prototype.substring() wrapper
 pub fn string_substring_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
-
-    let start = args.get(0).map(|v| to_int32(ctx, *v)).unwrap_or(0);
-    let end = args.get(1).map(|v| to_int32(ctx, *v));
+    let this = this_string(ctx, this, "String.prototype.substring")?;
+    let start = integer_arg(ctx, args, 0).unwrap_or(0);
+    let end = integer_arg(ctx, args, 1);
 
     string::substring(ctx, this, start, end)
 }
 
 /// String.prototype.indexOf() wrapper
 pub fn string_index_of_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
-
+    let this = this_string(ctx, this, "String.prototype.indexOf")?;
     let search = args.get(0).copied().unwrap_or(JSValue::undefined());
-    let from_index = args.get(1).map(|v| to_int32(ctx, *v));
+    let from_index = integer_arg(ctx, args, 1);
 
     let index = string::index_of(ctx, this, search, from_index)?;
     ctx.new_number(index as f64)
@@ -435,11 +411,17 @@ pub fn string_index_of_native(ctx: &mut Context, this: JSValue, args: &[JSValue]
 }
 
 /// String.prototype.lastIndexOf() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_last_index_of_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
+    use crate::runtime::conversion::to_number;
 
+    let this = this_string(ctx, this, "String.prototype.lastIndexOf")?;
     let search = args.get(0).copied().unwrap_or(JSValue::undefined());
-    let from_index = args.get(1).map(|v| to_int32(ctx, *v));
+    // NaN means search the whole string
+    let from_index = args.get(1)
+        .map(|v| to_number(ctx, *v))
+        .filter(|n| !n.is_nan())
+        .map(|n| libm::trunc(n) as i32);
 
     let index = string::last_index_of(ctx, this, search, from_index)?;
     ctx.new_number(index as f64)
@@ -447,19 +429,25 @@ pub fn string_last_index_of_native(ctx: &mut Context, this: JSValue, args: &[JSV
 }
 
 /// String.prototype.toLowerCase() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_to_lower_case_native(ctx: &mut Context, this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let this = this_string(ctx, this, "String.prototype.toLowerCase")?;
     string::to_lower_case(ctx, this)
 }
 
 /// String.prototype.toUpperCase() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_to_upper_case_native(ctx: &mut Context, this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let this = this_string(ctx, this, "String.prototype.toUpperCase")?;
     string::to_upper_case(ctx, this)
 }
 
 /// String.prototype.split() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_split_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     use crate::runtime::conversion::to_int32;
 
+    let this = this_string(ctx, this, "String.prototype.split")?;
     let separator = args.get(0).copied();
     let limit = args.get(1).map(|v| to_int32(ctx, *v));
 
@@ -467,86 +455,126 @@ pub fn string_split_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -
 }
 
 /// String.prototype.trim() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_trim_native(ctx: &mut Context, this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let this = this_string(ctx, this, "String.prototype.trim")?;
     string::trim(ctx, this)
 }
 
 /// String.prototype.trimStart() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_trim_start_native(ctx: &mut Context, this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let this = this_string(ctx, this, "String.prototype.trimStart")?;
     string::trim_start(ctx, this)
 }
 
 /// String.prototype.trimEnd() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_trim_end_native(ctx: &mut Context, this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let this = this_string(ctx, this, "String.prototype.trimEnd")?;
     string::trim_end(ctx, this)
 }
 
 /// String.prototype.replace() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_replace_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let this = this_string(ctx, this, "String.prototype.replace")?;
     let search = args.get(0).copied().unwrap_or(JSValue::undefined());
     let replace_val = args.get(1).copied().unwrap_or(JSValue::undefined());
     string::replace(ctx, this, search, replace_val)
 }
 
 /// String.prototype.replaceAll() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_replace_all_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let this = this_string(ctx, this, "String.prototype.replaceAll")?;
     let search = args.get(0).copied().unwrap_or(JSValue::undefined());
     let replace_val = args.get(1).copied().unwrap_or(JSValue::undefined());
     string::replace_all(ctx, this, search, replace_val)
 }
 
 /// String.prototype.includes() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_includes_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
-
+    let this = this_string(ctx, this, "String.prototype.includes")?;
     let search = args.get(0).copied().unwrap_or(JSValue::undefined());
-    let position = args.get(1).map(|v| to_int32(ctx, *v));
+    let position = integer_arg(ctx, args, 1);
 
     let result = string::includes(ctx, this, search, position)?;
     Ok(JSValue::bool(result))
 }
 
 /// String.prototype.startsWith() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_starts_with_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
-
+    let this = this_string(ctx, this, "String.prototype.startsWith")?;
     let search = args.get(0).copied().unwrap_or(JSValue::undefined());
-    let position = args.get(1).map(|v| to_int32(ctx, *v));
+    let position = integer_arg(ctx, args, 1);
 
     let result = string::starts_with(ctx, this, search, position)?;
     Ok(JSValue::bool(result))
 }
 
 /// String.prototype.endsWith() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_ends_with_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
-
+    let this = this_string(ctx, this, "String.prototype.endsWith")?;
     let search = args.get(0).copied().unwrap_or(JSValue::undefined());
-    let length = args.get(1).map(|v| to_int32(ctx, *v));
+    let length = integer_arg(ctx, args, 1);
 
     let result = string::ends_with(ctx, this, search, length)?;
     Ok(JSValue::bool(result))
 }
 
+/// String.prototype.padStart() wrapper
+#[cfg(feature = "builtin-string-extras")]
+pub fn string_pad_start_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let this = this_string(ctx, this, "String.prototype.padStart")?;
+    let (max_length, fill) = pad_args(ctx, args);
+    string::pad(ctx, this, max_length, fill, true)
+}
+
+/// String.prototype.padEnd() wrapper
+#[cfg(feature = "builtin-string-extras")]
+pub fn string_pad_end_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let this = this_string(ctx, this, "String.prototype.padEnd")?;
+    let (max_length, fill) = pad_args(ctx, args);
+    string::pad(ctx, this, max_length, fill, false)
+}
+
+/// Converts padStart/padEnd arguments to a target length and fill string
+#[cfg(feature = "builtin-string-extras")]
+fn pad_args(ctx: &Context, args: &[JSValue]) -> (u64, Option<JSValue>) {
+    use crate::runtime::conversion::to_length;
+
+    let max_length = args.get(0).map_or(0, |v| to_length(ctx, *v));
+    let fill = args.get(1).copied().filter(|v| !v.is_undefined());
+    (max_length, fill)
+}
+
 /// String.prototype.concat() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_concat_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let this = this_string(ctx, this, "String.prototype.concat")?;
     string::concat(ctx, this, args)
 }
 
 /// String.prototype.codePointAt() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_code_point_at_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::conversion::to_int32;
-
-    let index = args.get(0).map(|v| to_int32(ctx, *v)).unwrap_or(0);
+    let this = this_string(ctx, this, "String.prototype.codePointAt")?;
+    let index = integer_arg(ctx, args, 0).unwrap_or(0);
     string::code_point_at(ctx, this, index)
 }
 
 /// String.fromCharCode() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_from_char_code_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     string::from_char_code(ctx, args)
 }
 
 /// String.fromCodePoint() wrapper
+#[cfg(feature = "builtin-string-extras")]
 pub fn string_from_code_point_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     string::from_code_point(ctx, args)
 }
@@ -556,18 +584,21 @@ pub fn string_from_code_point_native(ctx: &mut Context, _this: JSValue, args: &[
 /// Object.keys() wrapper
 pub fn object_keys_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     let obj = args.get(0).copied().unwrap_or(JSValue::undefined());
+    let obj = crate::runtime::conversion::to_object(ctx, obj)?;
     object::object_keys(ctx, obj)
 }
 
 /// Object.values() wrapper
 pub fn object_values_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     let obj = args.get(0).copied().unwrap_or(JSValue::undefined());
+    let obj = crate::runtime::conversion::to_object(ctx, obj)?;
     object::object_values(ctx, obj)
 }
 
 /// Object.entries() wrapper
 pub fn object_entries_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     let obj = args.get(0).copied().unwrap_or(JSValue::undefined());
+    let obj = crate::runtime::conversion::to_object(ctx, obj)?;
     object::object_entries(ctx, obj)
 }
 
@@ -612,15 +643,17 @@ pub fn object_define_property_native(ctx: &mut Context, _this: JSValue, args: &[
 
 /// Object.prototype.hasOwnProperty() wrapper
 pub fn object_has_own_property_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::init::string_to_atom;
-
     let prop = args.get(0).copied().unwrap_or(JSValue::undefined());
 
+    // A name that was never interned can't be an own property
     let prop_atom = if let Some(s) = ctx.get_string(prop) {
-        string_to_atom(s)
+        ctx.lookup_atom(s)
     } else if let Some(n) = prop.to_int() {
-        string_to_atom(&alloc::format!("{}", n))
+        ctx.lookup_atom(&alloc::format!("{}", n))
     } else {
+        None
+    };
+    let Some(prop_atom) = prop_atom else {
         return Ok(JSValue::bool(false));
     };
 
@@ -701,6 +734,14 @@ pub fn decode_uri_component_native(ctx: &mut Context, _this: JSValue, args: &[JS
     decode_uri_component(ctx, component)
 }
 
+/// exit() - ends the script with a status code
+///
+/// Not installed by default; hosts opt in with `Engine::enable_exit`.
+pub fn exit_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let code = args.first().map_or(0, |&code| crate::runtime::conversion::to_int32(ctx, code));
+    Err(crate::builtins::error::create_exit_request(ctx, code)?)
+}
+
 // ========== Function.prototype Methods ==========
 
 /// Function.prototype.call() wrapper
@@ -715,6 +756,7 @@ pub fn function_call_native(ctx: &mut Context, this: JSValue, args: &[JSValue])
 /// Function.prototype.apply() wrapper
 pub fn function_apply_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     use alloc::vec::Vec;
+    use crate::runtime::conversion::to_length;
     use crate::runtime::init::string_to_atom;
 
     let this_arg = args.get(0).copied().unwrap_or(JSValue::undefined());
@@ -723,23 +765,22 @@ pub fn function_apply_native(ctx: &mut Context, this: JSValue, args: &[JSValue])
     // Extract elements from the array object
     let call_args_vec: Vec<JSValue> = if args_array.is_object() || args_array.to_ptr().is_some() {
         // Get the length
-        let length_atom = string_to_atom("length");
+        let length_atom = atoms::LENGTH;
         let length = ctx.get_property(args_array, length_atom)
-            .and_then(|v| {
-                if let Some(i) = v.to_int() {
-                    Some(i as usize)
-                } else if let Some(n) = ctx.get_number(v) {
-                    Some(n as usize)
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(0);
+            .map_or(0, |v| to_length(ctx, v));
+
+        // Every argument takes a stack slot, so more than the heap could
+        // ever hold is an error rather than a huge allocation
+        let max_args = ctx.arena_size() / core::mem::size_of::<JSValue>();
+        if length > max_args as u64 {
+            return Err(ctx.new_error(crate::builtins::error::ErrorKind::Range, "Too many arguments"));
+        }
+        let length = length as usize;
 
         // Extract each element by index
         let mut result = Vec::with_capacity(length);
         for i in 0..length {
-            let idx_atom = string_to_atom(&alloc::format!("{}", i));
+            let idx_atom = string_to_atom(ctx, &alloc::format!("{}", i));
             let val = ctx.get_property(args_array, idx_atom)
                 .unwrap_or(JSValue::undefined());
             result.push(val);
@@ -762,13 +803,13 @@ pub fn function_bind_native(ctx: &mut Context, this: JSValue, args: &[JSValue])
         .map_err(|_| JSValue::exception())?;
 
     // Store the target function
-    let target_atom = string_to_atom("__boundTarget__");
+    let target_atom = atoms::BOUND_TARGET;
     ctx.add_property(bound_obj, target_atom, this, crate::object::PropertyFlags::empty())
         .map_err(|_| JSValue::exception())?;
 
     // Store the bound this value
     let bound_this = args.get(0).copied().unwrap_or(JSValue::undefined());
-    let this_atom = string_to_atom("__boundThis__");
+    let this_atom = atoms::BOUND_THIS;
     ctx.add_property(bound_obj, this_atom, bound_this, crate::object::PropertyFlags::empty())
         .map_err(|_| JSValue::exception())?;
 
@@ -777,22 +818,22 @@ pub fn function_bind_native(ctx: &mut Context, this: JSValue, args: &[JSValue])
         let bound_args = ctx.new_object()
             .map_err(|_| JSValue::exception())?;
         for (i, arg) in args[1..].iter().enumerate() {
-            let idx_atom = string_to_atom(&alloc::format!("{}", i));
+            let idx_atom = string_to_atom(ctx, &alloc::format!("{}", i));
             ctx.add_property(bound_args, idx_atom, *arg, crate::object::PropertyFlags::empty())
                 .map_err(|_| JSValue::exception())?;
         }
-        let length_atom = string_to_atom("length");
+        let length_atom = atoms::LENGTH;
         let length_val = JSValue::from_int((args.len() - 1) as i32);
         ctx.add_property(bound_args, length_atom, length_val, crate::object::PropertyFlags::empty())
             .map_err(|_| JSValue::exception())?;
 
-        let args_atom = string_to_atom("__boundArgs__");
+        let args_atom = atoms::BOUND_ARGS;
         ctx.add_property(bound_obj, args_atom, bound_args, crate::object::PropertyFlags::empty())
             .map_err(|_| JSValue::exception())?;
     }
 
     // Mark this as a bound function (for call_function to recognize)
-    let is_bound_atom = string_to_atom("__isBoundFunction__");
+    let is_bound_atom = atoms::IS_BOUND_FUNCTION;
     ctx.add_property(bound_obj, is_bound_atom, JSValue::bool(true), crate::object::PropertyFlags::empty())
         .map_err(|_| JSValue::exception())?;
 
@@ -828,142 +869,94 @@ pub fn number_is_integer_native(ctx: &mut Context, _this: JSValue, args: &[JSVal
 /// Number.prototype.toFixed() wrapper
 pub fn number_to_fixed_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     use crate::builtins::number;
-    use crate::runtime::conversion::to_int32;
-
-    let digits = args.get(0).map(|v| to_int32(ctx, *v));
+    let digits = integer_arg(ctx, args, 0);
     number::to_fixed(ctx, this, digits)
 }
 
 /// Number.prototype.toString() wrapper
 pub fn number_to_string_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     use crate::builtins::number;
-    use crate::runtime::conversion::to_int32;
-
-    let radix = args.get(0).map(|v| to_int32(ctx, *v));
+    let radix = integer_arg(ctx, args, 0);
     number::to_string(ctx, this, radix)
 }
 
 // ========== JSON Methods ==========
 
 /// JSON.parse() wrapper
+#[cfg(feature = "builtin-json")]
 pub fn json_parse_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     use crate::builtins::json;
-    use alloc::string::ToString;
 
     let json_str = args.get(0).copied().unwrap_or(JSValue::undefined());
-    let s = match ctx.get_string(json_str) {
-        Some(s) => s.to_string(),
-        None => return Err(ctx.new_string("JSON.parse requires a string").unwrap_or(JSValue::exception())),
-    };
+    let s = crate::runtime::conversion::to_string(ctx, json_str);
 
     json::parse(ctx, &s)
 }
 
 /// JSON.stringify() wrapper
+#[cfg(feature = "builtin-json")]
 pub fn json_stringify_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     use crate::builtins::json;
 
     let value = args.get(0).copied().unwrap_or(JSValue::undefined());
-    let result = json::stringify(ctx, value)?;
-
-    ctx.new_string(&result).map_err(|_| JSValue::exception())
+    match json::stringify(ctx, value)? {
+        Some(result) => ctx.new_string(&result).map_err(|_| JSValue::exception()),
+        None => Ok(JSValue::undefined()),
+    }
 }
 
 // ========== Error Constructors ==========
 
-/// Error() constructor - creates an Error object
-pub fn error_constructor(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    let error_obj = ctx.new_object()
-        .map_err(|_| JSValue::exception())?;
+/// Shared body of the error constructors: builds an error with `name`,
+/// `message` (if given) and the constructor's [`ErrorKind`](crate::builtins::error::ErrorKind)
+fn construct_error(ctx: &mut Context, error_type: crate::builtins::error::ErrorType, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let message = args.get(0).copied().filter(|msg| !msg.is_undefined());
+    crate::builtins::error::new_error_object(ctx, error_type, error_type.name(), message, error_type.kind())
+}
 
-    // Set the message property if provided
-    if let Some(msg) = args.get(0) {
-        let message_atom = crate::runtime::init::string_to_atom("message");
-        ctx.add_property(error_obj, message_atom, *msg, crate::object::PropertyFlags::default())
-            .map_err(|_| JSValue::exception())?;
-    }
+/// Constructor behind every error class registered with
+/// `Context::register_error_class`
+///
+/// Each class is a function bound to this one with the class's prototype as
+/// `this`, so `this` says which class to build; the error's `name` comes
+/// from the prototype and its kind from the builtin the class extends.
+pub fn error_class_constructor(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    use crate::builtins::error;
 
-    // Set the name property
-    let name_atom = crate::runtime::init::string_to_atom("name");
-    let name_val = ctx.new_string("Error").map_err(|_| JSValue::exception())?;
-    ctx.add_property(error_obj, name_atom, name_val, crate::object::PropertyFlags::default())
-        .map_err(|_| JSValue::exception())?;
+    let name = ctx.get_property(this, atoms::NAME)
+        .and_then(|name| ctx.get_string(name).map(alloc::string::String::from))
+        .unwrap_or_default();
+    let kind = error::builtin_error_type(ctx, this).kind();
+    let message = args.first().copied().filter(|msg| !msg.is_undefined());
+    error::new_error_object_with_proto(ctx, this, &name, message, kind)
+}
 
-    Ok(error_obj)
+/// Error() constructor - creates an Error object
+pub fn error_constructor(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    construct_error(ctx, crate::builtins::error::ErrorType::Error, args)
 }
 
 /// TypeError() constructor
 pub fn type_error_constructor(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    let error_obj = ctx.new_object()
-        .map_err(|_| JSValue::exception())?;
-
-    if let Some(msg) = args.get(0) {
-        let message_atom = crate::runtime::init::string_to_atom("message");
-        ctx.add_property(error_obj, message_atom, *msg, crate::object::PropertyFlags::default())
-            .map_err(|_| JSValue::exception())?;
-    }
-
-    let name_atom = crate::runtime::init::string_to_atom("name");
-    let name_val = ctx.new_string("TypeError").map_err(|_| JSValue::exception())?;
-    ctx.add_property(error_obj, name_atom, name_val, crate::object::PropertyFlags::default())
-        .map_err(|_| JSValue::exception())?;
-
-    Ok(error_obj)
+    construct_error(ctx, crate::builtins::error::ErrorType::TypeError, args)
 }
 
 /// ReferenceError() constructor
 pub fn reference_error_constructor(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    let error_obj = ctx.new_object()
-        .map_err(|_| JSValue::exception())?;
-
-    if let Some(msg) = args.get(0) {
-        let message_atom = crate::runtime::init::string_to_atom("message");
-        ctx.add_property(error_obj, message_atom, *msg, crate::object::PropertyFlags::default())
-            .map_err(|_| JSValue::exception())?;
-    }
-
-    let name_atom = crate::runtime::init::string_to_atom("name");
-    let name_val = ctx.new_string("ReferenceError").map_err(|_| JSValue::exception())?;
-    ctx.add_property(error_obj, name_atom, name_val, crate::object::PropertyFlags::default())
-        .map_err(|_| JSValue::exception())?;
-
-    Ok(error_obj)
+    construct_error(ctx, crate::builtins::error::ErrorType::ReferenceError, args)
 }
 
 /// RangeError() constructor
 pub fn range_error_constructor(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    let error_obj = ctx.new_object()
-        .map_err(|_| JSValue::exception())?;
-
-    if let Some(msg) = args.get(0) {
-        let message_atom = crate::runtime::init::string_to_atom("message");
-        ctx.add_property(error_obj, message_atom, *msg, crate::object::PropertyFlags::default())
-            .map_err(|_| JSValue::exception())?;
-    }
-
-    let name_atom = crate::runtime::init::string_to_atom("name");
-    let name_val = ctx.new_string("RangeError").map_err(|_| JSValue::exception())?;
-    ctx.add_property(error_obj, name_atom, name_val, crate::object::PropertyFlags::default())
-        .map_err(|_| JSValue::exception())?;
-
-    Ok(error_obj)
+    construct_error(ctx, crate::builtins::error::ErrorType::RangeError, args)
 }
 
 /// SyntaxError() constructor
 pub fn syntax_error_constructor(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-    let error_obj = ctx.new_object()
-        .map_err(|_| JSValue::exception())?;
-
-    if let Some(msg) = args.get(0) {
-        let message_atom = crate::runtime::init::string_to_atom("message");
-        ctx.add_property(error_obj, message_atom, *msg, crate::object::PropertyFlags::default())
-            .map_err(|_| JSValue::exception())?;
-    }
-
-    let name_atom = crate::runtime::init::string_to_atom("name");
-    let name_val = ctx.new_string("SyntaxError").map_err(|_| JSValue::exception())?;
-    ctx.add_property(error_obj, name_atom, name_val, crate::object::PropertyFlags::default())
-        .map_err(|_| JSValue::exception())?;
+    construct_error(ctx, crate::builtins::error::ErrorType::SyntaxError, args)
+}
 
-    Ok(error_obj)
+/// EvalError() constructor
+pub fn eval_error_constructor(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    construct_error(ctx, crate::builtins::error::ErrorType::EvalError, args)
 }
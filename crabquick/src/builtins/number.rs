@@ -4,6 +4,7 @@
 //! Number.parseInt(), Number.parseFloat(), Number.prototype.toString(),
 //! Number.prototype.toFixed(), and Number constants
 
+use crate::builtins::error::ErrorKind;
 use crate::context::Context;
 use crate::value::JSValue;
 use alloc::string::String;
@@ -73,94 +74,52 @@ pub fn parse_float(s: &str) -> f64 {
 }
 
 /// Number.prototype.toString() - Returns string representation
+///
+/// `radix` must be 2 to 36 (RangeError otherwise); it defaults to 10.
+/// Other radixes write fractions too, so `(0.5).toString(2)` is "0.1".
 pub fn to_string(ctx: &mut Context, num: JSValue, radix: Option<i32>) -> Result<JSValue, JSValue> {
-    // Get the number value (handle both inline ints and boxed floats)
-    let n = if let Some(i) = num.to_int() {
-        i as f64
-    } else if let Some(f) = ctx.get_number(num) {
-        f
-    } else {
-        return Err(JSValue::exception());
-    };
-
-    if n.is_nan() {
-        return ctx.new_string("NaN").map_err(|_| JSValue::exception());
+    let n = this_number(ctx, num)?;
+    let radix = radix.unwrap_or(10);
+    if !(2..=36).contains(&radix) {
+        return Err(ctx.new_error(ErrorKind::Range, "toString() radix must be between 2 and 36"));
     }
 
-    if n.is_infinite() {
-        let s = if n > 0.0 { "Infinity" } else { "-Infinity" };
-        return ctx.new_string(s).map_err(|_| JSValue::exception());
-    }
-
-    let radix = radix.unwrap_or(10).clamp(2, 36);
-    if radix == 10 {
-        let s = alloc::format!("{}", n);
-        ctx.new_string(&s).map_err(|_| JSValue::exception())
+    let result = if radix == 10 {
+        ctx.new_string(&crate::util::format_number(n))
     } else {
-        // Convert to integer for radix conversion
-        let int_val = n as i64;
-        let s = int_to_string_radix(int_val, radix as u32);
-        ctx.new_string(&s).map_err(|_| JSValue::exception())
-    }
+        let mut text = crate::util::NumberBuf::new();
+        crate::util::format_radix(n, radix as u32, &mut text);
+        ctx.new_string(text.as_str())
+    };
+    result.map_err(|_| JSValue::exception())
 }
 
-/// Convert an integer to a string in the given radix (2-36)
-fn int_to_string_radix(mut n: i64, radix: u32) -> alloc::string::String {
-    use alloc::string::String;
-    use alloc::vec::Vec;
-
-    if n == 0 {
-        return String::from("0");
-    }
-
-    let negative = n < 0;
-    if negative {
-        n = -n;
-    }
-
-    let mut digits = Vec::new();
-    while n > 0 {
-        let digit = (n % radix as i64) as u32;
-        let c = if digit < 10 {
-            (b'0' + digit as u8) as char
-        } else {
-            (b'a' + (digit - 10) as u8) as char
-        };
-        digits.push(c);
-        n /= radix as i64;
-    }
-
-    if negative {
-        digits.push('-');
+/// Number.prototype.toFixed() - Formats number with fixed decimal places
+///
+/// `digits` must be 0 to 100 (RangeError otherwise); it defaults to 0.
+pub fn to_fixed(ctx: &mut Context, num: JSValue, digits: Option<i32>) -> Result<JSValue, JSValue> {
+    let n = this_number(ctx, num)?;
+    let digits = digits.unwrap_or(0);
+    if !(0..=100).contains(&digits) {
+        return Err(ctx.new_error(ErrorKind::Range, "toFixed() digits must be between 0 and 100"));
     }
 
-    digits.reverse();
-    digits.into_iter().collect()
+    let mut text = crate::util::NumberBuf::new();
+    crate::util::format_fixed(n, digits as usize, &mut text);
+    ctx.new_string(text.as_str()).map_err(|_| JSValue::exception())
 }
 
-/// Number.prototype.toFixed() - Formats number with fixed decimal places
-pub fn to_fixed(ctx: &mut Context, num: JSValue, digits: Option<i32>) -> Result<JSValue, JSValue> {
-    // Get the number value (handle both inline ints and boxed floats)
-    let n = if let Some(i) = num.to_int() {
-        i as f64
+/// Returns the number a Number.prototype method was called on
+///
+/// Handles both inline ints and boxed floats.
+fn this_number(ctx: &mut Context, num: JSValue) -> Result<f64, JSValue> {
+    if let Some(i) = num.to_int() {
+        Ok(i as f64)
     } else if let Some(f) = ctx.get_number(num) {
-        f
+        Ok(f)
     } else {
-        return Err(JSValue::exception());
-    };
-    let digits = digits.unwrap_or(0).clamp(0, 20);
-
-    if n.is_nan() {
-        return ctx.new_string("NaN").map_err(|_| JSValue::exception());
+        Err(ctx.new_error(ErrorKind::Type, "not a number"))
     }
-
-    if n.is_infinite() {
-        let s = if n > 0.0 { "Infinity" } else { "-Infinity" };
-        return ctx.new_string(s).map_err(|_| JSValue::exception());
-    }
-
-    let s = alloc::format!("{:.prec$}", n, prec = digits as usize);
-    ctx.new_string(&s).map_err(|_| JSValue::exception())
 }
 
 /// Number constants
@@ -5,8 +5,7 @@
 //! Object.prototype.toString()
 
 use crate::context::Context;
-use crate::value::{JSValue, JSAtom};
-use crate::object::PropertyFlags;
+use crate::value::{atoms, JSValue, JSAtom};
 use alloc::vec::Vec;
 use alloc::string::String;
 
@@ -32,6 +31,10 @@ pub fn object_constructor(ctx: &mut Context, value: Option<JSValue>) -> Result<J
 
 /// Object.keys() - Returns an array of a given object's own property names
 ///
+/// Keys come in the same order as for-in and JSON.stringify use (see
+/// `Context::own_enumerable_keys`): integer keys ascending, then the other
+/// names in insertion order. Array holes have no key.
+///
 /// # Arguments
 ///
 /// * `ctx` - JavaScript execution context
@@ -41,105 +44,31 @@ pub fn object_constructor(ctx: &mut Context, value: Option<JSValue>) -> Result<J
 ///
 /// Array of property keys
 pub fn object_keys(ctx: &mut Context, obj: JSValue) -> Result<JSValue, JSValue> {
-    if !obj.is_object() {
-        // Should throw TypeError, but for simplicity return empty array
-        return create_empty_array(ctx);
-    }
-
-    let obj_ref = ctx.get_object(obj).ok_or(JSValue::exception())?;
-
-    if !obj_ref.has_properties() {
-        return create_empty_array(ctx);
-    }
-
-    // Get property table
-    let props_index = obj_ref.props_index();
-    let props_table = ctx.get_property_table(props_index)
-        .ok_or(JSValue::exception())?;
-
-    // Collect keys
     let mut keys = Vec::new();
-    unsafe {
-        let header = props_table.header();
-        let count = header.count() as usize;
-        let properties = props_table.properties();
-
-        for i in 0..count {
-            let prop: &crate::object::Property = &properties[i];
-            if prop.flags().is_enumerable() {
-                // For simplicity, store atom ID as integer
-                let key_id = prop.key().id();
-                keys.push(JSValue::from_int(key_id as i32));
-            }
-        }
+    for key in own_keys(ctx, obj) {
+        keys.push(key_string(ctx, key)?);
     }
-
-    // Create array with keys (simplified version)
     create_array_from_values(ctx, &keys)
 }
 
 /// Object.values() - Returns an array of a given object's own property values
 pub fn object_values(ctx: &mut Context, obj: JSValue) -> Result<JSValue, JSValue> {
-    if !obj.is_object() {
-        return create_empty_array(ctx);
-    }
-
-    let obj_ref = ctx.get_object(obj).ok_or(JSValue::exception())?;
-
-    if !obj_ref.has_properties() {
-        return create_empty_array(ctx);
-    }
-
-    let props_index = obj_ref.props_index();
-    let props_table = ctx.get_property_table(props_index)
-        .ok_or(JSValue::exception())?;
-
-    let mut values = Vec::new();
-    unsafe {
-        let header = props_table.header();
-        let count = header.count() as usize;
-        let properties = props_table.properties();
-
-        for i in 0..count {
-            let prop: &crate::object::Property = &properties[i];
-            if prop.flags().is_enumerable() {
-                values.push(prop.value());
-            }
-        }
-    }
-
+    let values = own_enumerable_entries(ctx, obj)?
+        .into_iter()
+        .map(|(_, value)| value)
+        .collect::<Vec<_>>();
     create_array_from_values(ctx, &values)
 }
 
 /// Object.entries() - Returns an array of [key, value] pairs
 pub fn object_entries(ctx: &mut Context, obj: JSValue) -> Result<JSValue, JSValue> {
-    if !obj.is_object() {
-        return create_empty_array(ctx);
-    }
+    let props = own_enumerable_entries(ctx, obj)?;
 
-    let obj_ref = ctx.get_object(obj).ok_or(JSValue::exception())?;
-
-    if !obj_ref.has_properties() {
-        return create_empty_array(ctx);
-    }
-
-    let props_index = obj_ref.props_index();
-    let props_table = ctx.get_property_table(props_index)
-        .ok_or(JSValue::exception())?;
-
-    let mut entries = Vec::new();
-    unsafe {
-        let header = props_table.header();
-        let count = header.count() as usize;
-        let properties = props_table.properties();
-
-        for i in 0..count {
-            let prop: &crate::object::Property = &properties[i];
-            if prop.flags().is_enumerable() {
-                // Create [key, value] pair (simplified: store as values)
-                entries.push(prop.value());
-            }
-        }
+    // Create a [key, value] pair for each property
+    let mut entries = Vec::with_capacity(props.len());
+    for (key, value) in props {
+        let key = key_string(ctx, key)?;
+        entries.push(create_array_from_values(ctx, &[key, value])?);
     }
 
     create_array_from_values(ctx, &entries)
@@ -147,59 +76,155 @@ pub fn object_entries(ctx: &mut Context, obj: JSValue) -> Result<JSValue, JSValu
 
 /// Object.assign() - Copies properties from source objects to target
 ///
-/// Simplified implementation
+/// Copies each source's own enumerable properties, in Object.keys order and
+/// source order, so later sources win. Values are read as property reads
+/// do, calling getters, and written as assignments do: setters on the
+/// target or its prototypes run, and read-only targets throw a TypeError.
 pub fn object_assign(ctx: &mut Context, target: JSValue, sources: &[JSValue]) -> Result<JSValue, JSValue> {
     if !target.is_object() {
         return Err(JSValue::exception());
     }
 
-    // Copy properties from each source
-    for source in sources.iter() {
-        let source: &JSValue = source;
-        if !source.is_object() {
+    for &source in sources.iter() {
+        copy_own_enumerable(ctx, target, source, CopyMode::Assign)?;
+    }
+
+    Ok(target)
+}
+
+/// How [`copy_own_enumerable`] writes to the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyMode {
+    /// As an assignment, for `Object.assign`: setters run
+    Assign,
+    /// As an object literal's own data property, for `{...source}`
+    Define,
+}
+
+/// Copies `source`'s own enumerable properties onto `target`
+///
+/// Keys come from `Context::own_enumerable_keys`, and each value is read
+/// just before it is written, so a getter that deletes a later key keeps
+/// it from being copied. Sources that aren't objects copy nothing.
+pub fn copy_own_enumerable(ctx: &mut Context, target: JSValue, source: JSValue, mode: CopyMode) -> Result<(), JSValue> {
+    for key in ctx.own_enumerable_keys(source) {
+        let still_own = ctx.get_array_element(source, key.to_index().unwrap_or(u32::MAX)).is_some()
+            || ctx.find_own_property(source, key).is_some_and(|prop| prop.flags().is_enumerable());
+        if !still_own {
             continue;
         }
+        let value = get_value(ctx, source, key)?;
+        match mode {
+            CopyMode::Assign => put_value(ctx, target, key, value)?,
+            CopyMode::Define => ctx.set_property(target, key, value).map_err(|_| JSValue::exception())?,
+        }
+    }
+    Ok(())
+}
 
-        let src_obj = ctx.get_object(*source).ok_or(JSValue::exception())?;
-        if !src_obj.has_properties() {
-            continue;
+/// Reads a property as `obj[key]` does, calling a getter if there is one
+fn get_value(ctx: &mut Context, obj: JSValue, key: JSAtom) -> Result<JSValue, JSValue> {
+    use crate::context::PropertyLookupResult;
+
+    match ctx.find_property_with_accessor(obj, key) {
+        PropertyLookupResult::Value(value) => Ok(value),
+        PropertyLookupResult::Getter(getter) | PropertyLookupResult::GetterSetter(getter, _) => {
+            ctx.call_function(getter, obj, &[])
         }
+        _ => Ok(JSValue::undefined()),
+    }
+}
 
-        let props_index = src_obj.props_index();
-
-        // Extract property data first to avoid borrow conflict
-        let props_to_copy: Vec<(crate::value::JSAtom, JSValue)> = {
-            let props_table = ctx.get_property_table(props_index)
-                .ok_or(JSValue::exception())?;
-
-            unsafe {
-                let header = props_table.header();
-                let count = header.count() as usize;
-                let properties = props_table.properties();
-
-                let mut result = Vec::new();
-                for i in 0..count {
-                    let prop: &crate::object::Property = &properties[i];
-                    if prop.flags().is_enumerable() {
-                        result.push((prop.key(), prop.value()));
-                    }
-                }
-                result
+/// Writes a property as `obj[key] = value` does in strict code
+///
+/// The first property named `key` on `obj` or its prototypes decides: a
+/// setter is called with `obj` as `this`, and a getter-only accessor or a
+/// read-only data property throws a TypeError. Otherwise the value becomes
+/// an own property of `obj`.
+fn put_value(ctx: &mut Context, obj: JSValue, key: JSAtom, value: JSValue) -> Result<(), JSValue> {
+    use crate::builtins::error::ErrorKind;
+
+    let mut current = obj;
+    for depth in 0..=ctx.max_prototype_depth() {
+        if ctx.get_array_element(current, key.to_index().unwrap_or(u32::MAX)).is_some() {
+            break;
+        }
+        if let Some(prop) = ctx.find_own_property(current, key) {
+            let (flags, setter) = (prop.flags(), prop.setter());
+            let name = ctx.atom_name(key).map(|name| name.into_owned()).unwrap_or_default();
+            if flags.has_set() {
+                ctx.call_function(setter, obj, &[value])?;
+                return Ok(());
+            }
+            if flags.has_get() {
+                let msg = alloc::format!("Cannot set property '{}', which has only a getter", name);
+                return Err(ctx.new_error(ErrorKind::Type, &msg));
+            }
+            if !flags.is_writable() {
+                let msg = alloc::format!("Cannot assign to read-only property '{}'", name);
+                return Err(ctx.new_error(ErrorKind::Type, &msg));
+            }
+            break;
+        }
+        match ctx.get_object(current).map(|current| current.prototype()) {
+            Some(proto) if !proto.is_null() => {
+                ctx.follow_prototype_link(depth + 1);
+                current = proto;
             }
-        };
+            _ => break,
+        }
+    }
 
-        // Now apply the properties
-        for (key, value) in props_to_copy {
-            ctx.add_property(target, key, value, PropertyFlags::default())
-                .map_err(|_| JSValue::exception())?;
+    ctx.set_property(obj, key, value).map_err(|_| JSValue::exception())
+}
+
+/// Returns an object's own enumerable properties and their values
+///
+/// In Object.keys order; getters are called for their values.
+fn own_enumerable_entries(ctx: &mut Context, obj: JSValue) -> Result<Vec<(JSAtom, JSValue)>, JSValue> {
+    if let Some(chars) = ctx.get_string(obj).map(|s| s.chars().collect::<Vec<_>>()) {
+        let mut entries = Vec::with_capacity(chars.len());
+        for (key, ch) in own_keys(ctx, obj).into_iter().zip(chars) {
+            let mut buf = [0u8; 4];
+            let value = ctx.new_string(ch.encode_utf8(&mut buf)).map_err(|_| JSValue::exception())?;
+            entries.push((key, value));
         }
+        return Ok(entries);
     }
 
-    Ok(target)
+    let mut entries = Vec::new();
+    for key in ctx.own_enumerable_keys(obj) {
+        let value = get_value(ctx, obj, key)?;
+        entries.push((key, value));
+    }
+    Ok(entries)
+}
+
+/// Own enumerable keys of an object, or of the String object a string
+/// primitive would box to: one index per character
+///
+/// Other primitives box to objects with no own keys.
+fn own_keys(ctx: &Context, obj: JSValue) -> Vec<JSAtom> {
+    match ctx.get_string(obj) {
+        Some(s) => (0..).zip(s.chars()).filter_map(|(index, _)| JSAtom::from_index(index)).collect(),
+        None => ctx.own_enumerable_keys(obj),
+    }
+}
+
+/// Returns a property key as a string value
+fn key_string(ctx: &mut Context, key: JSAtom) -> Result<JSValue, JSValue> {
+    let name = ctx.atom_name(key).map(|name| name.into_owned()).unwrap_or_default();
+    ctx.new_string(&name).map_err(|_| JSValue::exception())
 }
 
 /// Object.create() - Creates a new object with specified prototype
+///
+/// Throws a `RangeError` if the new object's chain would be longer than
+/// `Context::max_prototype_depth`.
 pub fn object_create(ctx: &mut Context, proto: JSValue) -> Result<JSValue, JSValue> {
+    if ctx.prototype_depth_above(proto) > ctx.max_prototype_depth() {
+        return Err(ctx.prototype_depth_error());
+    }
     ctx.new_object_with_proto(proto).map_err(|_| JSValue::exception())
 }
 
@@ -214,6 +239,10 @@ pub fn to_string(ctx: &mut Context, obj: JSValue) -> Result<JSValue, JSValue> {
         "[object Null]"
     } else if obj.is_undefined() {
         "[object Undefined]"
+    } else if ctx.is_array(obj) {
+        "[object Array]"
+    } else if ctx.is_callable(obj) {
+        "[object Function]"
     } else if obj.is_object() {
         "[object Object]"
     } else if obj.is_bool() {
@@ -239,7 +268,31 @@ pub fn get_prototype_of(ctx: &Context, obj: JSValue) -> Result<JSValue, JSValue>
 }
 
 /// Object.setPrototypeOf() - Sets the prototype of an object
+///
+/// `proto` must be an object, a function or null. A prototype that has
+/// `obj` on its own chain throws a TypeError, so lookups can't loop, and
+/// one that would give `obj` a chain longer than
+/// `Context::max_prototype_depth` throws a `RangeError`. Objects that already
+/// inherit from `obj` aren't checked; lookups on them stop at the limit.
 pub fn set_prototype_of(ctx: &mut Context, obj: JSValue, proto: JSValue) -> Result<JSValue, JSValue> {
+    use crate::builtins::error::ErrorKind;
+
+    if !proto.is_null() && ctx.get_object(proto).is_none() && !ctx.is_callable(proto) {
+        return Err(ctx.new_error(ErrorKind::Type, "Object prototype may only be an Object or null"));
+    }
+    let mut current = proto;
+    let mut depth = 0;
+    while let Some(o) = ctx.get_object(current) {
+        if current == obj {
+            return Err(ctx.new_error(ErrorKind::Type, "Cyclic __proto__ value"));
+        }
+        depth += 1;
+        if depth > ctx.max_prototype_depth() {
+            return Err(ctx.prototype_depth_error());
+        }
+        current = o.prototype();
+    }
+
     if let Some(o) = ctx.get_object_mut(obj) {
         o.set_prototype(proto);
         Ok(obj)
@@ -259,22 +312,22 @@ pub fn define_property(
     use crate::object::PropertyFlags;
 
     // Get property name as atom
-    let prop_atom = if let Some(s) = ctx.get_string(prop) {
-        string_to_atom(s)
+    let prop_atom = if let Some(s) = ctx.get_string(prop).map(String::from) {
+        string_to_atom(ctx, &s)
     } else if let Some(n) = prop.to_int() {
-        string_to_atom(&alloc::format!("{}", n))
+        string_to_atom(ctx, &alloc::format!("{}", n))
     } else {
         return Err(JSValue::exception());
     };
 
     // Get value from descriptor
-    let value_atom = string_to_atom("value");
+    let value_atom = atoms::VALUE;
     let value = ctx.get_property(descriptor, value_atom).unwrap_or(JSValue::undefined());
 
     // Get flags from descriptor
-    let writable_atom = string_to_atom("writable");
-    let enumerable_atom = string_to_atom("enumerable");
-    let configurable_atom = string_to_atom("configurable");
+    let writable_atom = atoms::WRITABLE;
+    let enumerable_atom = atoms::ENUMERABLE;
+    let configurable_atom = atoms::CONFIGURABLE;
 
     let writable = ctx.get_property(descriptor, writable_atom)
         .and_then(|v| v.to_bool())
@@ -292,8 +345,8 @@ pub fn define_property(
     flags.set_configurable(configurable);
 
     // Check for getter/setter
-    let get_atom = string_to_atom("get");
-    let set_atom = string_to_atom("set");
+    let get_atom = atoms::GET;
+    let set_atom = atoms::SET;
 
     if let Some(_getter) = ctx.get_property(descriptor, get_atom) {
         // TODO: Implement getter/setter properties
@@ -310,46 +363,22 @@ pub fn define_property(
     Ok(obj)
 }
 
-/// Helper: Create an empty array
-fn create_empty_array(ctx: &mut Context) -> Result<JSValue, JSValue> {
-    create_array_from_values(ctx, &[])
-}
-
 /// Helper: Create an array from values
 fn create_array_from_values(ctx: &mut Context, values: &[JSValue]) -> Result<JSValue, JSValue> {
-    use crate::runtime::init::string_to_atom;
-
-    // Get Array.prototype for proper inheritance
-    let array_atom = string_to_atom("Array");
-    let proto_atom = string_to_atom("prototype");
-    let array_proto = ctx.get_global_property(array_atom)
-        .and_then(|arr_ctor| ctx.get_property(arr_ctor, proto_atom))
-        .unwrap_or(JSValue::null());
+    let arr = ctx.new_array().map_err(|_| JSValue::exception())?;
 
-    // Create a new array object with Array.prototype
-    let arr = ctx.new_object_with_proto(array_proto)
-        .map_err(|_| JSValue::exception())?;
-
-    // Set each element as a numbered property
     for (i, val) in values.iter().enumerate() {
-        let idx_atom = string_to_atom(&alloc::format!("{}", i));
-        ctx.add_property(arr, idx_atom, *val, PropertyFlags::default())
+        ctx.set_array_element(arr, i as u32, *val)
             .map_err(|_| JSValue::exception())?;
     }
 
-    // Set length property
-    let length_atom = string_to_atom("length");
-    let length_val = ctx.new_number(values.len() as f64)
-        .map_err(|_| JSValue::exception())?;
-    ctx.add_property(arr, length_atom, length_val, PropertyFlags::default())
-        .map_err(|_| JSValue::exception())?;
-
     Ok(arr)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::object::PropertyFlags;
 
     #[test]
     fn test_object_constructor() {
@@ -371,6 +400,18 @@ mod tests {
         assert!(keys.is_ptr());
     }
 
+    #[test]
+    fn test_string_keys_are_char_indices() {
+        let mut ctx = Context::new(8192);
+
+        let s = ctx.new_string("h\u{e9}!").unwrap();
+        let keys = own_keys(&ctx, s);
+        assert_eq!(keys, [0, 1, 2].map(|i| JSAtom::from_index(i).unwrap()));
+        let entries = own_enumerable_entries(&mut ctx, s).unwrap();
+        assert_eq!(ctx.get_string(entries[1].1), Some("\u{e9}"));
+        assert!(own_keys(&ctx, JSValue::from_int(5)).is_empty());
+    }
+
     #[test]
     fn test_object_create() {
         let mut ctx = Context::new(4096);
@@ -1,47 +1,71 @@
 //! String built-in constructor and methods
 //!
 //! Implements String(), String.prototype.length, and String.prototype methods:
-//! charAt, charCodeAt, indexOf, lastIndexOf, slice, substring, substr,
-//! toLowerCase, toUpperCase, trim, split, replace, includes, startsWith, endsWith
+//! at, charAt, charCodeAt, indexOf, lastIndexOf, slice, substring, substr,
+//! toLowerCase, toUpperCase, trim, split, replace, includes, startsWith, endsWith,
+//! padStart, padEnd
 
 use crate::context::Context;
-use crate::value::JSValue;
-use alloc::string::{String, ToString};
+use crate::runtime::conversion::to_string;
+use crate::util::{byte_to_char_index, char_to_byte_index, count_utf8_chars};
+use crate::value::{atoms, JSValue};
+use alloc::string::ToString;
+#[cfg(feature = "builtin-string-extras")]
+use alloc::string::String;
+#[cfg(feature = "builtin-string-extras")]
 use alloc::vec;
+#[cfg(feature = "builtin-string-extras")]
 use alloc::vec::Vec;
 
-/// String() constructor
+/// String() called as a function
 ///
-/// Converts a value to a string
+/// Converts a value to a string: objects through their `toString` or
+/// `valueOf`, numbers through [`crate::util::format_number`] like every other
+/// number-to-string conversion, so `String(-0)` is `"0"`.
 pub fn string_constructor(ctx: &mut Context, value: Option<JSValue>) -> Result<JSValue, JSValue> {
-    match value {
-        None => ctx.new_string("").map_err(|_| JSValue::exception()),
-        Some(val) => {
-            // Convert value to string
-            if let Some(s) = ctx.get_string(val) {
-                let owned = s.to_string();
-                ctx.new_string(&owned).map_err(|_| JSValue::exception())
-            } else if let Some(n) = ctx.get_number(val) {
-                let s = alloc::format!("{}", n);
-                ctx.new_string(&s).map_err(|_| JSValue::exception())
-            } else if val.is_null() {
-                ctx.new_string("null").map_err(|_| JSValue::exception())
-            } else if val.is_undefined() {
-                ctx.new_string("undefined").map_err(|_| JSValue::exception())
-            } else if let Some(b) = val.to_bool() {
-                let s = if b { "true" } else { "false" };
-                ctx.new_string(s).map_err(|_| JSValue::exception())
-            } else {
-                ctx.new_string("[object Object]").map_err(|_| JSValue::exception())
-            }
-        }
+    use crate::runtime::conversion::{to_primitive, PreferredType};
+
+    let Some(val) = value else {
+        return ctx.new_string("").map_err(|_| JSValue::exception());
+    };
+    if ctx.get_string(val).is_some() {
+        return Ok(val);
     }
+    let primitive = to_primitive(ctx, val, PreferredType::String)?;
+    let s = to_string(ctx, primitive);
+    ctx.new_string(&s).map_err(|_| JSValue::exception())
+}
+
+/// Returns the byte offset of character `index`, or the length past the end
+fn byte_offset(s: &str, index: usize) -> usize {
+    char_to_byte_index(s.as_bytes(), index)
 }
 
-/// String.prototype.length - Returns the length of a string
+/// Returns the number of characters in `s`
+fn char_len(s: &str) -> usize {
+    count_utf8_chars(s.as_bytes())
+}
+
+/// Resolves a relative index (negative counts back from the end) into [0, len]
+fn relative_index(index: i32, len: usize) -> usize {
+    if index < 0 {
+        len.saturating_sub(index.unsigned_abs() as usize)
+    } else {
+        (index as usize).min(len)
+    }
+}
+
+/// Returns the characters [start, end) of `s`
+fn char_range(s: &str, start: usize, end: usize) -> &str {
+    let start_byte = byte_offset(s, start);
+    let end_byte = start_byte + byte_offset(&s[start_byte..], end.saturating_sub(start));
+    &s[start_byte..end_byte]
+}
+
+/// String.prototype.length - Returns the length of a string in characters
 pub fn string_length(ctx: &Context, str_val: JSValue) -> Result<i32, JSValue> {
     if let Some(s) = ctx.get_string(str_val) {
-        Ok(s.len() as i32)
+        Ok(char_len(s) as i32)
     } else {
         Err(JSValue::exception())
     }
@@ -51,111 +75,121 @@ pub fn string_length(ctx: &Context, str_val: JSValue) -> Result<i32, JSValue> {
 pub fn char_at(ctx: &mut Context, str_val: JSValue, index: i32) -> Result<JSValue, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?;
 
-    if index < 0 || index >= s.len() as i32 {
-        return ctx.new_string("").map_err(|_| JSValue::exception());
-    }
+    let ch = usize::try_from(index).ok().and_then(|i| s.chars().nth(i));
+    let mut buf = [0u8; 4];
+    let ch_str = match ch {
+        Some(ch) => &*ch.encode_utf8(&mut buf),
+        None => "",
+    };
+
+    ctx.new_string(ch_str).map_err(|_| JSValue::exception())
+}
+
+/// String.prototype.at() - Returns the character at an index
+///
+/// Negative indices count back from the end; out-of-range ones give undefined.
+#[cfg(feature = "builtin-string-extras")]
+pub fn at(ctx: &mut Context, str_val: JSValue, index: i32) -> Result<JSValue, JSValue> {
+    let s = ctx.get_string(str_val).ok_or(JSValue::exception())?;
+    let len = char_len(s);
+
+    let index = if index < 0 {
+        len.checked_sub(index.unsigned_abs() as usize)
+    } else {
+        Some(index as usize)
+    };
+    let Some(ch) = index.and_then(|i| s.chars().nth(i)) else {
+        return Ok(JSValue::undefined());
+    };
 
-    let ch = s.chars().nth(index as usize).unwrap_or('\0');
     let mut buf = [0u8; 4];
     let ch_str = ch.encode_utf8(&mut buf);
-
     ctx.new_string(ch_str).map_err(|_| JSValue::exception())
 }
 
 /// String.prototype.charCodeAt() - Returns character code at specified index
+///
+/// Returns -1 when the index is out of range (NaN in JS).
 pub fn char_code_at(ctx: &Context, str_val: JSValue, index: i32) -> Result<i32, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?;
 
-    if index < 0 || index >= s.len() as i32 {
-        return Ok(-1); // Return NaN in real implementation
-    }
-
-    let ch = s.chars().nth(index as usize).unwrap_or('\0');
-    Ok(ch as i32)
+    let ch = usize::try_from(index).ok().and_then(|i| s.chars().nth(i));
+    Ok(ch.map_or(-1, |ch| ch as i32))
 }
 
-/// String.prototype.indexOf() - Returns first index of substring
+/// String.prototype.indexOf() - Returns first character index of substring
 pub fn index_of(ctx: &Context, str_val: JSValue, search: JSValue, from_index: Option<i32>) -> Result<i32, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?;
-    let search_str = ctx.get_string(search).ok_or(JSValue::exception())?;
+    let search_str = to_string(ctx, search);
 
-    let start = from_index.unwrap_or(0).max(0) as usize;
-
-    if start >= s.len() {
-        return Ok(-1);
-    }
+    let start = byte_offset(s, from_index.unwrap_or(0).max(0) as usize);
 
-    match s[start..].find(search_str) {
-        Some(pos) => Ok((start + pos) as i32),
+    match s[start..].find(search_str.as_str()) {
+        Some(pos) => Ok(byte_to_char_index(s.as_bytes(), start + pos) as i32),
         None => Ok(-1),
     }
 }
 
-/// String.prototype.lastIndexOf() - Returns last index of substring
+/// String.prototype.lastIndexOf() - Returns last character index of substring
+///
+/// Only matches starting at or before `from_index` are considered.
+#[cfg(feature = "builtin-string-extras")]
 pub fn last_index_of(ctx: &Context, str_val: JSValue, search: JSValue, from_index: Option<i32>) -> Result<i32, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?;
-    let search_str = ctx.get_string(search).ok_or(JSValue::exception())?;
+    let search_str = to_string(ctx, search);
 
-    let end = from_index.map(|i| (i as usize).min(s.len())).unwrap_or(s.len());
+    let starts: Vec<usize> = s.char_indices()
+        .map(|(i, _)| i)
+        .chain(core::iter::once(s.len()))
+        .collect();
+    let from = from_index.map_or(starts.len() - 1, |i| relative_index(i.max(0), starts.len() - 1));
 
-    match s[..end].rfind(search_str) {
-        Some(pos) => Ok(pos as i32),
-        None => Ok(-1),
-    }
+    let found = (0..=from).rev().find(|&i| s[starts[i]..].starts_with(search_str.as_str()));
+    Ok(found.map_or(-1, |i| i as i32))
 }
 
 /// String.prototype.slice() - Extracts a section of a string
+///
+/// Negative indices count back from the end.
 pub fn slice(ctx: &mut Context, str_val: JSValue, start: i32, end: Option<i32>) -> Result<JSValue, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?.to_string();
-    let len = s.len() as i32;
+    let len = char_len(&s);
 
-    let start_idx = if start < 0 { (len + start).max(0) } else { start.min(len) } as usize;
-    let end_idx = if let Some(e) = end {
-        if e < 0 { (len + e).max(0) } else { e.min(len) }
-    } else {
-        len
-    } as usize;
-
-    if start_idx >= end_idx {
-        return ctx.new_string("").map_err(|_| JSValue::exception());
-    }
+    let start_idx = relative_index(start, len);
+    let end_idx = end.map_or(len, |e| relative_index(e, len));
 
-    let result = &s[start_idx..end_idx];
+    let result = char_range(&s, start_idx, end_idx);
     ctx.new_string(result).map_err(|_| JSValue::exception())
 }
 
 /// String.prototype.substring() - Returns substring between two indices
+///
+/// Negative indices clamp to 0 and the indices are swapped if reversed.
 pub fn substring(ctx: &mut Context, str_val: JSValue, start: i32, end: Option<i32>) -> Result<JSValue, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?.to_string();
-    let len = s.len() as i32;
+    let len = char_len(&s);
 
-    let start_idx = start.max(0).min(len) as usize;
-    let end_idx = end.unwrap_or(len).max(0).min(len) as usize;
+    let start_idx = relative_index(start.max(0), len);
+    let end_idx = end.map_or(len, |e| relative_index(e.max(0), len));
 
-    let (start_idx, end_idx) = if start_idx > end_idx {
-        (end_idx, start_idx)
-    } else {
-        (start_idx, end_idx)
-    };
-
-    let result = &s[start_idx..end_idx];
+    let result = char_range(&s, start_idx.min(end_idx), start_idx.max(end_idx));
     ctx.new_string(result).map_err(|_| JSValue::exception())
 }
 
 /// String.prototype.substr() - Returns substring starting at index with length
 pub fn substr(ctx: &mut Context, str_val: JSValue, start: i32, length: Option<i32>) -> Result<JSValue, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?.to_string();
-    let len = s.len() as i32;
+    let len = char_len(&s);
 
-    let start_idx = if start < 0 { (len + start).max(0) } else { start.min(len) } as usize;
-    let length = length.unwrap_or(len).max(0) as usize;
-    let end_idx = (start_idx + length).min(s.len());
+    let start_idx = relative_index(start, len);
+    let length = length.map_or(len, |l| l.max(0) as usize);
 
-    let result = &s[start_idx..end_idx];
+    let result = char_range(&s, start_idx, start_idx.saturating_add(length));
     ctx.new_string(result).map_err(|_| JSValue::exception())
 }
 
 /// String.prototype.toLowerCase() - Converts string to lowercase
+#[cfg(feature = "builtin-string-extras")]
 pub fn to_lower_case(ctx: &mut Context, str_val: JSValue) -> Result<JSValue, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?.to_string();
     let lower = s.to_lowercase();
@@ -163,6 +197,7 @@ pub fn to_lower_case(ctx: &mut Context, str_val: JSValue) -> Result<JSValue, JSV
 }
 
 /// String.prototype.toUpperCase() - Converts string to uppercase
+#[cfg(feature = "builtin-string-extras")]
 pub fn to_upper_case(ctx: &mut Context, str_val: JSValue) -> Result<JSValue, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?.to_string();
     let upper = s.to_uppercase();
@@ -170,6 +205,7 @@ pub fn to_upper_case(ctx: &mut Context, str_val: JSValue) -> Result<JSValue, JSV
 }
 
 /// String.prototype.trim() - Removes whitespace from both ends
+#[cfg(feature = "builtin-string-extras")]
 pub fn trim(ctx: &mut Context, str_val: JSValue) -> Result<JSValue, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?.to_string();
     let trimmed = s.trim();
@@ -178,66 +214,41 @@ pub fn trim(ctx: &mut Context, str_val: JSValue) -> Result<JSValue, JSValue> {
 
 /// String.prototype.split() - Splits string into array
 ///
-/// Simplified implementation
+/// An empty separator splits into characters; a missing one yields the
+/// whole string. `limit` is taken as unsigned, so negative limits don't cap.
+#[cfg(feature = "builtin-string-extras")]
 pub fn split(ctx: &mut Context, str_val: JSValue, separator: Option<JSValue>, limit: Option<i32>) -> Result<JSValue, JSValue> {
-    use crate::runtime::init::string_to_atom;
-    use crate::object::PropertyFlags;
-
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?.to_string();
 
-    let parts: Vec<String> = if let Some(sep) = separator {
-        if let Some(sep_str) = ctx.get_string(sep) {
-            if sep_str.is_empty() {
-                // Split into individual characters
-                s.chars().map(|c| c.to_string()).collect()
-            } else {
-                s.split(sep_str).map(|p: &str| p.to_string()).collect()
-            }
-        } else {
-            vec![s.clone()]
-        }
-    } else {
-        vec![s.clone()]
+    let separator = separator
+        .filter(|sep| !sep.is_undefined())
+        .map(|sep| to_string(ctx, sep));
+
+    let parts: Vec<String> = match separator {
+        Some(sep) if sep.is_empty() => s.chars().map(|c| c.to_string()).collect(),
+        Some(sep) => s.split(sep.as_str()).map(|p: &str| p.to_string()).collect(),
+        None => vec![s.clone()],
     };
 
-    let limit = limit.unwrap_or(i32::MAX) as usize;
+    let limit = limit.map_or(usize::MAX, |l| l as u32 as usize);
     let parts: Vec<String> = parts.into_iter().take(limit).collect();
 
     // Create a proper JS array object with Array.prototype
-    let result = ctx.new_object().map_err(|_| JSValue::exception())?;
-
-    // Set Array.prototype
-    let array_atom = string_to_atom("Array");
-    let proto_atom = string_to_atom("prototype");
-    if let Some(array_ctor) = ctx.get_global_property(array_atom) {
-        if let Some(array_proto) = ctx.get_property(array_ctor, proto_atom) {
-            if let Some(obj) = ctx.get_object_mut(result) {
-                obj.set_prototype(array_proto);
-            }
-        }
-    }
+    let result = ctx.new_array().map_err(|_| JSValue::exception())?;
 
-    // Add each part as a numbered property
     for (i, part) in parts.iter().enumerate() {
         let part_val = ctx.new_string(part).map_err(|_| JSValue::exception())?;
-        let idx_str = alloc::format!("{}", i);
-        let idx_atom = string_to_atom(&idx_str);
-        ctx.add_property(result, idx_atom, part_val, PropertyFlags::default())
+        ctx.set_array_element(result, i as u32, part_val)
             .map_err(|_| JSValue::exception())?;
     }
 
-    // Set length
-    let length_atom = string_to_atom("length");
-    let length_val = JSValue::from_int(parts.len() as i32);
-    ctx.add_property(result, length_atom, length_val, PropertyFlags::default())
-        .map_err(|_| JSValue::exception())?;
-
     Ok(result)
 }
 
 /// String.prototype.replace() - Replaces first occurrence
 ///
 /// Simplified implementation
+#[cfg(feature = "builtin-string-extras")]
 pub fn replace(ctx: &mut Context, str_val: JSValue, search: JSValue, replace_val: JSValue) -> Result<JSValue, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?;
     let search_str = ctx.get_string(search).ok_or(JSValue::exception())?;
@@ -248,44 +259,68 @@ pub fn replace(ctx: &mut Context, str_val: JSValue, search: JSValue, replace_val
 }
 
 /// String.prototype.includes() - Checks if string contains substring
+#[cfg(feature = "builtin-string-extras")]
 pub fn includes(ctx: &Context, str_val: JSValue, search: JSValue, position: Option<i32>) -> Result<bool, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?;
     let search_str = ctx.get_string(search).ok_or(JSValue::exception())?;
 
-    let start = position.unwrap_or(0).max(0) as usize;
-
-    if start >= s.len() {
-        return Ok(false);
-    }
+    let start = byte_offset(s, position.unwrap_or(0).max(0) as usize);
 
     Ok(s[start..].contains(search_str))
 }
 
 /// String.prototype.startsWith() - Checks if string starts with substring
+#[cfg(feature = "builtin-string-extras")]
 pub fn starts_with(ctx: &Context, str_val: JSValue, search: JSValue, position: Option<i32>) -> Result<bool, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?;
     let search_str = ctx.get_string(search).ok_or(JSValue::exception())?;
 
-    let start = position.unwrap_or(0).max(0) as usize;
-
-    if start >= s.len() {
-        return Ok(false);
-    }
+    let start = byte_offset(s, position.unwrap_or(0).max(0) as usize);
 
     Ok(s[start..].starts_with(search_str))
 }
 
 /// String.prototype.endsWith() - Checks if string ends with substring
+#[cfg(feature = "builtin-string-extras")]
 pub fn ends_with(ctx: &Context, str_val: JSValue, search: JSValue, length: Option<i32>) -> Result<bool, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?;
     let search_str = ctx.get_string(search).ok_or(JSValue::exception())?;
 
-    let end = length.map(|l| (l as usize).min(s.len())).unwrap_or(s.len());
+    let end = length.map_or(s.len(), |l| byte_offset(s, l.max(0) as usize));
 
     Ok(s[..end].ends_with(search_str))
 }
 
+/// String.prototype.padStart() / padEnd() - Pads a string to `max_length` characters
+///
+/// `fill` (a space when None) repeats on the start or end, with its last copy
+/// cut short. A `max_length` larger than the heap's free space throws a
+/// RangeError up front rather than building a string that can't fit.
+#[cfg(feature = "builtin-string-extras")]
+pub fn pad(ctx: &mut Context, str_val: JSValue, max_length: u64, fill: Option<JSValue>, at_start: bool) -> Result<JSValue, JSValue> {
+    let s = ctx.get_string(str_val).ok_or(JSValue::exception())?;
+    let len = char_len(s) as u64;
+    let fill = fill.map_or_else(|| " ".to_string(), |fill| to_string(ctx, fill));
+
+    if max_length <= len || fill.is_empty() {
+        return Ok(str_val);
+    }
+    if max_length > ctx.free_memory() as u64 {
+        return Err(ctx.new_error(crate::builtins::error::ErrorKind::Range, "Invalid string length"));
+    }
+
+    let padding: String = fill.chars().cycle().take((max_length - len) as usize).collect();
+    let s = ctx.get_string(str_val).ok_or(JSValue::exception())?;
+    let result = if at_start {
+        alloc::format!("{}{}", padding, s)
+    } else {
+        alloc::format!("{}{}", s, padding)
+    };
+    ctx.new_string(&result).map_err(|_| JSValue::exception())
+}
+
 /// String.prototype.trimStart() - Removes whitespace from beginning
+#[cfg(feature = "builtin-string-extras")]
 pub fn trim_start(ctx: &mut Context, str_val: JSValue) -> Result<JSValue, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?.to_string();
     let trimmed = s.trim_start();
@@ -293,6 +328,7 @@ pub fn trim_start(ctx: &mut Context, str_val: JSValue) -> Result<JSValue, JSValu
 }
 
 /// String.prototype.trimEnd() - Removes whitespace from end
+#[cfg(feature = "builtin-string-extras")]
 pub fn trim_end(ctx: &mut Context, str_val: JSValue) -> Result<JSValue, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?.to_string();
     let trimmed = s.trim_end();
@@ -300,6 +336,7 @@ pub fn trim_end(ctx: &mut Context, str_val: JSValue) -> Result<JSValue, JSValue>
 }
 
 /// String.prototype.replaceAll() - Replaces all occurrences
+#[cfg(feature = "builtin-string-extras")]
 pub fn replace_all(ctx: &mut Context, str_val: JSValue, search: JSValue, replace_val: JSValue) -> Result<JSValue, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?;
     let search_str = ctx.get_string(search).ok_or(JSValue::exception())?;
@@ -310,6 +347,7 @@ pub fn replace_all(ctx: &mut Context, str_val: JSValue, search: JSValue, replace
 }
 
 /// String.prototype.concat() - Concatenates strings
+#[cfg(feature = "builtin-string-extras")]
 pub fn concat(ctx: &mut Context, str_val: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
     let mut result = ctx.get_string(str_val).ok_or(JSValue::exception())?.to_string();
 
@@ -333,6 +371,7 @@ pub fn concat(ctx: &mut Context, str_val: JSValue, args: &[JSValue]) -> Result<J
 }
 
 /// String.prototype.codePointAt() - Returns code point at position
+#[cfg(feature = "builtin-string-extras")]
 pub fn code_point_at(ctx: &Context, str_val: JSValue, index: i32) -> Result<JSValue, JSValue> {
     let s = ctx.get_string(str_val).ok_or(JSValue::exception())?;
 
@@ -351,6 +390,7 @@ pub fn code_point_at(ctx: &Context, str_val: JSValue, index: i32) -> Result<JSVa
 }
 
 /// String.fromCharCode() - Creates string from char codes
+#[cfg(feature = "builtin-string-extras")]
 pub fn from_char_code(ctx: &mut Context, codes: &[JSValue]) -> Result<JSValue, JSValue> {
     let mut result = String::new();
 
@@ -374,6 +414,7 @@ pub fn from_char_code(ctx: &mut Context, codes: &[JSValue]) -> Result<JSValue, J
 }
 
 /// String.fromCodePoint() - Creates string from code points
+#[cfg(feature = "builtin-string-extras")]
 pub fn from_code_point(ctx: &mut Context, codes: &[JSValue]) -> Result<JSValue, JSValue> {
     let mut result = String::new();
 
@@ -409,6 +450,10 @@ mod tests {
 
         let s = string_constructor(&mut ctx, None).unwrap();
         assert_eq!(ctx.get_string(s).unwrap(), "");
+
+        let negative_zero = ctx.new_number(-0.0).unwrap();
+        let s = string_constructor(&mut ctx, Some(negative_zero)).unwrap();
+        assert_eq!(ctx.get_string(s).unwrap(), "0");
     }
 
     #[test]
@@ -446,6 +491,23 @@ mod tests {
         assert_eq!(ctx.get_string(result).unwrap(), "ell");
     }
 
+    #[test]
+    fn test_slice_multibyte() {
+        let mut ctx = Context::new(4096);
+
+        let s = ctx.new_string("éa€").unwrap();
+        let result = slice(&mut ctx, s, 1, None).unwrap();
+        assert_eq!(ctx.get_string(result).unwrap(), "a€");
+
+        let result = slice(&mut ctx, s, -1, None).unwrap();
+        assert_eq!(ctx.get_string(result).unwrap(), "€");
+
+        let result = substring(&mut ctx, s, 2, Some(0)).unwrap();
+        assert_eq!(ctx.get_string(result).unwrap(), "éa");
+        assert_eq!(string_length(&ctx, s).unwrap(), 3);
+    }
+
+    #[cfg(feature = "builtin-string-extras")]
     #[test]
     fn test_to_lower_case() {
         let mut ctx = Context::new(4096);
@@ -455,6 +517,7 @@ mod tests {
         assert_eq!(ctx.get_string(result).unwrap(), "hello");
     }
 
+    #[cfg(feature = "builtin-string-extras")]
     #[test]
     fn test_to_upper_case() {
         let mut ctx = Context::new(4096);
@@ -464,6 +527,7 @@ mod tests {
         assert_eq!(ctx.get_string(result).unwrap(), "HELLO");
     }
 
+    #[cfg(feature = "builtin-string-extras")]
     #[test]
     fn test_trim() {
         let mut ctx = Context::new(4096);
@@ -473,6 +537,7 @@ mod tests {
         assert_eq!(ctx.get_string(result).unwrap(), "hello");
     }
 
+    #[cfg(feature = "builtin-string-extras")]
     #[test]
     fn test_includes() {
         let mut ctx = Context::new(4096);
@@ -485,6 +550,7 @@ mod tests {
         assert!(!includes(&ctx, s, search, None).unwrap());
     }
 
+    #[cfg(feature = "builtin-string-extras")]
     #[test]
     fn test_starts_with() {
         let mut ctx = Context::new(4096);
@@ -494,6 +560,7 @@ mod tests {
         assert!(starts_with(&ctx, s, search, None).unwrap());
     }
 
+    #[cfg(feature = "builtin-string-extras")]
     #[test]
     fn test_ends_with() {
         let mut ctx = Context::new(4096);
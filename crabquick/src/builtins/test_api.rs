@@ -0,0 +1,97 @@
+//! Self-test globals: `test`, `assertEquals` and `assertThrows`
+//!
+//! For scripts that check a device at boot. `test(name, fn)` runs `fn` at
+//! once and records whether it threw; the host reads the records back with
+//! `Engine::test_results` instead of parsing console output. Compiled in by
+//! the `test-api` feature and installed by `Engine::enable_test_api`.
+
+use crate::builtins::error::{create_error, ErrorKind, ErrorType};
+use crate::context::Context;
+use crate::runtime::compare::same_value;
+use crate::runtime::display::{format_quoted, format_value};
+use crate::value::{atoms, JSValue};
+use alloc::format;
+use alloc::string::String;
+
+/// Outcome of one `test(name, fn)` call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestResult {
+    /// Name passed to `test`
+    pub name: String,
+    /// True if the function returned without throwing
+    pub passed: bool,
+    /// What the function threw, as `Name: message` for errors
+    pub message: Option<String>,
+}
+
+/// Errors a test must not swallow: the script or the host asked to stop,
+/// the script ran out of steps, or the heap is gone
+fn is_fatal(ctx: &Context, thrown: JSValue) -> bool {
+    matches!(
+        ctx.error_kind(thrown),
+        Some(ErrorKind::ExitRequested | ErrorKind::Timeout | ErrorKind::Interrupted | ErrorKind::OutOfMemory)
+    )
+}
+
+/// test(name, fn) - runs `fn` and records whether it threw
+///
+/// Returns true if it passed. Exit requests, timeouts, interrupts and
+/// running out of memory still end the script.
+pub fn test_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let name = args.first().map_or_else(String::new, |&name| format_value(ctx, name));
+    let func = args.get(1).copied().unwrap_or(JSValue::undefined());
+
+    let message = match ctx.call_function(func, JSValue::undefined(), &[]) {
+        Ok(_) => None,
+        Err(thrown) if is_fatal(ctx, thrown) => return Err(thrown),
+        Err(thrown) => Some(format_value(ctx, thrown)),
+    };
+    let passed = message.is_none();
+    ctx.record_test_result(TestResult { name, passed, message });
+    Ok(JSValue::bool(passed))
+}
+
+/// assertEquals(actual, expected, message?) - throws unless the two are
+/// the same value
+///
+/// Compares with SameValue, so `NaN` equals `NaN` but `0` and `-0` differ.
+/// The error shows both values, with strings quoted so `1` and `'1'` tell
+/// apart.
+pub fn assert_equals_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let actual = args.first().copied().unwrap_or(JSValue::undefined());
+    let expected = args.get(1).copied().unwrap_or(JSValue::undefined());
+    if same_value(ctx, actual, expected) {
+        return Ok(JSValue::undefined());
+    }
+
+    let mut text = format!("expected {}, got {}", format_quoted(ctx, expected), format_quoted(ctx, actual));
+    if let Some(&message) = args.get(2).filter(|message| !message.is_undefined()) {
+        text = format!("{}: {}", format_value(ctx, message), text);
+    }
+    Err(create_error(ctx, ErrorType::Error, Some(&text))?)
+}
+
+/// assertThrows(fn, kind?) - throws unless `fn` throws
+///
+/// `kind` is an error name such as `'TypeError'`, which the thrown value's
+/// `name` must match. Returns what `fn` threw.
+pub fn assert_throws_native(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let func = args.first().copied().unwrap_or(JSValue::undefined());
+    let thrown = match ctx.call_function(func, JSValue::undefined(), &[]) {
+        Ok(_) => return Err(create_error(ctx, ErrorType::Error, Some("expected the function to throw"))?),
+        Err(thrown) if is_fatal(ctx, thrown) => return Err(thrown),
+        Err(thrown) => thrown,
+    };
+
+    let Some(&kind) = args.get(1).filter(|kind| !kind.is_undefined()) else {
+        return Ok(thrown);
+    };
+    let wanted = format_value(ctx, kind);
+    let name = ctx.get_property(thrown, atoms::NAME).and_then(|name| ctx.get_string(name).map(String::from));
+    if name.as_deref() == Some(wanted.as_str()) {
+        return Ok(thrown);
+    }
+
+    let text = format!("expected {} to be thrown, got {}", wanted, format_quoted(ctx, thrown));
+    Err(create_error(ctx, ErrorType::Error, Some(&text))?)
+}
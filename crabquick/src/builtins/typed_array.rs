@@ -1,2 +1,114 @@
 //! Typed array built-in functions
-//! TODO: Implement Int8Array, Uint8Array, Float32Array, etc.
+//!
+//! Only `Uint8Array` so far, for binary I/O such as sensor buffers. The
+//! bytes live in a `JSByteArray` held as the object's class data, which
+//! `Context` reads and writes for indexing and `length`; hosts pass buffers
+//! in and read results out with `Context::new_uint8array_from_slice` and
+//! `Context::uint8array_data`. Stored values wrap modulo 256, as `ToUint8`
+//! does, so `a[0] = 257` stores 1. There is no `ArrayBuffer`, so `subarray`
+//! returns a copy rather than a view.
+
+use crate::builtins::error::{create_error, ErrorType};
+use crate::context::Context;
+use crate::runtime::conversion::{to_integer_or_infinity, to_length};
+use crate::value::{atoms, JSAtom, JSValue};
+use alloc::format;
+use alloc::vec::Vec;
+
+/// new Uint8Array(length | array | uint8array)
+///
+/// A number gives that many zero bytes; an array, array-like or other
+/// `Uint8Array` is copied element by element.
+pub fn uint8array_constructor(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let arg = args.first().copied().unwrap_or(JSValue::undefined());
+    if ctx.get_object(arg).is_some() {
+        let length = source_length(ctx, arg);
+        let arr = new_uint8array(ctx, length as f64)?;
+        copy_elements(ctx, arg, arr, 0, length)?;
+        return Ok(arr);
+    }
+    let length = to_integer_or_infinity(ctx, arg);
+    new_uint8array(ctx, length)
+}
+
+/// Uint8Array.prototype.set(source, offset?) - copies `source` in at `offset`
+///
+/// Throws a `RangeError` if it doesn't fit.
+pub fn uint8array_set_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let len = this_length(ctx, this, "set")?;
+    let source = args.first().copied().unwrap_or(JSValue::undefined());
+    let offset = args.get(1).map_or(0.0, |&offset| to_integer_or_infinity(ctx, offset));
+
+    let count = source_length(ctx, source);
+    if offset < 0.0 || offset + count as f64 > len as f64 {
+        return Err(create_error(ctx, ErrorType::RangeError, Some("offset is out of bounds"))?);
+    }
+    copy_elements(ctx, source, this, offset as u32, count)?;
+    Ok(JSValue::undefined())
+}
+
+/// Uint8Array.prototype.subarray(begin?, end?) - copies the bytes from
+/// `begin` up to `end`
+///
+/// Negative indices count from the end, as with `slice`.
+pub fn uint8array_subarray_native(ctx: &mut Context, this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+    let len = this_length(ctx, this, "subarray")?;
+    let begin = relative_index(ctx, args.first().copied(), len, 0);
+    let end = relative_index(ctx, args.get(1).copied(), len, len).max(begin);
+
+    let bytes: Vec<u8> = ctx.uint8array_data(this).map(|bytes| bytes[begin..end].to_vec()).unwrap_or_default();
+    ctx.new_uint8array_from_slice(&bytes).map_err(|_| JSValue::exception())
+}
+
+/// Creates a zeroed `Uint8Array`, throwing a `RangeError` for a negative or
+/// too large length
+fn new_uint8array(ctx: &mut Context, length: f64) -> Result<JSValue, JSValue> {
+    if !(0.0..=f64::from(u32::MAX)).contains(&length) {
+        let message = format!("Invalid typed array length: {}", crate::util::format_number(length));
+        return Err(create_error(ctx, ErrorType::RangeError, Some(&message))?);
+    }
+    ctx.new_uint8array(length as usize).map_err(|_| JSValue::exception())
+}
+
+/// Returns the length of `this`, throwing a `TypeError` if it isn't a
+/// `Uint8Array`
+fn this_length(ctx: &mut Context, this: JSValue, method: &str) -> Result<usize, JSValue> {
+    if let Some(bytes) = ctx.uint8array_data(this) {
+        return Ok(bytes.len());
+    }
+    let message = format!("Uint8Array.prototype.{method} called on a non-Uint8Array");
+    Err(create_error(ctx, ErrorType::TypeError, Some(&message))?)
+}
+
+/// Resolves a `slice`-style index argument against `len`, with `default`
+/// for a missing or undefined one
+fn relative_index(ctx: &Context, arg: Option<JSValue>, len: usize, default: usize) -> usize {
+    let Some(arg) = arg.filter(|arg| !arg.is_undefined()) else {
+        return default;
+    };
+    let relative = to_integer_or_infinity(ctx, arg);
+    let index = if relative < 0.0 { len as f64 + relative } else { relative };
+    index.clamp(0.0, len as f64) as usize
+}
+
+/// Returns the `length` of an array-like source, capped to what a typed
+/// array can hold
+fn source_length(ctx: &Context, source: JSValue) -> usize {
+    let length = ctx.get_property(source, atoms::LENGTH).map_or(0, |length| to_length(ctx, length));
+    length.min(u64::from(u32::MAX)) as usize
+}
+
+/// Copies `count` elements of an array-like `source` into a `Uint8Array`
+/// starting at `offset`
+///
+/// Reading and storing bytes doesn't allocate, so copying between typed
+/// arrays can't be disturbed by a collection.
+fn copy_elements(ctx: &mut Context, source: JSValue, arr: JSValue, offset: u32, count: usize) -> Result<(), JSValue> {
+    for index in 0..count as u32 {
+        let key = JSAtom::from_index(index).ok_or_else(JSValue::exception)?;
+        let target = JSAtom::from_index(offset + index).ok_or_else(JSValue::exception)?;
+        let value = ctx.get_property(source, key).unwrap_or(JSValue::undefined());
+        ctx.set_property(arr, target, value).map_err(|_| JSValue::exception())?;
+    }
+    Ok(())
+}
@@ -148,6 +148,57 @@ impl Instruction {
     }
 }
 
+/// Why an instruction couldn't be decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The byte at `pc` is not an opcode
+    InvalidOpcode {
+        /// Offset of the byte
+        pc: usize,
+        /// The byte itself
+        byte: u8,
+    },
+    /// The instruction at `pc` runs past the end of the code
+    Truncated {
+        /// Offset of the instruction's opcode
+        pc: usize,
+    },
+}
+
+impl DecodeError {
+    /// Offset of the instruction that failed to decode
+    pub fn pc(&self) -> usize {
+        match *self {
+            DecodeError::InvalidOpcode { pc, .. } | DecodeError::Truncated { pc } => pc,
+        }
+    }
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DecodeError::InvalidOpcode { pc, byte } => write!(f, "Invalid opcode 0x{:02x} at offset {}", byte, pc),
+            DecodeError::Truncated { pc } => write!(f, "Truncated instruction at offset {}", pc),
+        }
+    }
+}
+
+/// A jump or program counter outside the code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PcError {
+    /// Where the program counter would have gone; negative for jumps back
+    /// past the start
+    pub target: i64,
+    /// Length of the code
+    pub len: usize,
+}
+
+impl core::fmt::Display for PcError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Jump to offset {} outside code of {} bytes", self.target, self.len)
+    }
+}
+
 /// Bytecode reader for decoding instructions
 pub struct BytecodeReader<'a> {
     bytecode: &'a [u8],
@@ -165,9 +216,47 @@ impl<'a> BytecodeReader<'a> {
         self.pc
     }
 
+    /// Returns the bytes being read
+    pub fn bytecode(&self) -> &'a [u8] {
+        self.bytecode
+    }
+
+    /// Identifies the code being read by its address and length
+    ///
+    /// Readers over the same function body compare equal; readers over
+    /// different functions never do, since nested function bodies are
+    /// separate byte ranges.
+    pub fn code_id(&self) -> (usize, usize) {
+        (self.bytecode.as_ptr() as usize, self.bytecode.len())
+    }
+
     /// Sets the program counter
-    pub fn set_pc(&mut self, pc: usize) {
+    ///
+    /// `pc` may be the code's length, which ends it cleanly, but not past it.
+    pub fn set_pc(&mut self, pc: usize) -> Result<(), PcError> {
+        if pc > self.bytecode.len() {
+            return Err(PcError { target: pc as i64, len: self.bytecode.len() });
+        }
         self.pc = pc;
+        Ok(())
+    }
+
+    /// Returns the offset `offset` bytes from the program counter, as jump
+    /// operands count it
+    pub fn relative_pc(&self, offset: i32) -> Result<usize, PcError> {
+        let target = self.pc as i64 + i64::from(offset);
+        match usize::try_from(target) {
+            Ok(pc) if pc <= self.bytecode.len() => Ok(pc),
+            _ => Err(PcError { target, len: self.bytecode.len() }),
+        }
+    }
+
+    /// Moves the program counter `offset` bytes, as a jump does
+    ///
+    /// Leaves it where it was if the target is outside the code.
+    pub fn jump_relative(&mut self, offset: i32) -> Result<(), PcError> {
+        self.pc = self.relative_pc(offset)?;
+        Ok(())
     }
 
     /// Returns true if there are more bytes to read
@@ -232,10 +321,37 @@ impl<'a> BytecodeReader<'a> {
     }
 
     /// Decodes the next instruction
+    ///
+    /// Returns `None` at the end of the code and for code that doesn't
+    /// decode; use [`try_decode`](Self::try_decode) to tell them apart.
     pub fn decode(&mut self) -> Option<Instruction> {
-        let opcode_byte = self.read_u8()?;
-        let opcode = Opcode::from_u8(opcode_byte)?;
+        self.try_decode().ok().flatten()
+    }
 
+    /// Decodes the next instruction, or returns `Ok(None)` at the end of
+    /// the code
+    ///
+    /// On error the program counter stays at the bad instruction.
+    pub fn try_decode(&mut self) -> Result<Option<Instruction>, DecodeError> {
+        let pc = self.pc;
+        let Some(byte) = self.read_u8() else {
+            return Ok(None);
+        };
+        let Some(opcode) = Opcode::from_u8(byte) else {
+            self.pc = pc;
+            return Err(DecodeError::InvalidOpcode { pc, byte });
+        };
+        match self.decode_operand(opcode) {
+            Some(operand) => Ok(Some(Instruction { opcode, operand })),
+            None => {
+                self.pc = pc;
+                Err(DecodeError::Truncated { pc })
+            }
+        }
+    }
+
+    /// Reads the operand of `opcode`
+    fn decode_operand(&mut self, opcode: Opcode) -> Option<Operand> {
         let operand = match opcode.format() {
             InstructionFormat::None => Operand::None,
             InstructionFormat::U8 => Operand::U8(self.read_u8()?),
@@ -251,7 +367,7 @@ impl<'a> BytecodeReader<'a> {
             InstructionFormat::Atom16 => Operand::Atom16(self.read_u16()?),
         };
 
-        Some(Instruction { opcode, operand })
+        Some(operand)
     }
 
     /// Peeks at the next instruction without advancing the PC
@@ -261,6 +377,44 @@ impl<'a> BytecodeReader<'a> {
         self.pc = saved_pc;
         instruction
     }
+
+    /// Iterates over the rest of the code as `(pc, instruction)` pairs
+    ///
+    /// The capture bytes that follow `FClosure` are skipped. Iteration ends
+    /// after the first error.
+    pub fn instructions(self) -> Instructions<'a> {
+        Instructions { reader: self, failed: false }
+    }
+}
+
+/// Iterator over decoded instructions; see [`BytecodeReader::instructions`]
+pub struct Instructions<'a> {
+    reader: BytecodeReader<'a>,
+    failed: bool,
+}
+
+impl Iterator for Instructions<'_> {
+    type Item = Result<(usize, Instruction), DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let pc = self.reader.pc();
+        let result = self.reader.try_decode().and_then(|instruction| {
+            let Some(instruction) = instruction else {
+                return Ok(None);
+            };
+            if instruction.opcode == Opcode::FClosure {
+                let captured = self.reader.read_u8().ok_or(DecodeError::Truncated { pc })?;
+                let end = self.reader.relative_pc(i32::from(captured)).map_err(|_| DecodeError::Truncated { pc })?;
+                self.reader.pc = end;
+            }
+            Ok(Some((pc, instruction)))
+        });
+        self.failed = result.is_err();
+        result.transpose()
+    }
 }
 
 /// Bytecode writer for encoding instructions
@@ -537,9 +691,68 @@ mod tests {
         let bytecode = vec![0, 90, 163]; // drop, add, return
         let mut reader = BytecodeReader::new(&bytecode);
 
-        reader.set_pc(2);
+        reader.set_pc(2).unwrap();
         let inst = reader.decode().unwrap();
         assert_eq!(inst.opcode, Opcode::Return);
+
+        // The end is a valid target; past it isn't
+        reader.set_pc(3).unwrap();
+        assert_eq!(reader.try_decode(), Ok(None));
+        assert_eq!(reader.set_pc(4), Err(PcError { target: 4, len: 3 }));
+        assert_eq!(reader.pc(), 3);
+    }
+
+    #[test]
+    fn test_reader_jump_relative() {
+        let bytecode = vec![0, 90, 163]; // drop, add, return
+        let mut reader = BytecodeReader::new(&bytecode);
+
+        reader.set_pc(2).unwrap();
+        reader.jump_relative(-2).unwrap();
+        assert_eq!(reader.pc(), 0);
+
+        // Jumps before the start used to wrap to a huge pc
+        assert_eq!(reader.jump_relative(-1), Err(PcError { target: -1, len: 3 }));
+        assert_eq!(reader.jump_relative(i32::MIN), Err(PcError { target: i64::from(i32::MIN), len: 3 }));
+        assert_eq!(reader.jump_relative(4), Err(PcError { target: 4, len: 3 }));
+        assert_eq!(reader.pc(), 0);
+
+        reader.jump_relative(3).unwrap();
+        assert!(!reader.has_more());
+    }
+
+    #[test]
+    fn test_reader_truncated_instruction() {
+        let bytecode = vec![0, 160, 0x10, 0x00]; // drop, if_false missing two bytes
+        let mut reader = BytecodeReader::new(&bytecode);
+
+        assert_eq!(reader.try_decode().unwrap().unwrap().opcode, Opcode::Drop);
+        assert_eq!(reader.try_decode(), Err(DecodeError::Truncated { pc: 1 }));
+        assert_eq!(reader.pc(), 1);
+
+        let mut reader = BytecodeReader::new(&[37]);
+        assert_eq!(reader.try_decode(), Err(DecodeError::InvalidOpcode { pc: 0, byte: 37 }));
+    }
+
+    #[test]
+    fn test_reader_instructions() {
+        let mut writer = BytecodeWriter::new();
+        writer.emit(&Instruction::with_i8(Opcode::PushI8, 1));
+        writer.emit(&Instruction { opcode: Opcode::FClosure, operand: Operand::Const8(0) });
+        writer.emit_u8(2);
+        writer.emit_u8(0);
+        writer.emit_u8(1);
+        writer.emit(&Instruction::new(Opcode::Return));
+        let bytecode = writer.finish();
+
+        let pairs: Vec<_> = BytecodeReader::new(&bytecode).instructions().map(|item| item.unwrap()).collect();
+        let opcodes: Vec<_> = pairs.iter().map(|(pc, inst)| (*pc, inst.opcode)).collect();
+        assert_eq!(opcodes, [(0, Opcode::PushI8), (2, Opcode::FClosure), (7, Opcode::Return)]);
+
+        // Iteration stops at the first error
+        let items: Vec<_> = BytecodeReader::new(&[0, 160, 0]).instructions().collect();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1], Err(DecodeError::Truncated { pc: 1 }));
     }
 
     #[test]
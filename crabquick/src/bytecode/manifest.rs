@@ -0,0 +1,302 @@
+//! Script manifests
+//!
+//! A ScriptManifest describes what a compiled blob contains without running
+//! it: one entry per function, the top-level script first, with its counts,
+//! sizes, atoms and the globals its instructions touch.
+//!
+//! Every function in a blob has the same layout as the blob itself:
+//! [const_count: u16][constants...][atom_count: u16][atoms...]
+//! [func_count: u16][funcs...][lines_len: u16][line table]
+//! [hint_count: u16][hints...][code], where
+//! each nested function's bytes are again such a unit.
+
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+use crate::compiler::codegen::FUNC_FLAG_ARROW;
+use super::format::{BytecodeReader, Instruction, Operand};
+use super::opcode::Opcode;
+
+/// Why a bytecode blob couldn't be scanned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestError {
+    /// The blob ends in the middle of a header
+    Truncated,
+    /// An atom name isn't valid UTF-8
+    InvalidAtom,
+    /// A function's code holds an instruction that doesn't decode
+    InvalidInstruction {
+        /// Index of the function in the manifest
+        function: usize,
+        /// Offset of the instruction in the function's code
+        offset: usize,
+    },
+}
+
+/// Constant pool composition
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConstantStats {
+    /// Float constants
+    pub numbers: usize,
+    /// Other values (integers, booleans, ...)
+    pub values: usize,
+}
+
+impl ConstantStats {
+    /// Returns the number of constants
+    pub fn total(&self) -> usize {
+        self.numbers + self.values
+    }
+}
+
+/// One compiled function
+#[derive(Debug, Clone)]
+pub struct FunctionManifest {
+    /// Index of the enclosing function, None for the top-level script
+    pub parent: Option<usize>,
+    /// Declared name, None for anonymous functions and the top-level script
+    pub name: Option<String>,
+    /// Number of declared parameters
+    pub param_count: u8,
+    /// Number of local slots, parameters included
+    pub local_count: u8,
+    /// True for arrow functions
+    pub is_arrow: bool,
+    /// Size of the function's own code, headers and nested functions excluded
+    pub code_size: usize,
+    /// Number of instructions in the function's own code
+    pub instruction_count: usize,
+    /// The function's constant pool
+    pub constants: ConstantStats,
+    /// The function's atom table, in index order
+    pub atoms: Vec<String>,
+    /// Globals read or written by the function's own code, sorted
+    pub globals: Vec<String>,
+}
+
+/// What a compiled script contains
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let script = LoadedScript::compile("console.log(Math.max(1, 2))")?;
+/// let manifest = script.manifest()?;
+/// assert_eq!(manifest.referenced_globals(), ["Math", "console"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ScriptManifest {
+    functions: Vec<FunctionManifest>,
+    bytecode_size: usize,
+}
+
+impl ScriptManifest {
+    /// Scans a compiled blob
+    pub fn scan(bytecode: &[u8]) -> Result<Self, ManifestError> {
+        let mut functions = Vec::new();
+
+        // (unit bytes, parent, name, param_count, local_count, flags),
+        // visited in pre-order so children follow their parent
+        let mut pending = alloc::vec![(bytecode, None, None, 0u8, 0u8, 0u8)];
+        while let Some((unit, parent, name, param_count, local_count, flags)) = pending.pop() {
+            let index = functions.len();
+            let mut cursor = Cursor { bytes: unit, pos: 0 };
+
+            let mut constants = ConstantStats::default();
+            for _ in 0..cursor.u16()? {
+                if cursor.u8()? == 0 {
+                    constants.numbers += 1;
+                } else {
+                    constants.values += 1;
+                }
+                cursor.take(core::mem::size_of::<usize>())?;
+            }
+
+            let mut atoms = Vec::new();
+            for _ in 0..cursor.u16()? {
+                let len = cursor.u16()? as usize;
+                let name = core::str::from_utf8(cursor.take(len)?)
+                    .map_err(|_| ManifestError::InvalidAtom)?;
+                atoms.push(String::from(name));
+            }
+
+            let mut children = Vec::new();
+            for _ in 0..cursor.u16()? {
+                let param_count = cursor.u8()?;
+                let local_count = cursor.u8()?;
+                let _self_name_slot = cursor.u8()?;
+                let flags = cursor.u8()?;
+                let name = match cursor.u16()? {
+                    0xFFFF => None,
+                    atom => Some(atoms.get(atom as usize).ok_or(ManifestError::InvalidAtom)?.clone()),
+                };
+                let len = cursor.u32()? as usize;
+                children.push((cursor.take(len)?, Some(index), name, param_count, local_count, flags));
+            }
+            pending.extend(children.into_iter().rev());
+
+            let lines_len = cursor.u16()? as usize;
+            cursor.take(lines_len)?;
+            let hint_count = cursor.u16()? as usize;
+            cursor.take(hint_count * 4)?;
+
+            let code = &unit[cursor.pos..];
+            let mut globals = BTreeSet::new();
+            let invalid = |offset| ManifestError::InvalidInstruction { function: index, offset };
+            let instruction_count = scan_instructions(code, |offset, instruction| {
+                if let Some(atom) = global_atom(instruction) {
+                    let name = atoms.get(atom).ok_or(offset)?;
+                    globals.insert(name.clone());
+                }
+                Ok(())
+            }).map_err(invalid)?;
+
+            functions.push(FunctionManifest {
+                parent,
+                name,
+                param_count,
+                local_count,
+                is_arrow: flags & FUNC_FLAG_ARROW != 0,
+                code_size: code.len(),
+                instruction_count,
+                constants,
+                atoms,
+                globals: globals.into_iter().collect(),
+            });
+        }
+
+        Ok(ScriptManifest { functions, bytecode_size: bytecode.len() })
+    }
+
+    /// Returns every function, the top-level script first
+    pub fn functions(&self) -> &[FunctionManifest] {
+        &self.functions
+    }
+
+    /// Returns the number of functions, the top-level script included
+    pub fn function_count(&self) -> usize {
+        self.functions.len()
+    }
+
+    /// Returns the size of the whole blob
+    pub fn bytecode_size(&self) -> usize {
+        self.bytecode_size
+    }
+
+    /// Returns the constant pools of all functions combined
+    pub fn constants(&self) -> ConstantStats {
+        self.functions.iter().fold(ConstantStats::default(), |total, func| ConstantStats {
+            numbers: total.numbers + func.constants.numbers,
+            values: total.values + func.constants.values,
+        })
+    }
+
+    /// Returns the globals any function reads or writes, sorted
+    ///
+    /// This is the set of host APIs (and script-level globals) the script
+    /// can reach by name.
+    pub fn referenced_globals(&self) -> Vec<String> {
+        let globals: BTreeSet<&String> = self.functions.iter()
+            .flat_map(|func| func.globals.iter())
+            .collect();
+        globals.into_iter().cloned().collect()
+    }
+}
+
+/// Walks the instructions of one function's code
+///
+/// Calls `visit` with each instruction and its offset, skipping the capture
+/// bytes that follow `FClosure`. Returns the number of instructions, or the
+/// offset of the first one that doesn't decode or that `visit` rejects.
+pub(crate) fn scan_instructions(
+    code: &[u8],
+    mut visit: impl FnMut(usize, &Instruction) -> Result<(), usize>,
+) -> Result<usize, usize> {
+    let mut count = 0;
+    for item in BytecodeReader::new(code).instructions() {
+        let (offset, instruction) = item.map_err(|err| err.pc())?;
+        visit(offset, &instruction)?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Returns the atom index named by a global variable instruction
+fn global_atom(instruction: &Instruction) -> Option<usize> {
+    match (instruction.opcode, instruction.operand) {
+        (Opcode::GetGlobal8 | Opcode::PutGlobal8 | Opcode::SetGlobal8, Operand::Atom8(idx)) => Some(idx as usize),
+        (Opcode::GetGlobal16 | Opcode::PutGlobal16 | Opcode::SetGlobal16 | Opcode::GetGlobalUndef, Operand::Atom16(idx)) => Some(idx as usize),
+        _ => None,
+    }
+}
+
+/// Bounds-checked reads over a function's headers
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ManifestError> {
+        let end = self.pos.checked_add(len).ok_or(ManifestError::Truncated)?;
+        let bytes = self.bytes.get(self.pos..end).ok_or(ManifestError::Truncated)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, ManifestError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ManifestError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, ManifestError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler;
+
+    #[test]
+    fn test_manifest_functions() {
+        let source = "
+            function add(a, b) { return a + b; }
+            var twice = (x) => add(x, x);
+            console.log(Math.max(twice(2), 3.5));
+        ";
+        let manifest = ScriptManifest::scan(&compiler::compile(source).unwrap()).unwrap();
+
+        assert_eq!(manifest.function_count(), 3);
+        let functions = manifest.functions();
+        assert_eq!(functions[0].parent, None);
+        assert!(functions[1..].iter().all(|f| f.parent == Some(0)));
+        assert!(functions.iter().any(|f| f.param_count == 2 && !f.is_arrow && f.name.as_deref() == Some("add")));
+        assert!(functions.iter().any(|f| f.param_count == 1 && f.is_arrow && f.name.is_none()));
+        assert_eq!(manifest.constants().numbers, 1);
+
+        let globals = manifest.referenced_globals();
+        assert!(globals.iter().any(|g| g == "Math"));
+        assert!(globals.iter().any(|g| g == "console"));
+        assert!(!globals.iter().any(|g| g == "JSON"));
+    }
+
+    #[test]
+    fn test_manifest_rejects_malformed() {
+        let bytecode = compiler::compile("var x = 1; x + 2").unwrap();
+        assert_eq!(ScriptManifest::scan(&bytecode[..1]).unwrap_err(), ManifestError::Truncated);
+
+        let mut bad = bytecode.clone();
+        *bad.last_mut().unwrap() = 0xEF;
+        assert!(matches!(
+            ScriptManifest::scan(&bad),
+            Err(ManifestError::InvalidInstruction { function: 0, .. })
+        ));
+    }
+}
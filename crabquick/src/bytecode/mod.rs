@@ -7,9 +7,13 @@ pub mod opcode;
 pub mod format;
 pub mod constants;
 pub mod function;
+pub mod script;
+pub mod manifest;
 
 // Re-exports
 pub use opcode::{Opcode, InstructionFormat};
-pub use format::{Instruction, Operand, BytecodeReader, BytecodeWriter};
+pub use format::{Instruction, Operand, BytecodeReader, BytecodeWriter, DecodeError, Instructions, PcError};
 pub use constants::ConstantPool;
 pub use function::JSFunctionBytecode;
+pub use script::{LoadedScript, ScriptSource};
+pub use manifest::{ScriptManifest, FunctionManifest, ConstantStats, ManifestError};
@@ -157,6 +157,8 @@ pub enum Opcode {
     SetGlobal8 = 65,
     /// Set global variable (returns value, 16-bit atom index)
     SetGlobal16 = 66,
+    /// Get global variable, pushing undefined if it doesn't exist (for `typeof`)
+    GetGlobalUndef = 67,
 
     // ===== Property Access =====
     /// Get object field
@@ -331,9 +333,9 @@ pub enum Opcode {
     ForInStart = 208,
     /// for-in next
     ForInNext = 209,
-    /// for-of start
+    /// for-of start: pops the iterable and starts iterating it
     ForOfStart = 210,
-    /// for-of next
+    /// for-of next: pushes the next value, then whether the loop is done
     ForOfNext = 211,
     /// typeof operator
     TypeOf = 212,
@@ -371,6 +373,10 @@ pub enum Opcode {
     SetName = 228,
     /// Set prototype
     SetProto = 229,
+    /// for-in end: discards the innermost for-in iterator
+    ForInEnd = 230,
+    /// for-of end: discards the innermost for-of iterator
+    ForOfEnd = 231,
 
     // ===== Closure Operations =====
     /// Create closure
@@ -507,6 +513,7 @@ impl Opcode {
             Opcode::PutGlobal16 => "put_global16",
             Opcode::SetGlobal8 => "set_global8",
             Opcode::SetGlobal16 => "set_global16",
+            Opcode::GetGlobalUndef => "get_global_undef",
 
             // Property access
             Opcode::GetField => "get_field",
@@ -602,8 +609,10 @@ impl Opcode {
             Opcode::IteratorCheckObject => "iterator_check_object",
             Opcode::ForInStart => "for_in_start",
             Opcode::ForInNext => "for_in_next",
+            Opcode::ForInEnd => "for_in_end",
             Opcode::ForOfStart => "for_of_start",
             Opcode::ForOfNext => "for_of_next",
+            Opcode::ForOfEnd => "for_of_end",
             Opcode::TypeOf => "typeof",
             Opcode::Delete => "delete",
             Opcode::DeleteVar => "delete_var",
@@ -676,8 +685,8 @@ impl Opcode {
             Opcode::GetIterator | Opcode::GetAsyncIterator | Opcode::IteratorNext |
             Opcode::IteratorClose | Opcode::IteratorCheckObject |
             Opcode::TypeOf | Opcode::Delete | Opcode::DeleteVar | Opcode::Void |
-            Opcode::ForInStart | Opcode::ForInNext |
-            Opcode::ForOfStart | Opcode::ForOfNext |
+            Opcode::ForInStart | Opcode::ForInNext | Opcode::ForInEnd |
+            Opcode::ForOfStart | Opcode::ForOfNext | Opcode::ForOfEnd |
             Opcode::Nop => None,
 
             // U8 operands
@@ -695,7 +704,7 @@ impl Opcode {
 
             // Atom16 operands (for global variable names and string literals)
             Opcode::GetGlobal16 | Opcode::PutGlobal16 | Opcode::SetGlobal16 |
-            Opcode::PushAtomString16 => Atom16,
+            Opcode::GetGlobalUndef | Opcode::PushAtomString16 => Atom16,
 
             // I8 operands
             Opcode::PushI8 => I8,
@@ -760,9 +769,9 @@ impl Opcode {
         // SAFETY: We validate that the u8 value corresponds to a valid opcode
         // The repr(u8) ensures this is a valid representation
         match val {
-            0..=10 | 11..=36 | 40..=66 | 70..=85 | 90..=101 |
+            0..=10 | 11..=36 | 40..=67 | 70..=85 | 90..=101 |
             110..=119 | 130..=133 | 140..=146 | 160..=170 |
-            180..=188 | 200..=229 | 240..=245 | 250..=255 => unsafe {
+            180..=188 | 200..=231 | 240..=245 | 248 | 250..=255 => unsafe {
                 Some(core::mem::transmute(val))
             },
             _ => None,
@@ -819,6 +828,9 @@ mod tests {
         // Valid opcode values (new global opcodes)
         assert_eq!(Opcode::from_u8(61), Some(Opcode::GetGlobal8));
         assert_eq!(Opcode::from_u8(66), Some(Opcode::SetGlobal16));
+        assert_eq!(Opcode::from_u8(67), Some(Opcode::GetGlobalUndef));
+        assert_eq!(Opcode::from_u8(230), Some(Opcode::ForInEnd));
+        assert_eq!(Opcode::from_u8(231), Some(Opcode::ForOfEnd));
 
         // PushFunc8 and PushFunc are now valid opcodes
         assert_eq!(Opcode::from_u8(33), Some(Opcode::PushFunc8));
@@ -828,9 +840,13 @@ mod tests {
         assert_eq!(Opcode::from_u8(35), Some(Opcode::PushAtomString8));
         assert_eq!(Opcode::from_u8(36), Some(Opcode::PushAtomString16));
 
+        // ClearCatchOffset sits alone between the var ref and throw opcodes
+        assert_eq!(Opcode::from_u8(248), Some(Opcode::ClearCatchOffset));
+        assert_eq!(Opcode::from_u8(249), None);
+
         // Invalid opcode values should return None (gaps in opcode numbering)
         assert_eq!(Opcode::from_u8(37), None);
-        assert_eq!(Opcode::from_u8(67), None);
+        assert_eq!(Opcode::from_u8(68), None);
     }
 
     #[test]
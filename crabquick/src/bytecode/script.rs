@@ -0,0 +1,131 @@
+//! Loaded scripts
+//!
+//! A LoadedScript is a compiled bytecode blob ready to run on an Engine. The
+//! bytes are either owned, in which case running the script copies its code
+//! into the engine's heap, or borrowed, in which case the VM reads the code
+//! in place. Borrowed `'static` scripts (ROM images, `include_bytes!`
+//! blobs) can be shared by any number of engines on any number of threads,
+//! each paying only for its own runtime state.
+
+use alloc::borrow::ToOwned;
+use alloc::vec::Vec;
+use crate::compiler::{self, CompileError};
+use super::manifest::{ManifestError, ScriptManifest};
+
+/// Where a script's bytecode comes from
+#[derive(Debug, Clone)]
+pub enum ScriptSource<'a> {
+    /// Bytes the script doesn't own; `'static` ones run without being copied
+    Borrowed(&'a [u8]),
+    /// Bytes owned by the script, copied into the heap when run
+    Owned(Vec<u8>),
+}
+
+/// Compiled bytecode ready to run
+///
+/// Only `LoadedScript<'static>` can be run: closures created by a borrowed
+/// script point into its bytes and may outlive the call that created them.
+/// Use `into_owned` to run a script borrowed for a shorter lifetime.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// static SCRIPT: &[u8] = include_bytes!("app.cqb");
+///
+/// let script = LoadedScript::borrowed(SCRIPT);
+/// let mut engine = Engine::new(65536);
+/// engine.run(&script)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct LoadedScript<'a> {
+    source: ScriptSource<'a>,
+}
+
+impl<'a> LoadedScript<'a> {
+    /// Wraps bytecode that outlives the script, to be run in place
+    pub fn borrowed(bytecode: &'a [u8]) -> Self {
+        LoadedScript { source: ScriptSource::Borrowed(bytecode) }
+    }
+
+    /// Wraps bytecode owned by the script
+    pub fn owned(bytecode: Vec<u8>) -> LoadedScript<'static> {
+        LoadedScript { source: ScriptSource::Owned(bytecode) }
+    }
+
+    /// Compiles JavaScript source into an owned script
+    pub fn compile(source: &str) -> Result<LoadedScript<'static>, CompileError> {
+        compiler::compile(source).map(LoadedScript::owned)
+    }
+
+    /// Returns where the bytecode comes from
+    pub fn source(&self) -> &ScriptSource<'a> {
+        &self.source
+    }
+
+    /// Returns the raw bytecode
+    pub fn bytecode(&self) -> &[u8] {
+        match &self.source {
+            ScriptSource::Borrowed(bytes) => bytes,
+            ScriptSource::Owned(bytes) => bytes,
+        }
+    }
+
+    /// Returns true if the bytecode is borrowed
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.source, ScriptSource::Borrowed(_))
+    }
+
+    /// Returns a 64-bit FNV-1a hash of the bytecode
+    ///
+    /// Compiling is deterministic, so a changed fingerprint means changed
+    /// source, compiler version or features. It spots changes cheaply, for
+    /// deciding whether a device needs an update, but doesn't authenticate
+    /// an image.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hash = crate::util::Fnv64::new();
+        hash.write(self.bytecode());
+        hash.finish()
+    }
+
+    /// Describes the script's functions without running it
+    pub fn manifest(&self) -> Result<ScriptManifest, ManifestError> {
+        ScriptManifest::scan(self.bytecode())
+    }
+
+    /// Copies borrowed bytecode so the script no longer depends on it
+    pub fn into_owned(self) -> LoadedScript<'static> {
+        match self.source {
+            ScriptSource::Borrowed(bytes) => LoadedScript::owned(bytes.to_owned()),
+            ScriptSource::Owned(bytes) => LoadedScript::owned(bytes),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loaded_script_sources() {
+        let script = LoadedScript::compile("1 + 2").unwrap();
+        assert!(!script.is_borrowed());
+
+        let bytes = script.bytecode().to_vec();
+        let borrowed = LoadedScript::borrowed(&bytes);
+        assert!(borrowed.is_borrowed());
+        assert_eq!(borrowed.bytecode(), script.bytecode());
+
+        let owned = borrowed.into_owned();
+        assert!(!owned.is_borrowed());
+        assert_eq!(owned.bytecode(), script.bytecode());
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let script = LoadedScript::compile("var a = 1; a + 2").unwrap();
+        let again = LoadedScript::compile("var a = 1; a + 2").unwrap();
+        assert_eq!(script.fingerprint(), again.fingerprint());
+        assert_eq!(LoadedScript::borrowed(script.bytecode()).fingerprint(), script.fingerprint());
+        assert_ne!(LoadedScript::compile("var a = 1; a + 3").unwrap().fingerprint(), script.fingerprint());
+    }
+}
@@ -1,17 +1,26 @@
 //! Abstract Syntax Tree (AST) node types
 //!
 //! Represents the structure of JavaScript programs after parsing.
+//!
+//! The AST is public so tools (linters, formatters, bundlers) can build on
+//! the parser. Every node carries a [`Span`] covering its full source text,
+//! and [`visit`](super::visit) provides traversals. The enums here are
+//! `#[non_exhaustive]`: new node kinds and operators can arrive in minor
+//! releases, so matches outside this crate need a wildcard arm. Fields may
+//! also be added to struct variants and node structs, so destructure them
+//! with `..`.
 
 use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::vec::Vec;
-use super::lexer::SourceLocation;
+use super::lexer::{SourceLocation, Span};
 
 /// AST node ID for tracking
 pub type NodeId = u32;
 
 /// Binary operator
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum BinaryOp {
     // Arithmetic
     Add,
@@ -51,6 +60,7 @@ pub enum BinaryOp {
 
 /// Unary operator
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum UnaryOp {
     Plus,
     Minus,
@@ -63,6 +73,7 @@ pub enum UnaryOp {
 
 /// Update operator (++ or --)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum UpdateOp {
     Inc,
     Dec,
@@ -70,6 +81,7 @@ pub enum UpdateOp {
 
 /// Assignment operator
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum AssignOp {
     Assign,
     AddAssign,
@@ -77,32 +89,37 @@ pub enum AssignOp {
     MulAssign,
     DivAssign,
     ModAssign,
+    PowAssign,
     LeftShiftAssign,
     RightShiftAssign,
     UnsignedRightShiftAssign,
     BitAndAssign,
     BitOrAssign,
     BitXorAssign,
+    LogicalAndAssign,
+    LogicalOrAssign,
+    NullishAssign,
 }
 
 /// Expression node
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum Expr {
     /// Literal value
-    Literal(Literal, SourceLocation),
+    Literal(Literal, Span),
 
     /// Identifier reference
-    Identifier(String, SourceLocation),
+    Identifier(String, Span),
 
     /// this
-    This(SourceLocation),
+    This(Span),
 
     /// Binary operation
     Binary {
         op: BinaryOp,
         left: Box<Expr>,
         right: Box<Expr>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Unary operation
@@ -110,7 +127,7 @@ pub enum Expr {
         op: UnaryOp,
         arg: Box<Expr>,
         prefix: bool,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Update operation (++/--)
@@ -118,7 +135,7 @@ pub enum Expr {
         op: UpdateOp,
         arg: Box<Expr>,
         prefix: bool,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Assignment
@@ -126,7 +143,7 @@ pub enum Expr {
         op: AssignOp,
         left: Box<Expr>,
         right: Box<Expr>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Conditional (ternary) operator
@@ -134,21 +151,22 @@ pub enum Expr {
         test: Box<Expr>,
         consequent: Box<Expr>,
         alternate: Box<Expr>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Function call
     Call {
         callee: Box<Expr>,
         args: Vec<Expr>,
-        loc: SourceLocation,
+        optional: bool, // true for fn?.()
+        loc: Span,
     },
 
     /// new expression
     New {
         callee: Box<Expr>,
         args: Vec<Expr>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Member expression (obj.prop or obj[prop])
@@ -156,25 +174,41 @@ pub enum Expr {
         object: Box<Expr>,
         property: Box<Expr>,
         computed: bool, // true for [], false for .
-        loc: SourceLocation,
+        optional: bool, // true for ?. and ?.[]
+        loc: Span,
+    },
+
+    /// Optional chain (a?.b.c), evaluating to undefined when any `?.` link
+    /// finds null or undefined
+    OptionalChain {
+        expr: Box<Expr>,
+        loc: Span,
+    },
+
+    /// Template literal: `quasis[0]${exprs[0]}quasis[1]...`, with one
+    /// more text chunk than substitutions
+    Template {
+        quasis: Vec<String>,
+        exprs: Vec<Expr>,
+        loc: Span,
     },
 
     /// Sequence expression (comma operator)
     Sequence {
         exprs: Vec<Expr>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Array literal
     Array {
         elements: Vec<Option<Expr>>, // None for holes
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Object literal
     Object {
         properties: Vec<Property>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Function expression
@@ -182,20 +216,25 @@ pub enum Expr {
         name: Option<String>,
         params: Vec<String>,
         body: Vec<Stmt>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Arrow function
     Arrow {
         params: Vec<String>,
         body: ArrowBody,
-        loc: SourceLocation,
+        loc: Span,
     },
 }
 
 impl Expr {
-    /// Returns the source location of this expression
+    /// Returns the start of this expression
     pub fn location(&self) -> SourceLocation {
+        self.span().start
+    }
+
+    /// Returns the source range of this expression
+    pub fn span(&self) -> Span {
         match self {
             Expr::Literal(_, loc) |
             Expr::Identifier(_, loc) |
@@ -208,6 +247,8 @@ impl Expr {
             Expr::Call { loc, .. } |
             Expr::New { loc, .. } |
             Expr::Member { loc, .. } |
+            Expr::OptionalChain { loc, .. } |
+            Expr::Template { loc, .. } |
             Expr::Sequence { loc, .. } |
             Expr::Array { loc, .. } |
             Expr::Object { loc, .. } |
@@ -219,6 +260,7 @@ impl Expr {
 
 /// Literal value
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum Literal {
     Number(f64),
     String(String),
@@ -233,10 +275,13 @@ pub struct Property {
     pub key: PropertyKey,
     pub value: Expr,
     pub kind: PropertyKind,
+    /// Source range, from the key (or `get`/`set`) to the end of the value
+    pub loc: Span,
 }
 
 /// Property key
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum PropertyKey {
     Identifier(String),
     Literal(Literal),
@@ -245,14 +290,17 @@ pub enum PropertyKey {
 
 /// Property kind
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum PropertyKind {
     Init,   // Regular property
     Get,    // Getter
     Set,    // Setter
+    Spread, // `...value`; the key is an empty identifier
 }
 
 /// Arrow function body
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum ArrowBody {
     Expr(Box<Expr>),
     Block(Vec<Stmt>),
@@ -260,24 +308,25 @@ pub enum ArrowBody {
 
 /// Statement node
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum Stmt {
     /// Expression statement
     Expression {
         expr: Expr,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Block statement
     Block {
         stmts: Vec<Stmt>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Variable declaration
     VarDecl {
         kind: VarKind,
         declarations: Vec<VarDeclarator>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Function declaration
@@ -285,7 +334,7 @@ pub enum Stmt {
         name: String,
         params: Vec<String>,
         body: Vec<Stmt>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// If statement
@@ -293,21 +342,21 @@ pub enum Stmt {
         test: Expr,
         consequent: Box<Stmt>,
         alternate: Option<Box<Stmt>>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// While loop
     While {
         test: Expr,
         body: Box<Stmt>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Do-while loop
     DoWhile {
         body: Box<Stmt>,
         test: Expr,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// For loop
@@ -316,7 +365,7 @@ pub enum Stmt {
         test: Option<Expr>,
         update: Option<Expr>,
         body: Box<Stmt>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// For-in loop (iterate over object keys)
@@ -324,7 +373,7 @@ pub enum Stmt {
         left: ForInit,
         right: Expr,
         body: Box<Stmt>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// For-of loop (iterate over iterable values)
@@ -332,31 +381,31 @@ pub enum Stmt {
         left: ForInit,
         right: Expr,
         body: Box<Stmt>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Break statement
     Break {
         label: Option<String>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Continue statement
     Continue {
         label: Option<String>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Return statement
     Return {
         argument: Option<Expr>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Throw statement
     Throw {
         argument: Expr,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Try statement
@@ -364,32 +413,37 @@ pub enum Stmt {
         block: Vec<Stmt>,
         handler: Option<CatchClause>,
         finalizer: Option<Vec<Stmt>>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Switch statement
     Switch {
         discriminant: Expr,
         cases: Vec<SwitchCase>,
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Empty statement
     Empty {
-        loc: SourceLocation,
+        loc: Span,
     },
 
     /// Labeled statement
     Labeled {
         label: String,
         body: Box<Stmt>,
-        loc: SourceLocation,
+        loc: Span,
     },
 }
 
 impl Stmt {
-    /// Returns the source location of this statement
+    /// Returns the start of this statement
     pub fn location(&self) -> SourceLocation {
+        self.span().start
+    }
+
+    /// Returns the source range of this statement
+    pub fn span(&self) -> Span {
         match self {
             Stmt::Expression { loc, .. } |
             Stmt::Block { loc, .. } |
@@ -415,6 +469,7 @@ impl Stmt {
 
 /// Variable declaration kind
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum VarKind {
     Var,
     Let,
@@ -424,25 +479,69 @@ pub enum VarKind {
 /// Variable declarator
 #[derive(Debug, Clone, PartialEq)]
 pub struct VarDeclarator {
-    pub name: String,
+    pub target: Pattern,
     pub init: Option<Expr>,
+    /// Source range, from the target to the end of the initializer
+    pub loc: Span,
+}
+
+/// Binding target of a declaration
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Pattern {
+    /// Plain name
+    Identifier(String, Span),
+    /// `[a, , [b, c]]`, elements read by index; holes are None
+    Array(Vec<Option<Pattern>>, Span),
+}
+
+impl Pattern {
+    /// Returns the name bound by a plain identifier target
+    pub fn as_identifier(&self) -> Option<&str> {
+        match self {
+            Pattern::Identifier(name, _) => Some(name),
+            Pattern::Array(..) => None,
+        }
+    }
+
+    /// Returns the source range of this pattern
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Identifier(_, loc) | Pattern::Array(_, loc) => *loc,
+        }
+    }
 }
 
 /// For loop initialization
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum ForInit {
     VarDecl {
         kind: VarKind,
         declarations: Vec<VarDeclarator>,
+        /// Source range, from `var`/`let`/`const` to the last declarator
+        loc: Span,
     },
     Expr(Expr),
 }
 
+impl ForInit {
+    /// Returns the source range of this initializer
+    pub fn span(&self) -> Span {
+        match self {
+            ForInit::VarDecl { loc, .. } => *loc,
+            ForInit::Expr(expr) => expr.span(),
+        }
+    }
+}
+
 /// Catch clause
 #[derive(Debug, Clone, PartialEq)]
 pub struct CatchClause {
     pub param: Option<String>,
     pub body: Vec<Stmt>,
+    /// Source range, from `catch` to the closing brace
+    pub loc: Span,
 }
 
 /// Switch case
@@ -450,6 +549,8 @@ pub struct CatchClause {
 pub struct SwitchCase {
     pub test: Option<Expr>, // None for default case
     pub consequent: Vec<Stmt>,
+    /// Source range, from `case`/`default` to the last statement
+    pub loc: Span,
 }
 
 /// Program (top-level)
@@ -461,6 +562,7 @@ pub struct Program {
 
 /// Source type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum SourceType {
     Script,
     Module,
@@ -10,8 +10,10 @@ use alloc::format;
 
 use crate::bytecode::{BytecodeWriter, Instruction, Opcode, ConstantPool};
 use crate::value::JSValue;
+use crate::util::suggest::closest_name;
 use super::ast::*;
 use super::lexer::SourceLocation;
+use super::debug::DebugInfo;
 
 /// Code generation error
 #[derive(Debug, Clone, PartialEq)]
@@ -33,10 +35,20 @@ impl CodeGenError {
 /// Code generation result
 pub type CodeGenResult<T> = Result<T, CodeGenError>;
 
+/// Function table flag marking an arrow function
+pub const FUNC_FLAG_ARROW: u8 = 1 << 0;
+
 /// Label for forward jumps
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct LabelId(usize);
 
+/// Returns the operand of a jump whose operand is at `operand_offset`
+///
+/// Jump offsets are relative to the end of the instruction.
+fn jump_offset(operand_offset: usize, target: usize) -> i32 {
+    target as i32 - (operand_offset as i32 + 4)
+}
+
 /// Variable binding information
 #[derive(Debug, Clone)]
 struct VarBinding {
@@ -108,6 +120,50 @@ impl Scope {
         index
     }
 
+    /// Adds a function parameter
+    ///
+    /// Arguments are laid out positionally, so every parameter gets its own
+    /// slot. A repeated name hides the earlier slot (last parameter wins).
+    fn add_param(&mut self, name: String) -> u8 {
+        for binding in &mut self.bindings {
+            if binding.name == name {
+                binding.name.clear();
+            }
+        }
+        self.add_binding(name, VarKind::Var)
+    }
+
+    /// Declares a variable, returning its slot
+    ///
+    /// A `var` redeclaring a `var` or parameter reuses the existing slot.
+    /// Returns `None` if the declaration collides with a `let` or `const`
+    /// in the same scope, or if a `let` or `const` redeclares any name.
+    fn declare(&mut self, name: &str, kind: VarKind) -> Option<u8> {
+        if let Some(existing) = self.bindings.iter().find(|b| b.name == name) {
+            return match (kind, existing.kind) {
+                (VarKind::Var, VarKind::Var) => Some(existing.index),
+                _ => None,
+            };
+        }
+
+        if kind == VarKind::Var {
+            if let Some(index) = self.parent.as_ref().and_then(|p| p.find_var(name)) {
+                return Some(index);
+            }
+        }
+
+        Some(self.add_binding(name.to_string(), kind))
+    }
+
+    /// Finds a `var` binding in this scope or its parents
+    fn find_var(&self, name: &str) -> Option<u8> {
+        match self.bindings.iter().find(|b| b.name == name) {
+            Some(binding) if binding.kind == VarKind::Var => Some(binding.index),
+            Some(_) => None,
+            None => self.parent.as_ref().and_then(|p| p.find_var(name)),
+        }
+    }
+
     fn find_binding(&self, name: &str) -> Option<(u8, &VarKind)> {
         for binding in &self.bindings {
             if binding.name == name {
@@ -147,6 +203,19 @@ struct LoopContext {
     break_jumps: Vec<usize>,
     /// Positions of continue jumps that need patching
     continue_jumps: Vec<usize>,
+    /// Switch statements take `break` but leave `continue` to the enclosing loop
+    is_switch: bool,
+}
+
+/// Try statement context, for leaving it through `return`, `break` or `continue`
+#[derive(Debug, Clone)]
+struct TryContext {
+    /// True while the statement's exception handler is installed
+    has_handler: bool,
+    /// Finally block to run on the way out
+    finalizer: Option<Vec<Stmt>>,
+    /// `loop_stack` depth when the try statement was entered
+    loop_depth: usize,
 }
 
 /// Function bytecode entry
@@ -160,6 +229,10 @@ struct FunctionBytecode {
     /// For named function expressions: the local slot where the function self-reference should be stored
     /// The VM will set this slot to the function value when called
     self_name_slot: Option<u8>,
+    /// Arrow functions capture `this` and can't be constructed
+    is_arrow: bool,
+    /// Atom index of the declared name, for the function's `name`
+    name_atom: Option<u16>,
 }
 
 /// Code generator
@@ -169,8 +242,16 @@ pub struct CodeGenerator {
     /// Track which constants are f64 (true) vs JSValue (false)
     const_is_f64: Vec<bool>,
     labels: Vec<Option<usize>>, // Label ID -> bytecode offset
+    /// Forward jumps waiting for their label: (operand offset, label)
+    label_fixups: Vec<(usize, LabelId)>,
     scope: Scope,
+    /// Highest local slot count reached, including block scopes
+    local_high_water: u8,
     loop_stack: Vec<LoopContext>,
+    /// Enclosing try statements, innermost last
+    try_stack: Vec<TryContext>,
+    /// Where the innermost optional chain's `?.` links jump on null or undefined
+    optional_chain: Option<LabelId>,
     /// Atom table for identifier names (maps string to sequential index)
     atom_table: BTreeMap<String, u16>,
     /// Atom strings in order (index -> string)
@@ -185,6 +266,15 @@ pub struct CodeGenerator {
     outer_vars: Vec<(String, u8, bool)>,
     /// Is this a closure (has access to outer scope)?
     is_closure: bool,
+    /// Source line of the statement or expression being generated
+    line: u32,
+    /// Line of each emitted instruction
+    debug_info: DebugInfo,
+    /// Local names close to globals this function reads: (name atom, suggestion atom)
+    ///
+    /// Locals are gone by the time a ReferenceError is thrown, so the near
+    /// name is picked here and shipped with the unit.
+    name_hints: Vec<(u16, u16)>,
 }
 
 impl CodeGenerator {
@@ -195,14 +285,21 @@ impl CodeGenerator {
             constants: ConstantPool::new(),
             const_is_f64: Vec::new(),
             labels: Vec::new(),
+            label_fixups: Vec::new(),
             scope: Scope::new(),
+            local_high_water: 0,
             loop_stack: Vec::new(),
+            try_stack: Vec::new(),
+            optional_chain: None,
             atom_table: BTreeMap::new(),
             atom_strings: Vec::new(),
             function_bytecodes: Vec::new(),
             captured_vars: Vec::new(),
             outer_vars: Vec::new(),
             is_closure: false,
+            line: 0,
+            debug_info: DebugInfo::new(),
+            name_hints: Vec::new(),
         }
     }
 
@@ -213,15 +310,197 @@ impl CodeGenerator {
             constants: ConstantPool::new(),
             const_is_f64: Vec::new(),
             labels: Vec::new(),
+            label_fixups: Vec::new(),
             scope: Scope::new(),
+            local_high_water: 0,
             loop_stack: Vec::new(),
+            try_stack: Vec::new(),
+            optional_chain: None,
             atom_table: BTreeMap::new(),
             atom_strings: Vec::new(),
             function_bytecodes: Vec::new(),
             captured_vars: Vec::new(),
             outer_vars,
             is_closure: true,
+            line: 0,
+            debug_info: DebugInfo::new(),
+            name_hints: Vec::new(),
+        }
+    }
+
+    /// Declares a variable in the current scope, rejecting redeclarations
+    fn declare_var(&mut self, name: &str, kind: VarKind, loc: SourceLocation) -> CodeGenResult<u8> {
+        let index = self.scope.declare(name, kind).ok_or_else(|| {
+            CodeGenError::with_location(format!("Identifier '{}' has already been declared", name), loc)
+        })?;
+        self.reserve_local(index);
+        Ok(index)
+    }
+
+    /// Declares every `var` in a function body in the function scope
+    ///
+    /// Blocks and loop headers drop their scope once generated, so a `var`
+    /// declared in one has to be in place beforehand to outlive it. Nested
+    /// functions hoist their own.
+    fn hoist_vars(&mut self, stmts: &[Stmt]) -> CodeGenResult<()> {
+        stmts.iter().try_for_each(|stmt| self.hoist_vars_in_stmt(stmt))
+    }
+
+    fn hoist_vars_in_stmt(&mut self, stmt: &Stmt) -> CodeGenResult<()> {
+        match stmt {
+            Stmt::VarDecl { kind: VarKind::Var, declarations, loc } => {
+                for decl in declarations {
+                    self.declare_pattern(&decl.target, VarKind::Var, loc.start)?;
+                }
+            }
+            Stmt::Block { stmts, .. } => self.hoist_vars(stmts)?,
+            Stmt::If { consequent, alternate, .. } => {
+                self.hoist_vars_in_stmt(consequent)?;
+                if let Some(alternate) = alternate {
+                    self.hoist_vars_in_stmt(alternate)?;
+                }
+            }
+            Stmt::While { body, .. } | Stmt::DoWhile { body, .. } | Stmt::Labeled { body, .. } => {
+                self.hoist_vars_in_stmt(body)?;
+            }
+            Stmt::For { init, body, loc, .. } => {
+                if let Some(ForInit::VarDecl { kind: VarKind::Var, declarations, .. }) = init {
+                    for decl in declarations {
+                        self.declare_pattern(&decl.target, VarKind::Var, loc.start)?;
+                    }
+                }
+                self.hoist_vars_in_stmt(body)?;
+            }
+            Stmt::ForIn { left, body, loc, .. } | Stmt::ForOf { left, body, loc, .. } => {
+                if let ForInit::VarDecl { kind: VarKind::Var, declarations, .. } = left {
+                    for decl in declarations {
+                        self.declare_pattern(&decl.target, VarKind::Var, loc.start)?;
+                    }
+                }
+                self.hoist_vars_in_stmt(body)?;
+            }
+            Stmt::Try { block, handler, finalizer, .. } => {
+                self.hoist_vars(block)?;
+                if let Some(handler) = handler {
+                    self.hoist_vars(&handler.body)?;
+                }
+                if let Some(finalizer) = finalizer {
+                    self.hoist_vars(finalizer)?;
+                }
+            }
+            Stmt::Switch { cases, .. } => {
+                for case in cases {
+                    self.hoist_vars(&case.consequent)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Returns true if a loop header's declaration binds globals, as a
+    /// top-level `var` does
+    fn loop_binding_is_global(&self, kind: VarKind) -> bool {
+        kind == VarKind::Var && !self.is_closure
+    }
+
+    /// Makes sure the function reserves a slot for `index`
+    ///
+    /// Block scopes are discarded once generated, so their slots have to be
+    /// remembered separately from the function scope's bindings.
+    fn reserve_local(&mut self, index: u8) {
+        self.local_high_water = self.local_high_water.max(index.saturating_add(1));
+    }
+
+    /// Declares every name a declaration target binds
+    fn declare_pattern(&mut self, pattern: &Pattern, kind: VarKind, loc: SourceLocation) -> CodeGenResult<()> {
+        match pattern {
+            Pattern::Identifier(name, _) => {
+                self.declare_var(name, kind, loc)?;
+            }
+            Pattern::Array(elements, _) => {
+                for element in elements.iter().flatten() {
+                    self.declare_pattern(element, kind, loc)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Assigns the value on top of the stack to a declaration target
+    ///
+    /// Array patterns read their elements by index rather than through an
+    /// iterator. The value is consumed; with `global`, names are stored as
+    /// globals like top-level declarations.
+    fn gen_pattern_assign(&mut self, pattern: &Pattern, global: bool) -> CodeGenResult<()> {
+        match pattern {
+            Pattern::Identifier(name, _) => {
+                let location = if global { VarLocation::Global } else { self.resolve_variable(name) };
+                match location {
+                    VarLocation::Local(index) => {
+                        self.emit(Instruction::with_u8(Opcode::PutLoc, index));
+                    }
+                    VarLocation::Captured(index) => {
+                        self.emit(Instruction::with_u8(Opcode::PutVarRef, index));
+                    }
+                    VarLocation::Global => {
+                        let atom_id = self.get_or_create_atom(name);
+                        if atom_id <= 255 {
+                            self.emit(Instruction::with_atom8(Opcode::SetGlobal8, atom_id as u8));
+                        } else {
+                            self.emit(Instruction::with_atom16(Opcode::SetGlobal16, atom_id as u16));
+                        }
+                        self.emit_simple(Opcode::Drop);
+                    }
+                }
+            }
+            Pattern::Array(elements, _) => {
+                for (i, element) in elements.iter().enumerate() {
+                    if let Some(element) = element {
+                        self.emit_simple(Opcode::Dup);
+                        self.gen_literal(&Literal::Number(i as f64))?;
+                        self.emit_simple(Opcode::GetArrayEl);
+                        self.gen_pattern_assign(element, global)?;
+                    }
+                }
+                self.emit_simple(Opcode::Drop);
+            }
+        }
+        Ok(())
+    }
+
+    /// Leaves enclosing try statements down to `depth`
+    ///
+    /// Used by jumps out of try and catch blocks: each statement left has its
+    /// handler removed and its finally block run, innermost first. A finally
+    /// block is generated outside its own try statement, so jumps inside it
+    /// only unwind the statements around it.
+    fn gen_try_exits(&mut self, depth: usize) -> CodeGenResult<()> {
+        let exited = self.try_stack.split_off(depth.min(self.try_stack.len()));
+        let mut result = Ok(());
+        for (i, try_ctx) in exited.iter().enumerate().rev() {
+            if try_ctx.has_handler {
+                self.emit_simple(Opcode::ClearCatchOffset);
+            }
+            if let Some(ref finally_block) = try_ctx.finalizer {
+                self.try_stack.extend_from_slice(&exited[..i]);
+                result = finally_block.iter().try_for_each(|stmt| self.gen_stmt(stmt));
+                self.try_stack.truncate(depth);
+                if result.is_err() {
+                    break;
+                }
+            }
         }
+        self.try_stack.extend(exited);
+        result
+    }
+
+    /// Leaves the try statements inside the loop at `loop_depth`, for break and continue
+    fn gen_loop_try_exits(&mut self, loop_depth: usize) -> CodeGenResult<()> {
+        let depth = self.try_stack.iter()
+            .position(|t| t.loop_depth >= loop_depth)
+            .unwrap_or(self.try_stack.len());
+        self.gen_try_exits(depth)
     }
 
     /// Gets or creates an atom for an identifier name
@@ -273,6 +552,24 @@ impl CodeGenerator {
         VarLocation::Global
     }
 
+    /// Records a local spelled like `name` for the ReferenceError a read
+    /// of the global `name` throws if it's missing
+    fn note_name_hint(&mut self, name: &str) {
+        let mut locals = Vec::new();
+        self.collect_scope_vars(&self.scope, &mut locals);
+        let suggestion = closest_name(
+            name,
+            locals.iter().map(|(local, _, _)| local.as_str())
+                .chain(self.outer_vars.iter().map(|(outer, _, _)| outer.as_str())),
+        ).map(String::from);
+        if let Some(suggestion) = suggestion {
+            let hint = (self.get_or_create_atom(name), self.get_or_create_atom(&suggestion));
+            if !self.name_hints.contains(&hint) {
+                self.name_hints.push(hint);
+            }
+        }
+    }
+
     /// Generates bytecode for a program
     pub fn generate(mut self, program: &Program) -> CodeGenResult<Vec<u8>> {
         let len = program.body.len();
@@ -287,11 +584,14 @@ impl CodeGenerator {
         if len == 0 {
             self.emit_simple(Opcode::ReturnUndef);
         }
+        self.resolve_labels()?;
 
         // Serialize the constant pool, atom table, function table, and bytecode
         // Format: [constant_count: u16][(type: u8, value: usize)...]
         //         [atom_count: u16][(len: u16, string_bytes)...]
         //         [function_count: u16][(param_count: u8, local_count: u8, bytecode_len: u32, bytecode_bytes)...]
+        //         [line_table_len: u16][line table (see DebugInfo)]
+        //         [hint_count: u16][(name_atom: u16, suggestion_atom: u16)...]
         //         [bytecode...]
         // Type: 0 = f64 bits, 1 = JSValue
         let mut result = Vec::new();
@@ -332,11 +632,23 @@ impl CodeGenerator {
             result.push(func.local_count);
             // Write self_name_slot: 0xFF means None, otherwise it's the slot index
             result.push(func.self_name_slot.unwrap_or(0xFF));
+            result.push(if func.is_arrow { FUNC_FLAG_ARROW } else { 0 });
+            result.extend_from_slice(&func.name_atom.unwrap_or(0xFFFF).to_le_bytes());
             let bytecode_len = func.bytecode.len() as u32;
             result.extend_from_slice(&bytecode_len.to_le_bytes());
             result.extend_from_slice(&func.bytecode);
         }
 
+        // The Undefined slots below come before the first instruction
+        let lines = self.debug_info.encode(u32::from(self.local_high_water));
+        write_line_table(&mut result, &lines)?;
+        write_name_hints(&mut result, &self.name_hints);
+
+        // Top-level code has no local count in its header, so the locals
+        // declared by loop heads are reserved before any temporaries land
+        // on the stack
+        result.extend(core::iter::repeat(Opcode::Undefined as u8).take(self.local_high_water as usize));
+
         // Append the main bytecode
         result.extend_from_slice(self.writer.as_slice());
 
@@ -404,16 +716,18 @@ impl CodeGenerator {
 
         // Create a new code generator for the function with access to outer vars
         let mut func_gen = CodeGenerator::new_for_closure(outer_vars);
+        func_gen.line = self.line;
 
         // Create a new scope and add parameters as local variables FIRST
         // This ensures params match the VM's stack layout (args pushed first)
         for param in params {
-            func_gen.scope.add_binding(param.clone(), VarKind::Var);
+            func_gen.scope.add_param(param.clone());
         }
 
         // If this is a named function expression, add the name as a local AFTER params
-        // The function will be able to reference itself for recursion
-        let self_name_slot = if let Some(name) = func_name {
+        // The function will be able to reference itself for recursion, unless a
+        // parameter of the same name shadows it
+        let self_name_slot = if let Some(name) = func_name.filter(|n| !params.iter().any(|p| p == n)) {
             let slot = func_gen.scope.add_binding(name.to_string(), VarKind::Var);
             Some(slot)
         } else {
@@ -422,7 +736,8 @@ impl CodeGenerator {
 
         // Check if 'arguments' is referenced in the function body
         // If so, we need to add it as a local and emit the Arguments opcode
-        let uses_arguments = referenced_vars.iter().any(|v| v == "arguments");
+        let uses_arguments = referenced_vars.iter().any(|v| v == "arguments")
+            && !params.iter().any(|p| p == "arguments");
         if uses_arguments {
             // Add 'arguments' as a local variable
             let args_slot = func_gen.scope.add_binding("arguments".to_string(), VarKind::Var);
@@ -432,6 +747,9 @@ impl CodeGenerator {
             func_gen.emit(Instruction::with_u8(Opcode::PutLoc, args_slot));
         }
 
+        // `var` belongs to the function, wherever in the body it appears
+        func_gen.hoist_vars(body)?;
+
         // Compile all statements in the function body
         let last_idx = body.len().saturating_sub(1);
         for (i, stmt) in body.iter().enumerate() {
@@ -455,7 +773,7 @@ impl CodeGenerator {
         }
 
         // Get the local count (includes params and local vars)
-        let local_count = func_gen.scope.bindings.len() as u8;
+        let local_count = (func_gen.scope.bindings.len() as u8).max(func_gen.local_high_water);
 
         // Get captured vars before consuming func_gen
         let captured_vars = func_gen.captured_vars.clone();
@@ -624,6 +942,7 @@ impl CodeGenerator {
                     self.collect_vars_in_expr(property, vars);
                 }
             }
+            Expr::OptionalChain { expr, .. } => self.collect_vars_in_expr(expr, vars),
             Expr::Array { elements, .. } => {
                 for elem in elements {
                     if let Some(e) = elem {
@@ -633,6 +952,9 @@ impl CodeGenerator {
             }
             Expr::Object { properties, .. } => {
                 for prop in properties {
+                    if let crate::compiler::ast::PropertyKey::Computed(key) = &prop.key {
+                        self.collect_vars_in_expr(key, vars);
+                    }
                     self.collect_vars_in_expr(&prop.value, vars);
                 }
             }
@@ -652,7 +974,8 @@ impl CodeGenerator {
                     }
                 }
             }
-            Expr::Sequence { exprs, .. } => {
+            Expr::Sequence { exprs, .. } |
+            Expr::Template { exprs, .. } => {
                 for e in exprs {
                     self.collect_vars_in_expr(e, vars);
                 }
@@ -670,6 +993,7 @@ impl CodeGenerator {
 
     /// Generates raw bytecode without wrapping in a Program
     fn generate_raw(self) -> CodeGenResult<Vec<u8>> {
+        self.resolve_labels()?;
         let mut result = Vec::new();
 
         // Write constant count
@@ -709,11 +1033,16 @@ impl CodeGenerator {
             result.push(func.local_count);
             // Write self_name_slot: 0xFF means None, otherwise it's the slot index
             result.push(func.self_name_slot.unwrap_or(0xFF));
+            result.push(if func.is_arrow { FUNC_FLAG_ARROW } else { 0 });
+            result.extend_from_slice(&func.name_atom.unwrap_or(0xFFFF).to_le_bytes());
             let bytecode_len = func.bytecode.len() as u32;
             result.extend_from_slice(&bytecode_len.to_le_bytes());
             result.extend_from_slice(&func.bytecode);
         }
 
+        write_line_table(&mut result, &self.debug_info.encode(0))?;
+        write_name_hints(&mut result, &self.name_hints);
+
         // Append the bytecode
         result.extend_from_slice(self.writer.as_slice());
 
@@ -728,36 +1057,54 @@ impl CodeGenerator {
     }
 
     /// Marks a label at the current position
+    ///
+    /// Patches every jump already emitted to it.
     fn mark_label(&mut self, label: LabelId) {
-        self.labels[label.0] = Some(self.writer.pc());
+        let target = self.writer.pc();
+        self.labels[label.0] = Some(target);
+
+        let writer = &mut self.writer;
+        self.label_fixups.retain(|&(operand_offset, fixup_label)| {
+            if fixup_label != label {
+                return true;
+            }
+            writer.patch_i32(operand_offset, jump_offset(operand_offset, target));
+            false
+        });
     }
 
-    /// Emits a jump to a label (will be patched later)
+    /// Emits a jump to a label
+    ///
+    /// Backward jumps are encoded directly; forward jumps are patched when
+    /// their label is marked.
     fn emit_jump(&mut self, opcode: Opcode, label: LabelId) {
-        let patch_offset = self.writer.pc() + 1; // After opcode byte
-        self.emit(Instruction::with_label(opcode, 0)); // Placeholder
-
-        // Store for patching
-        if self.labels[label.0].is_none() {
-            // Forward jump - will patch in resolve_labels
+        let operand_offset = self.writer.pc() + 1; // After opcode byte
+        match self.labels[label.0] {
+            Some(target) => self.emit(Instruction::with_label(opcode, jump_offset(operand_offset, target))),
+            None => {
+                self.emit(Instruction::with_label(opcode, 0)); // Placeholder
+                self.label_fixups.push((operand_offset, label));
+            }
         }
     }
 
-    /// Resolves all forward jumps
-    fn resolve_labels(&mut self) -> CodeGenResult<()> {
-        // This is a simplified approach - in production we'd track which jumps need patching
-        // For now, we'll emit jumps with immediate values when possible
-        Ok(())
+    /// Checks that every jump reached its label
+    fn resolve_labels(&self) -> CodeGenResult<()> {
+        if self.label_fixups.is_empty() {
+            Ok(())
+        } else {
+            Err(CodeGenError::new("Jump to a label that was never marked".into()))
+        }
     }
 
-
     /// Emits a simple instruction (no operands)
     fn emit_simple(&mut self, opcode: Opcode) {
-        self.writer.emit(&Instruction::new(opcode));
+        self.emit(Instruction::new(opcode));
     }
 
-    /// Emits an instruction
+    /// Emits an instruction, recording the current line for it
     fn emit(&mut self, instruction: Instruction) {
+        self.debug_info.add_mapping(self.writer.pc() as u32, self.line);
         self.writer.emit(&instruction);
     }
 
@@ -789,6 +1136,13 @@ impl CodeGenerator {
 
     /// Generates bytecode for a statement
     fn gen_stmt(&mut self, stmt: &Stmt) -> CodeGenResult<()> {
+        let outer_line = core::mem::replace(&mut self.line, stmt.span().start.line);
+        let result = self.gen_stmt_inner(stmt);
+        self.line = outer_line;
+        result
+    }
+
+    fn gen_stmt_inner(&mut self, stmt: &Stmt) -> CodeGenResult<()> {
         match stmt {
             Stmt::Expression { expr, .. } => {
                 self.gen_expr(expr)?;
@@ -810,26 +1164,42 @@ impl CodeGenerator {
                 Ok(())
             }
 
-            Stmt::VarDecl { kind, declarations, .. } => {
+            Stmt::VarDecl { kind, declarations, loc } => {
                 for decl in declarations {
+                    let Some(name) = decl.target.as_identifier() else {
+                        let init = decl.init.as_ref().ok_or_else(|| {
+                            CodeGenError::with_location("Missing initializer in destructuring declaration".into(), loc.start)
+                        })?;
+                        if self.is_closure {
+                            self.declare_pattern(&decl.target, *kind, loc.start)?;
+                        }
+                        self.gen_expr(init)?;
+                        self.gen_pattern_assign(&decl.target, !self.is_closure)?;
+                        continue;
+                    };
+
                     if self.is_closure {
                         // Inside a function - use local variable
-                        let index = self.scope.add_binding(decl.name.clone(), *kind);
+                        let next_index = self.scope.next_index();
+                        let index = self.declare_var(name, *kind, loc.start)?;
 
                         if let Some(ref init) = decl.init {
                             self.gen_expr(init)?;
                             self.emit(Instruction::with_u8(Opcode::PutLoc, index));
-                        } else {
-                            // Initialize to undefined
+                        } else if index >= next_index {
+                            // Initialize to undefined (a redeclared var keeps its value)
                             self.emit_simple(Opcode::Undefined);
                             self.emit(Instruction::with_u8(Opcode::PutLoc, index));
                         }
                     } else {
                         // At top level - use global variable
-                        let atom_id = self.get_or_create_atom(&decl.name);
+                        let atom_id = self.get_or_create_atom(name);
 
                         if let Some(ref init) = decl.init {
                             self.gen_expr(init)?;
+                        } else if *kind == VarKind::Var {
+                            // A redeclared var keeps its value
+                            self.emit(Instruction::with_atom16(Opcode::GetGlobalUndef, atom_id));
                         } else {
                             self.emit_simple(Opcode::Undefined);
                         }
@@ -846,12 +1216,13 @@ impl CodeGenerator {
                 Ok(())
             }
 
-            Stmt::FunctionDecl { name, params, body, .. } => {
+            Stmt::FunctionDecl { name, params, body, loc } => {
                 // Compile function body to bytecode
                 // Function declarations don't need self_name_slot as the name is bound in outer scope
                 let (func_bytecode, local_count, captured_vars, _self_name_slot) = self.compile_function_body(params, body)?;
                 let param_count = params.len() as u8;
                 let has_captures = !captured_vars.is_empty();
+                let name_atom = self.get_or_create_atom(name);
 
                 // Add to function table
                 let func_index = self.function_bytecodes.len() as u16;
@@ -861,6 +1232,8 @@ impl CodeGenerator {
                     local_count,
                     captured_vars: captured_vars.clone(),
                     self_name_slot: None,  // Function declarations don't need self-reference
+                    is_arrow: false,
+                    name_atom: Some(name_atom),
                 });
 
                 if has_captures {
@@ -897,7 +1270,7 @@ impl CodeGenerator {
                     }
                 } else {
                     // Local scope - add to scope and use PutLoc
-                    let index = self.scope.add_binding(name.clone(), VarKind::Var);
+                    let index = self.declare_var(name, VarKind::Var, loc.start)?;
                     self.emit(Instruction::with_u8(Opcode::PutLoc, index));
                 }
 
@@ -905,39 +1278,20 @@ impl CodeGenerator {
             }
 
             Stmt::If { test, consequent, alternate, .. } => {
-                // Compile test
                 self.gen_expr(test)?;
 
-                // Create labels
                 let else_label = self.create_label();
-                let end_label = self.create_label();
-
-                // Jump to else if test is false
-                let if_false_offset = self.writer.pc() + 1;
-                self.emit(Instruction::with_label(Opcode::IfFalse, 0)); // Will patch
-
-                // Compile consequent
+                self.emit_jump(Opcode::IfFalse, else_label);
                 self.gen_stmt(consequent)?;
 
-                if alternate.is_some() {
-                    // Jump to end after consequent
-                    let goto_offset = self.writer.pc() + 1;
-                    self.emit(Instruction::with_label(Opcode::Goto, 0)); // Will patch
-
-                    // Patch else jump
-                    let else_pos = self.writer.pc();
-                    self.writer.patch_i32(if_false_offset, (else_pos as i32) - (if_false_offset as i32) - 4);
-
-                    // Compile alternate
-                    self.gen_stmt(alternate.as_ref().unwrap())?;
-
-                    // Patch end jump
-                    let end_pos = self.writer.pc();
-                    self.writer.patch_i32(goto_offset, (end_pos as i32) - (goto_offset as i32) - 4);
+                if let Some(alternate) = alternate {
+                    let end_label = self.create_label();
+                    self.emit_jump(Opcode::Goto, end_label);
+                    self.mark_label(else_label);
+                    self.gen_stmt(alternate)?;
+                    self.mark_label(end_label);
                 } else {
-                    // No else - just patch the if_false jump to end
-                    let end_pos = self.writer.pc();
-                    self.writer.patch_i32(if_false_offset, (end_pos as i32) - (if_false_offset as i32) - 4);
+                    self.mark_label(else_label);
                 }
 
                 Ok(())
@@ -948,7 +1302,7 @@ impl CodeGenerator {
                 let break_label = self.create_label();
                 let continue_label = self.create_label();
 
-                self.loop_stack.push(LoopContext { break_label, continue_label, break_jumps: Vec::new(), continue_jumps: Vec::new() });
+                self.loop_stack.push(LoopContext { break_label, continue_label, break_jumps: Vec::new(), continue_jumps: Vec::new(), is_switch: false });
 
                 // Compile test
                 self.gen_expr(test)?;
@@ -969,18 +1323,21 @@ impl CodeGenerator {
                 let end_pos = self.writer.pc();
                 self.writer.patch_i32(if_false_offset, (end_pos as i32) - (if_false_offset as i32) - 4);
 
-                // Patch all break jumps to point here
+                // Patch all break jumps to point here, continue jumps to the test
                 if let Some(ctx) = self.loop_stack.last() {
                     for &patch_offset in &ctx.break_jumps {
                         self.writer.patch_i32(patch_offset, (end_pos as i32) - (patch_offset as i32) - 4);
                     }
+                    for &patch_offset in &ctx.continue_jumps {
+                        self.writer.patch_i32(patch_offset, (loop_start as i32) - (patch_offset as i32) - 4);
+                    }
                 }
 
                 self.loop_stack.pop();
                 Ok(())
             }
 
-            Stmt::For { init, test, update, body, .. } => {
+            Stmt::For { init, test, update, body, loc } => {
                 // Create new scope for loop variable
                 let new_scope = Scope::with_parent(self.scope.clone());
                 let old_scope = core::mem::replace(&mut self.scope, new_scope);
@@ -988,12 +1345,15 @@ impl CodeGenerator {
                 // Compile init
                 if let Some(ref init) = init {
                     match init {
-                        ForInit::VarDecl { kind, declarations } => {
+                        ForInit::VarDecl { kind, declarations, .. } => {
+                            let global = self.loop_binding_is_global(*kind);
                             for decl in declarations {
-                                let index = self.scope.add_binding(decl.name.clone(), *kind);
+                                if !global {
+                                    self.declare_pattern(&decl.target, *kind, loc.start)?;
+                                }
                                 if let Some(ref init_expr) = decl.init {
                                     self.gen_expr(init_expr)?;
-                                    self.emit(Instruction::with_u8(Opcode::PutLoc, index));
+                                    self.gen_pattern_assign(&decl.target, global)?;
                                 }
                             }
                         }
@@ -1008,7 +1368,7 @@ impl CodeGenerator {
                 let break_label = self.create_label();
                 let continue_label = self.create_label();
 
-                self.loop_stack.push(LoopContext { break_label, continue_label, break_jumps: Vec::new(), continue_jumps: Vec::new() });
+                self.loop_stack.push(LoopContext { break_label, continue_label, break_jumps: Vec::new(), continue_jumps: Vec::new(), is_switch: false });
 
                 // Compile test (if present)
                 let if_false_offset = if let Some(ref test) = test {
@@ -1023,7 +1383,8 @@ impl CodeGenerator {
                 // Compile body
                 self.gen_stmt(body)?;
 
-                // Compile update
+                // Compile update; continue jumps land here
+                let update_pos = self.writer.pc();
                 if let Some(ref update) = update {
                     self.gen_expr(update)?;
                     self.emit_simple(Opcode::Drop);
@@ -1040,11 +1401,14 @@ impl CodeGenerator {
                     self.writer.patch_i32(offset, (end_pos as i32) - (offset as i32) - 4);
                 }
 
-                // Patch all break jumps to point here
+                // Patch all break jumps to point here, continue jumps to the update
                 if let Some(ctx) = self.loop_stack.last() {
                     for &patch_offset in &ctx.break_jumps {
                         self.writer.patch_i32(patch_offset, (end_pos as i32) - (patch_offset as i32) - 4);
                     }
+                    for &patch_offset in &ctx.continue_jumps {
+                        self.writer.patch_i32(patch_offset, (update_pos as i32) - (patch_offset as i32) - 4);
+                    }
                 }
 
                 self.loop_stack.pop();
@@ -1055,8 +1419,11 @@ impl CodeGenerator {
             Stmt::Return { argument, .. } => {
                 if let Some(ref arg) = argument {
                     self.gen_expr(arg)?;
+                    // The return value stays on the stack while finally blocks run
+                    self.gen_try_exits(0)?;
                     self.emit_simple(Opcode::Return);
                 } else {
+                    self.gen_try_exits(0)?;
                     self.emit_simple(Opcode::ReturnUndef);
                 }
                 Ok(())
@@ -1064,6 +1431,7 @@ impl CodeGenerator {
 
             Stmt::Break { .. } => {
                 if self.loop_stack.last().is_some() {
+                    self.gen_loop_try_exits(self.loop_stack.len())?;
                     // Emit a Goto with placeholder offset
                     let patch_offset = self.writer.pc() + 1;
                     self.emit(Instruction::with_label(Opcode::Goto, 0)); // Will patch
@@ -1076,14 +1444,14 @@ impl CodeGenerator {
             }
 
             Stmt::Continue { .. } => {
-                if self.loop_stack.last().is_some() {
+                // Skip past any switch statements to the innermost loop
+                if let Some(index) = self.loop_stack.iter().rposition(|ctx| !ctx.is_switch) {
+                    self.gen_loop_try_exits(index + 1)?;
                     // Emit a Goto with placeholder offset
                     let patch_offset = self.writer.pc() + 1;
                     self.emit(Instruction::with_label(Opcode::Goto, 0)); // Will patch
                     // Record this position for patching at end of loop
-                    if let Some(ctx) = self.loop_stack.last_mut() {
-                        ctx.continue_jumps.push(patch_offset);
-                    }
+                    self.loop_stack[index].continue_jumps.push(patch_offset);
                 }
                 Ok(())
             }
@@ -1095,43 +1463,69 @@ impl CodeGenerator {
             }
 
             Stmt::Try { block, handler, finalizer, .. } => {
-                // Try/catch implementation
+                // Try/catch/finally implementation
                 //
                 // Structure:
                 //   PushCatchOffset catch_label
                 //   [try block]
-                //   ClearCatchOffset      ; clear the exception handler
-                //   Goto end_label        ; skip catch block
-                // catch_label:
+                //   ClearCatchOffset      ; try completed normally
+                //   Goto finally_label
+                // catch_label:            ; exception on the stack, handler consumed
+                //   PushCatchOffset rethrow_label   (only with finally)
                 //   [store exception in catch var]
                 //   [catch block]
-                // end_label:
-                //   [finally block if present]
+                //   ClearCatchOffset                (only with finally)
+                //   Goto finally_label
+                // rethrow_label:          ; exception on the stack (only with finally)
+                //   [finally block]
+                //   Throw
+                // finally_label:
+                //   [finally block]
+                //
+                // Without a catch clause, catch_label is rethrow_label. A
+                // return, break or continue leaving the try or catch block
+                // clears the handler and runs the finally block itself (see
+                // gen_try_exits).
+                let loop_depth = self.loop_stack.len();
 
-                // Push catch handler offset - offset points to catch block
                 self.emit(Instruction::with_label(Opcode::PushCatchOffset, 0));
                 let catch_patch_offset = self.writer.pc(); // Position right after instruction
 
-                // Generate try block
+                self.try_stack.push(TryContext {
+                    has_handler: true,
+                    finalizer: finalizer.clone(),
+                    loop_depth,
+                });
                 for stmt in block {
                     self.gen_stmt(stmt)?;
                 }
+                self.try_stack.pop();
 
-                // Clear the catch offset and jump to end
-                self.emit_simple(Opcode::ClearCatchOffset);  // Clear exception handler after try completes normally
+                self.emit_simple(Opcode::ClearCatchOffset);
+                let mut finally_jumps = Vec::new();
                 self.emit(Instruction::with_label(Opcode::Goto, 0));
-                let end_patch_offset = self.writer.pc(); // Position right after goto instruction
+                finally_jumps.push(self.writer.pc());
 
-                // Catch label - exception value is on the stack
+                // The offset in PushCatchOffset is relative to the PC after the instruction
                 let catch_pc = self.writer.pc();
-                // Patch the PushCatchOffset to jump here. The offset in PushCatchOffset is relative to the PC after the instruction.
                 self.writer.patch_i32(catch_patch_offset - 4, (catch_pc as i32) - (catch_patch_offset as i32));
 
                 if let Some(catch_clause) = handler {
-                    // If there's a catch parameter, store the exception in it
+                    // Exceptions thrown by the catch block still run the finally block
+                    let rethrow_patch_offset = if finalizer.is_some() {
+                        self.emit(Instruction::with_label(Opcode::PushCatchOffset, 0));
+                        Some(self.writer.pc())
+                    } else {
+                        None
+                    };
+
+                    // The catch parameter is scoped to the catch clause
+                    let new_scope = Scope::with_parent(self.scope.clone());
+                    let old_scope = core::mem::replace(&mut self.scope, new_scope);
+
                     if let Some(ref param_name) = catch_clause.param {
-                        // Add catch parameter as a local variable
                         let var_idx = self.scope.add_binding(param_name.clone(), VarKind::Let);
+                        self.reserve_local(var_idx);
                         // Exception is on the stack, store it in the catch variable
                         self.emit(Instruction::with_u8(Opcode::PutLoc, var_idx));
                     } else {
@@ -1139,21 +1533,42 @@ impl CodeGenerator {
                         self.emit_simple(Opcode::Drop);
                     }
 
-                    // Generate catch block
-                    for stmt in &catch_clause.body {
+                    self.try_stack.push(TryContext {
+                        has_handler: finalizer.is_some(),
+                        finalizer: finalizer.clone(),
+                        loop_depth,
+                    });
+                    let body_result = catch_clause.body.iter().try_for_each(|stmt| self.gen_stmt(stmt));
+                    self.try_stack.pop();
+                    self.scope = old_scope;
+                    body_result?;
+
+                    if let Some(patch_offset) = rethrow_patch_offset {
+                        self.emit_simple(Opcode::ClearCatchOffset);
+                        self.emit(Instruction::with_label(Opcode::Goto, 0));
+                        finally_jumps.push(self.writer.pc());
+
+                        let rethrow_pc = self.writer.pc();
+                        self.writer.patch_i32(patch_offset - 4, (rethrow_pc as i32) - (patch_offset as i32));
+                    }
+                }
+
+                // Exceptional path: run the finally block, then rethrow
+                if let Some(finally_block) = finalizer {
+                    for stmt in finally_block {
                         self.gen_stmt(stmt)?;
                     }
-                } else {
-                    // No catch handler, just rethrow
+                    self.emit_simple(Opcode::Throw);
+                } else if handler.is_none() {
                     self.emit_simple(Opcode::Throw);
                 }
 
-                // End label
-                let end_pc = self.writer.pc();
-                // Patch the Goto to jump here
-                self.writer.patch_i32(end_patch_offset - 4, (end_pc as i32) - (end_patch_offset as i32));
+                let finally_pc = self.writer.pc();
+                for patch_offset in finally_jumps {
+                    self.writer.patch_i32(patch_offset - 4, (finally_pc as i32) - (patch_offset as i32));
+                }
 
-                // Generate finally block if present
+                // Normal path
                 if let Some(finally_block) = finalizer {
                     for stmt in finally_block {
                         self.gen_stmt(stmt)?;
@@ -1163,19 +1578,25 @@ impl CodeGenerator {
                 Ok(())
             }
 
-            Stmt::ForIn { left, right, body, .. } => {
+            Stmt::ForIn { left, right, body, loc } => {
                 // Create new scope for loop variable
                 let new_scope = Scope::with_parent(self.scope.clone());
                 let old_scope = core::mem::replace(&mut self.scope, new_scope);
 
-                // Get the loop variable index
+                // Declare the loop binding; a pattern is re-assigned from each
+                // element, otherwise the element goes to a single local
+                let mut target = None;
+                let mut global = false;
                 let var_index = match left {
-                    ForInit::VarDecl { kind, declarations } => {
+                    ForInit::VarDecl { kind, declarations, .. } => {
+                        global = self.loop_binding_is_global(*kind);
                         if let Some(decl) = declarations.first() {
-                            self.scope.add_binding(decl.name.clone(), *kind)
-                        } else {
-                            0
+                            if !global {
+                                self.declare_pattern(&decl.target, *kind, loc.start)?;
+                            }
+                            target = Some(&decl.target);
                         }
+                        0
                     }
                     ForInit::Expr(expr) => {
                         // For expression form, we need to store to the variable
@@ -1197,7 +1618,7 @@ impl CodeGenerator {
                 let loop_start = self.writer.pc();
                 let break_label = self.create_label();
                 let continue_label = self.create_label();
-                self.loop_stack.push(LoopContext { break_label, continue_label, break_jumps: Vec::new(), continue_jumps: Vec::new() });
+                self.loop_stack.push(LoopContext { break_label, continue_label, break_jumps: Vec::new(), continue_jumps: Vec::new(), is_switch: false });
 
                 // Duplicate the iterator result to check if done
                 self.emit_simple(Opcode::Dup);
@@ -1209,12 +1630,18 @@ impl CodeGenerator {
                 self.emit(Instruction::with_label(Opcode::IfTrue, 0)); // Will patch
 
                 // Store key in loop variable
-                self.emit(Instruction::with_u8(Opcode::PutLoc, var_index));
+                if let Some(target) = target {
+                    self.gen_pattern_assign(target, global)?;
+                } else {
+                    self.emit(Instruction::with_u8(Opcode::PutLoc, var_index));
+                }
 
                 // Execute body
                 self.gen_stmt(body)?;
 
-                // ForInNext: pops old state, pushes next key (or undefined if done)
+                // ForInNext: pops old state, pushes next key (or undefined if done);
+                // continue jumps land here
+                let next_pos = self.writer.pc();
                 self.emit_simple(Opcode::ForInNext);
 
                 // Jump back to loop start
@@ -1226,35 +1653,46 @@ impl CodeGenerator {
                 let end_pos = self.writer.pc();
                 self.writer.patch_i32(if_true_offset, (end_pos as i32) - (if_true_offset as i32) - 4);
 
-                // Drop remaining iterator state
+                // Drop the final undefined key
                 self.emit_simple(Opcode::Drop);
 
-                // Patch all break jumps to point here (after Drop)
+                // Patch all break jumps to point here (after Drop), so they
+                // discard the iterator too
                 let after_loop_pos = self.writer.pc();
                 if let Some(ctx) = self.loop_stack.last() {
                     for &patch_offset in &ctx.break_jumps {
                         self.writer.patch_i32(patch_offset, (after_loop_pos as i32) - (patch_offset as i32) - 4);
                     }
+                    for &patch_offset in &ctx.continue_jumps {
+                        self.writer.patch_i32(patch_offset, (next_pos as i32) - (patch_offset as i32) - 4);
+                    }
                 }
+                self.emit_simple(Opcode::ForInEnd);
 
                 self.loop_stack.pop();
                 self.scope = old_scope;
                 Ok(())
             }
 
-            Stmt::ForOf { left, right, body, .. } => {
+            Stmt::ForOf { left, right, body, loc } => {
                 // Create new scope for loop variable
                 let new_scope = Scope::with_parent(self.scope.clone());
                 let old_scope = core::mem::replace(&mut self.scope, new_scope);
 
-                // Get the loop variable index
+                // Declare the loop binding; a pattern is re-assigned from each
+                // element, otherwise the element goes to a single local
+                let mut target = None;
+                let mut global = false;
                 let var_index = match left {
-                    ForInit::VarDecl { kind, declarations } => {
+                    ForInit::VarDecl { kind, declarations, .. } => {
+                        global = self.loop_binding_is_global(*kind);
                         if let Some(decl) = declarations.first() {
-                            self.scope.add_binding(decl.name.clone(), *kind)
-                        } else {
-                            0
+                            if !global {
+                                self.declare_pattern(&decl.target, *kind, loc.start)?;
+                            }
+                            target = Some(&decl.target);
                         }
+                        0
                     }
                     ForInit::Expr(expr) => {
                         if let Expr::Identifier(name, _) = expr {
@@ -1268,32 +1706,32 @@ impl CodeGenerator {
                 // Evaluate the iterable
                 self.gen_expr(right)?;
 
-                // ForOfStart: takes iterable from stack, pushes iterator state and first value
+                // ForOfStart: takes the iterable from the stack and starts iterating it
                 self.emit_simple(Opcode::ForOfStart);
 
                 let loop_start = self.writer.pc();
                 let break_label = self.create_label();
                 let continue_label = self.create_label();
-                self.loop_stack.push(LoopContext { break_label, continue_label, break_jumps: Vec::new(), continue_jumps: Vec::new() });
+                self.loop_stack.push(LoopContext { break_label, continue_label, break_jumps: Vec::new(), continue_jumps: Vec::new(), is_switch: false });
 
-                // Duplicate to check if done (undefined means done)
-                self.emit_simple(Opcode::Dup);
-                self.emit_simple(Opcode::Undefined);
-                self.emit_simple(Opcode::StrictEq);
+                // ForOfNext: pushes the next value and a done flag; continue
+                // jumps land here
+                self.emit_simple(Opcode::ForOfNext);
 
-                // If equal to undefined, exit loop
+                // If done, exit loop
                 let if_true_offset = self.writer.pc() + 1;
                 self.emit(Instruction::with_label(Opcode::IfTrue, 0)); // Will patch
 
                 // Store value in loop variable
-                self.emit(Instruction::with_u8(Opcode::PutLoc, var_index));
+                if let Some(target) = target {
+                    self.gen_pattern_assign(target, global)?;
+                } else {
+                    self.emit(Instruction::with_u8(Opcode::PutLoc, var_index));
+                }
 
                 // Execute body
                 self.gen_stmt(body)?;
 
-                // ForOfNext: get next value
-                self.emit_simple(Opcode::ForOfNext);
-
                 // Jump back to loop start
                 let goto_offset = self.writer.pc() + 1;
                 let jump_dist = (loop_start as i32) - (goto_offset as i32) - 4;
@@ -1303,87 +1741,486 @@ impl CodeGenerator {
                 let end_pos = self.writer.pc();
                 self.writer.patch_i32(if_true_offset, (end_pos as i32) - (if_true_offset as i32) - 4);
 
-                // Drop remaining iterator state
+                // Drop the value pushed alongside the done flag
                 self.emit_simple(Opcode::Drop);
 
-                // Patch all break jumps to point here (after Drop)
+                // Patch all break jumps to point here (after Drop), so they
+                // discard the iterator too
                 let after_loop_pos = self.writer.pc();
                 if let Some(ctx) = self.loop_stack.last() {
                     for &patch_offset in &ctx.break_jumps {
                         self.writer.patch_i32(patch_offset, (after_loop_pos as i32) - (patch_offset as i32) - 4);
                     }
+                    for &patch_offset in &ctx.continue_jumps {
+                        self.writer.patch_i32(patch_offset, (loop_start as i32) - (patch_offset as i32) - 4);
+                    }
                 }
+                self.emit_simple(Opcode::ForOfEnd);
 
                 self.loop_stack.pop();
                 self.scope = old_scope;
                 Ok(())
             }
 
-            Stmt::DoWhile { .. } | Stmt::Switch { .. } | Stmt::Empty { .. } => {
-                // These are stubs for now
-                Ok(())
-            }
+            Stmt::DoWhile { body, test, .. } => {
+                let loop_start = self.writer.pc();
+                let break_label = self.create_label();
+                let continue_label = self.create_label();
 
-            Stmt::Labeled { body, .. } => {
-                // For now, just generate code for the body
-                // TODO: Support labeled break/continue targeting specific labels
-                self.gen_stmt(body)
-            }
-        }
-    }
+                self.loop_stack.push(LoopContext { break_label, continue_label, break_jumps: Vec::new(), continue_jumps: Vec::new(), is_switch: false });
 
-    /// Generates bytecode for an expression
-    fn gen_expr(&mut self, expr: &Expr) -> CodeGenResult<()> {
-        match expr {
-            Expr::Literal(lit, _) => {
-                self.gen_literal(lit)?;
-                Ok(())
-            }
+                // Compile body
+                self.gen_stmt(body)?;
 
-            Expr::Identifier(name, _loc) => {
-                match self.resolve_variable(name) {
-                    VarLocation::Local(index) => {
-                        self.emit(Instruction::with_u8(Opcode::GetLoc, index));
-                    }
-                    VarLocation::Captured(index) => {
-                        self.emit(Instruction::with_u8(Opcode::GetVarRef, index));
+                // Compile test; continue jumps land here
+                let test_pos = self.writer.pc();
+                self.gen_expr(test)?;
+
+                // Jump back to start while true
+                let if_true_offset = self.writer.pc() + 1;
+                let jump_dist = (loop_start as i32) - (if_true_offset as i32) - 4;
+                self.emit(Instruction::with_label(Opcode::IfTrue, jump_dist));
+
+                // Patch all break and continue jumps
+                let end_pos = self.writer.pc();
+                if let Some(ctx) = self.loop_stack.last() {
+                    for &patch_offset in &ctx.break_jumps {
+                        self.writer.patch_i32(patch_offset, (end_pos as i32) - (patch_offset as i32) - 4);
                     }
-                    VarLocation::Global => {
-                        // Global variable - emit GetGlobal
-                        let atom_id = self.get_or_create_atom(name);
-                        if atom_id <= 255 {
-                            self.emit(Instruction::with_atom8(Opcode::GetGlobal8, atom_id as u8));
-                        } else {
-                            self.emit(Instruction::with_atom16(Opcode::GetGlobal16, atom_id as u16));
-                        }
+                    for &patch_offset in &ctx.continue_jumps {
+                        self.writer.patch_i32(patch_offset, (test_pos as i32) - (patch_offset as i32) - 4);
                     }
                 }
-                Ok(())
-            }
 
-            Expr::This(_) => {
-                self.emit_simple(Opcode::PushThis);
+                self.loop_stack.pop();
                 Ok(())
             }
 
-            Expr::Binary { op, left, right, .. } => {
-                // Compile left operand
-                self.gen_expr(left)?;
+            Stmt::Switch { discriminant, cases, .. } => {
+                // Structure:
+                //   [discriminant]
+                //   Dup; [test 0]; StrictEq; IfFalse next_0; Drop; Goto case_0
+                // next_0:
+                //   ...                   ; one test per non-default case
+                //   Drop
+                //   Goto default_case (or end)
+                // case_0:
+                //   [consequent 0]        ; falls through into the next case
+                //   ...
+                // end:
+                self.gen_expr(discriminant)?;
+
+                let case_labels: Vec<LabelId> = cases.iter().map(|_| self.create_label()).collect();
+                let end_label = self.create_label();
 
-                // Compile right operand
-                self.gen_expr(right)?;
+                for (case, &case_label) in cases.iter().zip(&case_labels) {
+                    let Some(ref test) = case.test else { continue };
+                    let next_label = self.create_label();
+                    self.emit_simple(Opcode::Dup);
+                    self.gen_expr(test)?;
+                    self.emit_simple(Opcode::StrictEq);
+                    self.emit_jump(Opcode::IfFalse, next_label);
+                    self.emit_simple(Opcode::Drop);
+                    self.emit_jump(Opcode::Goto, case_label);
+                    self.mark_label(next_label);
+                }
 
-                // Emit operator
-                let opcode = match op {
-                    BinaryOp::Add => Opcode::Add,
-                    BinaryOp::Sub => Opcode::Sub,
-                    BinaryOp::Mul => Opcode::Mul,
-                    BinaryOp::Div => Opcode::Div,
-                    BinaryOp::Mod => Opcode::Mod,
-                    BinaryOp::Pow => Opcode::Pow,
-                    BinaryOp::Eq => Opcode::Eq,
-                    BinaryOp::NotEq => Opcode::Neq,
-                    BinaryOp::StrictEq => Opcode::StrictEq,
+                // No case matched
+                self.emit_simple(Opcode::Drop);
+                let default_label = cases.iter()
+                    .position(|case| case.test.is_none())
+                    .map_or(end_label, |index| case_labels[index]);
+                self.emit_jump(Opcode::Goto, default_label);
+
+                // The case bodies share one block scope
+                let new_scope = Scope::with_parent(self.scope.clone());
+                let old_scope = core::mem::replace(&mut self.scope, new_scope);
+                self.loop_stack.push(LoopContext { break_label: end_label, continue_label: end_label, break_jumps: Vec::new(), continue_jumps: Vec::new(), is_switch: true });
+
+                for (case, &case_label) in cases.iter().zip(&case_labels) {
+                    self.mark_label(case_label);
+                    for stmt in &case.consequent {
+                        self.gen_stmt(stmt)?;
+                    }
+                }
+
+                self.mark_label(end_label);
+
+                // Patch all break jumps to point here
+                let end_pos = self.writer.pc();
+                if let Some(ctx) = self.loop_stack.last() {
+                    for &patch_offset in &ctx.break_jumps {
+                        self.writer.patch_i32(patch_offset, (end_pos as i32) - (patch_offset as i32) - 4);
+                    }
+                }
+
+                self.loop_stack.pop();
+                self.scope = old_scope;
+                Ok(())
+            }
+
+            Stmt::Empty { .. } => Ok(()),
+
+            Stmt::Labeled { body, .. } => {
+                // For now, just generate code for the body
+                // TODO: Support labeled break/continue targeting specific labels
+                self.gen_stmt(body)
+            }
+        }
+    }
+
+    /// Emits the test of a short-circuit operator on the value on top of the stack
+    ///
+    /// Jumps to `label` keeping the value when it decides the result,
+    /// otherwise drops it.
+    fn gen_short_circuit(&mut self, op: BinaryOp, label: LabelId) {
+        let opcode = match op {
+            BinaryOp::LogicalAnd => Opcode::LAnd,
+            BinaryOp::LogicalOr => Opcode::LOr,
+            _ => Opcode::Nullish,
+        };
+        self.emit_jump(opcode, label);
+    }
+
+    /// Returns the exit label of the enclosing optional chain
+    fn optional_chain_label(&self) -> CodeGenResult<LabelId> {
+        self.optional_chain
+            .ok_or_else(|| CodeGenError::new("Optional link outside an optional chain".into()))
+    }
+
+    /// Leaves the optional chain if the value on top of the stack is null or undefined
+    fn gen_optional_check(&mut self) -> CodeGenResult<()> {
+        let chain_label = self.optional_chain_label()?;
+        self.emit_simple(Opcode::Dup);
+        self.emit_simple(Opcode::Null);
+        self.emit_simple(Opcode::Eq);
+        self.emit_jump(Opcode::IfTrue, chain_label);
+        Ok(())
+    }
+
+    /// Pushes an array literal index
+    fn emit_array_index(&mut self, i: usize) {
+        if i <= 7 {
+            let opcode = match i {
+                0 => Opcode::Push0,
+                1 => Opcode::Push1,
+                2 => Opcode::Push2,
+                3 => Opcode::Push3,
+                4 => Opcode::Push4,
+                5 => Opcode::Push5,
+                6 => Opcode::Push6,
+                7 => Opcode::Push7,
+                _ => unreachable!(),
+            };
+            self.emit_simple(opcode);
+        } else if i <= 127 {
+            self.emit(Instruction::with_i8(Opcode::PushI8, i as i8));
+        } else {
+            self.emit(Instruction::with_i16(Opcode::PushI16, i as i16));
+        }
+    }
+
+    /// Generates `delete <arg>`
+    ///
+    /// For a property reference the object and the key are left for the
+    /// Delete instruction, which removes the property and pushes whether it's
+    /// gone. Variables can't be deleted, so `delete name` is false without
+    /// looking the name up; any other operand is evaluated for its side
+    /// effects and the result is true.
+    fn gen_delete(&mut self, arg: &Expr) -> CodeGenResult<()> {
+        match arg {
+            Expr::Member { object, property, computed, optional, .. } => {
+                self.gen_expr(object)?;
+                if *optional {
+                    self.gen_optional_check()?;
+                }
+                match (&**property, *computed) {
+                    (property, true) => self.gen_expr(property)?,
+                    (Expr::Identifier(name, _), false) => self.gen_literal(&Literal::String(name.clone()))?,
+                    _ => self.emit_simple(Opcode::Undefined),
+                }
+                self.emit_simple(Opcode::Delete);
+            }
+            Expr::OptionalChain { expr, .. } => {
+                // `delete a?.b` is true when `a` is null or undefined
+                let nullish_label = self.create_label();
+                let end_label = self.create_label();
+
+                let outer_chain = self.optional_chain.replace(nullish_label);
+                let result = self.gen_delete(expr);
+                self.optional_chain = outer_chain;
+                result?;
+
+                self.emit_jump(Opcode::Goto, end_label);
+                self.mark_label(nullish_label);
+                self.emit_simple(Opcode::Drop);
+                self.emit_simple(Opcode::PushTrue);
+                self.mark_label(end_label);
+            }
+            Expr::Identifier(..) => self.emit_simple(Opcode::PushFalse),
+            _ => {
+                self.gen_expr(arg)?;
+                self.emit_simple(Opcode::Drop);
+                self.emit_simple(Opcode::PushTrue);
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates `a &&= b`, `a ||= b` and `a ??= b`
+    ///
+    /// `op` is the matching short-circuit operator. The right side is only
+    /// evaluated, and the target only written, when the current value
+    /// doesn't decide the result.
+    fn gen_logical_assignment(&mut self, op: BinaryOp, left: &Expr, right: &Expr) -> CodeGenResult<()> {
+        let keep_label = self.create_label();
+        let end_label = self.create_label();
+
+        match left {
+            Expr::Identifier(name, _) => {
+                let location = self.resolve_variable(name);
+                let atom_id = self.get_or_create_atom(name);
+                match location {
+                    VarLocation::Local(index) => self.emit(Instruction::with_u8(Opcode::GetLoc, index)),
+                    VarLocation::Captured(index) => self.emit(Instruction::with_u8(Opcode::GetVarRef, index)),
+                    VarLocation::Global if atom_id <= 255 => self.emit(Instruction::with_u8(Opcode::GetGlobal8, atom_id as u8)),
+                    VarLocation::Global => self.emit(Instruction::with_u16(Opcode::GetGlobal16, atom_id)),
+                }
+                self.gen_short_circuit(op, keep_label);
+                self.gen_expr(right)?;
+                match location {
+                    VarLocation::Local(index) => self.emit(Instruction::with_u8(Opcode::SetLoc, index)),
+                    VarLocation::Captured(index) => self.emit(Instruction::with_u8(Opcode::SetVarRef, index)),
+                    VarLocation::Global if atom_id <= 255 => self.emit(Instruction::with_atom8(Opcode::SetGlobal8, atom_id as u8)),
+                    VarLocation::Global => self.emit(Instruction::with_atom16(Opcode::SetGlobal16, atom_id)),
+                }
+                // The current value is the result as is
+                self.mark_label(keep_label);
+            }
+            Expr::Member { object, property, computed: false, .. } => {
+                let Expr::Identifier(name, _) = property.as_ref() else {
+                    return Err(CodeGenError::new("Invalid property in member expression".into()));
+                };
+                let atom_idx = self.get_or_create_atom(name);
+
+                // [obj] -> [obj, current]
+                self.gen_expr(object)?;
+                self.emit_simple(Opcode::Dup);
+                self.emit(Instruction::with_u16(Opcode::GetField, atom_idx));
+                self.gen_short_circuit(op, keep_label);
+
+                // [obj, value] -> [value]
+                self.gen_expr(right)?;
+                self.emit(Instruction::with_u16(Opcode::SetField, atom_idx));
+                self.emit_jump(Opcode::Goto, end_label);
+
+                // [obj, current] -> [current]
+                self.mark_label(keep_label);
+                self.emit_simple(Opcode::Nip);
+            }
+            Expr::Member { object, property, computed: true, .. } => {
+                // [obj, key] -> [obj, key, obj, key] -> [obj, key, current]
+                self.gen_expr(object)?;
+                self.emit_simple(Opcode::Dup);
+                self.gen_expr(property)?;
+                self.emit_simple(Opcode::Dup);
+                self.emit_simple(Opcode::Insert2);
+                self.emit_simple(Opcode::GetArrayEl);
+                self.gen_short_circuit(op, keep_label);
+
+                // [obj, key, value] -> [value, obj, key, value] -> [value]
+                self.gen_expr(right)?;
+                self.emit_simple(Opcode::Dup);
+                self.emit_simple(Opcode::Insert3);
+                self.emit_simple(Opcode::PutArrayEl);
+                self.emit_simple(Opcode::Drop);
+                self.emit_jump(Opcode::Goto, end_label);
+
+                // [obj, key, current] -> [current]
+                self.mark_label(keep_label);
+                self.emit_simple(Opcode::Insert2);
+                self.emit_simple(Opcode::Drop);
+                self.emit_simple(Opcode::Drop);
+            }
+            _ => return Err(CodeGenError::new("Invalid left-hand side in assignment".into())),
+        }
+
+        self.mark_label(end_label);
+        Ok(())
+    }
+
+    /// Generates `a = b` and the arithmetic compound assignments like `a += b`
+    ///
+    /// Compound forms load the current value before evaluating the right
+    /// side. A member target's object and key are evaluated once, and the
+    /// assigned value is left on the stack as the expression's result.
+    fn gen_assignment(&mut self, op: AssignOp, left: &Expr, right: &Expr) -> CodeGenResult<()> {
+        let combine = match op {
+            AssignOp::Assign => None,
+            AssignOp::AddAssign => Some(Opcode::Add),
+            AssignOp::SubAssign => Some(Opcode::Sub),
+            AssignOp::MulAssign => Some(Opcode::Mul),
+            AssignOp::DivAssign => Some(Opcode::Div),
+            AssignOp::ModAssign => Some(Opcode::Mod),
+            AssignOp::PowAssign => Some(Opcode::Pow),
+            AssignOp::LeftShiftAssign => Some(Opcode::Shl),
+            AssignOp::RightShiftAssign => Some(Opcode::Sar),
+            AssignOp::UnsignedRightShiftAssign => Some(Opcode::Shr),
+            AssignOp::BitAndAssign => Some(Opcode::And),
+            AssignOp::BitOrAssign => Some(Opcode::Or),
+            AssignOp::BitXorAssign => Some(Opcode::Xor),
+            AssignOp::LogicalAndAssign | AssignOp::LogicalOrAssign | AssignOp::NullishAssign => {
+                return Err(CodeGenError::new("Logical assignment must short-circuit".into()));
+            }
+        };
+
+        match left {
+            Expr::Identifier(name, _) => {
+                let location = self.resolve_variable(name);
+                let atom_id = self.get_or_create_atom(name);
+                if let Some(opcode) = combine {
+                    match location {
+                        VarLocation::Local(index) => self.emit(Instruction::with_u8(Opcode::GetLoc, index)),
+                        VarLocation::Captured(index) => self.emit(Instruction::with_u8(Opcode::GetVarRef, index)),
+                        VarLocation::Global if atom_id <= 255 => self.emit(Instruction::with_u8(Opcode::GetGlobal8, atom_id as u8)),
+                        VarLocation::Global => self.emit(Instruction::with_u16(Opcode::GetGlobal16, atom_id)),
+                    }
+                    self.gen_expr(right)?;
+                    self.emit_simple(opcode);
+                } else {
+                    self.gen_expr(right)?;
+                }
+                match location {
+                    VarLocation::Local(index) => self.emit(Instruction::with_u8(Opcode::SetLoc, index)),
+                    VarLocation::Captured(index) => self.emit(Instruction::with_u8(Opcode::SetVarRef, index)),
+                    VarLocation::Global if atom_id <= 255 => self.emit(Instruction::with_atom8(Opcode::SetGlobal8, atom_id as u8)),
+                    VarLocation::Global => self.emit(Instruction::with_atom16(Opcode::SetGlobal16, atom_id)),
+                }
+            }
+            Expr::Member { object, property, computed: false, .. } => {
+                let Expr::Identifier(name, _) = property.as_ref() else {
+                    return Err(CodeGenError::new("Invalid property in member expression".into()));
+                };
+                let atom_idx = self.get_or_create_atom(name);
+
+                self.gen_expr(object)?;
+                if let Some(opcode) = combine {
+                    // [obj] -> [obj, current] -> [obj, value]
+                    self.emit_simple(Opcode::Dup);
+                    self.emit(Instruction::with_u16(Opcode::GetField, atom_idx));
+                    self.gen_expr(right)?;
+                    self.emit_simple(opcode);
+                } else {
+                    self.gen_expr(right)?;
+                }
+
+                // [obj, value] -> [value]
+                self.emit(Instruction::with_u16(Opcode::SetField, atom_idx));
+            }
+            Expr::Member { object, property, computed: true, .. } => {
+                self.gen_expr(object)?;
+                if let Some(opcode) = combine {
+                    // [obj, key] -> [obj, key, obj, key] -> [obj, key, value]
+                    self.emit_simple(Opcode::Dup);
+                    self.gen_expr(property)?;
+                    self.emit_simple(Opcode::Dup);
+                    self.emit_simple(Opcode::Insert2);
+                    self.emit_simple(Opcode::GetArrayEl);
+                    self.gen_expr(right)?;
+                    self.emit_simple(opcode);
+                } else {
+                    self.gen_expr(property)?;
+                    self.gen_expr(right)?;
+                }
+
+                // [obj, key, value] -> [value, obj, key, value] -> [value]
+                self.emit_simple(Opcode::Dup);
+                self.emit_simple(Opcode::Insert3);
+                self.emit_simple(Opcode::PutArrayEl);
+                self.emit_simple(Opcode::Drop);
+            }
+            _ => return Err(CodeGenError::new("Invalid left-hand side in assignment".into())),
+        }
+
+        Ok(())
+    }
+
+    /// Generates bytecode for an expression
+    ///
+    /// Instructions emitted after a subexpression, such as the call after
+    /// its arguments, are attributed to the line the expression starts on.
+    fn gen_expr(&mut self, expr: &Expr) -> CodeGenResult<()> {
+        let outer_line = core::mem::replace(&mut self.line, expr.span().start.line);
+        let result = self.gen_expr_inner(expr);
+        self.line = outer_line;
+        result
+    }
+
+    fn gen_expr_inner(&mut self, expr: &Expr) -> CodeGenResult<()> {
+        match expr {
+            Expr::Literal(lit, _) => {
+                self.gen_literal(lit)?;
+                Ok(())
+            }
+
+            Expr::Identifier(name, _loc) => {
+                match self.resolve_variable(name) {
+                    VarLocation::Local(index) => {
+                        self.emit(Instruction::with_u8(Opcode::GetLoc, index));
+                    }
+                    VarLocation::Captured(index) => {
+                        self.emit(Instruction::with_u8(Opcode::GetVarRef, index));
+                    }
+                    VarLocation::Global => {
+                        // Global variable - emit GetGlobal
+                        self.note_name_hint(name);
+                        let atom_id = self.get_or_create_atom(name);
+                        if atom_id <= 255 {
+                            self.emit(Instruction::with_atom8(Opcode::GetGlobal8, atom_id as u8));
+                        } else {
+                            self.emit(Instruction::with_atom16(Opcode::GetGlobal16, atom_id as u16));
+                        }
+                    }
+                }
+                Ok(())
+            }
+
+            Expr::This(_) => {
+                self.emit_simple(Opcode::PushThis);
+                Ok(())
+            }
+
+            Expr::Binary { op: op @ (BinaryOp::LogicalAnd | BinaryOp::LogicalOr | BinaryOp::NullishCoalescing), left, right, .. } => {
+                // Keep the left value as the result unless it says to
+                // evaluate the right one:
+                //   <left>; LAnd/LOr/Nullish end; <right>; end:
+                self.gen_expr(left)?;
+                let end_label = self.create_label();
+                self.gen_short_circuit(*op, end_label);
+                self.gen_expr(right)?;
+                self.mark_label(end_label);
+
+                Ok(())
+            }
+
+            Expr::Binary { op, left, right, .. } => {
+                // Compile left operand
+                self.gen_expr(left)?;
+
+                // Compile right operand
+                self.gen_expr(right)?;
+
+                // Emit operator
+                let opcode = match op {
+                    BinaryOp::Add => Opcode::Add,
+                    BinaryOp::Sub => Opcode::Sub,
+                    BinaryOp::Mul => Opcode::Mul,
+                    BinaryOp::Div => Opcode::Div,
+                    BinaryOp::Mod => Opcode::Mod,
+                    BinaryOp::Pow => Opcode::Pow,
+                    BinaryOp::Eq => Opcode::Eq,
+                    BinaryOp::NotEq => Opcode::Neq,
+                    BinaryOp::StrictEq => Opcode::StrictEq,
                     BinaryOp::StrictNotEq => Opcode::StrictNeq,
                     BinaryOp::Lt => Opcode::Lt,
                     BinaryOp::LtEq => Opcode::Lte,
@@ -1398,8 +2235,7 @@ impl CodeGenerator {
                     BinaryOp::In => Opcode::In,
                     BinaryOp::InstanceOf => Opcode::Instanceof,
                     BinaryOp::LogicalAnd | BinaryOp::LogicalOr | BinaryOp::NullishCoalescing => {
-                        // These require short-circuit evaluation - handled separately
-                        return Ok(());
+                        unreachable!("short-circuit operators are generated above")
                     }
                 };
 
@@ -1407,8 +2243,19 @@ impl CodeGenerator {
                 Ok(())
             }
 
+            Expr::Unary { op: UnaryOp::Delete, arg, .. } => self.gen_delete(arg),
+
             Expr::Unary { op, arg, .. } => {
-                self.gen_expr(arg)?;
+                // `typeof undeclared` is "undefined" rather than a ReferenceError
+                match (op, arg.as_ref()) {
+                    (UnaryOp::TypeOf, Expr::Identifier(name, _))
+                        if matches!(self.resolve_variable(name), VarLocation::Global) =>
+                    {
+                        let atom_id = self.get_or_create_atom(name);
+                        self.emit(Instruction::with_atom16(Opcode::GetGlobalUndef, atom_id as u16));
+                    }
+                    _ => self.gen_expr(arg)?,
+                }
 
                 let opcode = match op {
                     UnaryOp::Plus => Opcode::Plus,
@@ -1417,7 +2264,7 @@ impl CodeGenerator {
                     UnaryOp::BitwiseNot => Opcode::Not,
                     UnaryOp::TypeOf => Opcode::TypeOf,
                     UnaryOp::Void => Opcode::Void,
-                    UnaryOp::Delete => Opcode::Delete,
+                    UnaryOp::Delete => unreachable!("delete is generated by gen_delete"),
                 };
 
                 self.emit_simple(opcode);
@@ -1430,10 +2277,13 @@ impl CodeGenerator {
                 // 2. Compute the new value (old +/- 1)
                 // 3. Store the new value back
                 // 4. Leave either old (postfix) or new (prefix) on stack
+                //
+                // Inc and Dec convert to a number rather than concatenating
+                // like Add, and postfix leaves the old value as a number
 
                 let add_opcode = match op {
-                    UpdateOp::Inc => Opcode::Add,
-                    UpdateOp::Dec => Opcode::Sub,
+                    UpdateOp::Inc => Opcode::Inc,
+                    UpdateOp::Dec => Opcode::Dec,
                 };
 
                 match arg.as_ref() {
@@ -1442,16 +2292,15 @@ impl CodeGenerator {
                             VarLocation::Local(index) => {
                                 // Local variable
                                 if *prefix {
-                                    // ++i or --i: get, add/sub 1, store (leave new value)
+                                    // ++i or --i: get, inc/dec, store (leave new value)
                                     self.emit(Instruction::with_u8(Opcode::GetLoc, index));
-                                    self.emit_simple(Opcode::Push1);
                                     self.emit_simple(add_opcode);
                                     self.emit(Instruction::with_u8(Opcode::SetLoc, index));
                                 } else {
-                                    // i++ or i--: get, dup, add/sub 1, store (leave old value)
+                                    // i++ or i--: get, to number, dup, inc/dec, store (leave old value)
                                     self.emit(Instruction::with_u8(Opcode::GetLoc, index));
+                                    self.emit_simple(Opcode::Plus);
                                     self.emit_simple(Opcode::Dup);
-                                    self.emit_simple(Opcode::Push1);
                                     self.emit_simple(add_opcode);
                                     self.emit(Instruction::with_u8(Opcode::PutLoc, index));
                                 }
@@ -1459,16 +2308,15 @@ impl CodeGenerator {
                             VarLocation::Captured(index) => {
                                 // Captured variable
                                 if *prefix {
-                                    // ++i or --i: get, add/sub 1, store (leave new value)
+                                    // ++i or --i: get, inc/dec, store (leave new value)
                                     self.emit(Instruction::with_u8(Opcode::GetVarRef, index));
-                                    self.emit_simple(Opcode::Push1);
                                     self.emit_simple(add_opcode);
                                     self.emit(Instruction::with_u8(Opcode::SetVarRef, index));
                                 } else {
-                                    // i++ or i--: get, dup, add/sub 1, store (leave old value)
+                                    // i++ or i--: get, to number, dup, inc/dec, store (leave old value)
                                     self.emit(Instruction::with_u8(Opcode::GetVarRef, index));
+                                    self.emit_simple(Opcode::Plus);
                                     self.emit_simple(Opcode::Dup);
-                                    self.emit_simple(Opcode::Push1);
                                     self.emit_simple(add_opcode);
                                     self.emit(Instruction::with_u8(Opcode::PutVarRef, index));
                                 }
@@ -1479,13 +2327,12 @@ impl CodeGenerator {
                                 let get_op = if atom_id <= 255 { Opcode::GetGlobal8 } else { Opcode::GetGlobal16 };
 
                                 if *prefix {
-                                    // ++x or --x (global): get, add/sub 1, store (leave new value)
+                                    // ++x or --x (global): get, inc/dec, store (leave new value)
                                     if atom_id <= 255 {
                                         self.emit(Instruction::with_u8(get_op, atom_id as u8));
                                     } else {
                                         self.emit(Instruction::with_u16(get_op, atom_id as u16));
                                     }
-                                    self.emit_simple(Opcode::Push1);
                                     self.emit_simple(add_opcode);
                                     // SetGlobal leaves value on stack
                                     if atom_id <= 255 {
@@ -1494,14 +2341,14 @@ impl CodeGenerator {
                                         self.emit(Instruction::with_u16(Opcode::SetGlobal16, atom_id as u16));
                                     }
                                 } else {
-                                    // x++ or x-- (global): get, dup, add/sub 1, store (leave old value)
+                                    // x++ or x-- (global): get, to number, dup, inc/dec, store (leave old value)
                                     if atom_id <= 255 {
                                         self.emit(Instruction::with_u8(get_op, atom_id as u8));
                                     } else {
                                         self.emit(Instruction::with_u16(get_op, atom_id as u16));
                                     }
+                                    self.emit_simple(Opcode::Plus);
                                     self.emit_simple(Opcode::Dup);
-                                    self.emit_simple(Opcode::Push1);
                                     self.emit_simple(add_opcode);
                                     // PutGlobal pops the value
                                     if atom_id <= 255 {
@@ -1529,170 +2376,45 @@ impl CodeGenerator {
                 Ok(())
             }
 
-            Expr::Assignment { op, left, right, .. } => {
-                // Handle compound assignment: need to load current value first
-                if !matches!(op, AssignOp::Assign) {
-                    // Load current value for compound assignment
-                    match left.as_ref() {
-                        Expr::Identifier(name, _) => {
-                            match self.resolve_variable(name) {
-                                VarLocation::Local(index) => {
-                                    self.emit(Instruction::with_u8(Opcode::GetLoc, index));
-                                }
-                                VarLocation::Captured(index) => {
-                                    self.emit(Instruction::with_u8(Opcode::GetVarRef, index));
-                                }
-                                VarLocation::Global => {
-                                    let atom_id = self.get_or_create_atom(name);
-                                    if atom_id <= 255 {
-                                        self.emit(Instruction::with_u8(Opcode::GetGlobal8, atom_id as u8));
-                                    } else {
-                                        self.emit(Instruction::with_u16(Opcode::GetGlobal16, atom_id as u16));
-                                    }
-                                }
-                            }
-                        }
-                        Expr::Member { .. } => {
-                            // For compound property assignment like obj.x += 1
-                            // This is complex - for now, fall back to simple approach
-                            // TODO: Properly implement compound member assignment
-                            // Just generate the member access expression
-                            self.gen_expr(left)?;
-                        }
-                        _ => {}
-                    }
-                }
-
-                // Compile right side
-                self.gen_expr(right)?;
-
-                // Apply binary operation for compound assignment
-                if !matches!(op, AssignOp::Assign) {
-                    let bin_op = match op {
-                        AssignOp::AddAssign => Opcode::Add,
-                        AssignOp::SubAssign => Opcode::Sub,
-                        AssignOp::MulAssign => Opcode::Mul,
-                        AssignOp::DivAssign => Opcode::Div,
-                        AssignOp::ModAssign => Opcode::Mod,
-                        AssignOp::LeftShiftAssign => Opcode::Shl,
-                        AssignOp::RightShiftAssign => Opcode::Sar,
-                        AssignOp::UnsignedRightShiftAssign => Opcode::Shr,
-                        AssignOp::BitAndAssign => Opcode::And,
-                        AssignOp::BitOrAssign => Opcode::Or,
-                        AssignOp::BitXorAssign => Opcode::Xor,
-                        AssignOp::Assign => unreachable!(),
-                    };
-                    self.emit_simple(bin_op);
-                }
-
-                // Handle assignment target
-                match left.as_ref() {
-                    Expr::Identifier(name, _) => {
-                        match self.resolve_variable(name) {
-                            VarLocation::Local(index) => {
-                                self.emit(Instruction::with_u8(Opcode::SetLoc, index));
-                            }
-                            VarLocation::Captured(index) => {
-                                self.emit(Instruction::with_u8(Opcode::SetVarRef, index));
-                            }
-                            VarLocation::Global => {
-                                let atom_id = self.get_or_create_atom(name);
-                                if atom_id <= 255 {
-                                    self.emit(Instruction::with_atom8(Opcode::SetGlobal8, atom_id as u8));
-                                } else {
-                                    self.emit(Instruction::with_atom16(Opcode::SetGlobal16, atom_id as u16));
-                                }
-                            }
-                        }
-                    }
-                    Expr::Member { object, property, computed, .. } => {
-                        // For obj.prop = value or obj[expr] = value
-                        // Stack currently has: [..., value]
-                        // We need: [..., obj, value] then SetField
-
-                        // Push the object
-                        self.gen_expr(object)?;
-                        // Stack: [..., value, obj]
-
-                        // Swap so we have [obj, value]
-                        self.emit_simple(Opcode::Swap);
-                        // Stack: [..., obj, value]
-
-                        if *computed {
-                            // obj[expr] = value - computed property access
-                            self.gen_expr(property)?;
-                            // Stack: [..., obj, value, key]
-                            // Need to reorder to [..., obj, key, value] then use SetArrayEl
-                            self.emit_simple(Opcode::Swap);
-                            // Stack: [..., obj, key, value]
-                            // TODO: Implement SetArrayEl properly
-                            // For now emit PutArrayEl (doesn't return value) and push value
-                            self.emit_simple(Opcode::PutArrayEl);
-                            // PutArrayEl pops value, key, but we need to push something back
-                            // This is a simplification - would need a proper SetArrayEl
-                        } else {
-                            // obj.prop = value - dot notation
-                            if let Expr::Identifier(name, _) = property.as_ref() {
-                                let atom_idx = self.get_or_create_atom(name);
-                                // Use SetField (u16) which pushes value back
-                                self.emit(Instruction::with_u16(Opcode::SetField, atom_idx));
-                            } else {
-                                return Err(CodeGenError::new("Invalid property in member expression".into()));
-                            }
-                        }
-                    }
-                    _ => {
-                        // Other patterns not yet supported
-                        self.emit_simple(Opcode::Drop);
-                    }
-                }
-
-                Ok(())
+            Expr::Assignment { op: op @ (AssignOp::LogicalAndAssign | AssignOp::LogicalOrAssign | AssignOp::NullishAssign), left, right, .. } => {
+                let op = match op {
+                    AssignOp::LogicalAndAssign => BinaryOp::LogicalAnd,
+                    AssignOp::LogicalOrAssign => BinaryOp::LogicalOr,
+                    _ => BinaryOp::NullishCoalescing,
+                };
+                self.gen_logical_assignment(op, left, right)
             }
 
+            Expr::Assignment { op, left, right, .. } => self.gen_assignment(*op, left, right),
+
             Expr::Conditional { test, consequent, alternate, .. } => {
-                // Compile test
                 self.gen_expr(test)?;
 
-                // Create labels
                 let else_label = self.create_label();
                 let end_label = self.create_label();
-
-                // Jump to else if false
-                let if_false_offset = self.writer.pc() + 1;
-                self.emit(Instruction::with_label(Opcode::IfFalse, 0)); // Will patch
-
-                // Compile consequent
+                self.emit_jump(Opcode::IfFalse, else_label);
                 self.gen_expr(consequent)?;
-
-                // Jump to end
-                let goto_offset = self.writer.pc() + 1;
-                self.emit(Instruction::with_label(Opcode::Goto, 0)); // Will patch
-
-                // Patch else jump
-                let else_pos = self.writer.pc();
-                self.writer.patch_i32(if_false_offset, (else_pos as i32) - (if_false_offset as i32) - 4);
-
-                // Compile alternate
+                self.emit_jump(Opcode::Goto, end_label);
+                self.mark_label(else_label);
                 self.gen_expr(alternate)?;
-
-                // Patch end jump
-                let end_pos = self.writer.pc();
-                self.writer.patch_i32(goto_offset, (end_pos as i32) - (goto_offset as i32) - 4);
+                self.mark_label(end_label);
 
                 Ok(())
             }
 
-            Expr::Call { callee, args, .. } => {
+            Expr::Call { callee, args, optional, .. } => {
                 // Check if it's a method call (callee is a member expression)
                 let is_method_call = matches!(**callee, Expr::Member { .. });
 
                 if is_method_call {
                     // For method calls: Math.abs(-5)
                     // We need to emit: obj, func, args... then CallMethod
-                    if let Expr::Member { object, property, computed, .. } = &**callee {
+                    if let Expr::Member { object, property, computed, optional: optional_member, .. } = &**callee {
                         // Emit object (for 'this' binding)
                         self.gen_expr(object)?;
+                        if *optional_member {
+                            self.gen_optional_check()?;
+                        }
 
                         // Duplicate object on stack for property access
                         self.emit_simple(Opcode::Dup);
@@ -1715,6 +2437,19 @@ impl CodeGenerator {
                             }
                         }
 
+                        // obj.method?.(): leave only the object for the chain to drop
+                        if *optional {
+                            let chain_label = self.optional_chain_label()?;
+                            let call_label = self.create_label();
+                            self.emit_simple(Opcode::Dup);
+                            self.emit_simple(Opcode::Null);
+                            self.emit_simple(Opcode::Eq);
+                            self.emit_jump(Opcode::IfFalse, call_label);
+                            self.emit_simple(Opcode::Drop);
+                            self.emit_jump(Opcode::Goto, chain_label);
+                            self.mark_label(call_label);
+                        }
+
                         // Compile arguments
                         for arg in args {
                             self.gen_expr(arg)?;
@@ -1728,6 +2463,9 @@ impl CodeGenerator {
                     // Regular function call
                     // Compile callee
                     self.gen_expr(callee)?;
+                    if *optional {
+                        self.gen_optional_check()?;
+                    }
 
                     // Compile arguments
                     for arg in args {
@@ -1742,9 +2480,12 @@ impl CodeGenerator {
                 Ok(())
             }
 
-            Expr::Member { object, property, computed, .. } => {
+            Expr::Member { object, property, computed, optional, .. } => {
                 // Compile object
                 self.gen_expr(object)?;
+                if *optional {
+                    self.gen_optional_check()?;
+                }
 
                 if *computed {
                     // Compile property expression
@@ -1767,6 +2508,27 @@ impl CodeGenerator {
                 Ok(())
             }
 
+            Expr::OptionalChain { expr, .. } => {
+                // Each `?.` link jumps to nullish_label with just the null or
+                // undefined value left on the stack:
+                //   <chain>; Goto end; nullish: Drop; Undefined; end:
+                let nullish_label = self.create_label();
+                let end_label = self.create_label();
+
+                let outer_chain = self.optional_chain.replace(nullish_label);
+                let result = self.gen_expr(expr);
+                self.optional_chain = outer_chain;
+                result?;
+
+                self.emit_jump(Opcode::Goto, end_label);
+                self.mark_label(nullish_label);
+                self.emit_simple(Opcode::Drop);
+                self.emit_simple(Opcode::Undefined);
+                self.mark_label(end_label);
+
+                Ok(())
+            }
+
             Expr::Array { elements, .. } => {
                 // Create empty array object
                 self.emit(Instruction::with_u8(Opcode::Array, 0));
@@ -1776,36 +2538,14 @@ impl CodeGenerator {
                 // 2. Push the index
                 // 3. Push the element value
                 // 4. Call PutArrayEl to store it
+                // Holes are skipped, so they stay missing rather than undefined
                 for (i, elem_opt) in elements.iter().enumerate() {
+                    let Some(elem) = elem_opt else { continue };
+
                     // Duplicate array ref
                     self.emit_simple(Opcode::Dup);
-
-                    // Push index
-                    if i <= 7 {
-                        let opcode = match i {
-                            0 => Opcode::Push0,
-                            1 => Opcode::Push1,
-                            2 => Opcode::Push2,
-                            3 => Opcode::Push3,
-                            4 => Opcode::Push4,
-                            5 => Opcode::Push5,
-                            6 => Opcode::Push6,
-                            7 => Opcode::Push7,
-                            _ => unreachable!(),
-                        };
-                        self.emit_simple(opcode);
-                    } else if i <= 127 {
-                        self.emit(Instruction::with_i8(Opcode::PushI8, i as i8));
-                    } else {
-                        self.emit(Instruction::with_i16(Opcode::PushI16, i as i16));
-                    }
-
-                    // Push element value (or undefined for holes)
-                    if let Some(elem) = elem_opt {
-                        self.gen_expr(elem)?;
-                    } else {
-                        self.emit_simple(Opcode::Undefined);
-                    }
+                    self.emit_array_index(i);
+                    self.gen_expr(elem)?;
 
                     // Store: [arr, arr_dup, index, value] -> [arr, arr_dup]
                     // PutArrayEl peeks obj (doesn't pop), so we need to drop the dup'd copy
@@ -1813,6 +2553,14 @@ impl CodeGenerator {
                     self.emit_simple(Opcode::Drop); // Remove the dup'd array copy
                 }
 
+                // Trailing holes still count towards the length: `[1, ,].length` is 2
+                if matches!(elements.last(), Some(None)) {
+                    self.emit_simple(Opcode::Dup);
+                    self.emit_array_index(elements.len());
+                    let atom_id = self.get_or_create_atom("length");
+                    self.emit(Instruction::with_u16(Opcode::PutField, atom_id));
+                }
+
                 Ok(())
             }
 
@@ -1825,42 +2573,60 @@ impl CodeGenerator {
                     // Duplicate object for property access
                     self.emit_simple(Opcode::Dup);
 
+                    if let crate::compiler::ast::PropertyKey::Computed(key) = &prop.key {
+                        if prop.kind != crate::compiler::ast::PropertyKind::Init {
+                            return Err(CodeGenError::with_location(
+                                "Computed getter and setter names are not supported".into(),
+                                prop.loc.start,
+                            ));
+                        }
+                        // The key is evaluated before the value, as in source order
+                        // Stack: [obj, obj, key, value] -> [obj, obj] -> [obj]
+                        self.gen_expr(key)?;
+                        self.gen_expr(&prop.value)?;
+                        self.emit_simple(Opcode::PutArrayEl);
+                        self.emit_simple(Opcode::Drop);
+                        continue;
+                    }
+
                     // Compile the property value (getter/setter function or regular value)
                     self.gen_expr(&prop.value)?;
 
+                    if prop.kind == crate::compiler::ast::PropertyKind::Spread {
+                        // Stack: [obj, obj, source] -> [obj] (copies the source's properties)
+                        self.emit(Instruction::with_u8(Opcode::CopyDataProperties, 0));
+                        continue;
+                    }
+
                     // Get the property name as an atom
                     let atom_idx = match &prop.key {
                         crate::compiler::ast::PropertyKey::Identifier(name) => {
                             self.get_or_create_atom(name)
                         }
                         crate::compiler::ast::PropertyKey::Literal(lit) => {
+                            // Number keys use their JS string form: `{1.0: x}` is key "1"
                             let name = match lit {
                                 crate::compiler::ast::Literal::String(s) => s.clone(),
-                                crate::compiler::ast::Literal::Number(n) => format!("{}", n),
+                                crate::compiler::ast::Literal::Number(n) => crate::util::format_number(*n),
                                 _ => "".to_string(),
                             };
                             self.get_or_create_atom(&name)
                         }
-                        crate::compiler::ast::PropertyKey::Computed(_) => {
-                            // For computed properties, skip for now
-                            self.emit_simple(Opcode::Drop); // drop value
-                            self.emit_simple(Opcode::Drop); // drop dup'd obj
-                            continue;
-                        }
+                        crate::compiler::ast::PropertyKey::Computed(_) => unreachable!("computed keys are handled above"),
                     };
 
                     // Emit the appropriate opcode based on property kind
                     match prop.kind {
                         crate::compiler::ast::PropertyKind::Get => {
-                            // Stack: [obj, getter] -> [obj] (defines getter)
+                            // Stack: [obj, obj, getter] -> [obj] (defines getter)
                             self.emit(Instruction::with_u16(Opcode::DefineGetter, atom_idx));
                         }
                         crate::compiler::ast::PropertyKind::Set => {
-                            // Stack: [obj, setter] -> [obj] (defines setter)
+                            // Stack: [obj, obj, setter] -> [obj] (defines setter)
                             self.emit(Instruction::with_u16(Opcode::DefineSetter, atom_idx));
                         }
-                        crate::compiler::ast::PropertyKind::Init => {
-                            // Stack: [obj, value] -> [obj] (sets property)
+                        crate::compiler::ast::PropertyKind::Init | crate::compiler::ast::PropertyKind::Spread => {
+                            // Stack: [obj, obj, value] -> [obj] (sets property)
                             if atom_idx < 256 {
                                 self.emit(Instruction::with_atom8(Opcode::PutField8, atom_idx as u8));
                             } else {
@@ -1873,6 +2639,21 @@ impl CodeGenerator {
                 Ok(())
             }
 
+            Expr::Template { quasis, exprs, .. } => {
+                // Lowered to string concatenation; the leading chunk, even
+                // when empty, makes every Add a string one
+                self.gen_literal(&Literal::String(quasis[0].clone()))?;
+                for (expr, quasi) in exprs.iter().zip(&quasis[1..]) {
+                    self.gen_expr(expr)?;
+                    self.emit_simple(Opcode::Add);
+                    if !quasi.is_empty() {
+                        self.gen_literal(&Literal::String(quasi.clone()))?;
+                        self.emit_simple(Opcode::Add);
+                    }
+                }
+                Ok(())
+            }
+
             Expr::Sequence { exprs, .. } => {
                 for (i, expr) in exprs.iter().enumerate() {
                     self.gen_expr(expr)?;
@@ -1891,6 +2672,7 @@ impl CodeGenerator {
                     self.compile_function_body_with_name(name.as_deref(), params, body)?;
                 let param_count = params.len() as u8;
                 let has_captures = !captured_vars.is_empty() || self_name_slot.is_some();
+                let name_atom = name.as_deref().map(|name| self.get_or_create_atom(name));
 
                 // Add to function table
                 let func_index = self.function_bytecodes.len() as u16;
@@ -1900,6 +2682,8 @@ impl CodeGenerator {
                     local_count,
                     captured_vars: captured_vars.clone(),
                     self_name_slot,
+                    is_arrow: false,
+                    name_atom,
                 });
 
                 if has_captures {
@@ -1947,7 +2731,6 @@ impl CodeGenerator {
                 let (func_bytecode, local_count, captured_vars, _self_name_slot) =
                     self.compile_function_body_with_name(None, params, &body_stmts)?;
                 let param_count = params.len() as u8;
-                let has_captures = !captured_vars.is_empty();
 
                 // Add to function table
                 let func_index = self.function_bytecodes.len() as u16;
@@ -1957,34 +2740,34 @@ impl CodeGenerator {
                     local_count,
                     captured_vars: captured_vars.clone(),
                     self_name_slot: None, // Arrow functions don't have a self-reference name
+                    is_arrow: true,
+                    name_atom: None,
                 });
 
-                if has_captures {
-                    // Emit FClosure which creates a closure with captured variables
-                    self.emit(Instruction::with_u8(Opcode::FClosure, func_index as u8));
-                    self.writer.emit_u8(captured_vars.len() as u8);
-                    for cv in &captured_vars {
-                        let capture_byte = if cv.from_capture {
-                            cv.parent_index | 0x80
-                        } else {
-                            cv.parent_index
-                        };
-                        self.writer.emit_u8(capture_byte);
-                    }
-                } else {
-                    // No captures - emit regular PushFunc
-                    if func_index <= 255 {
-                        self.emit(Instruction::with_u8(Opcode::PushFunc8, func_index as u8));
+                // Always emit FClosure: even without captured variables the
+                // closure has to hold the enclosing `this`
+                self.emit(Instruction::with_u8(Opcode::FClosure, func_index as u8));
+                self.writer.emit_u8(captured_vars.len() as u8);
+                for cv in &captured_vars {
+                    let capture_byte = if cv.from_capture {
+                        cv.parent_index | 0x80
                     } else {
-                        self.emit(Instruction::with_u16(Opcode::PushFunc, func_index));
-                    }
+                        cv.parent_index
+                    };
+                    self.writer.emit_u8(capture_byte);
                 }
                 Ok(())
             }
 
-            Expr::New { .. } => {
-                // Stub for now
-                self.emit_simple(Opcode::Undefined);
+            Expr::New { callee, args, .. } => {
+                // Same stack layout as a regular call: [func, arg1, ..., argN]
+                self.gen_expr(callee)?;
+                for arg in args {
+                    self.gen_expr(arg)?;
+                }
+
+                let argc = args.len() as u8;
+                self.emit(Instruction::with_u8(Opcode::CallConstructor, argc));
                 Ok(())
             }
         }
@@ -1994,8 +2777,10 @@ impl CodeGenerator {
     fn gen_literal(&mut self, lit: &Literal) -> CodeGenResult<()> {
         match lit {
             Literal::Number(n) => {
-                // Try to emit as inline integer
-                if libm::floor(*n) == *n && *n >= -1.0 && *n <= 7.0 {
+                // Try to emit as inline integer; -0 goes to the pool, since
+                // inline integers can't hold it
+                let is_int = libm::floor(*n) == *n && (*n != 0.0 || n.is_sign_positive());
+                if is_int && *n >= -1.0 && *n <= 7.0 {
                     let opcode = match *n as i32 {
                         -1 => Opcode::PushMinus1,
                         0 => Opcode::Push0,
@@ -2009,11 +2794,11 @@ impl CodeGenerator {
                         _ => unreachable!(),
                     };
                     self.emit_simple(opcode);
-                } else if libm::floor(*n) == *n && *n >= i8::MIN as f64 && *n <= i8::MAX as f64 {
+                } else if is_int && *n >= i8::MIN as f64 && *n <= i8::MAX as f64 {
                     self.emit(Instruction::with_i8(Opcode::PushI8, *n as i8));
-                } else if libm::floor(*n) == *n && *n >= i16::MIN as f64 && *n <= i16::MAX as f64 {
+                } else if is_int && *n >= i16::MIN as f64 && *n <= i16::MAX as f64 {
                     self.emit(Instruction::with_i16(Opcode::PushI16, *n as i16));
-                } else if libm::floor(*n) == *n && *n >= i32::MIN as f64 && *n <= i32::MAX as f64 {
+                } else if is_int && *n >= i32::MIN as f64 && *n <= i32::MAX as f64 {
                     self.emit(Instruction::with_i32(Opcode::PushI32, *n as i32));
                 } else {
                     // Add to constant pool - store raw f64 bits as JSValue
@@ -2079,6 +2864,24 @@ impl CodeGenerator {
     }
 }
 
+/// Appends a serialized line table with its length
+fn write_line_table(out: &mut Vec<u8>, table: &[u8]) -> CodeGenResult<()> {
+    let len = u16::try_from(table.len())
+        .map_err(|_| CodeGenError::new("Function too long for its line table".into()))?;
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(table);
+    Ok(())
+}
+
+/// Appends the near-name hints with their count
+fn write_name_hints(out: &mut Vec<u8>, hints: &[(u16, u16)]) {
+    out.extend_from_slice(&(hints.len() as u16).to_le_bytes());
+    for &(name, suggestion) in hints {
+        out.extend_from_slice(&name.to_le_bytes());
+        out.extend_from_slice(&suggestion.to_le_bytes());
+    }
+}
+
 impl Default for CodeGenerator {
     fn default() -> Self {
         Self::new()
@@ -2099,6 +2902,25 @@ mod tests {
         assert!(!bytecode.is_empty());
     }
 
+    #[test]
+    fn test_labels() {
+        let mut gen = CodeGenerator::new();
+        let top = gen.create_label();
+        let end = gen.create_label();
+
+        gen.mark_label(top);
+        gen.emit_jump(Opcode::IfFalse, end); // 0..5, forward
+        gen.emit_simple(Opcode::Push1); // 5
+        gen.emit_jump(Opcode::Goto, top); // 6..11, backward
+        assert!(gen.resolve_labels().is_err());
+        gen.mark_label(end); // 11
+        assert!(gen.resolve_labels().is_ok());
+
+        let code = gen.writer.finish();
+        assert_eq!(i32::from_le_bytes([code[1], code[2], code[3], code[4]]), 6);
+        assert_eq!(i32::from_le_bytes([code[7], code[8], code[9], code[10]]), -11);
+    }
+
     #[test]
     fn test_gen_binary_expr() {
         let parser = Parser::new("2 + 3");
@@ -2110,6 +2932,48 @@ mod tests {
         assert!(!bytecode.is_empty());
     }
 
+    /// Returns the stack depth at the end of code made of pushes, drops and
+    /// forward short-circuit jumps, checking every path agrees on it
+    fn stack_depth(code: &[u8]) -> usize {
+        let mut reader = crate::bytecode::BytecodeReader::new(code);
+        let mut jumps = Vec::new();
+        let mut depth = 0usize;
+        while reader.has_more() {
+            let pc = reader.pc();
+            for &(_, jump_depth) in jumps.iter().filter(|&&(target, _)| target == pc) {
+                assert_eq!(jump_depth, depth, "paths disagree at {pc}");
+            }
+            let inst = reader.decode().unwrap();
+            match (inst.opcode, inst.operand) {
+                (Opcode::LAnd | Opcode::LOr | Opcode::Nullish, crate::bytecode::Operand::Label(offset)) => {
+                    // Jumping keeps the left value; falling through drops it
+                    jumps.push((reader.relative_pc(offset).unwrap(), depth));
+                    depth -= 1;
+                }
+                (Opcode::Drop, _) => depth -= 1,
+                _ => depth += 1,
+            }
+        }
+        for &(target, jump_depth) in &jumps {
+            assert!(target <= code.len());
+            if target == code.len() {
+                assert_eq!(jump_depth, depth, "paths disagree at the end");
+            }
+        }
+        depth
+    }
+
+    #[test]
+    fn test_short_circuit_stack_balance() {
+        for source in ["false && 1", "0 || 'default'", "null ?? 5", "(1 && 0) || (null ?? 2)", "1 && 2 && 3 || 4"] {
+            let expr = Parser::new(source).parse_expression().unwrap();
+            let mut gen = CodeGenerator::new();
+            gen.gen_expr(&expr).unwrap();
+            gen.resolve_labels().unwrap();
+            assert_eq!(stack_depth(&gen.writer.finish()), 1, "{source}");
+        }
+    }
+
     #[test]
     fn test_gen_var_decl() {
         let parser = Parser::new("var x = 10;");
@@ -2170,6 +3034,39 @@ mod tests {
         assert!(bytecode.contains(&17), "Should contain PushConst8 opcode");
     }
 
+    #[test]
+    fn test_negated_literals_push_once() {
+        let code = |source: &str| {
+            let expr = crate::compiler::fold::fold_expr(Parser::new(source).parse_expression().unwrap());
+            let mut gen = CodeGenerator::new();
+            gen.gen_expr(&expr).unwrap();
+            (gen.writer.finish(), gen.const_is_f64)
+        };
+        assert_eq!(code("-1").0, [Opcode::PushMinus1 as u8]);
+        assert_eq!(code("-5").0, [Opcode::PushI8 as u8, -5i8 as u8]);
+        assert_eq!(code("-300").0[0], Opcode::PushI16 as u8);
+        assert_eq!(code("-0.5").0[0], Opcode::PushConst8 as u8);
+
+        // -0 can't be an inline integer
+        let (bytecode, consts) = code("-0");
+        assert_eq!(bytecode, [Opcode::PushConst8 as u8, 0]);
+        assert_eq!(consts, [true]);
+        assert_eq!(code("-(-0)").0, [Opcode::Push0 as u8]);
+
+        // Anything but a literal is negated at run time
+        assert_eq!(code("-'3'").0.last(), Some(&(Opcode::Neg as u8)));
+    }
+
+    #[test]
+    fn test_large_integer_literals_use_constant_pool() {
+        // 0xFFFFFFFF doesn't fit an inline integer
+        for (source, consts) in [("0xFFFFFFFF", 1), ("0x3FFFFFFF", 0), ("0b1010 + 1_000", 0)] {
+            let program = Parser::new(source).parse().unwrap();
+            let bytecode = CodeGenerator::new().generate(&program).unwrap();
+            assert_eq!(u16::from_le_bytes([bytecode[0], bytecode[1]]), consts, "{source}");
+        }
+    }
+
     #[test]
     fn test_multiple_expressions_last_one_returned() {
         // Test that only the last expression is returned
@@ -2200,4 +3097,52 @@ mod tests {
         // Should end with ReturnUndef (opcode value 164)
         assert!(bytecode.contains(&164), "Should contain ReturnUndef opcode");
     }
+
+    #[test]
+    fn test_scope_declare() {
+        let mut scope = Scope::new();
+        let a = scope.add_param("a".to_string());
+
+        // var redeclaring a parameter shares its slot
+        assert_eq!(scope.declare("a", VarKind::Var), Some(a));
+        let b = scope.declare("b", VarKind::Var).unwrap();
+        assert_eq!(scope.declare("b", VarKind::Var), Some(b));
+
+        // let/const may not redeclare anything in the same scope
+        assert_eq!(scope.declare("a", VarKind::Let), None);
+        let c = scope.declare("c", VarKind::Let).unwrap();
+        assert_eq!(scope.declare("c", VarKind::Var), None);
+        assert_eq!(scope.declare("c", VarKind::Const), None);
+
+        // A nested var resolves to the enclosing var, a nested let shadows it
+        let mut inner = Scope::with_parent(scope);
+        assert_eq!(inner.declare("b", VarKind::Var), Some(b));
+        assert_ne!(inner.declare("c", VarKind::Let), Some(c));
+    }
+
+    #[test]
+    fn test_scope_duplicate_params() {
+        let mut scope = Scope::new();
+        let first = scope.add_param("a".to_string());
+        let second = scope.add_param("a".to_string());
+
+        // Both slots exist, the last parameter owns the name
+        assert_ne!(first, second);
+        assert_eq!(scope.next_index(), 2);
+        assert_eq!(scope.find_binding("a").map(|(index, _)| index), Some(second));
+    }
+
+    #[test]
+    fn test_duplicate_lexical_declaration_error() {
+        let parser = Parser::new("function f() {\n  let x = 1;\n  let x = 2;\n}");
+        let program = parser.parse().unwrap();
+
+        let err = CodeGenerator::new().generate(&program).unwrap_err();
+        assert_eq!(err.message, "Identifier 'x' has already been declared");
+        assert_eq!(err.location.map(|loc| loc.line), Some(3));
+
+        let parser = Parser::new("function f(a) { const a = 1; }");
+        let program = parser.parse().unwrap();
+        assert!(CodeGenerator::new().generate(&program).is_err());
+    }
 }
@@ -1,34 +1,560 @@
-//! Debug information (pc2line mapping)
+//! Line tables (pc to source line mapping) and bytecode disassembly
 
-/// Debug information for mapping PC to source line/column
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::bytecode::format::{BytecodeReader, Instruction, Operand};
+use crate::bytecode::opcode::Opcode;
+use super::codegen::FUNC_FLAG_ARROW;
+
+/// Maps bytecode offsets to source lines
+///
+/// The code generator records the line of each instruction as it emits it;
+/// only the offsets where the line changes are kept. [`DebugInfo::encode`]
+/// writes each of those as two LEB128 numbers, the distance in bytes from
+/// the previous one and the line change (zigzag encoded, since loops jump
+/// back), so a statement usually costs two bytes of ROM.
+#[derive(Debug, Clone, Default)]
 pub struct DebugInfo {
-    // TODO: Implement fields:
-    // - pc2line: Vec<u8> (compressed line number mapping)
-    _placeholder: u8,
+    /// (pc, line) wherever the line changes, in pc order
+    entries: Vec<(u32, u32)>,
 }
 
 impl DebugInfo {
-    /// Creates new debug info
+    /// Creates an empty table
     pub fn new() -> Self {
-        DebugInfo {
-            _placeholder: 0,
+        DebugInfo { entries: Vec::new() }
+    }
+
+    /// Records that the code from `pc` on comes from `line`
+    ///
+    /// Offsets must not decrease. Line 0 (unknown) is ignored.
+    pub fn add_mapping(&mut self, pc: u32, line: u32) {
+        if line == 0 {
+            return;
+        }
+        match self.entries.last_mut() {
+            Some(last) if last.1 == line => {}
+            // Nothing was emitted for the previous line
+            Some(last) if last.0 == pc => {
+                last.1 = line;
+                let len = self.entries.len();
+                if len >= 2 && self.entries[len - 2].1 == line {
+                    self.entries.pop();
+                }
+            }
+            _ => self.entries.push((pc, line)),
         }
     }
 
-    /// Adds a PC to line mapping
-    pub fn add_mapping(&mut self, _pc: u32, _line: u32, _column: u32) {
-        // TODO: Compress and add mapping
+    /// Gets the line of the instruction at `pc`
+    pub fn get_line(&self, pc: u32) -> Option<u32> {
+        self.entries.iter().take_while(|&&(start, _)| start <= pc).last().map(|&(_, line)| line)
     }
 
-    /// Gets the line number for a given PC
-    pub fn get_line(&self, _pc: u32) -> Option<(u32, u32)> {
-        // TODO: Decompress and binary search
+    /// Serializes the table, with every offset moved up by `base`
+    pub fn encode(&self, base: u32) -> Vec<u8> {
+        let mut out = Vec::new();
+        let (mut pc, mut line) = (0u32, 0u32);
+        for &(start, start_line) in &self.entries {
+            write_uleb(&mut out, start + base - pc);
+            let delta = start_line.wrapping_sub(line) as i32;
+            write_uleb(&mut out, ((delta << 1) ^ (delta >> 31)) as u32);
+            pc = start + base;
+            line = start_line;
+        }
+        out
+    }
+}
+
+/// Looks up the line of the instruction at `pc` in an encoded table
+pub fn line_at(table: &[u8], pc: usize) -> Option<u32> {
+    LineEntries::new(table).take_while(|&(start, _)| start <= pc).last().map(|(_, line)| line)
+}
+
+/// Finds a unit's encoded line table and the offset its code starts at
+pub(crate) fn unit_line_table(unit: &[u8]) -> Option<(&[u8], usize)> {
+    let mut cursor = Cursor { bytes: unit, pos: 0 };
+    for _ in 0..cursor.u16()? {
+        cursor.take(1 + core::mem::size_of::<usize>())?;
+    }
+    for _ in 0..cursor.u16()? {
+        let len = cursor.u16()? as usize;
+        cursor.take(len)?;
+    }
+    for _ in 0..cursor.u16()? {
+        cursor.take(6)?;
+        let len = cursor.u32()? as usize;
+        cursor.take(len)?;
+    }
+    let len = cursor.u16()? as usize;
+    let table = cursor.take(len)?;
+    let hints = cursor.u16()? as usize;
+    cursor.take(hints * 4)?;
+    Some((table, cursor.pos))
+}
+
+fn write_uleb(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Decodes an encoded line table into (pc, line) pairs
+struct LineEntries<'a> {
+    table: &'a [u8],
+    pos: usize,
+    pc: usize,
+    line: u32,
+}
+
+impl<'a> LineEntries<'a> {
+    fn new(table: &'a [u8]) -> Self {
+        LineEntries { table, pos: 0, pc: 0, line: 0 }
+    }
+
+    fn read_uleb(&mut self) -> Option<u32> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = *self.table.get(self.pos)?;
+            self.pos += 1;
+            value |= u32::from(byte & 0x7F).checked_shl(shift)?;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
         None
     }
 }
 
-impl Default for DebugInfo {
-    fn default() -> Self {
-        Self::new()
+impl Iterator for LineEntries<'_> {
+    type Item = (usize, u32);
+
+    fn next(&mut self) -> Option<(usize, u32)> {
+        let pc_delta = self.read_uleb()?;
+        let zigzag = self.read_uleb()?;
+        let line_delta = ((zigzag >> 1) as i32) ^ -((zigzag & 1) as i32);
+        self.pc += pc_delta as usize;
+        self.line = self.line.wrapping_add(line_delta as u32);
+        Some((self.pc, self.line))
+    }
+}
+
+/// Renders a compiled module as a readable listing
+///
+/// Takes the bytes [`CodeGenerator::generate`](super::CodeGenerator::generate)
+/// produces. Each function (the top-level script first, then its nested
+/// functions depth-first) lists its constant pool, atom table, line table and
+/// instructions with their byte offsets; jumps show the absolute offset
+/// they land on. Malformed input doesn't stop the listing: bytes that don't
+/// decode show up as `unknown opcode` or `truncated` lines.
+///
+/// # Example
+///
+/// ```ignore
+/// let bytecode = compile("var x = 1; x + 2.5")?;
+/// println!("{}", disassemble(&bytecode));
+/// ```
+pub fn disassemble(bytecode: &[u8]) -> String {
+    let mut out = String::new();
+    let header = FunctionHeader { index: 0, parent: None, name: None, param_count: 0, local_count: 0, self_name_slot: 0xFF, flags: 0 };
+    disassemble_unit(bytecode, &header, &mut out);
+    out
+}
+
+/// Where a function sits and what its function table entry says about it
+struct FunctionHeader<'a> {
+    index: usize,
+    parent: Option<usize>,
+    name: Option<&'a str>,
+    param_count: u8,
+    local_count: u8,
+    self_name_slot: u8,
+    flags: u8,
+}
+
+/// Appends the listing for one function and, after it, its nested functions
+fn disassemble_unit(unit: &[u8], header: &FunctionHeader, out: &mut String) {
+    let _ = write!(out, "function #{}", header.index);
+    if let Some(name) = header.name {
+        let _ = write!(out, " {name}");
+    }
+    match header.parent {
+        None => out.push_str(" <script>"),
+        Some(parent) => {
+            let _ = write!(out, " (in #{}): params {}, locals {}", parent, header.param_count, header.local_count);
+            if header.self_name_slot != 0xFF {
+                let _ = write!(out, ", self in loc {}", header.self_name_slot);
+            }
+            if header.flags & FUNC_FLAG_ARROW != 0 {
+                out.push_str(", arrow");
+            }
+        }
+    }
+    out.push('\n');
+
+    let mut cursor = Cursor { bytes: unit, pos: 0 };
+    let Some(tables) = read_tables(&mut cursor, header.index, out) else {
+        out.push_str("  truncated header\n\n");
+        return;
+    };
+
+    let code = &unit[cursor.pos..];
+    let _ = writeln!(out, "  code ({} bytes):", code.len());
+    disassemble_code(code, &tables, out);
+    out.push('\n');
+
+    for (child, child_header) in tables.functions {
+        disassemble_unit(child, &child_header, out);
+    }
+}
+
+/// A function's constant, atom and function tables
+struct Tables<'a> {
+    /// (is a raw f64, raw bits)
+    constants: Vec<(bool, u64)>,
+    atoms: Vec<&'a str>,
+    functions: Vec<(&'a [u8], FunctionHeader<'a>)>,
+}
+
+/// Reads and lists the tables of function `index`; None if the header is
+/// cut short
+fn read_tables<'a>(cursor: &mut Cursor<'a>, index: usize, out: &mut String) -> Option<Tables<'a>> {
+    let mut tables = Tables { constants: Vec::new(), atoms: Vec::new(), functions: Vec::new() };
+
+    let const_count = cursor.u16()?;
+    let _ = writeln!(out, "  constants ({}):", const_count);
+    for i in 0..const_count {
+        let is_f64 = cursor.u8()? == 0;
+        let mut bytes = [0u8; 8];
+        let size = core::mem::size_of::<usize>();
+        bytes[..size].copy_from_slice(cursor.take(size)?);
+        let raw = u64::from_le_bytes(bytes);
+        let _ = writeln!(out, "    [{}] {}", i, describe_constant(is_f64, raw));
+        tables.constants.push((is_f64, raw));
+    }
+
+    let atom_count = cursor.u16()?;
+    let _ = writeln!(out, "  atoms ({}):", atom_count);
+    for i in 0..atom_count {
+        let len = cursor.u16()? as usize;
+        let name = core::str::from_utf8(cursor.take(len)?).unwrap_or("<invalid utf-8>");
+        let _ = writeln!(out, "    [{}] {}", i, name);
+        tables.atoms.push(name);
+    }
+
+    // Nested functions are numbered depth-first, in listing order
+    let mut child_index = index + 1;
+    let func_count = cursor.u16()?;
+    let _ = writeln!(out, "  functions ({}):", func_count);
+    for i in 0..func_count {
+        let mut header = FunctionHeader {
+            index: child_index,
+            parent: Some(index),
+            name: None,
+            param_count: cursor.u8()?,
+            local_count: cursor.u8()?,
+            self_name_slot: cursor.u8()?,
+            flags: cursor.u8()?,
+        };
+        header.name = tables.atoms.get(cursor.u16()? as usize).copied();
+        let len = cursor.u32()? as usize;
+        let _ = writeln!(out, "    [{}] function #{}, {} bytes", i, header.index, len);
+        let child = cursor.take(len)?;
+        child_index += count_functions(child);
+        tables.functions.push((child, header));
+    }
+
+    let lines_len = cursor.u16()? as usize;
+    let lines = cursor.take(lines_len)?;
+    let _ = writeln!(out, "  lines ({} bytes):", lines_len);
+    for (pc, line) in LineEntries::new(lines) {
+        let _ = writeln!(out, "    line {} from {:04}", line, pc);
+    }
+
+    let hint_count = cursor.u16()?;
+    if hint_count > 0 {
+        let _ = writeln!(out, "  hints ({}):", hint_count);
+    }
+    for _ in 0..hint_count {
+        let name = |index: u16| tables.atoms.get(index as usize).copied().unwrap_or("?");
+        let (global, local) = (cursor.u16()?, cursor.u16()?);
+        let _ = writeln!(out, "    {} -> {}", name(global), name(local));
+    }
+
+    Some(tables)
+}
+
+/// Counts a function and the functions nested in it
+fn count_functions(unit: &[u8]) -> usize {
+    let mut cursor = Cursor { bytes: unit, pos: 0 };
+    let mut skip_tables = || -> Option<usize> {
+        for _ in 0..cursor.u16()? {
+            cursor.take(1 + core::mem::size_of::<usize>())?;
+        }
+        for _ in 0..cursor.u16()? {
+            let len = cursor.u16()? as usize;
+            cursor.take(len)?;
+        }
+        let mut count = 1;
+        for _ in 0..cursor.u16()? {
+            cursor.take(6)?;
+            let len = cursor.u32()? as usize;
+            count += count_functions(cursor.take(len)?);
+        }
+        Some(count)
+    };
+    skip_tables().unwrap_or(1)
+}
+
+/// Appends one line per instruction in `code`
+fn disassemble_code(code: &[u8], tables: &Tables, out: &mut String) {
+    let mut reader = BytecodeReader::new(code);
+    while reader.has_more() {
+        let offset = reader.pc();
+        let _ = write!(out, "    {:04}  ", offset);
+        let Some(instruction) = reader.decode() else {
+            match Opcode::from_u8(code[offset]) {
+                Some(opcode) => { let _ = writeln!(out, "{} <truncated>", opcode.name()); }
+                None => { let _ = writeln!(out, "unknown opcode 0x{:02x}", code[offset]); }
+            }
+            let _ = reader.set_pc(offset + 1);
+            continue;
+        };
+
+        out.push_str(instruction.opcode.name());
+        write_operand(&instruction, reader.pc(), tables, out);
+
+        if instruction.opcode == Opcode::FClosure {
+            let captured = reader.read_u8().unwrap_or(0);
+            out.push_str(" captures [");
+            for i in 0..captured {
+                let Some(byte) = reader.read_u8() else {
+                    out.push_str("<truncated>");
+                    break;
+                };
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                let source = if byte & 0x80 != 0 { "var_ref" } else { "loc" };
+                let _ = write!(out, "{} {}", source, byte & 0x7F);
+            }
+            out.push(']');
+        }
+        out.push('\n');
+    }
+}
+
+/// Appends an instruction's operand; `next_pc` is where the following
+/// instruction starts, which jump offsets are relative to
+fn write_operand(instruction: &Instruction, next_pc: usize, tables: &Tables, out: &mut String) {
+    let is_function_index = matches!(
+        instruction.opcode,
+        Opcode::PushFunc8 | Opcode::PushFunc | Opcode::FClosure | Opcode::FClosureVarArgs
+    );
+    let _ = match instruction.operand {
+        Operand::None => Ok(()),
+        Operand::U8(index) if is_function_index => write!(out, " function [{}]", index),
+        Operand::U16(index) if is_function_index => write!(out, " function [{}]", index),
+        Operand::U8(v) => write!(out, " {}", v),
+        Operand::I8(v) => write!(out, " {}", v),
+        Operand::U16(v) => write!(out, " {}", v),
+        Operand::I16(v) => write!(out, " {}", v),
+        Operand::U32(v) => write!(out, " {}", v),
+        Operand::I32(v) => write!(out, " {}", v),
+        Operand::Label(delta) => write!(out, " -> {:04}", next_pc as i64 + i64::from(delta)),
+        Operand::Const8(index) if is_function_index => write!(out, " function [{}]", index),
+        Operand::Const16(index) if is_function_index => write!(out, " function [{}]", index),
+        Operand::Const8(index) => write_constant(usize::from(index), tables, out),
+        Operand::Const16(index) => write_constant(usize::from(index), tables, out),
+        Operand::Atom8(index) => write_atom(usize::from(index), tables, out),
+        Operand::Atom16(index) => write_atom(usize::from(index), tables, out),
+    };
+}
+
+fn write_constant(index: usize, tables: &Tables, out: &mut String) -> core::fmt::Result {
+    match tables.constants.get(index) {
+        Some(&(is_f64, raw)) => write!(out, " [{}] ({})", index, describe_constant(is_f64, raw)),
+        None => write!(out, " [{}] (out of range)", index),
+    }
+}
+
+fn write_atom(index: usize, tables: &Tables, out: &mut String) -> core::fmt::Result {
+    match tables.atoms.get(index) {
+        Some(name) => write!(out, " [{}] ({})", index, name),
+        None => write!(out, " [{}] (out of range)", index),
+    }
+}
+
+/// Describes a constant pool entry by its type tag
+fn describe_constant(is_f64: bool, raw: u64) -> String {
+    if is_f64 {
+        alloc::format!("number {}", crate::util::format_number(f64::from_bits(raw)))
+    } else {
+        alloc::format!("value 0x{:x}", raw)
+    }
+}
+
+/// Bounds-checked reads over a function's headers
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.bytes.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let bytes = self.take(2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let bytes = self.take(4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::compile;
+
+    /// Scripts covering the constructs the code generator handles, the
+    /// sources of its own tests included
+    const CORPUS: &[&str] = &[
+        "2 + 3",
+        "var x = 10;",
+        "function add(a, b) { return a + b; }",
+        "3.14; 1 + 1; 2 + 2",
+        "var x = 5; x += 2; x **= 2; x >>>= 1; x ||= 3; x ??= 4; x &&= 0;",
+        "var o = { a: 1, get b() { return 2; }, set b(v) {}, m() { return this.a; }, ['k' + 1]: 3 }; o.a++; --o['a']; delete o.a;",
+        "var a = [1, , 3]; for (var i in a) {} for (var v of a) {} for (let [p, q] of [[1, 2]]) {}",
+        "outer: for (let i = 0; i < 3; i++) { for (;;) { if (i) continue outer; break outer; } }",
+        "var n = 0; do { n++; } while (n < 3); while (n) { n--; }",
+        "switch (typeof x) { case 'number': 1; break; default: 2; }",
+        "try { throw new Error('bad'); } catch (e) { e.message; } finally { 0; }",
+        "function outer(a) { var b = a; return function inner() { return () => a + b; }; } outer(1)()();",
+        "var f = function fact(n) { return n <= 1 ? 1 : n * fact(n - 1); }; f(5);",
+        "var s = `a${1 + 2}b${'c'}`; s?.length; s?.[0]; s?.charAt?.(0);",
+        "var y = !true || null && void 0; y = typeof y === 'undefined' ? -1 : ~2; y = 1 < 2 && 3 >= 4 && 5 != '5';",
+        "var z = 1 << 2 | 3 & 4 ^ 5 >> 1; z = 2 ** -1 % 7 / 3; z = 'a' in {} || [] instanceof Array;",
+        "var big = 123456789012; var r = 0.5; r = -r; var [m, , [k]] = [1, 2, [3]];",
+        "function g() { return this; } new g(); g.call(null); (function () { var self = this; })();",
+    ];
+
+    #[test]
+    fn test_disassemble_recognizes_every_opcode() {
+        for source in CORPUS {
+            let bytecode = compile(source).unwrap_or_else(|e| panic!("{:?} compiling {}", e, source));
+            let listing = disassemble(&bytecode);
+            assert!(!listing.contains("unknown opcode"), "{}\n{}", source, listing);
+            assert!(!listing.contains("truncated"), "{}\n{}", source, listing);
+
+            // Every jump lands on an instruction of its own function
+            for function in listing.split("\n\n") {
+                let instructions: Vec<&str> = function.lines()
+                    .filter(|line| line.starts_with("    ") && line.as_bytes().get(8) == Some(&b' '))
+                    .collect();
+                let offsets: Vec<&str> = instructions.iter().map(|line| line[4..8].trim()).collect();
+                for line in &instructions {
+                    if let Some((_, target)) = line.split_once(" -> ") {
+                        assert!(offsets.contains(&&target[..4]), "{}: jump to {} in\n{}", source, target, function);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_disassemble_listing() {
+        let bytecode = compile("var t = 2.5; if (t) { t = function (a) { return a + t; }; }").unwrap();
+        let listing = disassemble(&bytecode);
+
+        assert!(listing.starts_with("function #0 <script>\n"), "{}", listing);
+        assert!(listing.contains("    [0] number 2.5\n"), "{}", listing);
+        assert!(listing.contains("    [0] t\n"), "{}", listing);
+        assert!(listing.contains("push_const8 [0] (number 2.5)"), "{}", listing);
+        assert!(listing.contains("put_global8 [0] (t)") || listing.contains("set_global8 [0] (t)"), "{}", listing);
+        assert!(listing.contains("function #1 (in #0): params 1, locals 1\n"), "{}", listing);
+
+        // The if_false skips over the assignment, to the script's end
+        let jump = listing.lines().find(|line| line.contains("if_false")).unwrap();
+        let target = jump.rsplit("-> ").next().unwrap();
+        assert!(listing.lines().any(|line| line.starts_with(&alloc::format!("    {}  ", target))), "{}", listing);
+    }
+
+    #[test]
+    fn test_disassemble_malformed() {
+        let mut bytecode = compile("var x = 1; x + 2").unwrap();
+        assert!(disassemble(&bytecode[..1]).contains("truncated header"));
+
+        *bytecode.last_mut().unwrap() = 0xEF;
+        assert!(disassemble(&bytecode).contains("unknown opcode 0xef"));
+    }
+
+    #[test]
+    fn test_line_table_round_trip() {
+        let mut info = DebugInfo::new();
+        info.add_mapping(0, 1);
+        info.add_mapping(3, 1);
+        info.add_mapping(5, 0); // unknown, ignored
+        info.add_mapping(7, 4);
+        info.add_mapping(7, 3); // nothing was emitted for line 4
+        info.add_mapping(200, 2); // loops jump back
+        info.add_mapping(201, 300);
+
+        assert_eq!(info.get_line(6), Some(1));
+        assert_eq!(info.get_line(7), Some(3));
+
+        let table = info.encode(0);
+        assert_eq!(table.len(), 10);
+        for pc in [0, 6, 7, 199, 200, 201, 5000] {
+            assert_eq!(line_at(&table, pc), info.get_line(pc as u32), "pc {}", pc);
+        }
+
+        // A base shifts every offset
+        let shifted = info.encode(2);
+        assert_eq!(line_at(&shifted, 1), None);
+        assert_eq!(line_at(&shifted, 9), Some(3));
+        assert_eq!(line_at(&[], 0), None);
+    }
+
+    #[test]
+    fn test_compiled_line_table() {
+        let source = "var a = 1;\nfunction f(x) {\n  return x.y;\n}\n\nf(a);";
+        let bytecode = compile(source).unwrap();
+        let (table, code_start) = unit_line_table(&bytecode).unwrap();
+
+        let code = &bytecode[code_start..];
+        let listing = disassemble(&bytecode);
+        assert!(listing.contains("    line 6 from "), "{}", listing);
+
+        // The call is the last instruction before the script's return
+        let mut reader = BytecodeReader::new(code);
+        let mut call_pc = None;
+        while let Some(instruction) = reader.peek() {
+            if matches!(instruction.opcode, Opcode::Call | Opcode::CallMethod) {
+                call_pc = Some(reader.pc());
+            }
+            reader.decode();
+        }
+        assert_eq!(line_at(table, call_pc.unwrap()), Some(6));
+        assert_eq!(line_at(table, 0), Some(1));
     }
 }
@@ -0,0 +1,653 @@
+//! Constant folding and dead branch elimination
+//!
+//! Runs between parsing and code generation so code guarded out by a
+//! constant, such as `if (DEBUG) { ... }` with `DEBUG` false, costs no
+//! bytecode:
+//!
+//! - Unary and binary operators on literal operands fold to a literal.
+//! - `&&`, `||`, `??` and `?:` with a constant left side or test keep only
+//!   the side that can run.
+//! - `if` with a constant test keeps only the live branch, and `while` and
+//!   `for` loops whose test is constant false drop their bodies.
+//! - Statements after `return`, `throw`, `break` or `continue` in the same
+//!   list are dropped.
+//! - A top-level `const NAME = <constant>` is substituted into the
+//!   statements after it, provided nothing else in the program binds
+//!   `NAME`.
+//!
+//! Removed code still declares its names: each `var` in it, and each
+//! function declared in a removed branch, becomes a `var NAME;` without an
+//! initializer, as Annex B gives a function in a block that never runs an
+//! `undefined` binding. Function declarations after a `return` or similar
+//! are kept as written, since they hoist in full.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::ast::*;
+use super::lexer::Span;
+use super::visit::{self, Visitor};
+
+/// Folds constants and removes dead code throughout a program
+pub fn fold_program(program: &mut Program) {
+    let mut bindings = Bindings::default();
+    bindings.visit_program(program);
+
+    let mut folder = Folder::default();
+    let body = core::mem::take(&mut program.body);
+    let mut out = Vec::with_capacity(body.len());
+    let mut reachable = true;
+    for stmt in body {
+        folder.push(&mut out, &mut reachable, stmt);
+        if let Some(Stmt::VarDecl { kind: VarKind::Const, declarations, .. }) = out.last().filter(|_| reachable) {
+            for decl in declarations {
+                if let (Some(name), Some(Expr::Literal(lit, _))) = (decl.target.as_identifier(), &decl.init) {
+                    if bindings.count(name) == 1 {
+                        folder.consts.push((name.into(), lit.clone()));
+                    }
+                }
+            }
+        }
+    }
+    program.body = out;
+}
+
+/// Folds a single expression, with no constant bindings in scope
+pub fn fold_expr(expr: Expr) -> Expr {
+    Folder::default().expr(expr)
+}
+
+#[derive(Default)]
+struct Folder {
+    /// Top-level constants substituted for references to their names
+    consts: Vec<(String, Literal)>,
+}
+
+impl Folder {
+    fn stmts(&self, stmts: Vec<Stmt>) -> Vec<Stmt> {
+        let mut out = Vec::with_capacity(stmts.len());
+        let mut reachable = true;
+        for stmt in stmts {
+            self.push(&mut out, &mut reachable, stmt);
+        }
+        out
+    }
+
+    /// Appends the folded form of `stmt`, or only its declarations once an
+    /// earlier statement in the list has jumped away
+    fn push(&self, out: &mut Vec<Stmt>, reachable: &mut bool, stmt: Stmt) {
+        if !*reachable {
+            match stmt {
+                Stmt::FunctionDecl { .. } => out.push(self.stmt(stmt)),
+                _ => out.extend(hoisted(&stmt)),
+            }
+            return;
+        }
+        let stmt = self.stmt(stmt);
+        *reachable = !matches!(
+            stmt,
+            Stmt::Return { .. } | Stmt::Throw { .. } | Stmt::Break { .. } | Stmt::Continue { .. }
+        );
+        out.push(stmt);
+    }
+
+    fn boxed(&self, stmt: Box<Stmt>) -> Box<Stmt> {
+        Box::new(self.stmt(*stmt))
+    }
+
+    fn stmt(&self, stmt: Stmt) -> Stmt {
+        match stmt {
+            Stmt::Expression { expr, loc } => Stmt::Expression { expr: self.expr(expr), loc },
+            Stmt::Block { stmts, loc } => Stmt::Block { stmts: self.stmts(stmts), loc },
+            Stmt::VarDecl { kind, declarations, loc } => {
+                Stmt::VarDecl { kind, declarations: self.declarators(declarations), loc }
+            }
+            Stmt::FunctionDecl { name, params, body, loc } => {
+                Stmt::FunctionDecl { name, params, body: self.stmts(body), loc }
+            }
+            Stmt::If { test, consequent, alternate, loc } => match self.expr(test) {
+                Expr::Literal(lit, _) if is_truthy(&lit) => {
+                    live_and_dead(Some(self.stmt(*consequent)), alternate.as_deref(), loc)
+                }
+                Expr::Literal(..) => live_and_dead(alternate.map(|alt| self.stmt(*alt)), Some(&consequent), loc),
+                test => Stmt::If {
+                    test,
+                    consequent: self.boxed(consequent),
+                    alternate: alternate.map(|alt| self.boxed(alt)),
+                    loc,
+                },
+            },
+            Stmt::While { test, body, loc } => match self.expr(test) {
+                Expr::Literal(lit, _) if !is_truthy(&lit) => live_and_dead(None, Some(&body), loc),
+                test => Stmt::While { test, body: self.boxed(body), loc },
+            },
+            Stmt::DoWhile { body, test, loc } => Stmt::DoWhile { body: self.boxed(body), test: self.expr(test), loc },
+            Stmt::For { init, test, update, body, loc } => {
+                let init = init.map(|init| self.for_init(init));
+                match test.map(|test| self.expr(test)) {
+                    Some(Expr::Literal(lit, _)) if !is_truthy(&lit) => {
+                        // The init still runs, in a block standing in for
+                        // the loop's own scope
+                        let init = init.map(|init| match init {
+                            ForInit::VarDecl { kind, declarations, loc } => Stmt::VarDecl { kind, declarations, loc },
+                            ForInit::Expr(expr) => Stmt::Expression { loc: expr.span(), expr },
+                        });
+                        let stmts: Vec<Stmt> = init.into_iter().chain(hoisted(&body)).collect();
+                        if stmts.is_empty() {
+                            Stmt::Empty { loc }
+                        } else {
+                            Stmt::Block { stmts, loc }
+                        }
+                    }
+                    test => Stmt::For {
+                        init,
+                        test,
+                        update: update.map(|update| self.expr(update)),
+                        body: self.boxed(body),
+                        loc,
+                    },
+                }
+            }
+            Stmt::ForIn { left, right, body, loc } => Stmt::ForIn {
+                left: self.for_init(left),
+                right: self.expr(right),
+                body: self.boxed(body),
+                loc,
+            },
+            Stmt::ForOf { left, right, body, loc } => Stmt::ForOf {
+                left: self.for_init(left),
+                right: self.expr(right),
+                body: self.boxed(body),
+                loc,
+            },
+            Stmt::Return { argument, loc } => Stmt::Return { argument: argument.map(|arg| self.expr(arg)), loc },
+            Stmt::Throw { argument, loc } => Stmt::Throw { argument: self.expr(argument), loc },
+            Stmt::Try { block, handler, finalizer, loc } => Stmt::Try {
+                block: self.stmts(block),
+                handler: handler.map(|handler| CatchClause { body: self.stmts(handler.body), ..handler }),
+                finalizer: finalizer.map(|stmts| self.stmts(stmts)),
+                loc,
+            },
+            Stmt::Switch { discriminant, cases, loc } => Stmt::Switch {
+                discriminant: self.expr(discriminant),
+                cases: cases
+                    .into_iter()
+                    .map(|case| SwitchCase {
+                        test: case.test.map(|test| self.expr(test)),
+                        consequent: self.stmts(case.consequent),
+                        loc: case.loc,
+                    })
+                    .collect(),
+                loc,
+            },
+            Stmt::Labeled { label, body, loc } => Stmt::Labeled { label, body: self.boxed(body), loc },
+            stmt @ (Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Empty { .. }) => stmt,
+        }
+    }
+
+    fn declarators(&self, declarations: Vec<VarDeclarator>) -> Vec<VarDeclarator> {
+        declarations
+            .into_iter()
+            .map(|decl| VarDeclarator { init: decl.init.map(|init| self.expr(init)), ..decl })
+            .collect()
+    }
+
+    fn for_init(&self, init: ForInit) -> ForInit {
+        match init {
+            ForInit::VarDecl { kind, declarations, loc } => {
+                ForInit::VarDecl { kind, declarations: self.declarators(declarations), loc }
+            }
+            ForInit::Expr(expr) => ForInit::Expr(self.expr(expr)),
+        }
+    }
+
+    fn boxed_expr(&self, expr: Expr) -> Box<Expr> {
+        Box::new(self.expr(expr))
+    }
+
+    fn exprs(&self, exprs: Vec<Expr>) -> Vec<Expr> {
+        exprs.into_iter().map(|expr| self.expr(expr)).collect()
+    }
+
+    /// Folds inside an assignment, update or delete target, which itself
+    /// must keep its shape
+    fn target(&self, expr: Expr) -> Box<Expr> {
+        match expr {
+            Expr::Member { object, property, computed, optional, loc } => Box::new(Expr::Member {
+                object: self.boxed_expr(*object),
+                property: if computed { self.boxed_expr(*property) } else { property },
+                computed,
+                optional,
+                loc,
+            }),
+            expr => Box::new(expr),
+        }
+    }
+
+    fn expr(&self, expr: Expr) -> Expr {
+        match expr {
+            Expr::Identifier(name, loc) => match self.consts.iter().find(|(n, _)| *n == name) {
+                Some((_, lit)) => Expr::Literal(lit.clone(), loc),
+                None => Expr::Identifier(name, loc),
+            },
+            Expr::Binary { op, left, right, loc } => {
+                let left = self.expr(*left);
+                if let (Some(keep_left), Expr::Literal(lit, _)) = (short_circuits(op), &left) {
+                    return if keep_left(lit) { left } else { self.expr(*right) };
+                }
+                let right = self.expr(*right);
+                match (&left, &right) {
+                    (Expr::Literal(a, _), Expr::Literal(b, _)) => match fold_binary(op, a, b) {
+                        Some(lit) => Expr::Literal(lit, loc),
+                        None => Expr::Binary { op, left: Box::new(left), right: Box::new(right), loc },
+                    },
+                    _ => Expr::Binary { op, left: Box::new(left), right: Box::new(right), loc },
+                }
+            }
+            Expr::Unary { op: UnaryOp::Delete, arg, prefix, loc } => {
+                Expr::Unary { op: UnaryOp::Delete, arg: self.target(*arg), prefix, loc }
+            }
+            Expr::Unary { op, arg, prefix, loc } => match self.expr(*arg) {
+                Expr::Literal(lit, lit_loc) => match fold_unary(op, &lit) {
+                    Some(lit) => Expr::Literal(lit, loc),
+                    None => Expr::Unary { op, arg: Box::new(Expr::Literal(lit, lit_loc)), prefix, loc },
+                },
+                arg => Expr::Unary { op, arg: Box::new(arg), prefix, loc },
+            },
+            Expr::Update { op, arg, prefix, loc } => Expr::Update { op, arg: self.target(*arg), prefix, loc },
+            Expr::Assignment { op, left, right, loc } => {
+                Expr::Assignment { op, left: self.target(*left), right: self.boxed_expr(*right), loc }
+            }
+            Expr::Conditional { test, consequent, alternate, loc } => match self.expr(*test) {
+                Expr::Literal(lit, _) if is_truthy(&lit) => self.expr(*consequent),
+                Expr::Literal(..) => self.expr(*alternate),
+                test => Expr::Conditional {
+                    test: Box::new(test),
+                    consequent: self.boxed_expr(*consequent),
+                    alternate: self.boxed_expr(*alternate),
+                    loc,
+                },
+            },
+            Expr::Call { callee, args, optional, loc } => {
+                let was_member = is_member(&callee);
+                let mut callee = self.expr(*callee);
+                if !was_member && is_member(&callee) {
+                    // `(true && obj.f)()` calls f without obj as its
+                    // receiver, which `(0, obj.f)()` keeps
+                    let span = callee.span();
+                    callee = Expr::Sequence { exprs: alloc::vec![Expr::Literal(Literal::Number(0.0), span), callee], loc: span };
+                }
+                Expr::Call { callee: Box::new(callee), args: self.exprs(args), optional, loc }
+            }
+            Expr::New { callee, args, loc } => Expr::New { callee: self.boxed_expr(*callee), args: self.exprs(args), loc },
+            Expr::Member { .. } => *self.target(expr),
+            Expr::OptionalChain { expr, loc } => Expr::OptionalChain { expr: self.boxed_expr(*expr), loc },
+            Expr::Template { quasis, exprs, loc } => Expr::Template { quasis, exprs: self.exprs(exprs), loc },
+            Expr::Sequence { exprs, loc } => Expr::Sequence { exprs: self.exprs(exprs), loc },
+            Expr::Array { elements, loc } => Expr::Array {
+                elements: elements.into_iter().map(|element| element.map(|expr| self.expr(expr))).collect(),
+                loc,
+            },
+            Expr::Object { properties, loc } => Expr::Object {
+                properties: properties
+                    .into_iter()
+                    .map(|property| Property {
+                        key: match property.key {
+                            PropertyKey::Computed(key) => PropertyKey::Computed(self.boxed_expr(*key)),
+                            key => key,
+                        },
+                        value: self.expr(property.value),
+                        ..property
+                    })
+                    .collect(),
+                loc,
+            },
+            Expr::Function { name, params, body, loc } => Expr::Function { name, params, body: self.stmts(body), loc },
+            Expr::Arrow { params, body, loc } => Expr::Arrow {
+                params,
+                body: match body {
+                    ArrowBody::Expr(expr) => ArrowBody::Expr(self.boxed_expr(*expr)),
+                    ArrowBody::Block(stmts) => ArrowBody::Block(self.stmts(stmts)),
+                },
+                loc,
+            },
+            expr @ (Expr::Literal(..) | Expr::This(_)) => expr,
+        }
+    }
+}
+
+fn is_member(expr: &Expr) -> bool {
+    matches!(expr, Expr::Member { .. } | Expr::OptionalChain { .. })
+}
+
+/// Returns the live statement, preceded by the declarations of the dead one
+fn live_and_dead(live: Option<Stmt>, dead: Option<&Stmt>, loc: Span) -> Stmt {
+    match (live, dead.and_then(hoisted)) {
+        (Some(live), None) => live,
+        (None, Some(decls)) => decls,
+        (Some(live), Some(decls)) => Stmt::Block { stmts: alloc::vec![decls, live], loc },
+        (None, None) => Stmt::Empty { loc },
+    }
+}
+
+/// Returns `var` declarations, without initializers, of the names that
+/// `stmt` would declare outside itself
+fn hoisted(stmt: &Stmt) -> Option<Stmt> {
+    let mut names = HoistedNames::default();
+    names.visit_stmt(stmt);
+    if names.0.is_empty() {
+        return None;
+    }
+    let loc = stmt.span();
+    let declarations = names
+        .0
+        .into_iter()
+        .map(|(name, span)| VarDeclarator { target: Pattern::Identifier(name, span), init: None, loc: span })
+        .collect();
+    Some(Stmt::VarDecl { kind: VarKind::Var, declarations, loc })
+}
+
+/// Collects `var` names and function declaration names, without entering
+/// functions
+#[derive(Default)]
+struct HoistedNames(Vec<(String, Span)>);
+
+impl HoistedNames {
+    fn add(&mut self, name: &str, span: Span) {
+        if !self.0.iter().any(|(n, _)| n == name) {
+            self.0.push((name.into(), span));
+        }
+    }
+
+    fn add_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Identifier(name, span) => self.add(name, *span),
+            Pattern::Array(elements, _) => {
+                for element in elements.iter().flatten() {
+                    self.add_pattern(element);
+                }
+            }
+        }
+    }
+
+    fn add_declarators(&mut self, kind: VarKind, declarations: &[VarDeclarator]) {
+        if kind == VarKind::Var {
+            for decl in declarations {
+                self.add_pattern(&decl.target);
+            }
+        }
+    }
+}
+
+impl Visitor for HoistedNames {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::FunctionDecl { name, loc, .. } => return self.add(name, *loc),
+            Stmt::VarDecl { kind, declarations, .. }
+            | Stmt::For { init: Some(ForInit::VarDecl { kind, declarations, .. }), .. }
+            | Stmt::ForIn { left: ForInit::VarDecl { kind, declarations, .. }, .. }
+            | Stmt::ForOf { left: ForInit::VarDecl { kind, declarations, .. }, .. } => {
+                self.add_declarators(*kind, declarations);
+            }
+            _ => {}
+        }
+        visit::walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, _expr: &Expr) {}
+}
+
+/// Counts every binding of each name in a program, at any depth
+#[derive(Default)]
+struct Bindings(Vec<String>);
+
+impl Bindings {
+    fn count(&self, name: &str) -> usize {
+        self.0.iter().filter(|n| *n == name).count()
+    }
+
+    fn add_all(&mut self, names: &[String]) {
+        self.0.extend(names.iter().cloned());
+    }
+}
+
+impl Visitor for Bindings {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::FunctionDecl { name, params, .. } => {
+                self.0.push(name.clone());
+                self.add_all(params);
+            }
+            Stmt::Try { handler: Some(CatchClause { param: Some(param), .. }), .. } => self.0.push(param.clone()),
+            _ => {}
+        }
+        visit::walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Function { name, params, .. } => {
+                self.0.extend(name.iter().cloned());
+                self.add_all(params);
+            }
+            Expr::Arrow { params, .. } => self.add_all(params),
+            _ => {}
+        }
+        visit::walk_expr(self, expr);
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        if let Pattern::Identifier(name, _) = pattern {
+            self.0.push(name.clone());
+        }
+        visit::walk_pattern(self, pattern);
+    }
+}
+
+/// ToBoolean of a literal
+fn is_truthy(lit: &Literal) -> bool {
+    match lit {
+        Literal::Number(n) => *n != 0.0 && !n.is_nan(),
+        Literal::String(s) => !s.is_empty(),
+        Literal::Boolean(b) => *b,
+        Literal::Null | Literal::Undefined => false,
+    }
+}
+
+/// For a short-circuiting operator, returns whether a constant left side
+/// is the result, leaving the right side unevaluated
+fn short_circuits(op: BinaryOp) -> Option<fn(&Literal) -> bool> {
+    match op {
+        BinaryOp::LogicalAnd => Some(|lit| !is_truthy(lit)),
+        BinaryOp::LogicalOr => Some(is_truthy),
+        BinaryOp::NullishCoalescing => Some(|lit| !matches!(lit, Literal::Null | Literal::Undefined)),
+        _ => None,
+    }
+}
+
+fn fold_unary(op: UnaryOp, lit: &Literal) -> Option<Literal> {
+    match (op, lit) {
+        (UnaryOp::LogicalNot, _) => Some(Literal::Boolean(!is_truthy(lit))),
+        (UnaryOp::Minus, Literal::Number(n)) => Some(Literal::Number(-n)),
+        (UnaryOp::Plus, Literal::Number(n)) => Some(Literal::Number(*n)),
+        (UnaryOp::Void, _) => Some(Literal::Undefined),
+        (UnaryOp::TypeOf, _) => Some(Literal::String(
+            match lit {
+                Literal::Number(_) => "number",
+                Literal::String(_) => "string",
+                Literal::Boolean(_) => "boolean",
+                Literal::Null => "object",
+                Literal::Undefined => "undefined",
+            }
+            .into(),
+        )),
+        _ => None,
+    }
+}
+
+fn fold_binary(op: BinaryOp, left: &Literal, right: &Literal) -> Option<Literal> {
+    if let (Literal::Number(a), Literal::Number(b)) = (left, right) {
+        let (a, b) = (*a, *b);
+        let compared = match op {
+            BinaryOp::Add => return Some(Literal::Number(a + b)),
+            BinaryOp::Sub => return Some(Literal::Number(a - b)),
+            BinaryOp::Mul => return Some(Literal::Number(a * b)),
+            BinaryOp::Div => return Some(Literal::Number(a / b)),
+            BinaryOp::Lt => a < b,
+            BinaryOp::LtEq => a <= b,
+            BinaryOp::Gt => a > b,
+            BinaryOp::GtEq => a >= b,
+            BinaryOp::Eq | BinaryOp::StrictEq => a == b,
+            BinaryOp::NotEq | BinaryOp::StrictNotEq => a != b,
+            _ => return None,
+        };
+        return Some(Literal::Boolean(compared));
+    }
+
+    if let (BinaryOp::Add, Literal::String(a), Literal::String(b)) = (op, left, right) {
+        let mut joined = a.clone();
+        joined.push_str(b);
+        return Some(Literal::String(joined));
+    }
+
+    // Equality between literals of different types other than numbers
+    // needs conversions, except that null and undefined are loosely equal
+    let nullish = |lit: &Literal| matches!(lit, Literal::Null | Literal::Undefined);
+    let same_type = core::mem::discriminant(left) == core::mem::discriminant(right);
+    match op {
+        BinaryOp::StrictEq => Some(Literal::Boolean(same_type && left == right)),
+        BinaryOp::StrictNotEq => Some(Literal::Boolean(!(same_type && left == right))),
+        BinaryOp::Eq | BinaryOp::NotEq => {
+            let equal = if same_type {
+                left == right
+            } else if nullish(left) && nullish(right) {
+                true
+            } else {
+                return None;
+            };
+            Some(Literal::Boolean(equal == (op == BinaryOp::Eq)))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::{compile, Parser};
+
+    fn folded(source: &str) -> Vec<Stmt> {
+        let mut program = Parser::new(source).parse().unwrap();
+        fold_program(&mut program);
+        program.body
+    }
+
+    fn folded_expr(source: &str) -> Expr {
+        fold_expr(Parser::new(source).parse_expression().unwrap())
+    }
+
+    fn literal(source: &str) -> Literal {
+        match folded_expr(source) {
+            Expr::Literal(lit, _) => lit,
+            other => panic!("{} did not fold: {:?}", source, other),
+        }
+    }
+
+    #[test]
+    fn test_fold_operators() {
+        assert_eq!(literal("1 + 2 * 3"), Literal::Number(7.0));
+        assert_eq!(literal("'a' + 'b'"), Literal::String("ab".into()));
+        assert_eq!(literal("!0"), Literal::Boolean(true));
+        assert_eq!(literal("typeof null"), Literal::String("object".into()));
+        assert_eq!(literal("3 > 2 === true"), Literal::Boolean(true));
+        assert_eq!(literal("null == undefined"), Literal::Boolean(true));
+        assert_eq!(literal("null === undefined"), Literal::Boolean(false));
+        assert_eq!(literal("1 !== '1'"), Literal::Boolean(true));
+        assert_eq!(literal("false && f()"), Literal::Boolean(false));
+        assert_eq!(literal("0 || 'x'"), Literal::String("x".into()));
+        assert_eq!(literal("null ?? 4"), Literal::Number(4.0));
+        assert_eq!(literal("1 ? 2 : f()"), Literal::Number(2.0));
+
+        assert_eq!(literal("-5"), Literal::Number(-5.0));
+        assert!(matches!(literal("-0"), Literal::Number(n) if n == 0.0 && n.is_sign_negative()));
+        assert!(matches!(literal("0 * -1"), Literal::Number(n) if n.is_sign_negative()));
+
+        // Left unfolded: loose equality needing conversion, a variable
+        assert!(matches!(folded_expr("1 == '1'"), Expr::Binary { .. }));
+        assert!(matches!(folded_expr("x && false"), Expr::Binary { .. }));
+    }
+
+    #[test]
+    fn test_folded_callee_keeps_receiver_off() {
+        let Expr::Call { callee, .. } = folded_expr("(true && obj.f)()") else { panic!() };
+        assert!(matches!(*callee, Expr::Sequence { .. }));
+
+        let Expr::Call { callee, .. } = folded_expr("obj.f()") else { panic!() };
+        assert!(matches!(*callee, Expr::Member { .. }));
+    }
+
+    #[test]
+    fn test_dead_branches_removed() {
+        assert!(matches!(&folded("if (false) { log('x'); }")[..], [Stmt::Empty { .. }]));
+        assert!(matches!(&folded("if (1) a(); else b();")[..], [Stmt::Expression { .. }]));
+        assert!(matches!(&folded("if (0) a(); else b();")[..], [Stmt::Expression { .. }]));
+        assert!(matches!(&folded("while (false) { a(); }")[..], [Stmt::Empty { .. }]));
+        assert!(matches!(&folded("for (let i = 0; false; i++) { a(); }")[..], [Stmt::Block { stmts, .. }] if stmts.len() == 1));
+    }
+
+    #[test]
+    fn test_dead_branch_keeps_declarations() {
+        let body = folded("if (false) { var a = 1, [b] = c; function f() { var inner; } let d; }");
+        let [Stmt::VarDecl { kind: VarKind::Var, declarations, .. }] = &body[..] else { panic!("{:?}", body) };
+        let names: Vec<_> = declarations.iter().map(|d| d.target.as_identifier().unwrap()).collect();
+        assert_eq!(names, ["a", "b", "f"]);
+        assert!(declarations.iter().all(|d| d.init.is_none()));
+    }
+
+    #[test]
+    fn test_unreachable_tail_removed() {
+        let body = folded("function g() { return 1; h(); var v = 2; function k() {} }");
+        let [Stmt::FunctionDecl { body, .. }] = &body[..] else { panic!() };
+        assert!(matches!(
+            &body[..],
+            [Stmt::Return { .. }, Stmt::VarDecl { declarations, .. }, Stmt::FunctionDecl { .. }] if declarations[0].init.is_none()
+        ));
+    }
+
+    #[test]
+    fn test_top_level_const_substituted() {
+        let body = folded("const DEBUG = false; if (DEBUG) { log(); } function f() { return DEBUG ? 1 : 2; }");
+        assert!(matches!(&body[..], [Stmt::VarDecl { .. }, Stmt::Empty { .. }, Stmt::FunctionDecl { .. }]));
+        let Stmt::FunctionDecl { body, .. } = &body[2] else { panic!() };
+        assert!(matches!(&body[..], [Stmt::Return { argument: Some(Expr::Literal(Literal::Number(n), _)), .. }] if *n == 2.0));
+
+        // Not substituted before the declaration, or when shadowed anywhere
+        assert!(matches!(&folded("if (D) a(); const D = 0;")[0], Stmt::If { .. }));
+        assert!(matches!(&folded("const D = 0; if (D) a(); function g(D) {}")[1], Stmt::If { .. }));
+        assert!(matches!(&folded("const D = 0; { if (D) a(); }")[1], Stmt::Block { stmts, .. } if matches!(stmts[..], [Stmt::Empty { .. }])));
+    }
+
+    #[test]
+    fn test_guarded_debug_block_costs_no_bytecode() {
+        let mut debug_block = String::new();
+        for i in 0..50 {
+            debug_block.push_str(&alloc::format!("    console.log('step {}', state.count * {}, state.name);\n", i, i));
+        }
+        let guarded = alloc::format!(
+            "const DEBUG = false;\nvar state = {{ count: 1, name: 'x' }};\nif (DEBUG) {{\n{}}}\nstate.count + 1",
+            debug_block,
+        );
+        let plain = "const DEBUG = false;\nvar state = { count: 1, name: 'x' };\n\nstate.count + 1";
+        let unfolded = "var DEBUG = false;\nvar state = { count: 1, name: 'x' };\n";
+
+        let guarded_len = compile(&guarded).unwrap().len();
+        let plain_len = compile(plain).unwrap().len();
+        // The line table can differ by a few bytes, since the expression
+        // after the block starts on a later line
+        assert!(guarded_len <= plain_len + 4, "{} vs {}", guarded_len, plain_len);
+        // A mutable flag can't be folded, so the block is all compiled
+        let kept_len = compile(&alloc::format!("{}if (DEBUG) {{\n{}}}\nstate.count + 1", unfolded, debug_block)).unwrap().len();
+        assert!(kept_len > guarded_len + 1000, "{} vs {}", kept_len, guarded_len);
+    }
+}
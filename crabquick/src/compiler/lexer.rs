@@ -24,6 +24,43 @@ impl SourceLocation {
     }
 }
 
+/// Source range covered by a token, comment or AST node
+///
+/// `start` is the first character and `end` the position just past the last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// First character
+    pub start: SourceLocation,
+    /// Position just past the last character
+    pub end: SourceLocation,
+}
+
+impl Span {
+    /// Creates a new span
+    pub fn new(start: SourceLocation, end: SourceLocation) -> Self {
+        Span { start, end }
+    }
+
+    /// Returns true if `other` lies entirely within this span
+    pub fn contains(&self, other: Span) -> bool {
+        self.start.offset <= other.start.offset && other.end.offset <= self.end.offset
+    }
+
+    /// Returns the source text covered by this span
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start.offset..self.end.offset]
+    }
+}
+
+/// A comment, recorded when the lexer is asked to keep them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// Where the comment sits, delimiters included
+    pub span: Span,
+    /// Comment text, delimiters included (`// note`, `/* note */`)
+    pub text: String,
+}
+
 /// Token types
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenKind {
@@ -162,6 +199,8 @@ pub enum TokenKind {
     MinusAssign,
     /// *=
     StarAssign,
+    /// **=
+    StarStarAssign,
     /// /=
     SlashAssign,
     /// %=
@@ -178,8 +217,16 @@ pub enum TokenKind {
     GtGtAssign,
     /// >>>=
     GtGtGtAssign,
+    /// &&=
+    LogicalAndAssign,
+    /// ||=
+    LogicalOrAssign,
+    /// ??=
+    NullishAssign,
     /// ?
     Question,
+    /// ?.
+    QuestionDot,
     /// ??
     NullishCoalescing,
 
@@ -202,11 +249,23 @@ pub enum TokenKind {
     Comma,
     /// .
     Dot,
+    /// ...
+    Ellipsis,
     /// :
     Colon,
     /// =>
     Arrow,
 
+    // Template literals, split at each `${ ... }` substitution
+    /// `text` with no substitutions
+    NoSubstitutionTemplate(String),
+    /// `text${ (template start up to the first substitution)
+    TemplateHead(String),
+    /// }text${ (text between two substitutions)
+    TemplateMiddle(String),
+    /// }text` (text after the last substitution)
+    TemplateTail(String),
+
     // Special
     /// End of file
     Eof,
@@ -279,6 +338,8 @@ pub struct Token {
     pub kind: TokenKind,
     /// Source location
     pub location: SourceLocation,
+    /// Position just past the token's last character
+    pub end: SourceLocation,
     /// True if there was a newline before this token
     pub had_newline: bool,
 }
@@ -286,12 +347,12 @@ pub struct Token {
 impl Token {
     /// Creates a new token
     pub fn new(kind: TokenKind, location: SourceLocation) -> Self {
-        Token { kind, location, had_newline: false }
+        Token { kind, location, end: location, had_newline: false }
     }
 
     /// Creates a new token with newline flag
     pub fn with_newline(kind: TokenKind, location: SourceLocation, had_newline: bool) -> Self {
-        Token { kind, location, had_newline }
+        Token { kind, location, end: location, had_newline }
     }
 }
 
@@ -309,6 +370,21 @@ pub struct Lexer<'a> {
     column: u32,
     /// True if we saw a newline while skipping whitespace
     saw_newline: bool,
+    /// For each template substitution we're inside, the number of `{`
+    /// opened in it and not yet closed; its closing `}` resumes the template
+    template_braces: Vec<u32>,
+    /// Comments seen so far, when they are being kept
+    comments: Option<Vec<Comment>>,
+}
+
+/// Lexer state saved by [`Lexer::checkpoint`]
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pos: usize,
+    line: u32,
+    column: u32,
+    saw_newline: bool,
+    template_braces: Vec<u32>,
 }
 
 impl<'a> Lexer<'a> {
@@ -321,6 +397,39 @@ impl<'a> Lexer<'a> {
             line: 1,
             column: 1,
             saw_newline: false,
+            template_braces: Vec::new(),
+            comments: None,
+        }
+    }
+
+    /// Creates a lexer that keeps the comments it skips
+    ///
+    /// Read them back with [`take_comments`](Self::take_comments).
+    pub fn with_comments(source: &'a str) -> Self {
+        let mut lexer = Self::new(source);
+        lexer.comments = Some(Vec::new());
+        lexer
+    }
+
+    /// Creates a lexer whose first line is numbered `line_offset + 1`, for
+    /// source that starts partway into its file
+    pub fn with_line_offset(source: &'a str, line_offset: u32) -> Self {
+        let mut lexer = Self::new(source);
+        lexer.line = line_offset.saturating_add(1);
+        lexer
+    }
+
+    /// Returns the comments kept so far, in source order, and clears the list
+    pub fn take_comments(&mut self) -> Vec<Comment> {
+        self.comments.as_mut().map(core::mem::take).unwrap_or_default()
+    }
+
+    /// Records the comment running from `start` to the current position
+    fn record_comment(&mut self, start: SourceLocation) {
+        let end = self.location();
+        if let Some(comments) = &mut self.comments {
+            let span = Span::new(start, end);
+            comments.push(Comment { span, text: span.text(self.source).to_string() });
         }
     }
 
@@ -354,6 +463,21 @@ impl<'a> Lexer<'a> {
         Some(ch)
     }
 
+    /// Consumes a run of characters up to the next byte matching `stop`,
+    /// or the end of the source, and returns the run
+    ///
+    /// `stop` must match `\n` and only ASCII bytes, so the run stays on one
+    /// line and ends on a character boundary. Long strings and comments are
+    /// taken a run at a time rather than a character at a time.
+    fn consume_run(&mut self, stop: impl Fn(u8) -> bool) -> &'a str {
+        let start = self.pos;
+        let len = self.bytes[start..].iter().position(|&b| stop(b)).unwrap_or(self.bytes.len() - start);
+        let run = &self.source[start..start + len];
+        self.pos += len;
+        self.column += if run.is_ascii() { len } else { run.chars().count() } as u32;
+        run
+    }
+
     /// Skips whitespace and tracks newlines
     fn skip_whitespace(&mut self) {
         while let Some(ch) = self.peek() {
@@ -374,14 +498,8 @@ impl<'a> Lexer<'a> {
         self.consume();
         self.consume();
 
-        // Skip until newline
-        while let Some(ch) = self.peek() {
-            self.consume();
-            if ch == '\n' {
-                self.saw_newline = true;
-                break;
-            }
-        }
+        // Skip up to the newline, which is left for skip_whitespace
+        self.consume_run(|b| b == b'\n');
     }
 
     /// Skips a multi-line comment
@@ -392,6 +510,7 @@ impl<'a> Lexer<'a> {
 
         // Skip until '*/'
         loop {
+            self.consume_run(|b| b == b'*' || b == b'\n');
             match self.peek() {
                 None => return Err("Unterminated block comment".to_string()),
                 Some('*') => {
@@ -476,157 +595,117 @@ impl<'a> Lexer<'a> {
     }
 
     /// Reads a number literal
+    ///
+    /// Decimal literals take an optional fraction and exponent (`1.5e-3`,
+    /// `.5`, `1.`); integers may also be written with a `0x`, `0o` or `0b`
+    /// prefix. `_` may separate digits, as in `1_000`. Legacy octal such as
+    /// `0755` is an error rather than silently decimal, as is a literal
+    /// running straight into an identifier, as in `3in`.
     fn read_number(&mut self) -> Result<TokenKind, String> {
-        let start = self.pos;
-
-        // Check for hex/octal/binary prefix
         if self.peek() == Some('0') {
-            self.consume();
-            match self.peek() {
-                Some('x') | Some('X') => {
-                    self.consume();
-                    return self.read_hex_number();
-                }
-                Some('o') | Some('O') => {
-                    self.consume();
-                    return self.read_octal_number();
-                }
-                Some('b') | Some('B') => {
-                    self.consume();
-                    return self.read_binary_number();
+            let radix = match self.peek_next() {
+                Some('x' | 'X') => 16,
+                Some('o' | 'O') => 8,
+                Some('b' | 'B') => 2,
+                Some(ch) if ch.is_ascii_digit() || ch == '_' => {
+                    return Err("Invalid number: leading zero (use the 0o prefix for octal)".to_string());
                 }
-                Some(ch) if ch.is_ascii_digit() => {
-                    // Legacy octal (just parse as decimal for simplicity)
-                }
-                _ => {}
-            }
-        }
-
-        // Read integer part
-        while let Some(ch) = self.peek() {
-            if ch.is_ascii_digit() {
+                _ => 10,
+            };
+            if radix != 10 {
                 self.consume();
-            } else {
-                break;
+                self.consume();
+                return self.read_radix_number(radix);
             }
         }
 
-        // Check for decimal point
-        if self.peek() == Some('.') && self.peek_next().map_or(false, |c| c.is_ascii_digit()) {
-            self.consume(); // consume '.'
+        // Integer part, empty for `.5`
+        let mut text = String::new();
+        self.read_digits(10, &mut text)?;
 
-            // Read fractional part
-            while let Some(ch) = self.peek() {
-                if ch.is_ascii_digit() {
-                    self.consume();
-                } else {
-                    break;
-                }
-            }
+        // Fraction, possibly empty for `1.`
+        if self.peek() == Some('.') {
+            self.consume();
+            text.push('.');
+            self.read_digits(10, &mut text)?;
         }
 
-        // Check for exponent
-        if let Some(ch) = self.peek() {
-            if ch == 'e' || ch == 'E' {
+        // Exponent
+        if let Some(e @ ('e' | 'E')) = self.peek() {
+            self.consume();
+            text.push(e);
+            if let Some(sign @ ('+' | '-')) = self.peek() {
                 self.consume();
-
-                // Optional +/- sign
-                if let Some(sign) = self.peek() {
-                    if sign == '+' || sign == '-' {
-                        self.consume();
-                    }
-                }
-
-                // Read exponent digits
-                let exp_start = self.pos;
-                while let Some(ch) = self.peek() {
-                    if ch.is_ascii_digit() {
-                        self.consume();
-                    } else {
-                        break;
-                    }
-                }
-
-                if exp_start == self.pos {
-                    return Err("Invalid number: expected exponent digits".to_string());
-                }
+                text.push(sign);
+            }
+            if self.read_digits(10, &mut text)? == 0 {
+                return Err("Invalid number: expected exponent digits".to_string());
             }
         }
 
-        let text = &self.source[start..self.pos];
+        self.check_number_end()?;
         let value = text.parse::<f64>()
             .map_err(|_| format!("Invalid number: {}", text))?;
 
         Ok(TokenKind::Number(value))
     }
 
-    /// Reads a hexadecimal number
-    fn read_hex_number(&mut self) -> Result<TokenKind, String> {
-        let start = self.pos;
-
-        while let Some(ch) = self.peek() {
-            if ch.is_ascii_hexdigit() {
-                self.consume();
-            } else {
-                break;
-            }
-        }
-
-        if start == self.pos {
-            return Err("Invalid hex number: no digits".to_string());
+    /// Reads the digits of a `0x`, `0o` or `0b` literal, after the prefix
+    fn read_radix_number(&mut self, radix: u32) -> Result<TokenKind, String> {
+        let mut digits = String::new();
+        if self.read_digits(radix, &mut digits)? == 0 {
+            let prefix = match radix {
+                16 => "0x",
+                8 => "0o",
+                _ => "0b",
+            };
+            return Err(format!("Invalid number: expected digits after {}", prefix));
         }
+        self.check_number_end()?;
 
-        let text = &self.source[start..self.pos];
-        let value = u64::from_str_radix(text, 16)
-            .map_err(|_| format!("Invalid hex number: {}", text))?;
+        // Up to 64 bits convert with a single rounding; longer literals
+        // are rare enough to accumulate
+        let value = u64::from_str_radix(&digits, radix).map_or_else(
+            |_| digits.chars().fold(0.0, |acc, ch| acc * f64::from(radix) + f64::from(ch.to_digit(radix).unwrap_or(0))),
+            |value| value as f64,
+        );
 
-        Ok(TokenKind::Number(value as f64))
+        Ok(TokenKind::Number(value))
     }
 
-    /// Reads an octal number
-    fn read_octal_number(&mut self) -> Result<TokenKind, String> {
-        let start = self.pos;
-
+    /// Reads digits of `radix` into `out`, dropping `_` separators, and
+    /// returns how many digits there were
+    ///
+    /// A separator must sit between two digits, so `1__0`, `1_` and `0x_1`
+    /// are errors.
+    fn read_digits(&mut self, radix: u32, out: &mut String) -> Result<usize, String> {
+        let mut count = 0;
         while let Some(ch) = self.peek() {
-            if ch >= '0' && ch <= '7' {
+            if ch.is_digit(radix) {
+                self.consume();
+                out.push(ch);
+                count += 1;
+            } else if ch == '_' {
+                if count == 0 || !self.peek_next().is_some_and(|next| next.is_digit(radix)) {
+                    return Err("Invalid number: '_' must sit between digits".to_string());
+                }
                 self.consume();
             } else {
                 break;
             }
         }
-
-        if start == self.pos {
-            return Err("Invalid octal number: no digits".to_string());
-        }
-
-        let text = &self.source[start..self.pos];
-        let value = u64::from_str_radix(text, 8)
-            .map_err(|_| format!("Invalid octal number: {}", text))?;
-
-        Ok(TokenKind::Number(value as f64))
+        Ok(count)
     }
 
-    /// Reads a binary number
-    fn read_binary_number(&mut self) -> Result<TokenKind, String> {
-        let start = self.pos;
-
-        while let Some(ch) = self.peek() {
-            if ch == '0' || ch == '1' {
-                self.consume();
-            } else {
-                break;
+    /// Rejects a number literal followed directly by an identifier or a
+    /// digit its radix doesn't allow, as in `3in` or `0b12`
+    fn check_number_end(&self) -> Result<(), String> {
+        match self.peek() {
+            Some(ch) if Self::is_identifier_continue(ch) => {
+                Err(format!("Invalid number: unexpected '{}' after number", ch))
             }
+            _ => Ok(()),
         }
-
-        if start == self.pos {
-            return Err("Invalid binary number: no digits".to_string());
-        }
-
-        let text = &self.source[start..self.pos];
-        let value = u64::from_str_radix(text, 2)
-            .map_err(|_| format!("Invalid binary number: {}", text))?;
-
-        Ok(TokenKind::Number(value as f64))
     }
 
     /// Reads a string literal
@@ -646,64 +725,11 @@ impl<'a> Lexer<'a> {
                 }
                 Some('\\') => {
                     self.consume();
-                    match self.peek() {
-                        None => return Err("Unterminated string escape".to_string()),
-                        Some('n') => {
-                            self.consume();
-                            result.push('\n');
-                        }
-                        Some('r') => {
-                            self.consume();
-                            result.push('\r');
-                        }
-                        Some('t') => {
-                            self.consume();
-                            result.push('\t');
-                        }
-                        Some('\\') => {
-                            self.consume();
-                            result.push('\\');
-                        }
-                        Some('\'') => {
-                            self.consume();
-                            result.push('\'');
-                        }
-                        Some('"') => {
-                            self.consume();
-                            result.push('"');
-                        }
-                        Some('0') => {
-                            self.consume();
-                            result.push('\0');
-                        }
-                        Some('x') => {
-                            self.consume();
-                            let hex = self.read_hex_escape(2)?;
-                            if let Some(ch) = char::from_u32(hex) {
-                                result.push(ch);
-                            } else {
-                                result.push('\0');
-                            }
-                        }
-                        Some('u') => {
-                            self.consume();
-                            let hex = self.read_hex_escape(4)?;
-                            if let Some(ch) = char::from_u32(hex) {
-                                result.push(ch);
-                            } else {
-                                return Err(format!("Invalid unicode escape: \\u{:04x}", hex));
-                            }
-                        }
-                        Some(ch) => {
-                            // Invalid escape, just include the character
-                            self.consume();
-                            result.push(ch);
-                        }
-                    }
+                    self.read_escape(&mut result)?;
                 }
-                Some(ch) => {
-                    self.consume();
-                    result.push(ch);
+                Some(_) => {
+                    let quote = quote as u8;
+                    result.push_str(self.consume_run(|b| b == quote || b == b'\\' || b == b'\n'));
                 }
             }
         }
@@ -711,25 +737,171 @@ impl<'a> Lexer<'a> {
         Ok(TokenKind::String(result))
     }
 
-    /// Reads a hex escape sequence
-    fn read_hex_escape(&mut self, len: usize) -> Result<u32, String> {
-        let mut value = 0u32;
+    /// Reads the escape sequence after a `\\` into `result`
+    ///
+    /// A backslash before a line terminator is a line continuation and adds
+    /// nothing. Legacy octal escapes such as `\12` are rejected, as they are
+    /// in strict mode; any other character escapes to itself.
+    fn read_escape(&mut self, result: &mut String) -> Result<(), String> {
+        let Some(ch) = self.consume() else {
+            return Err("Unterminated string escape".to_string());
+        };
+        match ch {
+            'n' => result.push('\n'),
+            'r' => result.push('\r'),
+            't' => result.push('\t'),
+            'b' => result.push('\u{8}'),
+            'f' => result.push('\u{c}'),
+            'v' => result.push('\u{b}'),
+            '0' if !self.peek().is_some_and(|next| next.is_ascii_digit()) => result.push('\0'),
+            '0'..='9' => {
+                return Err(format!("Invalid escape sequence '\\{ch}' (use \\x or \\u for character codes)"));
+            }
+            'x' => {
+                let hex = self.read_hex_escape(2)
+                    .ok_or_else(|| "Invalid hex escape: expected 2 hex digits after \\x".to_string())?;
+                result.push(char::from_u32(hex).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            'u' => {
+                let ch = self.read_unicode_escape()?;
+                result.push(ch);
+            }
+            '\r' => {
+                if self.peek() == Some('\n') {
+                    self.consume();
+                }
+            }
+            '\n' | '\u{2028}' | '\u{2029}' => {}
+            ch => result.push(ch),
+        }
 
-        for _ in 0..len {
+        Ok(())
+    }
+
+    /// Reads the code point of a `\u` escape, the `u` having been consumed
+    ///
+    /// Takes `XXXX` or `{X...}` up to U+10FFFF. A `\uXXXX` high surrogate
+    /// followed by a low one combines into one character; lone surrogates
+    /// can't be held in a Rust string and become U+FFFD, as in `JSON.parse`.
+    fn read_unicode_escape(&mut self) -> Result<char, String> {
+        if self.peek() == Some('{') {
+            self.consume();
+            let mut value = 0u32;
+            let mut digits = 0;
+            while let Some(digit) = self.peek().and_then(|ch| ch.to_digit(16)) {
+                self.consume();
+                value = value.saturating_mul(16).saturating_add(digit);
+                digits += 1;
+            }
+            if digits == 0 || self.consume() != Some('}') {
+                return Err("Invalid unicode escape: expected hex digits and '}' after \\u{".to_string());
+            }
+            if value > u32::from(char::MAX) {
+                return Err("Invalid unicode escape: code point above U+10FFFF".to_string());
+            }
+            return Ok(char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+
+        let high = self.read_hex_escape(4)
+            .ok_or_else(|| "Invalid unicode escape: expected 4 hex digits or '{' after \\u".to_string())?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return Ok(char::from_u32(high).unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+
+        if self.source[self.pos..].starts_with("\\u") {
+            // Escapes are ASCII on one line, so restoring pos and column is enough
+            let (pos, column) = (self.pos, self.column);
+            self.consume();
+            self.consume();
+            if let Some(low @ 0xDC00..=0xDFFF) = self.read_hex_escape(4) {
+                let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                return Ok(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            self.pos = pos;
+            self.column = column;
+        }
+        Ok(char::REPLACEMENT_CHARACTER)
+    }
+
+    /// Reads template text up to and including the closing backtick or the
+    /// next `${`, the opening backtick or `}` having been consumed
+    fn read_template_chunk(&mut self, head: bool) -> Result<TokenKind, String> {
+        let mut result = String::new();
+
+        loop {
             match self.peek() {
-                Some(ch) if ch.is_ascii_hexdigit() => {
+                None => return Err("Unterminated template literal".to_string()),
+                Some('`') => {
+                    self.consume();
+                    return Ok(if head {
+                        TokenKind::NoSubstitutionTemplate(result)
+                    } else {
+                        TokenKind::TemplateTail(result)
+                    });
+                }
+                Some('$') if self.peek_next() == Some('{') => {
+                    self.consume();
+                    self.consume();
+                    self.template_braces.push(0);
+                    return Ok(if head {
+                        TokenKind::TemplateHead(result)
+                    } else {
+                        TokenKind::TemplateMiddle(result)
+                    });
+                }
+                Some('\\') => {
                     self.consume();
-                    value = value * 16 + ch.to_digit(16).unwrap();
+                    self.read_escape(&mut result)?;
+                }
+                // Line terminators in the source are normalized to \n
+                Some('\r') => {
+                    self.consume();
+                    if self.peek() == Some('\n') {
+                        self.consume();
+                    }
+                    result.push('\n');
+                }
+                Some('\n') => {
+                    self.consume();
+                    result.push('\n');
+                }
+                Some(_) => {
+                    let run = self.consume_run(|b| matches!(b, b'`' | b'$' | b'\\' | b'\r' | b'\n'));
+                    if run.is_empty() {
+                        // A `$` not starting a substitution
+                        self.consume();
+                        result.push('$');
+                    } else {
+                        result.push_str(run);
+                    }
                 }
-                _ => return Err("Invalid hex escape sequence".to_string()),
             }
         }
+    }
+
+    /// Reads exactly `len` hex digits, or returns `None` at the first
+    /// character that isn't one
+    fn read_hex_escape(&mut self, len: usize) -> Option<u32> {
+        let mut value = 0u32;
+
+        for _ in 0..len {
+            let digit = self.peek()?.to_digit(16)?;
+            self.consume();
+            value = value * 16 + digit;
+        }
 
-        Ok(value)
+        Some(value)
     }
 
     /// Returns the next token
     pub fn next_token(&mut self) -> Token {
+        let mut token = self.scan_token();
+        token.end = self.location();
+        token
+    }
+
+    /// Scans the next token, leaving its end position unset
+    fn scan_token(&mut self) -> Token {
         // Reset newline flag
         self.saw_newline = false;
 
@@ -741,7 +913,9 @@ impl<'a> Lexer<'a> {
             if self.peek() == Some('/') {
                 match self.peek_next() {
                     Some('/') => {
+                        let loc = self.location();
                         self.skip_line_comment();
+                        self.record_comment(loc);
                         continue;
                     }
                     Some('*') => {
@@ -749,6 +923,7 @@ impl<'a> Lexer<'a> {
                         if let Err(err) = self.skip_block_comment() {
                             return Token::with_newline(TokenKind::Error(err), loc, self.saw_newline);
                         }
+                        self.record_comment(loc);
                         continue;
                     }
                     _ => break,
@@ -773,8 +948,8 @@ impl<'a> Lexer<'a> {
             return Token::with_newline(kind, loc, had_newline);
         }
 
-        // Number
-        if ch.is_ascii_digit() {
+        // Number, including `.5`
+        if ch.is_ascii_digit() || (ch == '.' && self.peek_next().is_some_and(|c| c.is_ascii_digit())) {
             let kind = match self.read_number() {
                 Ok(k) => k,
                 Err(err) => TokenKind::Error(err),
@@ -791,14 +966,45 @@ impl<'a> Lexer<'a> {
             return Token::with_newline(kind, loc, had_newline);
         }
 
+        // Template literal
+        if ch == '`' {
+            self.consume();
+            let kind = match self.read_template_chunk(true) {
+                Ok(k) => k,
+                Err(err) => TokenKind::Error(err),
+            };
+            return Token::with_newline(kind, loc, had_newline);
+        }
+
         // Operators and punctuation
         self.consume();
 
         let kind = match ch {
             '(' => TokenKind::LParen,
             ')' => TokenKind::RParen,
-            '{' => TokenKind::LBrace,
-            '}' => TokenKind::RBrace,
+            '{' => {
+                if let Some(depth) = self.template_braces.last_mut() {
+                    *depth += 1;
+                }
+                TokenKind::LBrace
+            }
+            '}' => {
+                match self.template_braces.last_mut() {
+                    // Closes a substitution: the template continues
+                    Some(0) => {
+                        self.template_braces.pop();
+                        match self.read_template_chunk(false) {
+                            Ok(k) => k,
+                            Err(err) => TokenKind::Error(err),
+                        }
+                    }
+                    Some(depth) => {
+                        *depth -= 1;
+                        TokenKind::RBrace
+                    }
+                    None => TokenKind::RBrace,
+                }
+            }
             '[' => TokenKind::LBracket,
             ']' => TokenKind::RBracket,
             ';' => TokenKind::Semicolon,
@@ -806,13 +1012,29 @@ impl<'a> Lexer<'a> {
             ':' => TokenKind::Colon,
             '~' => TokenKind::Tilde,
             '?' => {
-                if self.peek() == Some('?') {
-                    self.consume();
-                    TokenKind::NullishCoalescing
-                } else {
-                    TokenKind::Question
+                match self.peek() {
+                    Some('?') => {
+                        self.consume();
+                        if self.peek() == Some('=') {
+                            self.consume();
+                            TokenKind::NullishAssign
+                        } else {
+                            TokenKind::NullishCoalescing
+                        }
+                    }
+                    // `a?.5:b` is a conditional, not an optional chain
+                    Some('.') if !self.peek_next().is_some_and(|c| c.is_ascii_digit()) => {
+                        self.consume();
+                        TokenKind::QuestionDot
+                    }
+                    _ => TokenKind::Question,
                 }
             }
+            '.' if self.peek() == Some('.') && self.peek_next() == Some('.') => {
+                self.consume();
+                self.consume();
+                TokenKind::Ellipsis
+            }
             '.' => TokenKind::Dot,
             '+' => {
                 match self.peek() {
@@ -844,7 +1066,12 @@ impl<'a> Lexer<'a> {
                 match self.peek() {
                     Some('*') => {
                         self.consume();
-                        TokenKind::StarStar
+                        if self.peek() == Some('=') {
+                            self.consume();
+                            TokenKind::StarStarAssign
+                        } else {
+                            TokenKind::StarStar
+                        }
                     }
                     Some('=') => {
                         self.consume();
@@ -951,7 +1178,12 @@ impl<'a> Lexer<'a> {
                 match self.peek() {
                     Some('&') => {
                         self.consume();
-                        TokenKind::LogicalAnd
+                        if self.peek() == Some('=') {
+                            self.consume();
+                            TokenKind::LogicalAndAssign
+                        } else {
+                            TokenKind::LogicalAnd
+                        }
                     }
                     Some('=') => {
                         self.consume();
@@ -964,7 +1196,12 @@ impl<'a> Lexer<'a> {
                 match self.peek() {
                     Some('|') => {
                         self.consume();
-                        TokenKind::LogicalOr
+                        if self.peek() == Some('=') {
+                            self.consume();
+                            TokenKind::LogicalOrAssign
+                        } else {
+                            TokenKind::LogicalOr
+                        }
                     }
                     Some('=') => {
                         self.consume();
@@ -989,19 +1226,34 @@ impl<'a> Lexer<'a> {
 
     /// Peeks at the next token without consuming it
     pub fn peek_token(&mut self) -> Token {
-        let saved_pos = self.pos;
-        let saved_line = self.line;
-        let saved_column = self.column;
-        let saved_saw_newline = self.saw_newline;
-
+        let saved = self.checkpoint();
         let token = self.next_token();
+        self.restore(saved);
+        token
+    }
 
-        self.pos = saved_pos;
-        self.line = saved_line;
-        self.column = saved_column;
-        self.saw_newline = saved_saw_newline;
+    /// Saves the lexer state so the parser can backtrack to it
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            pos: self.pos,
+            line: self.line,
+            column: self.column,
+            saw_newline: self.saw_newline,
+            template_braces: self.template_braces.clone(),
+        }
+    }
 
-        token
+    /// Returns to a state saved by [`checkpoint`](Self::checkpoint)
+    pub fn restore(&mut self, checkpoint: Checkpoint) {
+        self.pos = checkpoint.pos;
+        self.line = checkpoint.line;
+        self.column = checkpoint.column;
+        self.saw_newline = checkpoint.saw_newline;
+        self.template_braces = checkpoint.template_braces;
+        // Anything past the checkpoint will be seen, and recorded, again
+        if let Some(comments) = &mut self.comments {
+            comments.retain(|comment| comment.span.end.offset <= checkpoint.pos);
+        }
     }
 
     /// Gets the current position for parser checkpointing
@@ -1063,6 +1315,54 @@ mod tests {
         assert!(matches!(lexer.next_token().kind, TokenKind::Number(n) if n == 43981.0));
     }
 
+    #[test]
+    fn test_radix_and_separated_numbers() {
+        let mut lexer = Lexer::new("0b1010 0O755 0xFFFFFFFF 1_000 0xff_ff 1e6 1_0.2_5e1_0 .5 1. 0 0.5 0x10000000000000001");
+        let expected = [10.0, 493.0, 4294967295.0, 1000.0, 65535.0, 1e6, 10.25e10, 0.5, 1.0, 0.0, 0.5, 18446744073709551617.0];
+        for value in expected {
+            assert!(matches!(lexer.next_token().kind, TokenKind::Number(n) if n == value), "{}", value);
+        }
+        assert!(matches!(lexer.next_token().kind, TokenKind::Eof));
+
+        // `1..toString` is a number then a member access
+        let mut lexer = Lexer::new("1..toString a?.5:b");
+        assert!(matches!(lexer.next_token().kind, TokenKind::Number(n) if n == 1.0));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Dot));
+        lexer.next_token();
+        lexer.next_token();
+        assert!(matches!(lexer.next_token().kind, TokenKind::Question));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Number(n) if n == 0.5));
+    }
+
+    #[test]
+    fn test_malformed_numbers() {
+        let cases = [
+            ("0x", "Invalid number: expected digits after 0x"),
+            ("0b", "Invalid number: expected digits after 0b"),
+            ("0b102", "Invalid number: unexpected '2' after number"),
+            ("0o8", "Invalid number: expected digits after 0o"),
+            ("0755", "Invalid number: leading zero (use the 0o prefix for octal)"),
+            ("08", "Invalid number: leading zero (use the 0o prefix for octal)"),
+            ("0_1", "Invalid number: leading zero (use the 0o prefix for octal)"),
+            ("1__0", "Invalid number: '_' must sit between digits"),
+            ("1_", "Invalid number: '_' must sit between digits"),
+            ("1._5", "Invalid number: '_' must sit between digits"),
+            ("1e", "Invalid number: expected exponent digits"),
+            ("3in x", "Invalid number: unexpected 'i' after number"),
+            ("0xffg", "Invalid number: unexpected 'g' after number"),
+        ];
+        for (source, message) in cases {
+            let mut lexer = Lexer::new(source);
+            assert!(matches!(lexer.next_token().kind, TokenKind::Error(ref m) if m == message), "{}", source);
+        }
+
+        // The error points at the literal
+        let mut lexer = Lexer::new("var x =\n  0x;");
+        let token = (0..4).map(|_| lexer.next_token()).last().unwrap();
+        assert!(matches!(token.kind, TokenKind::Error(_)));
+        assert_eq!((token.location.line, token.location.column), (2, 3));
+    }
+
     #[test]
     fn test_strings() {
         let mut lexer = Lexer::new(r#""hello" 'world' "foo\"bar""#);
@@ -1080,6 +1380,54 @@ mod tests {
         assert!(matches!(lexer.next_token().kind, TokenKind::String(ref s) if s == "tab\there"));
     }
 
+    #[test]
+    fn test_unicode_and_control_escapes() {
+        let cases = [
+            (r#""A\x42""#, "AB"),
+            (r#""\u{1F600}" "#, "\u{1F600}"),
+            (r#""\uD83D\uDE00""#, "\u{1F600}"),
+            (r#""\uD83Dx\uDE00""#, "\u{FFFD}x\u{FFFD}"),
+            (r#""\uD83DA""#, "\u{FFFD}A"),
+            (r#""\b\f\v\0""#, "\u{8}\u{c}\u{b}\0"),
+            (r#""\q\'""#, "q'"),
+            ("'a\\\nb' ", "ab"),
+            ("'a\\\r\nb' ", "ab"),
+            ("`\\u{41}${", "A"),
+        ];
+        for (source, expected) in cases {
+            let kind = Lexer::new(source).next_token().kind;
+            let text = match &kind {
+                TokenKind::String(s) | TokenKind::TemplateHead(s) => s.as_str(),
+                _ => panic!("{source}: {kind:?}"),
+            };
+            assert_eq!(text, expected, "{source}");
+        }
+
+        // A line continuation still counts the line
+        let mut lexer = Lexer::new("'a\\\nb' x");
+        lexer.next_token();
+        assert_eq!(lexer.next_token().location.line, 2);
+    }
+
+    #[test]
+    fn test_malformed_escapes() {
+        let cases = [
+            (r#""\x4""#, "Invalid hex escape: expected 2 hex digits after \\x"),
+            (r#""\u12""#, "Invalid unicode escape: expected 4 hex digits or '{' after \\u"),
+            (r#""\u""#, "Invalid unicode escape: expected 4 hex digits or '{' after \\u"),
+            (r#""\u{}""#, "Invalid unicode escape: expected hex digits and '}' after \\u{"),
+            (r#""\u{41""#, "Invalid unicode escape: expected hex digits and '}' after \\u{"),
+            (r#""\u{110000}""#, "Invalid unicode escape: code point above U+10FFFF"),
+            (r#""\12""#, "Invalid escape sequence '\\1' (use \\x or \\u for character codes)"),
+            (r#""\01""#, "Invalid escape sequence '\\0' (use \\x or \\u for character codes)"),
+            ("\"abc\\", "Unterminated string escape"),
+            ("\"abc", "Unterminated string literal"),
+        ];
+        for (source, message) in cases {
+            assert!(matches!(Lexer::new(source).next_token().kind, TokenKind::Error(ref m) if m == message), "{source}");
+        }
+    }
+
     #[test]
     fn test_operators() {
         let mut lexer = Lexer::new("+ - * / % == === != !== < > <= >= && || !");
@@ -1102,6 +1450,73 @@ mod tests {
         assert!(matches!(lexer.next_token().kind, TokenKind::Bang));
     }
 
+    #[test]
+    fn test_ellipsis() {
+        let mut lexer = Lexer::new("...a a.b");
+
+        assert!(matches!(lexer.next_token().kind, TokenKind::Ellipsis));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Identifier(_)));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Identifier(_)));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Dot));
+    }
+
+    #[test]
+    fn test_exponent_operators() {
+        let mut lexer = Lexer::new("** **= *=");
+
+        assert!(matches!(lexer.next_token().kind, TokenKind::StarStar));
+        assert!(matches!(lexer.next_token().kind, TokenKind::StarStarAssign));
+        assert!(matches!(lexer.next_token().kind, TokenKind::StarAssign));
+    }
+
+    #[test]
+    fn test_optional_chaining_and_logical_assignment() {
+        let mut lexer = Lexer::new("a?.b ?? c ??= d ||= e &&= f ? 5 : g?.5:h");
+
+        assert!(matches!(lexer.next_token().kind, TokenKind::Identifier(_)));
+        assert!(matches!(lexer.next_token().kind, TokenKind::QuestionDot));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Identifier(_)));
+        assert!(matches!(lexer.next_token().kind, TokenKind::NullishCoalescing));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Identifier(_)));
+        assert!(matches!(lexer.next_token().kind, TokenKind::NullishAssign));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Identifier(_)));
+        assert!(matches!(lexer.next_token().kind, TokenKind::LogicalOrAssign));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Identifier(_)));
+        assert!(matches!(lexer.next_token().kind, TokenKind::LogicalAndAssign));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Identifier(_)));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Question));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Number(_)));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Colon));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Identifier(_)));
+        // A digit after `?.` makes it a conditional
+        assert!(matches!(lexer.next_token().kind, TokenKind::Question));
+    }
+
+    #[test]
+    fn test_template_literals() {
+        let mut lexer = Lexer::new("`plain` `a${x}b${ {y: 1}.y }c` `\\`\\${}`");
+
+        assert!(matches!(lexer.next_token().kind, TokenKind::NoSubstitutionTemplate(ref s) if s == "plain"));
+        assert!(matches!(lexer.next_token().kind, TokenKind::TemplateHead(ref s) if s == "a"));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Identifier(ref s) if s == "x"));
+        assert!(matches!(lexer.next_token().kind, TokenKind::TemplateMiddle(ref s) if s == "b"));
+        // Braces inside a substitution don't end it
+        assert!(matches!(lexer.next_token().kind, TokenKind::LBrace));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Identifier(_)));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Colon));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Number(_)));
+        assert!(matches!(lexer.next_token().kind, TokenKind::RBrace));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Dot));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Identifier(_)));
+        assert!(matches!(lexer.next_token().kind, TokenKind::TemplateTail(ref s) if s == "c"));
+        assert!(matches!(lexer.next_token().kind, TokenKind::NoSubstitutionTemplate(ref s) if s == "`${}"));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Eof));
+
+        let mut lexer = Lexer::new("`line1\r\nline2` `open");
+        assert!(matches!(lexer.next_token().kind, TokenKind::NoSubstitutionTemplate(ref s) if s == "line1\nline2"));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Error(_)));
+    }
+
     #[test]
     fn test_comments() {
         let mut lexer = Lexer::new("foo // comment\nbar /* block */ baz");
@@ -1111,6 +1526,26 @@ mod tests {
         assert!(matches!(lexer.next_token().kind, TokenKind::Identifier(ref s) if s == "baz"));
     }
 
+    #[test]
+    fn test_kept_comments_and_token_ends() {
+        let source = "foo // comment\nbar /* block */ 'baz'";
+        let mut lexer = Lexer::with_comments(source);
+
+        let foo = lexer.next_token();
+        assert_eq!(Span::new(foo.location, foo.end).text(source), "foo");
+        let bar = lexer.next_token();
+        assert!(bar.had_newline);
+        assert_eq!((bar.end.line, bar.end.column), (2, 4));
+        let baz = lexer.next_token();
+        assert_eq!(Span::new(baz.location, baz.end).text(source), "'baz'");
+
+        let comments = lexer.take_comments();
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].text, "// comment");
+        assert_eq!(comments[1].span.text(source), "/* block */");
+        assert!(lexer.take_comments().is_empty());
+    }
+
     #[test]
     fn test_location_tracking() {
         let mut lexer = Lexer::new("foo\nbar");
@@ -1123,4 +1558,45 @@ mod tests {
         assert_eq!(tok2.location.line, 2);
         assert_eq!(tok2.location.column, 1);
     }
+
+    #[test]
+    fn test_runs_keep_locations() {
+        let source = "'h\\u00e9llo wörld' /* é\n** */ `a$b\nç${x}` z";
+        let mut lexer = Lexer::new(source);
+
+        assert!(matches!(lexer.next_token().kind, TokenKind::String(ref s) if s == "héllo wörld"));
+        let template = lexer.next_token();
+        assert!(template.had_newline);
+        assert!(matches!(template.kind, TokenKind::TemplateHead(ref s) if s == "a$b\nç"));
+        assert!(matches!(lexer.next_token().kind, TokenKind::Identifier(ref s) if s == "x"));
+        assert!(matches!(lexer.next_token().kind, TokenKind::TemplateTail(ref s) if s.is_empty()));
+        let z = lexer.next_token();
+        assert_eq!((z.location.line, z.location.column), (3, 8));
+    }
+
+    /// Lexes `source` to the end, returning the fastest of a few runs
+    fn time_to_lex(source: &str) -> std::time::Duration {
+        (0..3)
+            .map(|_| {
+                let start = std::time::Instant::now();
+                let mut lexer = Lexer::new(source);
+                while !matches!(lexer.next_token().kind, TokenKind::Eof | TokenKind::Error(_)) {}
+                start.elapsed()
+            })
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_long_tokens_lex_in_linear_time() {
+        // 1 MB of one token should cost about 16x what 64 kB does; allow
+        // for noise, but nowhere near the 256x of a quadratic lexer
+        let string = |n: usize| alloc::format!("\"{}\"", "ab\\n\\x41".repeat(n / 8));
+        let comment = |n: usize| alloc::format!("/*{}*/", "* é\n".repeat(n / 5));
+        for make in [&string as &dyn Fn(usize) -> String, &comment] {
+            let small = time_to_lex(&make(64 << 10));
+            let large = time_to_lex(&make(1 << 20));
+            assert!(large < small * 64 + std::time::Duration::from_millis(20), "{:?} vs {:?}", large, small);
+        }
+    }
 }
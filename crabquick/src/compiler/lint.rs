@@ -0,0 +1,257 @@
+//! Compile-time warnings
+//!
+//! Checks that run on the AST before folding and report code that compiles
+//! but probably doesn't do what was meant. Warnings never stop a compile;
+//! [`compile_with_diagnostics`](super::compile_with_diagnostics) returns
+//! them next to the bytecode.
+//!
+//! The one check so far is for implicit globals: a name that no enclosing
+//! scope declares, first assigned in a `for` head or inside a function.
+//! `for (i = 0; i < n; i++)` in a callback without `var` makes `i` a
+//! global, so two callbacks running the same loop clobber each other's
+//! counters. A name first assigned at the top level outside a `for` head is
+//! taken as an intentional global and not reported, even if functions
+//! assign it later.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::ast::*;
+use super::lexer::{SourceLocation, Span};
+use super::visit::{self, Visitor};
+
+/// What a warning is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DiagnosticCode {
+    /// Assignment to an undeclared name in a `for` head or a function
+    ImplicitGlobal,
+}
+
+impl DiagnosticCode {
+    /// Returns the code's name, for hosts that filter or suppress warnings
+    pub fn name(self) -> &'static str {
+        match self {
+            DiagnosticCode::ImplicitGlobal => "implicit-global",
+        }
+    }
+}
+
+/// Compile-time warning
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub code: DiagnosticCode,
+    pub message: String,
+    pub location: SourceLocation,
+}
+
+/// Returns the warnings for a program, in source order
+pub fn lint_program(program: &Program) -> Vec<Diagnostic> {
+    let mut lint = ImplicitGlobals::default();
+    lint.scopes.push(declared_in(&program.body));
+    visit::walk_program(&mut lint, program);
+    lint.diagnostics
+}
+
+/// Collects the names a function body declares, without entering nested
+/// functions
+///
+/// Block-scoped names count for the whole body, which can only hide a
+/// warning, never invent one.
+#[derive(Default)]
+struct Declared(Vec<String>);
+
+impl Visitor for Declared {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::FunctionDecl { name, .. } => {
+                self.0.push(name.clone());
+                return;
+            }
+            Stmt::Try { handler: Some(CatchClause { param: Some(param), .. }), .. } => self.0.push(param.clone()),
+            _ => {}
+        }
+        visit::walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if !matches!(expr, Expr::Function { .. } | Expr::Arrow { .. }) {
+            visit::walk_expr(self, expr);
+        }
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        if let Pattern::Identifier(name, _) = pattern {
+            self.0.push(name.clone());
+        }
+        visit::walk_pattern(self, pattern);
+    }
+}
+
+fn declared_in(body: &[Stmt]) -> Vec<String> {
+    let mut declared = Declared::default();
+    for stmt in body {
+        declared.visit_stmt(stmt);
+    }
+    declared.0
+}
+
+#[derive(Default)]
+struct ImplicitGlobals {
+    /// Names declared by each enclosing function, the program first
+    scopes: Vec<Vec<String>>,
+    /// Undeclared names already assigned somewhere
+    assigned: Vec<String>,
+    /// Inside the initializer or left side of a `for` head
+    in_for_head: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ImplicitGlobals {
+    fn assign(&mut self, name: &str, loc: Span) {
+        if self.scopes.iter().any(|scope| scope.iter().any(|n| n == name))
+            || self.assigned.iter().any(|n| n == name)
+        {
+            return;
+        }
+        self.assigned.push(name.into());
+        if self.in_for_head || self.scopes.len() > 1 {
+            self.diagnostics.push(Diagnostic {
+                code: DiagnosticCode::ImplicitGlobal,
+                message: format!("implicit global '{name}' created by assignment; did you mean 'var {name}'?"),
+                location: loc.start,
+            });
+        }
+    }
+
+    /// Visits a function body with the names it declares in scope
+    fn function(&mut self, declared: Vec<String>, visit_body: impl FnOnce(&mut Self)) {
+        self.scopes.push(declared);
+        let in_for_head = core::mem::replace(&mut self.in_for_head, false);
+        visit_body(self);
+        self.in_for_head = in_for_head;
+        self.scopes.pop();
+    }
+
+    /// Visits the initializer of a `for` or the left side of a `for-in` or
+    /// `for-of`, where `binds` says a plain name is assigned
+    fn for_head(&mut self, init: &ForInit, binds: bool) {
+        let in_for_head = core::mem::replace(&mut self.in_for_head, true);
+        match init {
+            ForInit::VarDecl { declarations, .. } => {
+                for decl in declarations {
+                    self.visit_pattern(&decl.target);
+                    if let Some(init) = &decl.init {
+                        self.visit_expr(init);
+                    }
+                }
+            }
+            ForInit::Expr(Expr::Identifier(name, loc)) if binds => self.assign(name, *loc),
+            ForInit::Expr(expr) => self.visit_expr(expr),
+        }
+        self.in_for_head = in_for_head;
+    }
+}
+
+impl Visitor for ImplicitGlobals {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::FunctionDecl { params, body, .. } => {
+                let mut declared = params.clone();
+                declared.extend(declared_in(body));
+                self.function(declared, |lint| body.iter().for_each(|stmt| lint.visit_stmt(stmt)));
+            }
+            Stmt::For { init, test, update, body, .. } => {
+                if let Some(init) = init {
+                    self.for_head(init, false);
+                }
+                test.iter().chain(update).for_each(|expr| self.visit_expr(expr));
+                self.visit_stmt(body);
+            }
+            Stmt::ForIn { left, right, body, .. } | Stmt::ForOf { left, right, body, .. } => {
+                self.for_head(left, true);
+                self.visit_expr(right);
+                self.visit_stmt(body);
+            }
+            _ => visit::walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Assignment { left, .. } | Expr::Update { arg: left, .. } => {
+                if let Expr::Identifier(name, loc) = &**left {
+                    self.assign(name, *loc);
+                }
+                visit::walk_expr(self, expr);
+            }
+            Expr::Function { name, params, body, .. } => {
+                let mut declared = params.clone();
+                declared.extend(name.iter().cloned());
+                declared.extend(declared_in(body));
+                self.function(declared, |lint| body.iter().for_each(|stmt| lint.visit_stmt(stmt)));
+            }
+            Expr::Arrow { params, body, .. } => {
+                let mut declared = params.clone();
+                if let ArrowBody::Block(stmts) = body {
+                    declared.extend(declared_in(stmts));
+                }
+                self.function(declared, |lint| visit::walk_expr(lint, expr));
+            }
+            _ => visit::walk_expr(self, expr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::Parser;
+
+    fn warnings(source: &str) -> Vec<(String, u32)> {
+        let program = Parser::new(source).parse().unwrap();
+        lint_program(&program)
+            .into_iter()
+            .map(|d| (d.message, d.location.line))
+            .collect()
+    }
+
+    #[test]
+    fn test_implicit_global_in_for_head() {
+        let source = "function count(n) {\n  for (i = 0; i < n; i++) {}\n  return i;\n}";
+        assert_eq!(
+            warnings(source),
+            [("implicit global 'i' created by assignment; did you mean 'var i'?".into(), 2)]
+        );
+        assert_eq!(warnings("for (i = 0; i < 3; i++) {}").len(), 1);
+        assert_eq!(warnings("var o = {}; for (k in o) {}").len(), 1);
+        assert_eq!(warnings("[1].forEach(x => { total = x; });").len(), 1);
+
+        let program = Parser::new("function f() { n = 1; n++; }").parse().unwrap();
+        let diagnostics = lint_program(&program);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code.name(), "implicit-global");
+        assert_eq!(diagnostics[0].location.column, 16);
+    }
+
+    #[test]
+    fn test_declared_and_intentional_globals() {
+        // Declared somewhere that encloses the assignment
+        assert!(warnings("function f(n) { var i; for (i = 0; i < n; i++) {} }").is_empty());
+        assert!(warnings("function f(i) { for (i = 0; i < 3; i++) {} }").is_empty());
+        assert!(warnings("var i; function f() { for (i = 0; i < 3; i++) {} }").is_empty());
+        assert!(warnings("function f() { for (i = 0; i < 3; i++) {} var i; }").is_empty());
+        assert!(warnings("function f() { for (var i = 0; i < 3; i++) {} }").is_empty());
+        assert!(warnings("function f() { try {} catch (e) { e = 1; } }").is_empty());
+        assert!(warnings("function f() {} function g() { f = 1; }").is_empty());
+
+        // A top-level assignment outside a for head is intentional
+        assert!(warnings("count = 0; function inc() { count++; }").is_empty());
+        let (_, diagnostics) = crate::compiler::compile_with_diagnostics("count = 0; for (i = 0; i < 2; i++) {}").unwrap();
+        assert_eq!(diagnostics.iter().map(|d| d.location.column).collect::<Vec<_>>(), [17]);
+
+        // A declaration in a sibling function doesn't count
+        assert_eq!(warnings("function f() { var i; } function g() { i = 1; }").len(), 1);
+    }
+}
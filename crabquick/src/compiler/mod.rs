@@ -1,19 +1,39 @@
 //! JavaScript compiler (lexer, parser, code generator)
+//!
+//! The lexer, parser, AST and [`visit`] module are public for tooling:
+//! `Parser::parse` and `Parser::parse_expression` produce spanned AST nodes,
+//! and `Parser::with_comments` keeps comments in a side table.
+//!
+//! # Determinism
+//!
+//! Compiling is deterministic: the same source, compiler version and
+//! features give byte-identical bytecode, whatever was compiled before and
+//! on whichever thread, so pipelines can diff compiled scripts or compare
+//! `LoadedScript::fingerprint`s. Atoms, constants and functions are
+//! numbered in the order the generator first meets them, constants are
+//! deduplicated by their bits, and no output depends on a hash map's
+//! iteration order or on an address. Keep it that way: maps here are
+//! `BTreeMap`s used only for lookups.
 
 pub mod lexer;
 pub mod ast;
 pub mod parser;
 pub mod codegen;
 pub mod debug;
+pub mod visit;
+pub mod fold;
+pub mod lint;
 
 use alloc::string::String;
 use alloc::vec::Vec;
 
 // Re-exports
-pub use lexer::{Lexer, Token, TokenKind, SourceLocation};
+pub use lexer::{Comment, Lexer, Token, TokenKind, SourceLocation, Span};
 pub use ast::{Expr, Stmt, Program, Literal, BinaryOp, UnaryOp};
+pub use visit::Visitor;
 pub use parser::{Parser, ParseError};
 pub use codegen::{CodeGenerator, CodeGenError};
+pub use lint::{Diagnostic, DiagnosticCode};
 
 /// Compilation error
 #[derive(Debug, Clone, PartialEq)]
@@ -53,9 +73,20 @@ impl From<CodeGenError> for CompileError {
 /// let bytecode = compile("2 + 3")?;
 /// ```
 pub fn compile(source: &str) -> Result<Vec<u8>, CompileError> {
+    compile_from_line(source, 0)
+}
+
+/// Compiles source that starts on line `line_offset + 1` of its file
+///
+/// Error locations and the line table count from there, so both compile
+/// and runtime errors name lines of the whole file.
+pub fn compile_from_line(source: &str, line_offset: u32) -> Result<Vec<u8>, CompileError> {
     // Parse source into AST
-    let parser = Parser::new(source);
-    let program = parser.parse()?;
+    let parser = Parser::with_line_offset(source, line_offset);
+    let mut program = parser.parse()?;
+
+    // Fold constants and drop dead branches
+    fold::fold_program(&mut program);
 
     // Generate bytecode from AST
     let generator = CodeGenerator::new();
@@ -63,3 +94,46 @@ pub fn compile(source: &str) -> Result<Vec<u8>, CompileError> {
 
     Ok(bytecode)
 }
+
+/// Compiles source code, returning the warnings found along with the
+/// bytecode
+///
+/// Warnings never fail the compile; see [`lint`] for what is checked.
+pub fn compile_with_diagnostics(source: &str) -> Result<(Vec<u8>, Vec<Diagnostic>), CompileError> {
+    let mut program = Parser::new(source).parse()?;
+    let diagnostics = lint::lint_program(&program);
+    fold::fold_program(&mut program);
+    let bytecode = CodeGenerator::new().generate(&program)?;
+    Ok((bytecode, diagnostics))
+}
+
+/// Compiles a single expression into a unit that returns its value
+///
+/// Unlike a program, the source can't hold statements, and `{ a: 1 }`
+/// is an object literal rather than a block, as a REPL wants.
+pub fn compile_expression(source: &str, line_offset: u32) -> Result<Vec<u8>, CompileError> {
+    let expr = fold::fold_expr(Parser::with_line_offset(source, line_offset).parse_expression()?);
+    let loc = expr.span();
+    let program = Program::new(alloc::vec![Stmt::Expression { expr, loc }]);
+    Ok(CodeGenerator::new().generate(&program)?)
+}
+
+/// Compiles source code no longer than `max_source_bytes`
+///
+/// Longer sources are rejected before lexing, so a host compiling scripts
+/// from untrusted users bounds the work any one compile can take.
+pub fn compile_bounded(source: &str, max_source_bytes: usize) -> Result<Vec<u8>, CompileError> {
+    check_source_size(source, max_source_bytes)?;
+    compile(source)
+}
+
+/// Rejects sources longer than `max_source_bytes`
+pub(crate) fn check_source_size(source: &str, max_source_bytes: usize) -> Result<(), CompileError> {
+    if source.len() > max_source_bytes {
+        return Err(CompileError::Parse(ParseError {
+            message: alloc::format!("Source too large: {} bytes (limit {})", source.len(), max_source_bytes),
+            location: SourceLocation::new(0, 0, 0),
+        }));
+    }
+    Ok(())
+}
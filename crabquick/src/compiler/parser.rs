@@ -8,7 +8,7 @@ use alloc::string::{String, ToString};
 use alloc::vec;
 use alloc::vec::Vec;
 
-use super::lexer::{Lexer, Token, TokenKind, SourceLocation};
+use super::lexer::{Comment, Lexer, Token, TokenKind, SourceLocation, Span};
 use super::ast::*;
 
 /// Parse error
@@ -32,18 +32,36 @@ pub struct Parser<'a> {
     lexer: Lexer<'a>,
     current: Token,
     peeked: Option<Token>,
+    /// End of the last token consumed, where the node being parsed ends
+    prev_end: SourceLocation,
 }
 
 impl<'a> Parser<'a> {
     /// Creates a new parser
     pub fn new(source: &'a str) -> Self {
-        let mut lexer = Lexer::new(source);
+        Self::with_lexer(Lexer::new(source))
+    }
+
+    /// Creates a parser that keeps comments, for
+    /// [`parse_with_comments`](Self::parse_with_comments)
+    pub fn with_comments(source: &'a str) -> Self {
+        Self::with_lexer(Lexer::with_comments(source))
+    }
+
+    /// Creates a parser whose locations count lines from `line_offset + 1`
+    pub fn with_line_offset(source: &'a str, line_offset: u32) -> Self {
+        Self::with_lexer(Lexer::with_line_offset(source, line_offset))
+    }
+
+    fn with_lexer(mut lexer: Lexer<'a>) -> Self {
         let current = lexer.next_token();
+        let prev_end = current.location;
 
         Parser {
             lexer,
             current,
             peeked: None,
+            prev_end,
         }
     }
 
@@ -58,6 +76,36 @@ impl<'a> Parser<'a> {
         Ok(Program::new(body))
     }
 
+    /// Parses the source into a Program, along with its comments in source
+    /// order
+    ///
+    /// Comments are only collected by a parser made with
+    /// [`with_comments`](Self::with_comments).
+    pub fn parse_with_comments(mut self) -> ParseResult<(Program, Vec<Comment>)> {
+        let mut body = Vec::new();
+
+        while !self.is_eof() {
+            body.push(self.parse_statement()?);
+        }
+
+        Ok((Program::new(body), self.lexer.take_comments()))
+    }
+
+    /// Parses the source as a single expression
+    ///
+    /// The whole source has to be one expression (commas allowed); anything
+    /// left over is an error.
+    pub fn parse_expression(mut self) -> ParseResult<Expr> {
+        let expr = self.parse_sequence_expression()?;
+        if !self.is_eof() {
+            return Err(ParseError::new(
+                format!("Unexpected {:?} after expression", self.current.kind),
+                self.current.location,
+            ));
+        }
+        Ok(expr)
+    }
+
     /// Returns the current token
     fn current_token(&self) -> &Token {
         &self.current
@@ -78,6 +126,7 @@ impl<'a> Parser<'a> {
 
     /// Advances to the next token
     fn advance(&mut self) {
+        self.prev_end = self.current.end;
         self.current = if let Some(peeked) = self.peeked.take() {
             peeked
         } else {
@@ -85,6 +134,11 @@ impl<'a> Parser<'a> {
         };
     }
 
+    /// Returns the span from `start` to the end of the last token consumed
+    fn span_from(&self, start: SourceLocation) -> Span {
+        Span::new(start, self.prev_end)
+    }
+
     /// Checks if we're at EOF
     fn is_eof(&self) -> bool {
         matches!(self.current.kind, TokenKind::Eof)
@@ -159,7 +213,7 @@ impl<'a> Parser<'a> {
             }
             TokenKind::Semicolon => {
                 self.advance();
-                Ok(Stmt::Empty { loc })
+                Ok(Stmt::Empty { loc: self.span_from(loc) })
             }
             TokenKind::Identifier(_) => {
                 // Check if this is a labeled statement (identifier followed by colon)
@@ -168,16 +222,16 @@ impl<'a> Parser<'a> {
                     self.parse_labeled_statement()
                 } else {
                     // Expression statement
-                    let expr = self.parse_expression()?;
+                    let expr = self.parse_sequence_expression()?;
                     self.consume_semicolon();
-                    Ok(Stmt::Expression { expr, loc })
+                    Ok(Stmt::Expression { expr, loc: self.span_from(loc) })
                 }
             }
             _ => {
                 // Expression statement
-                let expr = self.parse_expression()?;
+                let expr = self.parse_sequence_expression()?;
                 self.consume_semicolon();
-                Ok(Stmt::Expression { expr, loc })
+                Ok(Stmt::Expression { expr, loc: self.span_from(loc) })
             }
         }
     }
@@ -197,7 +251,7 @@ impl<'a> Parser<'a> {
         let mut declarations = Vec::new();
 
         loop {
-            let name = self.parse_identifier()?;
+            let target = self.parse_binding_pattern()?;
 
             let init = if self.consume_if(&TokenKind::Assign) {
                 Some(self.parse_assignment_expression()?)
@@ -205,7 +259,9 @@ impl<'a> Parser<'a> {
                 None
             };
 
-            declarations.push(VarDeclarator { name, init });
+            let decl_loc = self.span_from(target.span().start);
+            declarations.push(VarDeclarator { target, init, loc: decl_loc });
+            self.check_declarator_init(declarations.last().unwrap(), loc)?;
 
             if !self.consume_if(&TokenKind::Comma) {
                 break;
@@ -214,7 +270,7 @@ impl<'a> Parser<'a> {
 
         self.consume_semicolon();
 
-        Ok(Stmt::VarDecl { kind, declarations, loc })
+        Ok(Stmt::VarDecl { kind, declarations, loc: self.span_from(loc) })
     }
 
     /// Parses a function declaration
@@ -232,7 +288,7 @@ impl<'a> Parser<'a> {
         let body = self.parse_statement_list()?;
         self.expect(TokenKind::RBrace)?;
 
-        Ok(Stmt::FunctionDecl { name, params, body, loc })
+        Ok(Stmt::FunctionDecl { name, params, body, loc: self.span_from(loc) })
     }
 
     /// Parses an if statement
@@ -241,7 +297,7 @@ impl<'a> Parser<'a> {
         self.expect(TokenKind::If)?;
 
         self.expect(TokenKind::LParen)?;
-        let test = self.parse_expression()?;
+        let test = self.parse_sequence_expression()?;
         self.expect(TokenKind::RParen)?;
 
         let consequent = Box::new(self.parse_statement()?);
@@ -252,7 +308,7 @@ impl<'a> Parser<'a> {
             None
         };
 
-        Ok(Stmt::If { test, consequent, alternate, loc })
+        Ok(Stmt::If { test, consequent, alternate, loc: self.span_from(loc) })
     }
 
     /// Parses a while statement
@@ -261,12 +317,12 @@ impl<'a> Parser<'a> {
         self.expect(TokenKind::While)?;
 
         self.expect(TokenKind::LParen)?;
-        let test = self.parse_expression()?;
+        let test = self.parse_sequence_expression()?;
         self.expect(TokenKind::RParen)?;
 
         let body = Box::new(self.parse_statement()?);
 
-        Ok(Stmt::While { test, body, loc })
+        Ok(Stmt::While { test, body, loc: self.span_from(loc) })
     }
 
     /// Parses a do-while statement
@@ -278,12 +334,12 @@ impl<'a> Parser<'a> {
 
         self.expect(TokenKind::While)?;
         self.expect(TokenKind::LParen)?;
-        let test = self.parse_expression()?;
+        let test = self.parse_sequence_expression()?;
         self.expect(TokenKind::RParen)?;
 
         self.consume_semicolon();
 
-        Ok(Stmt::DoWhile { body, test, loc })
+        Ok(Stmt::DoWhile { body, test, loc: self.span_from(loc) })
     }
 
     /// Parses a for statement
@@ -297,6 +353,7 @@ impl<'a> Parser<'a> {
         let init = if self.consume_if(&TokenKind::Semicolon) {
             None
         } else if matches!(self.current.kind, TokenKind::Var | TokenKind::Let | TokenKind::Const) {
+            let init_loc = self.current.location;
             let kind = match &self.current.kind {
                 TokenKind::Var => VarKind::Var,
                 TokenKind::Let => VarKind::Let,
@@ -307,49 +364,47 @@ impl<'a> Parser<'a> {
 
             let mut declarations = Vec::new();
             loop {
-                let name = self.parse_identifier()?;
+                let target = self.parse_binding_pattern()?;
                 let init_expr = if self.consume_if(&TokenKind::Assign) {
                     Some(self.parse_assignment_expression()?)
                 } else {
                     None
                 };
-                declarations.push(VarDeclarator { name, init: init_expr });
+                let decl_loc = self.span_from(target.span().start);
+                declarations.push(VarDeclarator { target, init: init_expr, loc: decl_loc });
 
                 if !self.consume_if(&TokenKind::Comma) {
                     break;
                 }
             }
 
+            let left = ForInit::VarDecl { kind, declarations, loc: self.span_from(init_loc) };
+
             // Check for for-in
             if self.consume_if(&TokenKind::In) {
-                let right = self.parse_expression()?;
+                let right = self.parse_sequence_expression()?;
                 self.expect(TokenKind::RParen)?;
                 let body = Box::new(self.parse_statement()?);
 
-                return Ok(Stmt::ForIn {
-                    left: ForInit::VarDecl { kind, declarations },
-                    right,
-                    body,
-                    loc,
-                });
+                return Ok(Stmt::ForIn { left, right, body, loc: self.span_from(loc) });
             }
 
             // Check for for-of
             if self.consume_if(&TokenKind::Of) {
-                let right = self.parse_expression()?;
+                let right = self.parse_sequence_expression()?;
                 self.expect(TokenKind::RParen)?;
                 let body = Box::new(self.parse_statement()?);
 
-                return Ok(Stmt::ForOf {
-                    left: ForInit::VarDecl { kind, declarations },
-                    right,
-                    body,
-                    loc,
-                });
+                return Ok(Stmt::ForOf { left, right, body, loc: self.span_from(loc) });
             }
 
+            if let ForInit::VarDecl { declarations, .. } = &left {
+                for decl in declarations {
+                    self.check_declarator_init(decl, loc)?;
+                }
+            }
             self.expect(TokenKind::Semicolon)?;
-            Some(ForInit::VarDecl { kind, declarations })
+            Some(left)
         } else if self.current.kind != TokenKind::Semicolon {
             // For for-in/for-of, we need to parse LHS without treating 'in' as binary operator
             // First, try to parse as a simple LHS (identifier or member expression)
@@ -357,7 +412,7 @@ impl<'a> Parser<'a> {
 
             // Check for for-in
             if self.consume_if(&TokenKind::In) {
-                let right = self.parse_expression()?;
+                let right = self.parse_sequence_expression()?;
                 self.expect(TokenKind::RParen)?;
                 let body = Box::new(self.parse_statement()?);
 
@@ -365,13 +420,13 @@ impl<'a> Parser<'a> {
                     left: ForInit::Expr(expr),
                     right,
                     body,
-                    loc,
+                    loc: self.span_from(loc),
                 });
             }
 
             // Check for for-of
             if self.consume_if(&TokenKind::Of) {
-                let right = self.parse_expression()?;
+                let right = self.parse_sequence_expression()?;
                 self.expect(TokenKind::RParen)?;
                 let body = Box::new(self.parse_statement()?);
 
@@ -379,7 +434,7 @@ impl<'a> Parser<'a> {
                     left: ForInit::Expr(expr),
                     right,
                     body,
-                    loc,
+                    loc: self.span_from(loc),
                 });
             }
 
@@ -397,7 +452,7 @@ impl<'a> Parser<'a> {
         let test = if self.consume_if(&TokenKind::Semicolon) {
             None
         } else {
-            let t = Some(self.parse_expression()?);
+            let t = Some(self.parse_sequence_expression()?);
             self.expect(TokenKind::Semicolon)?;
             t
         };
@@ -406,14 +461,14 @@ impl<'a> Parser<'a> {
         let update = if self.consume_if(&TokenKind::RParen) {
             None
         } else {
-            let u = Some(self.parse_expression()?);
+            let u = Some(self.parse_sequence_expression()?);
             self.expect(TokenKind::RParen)?;
             u
         };
 
         let body = Box::new(self.parse_statement()?);
 
-        Ok(Stmt::For { init, test, update, body, loc })
+        Ok(Stmt::For { init, test, update, body, loc: self.span_from(loc) })
     }
 
     /// Parses a return statement
@@ -427,12 +482,12 @@ impl<'a> Parser<'a> {
             || matches!(self.current.kind, TokenKind::RBrace | TokenKind::Eof) {
             None
         } else {
-            let arg = Some(self.parse_expression()?);
+            let arg = Some(self.parse_sequence_expression()?);
             self.consume_semicolon();
             arg
         };
 
-        Ok(Stmt::Return { argument, loc })
+        Ok(Stmt::Return { argument, loc: self.span_from(loc) })
     }
 
     /// Parses a break statement
@@ -449,7 +504,7 @@ impl<'a> Parser<'a> {
 
         self.consume_semicolon();
 
-        Ok(Stmt::Break { label, loc })
+        Ok(Stmt::Break { label, loc: self.span_from(loc) })
     }
 
     /// Parses a continue statement
@@ -466,7 +521,7 @@ impl<'a> Parser<'a> {
 
         self.consume_semicolon();
 
-        Ok(Stmt::Continue { label, loc })
+        Ok(Stmt::Continue { label, loc: self.span_from(loc) })
     }
 
     /// Parses a labeled statement (label: statement)
@@ -482,7 +537,7 @@ impl<'a> Parser<'a> {
         // Parse the body statement
         let body = Box::new(self.parse_statement()?);
 
-        Ok(Stmt::Labeled { label, body, loc })
+        Ok(Stmt::Labeled { label, body, loc: self.span_from(loc) })
     }
 
     /// Parses a throw statement
@@ -490,10 +545,10 @@ impl<'a> Parser<'a> {
         let loc = self.current.location;
         self.expect(TokenKind::Throw)?;
 
-        let argument = self.parse_expression()?;
+        let argument = self.parse_sequence_expression()?;
         self.consume_semicolon();
 
-        Ok(Stmt::Throw { argument, loc })
+        Ok(Stmt::Throw { argument, loc: self.span_from(loc) })
     }
 
     /// Parses a try statement
@@ -505,7 +560,9 @@ impl<'a> Parser<'a> {
         let block = self.parse_statement_list()?;
         self.expect(TokenKind::RBrace)?;
 
-        let handler = if self.consume_if(&TokenKind::Catch) {
+        let handler = if matches!(self.current.kind, TokenKind::Catch) {
+            let catch_loc = self.current.location;
+            self.advance();
             let param = if self.consume_if(&TokenKind::LParen) {
                 let p = Some(self.parse_identifier()?);
                 self.expect(TokenKind::RParen)?;
@@ -518,7 +575,7 @@ impl<'a> Parser<'a> {
             let body = self.parse_statement_list()?;
             self.expect(TokenKind::RBrace)?;
 
-            Some(CatchClause { param, body })
+            Some(CatchClause { param, body, loc: self.span_from(catch_loc) })
         } else {
             None
         };
@@ -539,7 +596,7 @@ impl<'a> Parser<'a> {
             ));
         }
 
-        Ok(Stmt::Try { block, handler, finalizer, loc })
+        Ok(Stmt::Try { block, handler, finalizer, loc: self.span_from(loc) })
     }
 
     /// Parses a switch statement
@@ -548,7 +605,7 @@ impl<'a> Parser<'a> {
         self.expect(TokenKind::Switch)?;
 
         self.expect(TokenKind::LParen)?;
-        let discriminant = self.parse_expression()?;
+        let discriminant = self.parse_sequence_expression()?;
         self.expect(TokenKind::RParen)?;
 
         self.expect(TokenKind::LBrace)?;
@@ -556,8 +613,9 @@ impl<'a> Parser<'a> {
         let mut cases = Vec::new();
 
         while !self.consume_if(&TokenKind::RBrace) {
+            let case_loc = self.current.location;
             let test = if self.consume_if(&TokenKind::Case) {
-                Some(self.parse_expression()?)
+                Some(self.parse_sequence_expression()?)
             } else if self.consume_if(&TokenKind::Default) {
                 None
             } else {
@@ -574,10 +632,10 @@ impl<'a> Parser<'a> {
                 consequent.push(self.parse_statement()?);
             }
 
-            cases.push(SwitchCase { test, consequent });
+            cases.push(SwitchCase { test, consequent, loc: self.span_from(case_loc) });
         }
 
-        Ok(Stmt::Switch { discriminant, cases, loc })
+        Ok(Stmt::Switch { discriminant, cases, loc: self.span_from(loc) })
     }
 
     /// Parses a block statement
@@ -587,7 +645,7 @@ impl<'a> Parser<'a> {
         let stmts = self.parse_statement_list()?;
         self.expect(TokenKind::RBrace)?;
 
-        Ok(Stmt::Block { stmts, loc })
+        Ok(Stmt::Block { stmts, loc: self.span_from(loc) })
     }
 
     /// Parses a list of statements (until })
@@ -622,6 +680,40 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Parses a declaration target: an identifier or an array pattern
+    fn parse_binding_pattern(&mut self) -> ParseResult<Pattern> {
+        let loc = self.current.location;
+        if !self.consume_if(&TokenKind::LBracket) {
+            let name = self.parse_identifier()?;
+            return Ok(Pattern::Identifier(name, self.span_from(loc)));
+        }
+
+        let mut elements = Vec::new();
+        while !self.consume_if(&TokenKind::RBracket) {
+            if self.consume_if(&TokenKind::Comma) {
+                elements.push(None);
+                continue;
+            }
+            elements.push(Some(self.parse_binding_pattern()?));
+            if !self.consume_if(&TokenKind::Comma) {
+                self.expect(TokenKind::RBracket)?;
+                break;
+            }
+        }
+
+        Ok(Pattern::Array(elements, self.span_from(loc)))
+    }
+
+    /// Rejects a destructuring declarator without an initializer
+    ///
+    /// Only for-in and for-of heads may leave one out.
+    fn check_declarator_init(&self, decl: &VarDeclarator, loc: SourceLocation) -> ParseResult<()> {
+        if decl.init.is_none() && decl.target.as_identifier().is_none() {
+            return Err(ParseError::new("Missing initializer in destructuring declaration".to_string(), loc));
+        }
+        Ok(())
+    }
+
     /// Parses a property name (identifier or reserved word)
     /// Used for member access after dot and object property keys
     fn parse_property_name(&mut self) -> ParseResult<String> {
@@ -667,11 +759,6 @@ impl<'a> Parser<'a> {
 
     // ===== Expression Parsing =====
 
-    /// Parses an expression
-    fn parse_expression(&mut self) -> ParseResult<Expr> {
-        self.parse_sequence_expression()
-    }
-
     /// Parses a left-hand-side expression (for for-in/for-of loop variable)
     /// This parses identifier, member access, call expressions but NOT binary operators
     fn parse_left_hand_side_expression(&mut self) -> ParseResult<Expr> {
@@ -687,6 +774,7 @@ impl<'a> Parser<'a> {
             TokenKind::PlusAssign => Some(AssignOp::AddAssign),
             TokenKind::MinusAssign => Some(AssignOp::SubAssign),
             TokenKind::StarAssign => Some(AssignOp::MulAssign),
+            TokenKind::StarStarAssign => Some(AssignOp::PowAssign),
             TokenKind::SlashAssign => Some(AssignOp::DivAssign),
             TokenKind::PercentAssign => Some(AssignOp::ModAssign),
             TokenKind::AmpersandAssign => Some(AssignOp::BitAndAssign),
@@ -695,18 +783,22 @@ impl<'a> Parser<'a> {
             TokenKind::LtLtAssign => Some(AssignOp::LeftShiftAssign),
             TokenKind::GtGtAssign => Some(AssignOp::RightShiftAssign),
             TokenKind::GtGtGtAssign => Some(AssignOp::UnsignedRightShiftAssign),
+            TokenKind::LogicalAndAssign => Some(AssignOp::LogicalAndAssign),
+            TokenKind::LogicalOrAssign => Some(AssignOp::LogicalOrAssign),
+            TokenKind::NullishAssign => Some(AssignOp::NullishAssign),
             _ => None,
         };
 
         if let Some(op) = assign_op {
-            let loc = self.current.location;
+            Self::check_assignment_target(&left, self.current.location)?;
             self.advance();
+            let loc = left.location();
             let right = self.parse_assignment_expression()?;
             return Ok(Expr::Assignment {
                 op,
                 left: Box::new(left),
                 right: Box::new(right),
-                loc,
+                loc: self.span_from(loc),
             });
         }
 
@@ -767,7 +859,7 @@ impl<'a> Parser<'a> {
                 op,
                 left: Box::new(result),
                 right: Box::new(right),
-                loc,
+                loc: self.span_from(loc),
             };
         }
 
@@ -780,7 +872,7 @@ impl<'a> Parser<'a> {
                 test: Box::new(result),
                 consequent: Box::new(consequent),
                 alternate: Box::new(alternate),
-                loc,
+                loc: self.span_from(loc),
             };
         }
 
@@ -799,7 +891,7 @@ impl<'a> Parser<'a> {
         if exprs.len() == 1 {
             Ok(exprs.into_iter().next().unwrap())
         } else {
-            Ok(Expr::Sequence { exprs, loc })
+            Ok(Expr::Sequence { exprs, loc: self.span_from(loc) })
         }
     }
 
@@ -809,9 +901,10 @@ impl<'a> Parser<'a> {
 
         // Try arrow function
         if matches!(self.current.kind, TokenKind::Identifier(_)) {
-            let checkpoint_pos = self.lexer.pc();
+            let checkpoint_lexer = self.lexer.checkpoint();
             let checkpoint_current = self.current.clone();
             let checkpoint_peeked = self.peeked.clone();
+            let checkpoint_end = self.prev_end;
 
             if let Ok(name) = self.parse_identifier() {
                 if self.consume_if(&TokenKind::Arrow) {
@@ -819,15 +912,16 @@ impl<'a> Parser<'a> {
                     return Ok(Expr::Arrow {
                         params: alloc::vec![name],
                         body,
-                        loc,
+                        loc: self.span_from(loc),
                     });
                 }
             }
 
             // Restore state
-            self.lexer.set_pc(checkpoint_pos);
+            self.lexer.restore(checkpoint_lexer);
             self.current = checkpoint_current;
             self.peeked = checkpoint_peeked;
+            self.prev_end = checkpoint_end;
         }
 
         // Try conditional
@@ -839,6 +933,7 @@ impl<'a> Parser<'a> {
             TokenKind::PlusAssign => AssignOp::AddAssign,
             TokenKind::MinusAssign => AssignOp::SubAssign,
             TokenKind::StarAssign => AssignOp::MulAssign,
+            TokenKind::StarStarAssign => AssignOp::PowAssign,
             TokenKind::SlashAssign => AssignOp::DivAssign,
             TokenKind::PercentAssign => AssignOp::ModAssign,
             TokenKind::LtLtAssign => AssignOp::LeftShiftAssign,
@@ -847,9 +942,13 @@ impl<'a> Parser<'a> {
             TokenKind::AmpersandAssign => AssignOp::BitAndAssign,
             TokenKind::PipeAssign => AssignOp::BitOrAssign,
             TokenKind::CaretAssign => AssignOp::BitXorAssign,
+            TokenKind::LogicalAndAssign => AssignOp::LogicalAndAssign,
+            TokenKind::LogicalOrAssign => AssignOp::LogicalOrAssign,
+            TokenKind::NullishAssign => AssignOp::NullishAssign,
             _ => return Ok(expr),
         };
 
+        Self::check_assignment_target(&expr, self.current.location)?;
         self.advance();
         let right = Box::new(self.parse_assignment_expression()?);
 
@@ -857,10 +956,18 @@ impl<'a> Parser<'a> {
             op,
             left: Box::new(expr),
             right,
-            loc,
+            loc: self.span_from(loc),
         })
     }
 
+    /// Rejects assignments to optional chains (`a?.b = 1`)
+    fn check_assignment_target(target: &Expr, loc: SourceLocation) -> ParseResult<()> {
+        if matches!(target, Expr::OptionalChain { .. }) {
+            return Err(ParseError::new("Invalid left-hand side in assignment".to_string(), loc));
+        }
+        Ok(())
+    }
+
     /// Parses a conditional expression (ternary)
     fn parse_conditional_expression(&mut self) -> ParseResult<Expr> {
         let loc = self.current.location;
@@ -875,7 +982,7 @@ impl<'a> Parser<'a> {
                 test: Box::new(test),
                 consequent,
                 alternate,
-                loc,
+                loc: self.span_from(loc),
             })
         } else {
             Ok(test)
@@ -900,7 +1007,7 @@ impl<'a> Parser<'a> {
                 op,
                 left: Box::new(left),
                 right,
-                loc,
+                loc: self.span_from(loc),
             };
         }
 
@@ -918,7 +1025,7 @@ impl<'a> Parser<'a> {
                 op: BinaryOp::LogicalAnd,
                 left: Box::new(left),
                 right,
-                loc,
+                loc: self.span_from(loc),
             };
         }
 
@@ -936,7 +1043,7 @@ impl<'a> Parser<'a> {
                 op: BinaryOp::BitOr,
                 left: Box::new(left),
                 right,
-                loc,
+                loc: self.span_from(loc),
             };
         }
 
@@ -954,7 +1061,7 @@ impl<'a> Parser<'a> {
                 op: BinaryOp::BitXor,
                 left: Box::new(left),
                 right,
-                loc,
+                loc: self.span_from(loc),
             };
         }
 
@@ -972,7 +1079,7 @@ impl<'a> Parser<'a> {
                 op: BinaryOp::BitAnd,
                 left: Box::new(left),
                 right,
-                loc,
+                loc: self.span_from(loc),
             };
         }
 
@@ -999,7 +1106,7 @@ impl<'a> Parser<'a> {
                 op,
                 left: Box::new(left),
                 right,
-                loc,
+                loc: self.span_from(loc),
             };
         }
 
@@ -1028,7 +1135,7 @@ impl<'a> Parser<'a> {
                 op,
                 left: Box::new(left),
                 right,
-                loc,
+                loc: self.span_from(loc),
             };
         }
 
@@ -1054,7 +1161,7 @@ impl<'a> Parser<'a> {
                 op,
                 left: Box::new(left),
                 right,
-                loc,
+                loc: self.span_from(loc),
             };
         }
 
@@ -1079,7 +1186,7 @@ impl<'a> Parser<'a> {
                 op,
                 left: Box::new(left),
                 right,
-                loc,
+                loc: self.span_from(loc),
             };
         }
 
@@ -1105,7 +1212,7 @@ impl<'a> Parser<'a> {
                 op,
                 left: Box::new(left),
                 right,
-                loc,
+                loc: self.span_from(loc),
             };
         }
 
@@ -1123,7 +1230,7 @@ impl<'a> Parser<'a> {
                 op: BinaryOp::Pow,
                 left: Box::new(left),
                 right,
-                loc,
+                loc: self.span_from(loc),
             })
         } else {
             Ok(left)
@@ -1141,7 +1248,7 @@ impl<'a> Parser<'a> {
                     op: UnaryOp::Plus,
                     arg: Box::new(self.parse_unary_expression()?),
                     prefix: true,
-                    loc,
+                    loc: self.span_from(loc),
                 })
             }
             TokenKind::Minus => {
@@ -1150,7 +1257,7 @@ impl<'a> Parser<'a> {
                     op: UnaryOp::Minus,
                     arg: Box::new(self.parse_unary_expression()?),
                     prefix: true,
-                    loc,
+                    loc: self.span_from(loc),
                 })
             }
             TokenKind::Bang => {
@@ -1159,7 +1266,7 @@ impl<'a> Parser<'a> {
                     op: UnaryOp::LogicalNot,
                     arg: Box::new(self.parse_unary_expression()?),
                     prefix: true,
-                    loc,
+                    loc: self.span_from(loc),
                 })
             }
             TokenKind::Tilde => {
@@ -1168,7 +1275,7 @@ impl<'a> Parser<'a> {
                     op: UnaryOp::BitwiseNot,
                     arg: Box::new(self.parse_unary_expression()?),
                     prefix: true,
-                    loc,
+                    loc: self.span_from(loc),
                 })
             }
             TokenKind::TypeOf => {
@@ -1177,7 +1284,7 @@ impl<'a> Parser<'a> {
                     op: UnaryOp::TypeOf,
                     arg: Box::new(self.parse_unary_expression()?),
                     prefix: true,
-                    loc,
+                    loc: self.span_from(loc),
                 })
             }
             TokenKind::Void => {
@@ -1186,7 +1293,7 @@ impl<'a> Parser<'a> {
                     op: UnaryOp::Void,
                     arg: Box::new(self.parse_unary_expression()?),
                     prefix: true,
-                    loc,
+                    loc: self.span_from(loc),
                 })
             }
             TokenKind::Delete => {
@@ -1195,7 +1302,7 @@ impl<'a> Parser<'a> {
                     op: UnaryOp::Delete,
                     arg: Box::new(self.parse_unary_expression()?),
                     prefix: true,
-                    loc,
+                    loc: self.span_from(loc),
                 })
             }
             TokenKind::PlusPlus => {
@@ -1204,7 +1311,7 @@ impl<'a> Parser<'a> {
                     op: UpdateOp::Inc,
                     arg: Box::new(self.parse_unary_expression()?),
                     prefix: true,
-                    loc,
+                    loc: self.span_from(loc),
                 })
             }
             TokenKind::MinusMinus => {
@@ -1213,7 +1320,7 @@ impl<'a> Parser<'a> {
                     op: UpdateOp::Dec,
                     arg: Box::new(self.parse_unary_expression()?),
                     prefix: true,
-                    loc,
+                    loc: self.span_from(loc),
                 })
             }
             _ => self.parse_postfix_expression(),
@@ -1232,7 +1339,7 @@ impl<'a> Parser<'a> {
                     op: UpdateOp::Inc,
                     arg: Box::new(expr),
                     prefix: false,
-                    loc,
+                    loc: self.span_from(loc),
                 })
             }
             TokenKind::MinusMinus => {
@@ -1241,7 +1348,7 @@ impl<'a> Parser<'a> {
                     op: UpdateOp::Dec,
                     arg: Box::new(expr),
                     prefix: false,
-                    loc,
+                    loc: self.span_from(loc),
                 })
             }
             _ => Ok(expr),
@@ -1250,16 +1357,17 @@ impl<'a> Parser<'a> {
 
     /// Parses a call expression
     fn parse_call_expression(&mut self) -> ParseResult<Expr> {
-        let mut expr = if self.consume_if(&TokenKind::New) {
+        let loc = self.current.location;
+        let mut expr = if matches!(self.current.kind, TokenKind::New) {
             self.parse_new_expression()?
         } else {
             self.parse_member_expression()?
         };
+        let mut in_chain = false;
 
         loop {
             match &self.current.kind {
                 TokenKind::LParen => {
-                    let loc = self.current.location;
                     self.advance();
                     let args = self.parse_argument_list()?;
                     self.expect(TokenKind::RParen)?;
@@ -1267,44 +1375,92 @@ impl<'a> Parser<'a> {
                     expr = Expr::Call {
                         callee: Box::new(expr),
                         args,
-                        loc,
+                        optional: false,
+                        loc: self.span_from(loc),
                     };
                 }
                 TokenKind::Dot => {
-                    let loc = self.current.location;
                     self.advance();
-                    let property = self.parse_property_name()?;
+                    let property = self.parse_member_property()?;
 
                     expr = Expr::Member {
                         object: Box::new(expr),
-                        property: Box::new(Expr::Identifier(property, loc)),
+                        property: Box::new(property),
                         computed: false,
-                        loc,
+                        optional: false,
+                        loc: self.span_from(loc),
                     };
                 }
                 TokenKind::LBracket => {
-                    let loc = self.current.location;
                     self.advance();
-                    let property = self.parse_expression()?;
+                    let property = self.parse_sequence_expression()?;
                     self.expect(TokenKind::RBracket)?;
 
                     expr = Expr::Member {
                         object: Box::new(expr),
                         property: Box::new(property),
                         computed: true,
-                        loc,
+                        optional: false,
+                        loc: self.span_from(loc),
                     };
                 }
+                TokenKind::QuestionDot => {
+                    self.advance();
+                    in_chain = true;
+
+                    expr = match self.current.kind {
+                        TokenKind::LParen => {
+                            self.advance();
+                            let args = self.parse_argument_list()?;
+                            self.expect(TokenKind::RParen)?;
+                            Expr::Call { callee: Box::new(expr), args, optional: true, loc: self.span_from(loc) }
+                        }
+                        TokenKind::LBracket => {
+                            self.advance();
+                            let property = self.parse_sequence_expression()?;
+                            self.expect(TokenKind::RBracket)?;
+                            Expr::Member {
+                                object: Box::new(expr),
+                                property: Box::new(property),
+                                computed: true,
+                                optional: true,
+                                loc: self.span_from(loc),
+                            }
+                        }
+                        _ => {
+                            let property = self.parse_member_property()?;
+                            Expr::Member {
+                                object: Box::new(expr),
+                                property: Box::new(property),
+                                computed: false,
+                                optional: true,
+                                loc: self.span_from(loc),
+                            }
+                        }
+                    };
+                }
+                TokenKind::NoSubstitutionTemplate(_) | TokenKind::TemplateHead(_) => {
+                    return Err(ParseError::new(
+                        "Tagged templates are not supported".to_string(),
+                        self.current.location,
+                    ));
+                }
                 _ => break,
             }
         }
 
+        // The whole chain short-circuits from any of its `?.` links
+        if in_chain {
+            expr = Expr::OptionalChain { expr: Box::new(expr), loc: self.span_from(loc) };
+        }
+
         Ok(expr)
     }
 
     /// Parses a new expression
     fn parse_new_expression(&mut self) -> ParseResult<Expr> {
         let loc = self.current.location;
+        self.expect(TokenKind::New)?;
         let callee = Box::new(self.parse_member_expression()?);
 
         let args = if self.consume_if(&TokenKind::LParen) {
@@ -1315,40 +1471,47 @@ impl<'a> Parser<'a> {
             Vec::new()
         };
 
-        Ok(Expr::New { callee, args, loc })
+        Ok(Expr::New { callee, args, loc: self.span_from(loc) })
     }
 
     /// Parses a member expression
     fn parse_member_expression(&mut self) -> ParseResult<Expr> {
+        let loc = self.current.location;
         let mut expr = self.parse_primary_expression()?;
 
         loop {
             match &self.current.kind {
                 TokenKind::Dot => {
-                    let loc = self.current.location;
                     self.advance();
-                    let property = self.parse_property_name()?;
+                    let property = self.parse_member_property()?;
 
                     expr = Expr::Member {
                         object: Box::new(expr),
-                        property: Box::new(Expr::Identifier(property, loc)),
+                        property: Box::new(property),
                         computed: false,
-                        loc,
+                        optional: false,
+                        loc: self.span_from(loc),
                     };
                 }
                 TokenKind::LBracket => {
-                    let loc = self.current.location;
                     self.advance();
-                    let property = self.parse_expression()?;
+                    let property = self.parse_sequence_expression()?;
                     self.expect(TokenKind::RBracket)?;
 
                     expr = Expr::Member {
                         object: Box::new(expr),
                         property: Box::new(property),
                         computed: true,
-                        loc,
+                        optional: false,
+                        loc: self.span_from(loc),
                     };
                 }
+                TokenKind::NoSubstitutionTemplate(_) | TokenKind::TemplateHead(_) => {
+                    return Err(ParseError::new(
+                        "Tagged templates are not supported".to_string(),
+                        self.current.location,
+                    ));
+                }
                 _ => break,
             }
         }
@@ -1356,6 +1519,13 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
+    /// Parses the name after `.` or `?.` as an identifier node
+    fn parse_member_property(&mut self) -> ParseResult<Expr> {
+        let loc = self.current.location;
+        let name = self.parse_property_name()?;
+        Ok(Expr::Identifier(name, self.span_from(loc)))
+    }
+
     /// Parses a primary expression
     fn parse_primary_expression(&mut self) -> ParseResult<Expr> {
         let loc = self.current.location;
@@ -1364,43 +1534,44 @@ impl<'a> Parser<'a> {
             TokenKind::Number(n) => {
                 let value = *n;
                 self.advance();
-                Ok(Expr::Literal(Literal::Number(value), loc))
+                Ok(Expr::Literal(Literal::Number(value), self.span_from(loc)))
             }
             TokenKind::String(s) => {
                 let value = s.clone();
                 self.advance();
-                Ok(Expr::Literal(Literal::String(value), loc))
+                Ok(Expr::Literal(Literal::String(value), self.span_from(loc)))
             }
             TokenKind::True => {
                 self.advance();
-                Ok(Expr::Literal(Literal::Boolean(true), loc))
+                Ok(Expr::Literal(Literal::Boolean(true), self.span_from(loc)))
             }
             TokenKind::False => {
                 self.advance();
-                Ok(Expr::Literal(Literal::Boolean(false), loc))
+                Ok(Expr::Literal(Literal::Boolean(false), self.span_from(loc)))
             }
             TokenKind::Null => {
                 self.advance();
-                Ok(Expr::Literal(Literal::Null, loc))
+                Ok(Expr::Literal(Literal::Null, self.span_from(loc)))
             }
             TokenKind::Undefined => {
                 self.advance();
-                Ok(Expr::Literal(Literal::Undefined, loc))
+                Ok(Expr::Literal(Literal::Undefined, self.span_from(loc)))
             }
             TokenKind::This => {
                 self.advance();
-                Ok(Expr::This(loc))
+                Ok(Expr::This(self.span_from(loc)))
             }
             TokenKind::Identifier(name) => {
                 let name = name.clone();
                 self.advance();
-                Ok(Expr::Identifier(name, loc))
+                Ok(Expr::Identifier(name, self.span_from(loc)))
             }
             TokenKind::LParen => {
                 // Try to parse as arrow function: () => ... or (a, b) => ...
-                let checkpoint_pos = self.lexer.pc();
+                let checkpoint_lexer = self.lexer.checkpoint();
                 let checkpoint_current = self.current.clone();
                 let checkpoint_peeked = self.peeked.clone();
+                let checkpoint_end = self.prev_end;
 
                 self.advance(); // consume (
 
@@ -1438,18 +1609,19 @@ impl<'a> Parser<'a> {
                         return Ok(Expr::Arrow {
                             params,
                             body,
-                            loc,
+                            loc: self.span_from(loc),
                         });
                     }
                 }
 
                 // Not an arrow function, restore and parse as expression
-                self.lexer.set_pc(checkpoint_pos);
+                self.lexer.restore(checkpoint_lexer);
                 self.current = checkpoint_current;
                 self.peeked = checkpoint_peeked;
+                self.prev_end = checkpoint_end;
 
                 self.advance(); // consume ( again
-                let expr = self.parse_expression()?;
+                let expr = self.parse_sequence_expression()?;
                 self.expect(TokenKind::RParen)?;
                 Ok(expr)
             }
@@ -1462,6 +1634,15 @@ impl<'a> Parser<'a> {
             TokenKind::Function => {
                 self.parse_function_expression()
             }
+            TokenKind::NoSubstitutionTemplate(text) => {
+                let quasis = vec![text.clone()];
+                self.advance();
+                Ok(Expr::Template { quasis, exprs: Vec::new(), loc: self.span_from(loc) })
+            }
+            TokenKind::TemplateHead(_) => {
+                self.parse_template_literal()
+            }
+            TokenKind::Error(message) => Err(ParseError::new(message.clone(), loc)),
             _ => Err(ParseError::new(
                 format!("Unexpected token: {:?}", self.current.kind),
                 loc,
@@ -1469,6 +1650,45 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Parses a template literal with substitutions, starting at its head
+    fn parse_template_literal(&mut self) -> ParseResult<Expr> {
+        let loc = self.current.location;
+        let mut quasis = Vec::new();
+        let mut exprs = Vec::new();
+
+        if let TokenKind::TemplateHead(text) = &self.current.kind {
+            quasis.push(text.clone());
+        }
+        self.advance();
+
+        loop {
+            exprs.push(self.parse_sequence_expression()?);
+
+            match &self.current.kind {
+                TokenKind::TemplateMiddle(text) => {
+                    quasis.push(text.clone());
+                    self.advance();
+                }
+                TokenKind::TemplateTail(text) => {
+                    quasis.push(text.clone());
+                    self.advance();
+                    break;
+                }
+                TokenKind::Error(message) => {
+                    return Err(ParseError::new(message.clone(), self.current.location));
+                }
+                _ => {
+                    return Err(ParseError::new(
+                        format!("Expected '}}' after template substitution, found {:?}", self.current.kind),
+                        self.current.location,
+                    ));
+                }
+            }
+        }
+
+        Ok(Expr::Template { quasis, exprs, loc: self.span_from(loc) })
+    }
+
     /// Parses an array literal
     fn parse_array_literal(&mut self) -> ParseResult<Expr> {
         let loc = self.current.location;
@@ -1489,7 +1709,7 @@ impl<'a> Parser<'a> {
             }
         }
 
-        Ok(Expr::Array { elements, loc })
+        Ok(Expr::Array { elements, loc: self.span_from(loc) })
     }
 
     /// Parses an object literal
@@ -1509,13 +1729,23 @@ impl<'a> Parser<'a> {
             }
         }
 
-        Ok(Expr::Object { properties, loc })
+        Ok(Expr::Object { properties, loc: self.span_from(loc) })
     }
 
     /// Parses a single object property (handles get/set/method/regular)
     fn parse_object_property(&mut self) -> ParseResult<Property> {
         let prop_loc = self.current.location;
 
+        if self.consume_if(&TokenKind::Ellipsis) {
+            let value = self.parse_assignment_expression()?;
+            return Ok(Property {
+                key: PropertyKey::Identifier(String::new()),
+                value,
+                kind: PropertyKind::Spread,
+                loc: self.span_from(prop_loc),
+            });
+        }
+
         // Check for getter/setter: get/set followed by property name
         if let TokenKind::Identifier(name) = &self.current.kind.clone() {
             let is_get = name == "get";
@@ -1558,13 +1788,14 @@ impl<'a> Parser<'a> {
                         name: None,
                         params,
                         body,
-                        loc: prop_loc,
+                        loc: self.span_from(prop_loc),
                     };
 
                     return Ok(Property {
                         key,
                         value,
                         kind: if is_get { PropertyKind::Get } else { PropertyKind::Set },
+                        loc: self.span_from(prop_loc),
                     });
                 }
             }
@@ -1588,13 +1819,14 @@ impl<'a> Parser<'a> {
                     name: None,
                     params,
                     body,
-                    loc: prop_loc,
+                    loc: self.span_from(prop_loc),
                 };
 
                 Ok(Property {
                     key,
                     value,
                     kind: PropertyKind::Init,
+                    loc: self.span_from(prop_loc),
                 })
             }
             TokenKind::Colon => {
@@ -1605,6 +1837,7 @@ impl<'a> Parser<'a> {
                     key,
                     value,
                     kind: PropertyKind::Init,
+                    loc: self.span_from(prop_loc),
                 })
             }
             _ => Err(ParseError::new(
@@ -1669,7 +1902,7 @@ impl<'a> Parser<'a> {
         let body = self.parse_statement_list()?;
         self.expect(TokenKind::RBrace)?;
 
-        Ok(Expr::Function { name, params, body, loc })
+        Ok(Expr::Function { name, params, body, loc: self.span_from(loc) })
     }
 
     /// Parses an arrow function body
@@ -1752,12 +1985,75 @@ mod tests {
             Stmt::VarDecl { kind, declarations, .. } => {
                 assert_eq!(*kind, VarKind::Var);
                 assert_eq!(declarations.len(), 1);
-                assert_eq!(declarations[0].name, "x");
+                assert_eq!(declarations[0].target.as_identifier(), Some("x"));
             }
             _ => panic!("Expected var declaration"),
         }
     }
 
+    #[test]
+    fn test_parse_array_pattern() {
+        let program = Parser::new("let [a, , [b]] = x;").parse().unwrap();
+        match &program.body[0] {
+            Stmt::VarDecl { declarations, .. } => {
+                let Pattern::Array(elements, _) = &declarations[0].target else {
+                    panic!("Expected array pattern");
+                };
+                assert_eq!(elements.len(), 3);
+                assert_eq!(elements[0].as_ref().and_then(Pattern::as_identifier), Some("a"));
+                assert!(elements[1].is_none());
+                match &elements[2] {
+                    Some(Pattern::Array(inner, _)) => {
+                        assert_eq!(inner.len(), 1);
+                        assert_eq!(inner[0].as_ref().and_then(Pattern::as_identifier), Some("b"));
+                    }
+                    _ => panic!("Expected nested array pattern"),
+                }
+            }
+            _ => panic!("Expected var declaration"),
+        }
+
+        assert!(Parser::new("for (var [k, v] of pairs) {}").parse().is_ok());
+        assert!(Parser::new("var [a, b];").parse().is_err());
+    }
+
+    #[test]
+    fn test_parse_optional_chain() {
+        let program = Parser::new("a?.b.c;").parse().unwrap();
+        match &program.body[0] {
+            Stmt::Expression { expr: Expr::OptionalChain { expr, .. }, .. } => match expr.as_ref() {
+                Expr::Member { object, optional: false, .. } => {
+                    assert!(matches!(object.as_ref(), Expr::Member { optional: true, .. }));
+                }
+                other => panic!("Expected member expression, got {:?}", other),
+            },
+            other => panic!("Expected optional chain, got {:?}", other),
+        }
+
+        assert!(Parser::new("f?.(1); a?.[0]; x ??= y; x ||= y; x &&= y;").parse().is_ok());
+        assert!(Parser::new("a?.b = 1;").parse().is_err());
+    }
+
+    #[test]
+    fn test_parse_template_literal() {
+        let program = Parser::new("`a${x + 1}b${f()}`;").parse().unwrap();
+        match &program.body[0] {
+            Stmt::Expression { expr: Expr::Template { quasis, exprs, .. }, .. } => {
+                assert_eq!(quasis, &["a", "b", ""]);
+                assert!(matches!(exprs[0], Expr::Binary { .. }));
+                assert!(matches!(exprs[1], Expr::Call { .. }));
+            }
+            other => panic!("Expected template literal, got {:?}", other),
+        }
+
+        // Backtracking out of an arrow-function attempt keeps template state
+        assert!(Parser::new("(`${a}`); (x, `${ {b: 1}.b }`);").parse().is_ok());
+        assert!(Parser::new("`${a`;").parse().is_err());
+
+        let err = Parser::new("tag`x${y}`;").parse().unwrap_err();
+        assert_eq!(err.message, "Tagged templates are not supported");
+    }
+
     #[test]
     fn test_parse_function() {
         let parser = Parser::new("function add(a, b) { return a + b; }");
@@ -1787,4 +2083,148 @@ mod tests {
             _ => panic!("Expected if statement"),
         }
     }
+
+    /// Checks that every node lies within its parent and the source
+    struct SpanChecker<'s> {
+        source: &'s str,
+        parents: Vec<Span>,
+        nodes: usize,
+    }
+
+    impl SpanChecker<'_> {
+        fn check(&mut self, span: Span, what: &str) {
+            let text = span.text(self.source);
+            assert!(span.start.offset <= span.end.offset && span.end.offset <= self.source.len(), "{} out of bounds", what);
+            assert!(!text.is_empty(), "{} has an empty span", what);
+            let parent = self.parents.last().copied().unwrap();
+            assert!(parent.contains(span), "{} `{}` escapes `{}`", what, text, parent.text(self.source));
+            self.nodes += 1;
+        }
+    }
+
+    impl crate::compiler::visit::Visitor for SpanChecker<'_> {
+        fn visit_stmt(&mut self, stmt: &Stmt) {
+            self.check(stmt.span(), "statement");
+            self.parents.push(stmt.span());
+            crate::compiler::visit::walk_stmt(self, stmt);
+            self.parents.pop();
+        }
+
+        fn visit_expr(&mut self, expr: &Expr) {
+            self.check(expr.span(), "expression");
+            self.parents.push(expr.span());
+            crate::compiler::visit::walk_expr(self, expr);
+            self.parents.pop();
+        }
+
+        fn visit_pattern(&mut self, pattern: &Pattern) {
+            self.check(pattern.span(), "pattern");
+            self.parents.push(pattern.span());
+            crate::compiler::visit::walk_pattern(self, pattern);
+            self.parents.pop();
+        }
+
+        fn visit_property(&mut self, property: &Property) {
+            self.check(property.loc, "property");
+            self.parents.push(property.loc);
+            crate::compiler::visit::walk_property(self, property);
+            self.parents.pop();
+        }
+    }
+
+    #[test]
+    fn test_spans_nest() {
+        use crate::compiler::visit::Visitor;
+
+        let source = r#"
+            // Tally readings
+            var [first, , rest] = readings, total = 0;
+            function tally(list) {
+                for (let i = 0; i < list.length; i++) {
+                    total += list[i].value * 2 ** 3;
+                }
+                return total > 10 ? `high ${total}` : obj?.fallback.call(null, -total);
+            }
+            const o = { a: 1, [key]: () => x + 1, get b() { return this.a; }, m(p) { p++; } };
+            try { throw new Error("bad"); } catch (e) { o.a = (e, 2); } finally { tally([1, 2]); }
+            switch (typeof total) { case "number": label: while (!done) { break label; } default: ; }
+        "#;
+        let program = Parser::new(source).parse().unwrap();
+        let whole = Span::new(SourceLocation::new(1, 1, 0), SourceLocation::new(1, 1, source.len()));
+        let mut checker = SpanChecker { source, parents: vec![whole], nodes: 0 };
+        checker.visit_program(&program);
+        assert!(checker.nodes > 80, "only {} nodes visited", checker.nodes);
+
+        // Spans cover the node's text exactly
+        let Stmt::FunctionDecl { body, .. } = &program.body[1] else { panic!("Expected function") };
+        let Stmt::Return { argument: Some(argument), loc } = &body[1] else { panic!("Expected return") };
+        assert_eq!(loc.text(source), "return total > 10 ? `high ${total}` : obj?.fallback.call(null, -total);");
+        assert_eq!(argument.span().text(source), "total > 10 ? `high ${total}` : obj?.fallback.call(null, -total)");
+        assert_eq!((loc.start.line, loc.start.column), (8, 17));
+        assert_eq!(program.body[0].span().text(source), "var [first, , rest] = readings, total = 0;");
+    }
+
+    #[test]
+    fn test_chains_group() {
+        // Comparisons and equality group to the left
+        for source in ["a < b < c", "3 > 2 > 1", "a == b == c", "a !== b === c"] {
+            let Ok(Expr::Binary { left, right, .. }) = Parser::new(source).parse_expression() else {
+                panic!("Expected a binary expression for {source}");
+            };
+            assert!(matches!(*left, Expr::Binary { .. }), "{source}");
+            assert_eq!(right.span().text(source), &source[source.len() - 1..]);
+        }
+
+        // Assignment groups to the right
+        let source = "x = y.p = z[0] = 0";
+        let mut expr = Parser::new(source).parse_expression().unwrap();
+        let mut targets = Vec::new();
+        while let Expr::Assignment { left, right, .. } = expr {
+            targets.push(left.span().text(source).to_string());
+            expr = *right;
+        }
+        assert_eq!(targets, ["x", "y.p", "z[0]"]);
+        assert!(matches!(expr, Expr::Literal(Literal::Number(n), _) if n == 0.0));
+
+        let Ok(Expr::Sequence { exprs, .. }) = Parser::new("a, b = 1, c").parse_expression() else {
+            panic!("Expected a sequence");
+        };
+        assert_eq!(exprs.len(), 3);
+        assert!(matches!(exprs[1], Expr::Assignment { .. }));
+    }
+
+    #[test]
+    fn test_parse_expression_entry_point() {
+        let expr = Parser::new("a.b(1) + c").parse_expression().unwrap();
+        match &expr {
+            Expr::Binary { op: BinaryOp::Add, left, .. } => {
+                assert_eq!(left.span().text("a.b(1) + c"), "a.b(1)");
+            }
+            _ => panic!("Expected addition"),
+        }
+        assert_eq!(expr.span().end.offset, 10);
+
+        assert!(Parser::new("1, 2").parse_expression().is_ok());
+        let err = Parser::new("1 + 2; x").parse_expression().unwrap_err();
+        assert_eq!(err.location.column, 6);
+    }
+
+    #[test]
+    fn test_parse_with_comments() {
+        let source = "/* header */\nvar x = 1; // one\n// done";
+        let (program, comments) = Parser::with_comments(source).parse_with_comments().unwrap();
+        assert_eq!(program.body.len(), 1);
+        let texts: Vec<&str> = comments.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, ["/* header */", "// one", "// done"]);
+        assert_eq!(comments[1].span.text(source), "// one");
+        assert_eq!(comments[2].span.start.line, 3);
+
+        // Backtracking over an arrow function doesn't record comments twice
+        let (_, comments) = Parser::with_comments("(a /* p */, b);").parse_with_comments().unwrap();
+        assert_eq!(comments.len(), 1);
+
+        // Without with_comments, nothing is kept
+        let (_, comments) = Parser::new(source).parse_with_comments().unwrap();
+        assert!(comments.is_empty());
+    }
 }
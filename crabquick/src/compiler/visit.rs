@@ -0,0 +1,217 @@
+//! AST traversal
+//!
+//! A [`Visitor`] overrides the `visit_*` methods for the nodes it cares
+//! about; the defaults call the matching `walk_*` function, which visits
+//! the node's children in source order. An override that still wants the
+//! children visited calls `walk_*` itself.
+//!
+//! ```ignore
+//! use crabquick::compiler::{Parser, Expr};
+//! use crabquick::compiler::visit::{self, Visitor};
+//!
+//! struct Calls(usize);
+//!
+//! impl Visitor for Calls {
+//!     fn visit_expr(&mut self, expr: &Expr) {
+//!         if matches!(expr, Expr::Call { .. }) {
+//!             self.0 += 1;
+//!         }
+//!         visit::walk_expr(self, expr);
+//!     }
+//! }
+//!
+//! let program = Parser::new("f(g(1))").parse()?;
+//! let mut calls = Calls(0);
+//! calls.visit_program(&program);
+//! assert_eq!(calls.0, 2);
+//! ```
+
+use super::ast::*;
+
+/// Callbacks for each kind of AST node
+pub trait Visitor {
+    /// Visits a whole program
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+
+    /// Visits a statement
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    /// Visits an expression
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    /// Visits a declaration target
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        walk_pattern(self, pattern);
+    }
+
+    /// Visits an object literal property
+    fn visit_property(&mut self, property: &Property) {
+        walk_property(self, property);
+    }
+}
+
+/// Visits every statement in a program
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    walk_stmts(visitor, &program.body);
+}
+
+/// Visits the children of a statement
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Stmt) {
+    match stmt {
+        Stmt::Expression { expr, .. } => visitor.visit_expr(expr),
+        Stmt::Block { stmts, .. } => walk_stmts(visitor, stmts),
+        Stmt::VarDecl { declarations, .. } => walk_declarators(visitor, declarations),
+        Stmt::FunctionDecl { body, .. } => walk_stmts(visitor, body),
+        Stmt::If { test, consequent, alternate, .. } => {
+            visitor.visit_expr(test);
+            visitor.visit_stmt(consequent);
+            if let Some(alternate) = alternate {
+                visitor.visit_stmt(alternate);
+            }
+        }
+        Stmt::While { test, body, .. } => {
+            visitor.visit_expr(test);
+            visitor.visit_stmt(body);
+        }
+        Stmt::DoWhile { body, test, .. } => {
+            visitor.visit_stmt(body);
+            visitor.visit_expr(test);
+        }
+        Stmt::For { init, test, update, body, .. } => {
+            if let Some(init) = init {
+                walk_for_init(visitor, init);
+            }
+            if let Some(test) = test {
+                visitor.visit_expr(test);
+            }
+            if let Some(update) = update {
+                visitor.visit_expr(update);
+            }
+            visitor.visit_stmt(body);
+        }
+        Stmt::ForIn { left, right, body, .. } | Stmt::ForOf { left, right, body, .. } => {
+            walk_for_init(visitor, left);
+            visitor.visit_expr(right);
+            visitor.visit_stmt(body);
+        }
+        Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Empty { .. } => {}
+        Stmt::Return { argument, .. } => {
+            if let Some(argument) = argument {
+                visitor.visit_expr(argument);
+            }
+        }
+        Stmt::Throw { argument, .. } => visitor.visit_expr(argument),
+        Stmt::Try { block, handler, finalizer, .. } => {
+            walk_stmts(visitor, block);
+            if let Some(handler) = handler {
+                walk_stmts(visitor, &handler.body);
+            }
+            if let Some(finalizer) = finalizer {
+                walk_stmts(visitor, finalizer);
+            }
+        }
+        Stmt::Switch { discriminant, cases, .. } => {
+            visitor.visit_expr(discriminant);
+            for case in cases {
+                if let Some(test) = &case.test {
+                    visitor.visit_expr(test);
+                }
+                walk_stmts(visitor, &case.consequent);
+            }
+        }
+        Stmt::Labeled { body, .. } => visitor.visit_stmt(body),
+    }
+}
+
+/// Visits the children of an expression
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match expr {
+        Expr::Literal(..) | Expr::Identifier(..) | Expr::This(_) => {}
+        Expr::Binary { left, right, .. } | Expr::Assignment { left, right, .. } => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right);
+        }
+        Expr::Unary { arg, .. } | Expr::Update { arg, .. } => visitor.visit_expr(arg),
+        Expr::Conditional { test, consequent, alternate, .. } => {
+            visitor.visit_expr(test);
+            visitor.visit_expr(consequent);
+            visitor.visit_expr(alternate);
+        }
+        Expr::Call { callee, args, .. } | Expr::New { callee, args, .. } => {
+            visitor.visit_expr(callee);
+            walk_exprs(visitor, args);
+        }
+        Expr::Member { object, property, .. } => {
+            visitor.visit_expr(object);
+            visitor.visit_expr(property);
+        }
+        Expr::OptionalChain { expr, .. } => visitor.visit_expr(expr),
+        Expr::Template { exprs, .. } | Expr::Sequence { exprs, .. } => walk_exprs(visitor, exprs),
+        Expr::Array { elements, .. } => {
+            for element in elements.iter().flatten() {
+                visitor.visit_expr(element);
+            }
+        }
+        Expr::Object { properties, .. } => {
+            for property in properties {
+                visitor.visit_property(property);
+            }
+        }
+        Expr::Function { body, .. } => walk_stmts(visitor, body),
+        Expr::Arrow { body, .. } => match body {
+            ArrowBody::Expr(expr) => visitor.visit_expr(expr),
+            ArrowBody::Block(stmts) => walk_stmts(visitor, stmts),
+        },
+    }
+}
+
+/// Visits the elements of an array pattern
+pub fn walk_pattern<V: Visitor + ?Sized>(visitor: &mut V, pattern: &Pattern) {
+    if let Pattern::Array(elements, _) = pattern {
+        for element in elements.iter().flatten() {
+            visitor.visit_pattern(element);
+        }
+    }
+}
+
+/// Visits a property's computed key, if any, and its value
+pub fn walk_property<V: Visitor + ?Sized>(visitor: &mut V, property: &Property) {
+    if let PropertyKey::Computed(key) = &property.key {
+        visitor.visit_expr(key);
+    }
+    visitor.visit_expr(&property.value);
+}
+
+fn walk_stmts<V: Visitor + ?Sized>(visitor: &mut V, stmts: &[Stmt]) {
+    for stmt in stmts {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+fn walk_exprs<V: Visitor + ?Sized>(visitor: &mut V, exprs: &[Expr]) {
+    for expr in exprs {
+        visitor.visit_expr(expr);
+    }
+}
+
+fn walk_declarators<V: Visitor + ?Sized>(visitor: &mut V, declarations: &[VarDeclarator]) {
+    for decl in declarations {
+        visitor.visit_pattern(&decl.target);
+        if let Some(init) = &decl.init {
+            visitor.visit_expr(init);
+        }
+    }
+}
+
+fn walk_for_init<V: Visitor + ?Sized>(visitor: &mut V, init: &ForInit) {
+    match init {
+        ForInit::VarDecl { declarations, .. } => walk_declarators(visitor, declarations),
+        ForInit::Expr(expr) => visitor.visit_expr(expr),
+    }
+}
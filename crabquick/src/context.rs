@@ -4,22 +4,95 @@
 //! It manages memory, the runtime environment, and provides the API for evaluating
 //! JavaScript code.
 
-use crate::memory::{Arena, GarbageCollector, HeapIndex, MemTag};
-use crate::value::{JSValue, AtomTable};
+use crate::memory::{Arena, GarbageCollector, HeapIndex, HeapSizeError, MemTag, Persistent};
+use crate::value::{JSValue, JSAtom, AtomTable};
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// Default for `Context::set_max_nesting_depth`
+///
+/// Keeps the native stack a walker uses over a deeply nested value within
+/// what small embedded targets give the engine.
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 64;
+
+/// A `Context` operation that needs the engine idle was called while a
+/// script was running, typically from a native function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReentrancyError {
+    /// Name of the refused operation, e.g. `"eval"`
+    pub operation: &'static str,
+}
 
-/// JavaScript execution context
+impl core::fmt::Display for ReentrancyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} can't be called while a script is running", self.operation)
+    }
+}
+
+/// Host function the VM polls to learn whether to stop the running script
 ///
-/// Manages memory allocation, garbage collection, and the runtime environment.
-/// All JavaScript operations must be performed through a Context.
+/// Returning true interrupts it. See `Context::set_interrupt_handler`.
+pub type InterruptHandler = fn() -> bool;
+
+/// Host function `Date.now()` reads the time from
+///
+/// Returns milliseconds since the Unix epoch, or since boot on targets
+/// without a wall clock. See `Context::set_clock`.
+pub type Clock = fn() -> u64;
+
+/// Sizes a context is created with: its heap and the VM stacks scripts run
+/// on
+///
+/// The stacks are reserved in full on the Rust heap, outside the arena, when
+/// a run starts; [`stack_bytes`](Self::stack_bytes) says how much that is.
 ///
 /// # Example
 ///
 /// ```rust,ignore
-/// use crabquick::Context;
-///
-/// let mut ctx = Context::new(8192);
-/// let result = ctx.eval("1 + 1", "script.js", 0)?;
+/// let ctx = Context::with_config(ContextConfig { call_depth: 40, ..ContextConfig::new(16 * 1024) });
 /// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextConfig {
+    /// Size of the heap in bytes
+    pub memory_bytes: usize,
+    /// Values the VM's operand stack holds, capped at `ValueStack::MAX_SLOTS`
+    pub value_stack_slots: usize,
+    /// Deepest nesting of calls, the top-level script included, capped at
+    /// `CallStack::MAX_CALL_DEPTH`
+    ///
+    /// Each level also recurses in the interpreter, so the host thread's own
+    /// stack must have room for the depth chosen.
+    pub call_depth: usize,
+    /// Properties the global object's table is sized for up front
+    ///
+    /// Defaults to the globals the runtime installs for the enabled features.
+    /// Hosts whose scripts add many globals of their own can raise it so the
+    /// table doesn't grow (leaving the smaller tables behind as garbage) as
+    /// they run.
+    pub expected_globals: usize,
+}
+
+impl ContextConfig {
+    /// A `memory_bytes` heap with the VM's default stack sizes
+    pub const fn new(memory_bytes: usize) -> Self {
+        ContextConfig {
+            memory_bytes,
+            value_stack_slots: crate::vm::VM::DEFAULT_VALUE_STACK_SLOTS,
+            call_depth: crate::vm::VM::DEFAULT_CALL_DEPTH,
+            expected_globals: crate::runtime::init::BUILTIN_GLOBALS,
+        }
+    }
+
+    /// Bytes the two stacks take when reserved, on top of the heap
+    pub fn stack_bytes(&self) -> usize {
+        let slots = self.value_stack_slots.min(crate::vm::ValueStack::MAX_SLOTS);
+        let depth = self.call_depth.min(crate::vm::CallStack::MAX_CALL_DEPTH);
+        slots * core::mem::size_of::<JSValue>() + depth * core::mem::size_of::<crate::vm::StackFrame>()
+    }
+}
+
 /// Callback type for re-entering VM to call closures from native code
 pub type ReentrantCallFn = unsafe fn(
     vm_ptr: core::ptr::NonNull<u8>,
@@ -29,6 +102,28 @@ pub type ReentrantCallFn = unsafe fn(
     args: &[JSValue],
 ) -> Result<JSValue, JSValue>;
 
+/// JavaScript execution context
+///
+/// Manages memory allocation, garbage collection, and the runtime environment.
+/// All JavaScript operations must be performed through a Context.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use crabquick::Context;
+///
+/// let mut ctx = Context::new(8192);
+/// let result = ctx.eval("1 + 1", "script.js", 0)?;
+/// ```
+///
+/// # Reentrancy
+///
+/// Native functions get the context of the script that called them. They
+/// may allocate, read and write properties, and call back into script with
+/// `call_function`. Starting another run or collecting garbage from there
+/// would pull the VM's state out from under it, so `eval`, `eval_expr`,
+/// `execute_bytecode`, `execute_code` and `gc` fail with a
+/// [`ReentrancyError`] while a script is running.
 pub struct Context {
     /// Memory arena for heap allocations
     arena: Arena,
@@ -42,12 +137,102 @@ pub struct Context {
     object_prototype: JSValue,
     /// Function.prototype (null until initialized)
     function_prototype: JSValue,
+    /// Array.prototype (null until initialized)
+    array_prototype: JSValue,
+    /// Uint8Array.prototype (null until initialized)
+    uint8array_prototype: JSValue,
+    /// Error.prototype and the native error prototypes, indexed by `ErrorType`
+    /// (null until initialized)
+    error_prototypes: [JSValue; crate::builtins::error::ErrorType::ALL.len()],
+    /// The builtin error constructors, indexed by `ErrorType` (null until
+    /// initialized); native functions can't carry a `prototype`, so
+    /// `instanceof` finds theirs through here
+    error_constructors: [JSValue; crate::builtins::error::ErrorType::ALL.len()],
     /// Current exception value (if any)
     exception_value: JSValue,
     /// Raw pointer to VM for reentrant calls (set by VM during execution)
     vm_ptr: Option<core::ptr::NonNull<u8>>,
     /// Callback for calling functions from native code (set by VM during execution)
     reentrant_call: Option<ReentrantCallFn>,
+    /// Longest computed property name accepted, in bytes
+    max_property_name_len: usize,
+    /// Most distinct property names the context may record
+    max_property_names: usize,
+    /// Longest source `eval` compiles, in bytes
+    max_source_bytes: usize,
+    /// Instructions one run may execute; 0 for no limit
+    max_steps: u64,
+    /// Slots in the value stack of each VM the context runs scripts on
+    value_stack_slots: usize,
+    /// Call depth of each VM the context runs scripts on
+    call_depth: usize,
+    /// Polled by the VM to learn whether to interrupt the script
+    interrupt_handler: Option<InterruptHandler>,
+    /// Instructions between polls of `interrupt_handler`
+    interrupt_interval: u32,
+    /// Where `Date.now()` gets the time
+    clock: Option<Clock>,
+    /// Math.random state
+    #[cfg(feature = "builtin-math")]
+    random_state: u64,
+    /// Error thrown when the heap is too full to build a new one
+    out_of_memory_error: JSValue,
+    /// Values held by the host through `Persistent` handles
+    persistent: crate::memory::handle::PersistentTable,
+    /// Heap usage past which the VM collects at its next instruction
+    gc_threshold: usize,
+    /// Heap bytes left after the most recent collection
+    live_bytes: usize,
+    /// Whether the most recent collection ran mid-script, leaving the
+    /// running code in place with the rest compacted around it
+    pinned_since_compact: bool,
+    /// Highest heap usage seen
+    peak_used: usize,
+    /// Heap bytes allocated since the most recent collection
+    allocated_since_gc: usize,
+    /// Allocations made since the most recent collection
+    allocations_since_gc: usize,
+    /// Allocations after which the VM collects regardless of heap use,
+    /// or 0 to collect only when the heap fills
+    gc_stress_interval: usize,
+    /// Allocations that failed for lack of space
+    ///
+    /// The VM compares readings around an instruction to tell whether it
+    /// failed for want of memory, and can collect and retry it.
+    alloc_failures: usize,
+    /// Nesting depth of native function calls
+    ///
+    /// Natives hold values in Rust locals the collector can't see, so the
+    /// VM doesn't collect while one is running.
+    native_depth: usize,
+    /// Number of VM runs under way: scripts, and calls from the host
+    run_depth: usize,
+    /// Levels entered by native walkers over nested values (JSON and the
+    /// like), shared so walkers that call each other share the budget
+    nesting_depth: core::cell::Cell<usize>,
+    /// Deepest `nesting_depth` allowed before walkers throw
+    max_nesting_depth: usize,
+    /// Longest prototype chain, in links, that `Object.create` and
+    /// `Object.setPrototypeOf` build and that lookups follow
+    max_prototype_depth: usize,
+    /// Prototype links lookups followed since the VM last charged them
+    /// against the step budget
+    prototype_links: core::cell::Cell<u64>,
+    /// Most links a single lookup followed since `replace_prototype_peak`
+    prototype_peak: core::cell::Cell<usize>,
+    /// Lines console methods write while a host is capturing output,
+    /// instead of printing them
+    console_capture: Option<Vec<String>>,
+    /// Outcomes of the script's `test(name, fn)` calls, in order
+    #[cfg(feature = "test-api")]
+    test_results: Vec<crate::builtins::test_api::TestResult>,
+    /// What new heap blocks are recorded as allocated by
+    #[cfg(feature = "alloc-origin")]
+    alloc_origin: crate::memory::AllocOrigin,
+    /// Native functions the runtime installed, with the names it installed
+    /// them under; a builtin's origin is its position here
+    #[cfg(feature = "alloc-origin")]
+    builtin_origins: Vec<(usize, JSAtom)>,
     // TODO: Add more fields:
     // - class_array: Vec<JSClass>
     // - interrupt_handler: Option<InterruptHandler>
@@ -68,8 +253,32 @@ pub enum PropertyLookupResult {
 }
 
 impl Context {
+    /// Default limit on the length of a computed property name, in bytes
+    pub const DEFAULT_MAX_PROPERTY_NAME_LEN: usize = 256;
+
+    /// Default limit on distinct property names recorded per context
+    pub const DEFAULT_MAX_PROPERTY_NAMES: usize = 4096;
+
+    /// Default limit on the size of source `eval` compiles, in bytes
+    pub const DEFAULT_MAX_SOURCE_BYTES: usize = 1 << 20;
+
+    /// Default number of instructions between interrupt handler polls
+    pub const DEFAULT_INTERRUPT_INTERVAL: u32 = 1024;
+
+    /// Default limit on the length of a prototype chain, in links
+    pub const DEFAULT_MAX_PROTOTYPE_DEPTH: usize = 128;
+
+    /// Smallest heap a context accepts: room for the global object
+    pub const MIN_MEMORY_SIZE: usize = Arena::block_size(
+        core::mem::size_of::<crate::memory::MemBlockHeader>() + core::mem::size_of::<crate::object::JSObject>(),
+    );
+
     /// Creates a new JavaScript context with the specified memory size
     ///
+    /// Sizes below [`MIN_MEMORY_SIZE`](Self::MIN_MEMORY_SIZE) are raised to
+    /// it, and the size is rounded down to the allocator's alignment; use
+    /// [`try_new`](Self::try_new) to reject small sizes instead.
+    ///
     /// # Arguments
     ///
     /// * `memory_size` - Size of the heap in bytes
@@ -80,25 +289,95 @@ impl Context {
     /// let ctx = Context::new(8192); // 8 KB heap
     /// ```
     pub fn new(memory_size: usize) -> Self {
+        Self::with_config(ContextConfig::new(memory_size))
+    }
+
+    /// Creates a context with the heap and VM stack sizes in `config`
+    ///
+    /// The heap is adjusted as in [`new`](Self::new), and the stack sizes
+    /// are capped at the VM's limits. A script that nests calls deeper than
+    /// `call_depth` or runs out of value stack gets a catchable
+    /// `RangeError: Maximum call stack size exceeded`.
+    pub fn with_config(config: ContextConfig) -> Self {
         let mut ctx = Context {
-            arena: Arena::new(memory_size),
+            arena: Arena::new(config.memory_bytes.max(Self::MIN_MEMORY_SIZE)),
             gc: GarbageCollector::new(),
             atom_table: AtomTable::new(),
             global_object: JSValue::null(),
             object_prototype: JSValue::null(),
             function_prototype: JSValue::null(),
+            array_prototype: JSValue::null(),
+            uint8array_prototype: JSValue::null(),
+            error_prototypes: [JSValue::null(); crate::builtins::error::ErrorType::ALL.len()],
+            error_constructors: [JSValue::null(); crate::builtins::error::ErrorType::ALL.len()],
             exception_value: JSValue::undefined(),
             vm_ptr: None,
             reentrant_call: None,
+            max_property_name_len: Self::DEFAULT_MAX_PROPERTY_NAME_LEN,
+            max_property_names: Self::DEFAULT_MAX_PROPERTY_NAMES,
+            max_source_bytes: Self::DEFAULT_MAX_SOURCE_BYTES,
+            max_steps: 0,
+            value_stack_slots: config.value_stack_slots.min(crate::vm::ValueStack::MAX_SLOTS),
+            call_depth: config.call_depth.min(crate::vm::CallStack::MAX_CALL_DEPTH),
+            interrupt_handler: None,
+            interrupt_interval: Self::DEFAULT_INTERRUPT_INTERVAL,
+            #[cfg(feature = "std")]
+            clock: Some(Self::system_clock),
+            #[cfg(not(feature = "std"))]
+            clock: None,
+            #[cfg(feature = "builtin-math")]
+            random_state: Self::initial_random_seed(),
+            out_of_memory_error: JSValue::undefined(),
+            persistent: Default::default(),
+            gc_threshold: 0,
+            live_bytes: 0,
+            pinned_since_compact: false,
+            peak_used: 0,
+            allocated_since_gc: 0,
+            allocations_since_gc: 0,
+            gc_stress_interval: 0,
+            alloc_failures: 0,
+            native_depth: 0,
+            run_depth: 0,
+            nesting_depth: core::cell::Cell::new(0),
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            max_prototype_depth: Self::DEFAULT_MAX_PROTOTYPE_DEPTH,
+            prototype_links: core::cell::Cell::new(0),
+            prototype_peak: core::cell::Cell::new(0),
+            console_capture: None,
+            #[cfg(feature = "test-api")]
+            test_results: Vec::new(),
+            #[cfg(feature = "alloc-origin")]
+            alloc_origin: crate::memory::AllocOrigin::Host,
+            #[cfg(feature = "alloc-origin")]
+            builtin_origins: Vec::new(),
         };
 
         // Initialize global object (store as null if it fails)
         // This is called here to ensure the global object is always available
         ctx.global_object = ctx.new_object().unwrap_or(JSValue::null());
+        // A heap too small for the sized table still gets a global, whose
+        // table then grows as properties are added
+        let _ = ctx.reserve_properties(ctx.global_object, config.expected_globals);
+        ctx.gc_threshold = ctx.next_gc_threshold();
 
         ctx
     }
 
+    /// Creates a new context, failing if `memory_size` is below
+    /// [`MIN_MEMORY_SIZE`](Self::MIN_MEMORY_SIZE)
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// assert!(Context::try_new(0).is_err());
+    /// let ctx = Context::try_new(8192)?;
+    /// ```
+    pub fn try_new(memory_size: usize) -> Result<Self, HeapSizeError> {
+        HeapSizeError::check(memory_size, Self::MIN_MEMORY_SIZE)?;
+        Ok(Self::new(memory_size))
+    }
+
     /// Set the Object.prototype for this context
     pub fn set_object_prototype(&mut self, proto: JSValue) {
         self.object_prototype = proto;
@@ -119,6 +398,279 @@ impl Context {
         self.function_prototype
     }
 
+    /// Set the Array.prototype for this context
+    pub fn set_array_prototype(&mut self, proto: JSValue) {
+        self.array_prototype = proto;
+    }
+
+    /// Get the Array.prototype for this context
+    pub fn get_array_prototype(&self) -> JSValue {
+        self.array_prototype
+    }
+
+    /// Set the Uint8Array.prototype for this context
+    pub fn set_uint8array_prototype(&mut self, proto: JSValue) {
+        self.uint8array_prototype = proto;
+    }
+
+    /// Set the prototype for errors of the given type
+    pub fn set_error_prototype(&mut self, error_type: crate::builtins::error::ErrorType, proto: JSValue) {
+        self.error_prototypes[error_type as usize] = proto;
+    }
+
+    /// Set the builtin constructor for errors of the given type
+    pub fn set_error_constructor(&mut self, error_type: crate::builtins::error::ErrorType, ctor: JSValue) {
+        self.error_constructors[error_type as usize] = ctor;
+    }
+
+    /// Get the prototype for errors of the given type
+    ///
+    /// Falls back to Error.prototype for types without their own prototype.
+    pub fn get_error_prototype(&self, error_type: crate::builtins::error::ErrorType) -> JSValue {
+        let proto = self.error_prototypes[error_type as usize];
+        if proto.is_null() {
+            self.error_prototypes[crate::builtins::error::ErrorType::Error as usize]
+        } else {
+            proto
+        }
+    }
+
+    /// Set the longest property name a computed key (`obj[key]`) may have
+    ///
+    /// Longer names throw a RangeError.
+    pub fn set_max_property_name_len(&mut self, len: usize) {
+        self.max_property_name_len = len;
+    }
+
+    /// Get the longest property name a computed key may have
+    pub fn max_property_name_len(&self) -> usize {
+        self.max_property_name_len
+    }
+
+    /// Set the largest source, in bytes, `eval` will compile
+    ///
+    /// Longer sources fail with a compile error before the lexer sees them.
+    pub fn set_max_source_bytes(&mut self, len: usize) {
+        self.max_source_bytes = len;
+    }
+
+    /// Get the largest source `eval` will compile
+    pub fn max_source_bytes(&self) -> usize {
+        self.max_source_bytes
+    }
+
+    /// Set how many instructions one run may execute, 0 for no limit
+    ///
+    /// A run is a top-level script or a call from the host, functions it
+    /// calls included. Each prototype link a property lookup follows counts
+    /// as a step too. Past the limit the VM throws a `Timeout` error that
+    /// scripts can't catch.
+    pub fn set_max_steps(&mut self, steps: u64) {
+        self.max_steps = steps;
+    }
+
+    /// Get how many instructions one run may execute (0 means no limit)
+    pub fn max_steps(&self) -> u64 {
+        self.max_steps
+    }
+
+    /// Get how many slots the value stack of a script's VM has
+    pub fn value_stack_slots(&self) -> usize {
+        self.value_stack_slots
+    }
+
+    /// Get how deeply a script's calls may nest, the top level included
+    pub fn call_depth(&self) -> usize {
+        self.call_depth
+    }
+
+    /// Set the function the VM polls to learn whether to stop the script,
+    /// or `None` to stop polling
+    ///
+    /// When it returns true the VM throws an `Interrupted` error that
+    /// scripts can't catch.
+    pub fn set_interrupt_handler(&mut self, handler: Option<InterruptHandler>) {
+        self.interrupt_handler = handler;
+    }
+
+    /// Set how many instructions the VM runs between polls of the interrupt
+    /// handler
+    ///
+    /// Shorter intervals stop scripts sooner but slow every instruction
+    /// down a little. Values below 1 are raised to 1.
+    pub fn set_interrupt_interval(&mut self, instructions: u32) {
+        self.interrupt_interval = instructions.max(1);
+    }
+
+    /// Get how many instructions the VM runs between interrupt polls
+    pub fn interrupt_interval(&self) -> u32 {
+        self.interrupt_interval
+    }
+
+    /// Polls the interrupt handler; false if there is none
+    pub(crate) fn interrupt_requested(&self) -> bool {
+        self.interrupt_handler.is_some_and(|handler| handler())
+    }
+
+    /// Set the clock `Date.now()` reads, or `None` to have it throw
+    ///
+    /// With the `std` feature a context starts with
+    /// [`system_clock`](Self::system_clock); otherwise it has none until the
+    /// host sets one, such as a millisecond tick counter.
+    pub fn set_clock(&mut self, clock: Option<Clock>) {
+        self.clock = clock;
+    }
+
+    /// Reads the clock, if there is one
+    pub fn now_ms(&self) -> Option<u64> {
+        self.clock.map(|clock| clock())
+    }
+
+    /// Milliseconds since the Unix epoch from the OS clock, or 0 if it is
+    /// set before the epoch
+    #[cfg(feature = "std")]
+    pub fn system_clock() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64)
+    }
+
+    /// Set how many distinct property names the context may intern
+    ///
+    /// Every name other than a well-known one or an index up to
+    /// [`JSAtom::MAX_INDEX`] is interned in the context's [`AtomTable`],
+    /// which hands out sequential atoms and keeps the name so a key can be
+    /// turned back into a string (for-in, JSON). Interned names live outside
+    /// the heap and are never collected, so this quota bounds the table.
+    /// Defining a property under a new computed name past the quota throws a
+    /// `RangeError`. Identifiers from compiled source are always interned,
+    /// and reading unknown names never counts against the quota.
+    pub fn set_max_property_names(&mut self, count: usize) {
+        self.max_property_names = count;
+    }
+
+    /// Get how many distinct property names the context may intern
+    pub fn max_property_names(&self) -> usize {
+        self.max_property_names
+    }
+
+    /// Seeds Math.random
+    ///
+    /// The same seed always produces the same sequence. Without the `std`
+    /// feature every context starts from a fixed seed, so hosts with an
+    /// entropy source should call this; with `std` it is seeded from the OS.
+    #[cfg(feature = "builtin-math")]
+    pub fn seed_random(&mut self, seed: u64) {
+        self.random_state = seed;
+    }
+
+    /// Returns the next Math.random value
+    #[cfg(feature = "builtin-math")]
+    pub fn next_random(&mut self) -> f64 {
+        crate::builtins::math::random(&mut self.random_state)
+    }
+
+    #[cfg(all(feature = "builtin-math", feature = "std"))]
+    fn initial_random_seed() -> u64 {
+        use std::hash::{BuildHasher, Hasher};
+
+        // RandomState keys are drawn from the OS once per process and
+        // advanced per instance
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        if let Ok(now) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+            hasher.write_u128(now.as_nanos());
+        }
+        hasher.finish()
+    }
+
+    #[cfg(all(feature = "builtin-math", not(feature = "std")))]
+    fn initial_random_seed() -> u64 {
+        crate::builtins::math::DEFAULT_RANDOM_SEED
+    }
+
+    // ========== Atom Names ==========
+
+    /// Resolves a property name to its atom, interning the name
+    ///
+    /// Canonical array indices become index atoms and well-known names
+    /// their fixed atoms; any other name gets the atom the context's
+    /// [`AtomTable`] holds for it. Identifiers from compiled source go
+    /// through here, so they are always interned regardless of the
+    /// `max_property_names` quota.
+    pub fn intern_atom(&mut self, name: &str) -> JSAtom {
+        match crate::runtime::init::index_atom(name) {
+            Some(atom) => atom,
+            None => self.atom_table.intern(name),
+        }
+    }
+
+    /// Like [`Context::intern_atom`], but refuses to intern a new name once
+    /// the `max_property_names` quota is reached
+    pub fn try_intern_atom(&mut self, name: &str) -> Option<JSAtom> {
+        if let Some(atom) = self.lookup_atom(name) {
+            return Some(atom);
+        }
+        if self.atom_table.len() >= self.max_property_names {
+            return None;
+        }
+        Some(self.atom_table.intern(name))
+    }
+
+    /// Resolves a property name to its atom without interning it
+    ///
+    /// Returns None for a name that was never interned, which no property
+    /// can have.
+    pub fn lookup_atom(&self, name: &str) -> Option<JSAtom> {
+        crate::runtime::init::index_atom(name).or_else(|| self.atom_table.lookup(name))
+    }
+
+    /// Returns the string an interned atom stands for
+    ///
+    /// Returns None for index atoms, whose names aren't stored (see
+    /// [`Context::atom_name`]), and for atoms the context didn't hand out.
+    pub fn atom_to_str(&self, atom: JSAtom) -> Option<&str> {
+        self.atom_table.name(atom)
+    }
+
+    /// Returns the property name an atom stands for
+    ///
+    /// Like [`Context::atom_to_str`], but also spells out index atoms.
+    pub fn atom_name(&self, atom: JSAtom) -> Option<Cow<'_, str>> {
+        if let Some(index) = atom.to_index() {
+            return Some(Cow::Owned(index.to_string()));
+        }
+        self.atom_to_str(atom).map(Cow::Borrowed)
+    }
+
+    /// Returns the array index a property key stands for
+    ///
+    /// Index atoms only reach [`JSAtom::MAX_INDEX`]; the rest of the array
+    /// index range, up to 2^32 - 2, is interned under its decimal name.
+    pub fn array_index(&self, key: JSAtom) -> Option<u32> {
+        if let Some(index) = key.to_index() {
+            return Some(index);
+        }
+        let name = self.atom_to_str(key)?;
+        if name.len() != 10 || name.starts_with('0') {
+            return None;
+        }
+        name.parse::<u32>().ok().filter(|&index| index > JSAtom::MAX_INDEX && index != u32::MAX)
+    }
+
+    /// Returns the property key for an array index, interning the decimal
+    /// name of an index past [`JSAtom::MAX_INDEX`]
+    fn array_index_key(&mut self, index: u32) -> Result<JSAtom, crate::memory::allocator::OutOfMemory> {
+        match JSAtom::from_index(index) {
+            Some(key) => Ok(key),
+            None => self.try_intern_atom(&index.to_string()).ok_or(crate::memory::allocator::OutOfMemory),
+        }
+    }
+
+    /// Number of dynamic property names interned so far
+    pub fn atom_name_count(&self) -> usize {
+        self.atom_table.len()
+    }
+
     /// Set the reentrant call mechanism (called by VM during execution)
     pub fn set_reentrant_call(&mut self, vm_ptr: core::ptr::NonNull<u8>, call_fn: ReentrantCallFn) {
         self.vm_ptr = Some(vm_ptr);
@@ -133,29 +685,426 @@ impl Context {
 
     /// Evaluates JavaScript source code
     ///
+    /// Each call runs against the same global object, so a later file sees
+    /// the variables and functions an earlier one defined. Like any
+    /// program, only the value of a final expression statement comes back.
+    ///
     /// # Arguments
     ///
     /// * `source` - JavaScript source code
-    /// * `filename` - Filename for error reporting
-    /// * `eval_flags` - Evaluation flags
+    /// * `filename` - File named by errors from this source
+    /// * `line_offset` - Lines of the file before `source` starts, so
+    ///   errors report lines of the whole file
     ///
     /// # Returns
     ///
-    /// The result of evaluating the script, or an exception value
+    /// The result of evaluating the script, or why it failed
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// ctx.eval("function double(n) { return n * 2; }", "config.js", 0)?;
+    /// let result = ctx.eval("double(21)", "main.js", 0)?;
+    /// assert_eq!(result.to_int(), Some(42));
+    /// ```
+    pub fn eval(&mut self, source: &str, filename: &str, line_offset: u32) -> Result<JSValue, crate::EngineError> {
+        self.check_idle("eval")?;
+        let bytecode = crate::compiler::check_source_size(source, self.max_source_bytes)
+            .and_then(|()| crate::compiler::compile_from_line(source, line_offset));
+        self.run_source(bytecode, filename)
+    }
+
+    /// Evaluates a single expression and returns its value
+    ///
+    /// What a REPL wants: `{ a: 1 }` is an object rather than a block, and
+    /// statements are a syntax error. Arguments are as for `eval`.
     ///
     /// # Example
     ///
     /// ```rust,ignore
-    /// let result = ctx.eval("2 + 2", "calc.js", 0)?;
+    /// let obj = ctx.eval_expr("{ a: 1 }", "<repl>", 0)?;
+    /// assert!(obj.is_object());
     /// ```
-    pub fn eval(&mut self, _source: &str, _filename: &str, _eval_flags: i32) -> JSValue {
-        // TODO: Compile and execute source code
-        JSValue::undefined()
+    pub fn eval_expr(&mut self, source: &str, filename: &str, line_offset: u32) -> Result<JSValue, crate::EngineError> {
+        self.check_idle("eval_expr")?;
+        let bytecode = crate::compiler::check_source_size(source, self.max_source_bytes)
+            .and_then(|()| crate::compiler::compile_expression(source, line_offset));
+        self.run_source(bytecode, filename)
+    }
+
+    /// Runs freshly compiled code for `eval` and `eval_expr`
+    fn run_source(
+        &mut self,
+        bytecode: Result<Vec<u8>, crate::compiler::CompileError>,
+        filename: &str,
+    ) -> Result<JSValue, crate::EngineError> {
+        use crate::EngineError;
+
+        let bytecode = bytecode.map_err(|err| EngineError::from(err).in_file(filename))?;
+        self.compact_for_script();
+        let bytecode_index = self.store_bytecode(&bytecode)
+            .map_err(|_| EngineError::OutOfMemory)?;
+        self.execute_bytecode(bytecode_index)
+            .map_err(|err| EngineError::from_exception(self, err).in_file(filename))
+    }
+
+    /// Copies bytecode into a heap byte array
+    pub fn store_bytecode(&mut self, bytecode: &[u8]) -> Result<HeapIndex, crate::memory::allocator::OutOfMemory> {
+        let len = bytecode.len();
+        let index = self.alloc_byte_array(len)?;
+
+        unsafe {
+            let array = self.get_byte_array_mut(index)
+                .unwrap_or_else(|| crate::fatal::fatal("context", "Bytecode array vanished after allocation"));
+            let slice = array.as_full_mut_slice();
+            slice[..len].copy_from_slice(bytecode);
+            array.header_mut().set_count(len);
+        }
+
+        Ok(index)
+    }
+
+    /// Collects garbage before a new script is stored, if a collection
+    /// during an earlier run left code pinned
+    ///
+    /// A script's code can't move while it runs, so one stored on top of
+    /// the previous run's garbage would hold the heap up to its own end
+    /// until it finished, and each script after it would land higher.
+    /// Does nothing while a script is running.
+    pub(crate) fn compact_for_script(&mut self) {
+        if self.pinned_since_compact && self.run_depth == 0 {
+            self.collect_garbage(&[], false);
+        }
     }
 
     /// Triggers garbage collection
-    pub fn gc(&mut self) {
-        self.gc.collect(&mut self.arena);
+    ///
+    /// Only the context's own roots and `Persistent` handles keep values
+    /// alive, so it fails while a script is running; the VM collects
+    /// between instructions by itself.
+    pub fn gc(&mut self) -> Result<crate::GcStats, ReentrancyError> {
+        self.check_idle("gc")?;
+        Ok(self.collect_garbage(&[], false))
+    }
+
+    /// Fails if a script is running
+    pub(crate) fn check_idle(&self, operation: &'static str) -> Result<(), ReentrancyError> {
+        if self.run_depth > 0 {
+            return Err(ReentrancyError { operation });
+        }
+        Ok(())
+    }
+
+    /// Returns true while a script or a call from the host is running
+    pub fn is_running(&self) -> bool {
+        self.run_depth > 0
+    }
+
+    /// Marks the start of a VM run, for `check_idle`
+    pub(crate) fn enter_run(&mut self) {
+        self.run_depth += 1;
+    }
+
+    /// Marks the end of a run started with `enter_run`
+    pub(crate) fn leave_run(&mut self) {
+        self.run_depth = self.run_depth.saturating_sub(1);
+    }
+
+    /// Collects garbage, treating `roots` as live alongside the context's own
+    /// roots (the global object, the prototypes and the pending exception)
+    ///
+    /// The VM passes its stacks here, with `pin_code` set so the bytecode it
+    /// is running stays in place.
+    pub(crate) fn collect_garbage(&mut self, roots: &[JSValue], pin_code: bool) -> crate::GcStats {
+        let mut all_roots = Vec::with_capacity(roots.len() + 6 + 2 * self.error_prototypes.len());
+        all_roots.extend_from_slice(&[
+            self.global_object,
+            self.object_prototype,
+            self.function_prototype,
+            self.array_prototype,
+            self.uint8array_prototype,
+            self.exception_value,
+        ]);
+        all_roots.extend_from_slice(&self.error_prototypes);
+        all_roots.extend_from_slice(&self.error_constructors);
+        all_roots.extend_from_slice(roots);
+
+        let bytes_before = self.arena.heap_usage();
+        let objects_freed = self.gc.collect_with(&mut self.arena, &all_roots, pin_code);
+        self.live_bytes = self.arena.heap_usage();
+        self.pinned_since_compact = pin_code;
+        self.allocated_since_gc = 0;
+        self.allocations_since_gc = 0;
+        self.gc_threshold = self.next_gc_threshold();
+
+        crate::GcStats { bytes_before, bytes_after: self.live_bytes, objects_freed }
+    }
+
+    /// Heap usage at which the next collection is due: halfway through the
+    /// space that's currently free
+    fn next_gc_threshold(&self) -> usize {
+        self.arena.heap_usage() + self.arena.free_space() / 2
+    }
+
+    /// Returns true when the VM should collect before its next instruction
+    ///
+    /// Collection is due once the heap has grown past the threshold set by
+    /// the previous one, or after the stress interval's worth of
+    /// allocations, and is put off while a native function is running.
+    #[inline]
+    pub(crate) fn should_collect(&self) -> bool {
+        self.can_collect()
+            && (self.arena.heap_usage() > self.gc_threshold
+                || (self.gc_stress_interval != 0 && self.allocations_since_gc >= self.gc_stress_interval))
+    }
+
+    /// Makes the VM collect after every `allocations` allocations, at its
+    /// next instruction, however much heap is free; 0 turns this off
+    ///
+    /// Collecting this often is slow, but it shakes out values the
+    /// collector can't see.
+    pub fn set_gc_stress_interval(&mut self, allocations: usize) {
+        self.gc_stress_interval = allocations;
+    }
+
+    /// Sets how deeply native walkers may recurse into nested arrays and
+    /// objects before throwing a RangeError
+    ///
+    /// The default is [`DEFAULT_MAX_NESTING_DEPTH`]. Raise it only as far as
+    /// the host's stack allows: each level costs a few native frames.
+    pub fn set_max_nesting_depth(&mut self, depth: usize) {
+        self.max_nesting_depth = depth;
+    }
+
+    /// Returns the limit set by `set_max_nesting_depth`
+    pub fn max_nesting_depth(&self) -> usize {
+        self.max_nesting_depth
+    }
+
+    /// Enters one level of a recursive walk over a value
+    ///
+    /// Returns false, without entering, once the budget is spent; the walker
+    /// should then throw `nesting_error`. Every successful call must be
+    /// paired with `leave_nesting`.
+    pub fn enter_nesting(&self) -> bool {
+        let depth = self.nesting_depth.get();
+        if depth >= self.max_nesting_depth {
+            return false;
+        }
+        self.nesting_depth.set(depth + 1);
+        true
+    }
+
+    /// Leaves a level entered with `enter_nesting`
+    pub fn leave_nesting(&self) {
+        self.nesting_depth.set(self.nesting_depth.get().saturating_sub(1));
+    }
+
+    /// Creates the RangeError a walker throws when `enter_nesting` refuses
+    pub fn nesting_error(&mut self) -> JSValue {
+        self.new_error(crate::builtins::error::ErrorKind::Range, "Maximum nesting depth exceeded")
+    }
+
+    /// Sets how many prototype links a chain may have
+    ///
+    /// `Object.create` and `Object.setPrototypeOf` throw a `RangeError` rather
+    /// than build a longer chain, and property lookups stop after this many
+    /// links, so a lookup that misses costs at most the limit. The default is
+    /// [`DEFAULT_MAX_PROTOTYPE_DEPTH`](Self::DEFAULT_MAX_PROTOTYPE_DEPTH).
+    /// Lowering it doesn't shorten chains already built: lookups on them
+    /// just give up sooner.
+    pub fn set_max_prototype_depth(&mut self, depth: usize) {
+        self.max_prototype_depth = depth;
+    }
+
+    /// Returns the limit set by `set_max_prototype_depth`
+    pub fn max_prototype_depth(&self) -> usize {
+        self.max_prototype_depth
+    }
+
+    /// Returns how many prototype links an object with prototype `proto`
+    /// would have above it, counting no further than one past the limit
+    pub fn prototype_depth_above(&self, proto: JSValue) -> usize {
+        let mut depth = 0;
+        let mut current = proto;
+        while depth <= self.max_prototype_depth {
+            let Some(object) = self.get_object(current) else { break };
+            depth += 1;
+            current = object.prototype();
+        }
+        depth
+    }
+
+    /// Creates the `RangeError` thrown for a chain longer than the limit
+    pub fn prototype_depth_error(&mut self) -> JSValue {
+        self.new_error(crate::builtins::error::ErrorKind::Range, "Maximum prototype chain length exceeded")
+    }
+
+    /// Records that a lookup followed its `depth`th prototype link
+    pub(crate) fn follow_prototype_link(&self, depth: usize) {
+        self.prototype_links.set(self.prototype_links.get() + 1);
+        if depth > self.prototype_peak.get() {
+            self.prototype_peak.set(depth);
+        }
+    }
+
+    /// Returns the prototype links followed since the last call, and
+    /// starts counting again
+    pub(crate) fn take_prototype_links(&self) -> u64 {
+        self.prototype_links.replace(0)
+    }
+
+    /// Swaps the record of the longest walk a lookup made, returning the
+    /// previous one
+    pub(crate) fn replace_prototype_peak(&self, depth: usize) -> usize {
+        self.prototype_peak.replace(depth)
+    }
+
+    /// Returns true when the VM may collect at all: no native function is
+    /// holding values the collector can't see
+    #[inline]
+    pub(crate) fn can_collect(&self) -> bool {
+        self.native_depth == 0
+    }
+
+    /// Number of allocations that have failed for lack of space
+    #[inline]
+    pub(crate) fn alloc_failures(&self) -> usize {
+        self.alloc_failures
+    }
+
+    /// Number of collections run so far
+    pub(crate) fn gc_cycles(&self) -> usize {
+        self.gc.cycles()
+    }
+
+    /// Returns heap usage and collector statistics
+    pub fn memory_stats(&self) -> crate::MemoryStats {
+        crate::MemoryStats {
+            heap_size: self.arena_size(),
+            heap_used: self.memory_usage(),
+            object_count: 0, // TODO: Track object count
+            gc_count: self.gc.cycles(),
+            live_bytes: self.live_bytes,
+            peak_used: self.peak_used.max(self.arena.heap_usage()),
+            allocated_since_gc: self.allocated_since_gc,
+        }
+    }
+
+    /// Counts the heap's blocks by memory tag
+    ///
+    /// Walks every block the arena has handed out, the way the collector
+    /// does, and returns one entry per tag that has any blocks, in tag
+    /// order. Counts and byte totals are exact, but include blocks that
+    /// died since the last collection; call [`Context::gc`] first to count
+    /// only live ones.
+    pub fn heap_census(&self) -> Vec<(MemTag, crate::memory::CensusEntry)> {
+        let mut entries = [crate::memory::CensusEntry::default(); crate::memory::census::ALL_TAGS.len()];
+        for header in self.arena.block_headers() {
+            entries[header.mtag() as usize].add(header.size());
+        }
+        crate::memory::census::ALL_TAGS.iter()
+            .zip(entries)
+            .filter(|(_, entry)| entry.count > 0)
+            .map(|(&tag, entry)| (tag, entry))
+            .collect()
+    }
+
+    /// Counts the heap's blocks with tag `mtag` by what allocated them
+    ///
+    /// Like [`Context::heap_census`], but for one tag, broken down by
+    /// [`AllocOrigin`](crate::memory::AllocOrigin), in origin order. Blocks
+    /// allocated by the host or the runtime count as `Host`.
+    #[cfg(feature = "alloc-origin")]
+    pub fn heap_census_by_origin(&self, mtag: MemTag) -> Vec<(crate::memory::AllocOrigin, crate::memory::CensusEntry)> {
+        let mut origins: BTreeMap<crate::memory::AllocOrigin, crate::memory::CensusEntry> = BTreeMap::new();
+        for header in self.arena.block_headers().filter(|header| header.mtag() == mtag) {
+            let origin = crate::memory::AllocOrigin::from_code(header.origin_code());
+            origins.entry(origin).or_default().add(header.size());
+        }
+        origins.into_iter().collect()
+    }
+
+    /// Describes an allocation origin: "host", "native", an opcode name such
+    /// as "call_method", or the name a builtin was installed under
+    #[cfg(feature = "alloc-origin")]
+    pub fn alloc_origin_name(&self, origin: crate::memory::AllocOrigin) -> String {
+        use crate::memory::AllocOrigin;
+        match origin {
+            AllocOrigin::Host => String::from("host"),
+            AllocOrigin::Native => String::from("native"),
+            AllocOrigin::Opcode(op) => crate::bytecode::Opcode::from_u8(op)
+                .map_or_else(|| alloc::format!("opcode {}", op), |op| String::from(op.name())),
+            AllocOrigin::Builtin(index) => self.builtin_origins.get(index as usize)
+                .and_then(|&(_, name)| self.atom_name(name))
+                .map_or_else(|| String::from("builtin"), |name| name.into_owned()),
+        }
+    }
+
+    /// Sets what new heap blocks are recorded as allocated by, returning
+    /// the previous origin
+    #[cfg(feature = "alloc-origin")]
+    pub(crate) fn set_alloc_origin(&mut self, origin: crate::memory::AllocOrigin) -> crate::memory::AllocOrigin {
+        core::mem::replace(&mut self.alloc_origin, origin)
+    }
+
+    /// Registers `func`, if it's a native function, as the builtin `name`
+    ///
+    /// The runtime calls this as it installs builtins. The registry holds
+    /// 256 builtins; later ones count as plain natives.
+    #[cfg(feature = "alloc-origin")]
+    pub(crate) fn note_builtin_origin(&mut self, func: JSValue, name: JSAtom) {
+        let Some(cfunc) = self.get_native_function(func) else {
+            return;
+        };
+        let ptr = cfunc.func_ptr() as usize;
+        if self.builtin_origins.len() <= u8::MAX as usize && !self.builtin_origins.iter().any(|&(known, _)| known == ptr) {
+            self.builtin_origins.push((ptr, name));
+        }
+    }
+
+    /// Returns the origin allocations inside native function `func` get
+    #[cfg(feature = "alloc-origin")]
+    fn native_origin(&self, func: crate::object::function::NativeFn) -> crate::memory::AllocOrigin {
+        let ptr = func as usize;
+        match self.builtin_origins.iter().position(|&(known, _)| known == ptr) {
+            Some(index) => crate::memory::AllocOrigin::Builtin(index as u8),
+            None => crate::memory::AllocOrigin::Native,
+        }
+    }
+
+    /// Swaps the console capture buffer, returning the previous one
+    ///
+    /// While a buffer is installed, console methods append their lines to
+    /// it instead of printing. Callers restore what they got back when done,
+    /// so captures nest.
+    pub(crate) fn replace_console_capture(&mut self, capture: Option<Vec<String>>) -> Option<Vec<String>> {
+        core::mem::replace(&mut self.console_capture, capture)
+    }
+
+    /// Appends a console line to the capture buffer
+    ///
+    /// Returns false, leaving the line to be printed, when nothing is capturing.
+    pub(crate) fn capture_console_line(&mut self, line: &str) -> bool {
+        match &mut self.console_capture {
+            Some(lines) => {
+                lines.push(String::from(line));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Records the outcome of a `test(name, fn)` call
+    #[cfg(feature = "test-api")]
+    pub(crate) fn record_test_result(&mut self, result: crate::builtins::test_api::TestResult) {
+        self.test_results.push(result);
+    }
+
+    /// Outcomes of every `test(name, fn)` call so far, in order
+    #[cfg(feature = "test-api")]
+    pub fn test_results(&self) -> &[crate::builtins::test_api::TestResult] {
+        &self.test_results
     }
 
     /// Returns the current memory usage in bytes
@@ -186,6 +1135,37 @@ impl Context {
         self.gc.remove_root(value);
     }
 
+    /// Roots a value for the host until the returned handle is detached
+    /// or the current epoch ends
+    pub fn persist(&mut self, value: JSValue) -> Persistent {
+        self.persistent.insert(&mut self.gc, value)
+    }
+
+    /// Returns the value behind a handle, or None once it's been detached
+    /// or its epoch has ended
+    pub fn persistent_value(&self, handle: &Persistent) -> Option<JSValue> {
+        self.persistent.get(handle)
+    }
+
+    /// Unroots the value behind a handle and leaves the handle empty
+    pub fn detach(&mut self, handle: &mut Persistent) {
+        self.persistent.detach(&mut self.gc, handle);
+    }
+
+    /// Invalidates every outstanding `Persistent` handle at once
+    ///
+    /// Meant for tearing down a tenant's state: handles other subsystems
+    /// still hold read as None afterwards instead of keeping the old values
+    /// alive. Returns the new epoch.
+    pub fn bump_epoch(&mut self) -> u32 {
+        self.persistent.bump_epoch(&mut self.gc)
+    }
+
+    /// Returns the number of values held through `Persistent` handles
+    pub fn persistent_count(&self) -> usize {
+        self.persistent.len()
+    }
+
     /// Allocates memory from the arena
     ///
     /// This is a low-level method for internal use.
@@ -198,7 +1178,19 @@ impl Context {
         size: usize,
         mtag: crate::memory::MemTag,
     ) -> Result<HeapIndex, crate::memory::allocator::OutOfMemory> {
-        self.arena.alloc(size, mtag)
+        let result = self.arena.alloc(size, mtag);
+        #[cfg(feature = "alloc-origin")]
+        if let Ok(index) = result {
+            self.arena.get_header_mut(index).set_origin_code(self.alloc_origin.code());
+        }
+        if result.is_ok() {
+            self.allocated_since_gc += Arena::block_size(size);
+            self.allocations_since_gc += 1;
+            self.peak_used = self.peak_used.max(self.arena.heap_usage());
+        } else {
+            self.alloc_failures += 1;
+        }
+        result
     }
 
     /// Gets a reference to the arena (for internal use)
@@ -219,14 +1211,17 @@ impl Context {
     ///
     /// The string is allocated on the heap and stored in UTF-8 format.
     pub fn new_string(&mut self, s: &str) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
-        use crate::value::{JSString, JSStringHeader};
+        self.new_string_from_parts(&[s])
+    }
 
-        let bytes = s.as_bytes();
-        let len = bytes.len();
+    /// Creates a new JavaScript string from the concatenation of `parts`
+    ///
+    /// The parts are copied straight into the new string's block, which is
+    /// sealed once they're all written.
+    pub fn new_string_from_parts(&mut self, parts: &[&str]) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
+        use crate::value::{JSString, JSStringWriter};
 
-        // Check flags
-        let is_ascii = JSString::check_ascii(bytes);
-        let is_numeric = JSString::check_numeric(bytes);
+        let len = parts.iter().map(|part| part.len()).sum();
 
         // Calculate total size: MemBlockHeader + JSStringHeader + UTF-8 data
         let total_size = core::mem::size_of::<crate::memory::MemBlockHeader>()
@@ -235,16 +1230,12 @@ impl Context {
         // Allocate memory
         let index = unsafe { self.alloc_raw(total_size, MemTag::String)? };
 
-        // Initialize the string header
-        unsafe {
-            let string: &mut JSString = self.arena.get_mut(index);
-            *string.header_mut() = JSStringHeader::new(len, is_ascii, is_numeric);
-
-            // Copy UTF-8 data
-            let data_ptr = (string as *mut JSString as *mut u8)
-                .add(core::mem::size_of::<JSStringHeader>());
-            core::ptr::copy_nonoverlapping(bytes.as_ptr(), data_ptr, len);
+        // SAFETY: the block was just allocated with room for `len` bytes
+        let mut writer = unsafe { JSStringWriter::new(self.arena.get_mut(index), len) };
+        for part in parts {
+            writer.push_str(part);
         }
+        writer.finish();
 
         Ok(JSValue::from_ptr(index))
     }
@@ -316,6 +1307,23 @@ impl Context {
         }
     }
 
+    /// Compares two values with SameValue semantics
+    ///
+    /// NaN equals itself, +0 and -0 differ, strings compare by content and
+    /// objects by identity.
+    pub fn same_value(&self, a: JSValue, b: JSValue) -> bool {
+        crate::runtime::compare::same_value(self, a, b)
+    }
+
+    /// Hashes a value consistently with [`Context::same_value`]
+    ///
+    /// An object hashes by its heap index, which survives compaction but
+    /// may be reused once the object is collected, so keep hashed objects
+    /// rooted (e.g. through [`Context::persist`]).
+    pub fn value_hash(&self, value: JSValue) -> u64 {
+        crate::runtime::compare::value_hash(self, value)
+    }
+
     // ========== Array Operations ==========
 
     /// Allocates a JSValueArray with the specified capacity
@@ -365,51 +1373,547 @@ impl Context {
             }
         }
 
-        Ok(index)
+        Ok(index)
+    }
+
+    /// Gets a reference to a value array
+    pub fn get_value_array(&self, index: HeapIndex) -> Option<&crate::value::JSValueArray> {
+        unsafe {
+            let header = self.arena.get_header(index);
+            if header.mtag() != MemTag::ValueArray {
+                return None;
+            }
+            Some(self.arena.get(index))
+        }
+    }
+
+    /// Gets a mutable reference to a value array
+    pub fn get_value_array_mut(&mut self, index: HeapIndex) -> Option<&mut crate::value::JSValueArray> {
+        unsafe {
+            let header = self.arena.get_header(index);
+            if header.mtag() != MemTag::ValueArray {
+                return None;
+            }
+            Some(self.arena.get_mut(index))
+        }
+    }
+
+    /// Gets a reference to a byte array
+    pub fn get_byte_array(&self, index: HeapIndex) -> Option<&crate::value::JSByteArray> {
+        unsafe {
+            let header = self.arena.get_header(index);
+            if header.mtag() != MemTag::ByteArray {
+                return None;
+            }
+            Some(self.arena.get(index))
+        }
+    }
+
+    /// Gets a mutable reference to a byte array
+    pub fn get_byte_array_mut(&mut self, index: HeapIndex) -> Option<&mut crate::value::JSByteArray> {
+        unsafe {
+            let header = self.arena.get_header(index);
+            if header.mtag() != MemTag::ByteArray {
+                return None;
+            }
+            Some(self.arena.get_mut(index))
+        }
+    }
+
+    /// Creates a new empty Array object
+    ///
+    /// The array has `Array.prototype` as its prototype and stores its
+    /// elements in a dense backing store that is allocated on first write.
+    pub fn new_array(&mut self) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
+        use crate::object::{JSArray, JSClassID, JSObject};
+
+        let proto = self.array_prototype;
+        let arr = self.new_object_with_proto(proto)?;
+
+        let total_size = core::mem::size_of::<crate::memory::MemBlockHeader>()
+            + core::mem::size_of::<JSArray>();
+        // SAFETY: the block is sized for a JSArray and initialized below
+        let data_index = unsafe { self.alloc_raw(total_size, MemTag::ArrayData)? };
+
+        // SAFETY: both blocks were just allocated, with these types
+        unsafe {
+            let data: &mut JSArray = self.arena.get_mut(data_index);
+            *data = JSArray::new(0);
+
+            let obj: &mut JSObject = self.arena.get_mut(arr.to_ptr().unwrap_unchecked());
+            obj.set_class_id(JSClassID::Array);
+            obj.set_class_data_index(data_index);
+        }
+
+        Ok(arr)
+    }
+
+    /// Returns the array class data index if `val` is an Array object
+    fn array_data_index(&self, val: JSValue) -> Option<HeapIndex> {
+        let obj = self.get_object(val)?;
+        if !obj.is_array() || !obj.has_class_data() {
+            return None;
+        }
+        let index = obj.class_data_index();
+        // SAFETY: class data indices always name allocated blocks
+        unsafe {
+            if self.arena.get_header(index).mtag() != MemTag::ArrayData {
+                return None;
+            }
+        }
+        Some(index)
+    }
+
+    /// Returns true if `val` is an Array object with element storage
+    pub fn is_array(&self, val: JSValue) -> bool {
+        self.array_data_index(val).is_some()
+    }
+
+    /// Returns the length of an Array object
+    ///
+    /// Returns None if `val` is not an Array object.
+    pub fn array_length(&self, val: JSValue) -> Option<u32> {
+        let index = self.array_data_index(val)?;
+        // SAFETY: array_data_index checked the block is array data
+        let data: &crate::object::JSArray = unsafe { self.arena.get(index) };
+        Some(data.len())
+    }
+
+    /// Creates a `Uint8Array` of `length` zero bytes
+    ///
+    /// The bytes live in a `JSByteArray` held as the object's class data.
+    /// Unlike bytecode, they may move in any collection, so a slice from
+    /// [`Context::uint8array_data`] mustn't be kept across script calls.
+    pub fn new_uint8array(&mut self, length: usize) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
+        use crate::object::{JSClassID, JSObject};
+
+        let arr = self.new_object_with_proto(self.uint8array_prototype)?;
+        let bytes = self.alloc_byte_array(length)?;
+        // SAFETY: both blocks were just allocated, with these types
+        unsafe {
+            self.arena.get_header_mut(bytes).set_movable();
+            let data: &mut crate::value::JSByteArray = self.arena.get_mut(bytes);
+            data.header_mut().set_count(length);
+
+            let obj: &mut JSObject = self.arena.get_mut(arr.to_ptr().unwrap_unchecked());
+            obj.set_class_id(JSClassID::Uint8Array);
+            obj.set_class_data_index(bytes);
+        }
+        Ok(arr)
+    }
+
+    /// Creates a `Uint8Array` holding a copy of `data`
+    pub fn new_uint8array_from_slice(&mut self, data: &[u8]) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
+        let arr = self.new_uint8array(data.len())?;
+        if let Some(bytes) = self.uint8array_data_mut(arr) {
+            bytes.copy_from_slice(data);
+        }
+        Ok(arr)
+    }
+
+    /// Returns the byte store index if `val` is a `Uint8Array`
+    fn uint8array_index(&self, val: JSValue) -> Option<HeapIndex> {
+        let obj = self.get_object(val)?;
+        if obj.class_id() != crate::object::JSClassID::Uint8Array || !obj.has_class_data() {
+            return None;
+        }
+        let index = obj.class_data_index();
+        // SAFETY: class data indices always name allocated blocks
+        let mtag = unsafe { self.arena.get_header(index).mtag() };
+        (mtag == MemTag::ByteArray).then_some(index)
+    }
+
+    /// Returns the bytes of a `Uint8Array`
+    ///
+    /// Returns None if `val` is not a `Uint8Array`.
+    pub fn uint8array_data(&self, val: JSValue) -> Option<&[u8]> {
+        let index = self.uint8array_index(val)?;
+        // SAFETY: get_byte_array checked the block is a byte array
+        self.get_byte_array(index).map(|bytes| unsafe { bytes.as_slice() })
+    }
+
+    /// Returns the bytes of a `Uint8Array` for writing
+    ///
+    /// Returns None if `val` is not a `Uint8Array`.
+    pub fn uint8array_data_mut(&mut self, val: JSValue) -> Option<&mut [u8]> {
+        let index = self.uint8array_index(val)?;
+        // SAFETY: get_byte_array_mut checked the block is a byte array
+        self.get_byte_array_mut(index).map(|bytes| unsafe { bytes.as_mut_slice() })
+    }
+
+    /// Stores `value` at `index` of a `Uint8Array`, wrapped to a byte as
+    /// `ToUint8` does
+    ///
+    /// Writes past the end are dropped. Returns false if `val` is not a
+    /// `Uint8Array`.
+    fn set_uint8array_element(&mut self, val: JSValue, index: u32, value: JSValue) -> bool {
+        // ToUint8 is ToUint32 modulo 256
+        let wrapped = crate::runtime::conversion::to_uint32(self, value) as u8;
+        let Some(bytes) = self.uint8array_data_mut(val) else {
+            return false;
+        };
+        if let Some(byte) = bytes.get_mut(index as usize) {
+            *byte = wrapped;
+        }
+        true
+    }
+
+    /// Reads an element of an Array object
+    ///
+    /// Returns None if `val` is not an Array object or the element isn't in
+    /// the dense store (sparse elements live in the property table). Holes
+    /// inside the dense store, such as `[1, , 3][1]`, are missing too.
+    pub fn get_array_element(&self, val: JSValue, index: u32) -> Option<JSValue> {
+        let data_index = self.array_data_index(val)?;
+        // SAFETY: array_data_index checked the block is array data
+        let data: &crate::object::JSArray = unsafe { self.arena.get(data_index) };
+        if index >= data.len() || !data.has_elements() {
+            return None;
+        }
+        let elements = self.get_value_array(data.elements_index())?;
+        if (index as usize) >= elements.header().count() {
+            return None;
+        }
+        // SAFETY: the index is below the initialized count
+        Some(unsafe { elements.get_unchecked(index as usize) }).filter(|value| !value.is_hole())
+    }
+
+    /// Writes an element of an Array object, growing the dense store as needed
+    ///
+    /// Writing past the end extends `length`; skipped elements are holes. Indices far beyond the dense store's capacity are kept in
+    /// the property table instead, so `a[1000000] = 1` doesn't allocate a
+    /// million slots.
+    ///
+    /// Returns `Ok(false)` if `val` is not an Array object.
+    pub fn set_array_element(
+        &mut self,
+        val: JSValue,
+        index: u32,
+        value: JSValue,
+    ) -> Result<bool, crate::memory::allocator::OutOfMemory> {
+        use crate::object::JSArray;
+
+        /// Largest gap past the current capacity that is still stored densely
+        const MAX_DENSE_GAP: usize = 64;
+
+        let Some(data_index) = self.array_data_index(val) else {
+            return Ok(false);
+        };
+
+        let (elements, length) = {
+            // SAFETY: array_data_index checked the block is array data
+            let data: &JSArray = unsafe { self.arena.get(data_index) };
+            (data.elements_index(), data.len())
+        };
+        let capacity = if elements.is_null() {
+            0
+        } else {
+            self.get_value_array(elements).map_or(0, |a| a.header().capacity())
+        };
+
+        let slot = index as usize;
+        if slot >= capacity {
+            if slot > capacity + MAX_DENSE_GAP {
+                // Sparse write: keep it out of the dense store
+                let atom = self.array_index_key(index)?;
+                self.add_own_property(val, atom, value, crate::object::PropertyFlags::default())?;
+                if index >= length {
+                    // SAFETY: allocating never collects, so data_index still
+                    // names the array data after the property write
+                    let data: &mut JSArray = unsafe { self.arena.get_mut(data_index) };
+                    data.set_len(index + 1);
+                }
+                return Ok(true);
+            }
+
+            self.grow_array_elements(data_index, slot + 1)?;
+        }
+
+        let elements = {
+            // SAFETY: allocating never collects, so data_index still
+            // names the array data after growing the dense store
+            let data: &mut JSArray = unsafe { self.arena.get_mut(data_index) };
+            if index >= data.len() {
+                data.set_len(index + 1);
+            }
+            data.elements_index()
+        };
+        let array = self
+            .get_value_array_mut(elements)
+            .ok_or(crate::memory::allocator::OutOfMemory)?;
+        let count = array.header().count();
+        // SAFETY: the store was grown above so `slot` is within its
+        // capacity, and the slots up to it are filled before count moves
+        unsafe {
+            let slots = array.as_full_mut_slice();
+            if slot > count {
+                slots[count..slot].fill(JSValue::hole());
+            }
+            slots[slot] = value;
+        }
+        if slot >= count {
+            array.header_mut().set_count(slot + 1);
+        }
+
+        // An earlier sparse write may have left this index in the property
+        // table; the dense store holds it now
+        if let Some(key) = crate::value::JSAtom::from_index(index) {
+            if self.find_own_property(val, key).is_some() {
+                self.discard_own_properties(val, |k| k == key);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Creates an Array object holding `values`, for passing batches of
+    /// samples into a script
+    ///
+    /// The dense store is allocated once at full size rather than grown
+    /// element by element. Integers are stored inline; other numbers take
+    /// one boxed float each.
+    pub fn new_array_from_f64_slice(&mut self, values: &[f64]) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
+        use crate::object::JSArray;
+        use crate::value::JSValueArray;
+
+        let length = u32::try_from(values.len()).map_err(|_| crate::memory::allocator::OutOfMemory)?;
+        let arr = self.new_array()?;
+        if values.is_empty() {
+            return Ok(arr);
+        }
+
+        let elements = self.alloc_value_array(values.len())?;
+        let numbers = values
+            .iter()
+            .map(|&value| self.new_number(value))
+            .collect::<Result<Vec<_>, _>>()?;
+        // SAFETY: both blocks were allocated above, with these types, and
+        // allocating numbers never collects
+        unsafe {
+            let array = self.arena.get_mut::<JSValueArray>(elements);
+            array.as_full_mut_slice().copy_from_slice(&numbers);
+            array.header_mut().set_count(numbers.len());
+            let data_index = self.array_data_index(arr).unwrap_unchecked();
+            *self.arena.get_mut::<JSArray>(data_index) = JSArray::with_elements(elements, length);
+        }
+        Ok(arr)
+    }
+
+    /// Reads an Array object's elements as numbers, for reading back a
+    /// batch a script produced
+    ///
+    /// Numbers are read straight from the dense store. Anything else,
+    /// including holes and elements kept in the property table, goes
+    /// through `ToNumber`, so `[1, '2', , true]` reads as `[1, 2, NaN, 1]`.
+    ///
+    /// Returns None if `val` is not an Array object.
+    pub fn array_to_f64_vec(&self, val: JSValue) -> Option<Vec<f64>> {
+        use crate::runtime::conversion::to_number;
+
+        let data_index = self.array_data_index(val)?;
+        // SAFETY: array_data_index checked the block is array data
+        let data: &crate::object::JSArray = unsafe { self.arena.get(data_index) };
+        let dense: &[JSValue] = if data.has_elements() {
+            // SAFETY: as_slice only covers the initialized elements
+            self.get_value_array(data.elements_index()).map_or(&[], |elements| unsafe { elements.as_slice() })
+        } else {
+            &[]
+        };
+
+        let mut numbers = Vec::with_capacity(data.len() as usize);
+        for index in 0..data.len() {
+            let value = match dense.get(index as usize) {
+                Some(&value) if !value.is_hole() => value,
+                _ => JSAtom::from_index(index)
+                    .and_then(|key| self.get_property(val, key))
+                    .unwrap_or(JSValue::undefined()),
+            };
+            numbers.push(self.get_number(value).unwrap_or_else(|| to_number(self, value)));
+        }
+        Some(numbers)
     }
 
-    /// Gets a reference to a value array
-    pub fn get_value_array(&self, index: HeapIndex) -> Option<&crate::value::JSValueArray> {
-        unsafe {
-            let header = self.arena.get_header(index);
-            if header.mtag() != MemTag::ValueArray {
-                return None;
+    /// Moves an Array's dense store into a bigger one holding at least
+    /// `min_capacity` elements
+    ///
+    /// Grows geometrically, copying the existing elements across.
+    fn grow_array_elements(
+        &mut self,
+        data_index: HeapIndex,
+        min_capacity: usize,
+    ) -> Result<HeapIndex, crate::memory::allocator::OutOfMemory> {
+        use crate::object::JSArray;
+
+        // SAFETY: callers pass the index of an array's class data
+        let elements = unsafe { self.arena.get::<JSArray>(data_index) }.elements_index();
+        let capacity = if elements.is_null() {
+            0
+        } else {
+            self.get_value_array(elements).map_or(0, |a| a.header().capacity())
+        };
+
+        let new_capacity = (capacity * 2).max(min_capacity).max(4);
+        let new_elements = self.alloc_value_array(new_capacity)?;
+        if !elements.is_null() {
+            let old: alloc::vec::Vec<JSValue> = self
+                .get_value_array(elements)
+                // SAFETY: as_slice only covers the initialized elements
+                .map(|a| unsafe { a.as_slice() }.to_vec())
+                .unwrap_or_default();
+            if let Some(new) = self.get_value_array_mut(new_elements) {
+                // SAFETY: the new store's capacity is at least the old count
+                unsafe { new.as_full_mut_slice()[..old.len()].copy_from_slice(&old) };
+                new.header_mut().set_count(old.len());
             }
-            Some(self.arena.get(index))
         }
+        // SAFETY: allocating never collects, so data_index still names the
+        // array data
+        let data: &mut JSArray = unsafe { self.arena.get_mut(data_index) };
+        data.set_elements_index(new_elements);
+        Ok(new_elements)
     }
 
-    /// Gets a mutable reference to a value array
-    pub fn get_value_array_mut(&mut self, index: HeapIndex) -> Option<&mut crate::value::JSValueArray> {
+    /// Replaces `delete_count` elements of an Array object, starting at
+    /// `start`, with `items`
+    ///
+    /// The elements after the replaced range move as one block and
+    /// `length` follows. Only arrays whose elements all sit in the dense
+    /// store take this path: returns `Ok(false)`, changing nothing, if `val`
+    /// is not an Array object or its length runs past the dense store, so
+    /// the caller can fall back to moving one index at a time. `start +
+    /// delete_count` must not exceed the length.
+    pub fn splice_array_elements(
+        &mut self,
+        val: JSValue,
+        start: u32,
+        delete_count: u32,
+        items: &[JSValue],
+    ) -> Result<bool, crate::memory::allocator::OutOfMemory> {
+        use crate::object::JSArray;
+
+        let Some(data_index) = self.array_data_index(val) else {
+            return Ok(false);
+        };
+        let (elements, length) = {
+            // SAFETY: array_data_index checked the block is array data
+            let data: &JSArray = unsafe { self.arena.get(data_index) };
+            (data.elements_index(), data.len())
+        };
+        let (count, capacity) = if elements.is_null() {
+            (0, 0)
+        } else {
+            self.get_value_array(elements)
+                .map_or((0, 0), |a| (a.header().count(), a.header().capacity()))
+        };
+        if count != length as usize {
+            return Ok(false);
+        }
+
+        let (start, removed) = (start as usize, delete_count as usize);
+        if removed == 0 && items.is_empty() {
+            return Ok(true);
+        }
+        let new_count = count - removed + items.len();
+        let elements = if new_count > capacity {
+            self.grow_array_elements(data_index, new_count)?
+        } else {
+            elements
+        };
+
+        let array = self
+            .get_value_array_mut(elements)
+            .ok_or(crate::memory::allocator::OutOfMemory)?;
+        // SAFETY: the store holds at least max(count, new_count) slots, and
+        // start + removed <= count as the caller guarantees
         unsafe {
-            let header = self.arena.get_header(index);
-            if header.mtag() != MemTag::ValueArray {
-                return None;
+            let slots = array.as_full_mut_slice();
+            slots.copy_within(start + removed..count, start + items.len());
+            slots[start..start + items.len()].copy_from_slice(items);
+            // Cleared slots drop their references; count no longer covers
+            // them, so growing refills them with holes
+            if new_count < count {
+                slots[new_count..count].fill(JSValue::undefined());
             }
-            Some(self.arena.get_mut(index))
         }
+        array.header_mut().set_count(new_count);
+        // SAFETY: allocating never collects, so data_index still names the
+        // array data
+        let data: &mut JSArray = unsafe { self.arena.get_mut(data_index) };
+        data.set_len(new_count as u32);
+        Ok(true)
     }
 
-    /// Gets a reference to a byte array
-    pub fn get_byte_array(&self, index: HeapIndex) -> Option<&crate::value::JSByteArray> {
-        unsafe {
-            let header = self.arena.get_header(index);
-            if header.mtag() != MemTag::ByteArray {
-                return None;
+    /// Sets the length of an Array object
+    ///
+    /// Shrinking discards the elements past the new length. Returns
+    /// `Ok(false)` if `val` is not an Array object.
+    pub fn set_array_length(
+        &mut self,
+        val: JSValue,
+        length: u32,
+    ) -> Result<bool, crate::memory::allocator::OutOfMemory> {
+        use crate::object::JSArray;
+
+        let Some(data_index) = self.array_data_index(val) else {
+            return Ok(false);
+        };
+
+        let elements = {
+            // SAFETY: array_data_index checked the block is array data
+            let data: &mut JSArray = unsafe { self.arena.get_mut(data_index) };
+            data.set_len(length);
+            data.elements_index()
+        };
+
+        if !elements.is_null() {
+            if let Some(array) = self.get_value_array_mut(elements) {
+                let count = array.header().count();
+                let new_count = count.min(length as usize);
+                // Cleared slots drop their references; count no longer
+                // covers them, so growing refills them with holes.
+                // SAFETY: new_count..count lies within the initialized slots
+                for slot in unsafe { &mut array.as_full_mut_slice()[new_count..count] } {
+                    *slot = JSValue::undefined();
+                }
+                array.header_mut().set_count(new_count);
             }
-            Some(self.arena.get(index))
         }
+
+        // Sparse elements past the new length go too, including those keyed
+        // by name beyond the index atoms' range
+        let named: Vec<JSAtom> = self.own_property_names(val).into_iter()
+            .filter(|&key| self.array_index(key).is_some_and(|index| index >= length))
+            .collect();
+        self.discard_own_properties(val, |key| {
+            key.to_index().is_some_and(|index| index >= length) || named.contains(&key)
+        });
+
+        Ok(true)
     }
 
-    /// Gets a mutable reference to a byte array
-    pub fn get_byte_array_mut(&mut self, index: HeapIndex) -> Option<&mut crate::value::JSByteArray> {
-        unsafe {
-            let header = self.arena.get_header(index);
-            if header.mtag() != MemTag::ByteArray {
-                return None;
+    /// Reads an own element or `length` of an Array object
+    ///
+    /// Returns None when `key` isn't array state, so the caller should fall
+    /// back to the property table.
+    fn get_array_own(&self, val: JSValue, key: crate::value::JSAtom) -> Option<JSValue> {
+        if let Some(bytes) = self.uint8array_data(val) {
+            // Indices past the end read as undefined without a prototype lookup
+            if key == crate::value::atoms::LENGTH {
+                return Some(JSValue::from_int(bytes.len() as i32));
             }
-            Some(self.arena.get_mut(index))
+            let index = key.to_index()?;
+            let byte = bytes.get(index as usize);
+            return Some(byte.map_or(JSValue::undefined(), |&b| JSValue::from_int(i32::from(b))));
         }
+        if key == crate::value::atoms::LENGTH {
+            let length = self.array_length(val)?;
+            return Some(i32::try_from(length).map_or(JSValue::undefined(), JSValue::from_int));
+        }
+        let index = self.array_index(key)?;
+        if index >= self.array_length(val)? {
+            // Past the end; any stale sparse entry was truncated away
+            return Some(JSValue::undefined());
+        }
+        self.get_array_element(val, index)
     }
 
     // ========== Object Operations ==========
@@ -427,6 +1931,42 @@ impl Context {
         self.new_object_with_proto(proto)
     }
 
+    /// Creates a new plain object whose property table holds `capacity`
+    /// entries before it first grows
+    ///
+    /// For callers that know how many properties they are about to add, so
+    /// the table is allocated once instead of doubling its way up. With a
+    /// capacity of 0 this is [`new_object`](Self::new_object).
+    pub fn new_object_with_capacity(&mut self, capacity: usize) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
+        let obj = self.new_object()?;
+        self.reserve_properties(obj, capacity)?;
+        Ok(obj)
+    }
+
+    /// Gives an object or function without properties a table of
+    /// `capacity` entries
+    ///
+    /// Does nothing for a capacity of 0, or if it already has one.
+    pub(crate) fn reserve_properties(&mut self, obj_val: JSValue, capacity: usize) -> Result<(), crate::memory::allocator::OutOfMemory> {
+        use crate::object::Property;
+
+        let Some(obj_index) = obj_val.to_ptr() else {
+            return Ok(());
+        };
+        if capacity == 0 || self.props_slot(obj_index) != Some(HeapIndex::null()) {
+            return Ok(());
+        }
+        // More entries than the heap could hold can't be allocated anyway
+        let capacity = u32::try_from(capacity)
+            .ok()
+            .filter(|&capacity| capacity as usize <= self.arena_size() / core::mem::size_of::<Property>())
+            .ok_or(crate::memory::allocator::OutOfMemory)?;
+        let props = self.alloc_property_table(capacity)?;
+        // Allocating doesn't collect, so the object is still live
+        self.set_props_slot(obj_index, props);
+        Ok(())
+    }
+
     /// Creates a new JavaScript object with a specific prototype
     pub fn new_object_with_proto(
         &mut self,
@@ -478,6 +2018,31 @@ impl Context {
         }
     }
 
+    /// Freezes an object, making its properties read-only and
+    /// non-configurable and refusing new ones
+    ///
+    /// Elements of arrays and typed arrays stay writable. Returns false if
+    /// `val` is not an object.
+    pub fn freeze(&mut self, val: JSValue) -> bool {
+        let Some(obj) = self.get_object_mut(val) else {
+            return false;
+        };
+        obj.freeze();
+        let props_index = obj.has_properties().then(|| obj.props_index());
+        if let Some(props_table) = props_index.and_then(|index| self.get_property_table_mut(index)) {
+            // SAFETY: get_property_table_mut checked the block is a property table
+            for prop in unsafe { props_table.properties_mut() } {
+                let mut flags = prop.flags();
+                flags.set_configurable(false);
+                if prop.is_data() {
+                    flags.set_writable(false);
+                }
+                prop.set_flags(flags);
+            }
+        }
+        true
+    }
+
     /// Allocates a new property table with the specified capacity
     ///
     /// Returns the HeapIndex of the allocated property table.
@@ -556,46 +2121,118 @@ impl Context {
     ) -> Option<&crate::object::Property> {
         use crate::object::Property;
 
-        let obj = self.get_object(obj_val)?;
-        if !obj.has_properties() {
-            return None;
+        let props_table = self.get_property_table(self.own_props_index(obj_val)?)?;
+        let slot = Self::property_slot(props_table, key)?;
+        // SAFETY: get_property_table checked the block's tag
+        unsafe { props_table.properties().get(slot) }
+    }
+
+    /// Returns the property table slot of a heap block that can have own
+    /// properties: an object, or a function of any kind
+    ///
+    /// The slot holds a null index until the first property is added.
+    fn props_slot(&self, index: HeapIndex) -> Option<HeapIndex> {
+        use crate::object::function::{JSBytecodeFunction, JSCFunction, JSClosure};
+
+        // SAFETY: the block's tag says which struct it holds
+        unsafe {
+            match self.arena.get_header(index).mtag() {
+                MemTag::Object => Some(self.arena.get::<crate::object::JSObject>(index).props_index()),
+                MemTag::ClosureData => Some(self.arena.get::<JSClosure>(index).props),
+                MemTag::FunctionBytecode => Some(self.arena.get::<JSBytecodeFunction>(index).props),
+                MemTag::CFunctionData => Some(self.arena.get::<JSCFunction>(index).props),
+                _ => None,
+            }
         }
+    }
 
-        let props_table = self.get_property_table(obj.props_index())?;
+    /// Points the property table slot of an object or function at `props`
+    fn set_props_slot(&mut self, index: HeapIndex, props: HeapIndex) {
+        use crate::object::function::{JSBytecodeFunction, JSCFunction, JSClosure};
 
+        // SAFETY: the block's tag says which struct it holds
         unsafe {
-            let header = props_table.header();
-            let count = header.count();
+            match self.arena.get_header(index).mtag() {
+                MemTag::Object => self.arena.get_mut::<crate::object::JSObject>(index).set_props_index(props),
+                MemTag::ClosureData => self.arena.get_mut::<JSClosure>(index).props = props,
+                MemTag::FunctionBytecode => self.arena.get_mut::<JSBytecodeFunction>(index).props = props,
+                MemTag::CFunctionData => self.arena.get_mut::<JSCFunction>(index).props = props,
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns the property table holding a value's own properties, if it
+    /// has one yet
+    fn own_props_index(&self, val: JSValue) -> Option<HeapIndex> {
+        val.to_ptr()
+            .and_then(|index| self.props_slot(index))
+            .filter(|props| !props.is_null())
+    }
 
-            if count == 0 {
+    /// Returns the number of entries in an object's property table
+    ///
+    /// Array elements held in element storage aren't counted.
+    pub fn own_property_count(&self, obj_val: JSValue) -> usize {
+        self.own_props_index(obj_val)
+            .and_then(|index| self.get_property_table(index))
+            // SAFETY: get_property_table checked the block's tag
+            .map_or(0, |props_table| unsafe { props_table.header().count() as usize })
+    }
+
+    /// Returns how many entries an object's property table holds before it
+    /// next grows, or 0 if it has none yet
+    pub fn own_property_capacity(&self, obj_val: JSValue) -> usize {
+        self.own_props_index(obj_val)
+            .and_then(|index| self.get_property_table(index))
+            // SAFETY: get_property_table checked the block's tag
+            .map_or(0, |props_table| unsafe { props_table.header().capacity() as usize })
+    }
+
+    /// Returns the names of an object's own properties, enumerable or not
+    ///
+    /// Index keys and array elements are left out.
+    pub(crate) fn own_property_names(&self, obj_val: JSValue) -> Vec<JSAtom> {
+        self.own_props_index(obj_val)
+            .and_then(|index| self.get_property_table(index))
+            // SAFETY: get_property_table checked the block's tag
+            .map_or_else(Vec::new, |props_table| unsafe {
+                props_table.properties().iter()
+                    .map(|prop| prop.key())
+                    .filter(|key| key.to_index().is_none())
+                    .collect()
+            })
+    }
+
+    /// Finds the index of a key in a property table
+    ///
+    /// Walks the key's hash chain when the table has a hash table, and scans
+    /// the entries otherwise.
+    fn property_slot(props_table: &crate::object::PropertyTable, key: JSAtom) -> Option<usize> {
+        // SAFETY: tables are only reachable through get_property_table, which
+        // checks the tag, and their hash chains only link live entries
+        unsafe {
+            let header = props_table.header();
+            if header.count() == 0 {
                 return None;
             }
 
             // For small tables, use linear search
             if !header.has_hash_table() {
-                let properties = props_table.properties();
-                for prop in properties {
-                    if prop.key() == key {
-                        return Some(prop);
-                    }
-                }
-                return None;
+                return props_table.properties().iter().position(|prop| prop.key() == key);
             }
 
             // For larger tables, use hash table
             let hash = key.id(); // Use atom ID as hash
-            let hash_mask = header.hash_mask();
-            let slot = (hash & hash_mask) as usize;
-
-            let hash_table_ptr = props_table.hash_table_ptr();
-            let mut prop_idx = *hash_table_ptr.add(slot);
+            let slot = (hash & header.hash_mask()) as usize;
+            let mut prop_idx = *props_table.hash_table_ptr().add(slot);
 
             // Walk the hash chain
             let properties_ptr = props_table.properties_ptr();
             while prop_idx != u32::MAX {
                 let prop = &*properties_ptr.add(prop_idx as usize);
                 if prop.key() == key {
-                    return Some(prop);
+                    return Some(prop_idx as usize);
                 }
                 prop_idx = prop.hash_next();
             }
@@ -604,6 +2241,59 @@ impl Context {
         }
     }
 
+    /// Returns an object's own enumerable property keys
+    ///
+    /// Integer keys (array elements and index-named properties) come first
+    /// in ascending order, followed by the other names in insertion order.
+    /// Non-enumerable properties, including the engine's internal slots, are
+    /// left out. The keys are a snapshot: hosts can mutate the object while
+    /// walking them, and [`Context::get_property`] returns `None` for any key
+    /// deleted in the meantime.
+    pub fn own_enumerable_keys(&self, obj_val: JSValue) -> Vec<JSAtom> {
+        let mut keys = Vec::new();
+
+        if let Some(bytes) = self.uint8array_data(obj_val) {
+            keys.extend((0..bytes.len() as u32).filter_map(JSAtom::from_index));
+        }
+
+        if let Some(len) = self.array_length(obj_val) {
+            // Dense elements, skipping holes; sparse ones live in the property table
+            let dense = self.array_data_index(obj_val)
+                // SAFETY: array_data_index checked the block is array data
+                .map(|index| unsafe { self.arena.get::<crate::object::JSArray>(index) })
+                .filter(|data| data.has_elements())
+                .and_then(|data| self.get_value_array(data.elements_index()))
+                .map_or(0, |elements| elements.header().count() as u32);
+            for index in 0..len.min(dense) {
+                if self.get_array_element(obj_val, index).is_some() {
+                    keys.extend(JSAtom::from_index(index));
+                }
+            }
+        }
+
+        let Some(props_index) = self.own_props_index(obj_val) else {
+            return keys;
+        };
+        if let Some(props_table) = self.get_property_table(props_index) {
+            // SAFETY: get_property_table checked the block's tag
+            let properties = unsafe { props_table.properties() };
+            let dense = keys.len();
+            let mut names = Vec::new();
+            for prop in properties.iter().filter(|prop| prop.flags().is_enumerable()) {
+                if self.array_index(prop.key()).is_some() {
+                    keys.push(prop.key());
+                } else {
+                    names.push(prop.key());
+                }
+            }
+            if keys.len() > dense {
+                keys.sort_unstable_by_key(|&key| self.array_index(key));
+            }
+            keys.extend(names);
+        }
+        keys
+    }
+
     /// Looks up a property in an object (including prototype chain)
     ///
     /// Returns the property value if found.
@@ -613,55 +2303,81 @@ impl Context {
         obj_val: JSValue,
         key: crate::value::JSAtom,
     ) -> Option<JSValue> {
-        // Handle string primitives
-        if let Some(s) = self.get_string(obj_val) {
-            // Check for "length" property
-            let length_atom = crate::runtime::init::string_to_atom("length");
-            if key.id() == length_atom.id() {
-                // Return string length (count UTF-16 code units like JS does)
-                let len = s.chars().count() as i32;
-                return Some(JSValue::from_int(len));
-            }
-
-            // Check for numeric index (string character access)
-            // This is handled elsewhere, so just look up String.prototype
-            let string_atom = crate::runtime::init::string_to_atom("String");
-            let prototype_atom = crate::runtime::init::string_to_atom("prototype");
-
-            if let Some(string_ctor) = self.get_global_property(string_atom) {
-                if let Some(string_proto) = self.get_property_internal(string_ctor, prototype_atom) {
-                    return self.get_property_internal(string_proto, key);
-                }
-            }
-            return None;
+        if let Some(len) = self.string_length(obj_val, key) {
+            return Some(len);
         }
 
-        // Handle number primitives (inline int or boxed float)
-        if obj_val.is_int() || self.get_number(obj_val).is_some() {
-            let number_atom = crate::runtime::init::string_to_atom("Number");
-            let prototype_atom = crate::runtime::init::string_to_atom("prototype");
+        // Primitives have no own properties; look them up on the matching
+        // prototype
+        if let Some(proto) = self.primitive_prototype(obj_val) {
+            return self.get_property_internal(proto, key);
+        }
 
-            if let Some(number_ctor) = self.get_global_property(number_atom) {
-                if let Some(number_proto) = self.get_property_internal(number_ctor, prototype_atom) {
-                    return self.get_property_internal(number_proto, key);
-                }
+        self.get_property_internal(obj_val, key)
+    }
+
+    /// Returns true if an object or its prototype chain has a property
+    ///
+    /// This is the `in` operator once the key is an atom. Array indices at
+    /// or past `length` are never present.
+    pub fn has_property(&self, obj_val: JSValue, key: JSAtom) -> bool {
+        if let (Some(index), Some(bytes)) = (key.to_index(), self.uint8array_data(obj_val)) {
+            return (index as usize) < bytes.len();
+        }
+        if let (Some(index), Some(len)) = (self.array_index(key), self.array_length(obj_val)) {
+            if index >= len {
+                return false;
+            }
+            if self.get_array_element(obj_val, index).is_some() {
+                return true;
             }
-            return None;
         }
+        self.get_property(obj_val, key).is_some()
+    }
 
-        // Handle functions - they inherit from Function.prototype
-        if self.get_native_function(obj_val).is_some() || self.get_bytecode_function(obj_val).is_some() {
-            // Look up in Function.prototype
-            let function_proto = self.function_prototype;
-            if !function_proto.is_null() {
-                if let Some(prop) = self.get_property_internal(function_proto, key) {
-                    return Some(prop);
-                }
-            }
+    /// Returns a string's `length` when `key` is "length"
+    ///
+    /// Strings are indexed by character, so this is the character count.
+    fn string_length(&self, val: JSValue, key: crate::value::JSAtom) -> Option<JSValue> {
+        if key.id() != crate::value::atoms::LENGTH.id() {
             return None;
         }
+        let s = self.get_string(val)?;
+        Some(JSValue::from_int(crate::util::count_utf8_chars(s.as_bytes()) as i32))
+    }
 
-        self.get_property_internal(obj_val, key)
+    /// Returns the prototype that property lookups on a primitive start from
+    ///
+    /// Strings use String.prototype and numbers Number.prototype. Returns
+    /// None for objects and functions, for values without a wrapper
+    /// (undefined, null, booleans), or if the prototype is missing.
+    fn primitive_prototype(&self, val: JSValue) -> Option<JSValue> {
+        use crate::value::atoms;
+
+        let ctor = if self.get_string(val).is_some() {
+            atoms::STRING
+        } else if val.is_int() || self.get_number(val).is_some() {
+            atoms::NUMBER
+        } else {
+            return None;
+        };
+
+        let ctor = self.get_global_property(ctor)?;
+        self.get_property_internal(ctor, atoms::PROTOTYPE)
+    }
+
+    /// Returns the next link of a value's prototype chain
+    ///
+    /// That is an object's prototype, or Function.prototype for functions,
+    /// which keep their own properties but no prototype link. None ends the
+    /// chain.
+    fn prototype_link(&self, val: JSValue) -> Option<JSValue> {
+        let proto = match self.get_object(val) {
+            Some(obj) => obj.prototype(),
+            None if self.is_callable(val) => self.function_prototype,
+            None => return None,
+        };
+        (!proto.is_null()).then_some(proto)
     }
 
     /// Internal property lookup on objects only (no primitive handling)
@@ -671,23 +2387,19 @@ impl Context {
         key: crate::value::JSAtom,
     ) -> Option<JSValue> {
         let mut current = obj_val;
-        let max_depth = 100; // Prevent infinite loops in broken prototype chains
 
-        for _ in 0..max_depth {
+        for depth in 0..=self.max_prototype_depth {
             // Look in own properties
+            if let Some(value) = self.get_array_own(current, key) {
+                return Some(value);
+            }
             if let Some(prop) = self.find_own_property(current, key) {
                 return Some(prop.value());
             }
 
-            // Walk up prototype chain
-            let obj = self.get_object(current)?;
-            let proto = obj.prototype();
-
-            if proto.is_null() {
-                // Reached end of prototype chain
-                return None;
-            }
-
+            // Walk up prototype chain, which ends at a null prototype
+            let proto = self.prototype_link(current)?;
+            self.follow_prototype_link(depth + 1);
             current = proto;
         }
 
@@ -704,11 +2416,16 @@ impl Context {
         obj_val: JSValue,
         key: crate::value::JSAtom,
     ) -> PropertyLookupResult {
-        let mut current = obj_val;
-        let max_depth = 100;
+        if let Some(len) = self.string_length(obj_val, key) {
+            return PropertyLookupResult::Value(len);
+        }
+        let mut current = self.primitive_prototype(obj_val).unwrap_or(obj_val);
 
-        for _ in 0..max_depth {
+        for depth in 0..=self.max_prototype_depth {
             // Look in own properties
+            if let Some(value) = self.get_array_own(current, key) {
+                return PropertyLookupResult::Value(value);
+            }
             if let Some(prop) = self.find_own_property(current, key) {
                 let flags = prop.flags();
                 if flags.has_get() || flags.has_set() {
@@ -727,15 +2444,11 @@ impl Context {
             }
 
             // Walk up prototype chain
-            if let Some(obj) = self.get_object(current) {
-                let proto = obj.prototype();
-                if proto.is_null() {
-                    return PropertyLookupResult::NotFound;
-                }
-                current = proto;
-            } else {
+            let Some(proto) = self.prototype_link(current) else {
                 return PropertyLookupResult::NotFound;
-            }
+            };
+            self.follow_prototype_link(depth + 1);
+            current = proto;
         }
 
         PropertyLookupResult::NotFound
@@ -744,7 +2457,12 @@ impl Context {
     /// Adds a property to an object
     ///
     /// This adds to own properties only (doesn't affect prototype chain).
-    /// If the object doesn't have a property table yet, one will be created.
+    /// If the object doesn't have a property table yet, one will be created,
+    /// and a full one is grown. An own property with the same key is
+    /// replaced, flags included.
+    ///
+    /// On Array objects, integer keys and `length` update the array's element
+    /// storage rather than the property table.
     pub fn add_property(
         &mut self,
         obj_val: JSValue,
@@ -752,23 +2470,111 @@ impl Context {
         value: JSValue,
         flags: crate::object::PropertyFlags,
     ) -> Result<(), crate::memory::allocator::OutOfMemory> {
-        use crate::object::Property;
+        if self.uint8array_index(obj_val).is_some() {
+            // A typed array's length is fixed and its elements are bytes
+            if key == crate::value::atoms::LENGTH {
+                return Ok(());
+            }
+            if let Some(index) = key.to_index() {
+                self.set_uint8array_element(obj_val, index, value);
+                return Ok(());
+            }
+        }
+        if self.is_array(obj_val) {
+            if key == crate::value::atoms::LENGTH {
+                // Invalid lengths are ignored; the VM throws the RangeError
+                if let Some(length) = self.get_number(value) {
+                    if length >= 0.0 && length <= u32::MAX as f64 && length == libm::floor(length) {
+                        self.set_array_length(obj_val, length as u32)?;
+                    }
+                }
+                return Ok(());
+            }
+            if let Some(index) = self.array_index(key) {
+                self.set_array_element(obj_val, index, value)?;
+                return Ok(());
+            }
+        }
 
-        // Get or create property table
-        let obj_index = obj_val.to_ptr().ok_or(crate::memory::allocator::OutOfMemory)?;
+        self.add_own_property(obj_val, key, value, flags)
+    }
 
-        let props_index = {
-            let obj: &crate::object::JSObject = unsafe { self.arena.get(obj_index) };
-            if !obj.has_properties() {
-                // Create initial property table with enough capacity for global object + user vars
-                let props_idx = self.alloc_property_table(64)?; // 64 slots for global + user properties
-                let obj_mut: &mut crate::object::JSObject = unsafe { self.arena.get_mut(obj_index) };
-                obj_mut.set_props_index(props_idx);
-                props_idx
-            } else {
-                obj.props_index()
+    /// Sets a property on an object
+    ///
+    /// Updates an existing own property in place and only appends a new entry
+    /// when the object doesn't have the property yet. Writes to read-only and
+    /// accessor properties are ignored. On Array objects, integer keys and
+    /// `length` go to element storage as with [`Context::add_property`].
+    pub fn set_property(
+        &mut self,
+        obj_val: JSValue,
+        key: crate::value::JSAtom,
+        value: JSValue,
+    ) -> Result<(), crate::memory::allocator::OutOfMemory> {
+        let flags = crate::object::PropertyFlags::default();
+        let has_elements = self.is_array(obj_val) || self.uint8array_index(obj_val).is_some();
+        if has_elements && (key == crate::value::atoms::LENGTH || self.array_index(key).is_some()) {
+            return self.add_property(obj_val, key, value, flags);
+        }
+        if key == crate::value::atoms::NAME {
+            // A function's read-only `name` is only made on demand; make it
+            // now so the write can't add a writable one in its place
+            self.ensure_function_name(obj_val)?;
+        }
+
+        let props_index = self.own_props_index(obj_val);
+        if let Some(props_table) = props_index.and_then(|index| self.get_property_table_mut(index)) {
+            if let Some(slot) = Self::property_slot(props_table, key) {
+                // SAFETY: the table came from a live object, and `slot` from its own lookup
+                let prop = unsafe { &mut props_table.properties_mut()[slot] };
+                if prop.is_data() && prop.flags().is_writable() {
+                    prop.set_value(value);
+                }
+                return Ok(());
             }
+        }
+
+        self.add_own_property(obj_val, key, value, flags)
+    }
+
+    /// Adds a property to an object's property table, replacing an entry
+    /// with the same key
+    fn add_own_property(
+        &mut self,
+        obj_val: JSValue,
+        key: crate::value::JSAtom,
+        value: JSValue,
+        flags: crate::object::PropertyFlags,
+    ) -> Result<(), crate::memory::allocator::OutOfMemory> {
+        use crate::object::Property;
+
+        // Primitives have no property table to write to, and frozen objects
+        // take no new properties; the write is dropped, as it is for
+        // primitives in sloppy mode
+        let extensible = match self.get_object(obj_val) {
+            Some(obj) => obj.is_extensible(),
+            None => self.is_callable(obj_val),
         };
+        if !extensible {
+            return Ok(());
+        }
+
+        // Replace an existing entry in place, keeping its hash link
+        let props_index = self.own_props_index(obj_val);
+        if let Some(props_table) = props_index.and_then(|index| self.get_property_table_mut(index)) {
+            if let Some(slot) = Self::property_slot(props_table, key) {
+                // SAFETY: the table came from a live object, and `slot` from its own lookup
+                let prop = unsafe { &mut props_table.properties_mut()[slot] };
+                let hash_next = prop.hash_next();
+                *prop = Property::new_data(key, value, flags);
+                prop.set_hash_next(hash_next);
+                return Ok(());
+            }
+        }
+
+        // Get or create property table, with room for one more entry
+        let obj_index = obj_val.to_ptr().ok_or(crate::memory::allocator::OutOfMemory)?;
+        let props_index = self.property_table_with_room(obj_index)?;
 
         // Add the property
         let props_table = self.get_property_table_mut(props_index)
@@ -777,12 +2583,6 @@ impl Context {
         unsafe {
             let header = props_table.header_mut();
             let count = header.count();
-            let capacity = header.capacity();
-
-            // Check if we need to resize (not implemented yet - just fail if full)
-            if count >= capacity {
-                return Err(crate::memory::allocator::OutOfMemory);
-            }
 
             let new_prop = Property::new_data(key, value, flags);
             let prop_idx = count;
@@ -816,7 +2616,176 @@ impl Context {
             header.set_count(count + 1);
         }
 
-        Ok(())
+        Ok(())
+    }
+
+    /// Returns an object's or function's property table, creating it or
+    /// growing it first if it has no room for another entry
+    fn property_table_with_room(&mut self, obj_index: HeapIndex) -> Result<HeapIndex, crate::memory::allocator::OutOfMemory> {
+        use crate::object::PropertyTableHeader;
+
+        let props_index = self.props_slot(obj_index).ok_or(crate::memory::allocator::OutOfMemory)?;
+        if props_index.is_null() {
+            let props_idx = self.alloc_property_table(PropertyTableHeader::INITIAL_CAPACITY)?;
+            // Allocating doesn't collect, so the object is still live
+            self.set_props_slot(obj_index, props_idx);
+            return Ok(props_idx);
+        }
+
+        let (count, capacity) = self.get_property_table(props_index)
+            // SAFETY: get_property_table checked the block's tag
+            .map_or((0, 0), |table| unsafe { (table.header().count(), table.header().capacity()) });
+        if count < capacity {
+            return Ok(props_index);
+        }
+        self.grow_property_table(obj_index, count + 1)
+    }
+
+    /// Moves an object's properties to a new table with room for at least
+    /// `min_capacity` entries
+    ///
+    /// Grows geometrically, and the hash table is sized to the capacity, so
+    /// chains stay short. The entries keep their order and are relinked for
+    /// the new hash table; the old table is left to the collector.
+    fn grow_property_table(
+        &mut self,
+        obj_index: HeapIndex,
+        min_capacity: u32,
+    ) -> Result<HeapIndex, crate::memory::allocator::OutOfMemory> {
+        use crate::object::PropertyTableHeader;
+
+        let old_index = self.props_slot(obj_index).unwrap_or(HeapIndex::null());
+        let (old, capacity) = self.get_property_table(old_index)
+            // SAFETY: get_property_table checked the block's tag
+            .map_or((Vec::new(), 0), |table| unsafe { (table.properties().to_vec(), table.header().capacity()) });
+
+        let new_capacity = capacity.saturating_mul(2).max(min_capacity).max(PropertyTableHeader::INITIAL_CAPACITY);
+        let new_index = self.alloc_property_table(new_capacity)?;
+        if let Some(table) = self.get_property_table_mut(new_index) {
+            // SAFETY: the new table has room for at least `old.len()` entries
+            unsafe {
+                core::ptr::copy_nonoverlapping(old.as_ptr(), table.properties_ptr_mut(), old.len());
+                table.header_mut().set_count(old.len() as u32);
+                Self::rebuild_hash_chains(table);
+            }
+        }
+        // Allocating doesn't collect, so the object is still live
+        self.set_props_slot(obj_index, new_index);
+        Ok(new_index)
+    }
+
+    /// Removes an own property from an object
+    ///
+    /// Returns false if the property exists but isn't configurable, and true
+    /// otherwise, including when there was nothing to remove. The remaining
+    /// entries keep their order, so enumeration still follows insertion
+    /// order. Deleting a dense array element leaves a hole, and an array's
+    /// `length` can't be deleted. Neither can a typed array's elements.
+    pub fn delete_property(&mut self, obj_val: JSValue, key: JSAtom) -> bool {
+        if let Some(bytes) = self.uint8array_data(obj_val) {
+            if key == crate::value::atoms::LENGTH {
+                return false;
+            }
+            if let Some(index) = key.to_index() {
+                return index as usize >= bytes.len();
+            }
+        }
+        if let Some(len) = self.array_length(obj_val) {
+            if key == crate::value::atoms::LENGTH {
+                return false;
+            }
+            if let Some(index) = self.array_index(key).filter(|&index| index < len) {
+                if self.get_array_element(obj_val, index).is_some() {
+                    // In the dense store already, so this never allocates
+                    let _ = self.set_array_element(obj_val, index, JSValue::hole());
+                    return true;
+                }
+            }
+        }
+
+        let Some(props_index) = self.own_props_index(obj_val) else {
+            return true;
+        };
+        let Some(props_table) = self.get_property_table_mut(props_index) else {
+            return true;
+        };
+        let Some(slot) = Self::property_slot(props_table, key) else {
+            return true;
+        };
+
+        unsafe {
+            let properties = props_table.properties_mut();
+            if !properties[slot].flags().is_configurable() {
+                return false;
+            }
+            properties.copy_within(slot + 1.., slot);
+            let count = properties.len() as u32 - 1;
+            props_table.header_mut().set_count(count);
+            Self::rebuild_hash_chains(props_table);
+        }
+        true
+    }
+
+    /// Removes every own property whose key matches `discard`, configurable
+    /// or not
+    ///
+    /// For array elements that move out of the property table; the other
+    /// entries keep their order.
+    fn discard_own_properties(&mut self, obj_val: JSValue, discard: impl Fn(JSAtom) -> bool) {
+        let Some(props_index) = self.own_props_index(obj_val) else {
+            return;
+        };
+        let Some(props_table) = self.get_property_table_mut(props_index) else {
+            return;
+        };
+
+        // SAFETY: get_property_table_mut checked the block's tag, and the
+        // kept entries are compacted within the table's own storage
+        unsafe {
+            let properties = props_table.properties_mut();
+            let mut kept = 0;
+            for slot in 0..properties.len() {
+                if !discard(properties[slot].key()) {
+                    properties.swap(kept, slot);
+                    kept += 1;
+                }
+            }
+            if kept == properties.len() {
+                return;
+            }
+            props_table.header_mut().set_count(kept as u32);
+            Self::rebuild_hash_chains(props_table);
+        }
+    }
+
+    /// Relinks every entry of a property table into its key's hash chain
+    ///
+    /// Removing an entry moves the ones after it, so the chains are rebuilt
+    /// from the entries rather than patched.
+    ///
+    /// # Safety
+    ///
+    /// `props_table` must be a valid property table.
+    unsafe fn rebuild_hash_chains(props_table: &mut crate::object::PropertyTable) {
+        let header = props_table.header();
+        if !header.has_hash_table() {
+            return;
+        }
+        let hash_mask = header.hash_mask();
+        let table_size = header.hash_table_size() as usize;
+        let count = header.count();
+
+        let hash_table_ptr = props_table.hash_table_ptr_mut();
+        for slot in 0..table_size {
+            *hash_table_ptr.add(slot) = u32::MAX;
+        }
+        let properties_ptr = props_table.properties_ptr_mut();
+        for prop_idx in 0..count {
+            let prop = &mut *properties_ptr.add(prop_idx as usize);
+            let slot = (prop.key().id() & hash_mask) as usize;
+            prop.set_hash_next(*hash_table_ptr.add(slot));
+            *hash_table_ptr.add(slot) = prop_idx;
+        }
     }
 
     /// Defines a getter on an object property
@@ -831,19 +2800,12 @@ impl Context {
     ) -> Result<(), crate::memory::allocator::OutOfMemory> {
         use crate::object::{Property, PropertyFlags};
 
-        // Get or create property table
+        // Get or create property table, with room for the accessor unless
+        // it replaces an existing property
         let obj_index = obj_val.to_ptr().ok_or(crate::memory::allocator::OutOfMemory)?;
-
-        let props_index = {
-            let obj: &crate::object::JSObject = unsafe { self.arena.get(obj_index) };
-            if !obj.has_properties() {
-                let props_idx = self.alloc_property_table(64)?;
-                let obj_mut: &mut crate::object::JSObject = unsafe { self.arena.get_mut(obj_index) };
-                obj_mut.set_props_index(props_idx);
-                props_idx
-            } else {
-                obj.props_index()
-            }
+        let props_index = match self.own_props_index(obj_val) {
+            Some(props_index) if self.find_own_property(obj_val, key).is_some() => props_index,
+            _ => self.property_table_with_room(obj_index)?,
         };
 
         // Check if property already exists and update it
@@ -855,7 +2817,6 @@ impl Context {
             let count = header.count() as usize;
             let has_hash_table = header.has_hash_table();
             let hash_mask = if has_hash_table { header.hash_mask() } else { 0 };
-            let capacity = header.capacity();
 
             // Get both pointers upfront to avoid borrow issues
             let properties_ptr = props_table.properties_ptr_mut();
@@ -878,10 +2839,6 @@ impl Context {
             }
 
             // Property doesn't exist, create new accessor
-            if count as u32 >= capacity {
-                return Err(crate::memory::allocator::OutOfMemory);
-            }
-
             let flags = PropertyFlags::getset(true, false);
             let new_prop = Property::new_accessor(key, getter, JSValue::undefined(), flags);
             *properties_ptr.add(count) = new_prop;
@@ -915,19 +2872,12 @@ impl Context {
     ) -> Result<(), crate::memory::allocator::OutOfMemory> {
         use crate::object::{Property, PropertyFlags};
 
-        // Get or create property table
+        // Get or create property table, with room for the accessor unless
+        // it replaces an existing property
         let obj_index = obj_val.to_ptr().ok_or(crate::memory::allocator::OutOfMemory)?;
-
-        let props_index = {
-            let obj: &crate::object::JSObject = unsafe { self.arena.get(obj_index) };
-            if !obj.has_properties() {
-                let props_idx = self.alloc_property_table(64)?;
-                let obj_mut: &mut crate::object::JSObject = unsafe { self.arena.get_mut(obj_index) };
-                obj_mut.set_props_index(props_idx);
-                props_idx
-            } else {
-                obj.props_index()
-            }
+        let props_index = match self.own_props_index(obj_val) {
+            Some(props_index) if self.find_own_property(obj_val, key).is_some() => props_index,
+            _ => self.property_table_with_room(obj_index)?,
         };
 
         // Check if property already exists and update it
@@ -939,7 +2889,6 @@ impl Context {
             let count = header.count() as usize;
             let has_hash_table = header.has_hash_table();
             let hash_mask = if has_hash_table { header.hash_mask() } else { 0 };
-            let capacity = header.capacity();
 
             // Get both pointers upfront to avoid borrow issues
             let properties_ptr = props_table.properties_ptr_mut();
@@ -962,10 +2911,6 @@ impl Context {
             }
 
             // Property doesn't exist, create new accessor
-            if count as u32 >= capacity {
-                return Err(crate::memory::allocator::OutOfMemory);
-            }
-
             let flags = PropertyFlags::getset(false, true);
             let new_prop = Property::new_accessor(key, JSValue::undefined(), setter, flags);
             *properties_ptr.add(count) = new_prop;
@@ -1008,8 +2953,6 @@ impl Context {
     /// Sets a property on the global object
     ///
     /// Creates the property if it doesn't exist, or updates it if it does.
-    /// Note: This is a simplified implementation that always adds properties.
-    /// Multiple properties with the same key may exist, but get_property will return the latest one.
     pub fn set_global_property(
         &mut self,
         key: crate::value::JSAtom,
@@ -1019,10 +2962,172 @@ impl Context {
             return Err(crate::memory::allocator::OutOfMemory);
         }
 
-        // Simply add the property
-        // In a full implementation, we would check if it exists and update in place
-        // For now, get_property will return the most recent property with this key
-        self.add_property(self.global_object, key, value, crate::object::PropertyFlags::default())
+        self.set_property(self.global_object, key, value)
+    }
+
+    // ========== Errors ==========
+
+    /// Creates an error object of the given kind
+    ///
+    /// The object has `name` and `message` properties and carries `kind` in a
+    /// hidden slot readable through [`Context::error_kind`]. If the heap is too
+    /// exhausted to build an object, returns the preallocated `OutOfMemory`
+    /// error instead, whatever `kind` was asked for.
+    pub fn new_error(&mut self, kind: crate::builtins::error::ErrorKind, message: &str) -> JSValue {
+        match crate::builtins::error::create_error_with_kind(self, kind, message) {
+            Ok(err) => err,
+            Err(_) if !self.out_of_memory_error.is_undefined() => self.out_of_memory_error,
+            Err(_) => self.new_string(message).unwrap_or(JSValue::undefined()),
+        }
+    }
+
+    /// Builds the error thrown once the heap is exhausted
+    ///
+    /// Afterwards, running out of memory throws this shared OutOfMemory error
+    /// even when there's no room left to create one.
+    pub fn preallocate_out_of_memory_error(&mut self) -> Result<(), JSValue> {
+        let kind = crate::builtins::error::ErrorKind::OutOfMemory;
+        let err = crate::builtins::error::create_error_with_kind(self, kind, "Out of memory")?;
+        self.add_root(err);
+        self.out_of_memory_error = err;
+        Ok(())
+    }
+
+    /// Returns the category of an error value
+    ///
+    /// Returns `Some` for errors thrown by the engine and for objects created by
+    /// the builtin error constructors (except plain `Error`), including after
+    /// they have been caught and rethrown by script. Returns `None` for any
+    /// other value.
+    pub fn error_kind(&self, value: JSValue) -> Option<crate::builtins::error::ErrorKind> {
+        let code = self.get_object(value)?.error_kind_code()?;
+        crate::builtins::error::ErrorKind::from_code(code)
+    }
+
+    /// Registers a global error class that scripts can throw, catch and test
+    /// with `instanceof`
+    ///
+    /// `name` becomes a global constructor whose errors inherit from its
+    /// `prototype`, which inherits from `parent`'s, so they are also
+    /// `instanceof` `parent` and `Error`. Each error has `name` set to
+    /// `name`, carries `parent`'s [`ErrorKind`](crate::ErrorKind) and gets
+    /// `stack` and `lineNumber` like builtin errors. Native functions throw
+    /// one with [`throw_custom_error`](Self::throw_custom_error).
+    ///
+    /// Returns the constructor. Registering takes two objects' worth of
+    /// heap; call it after the runtime is initialized.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// ctx.register_error_class("DeviceBusyError", ErrorType::Error)?;
+    ///
+    /// // Later, in a native function:
+    /// let class = ctx.intern_atom("DeviceBusyError");
+    /// let class = ctx.get_global_property(class).unwrap_or(JSValue::undefined());
+    /// return Err(ctx.throw_custom_error(class, "device is busy"));
+    /// ```
+    pub fn register_error_class(
+        &mut self,
+        name: &str,
+        parent: crate::builtins::error::ErrorType,
+    ) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
+        use crate::value::atoms;
+
+        let global = self.global_object;
+        let parent_proto = self.get_error_prototype(parent);
+        if global.is_null() || parent_proto.is_null() {
+            return Err(crate::memory::allocator::OutOfMemory);
+        }
+
+        let mut hidden = crate::object::PropertyFlags::default();
+        hidden.set_enumerable(false);
+        let proto = self.new_object_with_proto(parent_proto)?;
+        let name_val = self.new_string(name)?;
+        self.add_property(proto, atoms::NAME, name_val, hidden)?;
+
+        // A function bound to the shared constructor, with the prototype as
+        // `this` so the constructor knows which class it is building
+        let target = self.new_native_function(crate::builtins::native_functions::error_class_constructor, 1)?;
+        let ctor = self.new_object()?;
+        let internal = crate::object::PropertyFlags::empty();
+        self.add_property(ctor, atoms::BOUND_TARGET, target, internal)?;
+        self.add_property(ctor, atoms::BOUND_THIS, proto, internal)?;
+        self.add_property(ctor, atoms::IS_BOUND_FUNCTION, JSValue::bool(true), internal)?;
+        self.add_property(ctor, atoms::PROTOTYPE, proto, hidden)?;
+        self.add_property(proto, atoms::CONSTRUCTOR, ctor, hidden)?;
+
+        let atom = self.intern_atom(name);
+        self.add_property(global, atom, ctor, crate::object::PropertyFlags::default())?;
+        Ok(ctor)
+    }
+
+    /// Builds an error of a class from
+    /// [`register_error_class`](Self::register_error_class), for a native
+    /// function to return as `Err`
+    ///
+    /// Like [`new_error`](Self::new_error), falls back to the shared
+    /// `OutOfMemory` error if the heap is too exhausted to build the error. If `class`
+    /// can't be called, returns the `TypeError` calling it raised.
+    pub fn throw_custom_error(&mut self, class: JSValue, message: &str) -> JSValue {
+        let Ok(message_val) = self.new_string(message) else {
+            return self.new_error(crate::builtins::error::ErrorKind::OutOfMemory, "Out of memory");
+        };
+        match self.call_function(class, JSValue::undefined(), &[message_val]) {
+            Ok(err) | Err(err) => err,
+        }
+    }
+
+    /// Returns the object `instanceof` looks for on the prototype chain of
+    /// values tested against `ctor`
+    ///
+    /// That is `ctor.prototype` if it has one, the registered prototype for
+    /// a builtin error constructor, or the target's for a bound function.
+    /// Script constructors only have a `prototype` once something asked for
+    /// it, so call [`Context::ensure_function_prototype`] first. `None`
+    /// means `ctor` can't be used with `instanceof`.
+    pub fn instanceof_prototype(&self, ctor: JSValue) -> Option<JSValue> {
+        use crate::value::atoms;
+
+        let mut current = ctor;
+        for _ in 0..=self.max_prototype_depth {
+            if let Some(prop) = self.find_own_property(current, atoms::PROTOTYPE) {
+                let proto = prop.value();
+                return self.get_object(proto).is_some().then_some(proto);
+            }
+            if let Some(index) = self.error_constructors.iter().position(|&builtin| builtin == current && !builtin.is_null()) {
+                return Some(self.error_prototypes[index]).filter(|proto| !proto.is_null());
+            }
+            if !self.is_bound_function(current) {
+                return None;
+            }
+            current = self.find_own_property(current, atoms::BOUND_TARGET)?.value();
+        }
+        None
+    }
+
+    /// Returns whether `proto` is on the prototype chain of `value`
+    ///
+    /// Primitives have no chain, so they are never instances.
+    pub fn has_in_prototype_chain(&self, value: JSValue, proto: JSValue) -> bool {
+        let mut current = value;
+        for depth in 0..=self.max_prototype_depth {
+            let Some(link) = self.prototype_link(current) else { return false };
+            current = link;
+            self.follow_prototype_link(depth + 1);
+            if current == proto {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns the status passed to `exit()` if `value` is an exit request
+    pub fn exit_code(&self, value: JSValue) -> Option<i32> {
+        if self.error_kind(value)? != crate::builtins::error::ErrorKind::ExitRequested {
+            return None;
+        }
+        self.find_own_property(value, crate::value::atoms::EXIT_CODE)?.value().to_int()
     }
 
     // ========== VM Execution ==========
@@ -1038,16 +3143,32 @@ impl Context {
     /// # Returns
     ///
     /// * `Ok(JSValue)` - The result of execution
-    /// * `Err(JSValue)` - An exception value
+    /// * `Err(JSValue)` - An exception value; an `Internal` error if a
+    ///   script is already running
     pub fn execute_bytecode(&mut self, bytecode_index: HeapIndex) -> Result<JSValue, JSValue> {
         use crate::vm::VM;
 
-        let mut vm = VM::new();
+        let mut vm = VM::with_stack_sizes(self.value_stack_slots, self.call_depth);
         vm.execute(self, bytecode_index)
     }
 
+    /// Executes bytecode wherever it lives
+    ///
+    /// Like `execute_bytecode`, but `FunctionCode::Static` code runs in place
+    /// without being copied into the heap.
+    pub fn execute_code(&mut self, code: crate::object::FunctionCode) -> Result<JSValue, JSValue> {
+        use crate::vm::VM;
+
+        let mut vm = VM::with_stack_sizes(self.value_stack_slots, self.call_depth);
+        vm.execute_code(self, code)
+    }
+
     /// Calls a JavaScript function
     ///
+    /// Works from native functions mid-script and from the host between
+    /// runs, so a function an earlier `eval` defined can be called later,
+    /// with whatever variables it captured.
+    ///
     /// # Arguments
     ///
     /// * `func` - The function to call
@@ -1065,7 +3186,7 @@ impl Context {
         args: &[JSValue],
     ) -> Result<JSValue, JSValue> {
         // Check if this is a bound function object
-        let is_bound_atom = crate::runtime::init::string_to_atom("__isBoundFunction__");
+        let is_bound_atom = crate::value::atoms::IS_BOUND_FUNCTION;
         if let Some(is_bound) = self.get_property(func, is_bound_atom) {
             if let Some(true) = is_bound.to_bool() {
                 return self.call_bound_function(func, args);
@@ -1075,7 +3196,7 @@ impl Context {
         // Check if it's a native function
         let func_index = match func.to_ptr() {
             Some(idx) => idx,
-            None => return Err(self.new_string("Not a function").unwrap_or(JSValue::undefined())),
+            None => return Err(self.new_error(crate::builtins::error::ErrorKind::Type, "Not a function")),
         };
 
         unsafe {
@@ -1084,7 +3205,20 @@ impl Context {
                 // It's a native function - call it directly
                 let cfunc: &crate::object::function::JSCFunction = self.arena.get(func_index);
                 let func_ptr = cfunc.func_ptr();
-                return func_ptr(self, this_val, args);
+                #[cfg(feature = "alloc-origin")]
+                let outer_origin = self.set_alloc_origin(self.native_origin(func_ptr));
+                self.native_depth += 1;
+                let result = func_ptr(self, this_val, args);
+                self.native_depth -= 1;
+                #[cfg(feature = "alloc-origin")]
+                self.set_alloc_origin(outer_origin);
+                // Natives report a failed allocation as the exception
+                // sentinel; script and host see an OutOfMemory error instead
+                return result.map_err(|err| if err.is_exception() {
+                    self.new_error(crate::builtins::error::ErrorKind::OutOfMemory, "Out of memory")
+                } else {
+                    err
+                });
             }
         }
 
@@ -1094,13 +3228,14 @@ impl Context {
             if let (Some(vm_ptr), Some(call_fn)) = (self.vm_ptr, self.reentrant_call) {
                 return unsafe { call_fn(vm_ptr, self, func, this_val, args) };
             }
-            // No VM available - can't call closures outside of execution
-            return Err(self.new_string("Cannot call closure outside of VM execution")
-                .unwrap_or(JSValue::undefined()));
+            // Called from the host between runs: closures carry their code
+            // and captured variables, so a fresh VM can run them
+            let mut vm = crate::vm::VM::with_stack_sizes(self.value_stack_slots, self.call_depth);
+            return vm.call_function(self, func, this_val, args);
         }
 
         // Unknown function type
-        Err(self.new_string("Not a callable function").unwrap_or(JSValue::undefined()))
+        Err(self.new_error(crate::builtins::error::ErrorKind::Type, "Not a callable function"))
     }
 
     /// Call a bound function object
@@ -1110,20 +3245,20 @@ impl Context {
         call_args: &[JSValue],
     ) -> Result<JSValue, JSValue> {
         // Get the target function
-        let target_atom = crate::runtime::init::string_to_atom("__boundTarget__");
+        let target_atom = crate::value::atoms::BOUND_TARGET;
         let target = self.get_property(bound_func, target_atom)
             .ok_or_else(|| self.new_string("Invalid bound function").unwrap_or(JSValue::undefined()))?;
 
         // Get the bound this value
-        let this_atom = crate::runtime::init::string_to_atom("__boundThis__");
+        let this_atom = crate::value::atoms::BOUND_THIS;
         let bound_this = self.get_property(bound_func, this_atom)
             .unwrap_or(JSValue::undefined());
 
         // Get any bound arguments and combine with call arguments
-        let args_atom = crate::runtime::init::string_to_atom("__boundArgs__");
+        let args_atom = crate::value::atoms::BOUND_ARGS;
         let combined_args: alloc::vec::Vec<JSValue> = if let Some(bound_args) = self.get_property(bound_func, args_atom) {
             // Get bound args length
-            let length_atom = crate::runtime::init::string_to_atom("length");
+            let length_atom = crate::value::atoms::LENGTH;
             let bound_len = self.get_property(bound_args, length_atom)
                 .and_then(|v| v.to_int())
                 .unwrap_or(0) as usize;
@@ -1131,7 +3266,7 @@ impl Context {
             // Collect bound args + call args
             let mut all_args = alloc::vec::Vec::with_capacity(bound_len + call_args.len());
             for i in 0..bound_len {
-                let idx_atom = crate::runtime::init::string_to_atom(&alloc::format!("{}", i));
+                let idx_atom = self.intern_atom(&alloc::format!("{}", i));
                 let val = self.get_property(bound_args, idx_atom).unwrap_or(JSValue::undefined());
                 all_args.push(val);
             }
@@ -1178,6 +3313,57 @@ impl Context {
         Ok(JSValue::from_ptr(index))
     }
 
+    /// Registers a native Rust function as a global JavaScript function
+    ///
+    /// Scripts can then call `name(...)` and the call dispatches into `func`
+    /// with the arguments exactly as passed: missing arguments are absent from
+    /// the slice and extra arguments are not truncated. Returning `Err(value)`
+    /// throws `value` into the script, where it can be caught with try/catch.
+    /// `Err(JSValue::exception())`, for an allocation that failed, throws an
+    /// `OutOfMemory` error instead.
+    ///
+    /// Registering the same name again replaces the previous function.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// fn pin_count(_ctx: &mut Context, _this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+    ///     Ok(JSValue::from_int(40))
+    /// }
+    ///
+    /// ctx.register_function("pinCount", pin_count)?;
+    /// ```
+    pub fn register_function(
+        &mut self,
+        name: &str,
+        func: crate::object::function::NativeFn,
+    ) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
+        let global = self.global_object;
+        if global.is_null() {
+            return Err(crate::memory::allocator::OutOfMemory);
+        }
+        self.define_function(global, name, func)
+    }
+
+    /// Installs a native Rust function as a method on `obj`
+    ///
+    /// Use this to group host functions under a namespace object, e.g. a
+    /// `gpio` global whose `write` property calls into Rust.
+    ///
+    /// Returns the function value that was installed.
+    pub fn define_function(
+        &mut self,
+        obj: JSValue,
+        name: &str,
+        func: crate::object::function::NativeFn,
+    ) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
+        let func_val = self.new_native_function(func, 0)?;
+        let atom = self.intern_atom(name);
+        self.set_function_name(func_val, atom);
+        self.add_property(obj, atom, func_val, crate::object::PropertyFlags::default())?;
+        Ok(func_val)
+    }
+
     /// Gets a reference to a native function
     pub fn get_native_function(&self, val: JSValue) -> Option<&crate::object::function::JSCFunction> {
         let index = val.to_ptr()?;
@@ -1195,7 +3381,7 @@ impl Context {
     ///
     /// # Arguments
     ///
-    /// * `bytecode_index` - HeapIndex pointing to the function's bytecode
+    /// * `code` - The function's bytecode
     /// * `param_count` - Number of parameters
     /// * `local_count` - Number of local variables (including parameters)
     ///
@@ -1204,7 +3390,7 @@ impl Context {
     /// A JSValue wrapping the bytecode function
     pub fn new_bytecode_function(
         &mut self,
-        bytecode_index: crate::memory::HeapIndex,
+        code: crate::object::FunctionCode,
         param_count: u8,
         local_count: u8,
     ) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
@@ -1220,7 +3406,7 @@ impl Context {
         // Initialize the bytecode function
         unsafe {
             let func: &mut JSBytecodeFunction = self.arena.get_mut(index);
-            *func = JSBytecodeFunction::new(bytecode_index, param_count, local_count);
+            *func = JSBytecodeFunction::new(code, param_count, local_count);
         }
 
         Ok(JSValue::from_ptr(index))
@@ -1297,7 +3483,7 @@ impl Context {
     ///
     /// # Arguments
     ///
-    /// * `bytecode_index` - HeapIndex pointing to the function's bytecode
+    /// * `code` - The function's bytecode
     /// * `param_count` - Number of parameters
     /// * `local_count` - Number of local variables
     /// * `var_refs` - Array of HeapIndex values pointing to JSVarRef objects
@@ -1307,18 +3493,18 @@ impl Context {
     /// The HeapIndex of the allocated closure
     pub fn alloc_closure(
         &mut self,
-        bytecode_index: HeapIndex,
+        code: crate::object::FunctionCode,
         param_count: u8,
         local_count: u8,
         var_refs: &[HeapIndex],
     ) -> Result<HeapIndex, crate::memory::allocator::OutOfMemory> {
-        self.alloc_closure_with_self_name(bytecode_index, param_count, local_count, var_refs, 0xFF)
+        self.alloc_closure_with_self_name(code, param_count, local_count, var_refs, 0xFF)
     }
 
     /// Allocates a closure with optional self-name slot for named function expressions
     pub fn alloc_closure_with_self_name(
         &mut self,
-        bytecode_index: HeapIndex,
+        code: crate::object::FunctionCode,
         param_count: u8,
         local_count: u8,
         var_refs: &[HeapIndex],
@@ -1333,11 +3519,15 @@ impl Context {
 
         unsafe {
             let closure: &mut JSClosure = self.arena.get_mut(index);
-            closure.bytecode_index = bytecode_index;
+            closure.code = code;
             closure.param_count = param_count;
             closure.local_count = local_count;
             closure.var_ref_count = var_refs.len() as u8;
             closure.self_name_slot = self_name_slot;
+            closure.flags = 0;
+            closure.props = HeapIndex::null();
+            closure.name = JSAtom::null();
+            closure.lexical_this = JSValue::undefined();
 
             for (i, &vr_idx) in var_refs.iter().enumerate() {
                 closure.set_var_ref(i, vr_idx);
@@ -1380,6 +3570,137 @@ impl Context {
             false
         }
     }
+
+    /// Checks if a value is an arrow function
+    pub fn is_arrow_function(&self, val: JSValue) -> bool {
+        val.to_ptr()
+            .and_then(|index| self.get_closure(index))
+            .is_some_and(|closure| closure.is_arrow())
+    }
+
+    /// Checks if a value is a function: a closure, bytecode function,
+    /// native function or bound function
+    pub fn is_callable(&self, val: JSValue) -> bool {
+        val.to_ptr().is_some_and(|index| unsafe {
+            matches!(
+                self.arena.get_header(index).mtag(),
+                MemTag::CFunctionData | MemTag::ClosureData | MemTag::FunctionBytecode
+            )
+        }) || self.is_bound_function(val)
+    }
+
+    /// Checks if a value is a function made by `Function.prototype.bind`
+    ///
+    /// Bound functions are plain objects marked by an own hidden property;
+    /// only the object's own table is read, so no getter runs.
+    pub fn is_bound_function(&self, val: JSValue) -> bool {
+        self.find_own_property(val, crate::value::atoms::IS_BOUND_FUNCTION)
+            .is_some_and(|prop| !prop.is_accessor() && prop.value().to_bool() == Some(true))
+    }
+
+    /// Checks if a value can be invoked with `new`
+    ///
+    /// Bytecode functions and closures are constructors; arrow functions and
+    /// native functions are not.
+    pub fn is_constructor(&self, val: JSValue) -> bool {
+        if self.is_closure(val) {
+            !self.is_arrow_function(val)
+        } else {
+            self.get_bytecode_function(val).is_some()
+        }
+    }
+
+    /// Gives a script constructor its `prototype` object if it has none yet
+    ///
+    /// Created on first use rather than with every closure, as a plain
+    /// object whose non-enumerable `constructor` points back at the
+    /// function. Arrow functions and natives get none, and a `prototype` the
+    /// script assigned is kept.
+    pub fn ensure_function_prototype(&mut self, func: JSValue) -> Result<(), crate::memory::allocator::OutOfMemory> {
+        use crate::value::atoms;
+
+        if !self.is_constructor(func) || self.find_own_property(func, atoms::PROTOTYPE).is_some() {
+            return Ok(());
+        }
+        let mut hidden = crate::object::PropertyFlags::default();
+        hidden.set_enumerable(false);
+        let proto = self.new_object()?;
+        self.add_property(proto, atoms::CONSTRUCTOR, func, hidden)?;
+        self.add_property(func, atoms::PROTOTYPE, proto, hidden)
+    }
+
+    /// Returns the name a function was created with
+    ///
+    /// That's the declared name of a script function, or the name a native
+    /// was first installed under. None for anonymous functions, bound
+    /// functions and anything that isn't a function.
+    pub fn function_name(&self, func: JSValue) -> Option<JSAtom> {
+        self.name_slot(func).filter(|name| !name.is_null())
+    }
+
+    /// Names `func` `name` unless it already has a name
+    ///
+    /// The VM names script functions as it creates them; the runtime names
+    /// each builtin after the property it installs it under.
+    pub fn set_function_name(&mut self, func: JSValue, name: JSAtom) {
+        use crate::object::function::{JSBytecodeFunction, JSCFunction, JSClosure};
+
+        if self.name_slot(func) != Some(JSAtom::null()) {
+            return;
+        }
+        let Some(index) = func.to_ptr() else {
+            return;
+        };
+        // SAFETY: name_slot checked that the block holds one of these structs
+        unsafe {
+            match self.arena.get_header(index).mtag() {
+                MemTag::ClosureData => self.arena.get_mut::<JSClosure>(index).name = name,
+                MemTag::FunctionBytecode => self.arena.get_mut::<JSBytecodeFunction>(index).name = name,
+                MemTag::CFunctionData => self.arena.get_mut::<JSCFunction>(index).name = name,
+                _ => {}
+            }
+        }
+    }
+
+    /// Gives a function its `name` property if it has none yet
+    ///
+    /// Created on first use like `prototype`, as a read-only,
+    /// non-enumerable string: the [`function_name`](Self::function_name),
+    /// or `""` for an anonymous function. A `name` the script defined is
+    /// kept.
+    pub fn ensure_function_name(&mut self, func: JSValue) -> Result<(), crate::memory::allocator::OutOfMemory> {
+        use crate::value::atoms;
+
+        let Some(name) = self.name_slot(func) else {
+            return Ok(());
+        };
+        if self.find_own_property(func, atoms::NAME).is_some() {
+            return Ok(());
+        }
+        let name = self.atom_name(name).map(Cow::into_owned).unwrap_or_default();
+        let name = self.new_string(&name)?;
+        let mut flags = crate::object::PropertyFlags::default();
+        flags.set_enumerable(false);
+        flags.set_writable(false);
+        self.add_property(func, atoms::NAME, name, flags)
+    }
+
+    /// Returns the name slot of a closure, bytecode or native function,
+    /// null if it's anonymous; None for other values
+    fn name_slot(&self, val: JSValue) -> Option<JSAtom> {
+        use crate::object::function::{JSBytecodeFunction, JSCFunction, JSClosure};
+
+        let index = val.to_ptr()?;
+        // SAFETY: the block's tag says which struct it holds
+        unsafe {
+            match self.arena.get_header(index).mtag() {
+                MemTag::ClosureData => Some(self.arena.get::<JSClosure>(index).name),
+                MemTag::FunctionBytecode => Some(self.arena.get::<JSBytecodeFunction>(index).name),
+                MemTag::CFunctionData => Some(self.arena.get::<JSCFunction>(index).name),
+                _ => None,
+            }
+        }
+    }
 }
 
 impl Drop for Context {
@@ -1403,6 +3724,105 @@ mod tests {
         assert_eq!(ctx.memory_usage() + ctx.free_memory(), 1024);
     }
 
+    #[test]
+    fn test_context_heap_sizes() {
+        assert_eq!(
+            Context::try_new(0).err(),
+            Some(HeapSizeError { requested: 0, minimum: Context::MIN_MEMORY_SIZE })
+        );
+        assert!(Context::try_new(Context::MIN_MEMORY_SIZE - 1).is_err());
+
+        let ctx = Context::try_new(Context::MIN_MEMORY_SIZE).ok().unwrap();
+        assert!(ctx.global_object().is_object());
+        assert_eq!(ctx.free_memory(), 0);
+
+        let ctx = Context::try_new(1 << 20).ok().unwrap();
+        assert_eq!(ctx.arena_size(), 1 << 20);
+
+        // new() raises tiny sizes to the minimum and rounds to the alignment
+        let ctx = Context::new(16);
+        assert_eq!(ctx.arena_size(), Context::MIN_MEMORY_SIZE);
+        assert!(ctx.global_object().is_object());
+        assert_eq!(Context::new(10_001).arena_size(), 10_000);
+    }
+
+    #[test]
+    fn test_presized_property_tables() {
+        let mut ctx = Context::new(8192);
+        let obj = ctx.new_object_with_capacity(6).unwrap();
+        assert_eq!(ctx.own_property_capacity(obj), 6);
+        for i in 0..7 {
+            let key = ctx.intern_atom(&alloc::format!("p{i}"));
+            ctx.set_property(obj, key, JSValue::from_int(i)).unwrap();
+        }
+        assert_eq!(ctx.own_property_count(obj), 7);
+        assert!(ctx.own_property_capacity(obj) >= 7);
+        let empty = ctx.new_object_with_capacity(0).unwrap();
+        assert_eq!(ctx.own_property_capacity(empty), 0);
+        assert!(ctx.new_object_with_capacity(usize::MAX).is_err());
+
+        // The global table is sized from the config, or left to grow when
+        // the heap has no room for it
+        let ctx = Context::with_config(ContextConfig { expected_globals: 100, ..ContextConfig::new(16384) });
+        assert_eq!(ctx.own_property_capacity(ctx.global_object()), 100);
+        let ctx = Context::with_config(ContextConfig { expected_globals: 100, ..ContextConfig::new(0) });
+        assert!(ctx.global_object().is_object());
+        assert_eq!(ctx.own_property_capacity(ctx.global_object()), 0);
+    }
+
+    #[test]
+    fn test_prototype_cycle_ends_lookup() {
+        let mut ctx = Context::new(8192);
+        let a = ctx.new_object().unwrap();
+        let b = ctx.new_object_with_proto(a).unwrap();
+        let key = crate::value::atoms::LENGTH;
+        ctx.set_property(a, key, JSValue::from_int(1)).unwrap();
+        assert_eq!(ctx.get_property(b, key), Some(JSValue::from_int(1)));
+
+        // A corrupted chain that loops back on itself ends the walk
+        ctx.get_object_mut(a).unwrap().set_prototype(b);
+        let missing = crate::value::atoms::PROTOTYPE;
+        assert_eq!(ctx.get_property(b, missing), None);
+        assert!(matches!(ctx.find_property_with_accessor(b, missing), PropertyLookupResult::NotFound));
+        assert!(!ctx.has_property(a, missing));
+    }
+
+    #[test]
+    fn test_set_property() {
+        let mut ctx = Context::new(8192);
+        let obj = ctx.new_object().unwrap();
+        let key = crate::value::atoms::LENGTH;
+        let other = crate::value::atoms::PROTOTYPE;
+
+        ctx.set_property(obj, key, JSValue::from_int(1)).unwrap();
+        ctx.set_property(obj, key, JSValue::from_int(2)).unwrap();
+        ctx.set_property(obj, other, JSValue::from_int(3)).unwrap();
+        assert_eq!(ctx.own_property_count(obj), 2);
+        assert_eq!(ctx.find_own_property(obj, key).unwrap().value().to_int(), Some(2));
+
+        // Read-only properties keep their value
+        let mut flags = crate::object::PropertyFlags::default();
+        flags.set_writable(false);
+        let frozen = ctx.new_object().unwrap();
+        ctx.add_property(frozen, key, JSValue::from_int(1), flags).unwrap();
+        ctx.set_property(frozen, key, JSValue::from_int(2)).unwrap();
+        assert_eq!(ctx.own_property_count(frozen), 1);
+        assert_eq!(ctx.find_own_property(frozen, key).unwrap().value().to_int(), Some(1));
+    }
+
+    #[test]
+    #[cfg(feature = "builtin-math")]
+    fn test_seed_random() {
+        let mut ctx = Context::new(1024);
+        ctx.seed_random(42);
+        let first: Vec<f64> = (0..4).map(|_| ctx.next_random()).collect();
+        ctx.seed_random(42);
+        let second: Vec<f64> = (0..4).map(|_| ctx.next_random()).collect();
+        assert_eq!(first, second);
+        assert!(first.iter().all(|r| (0.0..1.0).contains(r)));
+        assert_ne!(first[0], first[1]);
+    }
+
     #[test]
     fn test_context_gc() {
         let mut ctx = Context::new(2048);
@@ -1424,7 +3844,7 @@ mod tests {
         assert!(usage_before_gc > 0);
 
         // Run GC
-        ctx.gc();
+        ctx.gc().unwrap();
 
         // Memory usage should still be > 0 because we have a root
         let usage_after_gc = ctx.memory_usage();
@@ -1447,12 +3867,29 @@ mod tests {
         ctx.add_root(val);
 
         // GC should preserve it
-        ctx.gc();
+        ctx.gc().unwrap();
 
         // Remove root
         ctx.remove_root(val);
     }
 
+    #[test]
+    fn test_persistent_survives_gc_until_epoch_ends() {
+        let mut ctx = Context::new(4096);
+        let baseline = ctx.memory_usage();
+
+        let obj = ctx.new_object().unwrap();
+        let handle = ctx.persist(obj);
+        ctx.gc().unwrap();
+        assert_eq!(ctx.persistent_value(&handle), Some(obj));
+        assert!(ctx.get_object(obj).is_some());
+
+        ctx.bump_epoch();
+        ctx.gc().unwrap();
+        assert_eq!(ctx.persistent_value(&handle), None);
+        assert!(ctx.memory_usage() <= baseline);
+    }
+
     #[test]
     fn test_context_memory_tracking() {
         let mut ctx = Context::new(1024);
@@ -1635,6 +4072,70 @@ mod tests {
         assert!(prop.flags().is_configurable());
     }
 
+    #[test]
+    fn test_delete_property() {
+        use crate::object::PropertyFlags;
+        use crate::value::JSAtom;
+
+        let mut ctx = Context::new(16384);
+        let obj = ctx.new_object().unwrap();
+        // Keys that share hash chains: the table masks ids to its size
+        let keys: Vec<JSAtom> = (0..40).map(|i| JSAtom::from_id(1000 + i * 64)).collect();
+        for (i, &key) in keys.iter().enumerate() {
+            ctx.add_property(obj, key, JSValue::from_int(i as i32), PropertyFlags::default()).unwrap();
+        }
+        let fixed = JSAtom::from_id(999);
+        ctx.add_property(obj, fixed, JSValue::from_int(-1), PropertyFlags::empty()).unwrap();
+
+        assert!(ctx.delete_property(obj, keys[5]));
+        assert!(ctx.delete_property(obj, keys[5]));
+        assert!(!ctx.delete_property(obj, fixed));
+        assert_eq!(ctx.own_property_count(obj), 40);
+        assert!(ctx.find_own_property(obj, keys[5]).is_none());
+        for (i, &key) in keys.iter().enumerate().filter(|&(i, _)| i != 5) {
+            assert_eq!(ctx.get_property(obj, key), Some(JSValue::from_int(i as i32)));
+        }
+        assert_eq!(ctx.own_enumerable_keys(obj)[5], keys[6]);
+    }
+
+    #[test]
+    fn test_property_table_grows() {
+        use crate::object::{PropertyFlags, PropertyTableHeader};
+        use crate::value::JSAtom;
+
+        let mut ctx = Context::new(32768);
+        let obj = ctx.new_object().unwrap();
+        assert_eq!(ctx.own_property_capacity(obj), 0);
+
+        let keys: Vec<JSAtom> = (0..100).map(|i| JSAtom::from_id(2000 + i * 3)).collect();
+        let mut capacities = Vec::new();
+        for (i, &key) in keys.iter().enumerate() {
+            ctx.add_property(obj, key, JSValue::from_int(i as i32), PropertyFlags::default()).unwrap();
+            if capacities.last() != Some(&ctx.own_property_capacity(obj)) {
+                capacities.push(ctx.own_property_capacity(obj));
+            }
+        }
+        let first = PropertyTableHeader::INITIAL_CAPACITY as usize;
+        assert_eq!(capacities, [first, first * 2, first * 4, first * 8, first * 16, first * 32]);
+        assert_eq!(ctx.own_property_count(obj), 100);
+
+        // Accessors and replaced keys go through the grown table
+        let getter = JSAtom::from_id(1999);
+        ctx.define_getter(obj, getter, JSValue::from_int(7)).unwrap();
+        ctx.add_property(obj, keys[0], JSValue::from_int(-1), PropertyFlags::default()).unwrap();
+        assert_eq!(ctx.own_property_count(obj), 101);
+        assert!(ctx.find_own_property(obj, getter).unwrap().flags().has_get());
+
+        // Deleting keeps insertion order and the hash chains intact
+        assert!(ctx.delete_property(obj, keys[50]));
+        assert_eq!(ctx.get_property(obj, keys[0]), Some(JSValue::from_int(-1)));
+        for (i, &key) in keys.iter().enumerate().skip(1) {
+            let expected = (i != 50).then(|| JSValue::from_int(i as i32));
+            assert_eq!(ctx.get_property(obj, key), expected);
+        }
+        assert_eq!(ctx.own_enumerable_keys(obj)[50], keys[51]);
+    }
+
     #[test]
     fn test_property_lookup_chain() {
         use crate::object::PropertyFlags;
@@ -1709,6 +4210,7 @@ mod tests {
     #[test]
     fn test_gc_compaction_frees_memory() {
         let mut ctx = Context::new(4096);
+        let baseline = ctx.memory_usage();
 
         // Allocate objects without rooting them - they should be collected
         for _ in 0..10 {
@@ -1720,11 +4222,11 @@ mod tests {
         assert!(usage_before > 0, "Should have allocated some memory");
 
         // Run GC - all objects should be collected since they're not rooted
-        ctx.gc();
+        ctx.gc().unwrap();
 
         let usage_after = ctx.memory_usage();
 
-        // Memory should be freed (should be 0 or very close to 0)
+        // Memory should be freed, down to the global object
         assert!(
             usage_after < usage_before,
             "GC should free memory: before={}, after={}",
@@ -1732,7 +4234,7 @@ mod tests {
             usage_after
         );
         assert_eq!(
-            usage_after, 0,
+            usage_after, baseline,
             "All unreachable objects should be collected, usage={}",
             usage_after
         );
@@ -1741,6 +4243,7 @@ mod tests {
     #[test]
     fn test_gc_preserves_rooted_objects() {
         let mut ctx = Context::new(4096);
+        let baseline = ctx.memory_usage();
 
         // Allocate and root some objects
         let obj1 = ctx.new_object().unwrap();
@@ -1760,7 +4263,7 @@ mod tests {
         let usage_before = ctx.memory_usage();
 
         // Run GC
-        ctx.gc();
+        ctx.gc().unwrap();
 
         let usage_after = ctx.memory_usage();
 
@@ -1782,9 +4285,36 @@ mod tests {
         ctx.remove_root(obj2);
         ctx.remove_root(str1);
 
-        // Now everything should be collectable
-        ctx.gc();
-        assert_eq!(ctx.memory_usage(), 0);
+        // Now everything but the global object should be collectable
+        ctx.gc().unwrap();
+        assert_eq!(ctx.memory_usage(), baseline);
+    }
+
+    #[test]
+    fn test_gc_stats() {
+        let mut ctx = Context::new(4096);
+        let baseline = ctx.memory_usage();
+
+        let kept = ctx.new_string("kept").unwrap();
+        ctx.add_root(kept);
+        for _ in 0..4 {
+            let _ = ctx.new_string("garbage").unwrap();
+        }
+        let before = ctx.memory_stats();
+        assert!(before.allocated_since_gc >= before.heap_used - baseline);
+        assert_eq!(before.peak_used, before.heap_used);
+
+        let stats = ctx.gc().unwrap();
+        assert_eq!(stats.bytes_before, before.heap_used);
+        assert_eq!(stats.bytes_after, ctx.memory_usage());
+        assert!(stats.bytes_after < stats.bytes_before);
+        assert_eq!(stats.objects_freed, 4);
+
+        let after = ctx.memory_stats();
+        assert_eq!(after.gc_count, before.gc_count + 1);
+        assert_eq!(after.allocated_since_gc, 0);
+        assert_eq!(after.peak_used, before.heap_used);
+        assert_eq!(ctx.get_string(kept), Some("kept"));
     }
 
     #[test]
@@ -1808,7 +4338,7 @@ mod tests {
         let usage_before = ctx.memory_usage();
 
         // Run GC - should compact memory
-        ctx.gc();
+        ctx.gc().unwrap();
 
         let usage_after = ctx.memory_usage();
 
@@ -1831,6 +4361,107 @@ mod tests {
         ctx.remove_root(obj3);
     }
 
+    #[test]
+    fn test_same_value_and_hash() {
+        use crate::value::KeyedValue;
+
+        let mut ctx = Context::new(8192);
+
+        // Equal-content strings are distinct heap values with one key
+        let a = ctx.new_string("key").unwrap();
+        let b = ctx.new_string("key").unwrap();
+        assert_ne!(a, b);
+        assert!(ctx.same_value(a, b));
+        assert_eq!(ctx.value_hash(a), ctx.value_hash(b));
+        assert_eq!(KeyedValue::new(&ctx, a), KeyedValue::new(&ctx, b));
+        let other = ctx.new_string("other").unwrap();
+        assert!(!ctx.same_value(a, other));
+
+        // Inline and boxed numbers agree; NaN is itself, -0 isn't +0
+        let one = JSValue::from_int(1);
+        let boxed_one = ctx.new_number(1.5).unwrap();
+        assert!(!ctx.same_value(one, boxed_one));
+        assert!(ctx.same_value(one, JSValue::from_int(1)));
+        let nan = ctx.new_number(f64::NAN).unwrap();
+        let other_nan = ctx.new_number(-f64::NAN).unwrap();
+        assert!(ctx.same_value(nan, other_nan));
+        assert_eq!(ctx.value_hash(nan), ctx.value_hash(other_nan));
+        let zero = JSValue::from_int(0);
+        let neg_zero = ctx.new_number(-0.0).unwrap();
+        assert!(!ctx.same_value(zero, neg_zero));
+        let pos_zero = ctx.new_number(0.0).unwrap();
+        assert!(ctx.same_value(zero, pos_zero));
+        let one_string = ctx.new_string("1").unwrap();
+        assert!(!ctx.same_value(one, one_string));
+
+        // Objects with equal contents stay distinct
+        let o1 = ctx.new_object().unwrap();
+        let o2 = ctx.new_object().unwrap();
+        assert!(ctx.same_value(o1, o1));
+        assert!(!ctx.same_value(o1, o2));
+        assert_ne!(KeyedValue::new(&ctx, o1), KeyedValue::new(&ctx, o2));
+    }
+
+    #[test]
+    fn test_object_hash_survives_compaction() {
+        use std::collections::HashMap;
+        use crate::value::KeyedValue;
+
+        let mut ctx = Context::new(8192);
+
+        let _garbage = ctx.new_object().unwrap();
+        let obj = ctx.new_object().unwrap();
+        let mut handle = ctx.persist(obj);
+        let hash = ctx.value_hash(obj);
+
+        let mut map = HashMap::new();
+        map.insert(KeyedValue::new(&ctx, obj), "obj");
+
+        let usage_before = ctx.memory_usage();
+        ctx.gc().unwrap();
+        assert!(ctx.memory_usage() < usage_before, "GC should have compacted");
+
+        let obj = ctx.persistent_value(&handle).unwrap();
+        assert_eq!(ctx.value_hash(obj), hash);
+        assert_eq!(map.get(&KeyedValue::new(&ctx, obj)), Some(&"obj"));
+        ctx.detach(&mut handle);
+    }
+
+    #[test]
+    fn test_heap_census_counts_blocks() {
+        let mut ctx = Context::new(65536);
+        let count = |ctx: &Context, tag: MemTag| {
+            ctx.heap_census().into_iter().find(|&(t, _)| t == tag).map(|(_, entry)| entry).unwrap_or_default()
+        };
+        ctx.gc().unwrap();
+        let strings = count(&ctx, MemTag::String);
+        let objects = count(&ctx, MemTag::Object);
+
+        for i in 0..10 {
+            let s = ctx.new_string(&alloc::format!("kept {}", i)).unwrap();
+            ctx.add_root(s);
+            ctx.new_string("garbage").unwrap();
+        }
+        for _ in 0..4 {
+            let obj = ctx.new_object().unwrap();
+            ctx.add_root(obj);
+        }
+        ctx.gc().unwrap();
+
+        // Exact after a collection: only the rooted blocks are left
+        let after = count(&ctx, MemTag::String);
+        assert_eq!(after.count - strings.count, 10);
+        let string_block = Arena::block_size(
+            core::mem::size_of::<crate::memory::MemBlockHeader>() + crate::value::JSString::alloc_size("kept 0".len()),
+        );
+        assert_eq!(after.bytes - strings.bytes, 10 * string_block);
+        assert_eq!(count(&ctx, MemTag::Object).count - objects.count, 4);
+
+        // Every heap byte belongs to some block
+        let total: usize = ctx.heap_census().iter().map(|(_, entry)| entry.bytes).sum();
+        assert_eq!(total, ctx.memory_usage());
+    }
+
     #[test]
     fn test_native_function_as_property() {
         use crate::value::JSAtom;
@@ -1839,7 +4470,7 @@ mod tests {
         let mut ctx = Context::new(32768); // 32KB heap
 
         // Create a native function and add it as a property
-        let test_fn = ctx.new_native_function(crate::builtins::native_functions::math_abs, 1).unwrap();
+        let test_fn = ctx.new_native_function(crate::builtins::native_functions::number_is_nan_native, 1).unwrap();
         assert!(ctx.get_native_function(test_fn).is_some());
 
         // Create a test object
@@ -1882,7 +4513,7 @@ mod tests {
         }
 
         // Run GC
-        ctx.gc();
+        ctx.gc().unwrap();
 
         // All objects in the chain should still be accessible
         assert!(ctx.get_object(obj1).is_some());
@@ -1895,4 +4526,121 @@ mod tests {
 
         ctx.remove_root(obj1);
     }
+
+    #[test]
+    fn test_array_elements() {
+        use crate::value::JSAtom;
+
+        let mut ctx = Context::new(8192);
+
+        let arr = ctx.new_array().unwrap();
+        assert!(ctx.is_array(arr));
+        assert_eq!(ctx.array_length(arr), Some(0));
+
+        // Dense writes grow storage past the initial capacity
+        for i in 0..10 {
+            ctx.set_array_element(arr, i, JSValue::from_int(i as i32)).unwrap();
+        }
+        assert_eq!(ctx.array_length(arr), Some(10));
+        assert_eq!(ctx.get_array_element(arr, 9), Some(JSValue::from_int(9)));
+
+        // A far-out write lands in the property table but still extends length
+        ctx.set_array_element(arr, 1000, JSValue::from_int(7)).unwrap();
+        assert_eq!(ctx.array_length(arr), Some(1001));
+        assert_eq!(ctx.get_array_element(arr, 1000), None);
+        let key = JSAtom::from_index(1000).unwrap();
+        assert_eq!(ctx.get_property(arr, key), Some(JSValue::from_int(7)));
+
+        // Elements survive compaction
+        ctx.add_root(arr);
+        ctx.gc().unwrap();
+        assert_eq!(ctx.get_array_element(arr, 3), Some(JSValue::from_int(3)));
+
+        // Truncation clears the dropped elements
+        ctx.set_array_length(arr, 2).unwrap();
+        assert_eq!(ctx.array_length(arr), Some(2));
+        assert_eq!(ctx.get_property(arr, JSAtom::from_index(5).unwrap()), Some(JSValue::undefined()));
+        ctx.remove_root(arr);
+    }
+
+    #[test]
+    fn test_array_f64_round_trip() {
+        use crate::value::JSAtom;
+
+        let mut ctx = Context::new(8192);
+        let samples = [1.0, -2.5, 0.0, f64::NAN, 1e300];
+        let arr = ctx.new_array_from_f64_slice(&samples).unwrap();
+        assert_eq!(ctx.array_length(arr), Some(5));
+        assert_eq!(ctx.get_array_element(arr, 0), Some(JSValue::from_int(1)));
+        let back = ctx.array_to_f64_vec(arr).unwrap();
+        assert_eq!(back[..3], [1.0, -2.5, 0.0]);
+        assert!(back[3].is_nan());
+        assert_eq!(back[4], 1e300);
+
+        let empty = ctx.new_array_from_f64_slice(&[]).unwrap();
+        assert_eq!(ctx.array_to_f64_vec(empty), Some(Vec::new()));
+        assert_eq!(ctx.array_to_f64_vec(JSValue::from_int(1)), None);
+
+        // Mixed and sparse arrays fall back to ToNumber, holes read as NaN
+        let mixed = ctx.new_array().unwrap();
+        let two = ctx.new_string("2").unwrap();
+        ctx.set_array_element(mixed, 0, two).unwrap();
+        ctx.set_array_element(mixed, 1, JSValue::bool(true)).unwrap();
+        ctx.set_array_element(mixed, 3, JSValue::from_int(4)).unwrap();
+        ctx.set_array_element(mixed, 200, JSValue::from_int(5)).unwrap();
+        let back = ctx.array_to_f64_vec(mixed).unwrap();
+        assert_eq!(back.len(), 201);
+        assert_eq!((back[0], back[1], back[3], back[200]), (2.0, 1.0, 4.0, 5.0));
+        assert!(back[2].is_nan() && back[199].is_nan());
+        assert_eq!(ctx.get_property(mixed, JSAtom::from_index(200).unwrap()), Some(JSValue::from_int(5)));
+    }
+
+    #[test]
+    fn test_eval() {
+        let mut ctx = Context::new(8192);
+        let result = ctx.eval("2 + 2", "test.js", 0).unwrap();
+        assert_eq!(result.to_int(), Some(4));
+
+        let err = ctx.eval("2 +", "test.js", 0).unwrap_err();
+        assert!(matches!(err, crate::EngineError::Parse { line: 1, .. }));
+    }
+
+    #[test]
+    fn test_eval_files_share_globals() {
+        let mut ctx = Context::new(8192);
+        ctx.eval("function f() { return 'from a'; }\nvar count = 1;", "a.js", 0).unwrap();
+        let result = ctx.eval("count++; f()", "b.js", 0).unwrap();
+        assert_eq!(ctx.get_string(result), Some("from a"));
+        assert_eq!(ctx.eval("count", "c.js", 0).unwrap().to_int(), Some(2));
+
+        let err = ctx.eval("f(;", "b.js", 0).unwrap_err();
+        assert!(matches!(&err, crate::EngineError::Parse { file: Some(file), line: 1, .. } if file == "b.js"));
+        assert!(err.to_string().ends_with(" at b.js:1:3"), "{}", err);
+    }
+
+    #[test]
+    fn test_eval_line_offset() {
+        let mut ctx = Context::new(8192);
+        let err = ctx.eval("var ok = 1;\nvar bad = (;", "page.html", 10).unwrap_err();
+        assert!(matches!(err, crate::EngineError::Parse { line: 12, .. }), "{:?}", err);
+
+        let err = ctx.eval("var ok = 1;\nnull.x;", "page.html", 10).unwrap_err();
+        assert!(matches!(&err, crate::EngineError::Runtime { line: 12, file: Some(file), .. } if file == "page.html"));
+        assert!(err.to_string().ends_with(" at page.html:12"), "{}", err);
+    }
+
+    #[test]
+    fn test_eval_expr() {
+        let mut ctx = Context::new(8192);
+        ctx.eval("var base = 40;", "setup.js", 0).unwrap();
+        assert_eq!(ctx.eval_expr("base + 2", "<repl>", 0).unwrap().to_int(), Some(42));
+
+        // An object literal, where a program would see a block
+        let obj = ctx.eval_expr("{ a: 1 }", "<repl>", 0).unwrap();
+        assert!(obj.is_object());
+        assert!(ctx.eval("{ a: 1 }", "<repl>", 0).unwrap().is_undefined());
+
+        let err = ctx.eval_expr("var x = 1", "<repl>", 0).unwrap_err();
+        assert!(matches!(err, crate::EngineError::Parse { .. }), "{:?}", err);
+    }
 }
@@ -3,22 +3,224 @@
 //! This module provides a simplified interface for executing JavaScript code.
 //! It wraps the Context, Compiler, and VM into a single easy-to-use API.
 
-use crate::context::Context;
+use crate::builtins::error::{ErrorKind, ErrorType};
+use crate::bytecode::{LoadedScript, ScriptSource};
+use crate::compiler::CompileError;
+use crate::context::{Clock, Context, ContextConfig, InterruptHandler, ReentrancyError};
+use crate::memory::{HeapSizeError, Persistent};
+use crate::object::function::{FunctionCode, NativeFn};
 use crate::value::JSValue;
+use crate::vm::VM;
 use crate::compiler;
 use crate::runtime;
+use crate::runtime::EngineInfoHook;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 
 /// Memory statistics for the JavaScript engine
 #[derive(Debug, Clone, Copy)]
 pub struct MemoryStats {
-    /// Total heap size in bytes
+    /// Total heap size in bytes, after rounding down to the allocator's
+    /// alignment (and raising to [`Engine::MIN_HEAP_SIZE`] for `Engine::new`)
     pub heap_size: usize,
     /// Heap bytes currently in use
     pub heap_used: usize,
     /// Number of objects allocated (approximate)
     pub object_count: usize,
+    /// Number of garbage collection cycles run so far
+    pub gc_count: usize,
+    /// Heap bytes still in use after the most recent collection, or zero
+    /// if none has run
+    pub live_bytes: usize,
+    /// Highest heap usage seen so far
+    pub peak_used: usize,
+    /// Heap bytes allocated since the most recent collection
+    pub allocated_since_gc: usize,
+}
+
+/// What one garbage collection achieved
+///
+/// No timing is recorded, since `no_std` targets have no portable clock;
+/// hosts with a cycle counter can read it around the call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GcStats {
+    /// Heap bytes in use before the collection
+    pub bytes_before: usize,
+    /// Heap bytes in use after it
+    pub bytes_after: usize,
+    /// Heap blocks freed: objects, strings, closures and the like
+    pub objects_freed: usize,
+}
+
+/// Why running a script failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum EngineError {
+    /// The source didn't compile
+    Parse {
+        /// What the compiler objected to
+        message: String,
+        /// Line of the offending code (1-based; 0 when unknown)
+        line: u32,
+        /// Column of the offending code (1-based; 0 when unknown)
+        column: u32,
+        /// File the source came from, if the host named one
+        file: Option<String>,
+    },
+    /// The script threw and nothing caught it
+    Runtime {
+        /// The thrown value, which lives in the engine's heap
+        value: JSValue,
+        /// The thrown value as text: `Name: message` for error objects
+        message: String,
+        /// Line the error was thrown from (1-based; 0 when unknown)
+        ///
+        /// Only error objects record one, as their `lineNumber` property.
+        line: u32,
+        /// File of the script the error escaped from, if the host named one
+        ///
+        /// That's the file passed to `Context::eval`, even when a function
+        /// defined by an earlier file threw.
+        file: Option<String>,
+    },
+    /// The heap filled up
+    OutOfMemory,
+    /// The host's interrupt handler stopped the script
+    Interrupted,
+    /// A native function tried to start another run; see `Context`
+    Reentrancy(ReentrancyError),
+}
+
+impl EngineError {
+    /// Wraps a value thrown out of the VM
+    ///
+    /// Out-of-memory errors and interrupts lose their value: there may be
+    /// no room left to inspect it, and hosts only need to know what
+    /// happened.
+    pub(crate) fn from_exception(ctx: &Context, value: JSValue) -> Self {
+        match ctx.error_kind(value) {
+            Some(ErrorKind::OutOfMemory) => return EngineError::OutOfMemory,
+            Some(ErrorKind::Interrupted) => return EngineError::Interrupted,
+            _ => {}
+        }
+        let line = ctx
+            .find_own_property(value, crate::value::atoms::LINE_NUMBER)
+            .and_then(|prop| prop.value().to_int())
+            .and_then(|line| u32::try_from(line).ok())
+            .unwrap_or(0);
+        EngineError::Runtime { value, message: display_value(ctx, value), line, file: None }
+    }
+
+    /// Names the file a compile or runtime error came from
+    pub(crate) fn in_file(mut self, name: &str) -> Self {
+        if let EngineError::Parse { file, .. } | EngineError::Runtime { file, .. } = &mut self {
+            *file = Some(String::from(name));
+        }
+        self
+    }
+
+    /// Returns the thrown value of a runtime error
+    pub fn value(&self) -> Option<JSValue> {
+        match self {
+            EngineError::Runtime { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl From<CompileError> for EngineError {
+    fn from(err: CompileError) -> Self {
+        let (message, location) = match err {
+            CompileError::Parse(err) => (err.message, Some(err.location)),
+            CompileError::CodeGen(err) => (err.message, err.location),
+        };
+        EngineError::Parse {
+            message,
+            line: location.map_or(0, |loc| loc.line),
+            column: location.map_or(0, |loc| loc.column),
+            file: None,
+        }
+    }
+}
+
+impl From<ReentrancyError> for EngineError {
+    fn from(err: ReentrancyError) -> Self {
+        EngineError::Reentrancy(err)
+    }
+}
+
+impl core::fmt::Display for EngineError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            EngineError::Parse { message, line: 0, file: None, .. } => write!(f, "SyntaxError: {}", message),
+            EngineError::Parse { message, line: 0, file: Some(file), .. } => {
+                write!(f, "SyntaxError: {} in {}", message, file)
+            }
+            EngineError::Parse { message, line, column, file: None } => {
+                write!(f, "SyntaxError: {} at line {}, column {}", message, line, column)
+            }
+            EngineError::Parse { message, line, column, file: Some(file) } => {
+                write!(f, "SyntaxError: {} at {}:{}:{}", message, file, line, column)
+            }
+            EngineError::Runtime { message, line: 0, file: None, .. } => f.write_str(message),
+            EngineError::Runtime { message, line: 0, file: Some(file), .. } => write!(f, "{} in {}", message, file),
+            EngineError::Runtime { message, line, file: None, .. } => write!(f, "{} at line {}", message, line),
+            EngineError::Runtime { message, line, file: Some(file), .. } => write!(f, "{} at {}:{}", message, file, line),
+            EngineError::OutOfMemory => write!(f, "{}: Out of memory", ErrorKind::OutOfMemory.name()),
+            EngineError::Interrupted => write!(f, "{}: Interrupted", ErrorKind::Interrupted.name()),
+            EngineError::Reentrancy(err) => write!(f, "{}: {}", ErrorKind::Internal.name(), err),
+        }
+    }
+}
+
+/// Resource use of one `eval_captured` run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EvalMetrics {
+    /// Bytecode instructions the VM started
+    pub instructions: u64,
+    /// Garbage collection cycles run
+    pub gc_count: usize,
+    /// Heap bytes in use before the run
+    pub heap_used_before: usize,
+    /// Heap bytes in use after it, the rooted result included
+    pub heap_used_after: usize,
+    /// Most prototype links a single property lookup followed
+    pub prototype_depth: usize,
+}
+
+/// An uncaught error, broken out for reporting
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorReport {
+    /// Category, as `Engine::error_kind` reports it
+    pub kind: Option<ErrorKind>,
+    /// The error's `Display` text
+    pub message: String,
+    /// The thrown object's `stack` property, if it has a string one
+    pub stack: Option<String>,
+    /// Line of the offending code (1-based; 0 when unknown)
+    ///
+    /// Runtime errors only know it for error objects.
+    pub line: u32,
+    /// Column of the offending code (1-based; 0 when unknown)
+    ///
+    /// Only compile errors carry a column.
+    pub column: u32,
+}
+
+/// Everything `Engine::eval_captured` observed about one run
+#[derive(Debug)]
+pub struct EvalReport {
+    /// The completion value, rooted until the host detaches it; `None` if
+    /// the script failed
+    pub value: Option<Persistent>,
+    /// The completion value as `JSON.stringify` renders it, or a note on
+    /// why it has no JSON form
+    pub json: Result<String, String>,
+    /// Lines written by console methods, in order
+    pub output: Vec<String>,
+    /// Why the script failed, if it did
+    pub error: Option<ErrorReport>,
+    /// Resource use of the run
+    pub metrics: EvalMetrics,
 }
 
 /// High-level JavaScript engine
@@ -40,16 +242,26 @@ pub struct MemoryStats {
 pub struct Engine {
     /// Execution context
     context: Context,
-    /// Random state for Math.random()
-    random_state: u64,
+    /// Interpreter, kept between calls so its stacks are only allocated once
+    vm: VM,
 }
 
 impl Engine {
+    /// Smallest heap an engine accepts: the global object plus every
+    /// builtin this build installs, the reserved out-of-memory error included
+    ///
+    /// The value depends on the enabled `builtin-*` features.
+    pub const MIN_HEAP_SIZE: usize = Context::MIN_MEMORY_SIZE + runtime::init::RUNTIME_HEAP_SIZE;
+
     /// Create a new JavaScript engine with specified heap size
     ///
+    /// Sizes below [`MIN_HEAP_SIZE`](Self::MIN_HEAP_SIZE) are raised to it,
+    /// and the size is rounded down to the allocator's alignment; use
+    /// [`try_new`](Self::try_new) to reject small sizes instead.
+    ///
     /// # Arguments
     ///
-    /// * `heap_size` - Size of the heap in bytes (minimum 1024 bytes recommended)
+    /// * `heap_size` - Size of the heap in bytes
     ///
     /// # Example
     ///
@@ -57,20 +269,82 @@ impl Engine {
     /// let engine = Engine::new(65536); // 64 KB heap
     /// ```
     pub fn new(heap_size: usize) -> Self {
-        let mut context = Context::new(heap_size);
+        Self::with_init(ContextConfig::new(heap_size), None)
+    }
+
+    /// Create a new JavaScript engine with the heap and VM stack sizes in
+    /// `config`
+    ///
+    /// The heap is raised to [`MIN_HEAP_SIZE`](Self::MIN_HEAP_SIZE) as in
+    /// [`new`](Self::new). Scripts see the call depth as
+    /// `engine.limits.maxCallDepth`, and nesting calls deeper throws a
+    /// catchable `RangeError: Maximum call stack size exceeded`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let engine = Engine::with_config(ContextConfig { call_depth: 40, ..ContextConfig::new(65536) });
+    /// ```
+    pub fn with_config(config: ContextConfig) -> Self {
+        Self::with_init(config, None)
+    }
 
-        // Initialize the runtime (global object, built-ins, etc.)
-        // For now we skip this if it fails - we'll improve error handling later
-        let _ = runtime::init_runtime(&mut context);
+    /// Create a new JavaScript engine whose script-visible `engine` object
+    /// `hook` extends before it is frozen
+    ///
+    /// Whatever `hook` adds is on top of [`MIN_HEAP_SIZE`](Self::MIN_HEAP_SIZE),
+    /// so leave room for it in `heap_size`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// fn board_info(ctx: &mut Context, engine: JSValue) -> Result<(), JSValue> {
+    ///     let board = ctx.intern_atom("board");
+    ///     let name = ctx.new_string("rev-c").map_err(|_| JSValue::exception())?;
+    ///     ctx.add_property(engine, board, name, PropertyFlags::default()).map_err(|_| JSValue::exception())
+    /// }
+    ///
+    /// let mut engine = Engine::with_engine_info(65536, board_info);
+    /// assert_eq!(engine.eval_as_string("engine.board").unwrap(), "rev-c");
+    /// ```
+    pub fn with_engine_info(heap_size: usize, hook: EngineInfoHook) -> Self {
+        Self::with_init(ContextConfig::new(heap_size), Some(hook))
+    }
+
+    fn with_init(config: ContextConfig, engine_info: Option<EngineInfoHook>) -> Self {
+        let memory_bytes = config.memory_bytes.max(Self::MIN_HEAP_SIZE);
+        let mut context = Context::with_config(ContextConfig { memory_bytes, ..config });
+
+        // Initialize the runtime (global object, built-ins, etc.); the heap
+        // is at least MIN_HEAP_SIZE, which always holds it
+        let _ = runtime::init_runtime_with(&mut context, engine_info);
 
         Engine {
+            vm: VM::with_stack_sizes(context.value_stack_slots(), context.call_depth()),
             context,
-            random_state: 0x123456789ABCDEF0, // Simple initial seed
         }
     }
 
+    /// Create a new JavaScript engine, failing if `heap_size` is below
+    /// [`MIN_HEAP_SIZE`](Self::MIN_HEAP_SIZE)
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let err = Engine::try_new(1024).unwrap_err();
+    /// assert_eq!(err.minimum, Engine::MIN_HEAP_SIZE);
+    /// let engine = Engine::try_new(65536)?;
+    /// ```
+    pub fn try_new(heap_size: usize) -> Result<Self, HeapSizeError> {
+        HeapSizeError::check(heap_size, Self::MIN_HEAP_SIZE)?;
+        Ok(Self::new(heap_size))
+    }
+
     /// Execute JavaScript source code and return the result
     ///
+    /// Each call runs against the same global object, so variables and
+    /// functions defined by one `eval` remain visible to later calls.
+    ///
     /// # Arguments
     ///
     /// * `source` - JavaScript source code to execute
@@ -78,7 +352,8 @@ impl Engine {
     /// # Returns
     ///
     /// * `Ok(JSValue)` - The result of execution
-    /// * `Err(JSValue)` - An exception value (error)
+    /// * `Err(EngineError)` - A compile error, an uncaught exception, or
+    ///   running out of memory
     ///
     /// # Example
     ///
@@ -86,291 +361,2047 @@ impl Engine {
     /// let mut engine = Engine::new(65536);
     /// let result = engine.eval("1 + 2")?;
     /// ```
-    pub fn eval(&mut self, source: &str) -> Result<JSValue, JSValue> {
+    pub fn eval(&mut self, source: &str) -> Result<JSValue, EngineError> {
         // Compile the source code to bytecode
-        let bytecode = compiler::compile(source)
-            .map_err(|e| self.make_error(&alloc::format!("Compile error: {:?}", e)))?;
+        let bytecode = compiler::compile_bounded(source, self.context.max_source_bytes())?;
+
+        self.run_bytecode(&bytecode)
+    }
+
+    /// Run a compiled script
+    ///
+    /// Borrowed scripts run in place: their code is never copied into the
+    /// heap, so any number of engines can share one `'static` script. Owned
+    /// scripts are copied in, as `eval` does with freshly compiled code.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// static SCRIPT: &[u8] = include_bytes!("app.cqb");
+    ///
+    /// let mut engine = Engine::new(65536);
+    /// engine.run(&LoadedScript::borrowed(SCRIPT))?;
+    /// ```
+    pub fn run(&mut self, script: &LoadedScript<'static>) -> Result<JSValue, EngineError> {
+        match script.source() {
+            ScriptSource::Borrowed(bytes) => self.execute(FunctionCode::Static(bytes)),
+            ScriptSource::Owned(bytes) => self.run_bytecode(bytes),
+        }
+    }
+
+    /// Run a compiled script and get result as string
+    ///
+    /// The `run` counterpart of `eval_as_string`.
+    pub fn run_as_string(&mut self, script: &LoadedScript<'static>) -> Result<String, String> {
+        match self.run(script) {
+            Ok(value) => Ok(self.value_to_string(value)),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    /// Execute JavaScript and get result as string
+    ///
+    /// This is a convenience method that calls `eval()` and converts the result
+    /// to a string representation.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - JavaScript source code to execute
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(String)` - String representation of the result
+    /// * `Err(String)` - The error's `Display` text
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// let result = engine.eval_as_string("2 + 3")?;
+    /// assert_eq!(result, "5");
+    /// ```
+    pub fn eval_as_string(&mut self, source: &str) -> Result<String, String> {
+        match self.eval(source) {
+            Ok(value) => Ok(self.value_to_string(value)),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    /// Execute JavaScript and collect everything a host reports about the run
+    ///
+    /// Console output is captured rather than printed, and the previous
+    /// capture is restored afterwards, whether or not the script failed, so
+    /// a nested `eval_captured` reports only its own lines. Detach
+    /// `EvalReport::value` once it's no longer needed.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// let report = engine.eval_captured("console.log('hi'); ({ ok: true })");
+    /// assert_eq!(report.output, ["hi"]);
+    /// assert_eq!(report.json.as_deref(), Ok(r#"{"ok":true}"#));
+    /// ```
+    pub fn eval_captured(&mut self, source: &str) -> EvalReport {
+        let before = self.memory_stats();
+        let instructions = self.vm.instructions_executed();
+        let outer = self.context.replace_console_capture(Some(Vec::new()));
+        let outer_peak = self.context.replace_prototype_peak(0);
+
+        let result = self.eval(source);
+
+        let output = self.context.replace_console_capture(outer).unwrap_or_default();
+        let prototype_depth = self.context.replace_prototype_peak(0);
+        self.context.replace_prototype_peak(outer_peak.max(prototype_depth));
+        let (value, json, error) = match result {
+            Ok(value) => {
+                let handle = self.persist(value);
+                (Some(handle), self.result_json(value), None)
+            }
+            Err(err) => (None, Err(String::from("no result: the script failed")), Some(self.error_report(&err))),
+        };
+
+        let after = self.memory_stats();
+        let metrics = EvalMetrics {
+            instructions: self.vm.instructions_executed() - instructions,
+            gc_count: after.gc_count - before.gc_count,
+            heap_used_before: before.heap_used,
+            heap_used_after: after.heap_used,
+            prototype_depth,
+        };
+
+        EvalReport { value, json, output, error, metrics }
+    }
+
+    /// Renders a result for `EvalReport::json`
+    fn result_json(&mut self, value: JSValue) -> Result<String, String> {
+        #[cfg(feature = "builtin-json")]
+        {
+            match crate::builtins::json::stringify(&mut self.context, value) {
+                Ok(Some(json)) => Ok(json),
+                Ok(None) => Err(alloc::format!("{} has no JSON form", self.value_to_string(value))),
+                Err(thrown) => Err(display_value(&self.context, thrown)),
+            }
+        }
+
+        #[cfg(not(feature = "builtin-json"))]
+        {
+            let _ = value;
+            Err(String::from("JSON support is not enabled"))
+        }
+    }
+
+    /// Breaks an error out for `EvalReport::error`
+    fn error_report(&self, err: &EngineError) -> ErrorReport {
+        let (line, column) = match err {
+            EngineError::Parse { line, column, .. } => (*line, *column),
+            EngineError::Runtime { line, .. } => (*line, 0),
+            EngineError::OutOfMemory | EngineError::Interrupted | EngineError::Reentrancy(_) => (0, 0),
+        };
+        let stack = err
+            .value()
+            .and_then(|value| self.context.get_property(value, crate::value::atoms::STACK))
+            .and_then(|stack| self.context.get_string(stack).map(String::from));
+
+        ErrorReport { kind: self.error_kind(err), message: err.to_string(), stack, line, column }
+    }
+
+    /// Get the category of an error returned by `eval` or `call_function`
+    ///
+    /// Compile errors are `Syntax`. Returns `None` for thrown values that
+    /// aren't errors created by the engine or by one of the builtin error
+    /// constructors.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// let err = engine.eval("function f() { return f(); } f()").unwrap_err();
+    /// assert_eq!(engine.error_kind(&err), Some(ErrorKind::StackOverflow));
+    /// ```
+    pub fn error_kind(&self, error: &EngineError) -> Option<ErrorKind> {
+        match error {
+            EngineError::Parse { .. } => Some(ErrorKind::Syntax),
+            EngineError::Runtime { value, .. } => self.context.error_kind(*value),
+            EngineError::OutOfMemory => Some(ErrorKind::OutOfMemory),
+            EngineError::Interrupted => Some(ErrorKind::Interrupted),
+            EngineError::Reentrancy(_) => Some(ErrorKind::Internal),
+        }
+    }
+
+    /// Install the `exit(code)` global
+    ///
+    /// Calling `exit` ends the script with an uncatchable `ExitRequested`
+    /// error; the engine never terminates the process itself. Hosts that
+    /// want script-chosen exit statuses read it back with `exit_code`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// engine.enable_exit()?;
+    /// let err = engine.eval("try { exit(3) } catch (e) {}").unwrap_err();
+    /// assert_eq!(engine.exit_code(&err), Some(3));
+    /// ```
+    pub fn enable_exit(&mut self) -> Result<(), String> {
+        self.register_function("exit", crate::builtins::native_functions::exit_native)
+    }
+
+    /// Install the `test`, `assertEquals` and `assertThrows` globals
+    ///
+    /// For self-test scripts: `test(name, fn)` runs `fn` straight away and
+    /// records whether it threw, for the host to read back with
+    /// `test_results`. Needs the `test-api` feature, which is off by
+    /// default so production builds don't carry it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// engine.enable_test_api()?;
+    /// engine.eval("test('adds', function () { assertEquals(1 + 1, 2); })")?;
+    /// assert!(engine.test_results().iter().all(|result| result.passed));
+    /// ```
+    #[cfg(feature = "test-api")]
+    pub fn enable_test_api(&mut self) -> Result<(), String> {
+        use crate::builtins::test_api;
+
+        self.register_function("test", test_api::test_native)?;
+        self.register_function("assertEquals", test_api::assert_equals_native)?;
+        self.register_function("assertThrows", test_api::assert_throws_native)
+    }
+
+    /// Get the outcome of every `test(name, fn)` call so far, in order
+    #[cfg(feature = "test-api")]
+    pub fn test_results(&self) -> Vec<crate::builtins::test_api::TestResult> {
+        self.context.test_results().to_vec()
+    }
+
+    /// Get the status passed to `exit()` from an error returned by `eval`
+    ///
+    /// Returns `None` for any other error.
+    pub fn exit_code(&self, error: &EngineError) -> Option<i32> {
+        self.context.exit_code(error.value()?)
+    }
+
+    /// Get a global variable by name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the global variable
+    ///
+    /// # Returns
+    ///
+    /// The value of the global variable, or None if not found
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// engine.eval("var answer = 42")?;
+    /// assert_eq!(engine.get_global("answer").unwrap().to_int(), Some(42));
+    /// ```
+    pub fn get_global(&self, name: &str) -> Option<JSValue> {
+        // A name that was never interned can't be a property
+        let key = self.context.lookup_atom(name)?;
+        self.context.get_global_property(key)
+    }
+
+    /// Set a global variable
+    ///
+    /// Creates the variable if it doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the global variable
+    /// * `value` - Value to set
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Success
+    /// * `Err(String)` - Error message
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// engine.set_global("x", JSValue::from_int(42))?;
+    /// assert_eq!(engine.eval_as_string("x + 1")?, "43");
+    /// ```
+    pub fn set_global(&mut self, name: &str, value: JSValue) -> Result<(), String> {
+        let key = self.context.intern_atom(name);
+        self.context
+            .set_global_property(key, value)
+            .map_err(|_| "Out of memory".to_string())
+    }
+
+    /// Call a JavaScript function
+    ///
+    /// # Arguments
+    ///
+    /// * `func` - The function to call
+    /// * `args` - Arguments to pass to the function
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(JSValue)` - The return value
+    /// * `Err(JSValue)` - An exception value
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// engine.eval("function add(a, b) { return a + b; }")?;
+    /// let func = engine.get_global("add").unwrap();
+    /// let result = engine.call_function(func, &[JSValue::from_int(2), JSValue::from_int(3)])?;
+    /// ```
+    pub fn call_function(&mut self, func: JSValue, args: &[JSValue]) -> Result<JSValue, EngineError> {
+        self.vm.call_function(&mut self.context, func, JSValue::undefined(), args)
+            .map_err(|err| EngineError::from_exception(&self.context, err))
+    }
+
+    /// Register a native Rust function as a global JavaScript function
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Global name the function is installed under
+    /// * `func` - Rust function called with the `this` value and arguments
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - Success
+    /// * `Err(String)` - Error message
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// fn answer(_ctx: &mut Context, _this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+    ///     Ok(JSValue::from_int(42))
+    /// }
+    ///
+    /// let mut engine = Engine::new(65536);
+    /// engine.register_function("answer", answer)?;
+    /// let result = engine.eval("answer()")?;
+    /// ```
+    pub fn register_function(&mut self, name: &str, func: NativeFn) -> Result<(), String> {
+        self.context
+            .register_function(name, func)
+            .map(|_| ())
+            .map_err(|_| "Out of memory".to_string())
+    }
+
+    /// Register a global error class extending a builtin error type
+    ///
+    /// Scripts can tell its errors apart with `instanceof`, and natives
+    /// throw them with `Context::throw_custom_error`; see
+    /// `Context::register_error_class`. Returns the constructor.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// fn send(ctx: &mut Context, _this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+    ///     let class = ctx.intern_atom("DeviceBusyError");
+    ///     let class = ctx.get_global_property(class).unwrap_or(JSValue::undefined());
+    ///     Err(ctx.throw_custom_error(class, "device is busy"))
+    /// }
+    ///
+    /// let mut engine = Engine::new(65536);
+    /// engine.register_error_class("DeviceBusyError", ErrorType::Error)?;
+    /// engine.register_function("send", send)?;
+    /// engine.eval("try { send() } catch (e) { e instanceof DeviceBusyError }")?;
+    /// ```
+    pub fn register_error_class(&mut self, name: &str, parent: ErrorType) -> Result<JSValue, String> {
+        self.context
+            .register_error_class(name, parent)
+            .map_err(|_| "Out of memory".to_string())
+    }
+
+    /// Limit the length of computed property names (`obj[key]`)
+    ///
+    /// Longer names throw a RangeError. Defaults to
+    /// `Context::DEFAULT_MAX_PROPERTY_NAME_LEN` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// engine.set_max_property_name_len(64);
+    /// assert!(engine.eval("var o = {}; o['x'.repeat(100)] = 1").is_err());
+    /// ```
+    pub fn set_max_property_name_len(&mut self, len: usize) {
+        self.context.set_max_property_name_len(len);
+    }
+
+    /// Limit the size of source `eval` accepts
+    ///
+    /// Longer sources fail with a `Parse` error before any of them is
+    /// lexed. Defaults to `Context::DEFAULT_MAX_SOURCE_BYTES` (1 MiB).
+    /// Scripts compiled ahead of time with `LoadedScript::compile` aren't
+    /// limited.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// engine.set_max_source_bytes(4096);
+    /// assert!(engine.eval(&" ".repeat(5000)).is_err());
+    /// ```
+    pub fn set_max_source_bytes(&mut self, len: usize) {
+        self.context.set_max_source_bytes(len);
+    }
+
+    /// Limit how many instructions one `eval`, `run` or `call_function` may
+    /// execute
+    ///
+    /// Guards against runaway scripts such as `while (true) {}`. Past the
+    /// limit the script fails with a `Timeout` error that it can't catch,
+    /// and the engine is ready for the next call. Functions the script
+    /// calls, callbacks from builtins included, count against the same
+    /// budget. 0, the default, means no limit.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// engine.set_max_steps(10_000);
+    /// let err = engine.eval("while (true) {}").unwrap_err();
+    /// assert_eq!(engine.error_kind(&err), Some(ErrorKind::Timeout));
+    /// ```
+    pub fn set_max_steps(&mut self, steps: u64) {
+        self.context.set_max_steps(steps);
+    }
+
+    /// Set the function the engine polls while a script runs, or `None` to
+    /// stop polling
+    ///
+    /// For cancelling a script from elsewhere in the firmware, say on a
+    /// button press or a watchdog. When the handler returns true the script
+    /// stops with `EngineError::Interrupted`, which it can't catch, and the
+    /// engine is ready for the next call. The handler runs every
+    /// `set_interrupt_interval` instructions, so keep it cheap: reading a
+    /// flag an interrupt routine sets is typical.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// static CANCEL: AtomicBool = AtomicBool::new(false);
+    ///
+    /// let mut engine = Engine::new(65536);
+    /// engine.set_interrupt_handler(Some(|| CANCEL.load(Ordering::Relaxed)));
+    /// ```
+    pub fn set_interrupt_handler(&mut self, handler: Option<InterruptHandler>) {
+        self.context.set_interrupt_handler(handler);
+    }
+
+    /// Set the clock `Date.now()` reads, or `None` to have it throw
+    ///
+    /// Builds with the `std` feature start out with
+    /// `Context::system_clock`. Elsewhere, hand it a millisecond counter:
+    /// time since the epoch if the board has a real-time clock, since boot
+    /// otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// static TICKS: AtomicU64 = AtomicU64::new(0); // advanced by a timer interrupt
+    ///
+    /// engine.set_clock(Some(|| TICKS.load(Ordering::Relaxed)));
+    /// engine.eval("var start = Date.now()")?;
+    /// ```
+    pub fn set_clock(&mut self, clock: Option<Clock>) {
+        self.context.set_clock(clock);
+    }
+
+    /// Set how many instructions run between polls of the interrupt handler
+    ///
+    /// Lower values stop scripts sooner at the cost of slower dispatch.
+    /// Defaults to `Context::DEFAULT_INTERRUPT_INTERVAL`; values below 1
+    /// are raised to 1.
+    pub fn set_interrupt_interval(&mut self, instructions: u32) {
+        self.context.set_interrupt_interval(instructions);
+    }
+
+    /// Limit how many distinct property names the engine records
+    ///
+    /// Names are kept for the engine's lifetime. Defining a property under a
+    /// new computed name past the quota throws a RangeError; names already
+    /// seen keep working. Defaults to `Context::DEFAULT_MAX_PROPERTY_NAMES`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// engine.set_max_property_names(100);
+    /// ```
+    pub fn set_max_property_names(&mut self, count: usize) {
+        self.context.set_max_property_names(count);
+    }
+
+    /// Install a handler for unrecoverable internal errors
+    ///
+    /// Internal invariant violations that can't become JS exceptions (a
+    /// corrupted heap index, for instance) call the handler with the module,
+    /// a message and the last PC/opcode the VM executed, instead of going
+    /// straight to the panic handler. The handler is process-wide: it covers
+    /// every engine, and stays installed after this engine is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// fn on_fatal(info: &FatalInfo) -> ! {
+    ///     black_box_log(info.module, info.message, info.pc);
+    ///     reset_device()
+    /// }
+    ///
+    /// Engine::set_fatal_handler(on_fatal);
+    /// ```
+    pub fn set_fatal_handler(handler: crate::fatal::FatalHandler) {
+        crate::fatal::set_handler(Some(handler));
+    }
+
+    /// Remove the fatal handler, so internal errors panic again
+    pub fn clear_fatal_handler() {
+        crate::fatal::set_handler(None);
+    }
+
+    /// Run garbage collection
+    ///
+    /// This forces a garbage collection cycle, freeing memory used by
+    /// unreachable objects, and reports what it reclaimed.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// // ... do some work ...
+    /// let stats = engine.gc(); // Free unused memory
+    /// println!("freed {} bytes", stats.bytes_before - stats.bytes_after);
+    /// ```
+    pub fn gc(&mut self) -> GcStats {
+        // Natives only get the context, so the engine is never running here
+        self.context.gc().unwrap_or_default()
+    }
+
+    /// Collect garbage every `allocations` allocations while scripts run
+    ///
+    /// A stress-testing aid: collections normally wait until half the free
+    /// heap is used, so values the VM fails to root can survive for a long
+    /// time before being freed under it. Pass 0 to go back to normal.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// engine.set_gc_stress_interval(1);
+    /// engine.eval(script)?;
+    /// ```
+    pub fn set_gc_stress_interval(&mut self, allocations: usize) {
+        self.context.set_gc_stress_interval(allocations);
+    }
+
+    /// Limit how deeply builtins recurse into nested arrays and objects
+    ///
+    /// Walkers such as `JSON.parse` and `JSON.stringify` recurse on the
+    /// native stack; past this depth they throw a catchable RangeError
+    /// ("Maximum nesting depth exceeded") instead. The default,
+    /// `DEFAULT_MAX_NESTING_DEPTH`, suits small embedded stacks; hosts with
+    /// more stack can raise it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// engine.set_max_nesting_depth(16);
+    /// assert!(engine.eval("JSON.parse('[[[[[[[[[[[[[[[[[[]]]]]]]]]]]]]]]]]')").is_err());
+    /// ```
+    pub fn set_max_nesting_depth(&mut self, depth: usize) {
+        self.context.set_max_nesting_depth(depth);
+    }
+
+    /// Limit how long a prototype chain scripts may build
+    ///
+    /// `Object.create` and `Object.setPrototypeOf` throw a catchable
+    /// `RangeError` ("Maximum prototype chain length exceeded") rather than
+    /// make a chain with more links, so a missed property lookup stays
+    /// cheap. The default is `Context::DEFAULT_MAX_PROTOTYPE_DEPTH`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut engine = Engine::new(65536);
+    /// engine.set_max_prototype_depth(4);
+    /// assert!(engine.eval("var o = {}; for (var i = 0; i < 4; i++) o = Object.create(o)").is_err());
+    /// ```
+    pub fn set_max_prototype_depth(&mut self, depth: usize) {
+        self.context.set_max_prototype_depth(depth);
+    }
+
+    /// Keep a value alive between calls
+    ///
+    /// The handle doesn't borrow the engine, so host subsystems such as
+    /// timer queues can store it. Read it back with
+    /// [`persistent_value`](Self::persistent_value).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let callback = engine.eval("(function () { return 1 })")?;
+    /// let handle = engine.persist(callback);
+    /// // ... later ...
+    /// if let Some(callback) = engine.persistent_value(&handle) {
+    ///     engine.call_function(callback, &[])?;
+    /// }
+    /// ```
+    pub fn persist(&mut self, value: JSValue) -> Persistent {
+        self.context.persist(value)
+    }
+
+    /// Get the value behind a handle
+    ///
+    /// Returns `None` once the handle has been detached or
+    /// [`bump_epoch`](Self::bump_epoch) has invalidated it.
+    pub fn persistent_value(&self, handle: &Persistent) -> Option<JSValue> {
+        self.context.persistent_value(handle)
+    }
+
+    /// Release the value behind a handle and leave the handle empty
+    pub fn detach(&mut self, handle: &mut Persistent) {
+        self.context.detach(handle);
+    }
+
+    /// Invalidate every outstanding handle
+    ///
+    /// Call this when tearing down a tenant's script state: handles still
+    /// held elsewhere read as `None` from then on, and their values can be
+    /// collected. Returns the new epoch.
+    pub fn bump_epoch(&mut self) -> u32 {
+        self.context.bump_epoch()
+    }
+
+    /// Get memory statistics
+    ///
+    /// Returns information about heap usage and object allocation.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let engine = Engine::new(65536);
+    /// let stats = engine.memory_stats();
+    /// println!("Heap usage: {} / {}", stats.heap_used, stats.heap_size);
+    /// ```
+    pub fn memory_stats(&self) -> MemoryStats {
+        self.context.memory_stats()
+    }
+
+    /// Counts the heap's blocks by memory tag
+    ///
+    /// See [`Context::heap_census`]; collect first to count only live
+    /// blocks.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// engine.gc();
+    /// for (tag, entry) in engine.heap_census() {
+    ///     println!("{:?}: {} blocks, {} bytes", tag, entry.count, entry.bytes);
+    /// }
+    /// ```
+    pub fn heap_census(&self) -> Vec<(crate::memory::MemTag, crate::memory::CensusEntry)> {
+        self.context.heap_census()
+    }
+
+    /// Counts the heap's blocks with one tag by what allocated them
+    ///
+    /// See [`Context::heap_census_by_origin`].
+    #[cfg(feature = "alloc-origin")]
+    pub fn heap_census_by_origin(&self, mtag: crate::memory::MemTag) -> Vec<(crate::memory::AllocOrigin, crate::memory::CensusEntry)> {
+        self.context.heap_census_by_origin(mtag)
+    }
+
+    /// Describes an allocation origin; see [`Context::alloc_origin_name`]
+    #[cfg(feature = "alloc-origin")]
+    pub fn alloc_origin_name(&self, origin: crate::memory::AllocOrigin) -> String {
+        self.context.alloc_origin_name(origin)
+    }
+
+    // ========== Helper Methods ==========
+
+    /// Copy bytecode into the heap and execute it
+    fn run_bytecode(&mut self, bytecode: &[u8]) -> Result<JSValue, EngineError> {
+        // Store bytecode in a byte array on the heap, low enough that it
+        // doesn't hold the heap up while it runs
+        self.context.compact_for_script();
+        let bytecode_index = self.context.store_bytecode(bytecode)
+            .map_err(|_| EngineError::OutOfMemory)?;
+
+        // Execute the bytecode
+        self.execute(FunctionCode::Heap(bytecode_index))
+    }
+
+    /// Run top-level code on the engine's VM, starting from a clean state
+    fn execute(&mut self, code: FunctionCode) -> Result<JSValue, EngineError> {
+        self.vm.reset();
+        self.vm.execute_code(&mut self.context, code)
+            .map_err(|err| EngineError::from_exception(&self.context, err))
+    }
+
+    /// Convert a value to the string `eval_as_string` would return for it
+    ///
+    /// Error objects render as `Name: message`; objects, arrays and functions
+    /// are formatted like console.log output, e.g. `{ a: 1, b: [ 2 ] }`.
+    pub fn value_to_string(&self, value: JSValue) -> String {
+        display_value(&self.context, value)
+    }
+
+}
+
+/// Renders a value the way `Engine::value_to_string` does
+fn display_value(ctx: &Context, value: JSValue) -> String {
+    // Thrown values with `name` and `message` render as "Name: message",
+    // whether or not they inherit from Error.prototype
+    if let Some(message) = error_message(ctx, value) {
+        return message;
+    }
+    crate::runtime::display::format_value(ctx, value)
+}
+
+/// Formats an error object as `Name: message`
+///
+/// Returns None unless the value has both `name` and `message` string properties.
+fn error_message(ctx: &Context, value: JSValue) -> Option<String> {
+    let name = ctx.get_property(value, crate::value::atoms::NAME)?;
+    let message = ctx.get_property(value, crate::value::atoms::MESSAGE)?;
+    let name = ctx.get_string(name)?;
+    let message = ctx.get_string(message)?;
+    Some(alloc::format!("{}: {}", name, message))
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new(65536) // 64 KB default heap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_engine_new() {
+        let engine = Engine::new(131072);
+        let stats = engine.memory_stats();
+        assert_eq!(stats.heap_size, 131072);
+
+        // Too-small and unaligned sizes are coerced
+        assert_eq!(Engine::new(0).memory_stats().heap_size, Engine::MIN_HEAP_SIZE);
+        assert_eq!(Engine::new(131075).memory_stats().heap_size, 131072);
+    }
+
+    #[test]
+    fn test_try_new_heap_sizes() {
+        let err = Engine::try_new(0).err().unwrap();
+        assert_eq!(err, HeapSizeError { requested: 0, minimum: Engine::MIN_HEAP_SIZE });
+
+        let err = Engine::try_new(Engine::MIN_HEAP_SIZE - 1).err().unwrap();
+        assert_eq!(err.minimum, Engine::MIN_HEAP_SIZE);
+        assert_eq!(
+            err.to_string(),
+            alloc::format!("heap size of {} bytes is below the minimum of {} bytes", Engine::MIN_HEAP_SIZE - 1, Engine::MIN_HEAP_SIZE)
+        );
+
+        let engine = Engine::try_new(Engine::MIN_HEAP_SIZE).ok().unwrap();
+        assert_eq!(engine.memory_stats().heap_size, Engine::MIN_HEAP_SIZE);
+        assert!(engine.memory_stats().heap_used <= Engine::MIN_HEAP_SIZE);
+
+        let mut engine = Engine::try_new(1 << 20).ok().unwrap();
+        assert_eq!(engine.eval_as_string("'ok'").unwrap(), "ok");
+    }
+
+    #[test]
+    fn test_engine_default() {
+        let engine = Engine::default();
+        let stats = engine.memory_stats();
+        assert_eq!(stats.heap_size, 65536);
+    }
+
+    #[test]
+    fn test_memory_stats() {
+        let mut engine = Engine::new(65536);
+        let stats = engine.memory_stats();
+        assert_eq!(stats.heap_size, 65536);
+        assert!(stats.heap_used <= stats.heap_size);
+    }
+
+    #[test]
+    fn test_gc() {
+        let mut engine = Engine::new(65536);
+        // Just ensure GC doesn't crash
+        engine.gc();
+    }
+
+    #[test]
+    fn test_eval_returns_expression_value() {
+        let mut engine = Engine::new(65536);
+
+        // Test simple arithmetic - should return 4, not undefined
+        let result = engine.eval("2 + 2").unwrap();
+        assert_eq!(result.to_int(), Some(4));
+
+        // Test more complex expression
+        let result = engine.eval("10 * 5 + 3").unwrap();
+        assert_eq!(result.to_int(), Some(53));
+
+        // Test boolean expression
+        let result = engine.eval("true").unwrap();
+        assert_eq!(result.to_bool(), Some(true));
+
+        // Test string result as string
+        let result = engine.eval_as_string("42").unwrap();
+        assert_eq!(result, "42");
+    }
+
+    #[test]
+    fn test_eval_state_persists_across_calls() {
+        let mut engine = Engine::new(65536);
+
+        // Globals defined by one eval must be visible to the next (REPL usage)
+        engine.eval("var x = 5").unwrap();
+        assert_eq!(engine.eval_as_string("x + 1").unwrap(), "6");
+
+        engine.eval("function double(n) { return n * 2; }").unwrap();
+        assert_eq!(engine.eval_as_string("double(x)").unwrap(), "10");
+
+        // An error in one eval doesn't discard existing globals
+        assert!(engine.eval("undefinedFunction()").is_err());
+        assert_eq!(engine.eval_as_string("x").unwrap(), "5");
+    }
+
+    #[test]
+    fn test_error_kind_of_engine_errors() {
+        let mut engine = Engine::new(65536);
+
+        let err = engine.eval("var = ;").unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Syntax));
+
+        let err = engine.eval("var n = 1; n()").unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Type));
+        assert_eq!(err.to_string(), "TypeError: Not a function at line 1");
+
+        let err = engine.eval("function f() { return f(); } f()").unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::StackOverflow));
+
+        // Thrown values that aren't engine errors have no kind
+        let err = engine.eval("throw 42").unwrap_err();
+        assert_eq!(engine.error_kind(&err), None);
+    }
+
+    #[test]
+    fn test_engine_error_parse_location() {
+        let mut engine = Engine::new(65536);
+
+        let err = engine.eval("var a = 1;\nvar b = 2;\nvar c = (1 +;").unwrap_err();
+        match &err {
+            EngineError::Parse { line, column, .. } => assert_eq!((*line, *column), (3, 13)),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert_eq!(err.value(), None);
+        assert!(err.to_string().starts_with("SyntaxError: "));
+        assert!(err.to_string().ends_with(" at line 3, column 13"));
+    }
+
+    #[test]
+    fn test_engine_error_keeps_thrown_value() {
+        let mut engine = Engine::new(65536);
+
+        let err = engine.eval("throw { code: 7 }").unwrap_err();
+        let value = err.value().unwrap();
+        let atom = engine.context.intern_atom("code");
+        let code = engine.context.get_property(value, atom).unwrap();
+        assert_eq!(code.to_int(), Some(7));
+
+        let err = engine.eval("throw new RangeError('too big')").unwrap_err();
+        assert_eq!(err.to_string(), "RangeError: too big at line 1");
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Range));
+    }
+
+    #[test]
+    fn test_engine_error_from_vm_errors() {
+        let mut engine = Engine::new(65536);
+
+        let err = engine.eval("var o; o.x").unwrap_err();
+        assert_eq!(err.to_string(), "TypeError: cannot read property of undefined at line 1");
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Type));
+
+        let err = engine.eval("missing + 1").unwrap_err();
+        assert_eq!(err.to_string(), "ReferenceError: missing is not defined at line 1");
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Reference));
+    }
+
+    #[test]
+    fn test_fatal_handler_receives_location() {
+        use crate::fatal::FatalInfo;
+        use std::sync::Mutex;
+
+        static SEEN: Mutex<Option<FatalInfo>> = Mutex::new(None);
+
+        fn record(info: &FatalInfo) -> ! {
+            *SEEN.lock().unwrap() = Some(*info);
+            panic!("fatal: {}", info.message);
+        }
+
+        // Planted internal assertion: reads through a heap index that was never allocated
+        fn corrupt(ctx: &mut Context, _this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+            let forged = JSValue::from_ptr(crate::memory::HeapIndex(0xFFFF));
+            Ok(JSValue::bool(ctx.get_string(forged).is_some()))
+        }
+
+        let mut engine = Engine::new(65536);
+        engine.register_function("corrupt", corrupt).unwrap();
+
+        Engine::set_fatal_handler(record);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| engine.eval("var a = 1; corrupt()")));
+        Engine::clear_fatal_handler();
+
+        assert!(result.is_err());
+        let info = SEEN.lock().unwrap().take().expect("fatal handler was not called");
+        assert_eq!(info.module, "memory");
+        assert_eq!(info.message, "Invalid HeapIndex in get_header");
+        assert_eq!(info.opcode, Some(crate::bytecode::Opcode::Call));
+        assert!(info.pc.is_some());
+    }
+
+    #[test]
+    fn test_engine_error_out_of_memory() {
+        let mut engine = Engine::new(65536);
+
+        let err = engine.eval("var a = []; for (var i = 0; i < 100000; i = i + 1) { a[i] = { x: i }; }").unwrap_err();
+        assert_eq!(err, EngineError::OutOfMemory);
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::OutOfMemory));
+        assert_eq!(engine.exit_code(&err), None);
+    }
+
+    #[test]
+    fn test_out_of_memory_inside_builtins() {
+        let mut engine = Engine::new(65536);
+        let err = engine.eval("var a = []; for (var i = 0; i < 100000; i++) a.push('s' + i);").unwrap_err();
+        assert_eq!(err, EngineError::OutOfMemory);
+
+        let mut engine = Engine::new(65536);
+        let err = engine.eval("var o = {}; for (var i = 0; i < 500; i++) o['k' + i] = i; var ks = []; for (;;) ks.push(Object.keys(o));").unwrap_err();
+        assert_eq!(err, EngineError::OutOfMemory);
+
+        // Scripts catch it as an error object, not the internal sentinel
+        let mut engine = Engine::new(65536);
+        let caught = engine.eval_as_string(
+            "var a = []; var r; try { for (var i = 0; i < 100000; i++) a.push('s' + i); } catch (e) { r = typeof e + ' ' + e.name; } a = null; r"
+        ).unwrap();
+        assert_eq!(caught, "object InternalError");
+    }
+
+    #[test]
+    fn test_errors_thrown_on_a_full_heap() {
+        // With no room left for a TypeError, the shared OutOfMemory error is
+        // thrown in its place
+        let mut engine = Engine::new(65536);
+        let err = engine.eval("var a = []; try { for (;;) a.push([1, 2, 3]); } catch (e) {} null.x").unwrap_err();
+        assert_eq!(err, EngineError::OutOfMemory);
+    }
+
+    #[test]
+    fn test_bitwise_results_out_of_memory() {
+        let mut engine = Engine::new(65536);
+        engine.register_function("fillHeap", native_fill_heap).unwrap();
+
+        // m >>> 0 is past the inline int range, so the result needs a box.
+        // Inside a builtin's callback the heap can't be collected to retry
+        let err = engine.eval("var m = -1; [1].map(function () { fillHeap(); return m >>> 0; })").unwrap_err();
+        assert_eq!(err, EngineError::OutOfMemory);
+    }
+
+    #[test]
+    fn test_error_kind_of_builtin_constructors() {
+        let mut engine = Engine::new(65536);
+
+        for (source, kind) in [
+            ("TypeError('t')", Some(ErrorKind::Type)),
+            ("RangeError('r')", Some(ErrorKind::Range)),
+            ("ReferenceError('r')", Some(ErrorKind::Reference)),
+            ("SyntaxError('s')", Some(ErrorKind::Syntax)),
+            ("EvalError('e')", Some(ErrorKind::Eval)),
+            ("Error('e')", None),
+        ] {
+            let value = engine.eval(source).unwrap();
+            assert_eq!(engine.context.error_kind(value), kind, "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_error_kind_not_forgeable() {
+        let mut engine = Engine::new(65536);
+
+        let result = engine.eval_as_string("typeof new TypeError('x').__errorKind__").unwrap();
+        assert_eq!(result, "undefined");
+
+        let value = engine.eval("({__errorKind__: 6})").unwrap();
+        assert_eq!(engine.context.error_kind(value), None);
+
+        let err = engine.eval("throw {__errorKind__: 6}").unwrap_err();
+        assert!(matches!(err, EngineError::Runtime { .. }), "{:?}", err);
+        assert_eq!(engine.error_kind(&err), None);
+    }
+
+    #[test]
+    fn test_error_kind_visible_to_script() {
+        let mut engine = Engine::new(65536);
+
+        let result = engine.eval_as_string(
+            "var n; try { var x = 1; x(); } catch (e) { n = e.name + ': ' + e.message; } n"
+        ).unwrap();
+        assert_eq!(result, "TypeError: Not a function");
+
+        let result = engine.eval_as_string(
+            "var m; function deep() { return deep(); } try { deep(); } catch (e) { m = e.name; } m"
+        ).unwrap();
+        assert_eq!(result, ErrorKind::StackOverflow.name());
+    }
+
+    #[test]
+    #[cfg(not(feature = "builtin-math"))]
+    fn test_compiled_out_builtin_is_reference_error() {
+        let mut engine = Engine::new(65536);
+
+        let err = engine.eval("Math.abs(-1)").unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Reference));
+
+        let result = engine.eval_as_string(
+            "var m; try { Math; } catch (e) { m = e.name + ': ' + e.message; } m"
+        ).unwrap();
+        assert_eq!(result, "ReferenceError: Math is not defined (built without the `builtin-math` feature)");
+    }
+
+    #[test]
+    fn test_try_catch_finally() {
+        let mut engine = Engine::new(131072);
+
+        assert_eq!(engine.eval_as_string("var r; try { throw 42 } catch (e) { r = e } r").unwrap(), "42");
+
+        // Rethrow from a catch block reaches the outer handler
+        let result = engine.eval_as_string(
+            "var m; try { try { throw 1; } catch (e) { throw e + 1; } } catch (e) { m = e; } m"
+        ).unwrap();
+        assert_eq!(result, "2");
+
+        // The inner handler shadows the outer one, which is back in place afterwards
+        let result = engine.eval_as_string(
+            "var o = ''; try { try { throw 'x'; } catch (e) { o = o + 'in' + e; } throw 'y'; } \
+             catch (e) { o = o + 'out' + e; } o"
+        ).unwrap();
+        assert_eq!(result, "inxouty");
+
+        // Finally runs on normal, exceptional and catch-rethrow paths
+        let result = engine.eval_as_string(
+            "var s = ''; try { s = s + 't'; } finally { s = s + 'f'; } \
+             try { try { throw 3; } finally { s = s + 'f'; } } catch (e) { s = s + e; } \
+             try { try { throw 1; } catch (e) { throw 2; } finally { s = s + 'f'; } } catch (e) { s = s + e; } s"
+        ).unwrap();
+        assert_eq!(result, "tff3f2");
+
+        // A return inside try still runs the finally block
+        let result = engine.eval_as_string(
+            "var log = ''; function f() { try { return 'a'; } finally { log = log + 'f'; } } f() + log"
+        ).unwrap();
+        assert_eq!(result, "af");
+
+        // break leaves the try block through its finally
+        let result = engine.eval_as_string(
+            "function count() { var n = 0; for (var i = 0; i < 5; i++) { try { if (i == 2) break; } finally { n++; } } return n; } count()"
+        ).unwrap();
+        assert_eq!(result, "3");
+
+        // Exceptions mid-expression unwind the operand stack
+        let result = engine.eval_as_string(
+            "function boom() { throw 5; } function g() { var k = 1; try { return k + boom(); } catch (x) { return x * 10 + k; } } g()"
+        ).unwrap();
+        assert_eq!(result, "51");
+
+        // The catch parameter is scoped to the catch clause
+        assert_eq!(engine.eval_as_string("var e = 'outer'; try { throw 'inner'; } catch (e) {} e").unwrap(), "outer");
+    }
+
+    #[test]
+    #[cfg(all(feature = "builtin-json", feature = "builtin-console"))]
+    fn test_special_number_strings_agree() {
+        // (JS source, string/key form, JSON form)
+        let cases = [
+            ("Infinity", "Infinity", "null"),
+            ("-Infinity", "-Infinity", "null"),
+            ("NaN", "NaN", "null"),
+            ("-0", "0", "0"),
+        ];
+
+        for (src, string, json) in cases {
+            let mut engine = Engine::new(131072);
+            let as_string = engine.eval_as_string(&alloc::format!("'' + ({})", src)).unwrap();
+            assert_eq!(as_string, string, "ToString({})", src);
+            let called = engine.eval_as_string(&alloc::format!("String({})", src)).unwrap();
+            assert_eq!(called, string, "String({})", src);
+            let template = engine.eval_as_string(&alloc::format!("`${{{}}}`", src)).unwrap();
+            assert_eq!(template, string, "template {}", src);
+
+            let key = engine.eval_as_string(
+                &alloc::format!("var o = {{}}; o[{}] = 1; var k; for (var p in o) k = p; k", src)
+            ).unwrap();
+            assert_eq!(key, string, "property key {}", src);
+
+            let stringified = engine.eval_as_string(&alloc::format!("JSON.stringify({})", src)).unwrap();
+            assert_eq!(stringified, json, "JSON.stringify({})", src);
+            let element = engine.eval_as_string(&alloc::format!("JSON.stringify([{}])", src)).unwrap();
+            assert_eq!(element, alloc::format!("[{}]", json), "JSON.stringify([{}])", src);
+        }
+
+        // Console formatting, with the values built directly so -0 is a real float
+        let mut engine = Engine::new(65536);
+        for (n, string) in [(f64::INFINITY, "Infinity"), (f64::NEG_INFINITY, "-Infinity"), (f64::NAN, "NaN"), (-0.0, "0")] {
+            let value = engine.context.new_number(n).unwrap();
+            assert_eq!(crate::builtins::console::format_values(&engine.context, &[value]), string);
+            assert_eq!(crate::runtime::conversion::to_string(&engine.context, value), string);
+        }
+    }
+
+    #[test]
+    fn test_exit() {
+        let mut engine = Engine::new(131072);
+        assert_eq!(engine.eval_as_string("typeof exit").unwrap(), "undefined");
+
+        engine.enable_exit().unwrap();
+        let err = engine.eval("exit(3); 1").unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::ExitRequested));
+        assert_eq!(engine.exit_code(&err), Some(3));
+
+        // Scripts can't swallow the request, even from a nested call
+        let err = engine.eval("function quit() { exit(4) } try { quit() } catch (e) { 5 }").unwrap_err();
+        assert_eq!(engine.exit_code(&err), Some(4));
+        let err = engine.eval("exit()").unwrap_err();
+        assert_eq!(engine.exit_code(&err), Some(0));
+
+        let err = engine.eval("throw new Error('boom')").unwrap_err();
+        assert_eq!(engine.exit_code(&err), None);
+    }
+
+    #[test]
+    fn test_max_steps() {
+        let mut engine = Engine::new(131072);
+        engine.set_max_steps(10_000);
+
+        let before = engine.vm.instructions_executed();
+        let err = engine.eval("var n = 0; while (true) { n++ }").unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Timeout));
+        assert_eq!(engine.vm.instructions_executed() - before, 10_001);
+        assert_eq!(engine.vm.stack_depth(), 0);
+
+        // The next run gets a fresh budget
+        assert_eq!(engine.eval_as_string("n > 0 && n < 10000").unwrap(), "true");
+
+        // Catching doesn't help, and functions and callbacks share the budget
+        for src in [
+            "try { while (true) {} } catch (e) { 'caught' }",
+            "function spin() { for (;;) {} } try { spin() } catch (e) { 'caught' }",
+            "var f = function () { while (true) {} }; [1].map(function (x) { try { f() } catch (e) {} return x })",
+        ] {
+            let err = engine.eval(src).unwrap_err();
+            assert_eq!(engine.error_kind(&err), Some(ErrorKind::Timeout), "{}", src);
+        }
+
+        // So do calls from the host
+        let forever = engine.eval("(function () { while (true) {} })").unwrap();
+        let err = engine.call_function(forever, &[]).unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Timeout));
+
+        engine.set_max_steps(0);
+        assert_eq!(engine.eval_as_string("var i = 0; while (i < 20000) { i++ } i").unwrap(), "20000");
+    }
+
+    #[test]
+    fn test_configured_stack_limits() {
+        let recurse = r#"
+            var d = 0;
+            function f() { d++; f(); }
+            var msg;
+            try { f(); } catch (e) { msg = e.name + ": " + e.message; }
+            msg + " at " + d + ", limit " + engine.limits.maxCallDepth
+        "#;
+        // The top-level script takes one frame
+        for depth in [25, 60] {
+            let mut engine = Engine::with_config(ContextConfig { call_depth: depth, ..ContextConfig::new(131072) });
+            let expected = alloc::format!("RangeError: Maximum call stack size exceeded at {}, limit {depth}", depth - 1);
+            assert_eq!(engine.eval_as_string(recurse).unwrap(), expected);
+            // The engine is usable afterwards
+            assert_eq!(engine.eval_as_string("(function () { return 7 })()").unwrap(), "7");
+        }
+
+        // Uncaught, the host sees it as a stack overflow
+        let mut engine = Engine::with_config(ContextConfig { call_depth: 10, ..ContextConfig::new(131072) });
+        let err = engine.eval("function g(n) { return n ? g(n - 1) : 0 } g(20)").unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::StackOverflow));
+        assert_eq!(engine.eval_as_string("g(5)").unwrap(), "0");
+
+        // Running out of value stack reports the same error
+        let mut engine = Engine::with_config(ContextConfig { value_stack_slots: 64, ..ContextConfig::new(131072) });
+        let src = "var m; function h(a, b, c, d) { return h(a, b, c, d) } try { h(1, 2, 3, 4) } catch (e) { m = e.message } m";
+        assert_eq!(engine.eval_as_string(src).unwrap(), "Maximum call stack size exceeded");
+
+        // Sizes beyond the VM's limits are capped
+        let engine = Engine::with_config(ContextConfig { value_stack_slots: usize::MAX, call_depth: usize::MAX, memory_bytes: 0, expected_globals: usize::MAX });
+        assert_eq!(engine.context.call_depth(), crate::vm::CallStack::MAX_CALL_DEPTH);
+        assert_eq!(engine.context.value_stack_slots(), crate::vm::ValueStack::MAX_SLOTS);
+        assert_eq!(engine.memory_stats().heap_size, Engine::MIN_HEAP_SIZE);
+        let config = ContextConfig::new(0);
+        assert!(config.stack_bytes() >= config.value_stack_slots * core::mem::size_of::<JSValue>());
+    }
+
+    #[test]
+    #[cfg(feature = "test-api")]
+    fn test_test_api() {
+        let mut engine = Engine::new(131072);
+        assert_eq!(engine.eval_as_string("typeof test").unwrap(), "undefined");
+
+        engine.enable_test_api().unwrap();
+        let code = r#"
+            var ran = test('passes', function () { assertEquals(0.1 * 3, 0.30000000000000004); assertEquals(NaN, NaN); });
+            test('fails', function () { assertEquals('1', 1, 'count'); });
+            test('throws', function () { null.x; });
+            test('typed throw', function () {
+                var e = assertThrows(function () { null.x; }, 'TypeError');
+                assertThrows(function () { throw new RangeError('r'); }, 'RangeError');
+                assertThrows(function () { assertThrows(function () {}); });
+                assertThrows(function () { assertThrows(function () { throw 1; }, 'TypeError'); });
+                assertEquals(e.name, 'TypeError');
+            });
+            test('signed zero', function () { assertEquals(-0, 0); });
+            ran
+        "#;
+        assert_eq!(engine.eval_as_string(code).unwrap(), "true");
+
+        let results = engine.test_results();
+        let outcomes: Vec<_> = results.iter().map(|result| (result.name.as_str(), result.passed)).collect();
+        assert_eq!(outcomes, [("passes", true), ("fails", false), ("throws", false), ("typed throw", true), ("signed zero", false)]);
+        assert_eq!(results[3].message, None);
+        assert_eq!(results[1].message.as_deref(), Some("Error: count: expected 1, got '1'"));
+        assert!(results[2].message.as_deref().unwrap().starts_with("TypeError: "));
+        assert_eq!(results[4].message.as_deref(), Some("Error: expected 0, got -0"));
+
+        // Exit requests aren't recorded as failures
+        engine.enable_exit().unwrap();
+        let err = engine.eval("test('quits', function () { exit(2); })").unwrap_err();
+        assert_eq!(engine.exit_code(&err), Some(2));
+        assert_eq!(engine.test_results().len(), 5);
+    }
+
+    #[test]
+    fn test_vm_state_does_not_leak_between_evals() {
+        let mut engine = Engine::new(131072);
+
+        // Fail halfway through evaluating an expression
+        assert!(engine.eval("var a = [1, 2]; 1 + (2 * missing())").is_err());
+        assert_eq!(engine.vm.stack_depth(), 0);
+        assert!(engine.eval("for (var k in { x: 1 }) { missing() }").is_err());
+        assert_eq!(engine.vm.stack_depth(), 0);
+
+        assert_eq!(engine.eval_as_string("let b = 5; b + 1").unwrap(), "6");
+        assert_eq!(engine.eval_as_string("a.map(function (x) { return x * 3 }).join()").unwrap(), "3,6");
+
+        // The VM cleans up after itself even without a reset
+        let bytecode = compiler::compile("let c = 7; missing(c)").unwrap();
+        let index = engine.context.store_bytecode(&bytecode).unwrap();
+        for _ in 0..2 {
+            assert!(engine.vm.execute(&mut engine.context, index).is_err());
+            assert_eq!(engine.vm.stack_depth(), 0);
+        }
+    }
+
+    #[test]
+    fn test_persistent_handles() {
+        let mut engine = Engine::new(131072);
+
+        let callback = engine.eval("var fired = 0; (function () { fired = fired + 1; return fired })").unwrap();
+        let mut timer = engine.persist(callback);
+        let cache = engine.persist(callback);
+
+        let callback = engine.persistent_value(&timer).unwrap();
+        assert_eq!(engine.call_function(callback, &[]).unwrap().to_int(), Some(1));
+
+        engine.detach(&mut timer);
+        assert!(timer.is_empty());
+        assert_eq!(engine.persistent_value(&timer), None);
+        assert!(engine.persistent_value(&cache).is_some());
+
+        // Tearing down the tenant drops the handles other code still holds
+        engine.bump_epoch();
+        assert!(!cache.is_empty());
+        assert_eq!(engine.persistent_value(&cache), None);
+        assert_eq!(engine.context.persistent_count(), 0);
+
+        // Handles made in the new epoch work as before
+        let value = engine.eval("fired").unwrap();
+        let handle = engine.persist(value);
+        assert_eq!(engine.persistent_value(&handle), Some(JSValue::from_int(1)));
+    }
+
+    #[test]
+    fn test_persistent_function_identity() {
+        use crate::runtime::compare::same_value;
+
+        let mut engine = Engine::new(131072);
+        let code = "var registry = {}; function onTick() { return 1 } registry.tick = onTick; onTick";
+        let callback = engine.eval(code).unwrap();
+        let handle = engine.persist(callback);
+
+        // The function the script registers later is the one the host holds,
+        // before and after a collection
+        for _ in 0..2 {
+            let held = engine.persistent_value(&handle).unwrap();
+            let registered = engine.eval("registry.tick").unwrap();
+            assert!(same_value(&engine.context, held, registered));
+            let copied = engine.eval("[registry.tick].slice()[0]").unwrap();
+            assert!(same_value(&engine.context, held, copied));
+            engine.gc();
+        }
+
+        // A fresh function from the same source is a different value
+        let other = engine.eval("(function onTick() { return 1 })").unwrap();
+        assert!(!same_value(&engine.context, engine.persistent_value(&handle).unwrap(), other));
+    }
+
+    #[test]
+    fn test_get_and_set_global() {
+        let mut engine = Engine::new(65536);
+        assert!(engine.get_global("missing").is_none());
+        assert!(engine.get_global("undefined").unwrap().is_undefined());
+
+        engine.set_global("x", JSValue::from_int(42)).unwrap();
+        assert_eq!(engine.eval_as_string("x + 1").unwrap(), "43");
+        engine.eval("x = 7").unwrap();
+        assert_eq!(engine.get_global("x").unwrap().to_int(), Some(7));
+
+        // A function defined by one eval can be looked up and called
+        engine.eval("function add(a, b) { return a + b; }").unwrap();
+        let add = engine.get_global("add").unwrap();
+        let result = engine.call_function(add, &[JSValue::from_int(2), JSValue::from_int(3)]).unwrap();
+        assert_eq!(result.to_int(), Some(5));
+    }
+
+    #[test]
+    fn test_call_function_runs_closures() {
+        let mut engine = Engine::new(131072);
+        let add = engine.eval("var base = 10; (function (a, b) { return base + a + b })").unwrap();
+        let result = engine.call_function(add, &[JSValue::from_int(2), JSValue::from_int(3)]).unwrap();
+        assert_eq!(result.to_int(), Some(15));
+        assert_eq!(engine.vm.stack_depth(), 0);
+    }
+
+    #[test]
+    fn test_property_writes_update_in_place() {
+        let mut engine = Engine::new(131072);
+        assert_eq!(engine.eval_as_string("var o = {}; o.x = 1; o.x = 2; o.x").unwrap(), "2");
+        let o = engine.eval("o").unwrap();
+        assert_eq!(engine.context.own_property_count(o), 1);
+
+        // Computed keys and globals reuse their entries too
+        engine.eval("o['x'] = 3; o.y = 4; o.y = 5").unwrap();
+        assert_eq!(engine.context.own_property_count(o), 2);
+        assert_eq!(engine.eval_as_string("o.x + o.y").unwrap(), "8");
+
+        let global = engine.context.global_object();
+        let before = engine.context.own_property_count(global);
+        engine.eval("var g = 1; g = 2; g = 3").unwrap();
+        assert_eq!(engine.context.own_property_count(global), before + 1);
+        assert_eq!(engine.eval_as_string("g").unwrap(), "3");
+    }
+
+    #[test]
+    #[cfg(feature = "builtin-math")]
+    fn test_math_methods() {
+        let cases = [
+            ("Math.max()", "-Infinity"),
+            ("Math.min()", "Infinity"),
+            ("Math.max(1, NaN, 3)", "NaN"),
+            ("Math.min(4, 2, 8)", "2"),
+            ("1 / Math.round(-0.5)", "-Infinity"),
+            ("Math.round(-2.5)", "-2"),
+            ("Math.round(2.5)", "3"),
+            ("Math.trunc(-4.7)", "-4"),
+            ("Math.sign(-3)", "-1"),
+            ("Math.atan2(1, 1) * 4 === Math.PI", "true"),
+            ("Math.exp(0) + Math.cos(0) + Math.sin(0)", "2"),
+            ("Math.log(Math.E)", "1"),
+            ("Math.pow(2, 10)", "1024"),
+            ("Math.abs(-7)", "7"),
+        ];
+        for (src, expected) in cases {
+            let mut engine = Engine::new(131072);
+            assert_eq!(engine.eval_as_string(src).unwrap(), expected, "{}", src);
+        }
+
+        let mut engine = Engine::new(131072);
+        let in_range = engine.eval_as_string(
+            "var ok = true; for (var i = 0; i < 20; i = i + 1) { var r = Math.random(); if (r < 0 || r >= 1) ok = false; } ok"
+        ).unwrap();
+        assert_eq!(in_range, "true");
+    }
+
+    #[test]
+    fn test_new_operator() {
+        let mut engine = Engine::new(131072);
+
+        let result = engine.eval_as_string(
+            "function Point(x, y) { this.x = x; this.y = y } var p = new Point(1, 2); p.x + p.y"
+        ).unwrap();
+        assert_eq!(result, "3");
+
+        // An explicitly returned object replaces the new one; primitives don't
+        assert_eq!(engine.eval_as_string("function F() { this.a = 1; return { a: 2 } } new F().a").unwrap(), "2");
+        assert_eq!(engine.eval_as_string("function G() { this.a = 1; return 5 } new G().a").unwrap(), "1");
+
+        // Native constructors build their own object
+        assert_eq!(engine.eval_as_string("new Error('boom').message").unwrap(), "boom");
+
+        // Arrow functions can't be constructed
+        let result = engine.eval_as_string(
+            "var A = () => 1; var n; try { new A() } catch (e) { n = e.name } n"
+        ).unwrap();
+        assert_eq!(result, "TypeError");
+    }
+
+    #[test]
+    fn test_error_kind_survives_rethrow() {
+        let mut engine = Engine::new(65536);
+
+        // Caught and rethrown in script, then surfaced to the host
+        let err = engine.eval("try { var x = 1; x(); } catch (e) { throw e; }").unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Type));
+
+        // Rethrown from a callee and caught by the caller
+        let result = engine.eval_as_string(
+            "function g() { try { var y = 1; y(); } catch (e) { throw e; } } \
+             var n; try { g(); } catch (e) { n = e.name; } n"
+        ).unwrap();
+        assert_eq!(result, "TypeError");
+    }
+
+    fn native_sum(_ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+        let sum = args.iter().filter_map(|arg| arg.to_int()).sum();
+        Ok(JSValue::from_int(sum))
+    }
+
+    fn native_arg_count(_ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+        Ok(JSValue::from_int(args.len() as i32))
+    }
+
+    fn native_second_arg(_ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+        Ok(args.get(1).copied().unwrap_or_else(JSValue::undefined))
+    }
+
+    /// Roots boxed numbers until the heap is full
+    fn native_fill_heap(ctx: &mut Context, _this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+        while let Ok(number) = ctx.new_number(0.5) {
+            ctx.add_root(number);
+        }
+        Ok(JSValue::undefined())
+    }
+
+    /// Reports a failed allocation the way natives do
+    fn native_alloc_failure(_ctx: &mut Context, _this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+        Err(JSValue::exception())
+    }
+
+    fn native_check_pin(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+        match args.first().and_then(|arg| arg.to_int()) {
+            Some(pin) if (0..16).contains(&pin) => Ok(JSValue::from_int(pin)),
+            _ => Err(ctx.new_error(ErrorKind::Range, "invalid pin")),
+        }
+    }
+
+    /// Throws a `DeviceBusyError` while the device is busy (argument 1), a
+    /// `TypeError` for a missing payload, and returns the payload otherwise
+    fn native_device_send(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+        match args.first().and_then(|arg| arg.to_int()) {
+            None => Err(ctx.new_error(ErrorKind::Type, "payload required")),
+            Some(1) => {
+                let class = ctx.intern_atom("DeviceBusyError");
+                let class = ctx.get_global_property(class).unwrap_or(JSValue::undefined());
+                Err(ctx.throw_custom_error(class, "device is busy"))
+            }
+            Some(payload) => Ok(JSValue::from_int(payload)),
+        }
+    }
+
+    /// Tries every operation a native may not run mid-script and lists the
+    /// refusals
+    fn native_reenter(ctx: &mut Context, _this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+        let mut refused = Vec::new();
+        for result in [ctx.eval("1", "inner.js", 0), ctx.eval_expr("1", "inner.js", 0)] {
+            if let Err(EngineError::Reentrancy(err)) = result {
+                refused.push(String::from(err.operation));
+            }
+        }
+        if let Err(err) = ctx.gc() {
+            refused.push(String::from(err.operation));
+        }
+        let code = ctx.store_bytecode(&compiler::compile("1").unwrap()).map_err(|_| JSValue::exception())?;
+        if let Err(err) = ctx.execute_bytecode(code) {
+            refused.push(display_value(ctx, err));
+        }
+        let text = refused.join(", ");
+        ctx.new_string(&text).map_err(|_| JSValue::exception())
+    }
 
-        // Store bytecode in a byte array on the heap
-        let bytecode_index = self.store_bytecode(&bytecode)
-            .map_err(|_| self.make_error("Out of memory storing bytecode"))?;
+    /// Builds `{ label }` and passes it to the callback in `args[0]`
+    fn native_wrap(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+        let callback = args.first().copied().unwrap_or_else(JSValue::undefined);
+        let obj = ctx.new_object().map_err(|_| JSValue::exception())?;
+        let label = ctx.new_string("wrapped").map_err(|_| JSValue::exception())?;
+        let key = ctx.intern_atom("label");
+        ctx.set_property(obj, key, label).map_err(|_| JSValue::exception())?;
+        let result = ctx.call_function(callback, JSValue::undefined(), &[obj])?;
+        let label = ctx.get_property(obj, key).unwrap_or_else(JSValue::undefined);
+        let text = alloc::format!("{}/{}", display_value(ctx, label), display_value(ctx, result));
+        ctx.new_string(&text).map_err(|_| JSValue::exception())
+    }
 
-        // Execute the bytecode
-        self.context.execute_bytecode(bytecode_index)
+    /// Returns a Uint8Array of fake sensor readings
+    fn native_read_sensor(ctx: &mut Context, _this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+        ctx.new_uint8array_from_slice(&[1, 2, 250]).map_err(|_| JSValue::exception())
     }
 
-    /// Execute JavaScript and get result as string
-    ///
-    /// This is a convenience method that calls `eval()` and converts the result
-    /// to a string representation.
-    ///
-    /// # Arguments
-    ///
-    /// * `source` - JavaScript source code to execute
-    ///
-    /// # Returns
-    ///
-    /// * `Ok(String)` - String representation of the result
-    /// * `Err(String)` - Error message
-    ///
-    /// # Example
-    ///
-    /// ```rust,ignore
-    /// let mut engine = Engine::new(65536);
-    /// let result = engine.eval_as_string("2 + 3")?;
-    /// assert_eq!(result, "5");
-    /// ```
-    pub fn eval_as_string(&mut self, source: &str) -> Result<String, String> {
-        match self.eval(source) {
-            Ok(value) => Ok(self.value_to_string(value)),
-            Err(err) => Err(self.value_to_string(err)),
-        }
+    /// Sums the bytes of the Uint8Array in `args[0]`
+    fn native_checksum(ctx: &mut Context, _this: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
+        let bytes = args.first().and_then(|&arg| ctx.uint8array_data(arg)).unwrap_or_default();
+        let sum: u32 = bytes.iter().map(|&b| u32::from(b)).sum();
+        Ok(JSValue::from_int(sum as i32))
     }
 
-    /// Get a global variable by name
-    ///
-    /// # Arguments
-    ///
-    /// * `name` - Name of the global variable
-    ///
-    /// # Returns
-    ///
-    /// The value of the global variable, or None if not found
-    ///
-    /// # Example
-    ///
-    /// ```rust,ignore
-    /// let engine = Engine::new(65536);
-    /// let undefined = engine.get_global("undefined");
-    /// ```
-    pub fn get_global(&self, _name: &str) -> Option<JSValue> {
-        // TODO: Implement global variable lookup
-        // This requires:
-        // 1. Getting the global object from context
-        // 2. Looking up the property by name
-        None
+    #[test]
+    fn test_uint8array_host_buffers() {
+        let mut engine = Engine::new(131072);
+        engine.register_function("readSensor", native_read_sensor).unwrap();
+        engine.register_function("checksum", native_checksum).unwrap();
+        engine.set_gc_stress_interval(1);
+
+        // The buffers survive collections that move them mid-run
+        let source = "var b = readSensor(); var junk = []; for (var i = 0; i < 50; i++) junk.push('x' + i); \
+                      b[0] = 300; var c = b.subarray(1); [checksum(b), checksum(c), checksum({}), b.length]";
+        assert_eq!(engine.eval_as_string(source).unwrap(), "[ 296, 252, 0, 3 ]");
     }
 
-    /// Set a global variable
-    ///
-    /// # Arguments
-    ///
-    /// * `name` - Name of the global variable
-    /// * `value` - Value to set
-    ///
-    /// # Returns
-    ///
-    /// * `Ok(())` - Success
-    /// * `Err(String)` - Error message
-    ///
-    /// # Example
-    ///
-    /// ```rust,ignore
-    /// let mut engine = Engine::new(65536);
-    /// engine.set_global("x", JSValue::from_int(42))?;
-    /// ```
-    pub fn set_global(&mut self, _name: &str, _value: JSValue) -> Result<(), String> {
-        // TODO: Implement global variable setting
-        // This requires:
-        // 1. Getting the global object from context
-        // 2. Setting the property by name
-        Ok(())
+    static INTERRUPT_POLLS: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+    /// Asks to stop on the fifth poll
+    fn interrupt_after_five_polls() -> bool {
+        INTERRUPT_POLLS.fetch_add(1, core::sync::atomic::Ordering::Relaxed) + 1 >= 5
     }
 
-    /// Call a JavaScript function
-    ///
-    /// # Arguments
-    ///
-    /// * `func` - The function to call
-    /// * `args` - Arguments to pass to the function
-    ///
-    /// # Returns
-    ///
-    /// * `Ok(JSValue)` - The return value
-    /// * `Err(JSValue)` - An exception value
-    ///
-    /// # Example
-    ///
-    /// ```rust,ignore
-    /// let mut engine = Engine::new(65536);
-    /// engine.eval("function add(a, b) { return a + b; }")?;
-    /// let func = engine.get_global("add").unwrap();
-    /// let result = engine.call_function(func, &[JSValue::from_int(2), JSValue::from_int(3)])?;
-    /// ```
-    pub fn call_function(&mut self, func: JSValue, args: &[JSValue]) -> Result<JSValue, JSValue> {
-        self.context.call_function(func, JSValue::undefined(), args)
+    #[test]
+    fn test_interrupt_handler() {
+        use core::sync::atomic::Ordering;
+
+        let mut engine = Engine::new(131072);
+        engine.set_interrupt_interval(100);
+        engine.set_interrupt_handler(Some(interrupt_after_five_polls));
+
+        let before = engine.vm.instructions_executed();
+        let err = engine.eval("var n = 0; function spin() { while (true) { try { n++ } catch (e) {} } } try { spin() } catch (e) {}").unwrap_err();
+        assert_eq!(err, EngineError::Interrupted);
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Interrupted));
+        assert_eq!(err.to_string(), "InternalError: Interrupted");
+        assert_eq!(INTERRUPT_POLLS.load(Ordering::Relaxed), 5);
+        assert!(engine.vm.instructions_executed() - before <= 500);
+        assert_eq!(engine.vm.stack_depth(), 0);
+
+        // The engine stays usable once the host stops asking
+        engine.set_interrupt_handler(None);
+        assert_eq!(engine.eval_as_string("n > 0").unwrap(), "true");
     }
 
-    /// Run garbage collection
-    ///
-    /// This forces a garbage collection cycle, freeing memory used by
-    /// unreachable objects.
-    ///
-    /// # Example
-    ///
-    /// ```rust,ignore
-    /// let mut engine = Engine::new(65536);
-    /// // ... do some work ...
-    /// engine.gc(); // Free unused memory
-    /// ```
-    pub fn gc(&mut self) {
-        self.context.gc();
+    /// Milliseconds since the epoch, in late 2023, well past an inline int
+    static FAKE_MS: core::sync::atomic::AtomicU64 = core::sync::atomic::AtomicU64::new(1_700_000_000_000);
+
+    /// A clock that advances 5 ms every time it is read
+    fn fake_clock() -> u64 {
+        FAKE_MS.fetch_add(5, core::sync::atomic::Ordering::Relaxed)
     }
 
-    /// Get memory statistics
-    ///
-    /// Returns information about heap usage and object allocation.
-    ///
-    /// # Example
-    ///
-    /// ```rust,ignore
-    /// let engine = Engine::new(65536);
-    /// let stats = engine.memory_stats();
-    /// println!("Heap usage: {} / {}", stats.heap_used, stats.heap_size);
-    /// ```
-    pub fn memory_stats(&self) -> MemoryStats {
-        MemoryStats {
-            heap_size: self.context.arena_size(),
-            heap_used: self.context.memory_usage(),
-            object_count: 0, // TODO: Track object count
+    #[test]
+    #[cfg(feature = "builtin-date")]
+    fn test_date_now_reads_the_clock() {
+        let mut engine = Engine::new(131072);
+        if cfg!(feature = "std") {
+            assert_eq!(engine.eval_as_string("Date.now() > 1.6e12").unwrap(), "true");
+        }
+
+        engine.set_clock(Some(fake_clock));
+        engine.eval("var first = Date.now()").unwrap();
+        assert_eq!(engine.eval_as_string("first").unwrap(), "1700000000000");
+        let code = "var t = [Date.now(), Date.now()]; [typeof t[0], t[0] > first, t[1] - t[0], t[1] % 1000].join()";
+        assert_eq!(engine.eval_as_string(code).unwrap(), "number,true,5,10");
+        // Later evals keep counting up
+        for _ in 0..3 {
+            assert_eq!(engine.eval_as_string("var next = Date.now(); var up = next > t[1]; t[1] = next; up").unwrap(), "true");
         }
+
+        // Without a clock the script gets an error it can catch
+        engine.set_clock(None);
+        let code = "var m; try { Date.now() } catch (e) { m = e.name + ': ' + e.message } m";
+        assert_eq!(engine.eval_as_string(code).unwrap(), "Error: Date.now() needs a clock, and the host hasn't set one");
     }
 
-    /// Get next random number (for Math.random implementation)
-    pub(crate) fn next_random(&mut self) -> f64 {
-        // Simple xorshift64 PRNG
-        self.random_state ^= self.random_state << 13;
-        self.random_state ^= self.random_state >> 7;
-        self.random_state ^= self.random_state << 17;
+    #[test]
+    fn test_reentrancy_is_refused() {
+        let mut engine = Engine::new(131072);
+        engine.register_function("reenter", native_reenter).unwrap();
+
+        assert_eq!(
+            engine.eval_as_string("reenter()").unwrap(),
+            "eval, eval_expr, gc, InternalError: execute_code can't be called while a script is running"
+        );
+        // Nothing was left half-run
+        assert!(!engine.context.is_running());
+        assert_eq!(engine.eval_as_string("1 + 1").unwrap(), "2");
+        assert!(engine.context.eval("2", "outer.js", 0).is_ok());
+        assert!(engine.context.gc().is_ok());
 
-        // Convert to [0, 1) range
-        (self.random_state as f64) / (u64::MAX as f64)
+        let err = EngineError::from(ReentrancyError { operation: "eval" });
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Internal));
+        assert_eq!(err.to_string(), "InternalError: eval can't be called while a script is running");
     }
 
-    // ========== Helper Methods ==========
+    #[test]
+    fn test_reentrant_natives_under_gc_stress() {
+        let mut engine = Engine::new(131072);
+        engine.register_function("wrap", native_wrap).unwrap();
+        engine.set_gc_stress_interval(1);
 
-    /// Store bytecode in a heap-allocated byte array
-    fn store_bytecode(&mut self, bytecode: &[u8]) -> Result<crate::memory::HeapIndex, crate::memory::allocator::OutOfMemory> {
-        let len = bytecode.len();
-        let index = self.context.alloc_byte_array(len)?;
+        let src = "var out = [], n = 0; while (n < 20) { out.push(wrap(function (o) { return o.label + n })); n++ } out[19]";
+        assert_eq!(engine.eval_as_string(src).unwrap(), "wrapped/wrapped19");
+    }
 
-        unsafe {
-            let array = self.context.get_byte_array_mut(index).unwrap();
-            let slice = array.as_full_mut_slice();
-            slice[..len].copy_from_slice(bytecode);
-            array.header_mut().set_count(len);
-        }
+    #[test]
+    fn test_register_function() {
+        let mut engine = Engine::new(65536);
+        engine.register_function("sum", native_sum).unwrap();
 
-        Ok(index)
+        assert_eq!(engine.eval_as_string("sum(2, 3)").unwrap(), "5");
+        assert_eq!(engine.eval_as_string("var f = sum; f(1, 2, 3) + 1").unwrap(), "7");
+        assert_eq!(engine.eval_as_string("typeof sum").unwrap(), "function");
     }
 
-    /// Convert a JSValue to a string representation
-    fn value_to_string(&self, value: JSValue) -> String {
-        if value.is_undefined() {
-            return "undefined".to_string();
-        }
-        if value.is_null() {
-            return "null".to_string();
-        }
-        if value.is_bool() {
-            return if value.to_bool().unwrap() { "true" } else { "false" }.to_string();
-        }
-        if let Some(i) = value.to_int() {
-            return alloc::format!("{}", i);
-        }
-        if let Some(f) = self.context.get_number(value) {
-            return alloc::format!("{}", f);
-        }
-        if let Some(s) = self.context.get_string(value) {
-            return s.to_string();
+    #[cfg(feature = "alloc-origin")]
+    #[test]
+    fn test_heap_census_by_origin() {
+        use crate::memory::AllocOrigin;
+
+        let mut engine = crate::Engine::new(65536);
+        engine.eval("var kept = []; for (var i = 0; i < 20; i++) kept.push('s' + i); var joined = kept.join('-');").unwrap();
+        engine.gc();
+        let ctx = &engine.context;
+        let origins = ctx.heap_census_by_origin(crate::memory::MemTag::String);
+        let named = |name: &str| origins.iter()
+            .find(|&&(origin, _)| ctx.alloc_origin_name(origin) == name)
+            .map_or(0, |(_, entry)| entry.count);
+
+        assert_eq!(named("add"), 20);
+        assert_eq!(named("join"), 1);
+        assert!(origins.iter().any(|&(origin, _)| matches!(origin, AllocOrigin::Builtin(_))));
+        let total: usize = origins.iter().map(|(_, entry)| entry.count).sum();
+        assert_eq!(total, ctx.heap_census().iter().find(|&&(tag, _)| tag == crate::memory::MemTag::String).unwrap().1.count);
+    }
+
+    #[test]
+    fn test_define_function_on_object() {
+        let mut engine = Engine::new(65536);
+        engine.eval("var gpio = {}").unwrap();
+
+        let global = engine.context.global_object();
+        let key = engine.context.intern_atom("gpio");
+        let gpio = engine.context.get_property(global, key).unwrap();
+        engine.context.define_function(gpio, "write", native_sum).unwrap();
+
+        assert_eq!(engine.eval_as_string("gpio.write(5, 1)").unwrap(), "6");
+    }
+
+    static GPIO_GETS: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+    static DEVICE_CONVERSIONS: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+    /// Getter for `device.gpio`, counting its calls; returns a native function
+    fn native_gpio_getter(ctx: &mut Context, _this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+        GPIO_GETS.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+        ctx.new_native_function(native_sum, 2).map_err(|_| JSValue::exception())
+    }
+
+    /// `valueOf` and `toString` for `device`, counting its calls
+    fn native_device_convert(_ctx: &mut Context, _this: JSValue, _args: &[JSValue]) -> Result<JSValue, JSValue> {
+        DEVICE_CONVERSIONS.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+        Ok(JSValue::from_int(0))
+    }
+
+    #[test]
+    fn test_typeof_and_truthiness_of_host_values() {
+        use core::sync::atomic::Ordering;
+
+        let mut engine = Engine::new(65536);
+        engine.register_function("sum", native_sum).unwrap();
+        engine.eval("var device = {}").unwrap();
+        let key = engine.context.intern_atom("device");
+        let device = engine.context.get_property(engine.context.global_object(), key).unwrap();
+        let getter = engine.context.new_native_function(native_gpio_getter, 0).unwrap();
+        let gpio = engine.context.intern_atom("gpio");
+        engine.context.define_getter(device, gpio, getter).unwrap();
+        engine.context.define_function(device, "valueOf", native_device_convert).unwrap();
+        engine.context.define_function(device, "toString", native_device_convert).unwrap();
+
+        // Each read of `device.gpio` runs the getter exactly once
+        let cases = [
+            ("typeof device", "object", 0),
+            ("typeof sum", "function", 0),
+            ("typeof sum.bind(null)", "function", 0),
+            ("typeof device.gpio", "function", 1),
+            ("typeof device.gpio === 'function'", "true", 1),
+            ("device.gpio(2, 3)", "5", 1),
+            ("var r = 'no'; if (device) r = 'yes'; r", "yes", 0),
+            ("[device ? 1 : 2, !device, !!sum, device && 'and', sum || 'or'].length", "5", 0),
+            ("device.gpio ? typeof device.gpio : 'none'", "function", 2),
+        ];
+        for (source, expected, gets) in cases {
+            GPIO_GETS.store(0, Ordering::Relaxed);
+            assert_eq!(engine.eval_as_string(source).unwrap(), expected, "{source}");
+            assert_eq!(GPIO_GETS.load(Ordering::Relaxed), gets, "{source}");
         }
+        // Neither typeof nor truthiness converts the object
+        assert_eq!(DEVICE_CONVERSIONS.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_register_function_arity_mismatch() {
+        let mut engine = Engine::new(65536);
+        engine.register_function("count", native_arg_count).unwrap();
+        engine.register_function("second", native_second_arg).unwrap();
 
-        // For objects, arrays, functions, etc.
-        "[object]".to_string()
+        // Arguments are passed through exactly as written at the call site
+        assert_eq!(engine.eval_as_string("count()").unwrap(), "0");
+        assert_eq!(engine.eval_as_string("count(1, 2, 3, 4)").unwrap(), "4");
+        assert_eq!(engine.eval_as_string("second(1)").unwrap(), "undefined");
+        assert_eq!(engine.eval_as_string("second(1, 2, 3)").unwrap(), "2");
     }
 
-    /// Create an error value
-    fn make_error(&mut self, message: &str) -> JSValue {
-        // Try to create a string error message
-        self.context.new_string(message)
-            .unwrap_or(JSValue::undefined())
+    #[test]
+    fn test_register_function_throws() {
+        let mut engine = Engine::new(65536);
+        engine.register_function("checkPin", native_check_pin).unwrap();
+
+        assert_eq!(engine.eval_as_string("checkPin(3)").unwrap(), "3");
+
+        // Uncaught native errors surface to the host with their kind
+        let err = engine.eval("checkPin(99)").unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Range));
+
+        // And can be caught by the script
+        let result = engine.eval_as_string(
+            "var m; try { checkPin(-1); } catch (e) { m = e.message; } m"
+        ).unwrap();
+        assert_eq!(result, "invalid pin");
     }
-}
 
-impl Default for Engine {
-    fn default() -> Self {
-        Self::new(65536) // 64 KB default heap
+    #[test]
+    fn test_register_function_out_of_memory() {
+        let mut engine = Engine::new(65536);
+        engine.register_function("allocate", native_alloc_failure).unwrap();
+
+        // The exception sentinel reaches the host as OutOfMemory
+        let err = engine.eval("allocate()").unwrap_err();
+        assert_eq!(err, EngineError::OutOfMemory);
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::OutOfMemory));
+
+        // And the script as an error object
+        let result = engine.eval_as_string(
+            "var m; try { allocate(); } catch (e) { m = e.name + ': ' + e.message; } m"
+        ).unwrap();
+        assert_eq!(result, "InternalError: Out of memory");
+    }
+
+    #[test]
+    fn test_register_error_class() {
+        let mut engine = Engine::new(131072);
+        let class = engine.register_error_class("DeviceBusyError", ErrorType::Error).unwrap();
+        engine.register_error_class("SensorRangeError", ErrorType::RangeError).unwrap();
+        engine.register_function("send", native_device_send).unwrap();
+        assert_eq!(engine.eval_as_string("typeof DeviceBusyError").unwrap(), "function");
+
+        // The script tells the custom error from a builtin one
+        let code = r#"
+            function attempt(x) {
+                try { return "sent " + send(x); }
+                catch (e) {
+                    if (e instanceof DeviceBusyError) return "retry: " + e.name + ": " + e.message;
+                    if (e instanceof TypeError) return "bad: " + e.message;
+                    return "other";
+                }
+            }
+            [attempt(7), attempt(1), attempt()].join(" | ")
+        "#;
+        assert_eq!(
+            engine.eval_as_string(code).unwrap(),
+            "sent 7 | retry: DeviceBusyError: device is busy | bad: payload required"
+        );
+
+        // Instances inherit through the parent up to Error, and nothing else
+        let code = r#"
+            var e; try { send(1) } catch (err) { e = err }
+            [e instanceof DeviceBusyError, e instanceof Error, e instanceof Object,
+             e instanceof TypeError, e instanceof SensorRangeError,
+             new TypeError("t") instanceof DeviceBusyError].join()
+        "#;
+        assert_eq!(engine.eval_as_string(code).unwrap(), "true,true,true,false,false,false");
+        let code = r#"
+            var s = new SensorRangeError("too hot");
+            [s instanceof SensorRangeError, s instanceof RangeError, s instanceof Error,
+             s.name, s.stack, SensorRangeError("x") instanceof SensorRangeError].join()
+        "#;
+        assert_eq!(
+            engine.eval_as_string(code).unwrap(),
+            "true,true,true,SensorRangeError,SensorRangeError: too hot,true"
+        );
+
+        // Uncaught, it reaches the host with the parent's kind and a location
+        let err = engine.eval("var x = 1;\nsend(1)").unwrap_err();
+        assert_eq!(err.to_string(), "DeviceBusyError: device is busy at line 2");
+        let err = engine.eval("throw new SensorRangeError('too cold')").unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Range));
+
+        // The host can build one directly too
+        let err = engine.context.throw_custom_error(class, "from host");
+        let stack = engine.context.get_property(err, crate::value::atoms::STACK).unwrap();
+        assert_eq!(engine.context.get_string(stack), Some("DeviceBusyError: from host"));
+    }
+
+    #[test]
+    fn test_instanceof_builtins() {
+        let mut engine = Engine::new(131072);
+        let code = r#"
+            var e = new RangeError("r");
+            [e instanceof RangeError, e instanceof Error, e instanceof TypeError,
+             [] instanceof Array, [] instanceof Object, ({}) instanceof Array,
+             1 instanceof Number, null instanceof Object,
+             e instanceof RangeError.bind(null)].join()
+        "#;
+        assert_eq!(engine.eval_as_string(code).unwrap(), "true,true,false,true,true,false,false,false,true");
+        for src in ["({}) instanceof 1", "({}) instanceof {}", "({}) instanceof undefined"] {
+            let err = engine.eval(src).unwrap_err();
+            assert_eq!(engine.error_kind(&err), Some(ErrorKind::Type), "{src}");
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_well_known_property_names() {
+        let mut engine = Engine::new(65536);
+
+        assert_eq!(engine.eval_as_string("[1, 2, 3].length").unwrap(), "3");
+        assert_eq!(engine.eval_as_string("var o = { name: 'a', length: 2 }; o.name + o.length").unwrap(), "a2");
+
+        // for-in recovers well-known names without the per-VM reverse mapping
+        let result = engine.eval_as_string(
+            "var keys = ''; for (var k in { name: 1, custom: 2 }) { keys = keys + k + ','; } keys"
+        ).unwrap();
+        assert_eq!(result, "name,custom,");
+    }
 
     #[test]
-    fn test_engine_new() {
-        let engine = Engine::new(8192);
-        let stats = engine.memory_stats();
-        assert_eq!(stats.heap_size, 8192);
+    fn test_array_element_indices() {
+        let mut engine = Engine::new(65536);
+
+        assert_eq!(engine.eval_as_string("var a = []; a[0] = 1; a[5] = 2; a.length === 6").unwrap(), "true");
+
+        // Holes read as undefined
+        assert_eq!(engine.eval_as_string("a[3]").unwrap(), "undefined");
+        assert_eq!(engine.eval_as_string("a[5]").unwrap(), "2");
+
+        // String and integral float keys name the same element
+        assert_eq!(engine.eval_as_string("a['5'] + a[5.0]").unwrap(), "4");
+
+        // Shrinking length drops elements past it
+        assert_eq!(engine.eval_as_string("a.length = 1; a.length").unwrap(), "1");
+        assert_eq!(engine.eval_as_string("a[5]").unwrap(), "undefined");
+
+        // Far-out writes still update length
+        assert_eq!(engine.eval_as_string("var b = []; b[100000] = 7; b.length + ':' + b[100000]").unwrap(), "100001:7");
+
+        // Plain objects keep computed keys as properties
+        assert_eq!(engine.eval_as_string("var o = {}; o['x'] = 1; o[2] = 3; o.x + o['2']").unwrap(), "4");
     }
 
     #[test]
-    fn test_engine_default() {
-        let engine = Engine::default();
-        let stats = engine.memory_stats();
-        assert_eq!(stats.heap_size, 65536);
+    fn test_parameter_redeclaration() {
+        let mut engine = Engine::new(65536);
+
+        // var without initializer keeps the parameter's value
+        assert_eq!(engine.eval_as_string("(function (a) { var a; return a; })(5)").unwrap(), "5");
+        assert_eq!(engine.eval_as_string("(function (a) { var a = 2; return a; })(5)").unwrap(), "2");
+
+        // Duplicate parameters: the last one wins
+        assert_eq!(engine.eval_as_string("(function (a, a) { return a; })(1, 2)").unwrap(), "2");
+        assert_eq!(engine.eval_as_string("(function (a, b, a) { return a + b; })(1, 2, 3)").unwrap(), "5");
+
+        // Redeclared locals share one slot
+        assert_eq!(engine.eval_as_string("(function () { var x = 1; var x; return x; })()").unwrap(), "1");
+
+        // A parameter shadows a named function expression's own name
+        assert_eq!(engine.eval_as_string("(function f(f) { return f; })(3)").unwrap(), "3");
+
+        // Lexical redeclarations are rejected
+        assert!(engine.eval("(function () { let x = 1; let x = 2; })").is_err());
+        assert!(engine.eval("(function (a) { let a = 1; })").is_err());
     }
 
     #[test]
-    fn test_memory_stats() {
-        let mut engine = Engine::new(4096);
-        let stats = engine.memory_stats();
-        assert_eq!(stats.heap_size, 4096);
-        assert!(stats.heap_used <= stats.heap_size);
+    fn test_property_name_length_limit() {
+        let mut engine = Engine::new(65536);
+        engine.set_max_property_name_len(8);
+
+        assert_eq!(engine.eval_as_string("var o = {}; o['12345678'] = 1; o['12345678']").unwrap(), "1");
+
+        let err = engine.eval("o['123456789'] = 1").unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Range));
+        let err = engine.eval("o['123456789']").unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Range));
+
+        // Index keys aren't names
+        assert_eq!(engine.eval_as_string("var a = []; a[1234567890] = 1; a.length").unwrap(), "1234567891");
     }
 
     #[test]
-    fn test_gc() {
-        let mut engine = Engine::new(8192);
-        // Just ensure GC doesn't crash
-        engine.gc();
+    fn test_source_size_limit() {
+        let mut engine = Engine::new(65536);
+        engine.set_max_source_bytes(16);
+
+        assert_eq!(engine.eval_as_string("1 + 2 + 3 + 4 +5").unwrap(), "15");
+        let err = engine.eval("1 + 2 + 3 + 4 + 5").unwrap_err();
+        assert_eq!(err, EngineError::Parse {
+            message: String::from("Source too large: 17 bytes (limit 16)"),
+            line: 0,
+            column: 0,
+            file: None,
+        });
+        assert_eq!(err.to_string(), "SyntaxError: Source too large: 17 bytes (limit 16)");
+
+        // The limit is checked before lexing, so unlexable text fails the same way
+        let err = engine.eval(&"\"".repeat(1 << 20)).unwrap_err();
+        assert!(matches!(err, EngineError::Parse { line: 0, .. }), "{:?}", err);
+        assert!(engine.context.eval(&" ".repeat(17), "big.js", 0).is_err());
     }
 
     #[test]
-    fn test_random() {
-        let mut engine = Engine::new(1024);
-        let r1 = engine.next_random();
-        let r2 = engine.next_random();
+    fn test_property_name_churn() {
+        let mut engine = Engine::new(1 << 20);
 
-        assert!(r1 >= 0.0 && r1 < 1.0);
-        assert!(r2 >= 0.0 && r2 < 1.0);
-        assert_ne!(r1, r2); // Should be different
+        // Reading 10,000 unknown names records nothing
+        let result = engine.eval_as_string(
+            "function probe(o) { var i = 0, n = 0; while (i < 10000) { if (o['sensor_' + i] === undefined) { n++; } i++; } return n; } probe({})"
+        ).unwrap();
+        assert_eq!(result, "10000");
+
+        // Defining past the quota fails with a RangeError, not an OOM
+        let mut engine = Engine::new(1 << 18);
+        engine.set_max_property_names(32);
+        let err = engine.eval(
+            "function fill(o) { var i = 0; while (i < 10000) { o['sensor_' + i] = i; i++; } } fill({})"
+        ).unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Range));
+
+        // Names recorded before the quota was hit keep working
+        assert_eq!(engine.eval_as_string("var p = {}; p['sensor_0'] = 5; p.sensor_0").unwrap(), "5");
+        let err = engine.eval("p['sensor_' + 9999] = 1").unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Range));
     }
 
     #[test]
-    fn test_eval_returns_expression_value() {
-        let mut engine = Engine::new(8192);
+    fn test_arrow_function_invariants() {
+        let mut engine = Engine::new(65536);
 
-        // Test simple arithmetic - should return 4, not undefined
-        let result = engine.eval("2 + 2").unwrap();
-        assert_eq!(result.to_int(), Some(4));
+        assert_eq!(engine.eval_as_string("typeof (() => 1)").unwrap(), "function");
+        assert_eq!(engine.eval_as_string("(() => 1).prototype").unwrap(), "undefined");
 
-        // Test more complex expression
-        let result = engine.eval("10 * 5 + 3").unwrap();
-        assert_eq!(result.to_int(), Some(53));
+        // Arrows aren't constructors, plain functions are
+        let arrow = engine.eval("(() => 1)").unwrap();
+        assert!(engine.context.is_arrow_function(arrow));
+        assert!(!engine.context.is_constructor(arrow));
+        let func = engine.eval("(function (a) { return a; })").unwrap();
+        assert!(!engine.context.is_arrow_function(func));
+        assert!(engine.context.is_constructor(func));
 
-        // Test boolean expression
-        let result = engine.eval("true").unwrap();
-        assert_eq!(result.to_bool(), Some(true));
+        // Lexical this survives being stored on an object and called as a method
+        let result = engine.eval_as_string(
+            "var m = { x: 9 }; function mk() { return () => this.x; } var h = mk['call'](m); var other = { x: 0, h: h }; other.h()"
+        ).unwrap();
+        assert_eq!(result, "9");
 
-        // Test string result as string
-        let result = engine.eval_as_string("42").unwrap();
-        assert_eq!(result, "42");
+        // ...and can't be rebound explicitly
+        assert_eq!(engine.eval_as_string("h['call']({ x: 1 })").unwrap(), "9");
+
+        // Nested arrows see the same this
+        let result = engine.eval_as_string(
+            "function nest() { return () => () => this.z; } nest['call']({ z: 3 })()()"
+        ).unwrap();
+        assert_eq!(result, "3");
+    }
+
+    #[test]
+    #[cfg(feature = "builtin-json")]
+    fn test_json_round_trip() {
+        let mut engine = Engine::new(131072);
+
+        let result = engine.eval_as_string(
+            r#"JSON.stringify({ a: [1, "x\n", true, null], b: { c: 1.5 }, u: undefined })"#
+        ).unwrap();
+        assert_eq!(result, r#"{"a":[1,"x\n",true,null],"b":{"c":1.5}}"#);
+
+        assert_eq!(engine.eval_as_string(r#"JSON.parse('{"k":[1,2]}').k[1]"#).unwrap(), "2");
+        assert_eq!(engine.eval_as_string("typeof JSON.stringify(undefined)").unwrap(), "undefined");
+
+        let result = engine.eval_as_string(
+            "var n, o = {}; o.me = o; try { JSON.stringify(o); } catch (e) { n = e.name; } n"
+        ).unwrap();
+        assert_eq!(result, "TypeError");
+
+        let result = engine.eval_as_string(
+            "var m; try { JSON.parse('[1,}'); } catch (e) { m = e.name + ': ' + e.message; } m"
+        ).unwrap();
+        assert_eq!(result, "SyntaxError: Unexpected token '}' in JSON at position 3");
+    }
+
+    #[test]
+    fn test_run_borrowed_script() {
+        let script = LoadedScript::compile(
+            "function counter() { var n = 0; return function () { n = n + 1; return n; }; } \
+             var tick = counter(); tick(); tick()"
+        ).unwrap();
+        let bytes: &'static [u8] = alloc::boxed::Box::leak(script.bytecode().into());
+        let borrowed = LoadedScript::borrowed(bytes);
+
+        let mut engine = Engine::new(65536);
+        assert_eq!(engine.run_as_string(&borrowed).unwrap(), "2");
+
+        // Closures made by the borrowed script keep running from it
+        assert_eq!(engine.eval_as_string("tick()").unwrap(), "3");
+
+        // Owned and borrowed runs agree, but only the owned one copies code
+        let mut owned_engine = Engine::new(65536);
+        assert_eq!(owned_engine.run_as_string(&script).unwrap(), "2");
+        assert_eq!(owned_engine.eval_as_string("tick()").unwrap(), "3");
+        let saved = owned_engine.memory_stats().heap_used - engine.memory_stats().heap_used;
+        assert!(saved >= bytes.len());
     }
 
     #[test]
     fn test_eval_multiple_statements() {
-        let mut engine = Engine::new(8192);
+        let mut engine = Engine::new(65536);
 
         // When there are multiple statements, only the last expression should be returned
         let result = engine.eval("1 + 1; 2 + 2").unwrap();
@@ -383,7 +2414,7 @@ mod tests {
 
     #[test]
     fn test_eval_non_expression_returns_undefined() {
-        let mut engine = Engine::new(8192);
+        let mut engine = Engine::new(65536);
 
         // Variable declarations should still return undefined
         let result = engine.eval("var x = 5;").unwrap();
@@ -396,7 +2427,7 @@ mod tests {
 
     #[test]
     fn test_eval_float() {
-        let mut engine = Engine::new(8192);
+        let mut engine = Engine::new(65536);
 
         // Test basic float
         let result = engine.eval("3.14").unwrap();
@@ -416,7 +2447,7 @@ mod tests {
 
     #[test]
     fn test_eval_large_integer() {
-        let mut engine = Engine::new(8192);
+        let mut engine = Engine::new(65536);
 
         // Test large integers that don't fit in i8 or i16
         let result = engine.eval("12345").unwrap();
@@ -440,7 +2471,7 @@ mod tests {
 
     #[test]
     fn test_eval_float_arithmetic() {
-        let mut engine = Engine::new(8192);
+        let mut engine = Engine::new(65536);
 
         // Test simple literal first
         let result = engine.eval("1.5").unwrap();
@@ -464,14 +2495,14 @@ mod tests {
 
     #[test]
     fn test_eval_global_assignment() {
-        let mut engine = Engine::new(32768); // 16KB heap
+        let mut engine = Engine::new(65536); // 16KB heap
         let result = engine.eval("x = 5; x");
         match result {
             Ok(val) => {
                 assert_eq!(engine.context.get_number(val), Some(5.0));
             }
             Err(err) => {
-                let err_str = engine.value_to_string(err);
+                let err_str = err.to_string();
                 panic!("eval failed with error: {}", err_str);
             }
         }
@@ -479,14 +2510,14 @@ mod tests {
 
     #[test]
     fn test_eval_global_multiple() {
-        let mut engine = Engine::new(32768); // 16KB heap
+        let mut engine = Engine::new(65536); // 16KB heap
         let result = engine.eval("a = 10; b = 20; a + b").unwrap();
         assert_eq!(engine.context.get_number(result), Some(30.0));
     }
 
     #[test]
     fn test_eval_global_persistence() {
-        let mut engine = Engine::new(32768); // 16KB heap
+        let mut engine = Engine::new(65536); // 16KB heap
 
         // Set a global variable
         engine.eval("x = 42").unwrap();
@@ -503,7 +2534,7 @@ mod tests {
 
     #[test]
     fn test_eval_global_expression_sequence() {
-        let mut engine = Engine::new(32768); // 16KB heap
+        let mut engine = Engine::new(65536); // 16KB heap
 
         // Test that global assignments work in expression sequences
         let result = engine.eval("y = 5; z = y * 2; z + 3").unwrap();
@@ -511,56 +2542,63 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "builtin-math")]
     fn test_eval_math_abs() {
-        let mut engine = Engine::new(32768); // Need more memory for builtins
+        let mut engine = Engine::new(65536);
         let result = engine.eval("Math.abs(-5)");
         match result {
             Ok(val) => assert_eq!(engine.context.get_number(val), Some(5.0)),
             Err(err) => {
-                let err_str = engine.value_to_string(err);
+                let err_str = err.to_string();
                 panic!("eval failed: {}", err_str);
             }
         }
     }
 
     #[test]
+    #[cfg(feature = "builtin-math")]
     fn test_eval_math_floor() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         let result = engine.eval("Math.floor(3.7)").unwrap();
         assert_eq!(engine.context.get_number(result), Some(3.0));
     }
 
     #[test]
+    #[cfg(feature = "builtin-math")]
     fn test_eval_math_ceil() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         let result = engine.eval("Math.ceil(3.2)").unwrap();
         assert_eq!(engine.context.get_number(result), Some(4.0));
     }
 
     #[test]
+    #[cfg(feature = "builtin-math")]
     fn test_eval_math_round() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         let result = engine.eval("Math.round(3.5)").unwrap();
         assert_eq!(engine.context.get_number(result), Some(4.0));
     }
 
     #[test]
+    #[cfg(feature = "builtin-math")]
     fn test_eval_math_max() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         let result = engine.eval("Math.max(1, 5, 3)").unwrap();
         assert_eq!(engine.context.get_number(result), Some(5.0));
     }
 
     #[test]
+    #[cfg(feature = "builtin-math")]
     fn test_eval_math_min() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         let result = engine.eval("Math.min(1, 5, 3)").unwrap();
         assert_eq!(engine.context.get_number(result), Some(1.0));
     }
 
     #[test]
+    #[cfg(feature = "builtin-console")]
     fn test_eval_console_log() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // console.log should return a function
         let result = engine.eval("console.log").unwrap();
         // Should be a function object (pointer)
@@ -568,16 +2606,18 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "builtin-math")]
     fn test_eval_math_object() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Math should be an object
         let result = engine.eval("Math").unwrap();
         assert!(result.is_ptr());
     }
 
     #[test]
+    #[cfg(feature = "builtin-math")]
     fn test_eval_math_abs_property() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Math.abs should be a function
         let result = engine.eval("Math.abs").unwrap();
         if result.is_undefined() {
@@ -598,7 +2638,7 @@ mod tests {
 
     #[test]
     fn test_function_declaration_simple() {
-        let mut engine = Engine::new(8192);
+        let mut engine = Engine::new(65536);
 
         // Do everything in one eval - function declaration followed by call
         let result = engine.eval("function add(a, b) { return a + b; } add(2, 3)").unwrap();
@@ -612,35 +2652,35 @@ mod tests {
 
     #[test]
     fn test_function_declaration_no_params() {
-        let mut engine = Engine::new(8192);
+        let mut engine = Engine::new(65536);
         let result = engine.eval("function getFortyTwo() { return 42; } getFortyTwo()").unwrap();
         assert_eq!(result.to_int(), Some(42), "No-param function should return 42");
     }
 
     #[test]
     fn test_function_one_param() {
-        let mut engine = Engine::new(8192);
+        let mut engine = Engine::new(65536);
         let result = engine.eval("function double(x) { return x * 2; } double(21)").unwrap();
         assert_eq!(result.to_int(), Some(42), "Double function should return 42");
     }
 
     #[test]
     fn test_function_with_local_var() {
-        let mut engine = Engine::new(8192);
+        let mut engine = Engine::new(65536);
         let result = engine.eval("function sum(a, b) { var result = a + b; return result; } sum(5, 7)").unwrap();
         assert_eq!(result.to_int(), Some(12), "Function with local var should return 12");
     }
 
     #[test]
     fn test_function_recursive_factorial() {
-        let mut engine = Engine::new(8192);
+        let mut engine = Engine::new(65536);
         let result = engine.eval("function factorial(n) { if (n <= 1) return 1; return n * factorial(n - 1); } factorial(5)").unwrap();
         assert_eq!(result.to_int(), Some(120), "Factorial(5) should return 120");
     }
 
     #[test]
     fn test_function_recursive_fibonacci() {
-        let mut engine = Engine::new(8192);
+        let mut engine = Engine::new(65536);
         let result = engine.eval("function fib(n) { if (n <= 1) return n; return fib(n - 1) + fib(n - 2); } fib(10)").unwrap();
         assert_eq!(result.to_int(), Some(55), "Fibonacci(10) should return 55");
     }
@@ -649,7 +2689,7 @@ mod tests {
 
     #[test]
     fn test_string_plus_number_concatenation() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // "5" + 3 should be "53" (string concatenation)
         let result = engine.eval_as_string("\"5\" + 3").unwrap();
         assert_eq!(result, "53", "String + number should concatenate");
@@ -657,7 +2697,7 @@ mod tests {
 
     #[test]
     fn test_number_plus_string_concatenation() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // 5 + "3" should be "53" (string concatenation)
         let result = engine.eval_as_string("5 + \"3\"").unwrap();
         assert_eq!(result, "53", "Number + string should concatenate");
@@ -665,7 +2705,7 @@ mod tests {
 
     #[test]
     fn test_string_minus_number() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // "5" - 3 should be 2 (numeric subtraction)
         let result = engine.eval("\"5\" - 3").unwrap();
         if let Some(i) = result.to_int() {
@@ -678,7 +2718,7 @@ mod tests {
 
     #[test]
     fn test_string_multiply_number() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // "5" * 3 should be 15 (numeric multiplication)
         let result = engine.eval("\"5\" * 3").unwrap();
         if let Some(i) = result.to_int() {
@@ -691,7 +2731,7 @@ mod tests {
 
     #[test]
     fn test_string_divide_number() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // "10" / 2 should be 5 (numeric division)
         let result = engine.eval("\"10\" / 2").unwrap();
         if let Some(i) = result.to_int() {
@@ -704,7 +2744,7 @@ mod tests {
 
     #[test]
     fn test_abstract_equality_number_string() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // 5 == "5" should be true (abstract equality with coercion)
         let result = engine.eval("5 == \"5\"").unwrap();
         assert_eq!(result.to_bool(), Some(true), "5 == \"5\" should be true");
@@ -712,7 +2752,7 @@ mod tests {
 
     #[test]
     fn test_strict_equality_number_string() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // 5 === "5" should be false (strict equality without coercion)
         let result = engine.eval("5 === \"5\"").unwrap();
         assert_eq!(result.to_bool(), Some(false), "5 === \"5\" should be false");
@@ -720,7 +2760,7 @@ mod tests {
 
     #[test]
     fn test_null_equals_undefined() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // null == undefined should be true
         let result = engine.eval("null == undefined").unwrap();
         assert_eq!(result.to_bool(), Some(true), "null == undefined should be true");
@@ -728,7 +2768,7 @@ mod tests {
 
     #[test]
     fn test_null_strict_equals_undefined() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // null === undefined should be false
         let result = engine.eval("null === undefined").unwrap();
         assert_eq!(result.to_bool(), Some(false), "null === undefined should be false");
@@ -736,7 +2776,7 @@ mod tests {
 
     #[test]
     fn test_boolean_to_number_hello() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // !!"hello" should be true (non-empty string is truthy)
         let result = engine.eval("!!\"hello\"").unwrap();
         assert_eq!(result.to_bool(), Some(true), "!!\"hello\" should be true");
@@ -744,7 +2784,7 @@ mod tests {
 
     #[test]
     fn test_boolean_to_number_empty_string() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // !!"" should be false (empty string is falsy)
         let result = engine.eval("!!\"\"").unwrap();
         assert_eq!(result.to_bool(), Some(false), "!!\"\" should be false");
@@ -752,7 +2792,7 @@ mod tests {
 
     #[test]
     fn test_boolean_to_number_zero() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // !!0 should be false (zero is falsy)
         let result = engine.eval("!!0").unwrap();
         assert_eq!(result.to_bool(), Some(false), "!!0 should be false");
@@ -760,7 +2800,7 @@ mod tests {
 
     #[test]
     fn test_boolean_to_number_one() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // !!1 should be true (non-zero is truthy)
         let result = engine.eval("!!1").unwrap();
         assert_eq!(result.to_bool(), Some(true), "!!1 should be true");
@@ -768,7 +2808,7 @@ mod tests {
 
     #[test]
     fn test_tonumber_null() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // null should convert to 0 in numeric context
         let result = engine.eval("null + 5").unwrap();
         if let Some(i) = result.to_int() {
@@ -781,7 +2821,7 @@ mod tests {
 
     #[test]
     fn test_tonumber_true() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // true should convert to 1 in numeric context
         let result = engine.eval("true + 5").unwrap();
         if let Some(i) = result.to_int() {
@@ -794,7 +2834,7 @@ mod tests {
 
     #[test]
     fn test_tonumber_false() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // false should convert to 0 in numeric context
         let result = engine.eval("false + 5").unwrap();
         if let Some(i) = result.to_int() {
@@ -807,7 +2847,7 @@ mod tests {
 
     #[test]
     fn test_tostring_number() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Number should convert to string in string context
         let result = engine.eval_as_string("\"value: \" + 42").unwrap();
         assert_eq!(result, "value: 42", "Number should convert to string");
@@ -815,7 +2855,7 @@ mod tests {
 
     #[test]
     fn test_tostring_true() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // true should convert to "true"
         let result = engine.eval_as_string("\"boolean: \" + true").unwrap();
         assert_eq!(result, "boolean: true", "true should convert to \"true\"");
@@ -823,7 +2863,7 @@ mod tests {
 
     #[test]
     fn test_tostring_false() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // false should convert to "false"
         let result = engine.eval_as_string("\"boolean: \" + false").unwrap();
         assert_eq!(result, "boolean: false", "false should convert to \"false\"");
@@ -831,7 +2871,7 @@ mod tests {
 
     #[test]
     fn test_tostring_null() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // null should convert to "null"
         let result = engine.eval_as_string("\"value: \" + null").unwrap();
         assert_eq!(result, "value: null", "null should convert to \"null\"");
@@ -839,7 +2879,7 @@ mod tests {
 
     #[test]
     fn test_tostring_undefined() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // undefined should convert to "undefined"
         let result = engine.eval_as_string("\"value: \" + undefined").unwrap();
         assert_eq!(result, "value: undefined", "undefined should convert to \"undefined\"");
@@ -847,7 +2887,7 @@ mod tests {
 
     #[test]
     fn test_empty_string_to_number() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Empty string should convert to 0
         let result = engine.eval("\"\" - 0").unwrap();
         if let Some(i) = result.to_int() {
@@ -860,7 +2900,7 @@ mod tests {
 
     #[test]
     fn test_invalid_string_to_nan() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Invalid numeric string should convert to NaN
         let result = engine.eval("\"abc\" - 0").unwrap();
         let num = engine.context.get_number(result).expect("Should be a number");
@@ -876,7 +2916,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Array indexing returns the index instead of the value
     fn test_array_indexing_first_element() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // [10, 20, 30][0] should return 10, not 0
         let result = engine.eval("[10, 20, 30][0]").unwrap();
         if let Some(i) = result.to_int() {
@@ -890,7 +2930,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Array indexing returns the index instead of the value
     fn test_array_indexing_middle_element() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // [10, 20, 30][1] should return 20, not 1
         let result = engine.eval("[10, 20, 30][1]").unwrap();
         if let Some(i) = result.to_int() {
@@ -904,7 +2944,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Array indexing returns the index instead of the value
     fn test_array_indexing_last_element() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // [10, 20, 30][2] should return 30, not 2
         let result = engine.eval("[10, 20, 30][2]").unwrap();
         if let Some(i) = result.to_int() {
@@ -918,7 +2958,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Array indexing returns the index instead of the value
     fn test_array_indexing_with_variable() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Test that indexing with a variable also works correctly
         let result = engine.eval("var arr = [5, 10, 15]; arr[1]").unwrap();
         if let Some(i) = result.to_int() {
@@ -932,7 +2972,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Array indexing returns the index instead of the value
     fn test_array_indexing_expression_index() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Test indexing with an expression
         let result = engine.eval("[100, 200, 300][1 + 1]").unwrap();
         if let Some(i) = result.to_int() {
@@ -948,7 +2988,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: For-loop with assignment update causes stack underflow
     fn test_for_loop_basic_assignment_update() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Basic for-loop with assignment update: for (var i = 0; i < 3; i = i + 1)
         let result = engine.eval(
             "var sum = 0; for (var i = 0; i < 3; i = i + 1) { sum = sum + i; } sum"
@@ -964,7 +3004,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: For-loop with assignment update causes stack underflow
     fn test_for_loop_count_iterations() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Test that the loop executes the correct number of times
         let result = engine.eval(
             "var count = 0; for (var i = 0; i < 5; i = i + 1) { count = count + 1; } count"
@@ -980,7 +3020,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: For-loop with assignment update causes stack underflow
     fn test_for_loop_accumulator_pattern() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Test accumulator pattern in for-loop
         let result = engine.eval(
             "var total = 0; for (var i = 1; i < 6; i = i + 1) { total = total + i; } total"
@@ -996,7 +3036,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: For-loop with assignment update causes stack underflow
     fn test_for_loop_no_initialization() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Test for-loop without initialization
         let result = engine.eval(
             "var i = 0; var sum = 0; for (; i < 4; i = i + 1) { sum = sum + 1; } sum"
@@ -1014,7 +3054,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Postfix increment not implemented
     fn test_postfix_increment_returns_old_value() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // i++ should return the old value and then increment
         let result = engine.eval("var i = 5; i++").unwrap();
         if let Some(val) = result.to_int() {
@@ -1028,7 +3068,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Postfix increment not implemented
     fn test_postfix_increment_updates_variable() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // After i++, the variable should be incremented
         let result = engine.eval("var i = 5; i++; i").unwrap();
         if let Some(val) = result.to_int() {
@@ -1042,7 +3082,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Prefix increment not implemented
     fn test_prefix_increment_returns_new_value() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // ++i should increment and return the new value
         let result = engine.eval("var i = 5; ++i").unwrap();
         if let Some(val) = result.to_int() {
@@ -1056,7 +3096,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Prefix increment not implemented
     fn test_prefix_increment_updates_variable() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // After ++i, the variable should be incremented
         let result = engine.eval("var i = 5; ++i; i").unwrap();
         if let Some(val) = result.to_int() {
@@ -1070,7 +3110,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Postfix decrement not implemented
     fn test_postfix_decrement_returns_old_value() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // i-- should return the old value and then decrement
         let result = engine.eval("var i = 10; i--").unwrap();
         if let Some(val) = result.to_int() {
@@ -1084,7 +3124,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Postfix decrement not implemented
     fn test_postfix_decrement_updates_variable() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // After i--, the variable should be decremented
         let result = engine.eval("var i = 10; i--; i").unwrap();
         if let Some(val) = result.to_int() {
@@ -1098,7 +3138,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Prefix decrement not implemented
     fn test_prefix_decrement_returns_new_value() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // --i should decrement and return the new value
         let result = engine.eval("var i = 10; --i").unwrap();
         if let Some(val) = result.to_int() {
@@ -1112,7 +3152,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Prefix decrement not implemented
     fn test_prefix_decrement_updates_variable() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // After --i, the variable should be decremented
         let result = engine.eval("var i = 10; --i; i").unwrap();
         if let Some(val) = result.to_int() {
@@ -1126,7 +3166,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Increment/decrement operators not implemented
     fn test_increment_in_for_loop() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Test using i++ in a for-loop (common pattern)
         let result = engine.eval(
             "var sum = 0; for (var i = 0; i < 5; i++) { sum = sum + i; } sum"
@@ -1144,7 +3184,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Math.pow not implemented
     fn test_math_pow_basic() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Math.pow(2, 8) should return 256
         let result = engine.eval("Math.pow(2, 8)").unwrap();
         if let Some(i) = result.to_int() {
@@ -1158,7 +3198,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Math.pow not implemented
     fn test_math_pow_cube() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Math.pow(3, 3) should return 27
         let result = engine.eval("Math.pow(3, 3)").unwrap();
         if let Some(i) = result.to_int() {
@@ -1172,7 +3212,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Math.pow not implemented
     fn test_math_pow_square() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Math.pow(5, 2) should return 25
         let result = engine.eval("Math.pow(5, 2)").unwrap();
         if let Some(i) = result.to_int() {
@@ -1186,7 +3226,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Math.sqrt not implemented
     fn test_math_sqrt_basic() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Math.sqrt(16) should return 4
         let result = engine.eval("Math.sqrt(16)").unwrap();
         if let Some(i) = result.to_int() {
@@ -1200,7 +3240,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Math.sqrt not implemented
     fn test_math_sqrt_perfect_squares() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Math.sqrt(9) should return 3
         let result = engine.eval("Math.sqrt(9)").unwrap();
         if let Some(i) = result.to_int() {
@@ -1223,7 +3263,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Math.sqrt not implemented
     fn test_math_sqrt_non_perfect_square() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Math.sqrt(2) should return approximately 1.414
         let result = engine.eval("Math.sqrt(2)").unwrap();
         let num = engine.context.get_number(result).expect("Should be a number");
@@ -1235,7 +3275,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Array.length property not implemented
     fn test_array_length_basic() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // [1, 2, 3].length should return 3
         let result = engine.eval("[1, 2, 3].length").unwrap();
         if let Some(i) = result.to_int() {
@@ -1249,7 +3289,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Array.length property not implemented
     fn test_array_length_empty() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // [].length should return 0
         let result = engine.eval("[].length").unwrap();
         if let Some(i) = result.to_int() {
@@ -1263,7 +3303,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Array.length property not implemented
     fn test_array_length_large() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Test with larger array
         let result = engine.eval("[1, 2, 3, 4, 5, 6, 7, 8, 9, 10].length").unwrap();
         if let Some(i) = result.to_int() {
@@ -1277,7 +3317,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Array.length property not implemented
     fn test_array_length_variable() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Test length on variable-stored array
         let result = engine.eval("var arr = [1, 2, 3, 4]; arr.length").unwrap();
         if let Some(i) = result.to_int() {
@@ -1291,7 +3331,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Array.length property not implemented
     fn test_array_length_in_expression() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Test using length in an expression
         let result = engine.eval("[1, 2, 3, 4, 5].length * 2").unwrap();
         if let Some(i) = result.to_int() {
@@ -1307,7 +3347,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Object method calls not working
     fn test_object_method_call_basic() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Function stored in object property should be callable
         let result = engine.eval(
             "var obj = { method: function() { return 42; } }; obj.method()"
@@ -1323,7 +3363,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Object method calls not working
     fn test_object_method_with_args() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Object method with arguments
         let result = engine.eval(
             "var obj = { add: function(a, b) { return a + b; } }; obj.add(10, 20)"
@@ -1339,7 +3379,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Object method calls not working
     fn test_object_method_accessing_this() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Object method accessing 'this'
         let result = engine.eval(
             "var obj = { value: 100, getValue: function() { return this.value; } }; obj.getValue()"
@@ -1355,7 +3395,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Object method calls not working
     fn test_object_method_modifying_this() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Object method modifying 'this'
         let result = engine.eval(
             "var obj = { count: 0, increment: function() { this.count = this.count + 1; return this.count; } }; obj.increment()"
@@ -1371,7 +3411,7 @@ mod tests {
     #[test]
     #[ignore] // Bug: Object method calls not working
     fn test_object_multiple_methods() {
-        let mut engine = Engine::new(32768);
+        let mut engine = Engine::new(65536);
         // Object with multiple methods
         let result = engine.eval(
             "var calc = { add: function(a, b) { return a + b; }, multiply: function(a, b) { return a * b; } }; calc.add(5, 3) + calc.multiply(2, 4)"
@@ -1383,4 +3423,118 @@ mod tests {
             assert_eq!(num, 16.0, "Should calculate (5+3) + (2*4) = 16");
         }
     }
+
+    fn board_info(ctx: &mut Context, engine: JSValue) -> Result<(), JSValue> {
+        let board = ctx.intern_atom("board");
+        let name = ctx.new_string("rev-c").map_err(|_| JSValue::exception())?;
+        ctx.add_property(engine, board, name, crate::object::PropertyFlags::default())
+            .map_err(|_| JSValue::exception())
+    }
+
+    #[test]
+    fn test_engine_info_hook() {
+        let mut engine = Engine::with_engine_info(65536, board_info);
+        assert_eq!(engine.eval_as_string("engine.board").unwrap(), "rev-c");
+        assert_eq!(engine.eval_as_string("engine.board = 'x'; engine.board").unwrap(), "rev-c");
+        assert_eq!(engine.eval_as_string("typeof engine.features.json").unwrap(), "boolean");
+
+        let mut plain = Engine::new(65536);
+        assert_eq!(plain.eval_as_string("engine.board").unwrap(), "undefined");
+    }
+
+    #[test]
+    fn test_prototype_depth_limits() {
+        let mut engine = Engine::new(131072);
+        let chain = "var o = {}; for (var i = 0; i < 50; i++) o = Object.create(o);";
+
+        // A miss walks all 51 links, up to Object.prototype
+        let report = engine.eval_captured(&alloc::format!("{} o.missing", chain));
+        assert!(report.error.is_none());
+        assert_eq!(report.metrics.prototype_depth, 51);
+        let report = engine.eval_captured("({}).missing");
+        assert_eq!(report.metrics.prototype_depth, 1);
+
+        engine.set_max_prototype_depth(10);
+        let err = engine.eval(chain).unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Range));
+        engine.set_max_prototype_depth(Context::DEFAULT_MAX_PROTOTYPE_DEPTH);
+
+        // Each link a lookup follows costs a step, so deep misses run out
+        // of budget long before the instructions alone would
+        engine.set_max_steps(5_000);
+        let shallow = "for (var j = 0; j < 100; j++) ({}).missing;";
+        let deep = "for (var j = 0; j < 100; j++) o.missing;";
+        assert!(engine.eval(&alloc::format!("{} {}", chain, shallow)).is_ok());
+        let err = engine.eval(&alloc::format!("{} {}", chain, deep)).unwrap_err();
+        assert_eq!(engine.error_kind(&err), Some(ErrorKind::Timeout));
+    }
+
+    #[test]
+    #[cfg(all(feature = "builtin-console", feature = "builtin-json"))]
+    fn test_eval_captured_success() {
+        let mut engine = Engine::new(65536);
+        let report = engine.eval_captured("console.log('start', [1]); console.warn('careful'); ({ a: [1, 'x'] })");
+
+        assert_eq!(report.output, ["start [ 1 ]", "Warning: careful"]);
+        assert_eq!(report.json.as_deref(), Ok(r#"{"a":[1,"x"]}"#));
+        assert!(report.error.is_none());
+        assert!(report.metrics.instructions > 0);
+        assert!(report.metrics.heap_used_after > report.metrics.heap_used_before);
+
+        let mut handle = report.value.unwrap();
+        engine.gc();
+        let value = engine.persistent_value(&handle).unwrap();
+        assert_eq!(engine.value_to_string(value), "{ a: [ 1, 'x' ] }");
+        engine.detach(&mut handle);
+        assert!(engine.context.replace_console_capture(None).is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "builtin-console")]
+    fn test_eval_captured_error() {
+        let mut engine = Engine::new(65536);
+        let report = engine.eval_captured("console.log('before'); null.x; console.log('after')");
+
+        assert_eq!(report.output, ["before"]);
+        assert!(report.value.is_none());
+        assert!(report.json.is_err());
+        let error = report.error.unwrap();
+        assert_eq!(error.kind, Some(ErrorKind::Type));
+        assert!(error.message.starts_with("TypeError: "));
+        assert_eq!(error.message, alloc::format!("{} at line 1", error.stack.unwrap()));
+        assert_eq!((error.line, error.column), (1, 0));
+        assert!(engine.context.replace_console_capture(None).is_none());
+
+        let error = engine.eval_captured("var = 1").error.unwrap();
+        assert_eq!(error.kind, Some(ErrorKind::Syntax));
+        assert_eq!((error.line, error.column), (1, 5));
+        assert_eq!(error.stack, None);
+    }
+
+    #[test]
+    #[cfg(feature = "builtin-json")]
+    fn test_eval_captured_unserializable_result() {
+        let mut engine = Engine::new(65536);
+        let mut report = engine.eval_captured("(function () { return 1; })");
+
+        let note = report.json.unwrap_err();
+        assert!(note.contains("no JSON form"), "{}", note);
+        let handle = report.value.as_mut().unwrap();
+        assert!(engine.persistent_value(handle).is_some());
+        engine.detach(handle);
+    }
+
+    #[test]
+    #[cfg(feature = "builtin-console")]
+    fn test_eval_captured_restores_outer_capture() {
+        let mut engine = Engine::new(65536);
+        engine.context.replace_console_capture(Some(alloc::vec![String::from("outer")]));
+
+        let report = engine.eval_captured("console.log('inner'); throw 1");
+        assert_eq!(report.output, ["inner"]);
+
+        engine.eval("console.log('outer again')").unwrap();
+        let outer = engine.context.replace_console_capture(None).unwrap();
+        assert_eq!(outer, ["outer", "outer again"]);
+    }
 }
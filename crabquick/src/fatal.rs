@@ -0,0 +1,118 @@
+//! Fatal error funnel
+//!
+//! Every internal invariant violation the engine can't turn into a JS
+//! exception (a stale heap index, a corrupted closure) ends up in [`fatal`].
+//! Hosts can install a handler with `Engine::set_fatal_handler` to log the
+//! failure and reset cleanly; without one, `fatal` panics, which unwinds
+//! under std and reaches the embedder's panic handler on `no_std`.
+//!
+//! The handler is process-wide, since the allocator, where most of these
+//! checks live, has no engine to report to. The last executed location is
+//! per thread under std, and process-wide on `no_std`.
+
+use core::sync::atomic::{AtomicPtr, Ordering};
+#[cfg(not(any(test, feature = "std")))]
+use core::sync::atomic::AtomicUsize;
+
+use crate::bytecode::Opcode;
+
+/// Details passed to the fatal handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FatalInfo {
+    /// Engine module that detected the failure, e.g. "memory" or "vm"
+    pub module: &'static str,
+    /// What went wrong
+    pub message: &'static str,
+    /// Offset of the last instruction the VM started, if any
+    pub pc: Option<usize>,
+    /// Opcode of the last instruction the VM started, if any
+    pub opcode: Option<Opcode>,
+}
+
+/// Handler for unrecoverable internal errors; it must not return
+pub type FatalHandler = fn(&FatalInfo) -> !;
+
+/// Marks "no location recorded" in the location slots
+const NONE: usize = usize::MAX;
+
+static HANDLER: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+#[cfg(any(test, feature = "std"))]
+std::thread_local! {
+    static LAST_LOCATION: core::cell::Cell<(usize, usize)> = const { core::cell::Cell::new((NONE, NONE)) };
+}
+
+#[cfg(not(any(test, feature = "std")))]
+static LAST_PC: AtomicUsize = AtomicUsize::new(NONE);
+#[cfg(not(any(test, feature = "std")))]
+static LAST_OPCODE: AtomicUsize = AtomicUsize::new(NONE);
+
+/// Installs the process-wide fatal handler, replacing any previous one
+///
+/// Passing `None` restores the default of panicking.
+pub fn set_handler(handler: Option<FatalHandler>) {
+    let ptr = handler.map_or(core::ptr::null_mut(), |handler| handler as *mut ());
+    HANDLER.store(ptr, Ordering::Release);
+}
+
+/// Records the instruction the VM is about to execute
+#[inline]
+pub(crate) fn record_location(pc: usize, opcode: Opcode) {
+    #[cfg(any(test, feature = "std"))]
+    LAST_LOCATION.with(|location| location.set((pc, opcode as usize)));
+
+    #[cfg(not(any(test, feature = "std")))]
+    {
+        LAST_PC.store(pc, Ordering::Relaxed);
+        LAST_OPCODE.store(opcode as usize, Ordering::Relaxed);
+    }
+}
+
+/// Returns the last recorded VM location
+fn last_location() -> (Option<usize>, Option<Opcode>) {
+    #[cfg(any(test, feature = "std"))]
+    let (pc, opcode) = LAST_LOCATION.with(core::cell::Cell::get);
+
+    #[cfg(not(any(test, feature = "std")))]
+    let (pc, opcode) = (LAST_PC.load(Ordering::Relaxed), LAST_OPCODE.load(Ordering::Relaxed));
+
+    let pc = (pc != NONE).then_some(pc);
+    let opcode = u8::try_from(opcode).ok().and_then(Opcode::from_u8);
+    (pc, opcode)
+}
+
+/// Reports an unrecoverable internal error
+///
+/// Calls the installed handler, or panics if there is none.
+///
+/// # Panics
+///
+/// Always, when no handler is installed.
+#[cold]
+#[inline(never)]
+pub fn fatal(module: &'static str, message: &'static str) -> ! {
+    let (pc, opcode) = last_location();
+    let info = FatalInfo { module, message, pc, opcode };
+
+    let ptr = HANDLER.load(Ordering::Acquire);
+    if !ptr.is_null() {
+        // SAFETY: HANDLER only ever holds null or a `FatalHandler` stored by `set_handler`
+        let handler: FatalHandler = unsafe { core::mem::transmute::<*mut (), FatalHandler>(ptr) };
+        handler(&info);
+    }
+
+    panic!("{}: {} (pc {:?}, opcode {:?})", module, message, info.pc, info.opcode)
+}
+
+/// Checks an internal invariant, reporting a violation through [`fatal`]
+///
+/// Unlike `debug_assert!`, the check stays in release builds.
+macro_rules! internal_assert {
+    ($cond:expr, $module:expr, $message:expr) => {
+        if !$cond {
+            $crate::fatal::fatal($module, $message);
+        }
+    };
+}
+
+pub(crate) use internal_assert;
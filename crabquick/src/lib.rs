@@ -33,9 +33,18 @@
 extern crate alloc;
 
 // Public API exports
-pub use context::Context;
-pub use value::JSValue;
-pub use engine::{Engine, MemoryStats};
+pub use context::{Clock, Context, ContextConfig, InterruptHandler, ReentrancyError, DEFAULT_MAX_NESTING_DEPTH};
+pub use value::{JSValue, KeyedValue};
+pub use engine::{Engine, EngineError, ErrorReport, EvalMetrics, EvalReport, GcStats, MemoryStats};
+pub use memory::{CensusEntry, HeapSizeError, MemTag, Persistent};
+#[cfg(feature = "alloc-origin")]
+pub use memory::AllocOrigin;
+pub use builtins::error::{ErrorKind, ErrorType};
+#[cfg(feature = "test-api")]
+pub use builtins::test_api::TestResult;
+pub use fatal::{FatalHandler, FatalInfo};
+pub use runtime::EngineInfoHook;
+pub use bytecode::{LoadedScript, ScriptSource, ScriptManifest};
 
 // Module declarations
 pub mod memory;
@@ -48,6 +57,7 @@ pub mod builtins;
 pub mod runtime;
 pub mod util;
 pub mod engine;
+pub mod fatal;
 
 // Core types
 mod context;
@@ -55,7 +65,10 @@ mod context;
 // Re-exports for convenience
 pub mod prelude {
     //! Commonly used types and traits
-    pub use crate::context::Context;
+    pub use crate::context::{Context, ContextConfig, ReentrancyError};
     pub use crate::value::JSValue;
-    pub use crate::engine::{Engine, MemoryStats};
+    pub use crate::engine::{Engine, EngineError, ErrorReport, EvalMetrics, EvalReport, GcStats, MemoryStats};
+    pub use crate::memory::{HeapSizeError, Persistent};
+    pub use crate::builtins::error::{ErrorKind, ErrorType};
+    pub use crate::bytecode::{LoadedScript, ScriptSource, ScriptManifest};
 }
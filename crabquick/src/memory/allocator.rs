@@ -81,7 +81,9 @@ pub struct Arena {
 }
 
 /// 8-byte alignment for all allocations
-const ALIGNMENT: usize = 8;
+///
+/// Arena sizes are rounded down to a multiple of this.
+pub const ALIGNMENT: usize = 8;
 
 /// Aligns a size up to the specified alignment
 #[inline]
@@ -91,7 +93,11 @@ const fn align_up(size: usize, alignment: usize) -> usize {
 
 impl Arena {
     /// Creates a new arena with the specified size
+    ///
+    /// The size is rounded down to a multiple of [`ALIGNMENT`]; the tail
+    /// could never hold an allocation anyway.
     pub fn new(size: usize) -> Self {
+        let size = size & !(ALIGNMENT - 1);
         let mut memory = Vec::with_capacity(size);
         // Initialize memory to zero
         memory.resize(size, 0);
@@ -105,6 +111,13 @@ impl Arena {
         }
     }
 
+    /// Returns the arena bytes an allocation of `size` bytes takes up,
+    /// header and alignment padding included
+    #[inline]
+    pub const fn block_size(size: usize) -> usize {
+        align_up(mem::size_of::<MemBlockHeader>() + size, ALIGNMENT)
+    }
+
     /// Allocates memory from the heap
     ///
     /// # Arguments
@@ -121,8 +134,7 @@ impl Arena {
     /// The returned HeapIndex remains stable across GC compactions.
     pub fn alloc(&mut self, size: usize, mtag: MemTag) -> Result<HeapIndex, OutOfMemory> {
         // Calculate total size: header + data, aligned to 8 bytes
-        let header_size = mem::size_of::<MemBlockHeader>();
-        let total_size = align_up(header_size + size, ALIGNMENT);
+        let total_size = Self::block_size(size);
 
         // Check if we have enough space
         if self.heap_free + total_size > self.stack_bottom {
@@ -268,7 +280,7 @@ impl Arena {
         let offset = self.index_table
             .get(index.as_usize())
             .and_then(|opt| *opt)
-            .expect("Invalid HeapIndex in get");
+            .unwrap_or_else(|| crate::fatal::fatal("memory", "Invalid HeapIndex in get"));
 
         let header_size = mem::size_of::<MemBlockHeader>();
         let ptr = self.memory.as_ptr().add(offset + header_size);
@@ -289,7 +301,7 @@ impl Arena {
         let offset = self.index_table
             .get(index.as_usize())
             .and_then(|opt| *opt)
-            .expect("Invalid HeapIndex in get_mut");
+            .unwrap_or_else(|| crate::fatal::fatal("memory", "Invalid HeapIndex in get_mut"));
 
         let header_size = mem::size_of::<MemBlockHeader>();
         let ptr = self.memory.as_mut_ptr().add(offset + header_size);
@@ -307,7 +319,7 @@ impl Arena {
         let offset = self.index_table
             .get(index.as_usize())
             .and_then(|opt| *opt)
-            .expect("Invalid HeapIndex in get_header");
+            .unwrap_or_else(|| crate::fatal::fatal("memory", "Invalid HeapIndex in get_header"));
 
         let ptr = self.memory.as_ptr().add(offset);
         &*(ptr as *const MemBlockHeader)
@@ -324,7 +336,7 @@ impl Arena {
         let offset = self.index_table
             .get(index.as_usize())
             .and_then(|opt| *opt)
-            .expect("Invalid HeapIndex in get_header_mut");
+            .unwrap_or_else(|| crate::fatal::fatal("memory", "Invalid HeapIndex in get_header_mut"));
 
         let ptr = self.memory.as_mut_ptr().add(offset);
         &mut *(ptr as *mut MemBlockHeader)
@@ -358,6 +370,14 @@ impl Arena {
         self.index_table.len()
     }
 
+    /// Returns the header of every allocated block, in index order
+    pub(crate) fn block_headers(&self) -> impl Iterator<Item = MemBlockHeader> + '_ {
+        self.index_table.iter().flatten().map(|&offset| {
+            // SAFETY: the index table only holds offsets of allocated blocks
+            unsafe { (self.memory.as_ptr().add(offset) as *const MemBlockHeader).read() }
+        })
+    }
+
     /// Checks if an index is currently valid (allocated and not freed)
     #[inline]
     pub(crate) fn is_index_valid(&self, index: HeapIndex) -> bool {
@@ -431,6 +451,32 @@ impl Arena {
 #[derive(Debug, Clone, Copy)]
 pub struct OutOfMemory;
 
+/// A heap size too small to hold what has to live in it from the start
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapSizeError {
+    /// Size that was asked for, in bytes
+    pub requested: usize,
+    /// Smallest accepted size, in bytes
+    pub minimum: usize,
+}
+
+impl HeapSizeError {
+    /// Fails when `requested` is below `minimum`
+    pub fn check(requested: usize, minimum: usize) -> Result<(), HeapSizeError> {
+        if requested < minimum {
+            Err(HeapSizeError { requested, minimum })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl core::fmt::Display for HeapSizeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "heap size of {} bytes is below the minimum of {} bytes", self.requested, self.minimum)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -445,6 +491,13 @@ mod tests {
         assert!(null_idx.is_null());
     }
 
+    #[test]
+    fn test_arena_size_rounds_down_to_alignment() {
+        assert_eq!(Arena::new(1030).size(), 1024);
+        assert_eq!(Arena::new(7).size(), 0);
+        assert_eq!(Arena::new(10_000).size(), 10_000);
+    }
+
     #[test]
     fn test_arena_new() {
         let arena = Arena::new(1024);
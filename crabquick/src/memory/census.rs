@@ -0,0 +1,112 @@
+//! Heap census
+//!
+//! A census walks every block the arena has handed out and adds up how many
+//! there are of each kind and how many bytes they take, to find out what a
+//! growing heap is growing with. Blocks that died since the last collection
+//! are still counted until a collection frees them, so collect first to see
+//! only live data.
+//!
+//! With the `alloc-origin` feature, every block also records what was
+//! running when it was allocated (see [`AllocOrigin`]), and the census can
+//! break a kind of block down by origin.
+
+use super::header::MemTag;
+
+/// Blocks of one kind found by a census
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CensusEntry {
+    /// Number of blocks
+    pub count: usize,
+    /// Arena bytes they take up, headers and alignment padding included
+    pub bytes: usize,
+}
+
+impl CensusEntry {
+    /// Counts one more block of `bytes` bytes
+    #[inline]
+    pub fn add(&mut self, bytes: usize) {
+        self.count += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// Every memory tag, in tag order
+pub const ALL_TAGS: [MemTag; 11] = [
+    MemTag::Object,
+    MemTag::String,
+    MemTag::Float64,
+    MemTag::FunctionBytecode,
+    MemTag::ValueArray,
+    MemTag::ByteArray,
+    MemTag::PropertyTable,
+    MemTag::ClosureData,
+    MemTag::VarRef,
+    MemTag::CFunctionData,
+    MemTag::ArrayData,
+];
+
+/// What was running when a block was allocated
+///
+/// Kept in 16 reserved bits of the block header, so recording it costs no
+/// arena space; see [`MemBlockHeader::origin_code`](super::MemBlockHeader::origin_code).
+#[cfg(feature = "alloc-origin")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AllocOrigin {
+    /// The host or the runtime, outside any script code
+    Host,
+    /// A native function the runtime didn't install, such as one the host
+    /// defined with `Context::define_function`
+    Native,
+    /// The interpreter, running the instruction with this opcode byte
+    Opcode(u8),
+    /// The builtin at this position in the context's builtin registry
+    Builtin(u8),
+}
+
+#[cfg(feature = "alloc-origin")]
+impl AllocOrigin {
+    const OPCODE: u16 = 0x100;
+    const BUILTIN: u16 = 0x200;
+
+    /// Encodes the origin for a block header
+    pub const fn code(self) -> u16 {
+        match self {
+            AllocOrigin::Host => 0,
+            AllocOrigin::Native => 1,
+            AllocOrigin::Opcode(op) => Self::OPCODE | op as u16,
+            AllocOrigin::Builtin(index) => Self::BUILTIN | index as u16,
+        }
+    }
+
+    /// Decodes an origin from a block header
+    ///
+    /// Unknown codes read as [`AllocOrigin::Host`].
+    pub const fn from_code(code: u16) -> Self {
+        match code & 0xFF00 {
+            Self::OPCODE => AllocOrigin::Opcode(code as u8),
+            Self::BUILTIN => AllocOrigin::Builtin(code as u8),
+            _ if code == 1 => AllocOrigin::Native,
+            _ => AllocOrigin::Host,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_tags_in_order() {
+        for (i, tag) in ALL_TAGS.iter().enumerate() {
+            assert_eq!(*tag as usize, i);
+        }
+    }
+
+    #[cfg(feature = "alloc-origin")]
+    #[test]
+    fn test_origin_codes_round_trip() {
+        for origin in [AllocOrigin::Host, AllocOrigin::Native, AllocOrigin::Opcode(0), AllocOrigin::Opcode(183), AllocOrigin::Builtin(255)] {
+            assert_eq!(AllocOrigin::from_code(origin.code()), origin);
+        }
+    }
+}
@@ -17,6 +17,8 @@ pub struct GarbageCollector {
     marked_indices: Vec<HeapIndex>,
     /// GC roots that must be preserved
     roots: Vec<JSValue>,
+    /// Number of completed collection cycles
+    cycles: usize,
 }
 
 impl GarbageCollector {
@@ -26,9 +28,15 @@ impl GarbageCollector {
             mark_stack: Vec::new(),
             marked_indices: Vec::new(),
             roots: Vec::new(),
+            cycles: 0,
         }
     }
 
+    /// Returns how many collection cycles have run
+    pub fn cycles(&self) -> usize {
+        self.cycles
+    }
+
     /// Adds a root value that should be preserved during GC
     pub fn add_root(&mut self, value: JSValue) {
         self.roots.push(value);
@@ -49,18 +57,37 @@ impl GarbageCollector {
     /// 1. Mark roots
     /// 2. Mark reachable objects (tri-color marking)
     /// 3. Compact live objects and update index table
-    pub fn collect(&mut self, arena: &mut Arena) {
+    ///
+    /// Returns the number of blocks freed.
+    pub fn collect(&mut self, arena: &mut Arena) -> usize {
+        self.collect_with(arena, &[], false)
+    }
+
+    /// Performs a collection cycle with extra, temporary roots
+    ///
+    /// With `pin_code`, live byte arrays are not moved: the VM reads running
+    /// bytecode through raw slices into the arena, so a collection in the
+    /// middle of a run must leave it where it is. The VM roots the code it
+    /// runs, so unreachable code (that of finished scripts) is freed. Byte
+    /// arrays marked movable hold script data and are collected as usual.
+    /// Returns the number of blocks freed.
+    pub fn collect_with(&mut self, arena: &mut Arena, extra_roots: &[JSValue], pin_code: bool) -> usize {
         // Clear previous GC state
         self.mark_stack.clear();
         self.marked_indices.clear();
 
         // Phase 1: Mark all reachable objects
         self.mark_roots(arena);
+        for &root in extra_roots {
+            self.mark_value(root, arena);
+        }
         self.mark_phase(arena);
 
         // Phase 2: Compact live objects
         // This also implicitly sweeps dead objects
-        self.compact(arena);
+        let freed = self.compact(arena, pin_code);
+        self.cycles += 1;
+        freed
     }
 
     /// Marks all root objects
@@ -71,12 +98,6 @@ impl GarbageCollector {
         for root_value in roots {
             self.mark_value(root_value, arena);
         }
-
-        // TODO: In a full implementation, also mark:
-        // - Global object
-        // - Exception value
-        // - VM stack
-        // - Current function call frames
     }
 
     /// Marks a JSValue if it's a pointer to a heap object
@@ -187,12 +208,23 @@ impl GarbageCollector {
                     // These are leaf objects with no references
                 }
                 MemTag::FunctionBytecode => {
-                    // TODO: Mark function bytecode references when implemented
+                    // Mark the code, when it lives on the heap
+                    let func: &crate::object::function::JSBytecodeFunction = arena.get(index);
+                    let props = func.props;
+                    if let Some(code) = func.code.heap_index() {
+                        self.mark_object(code, arena);
+                    }
+                    if !props.is_null() {
+                        self.mark_object(props, arena);
+                    }
                 }
                 MemTag::ClosureData => {
                     // Scan closure - mark all captured variable references
                     let closure: &crate::object::function::JSClosure = arena.get(index);
                     let var_ref_count = closure.var_ref_count as usize;
+                    let lexical_this = closure.lexical_this;
+                    let code = closure.code;
+                    let props = closure.props;
 
                     // Collect var ref indices first to avoid borrow conflicts
                     let var_refs: Vec<HeapIndex> = (0..var_ref_count)
@@ -203,6 +235,13 @@ impl GarbageCollector {
                     for vr_idx in var_refs {
                         self.mark_object(vr_idx, arena);
                     }
+                    self.mark_value(lexical_this, arena);
+                    if let Some(code) = code.heap_index() {
+                        self.mark_object(code, arena);
+                    }
+                    if !props.is_null() {
+                        self.mark_object(props, arena);
+                    }
                 }
                 MemTag::VarRef => {
                     // Scan var ref - mark the contained value
@@ -211,7 +250,20 @@ impl GarbageCollector {
                     self.mark_value(value, arena);
                 }
                 MemTag::CFunctionData => {
-                    // C functions don't have GC references
+                    // C functions only refer to their own properties
+                    let func: &crate::object::function::JSCFunction = arena.get(index);
+                    let props = func.props;
+                    if !props.is_null() {
+                        self.mark_object(props, arena);
+                    }
+                }
+                MemTag::ArrayData => {
+                    // Mark the dense element storage
+                    let array: &crate::object::JSArray = arena.get(index);
+                    if array.has_elements() {
+                        let elements = array.elements_index();
+                        self.mark_object(elements, arena);
+                    }
                 }
             }
         }
@@ -219,15 +271,20 @@ impl GarbageCollector {
 
     /// Compacts live objects using index-based approach
     ///
-    /// This is the key simplification: we iterate through all indices,
-    /// move live objects to compact memory, and update the index table.
-    /// No need to thread pointers through objects!
-    fn compact(&mut self, arena: &mut Arena) {
-        let mut write_offset = 0;
+    /// This is the key simplification: we slide live objects down over the
+    /// dead ones and update the index table. No need to thread pointers
+    /// through objects! Blocks are visited in address order, so a block is
+    /// never copied over one that hasn't moved yet. With `pin_code`, live
+    /// byte arrays stay put; the space freed below one becomes a gap that
+    /// later blocks fill where they fit, and the rest compacts above it.
+    /// Returns the number of blocks freed.
+    fn compact(&mut self, arena: &mut Arena, pin_code: bool) -> usize {
+        use super::header::MemTag;
 
-        // Sort marked indices to process them in order
-        // This isn't strictly necessary but makes the compaction more predictable
-        self.marked_indices.sort_unstable();
+        let mut write_offset = 0;
+        let mut freed = 0;
+        // Free space left below pinned blocks, as (start, end)
+        let mut gaps: Vec<(usize, usize)> = Vec::new();
 
         // Create a set of marked indices for O(log n) lookup
         let marked_set: HashMap<HeapIndex, ()> = self.marked_indices
@@ -235,55 +292,88 @@ impl GarbageCollector {
             .map(|&idx| (idx, ()))
             .collect();
 
-        // Iterate through all indices in the index table
-        let index_count = arena.index_table_len();
-
-        for idx in 0..index_count {
-            let index = HeapIndex::from_usize(idx);
-
-            // Get the current offset for this index
-            let old_offset = match arena.get_offset(index) {
-                Some(offset) => offset,
-                None => continue, // Already freed, skip
-            };
+        // Collect every allocated block, in address order
+        let mut blocks: Vec<(usize, HeapIndex)> = (0..arena.index_table_len())
+            .map(HeapIndex::from_usize)
+            .filter_map(|index| arena.get_offset(index).map(|offset| (offset, index)))
+            .collect();
+        blocks.sort_unstable();
 
+        for (old_offset, index) in blocks {
             // Check if this object is marked (live)
             let is_marked = marked_set.contains_key(&index);
 
-            if is_marked {
-                // Live object - move it to the compacted region
+            let pinned = pin_code && is_marked && {
+                // SAFETY: the index table only holds offsets of allocated blocks
+                let header = unsafe { arena.get_header(index) };
+                header.mtag() == MemTag::ByteArray && !header.is_movable()
+            };
+
+            if pinned {
+                // Pinned block - keep it where it is
+                if write_offset < old_offset {
+                    gaps.push((write_offset, old_offset));
+                }
+                // SAFETY: `index` is allocated and its block starts at
+                // old_offset; a pinned block is never moved, so its header
+                // and size are read in place
+                unsafe {
+                    arena.get_header_mut(index).set_gc_mark(false);
+                    write_offset = old_offset + arena.get_block_size(old_offset);
+                }
+            } else if is_marked {
+                // Live object - move it into a gap below a pinned block if
+                // it fits there, or else to the compacted region. Everything
+                // below a gap's end has been visited, so the gap is free.
+                // SAFETY: the block lies at old_offset inside the arena. The
+                // target is a gap or the compacted region, both below
+                // old_offset and inside the arena, and never overlap a pinned
+                // block, which stays put. `ptr::copy` handles the source and
+                // destination overlapping when a block slides down by less
+                // than its size.
                 unsafe {
                     let size = arena.get_block_size(old_offset);
+                    let target = if let Some(gap) = gaps.iter_mut().find(|(start, end)| end - start >= size) {
+                        gap.0 += size;
+                        gap.0 - size
+                    } else {
+                        write_offset += size;
+                        write_offset - size
+                    };
 
                     // Only move if the object isn't already at the target location
-                    if write_offset != old_offset {
+                    if target != old_offset {
                         // Move the object (header + data)
                         let src = arena.as_ptr().add(old_offset);
-                        let dst = arena.as_mut_ptr().add(write_offset);
+                        let dst = arena.as_mut_ptr().add(target);
                         core::ptr::copy(src, dst, size);
                     }
 
                     // Update the index table to point to the new location
-                    arena.update_index_offset(index, write_offset);
+                    arena.update_index_offset(index, target);
 
                     // Clear the mark bit for next GC cycle
                     let header = arena.get_header_mut(index);
                     header.set_gc_mark(false);
-
-                    write_offset += size;
                 }
             } else {
                 // Dead object - free the index
+                // SAFETY: `index` came from the index table and is visited
+                // once, so it is freed once
                 unsafe {
                     arena.free_index(index);
                 }
+                freed += 1;
             }
         }
 
         // Update the heap free pointer to the end of the compacted region
+        // SAFETY: every live block now ends at or below write_offset, the end
+        // of the last block moved or pinned
         unsafe {
             arena.set_heap_free(write_offset);
         }
+        freed
     }
 }
 
@@ -340,6 +430,83 @@ mod tests {
         // This test mainly verifies GC doesn't crash
     }
 
+    #[test]
+    fn test_gc_compacts_in_address_order() {
+        let mut arena = Arena::new(4096);
+        let mut gc = GarbageCollector::new();
+
+        // Free index 0, so the next allocation reuses it at a higher address
+        let first = arena.alloc(32, MemTag::String).unwrap();
+        let low = arena.alloc(32, MemTag::String).unwrap();
+        gc.add_root(JSValue::from_ptr(low));
+        gc.collect(&mut arena);
+        let high = arena.alloc(32, MemTag::String).unwrap();
+        assert_eq!(high, first);
+
+        unsafe {
+            arena.get_mut::<[u8; 4]>(low).copy_from_slice(b"low!");
+            arena.get_mut::<[u8; 4]>(high).copy_from_slice(b"high");
+        }
+        gc.add_root(JSValue::from_ptr(high));
+        gc.collect(&mut arena);
+
+        // Moving index 0 first must not overwrite the block below it
+        unsafe {
+            assert_eq!(arena.get::<[u8; 4]>(low), b"low!");
+            assert_eq!(arena.get::<[u8; 4]>(high), b"high");
+        }
+    }
+
+    #[test]
+    fn test_gc_pinned_code_stays_put() {
+        let mut arena = Arena::new(4096);
+        let mut gc = GarbageCollector::new();
+
+        let _garbage = arena.alloc(64, MemTag::String).unwrap();
+        let code = arena.alloc(32, MemTag::ByteArray).unwrap();
+        let live = arena.alloc(32, MemTag::String).unwrap();
+        let _more_garbage = arena.alloc(64, MemTag::String).unwrap();
+        unsafe { arena.get_mut::<[u8; 4]>(live).copy_from_slice(b"live") };
+        let code_offset = arena.get_offset(code);
+        let live_offset = arena.get_offset(live);
+        let roots = [JSValue::from_ptr(code), JSValue::from_ptr(live)];
+
+        gc.collect_with(&mut arena, &roots, true);
+
+        // The running code survives in place, and the string moves into the
+        // space the garbage below it left
+        assert_eq!(arena.get_offset(code), code_offset);
+        assert_eq!(arena.get_offset(live), Some(0));
+        assert_ne!(arena.get_offset(live), live_offset);
+        assert_eq!(unsafe { arena.get::<[u8; 4]>(live) }, b"live");
+        assert_eq!(arena.heap_usage(), code_offset.unwrap() + Arena::block_size(32));
+
+        // Once nothing runs it, the code is freed
+        gc.collect_with(&mut arena, &[JSValue::from_ptr(live)], true);
+        assert!(arena.get_offset(code).is_none());
+        assert_eq!(arena.get_offset(live), Some(0));
+        assert_eq!(arena.heap_usage(), Arena::block_size(32));
+        assert_eq!(gc.cycles(), 2);
+    }
+
+    #[test]
+    fn test_gc_movable_byte_arrays_are_not_pinned() {
+        let mut arena = Arena::new(4096);
+        let mut gc = GarbageCollector::new();
+
+        let _garbage = arena.alloc(64, MemTag::String).unwrap();
+        let data = arena.alloc(32, MemTag::ByteArray).unwrap();
+        let dead = arena.alloc(32, MemTag::ByteArray).unwrap();
+        unsafe {
+            arena.get_header_mut(data).set_movable();
+            arena.get_header_mut(dead).set_movable();
+        }
+
+        gc.collect_with(&mut arena, &[JSValue::from_ptr(data)], true);
+        assert_eq!(arena.get_offset(data), Some(0));
+        assert!(arena.get_offset(dead).is_none());
+    }
+
     #[test]
     fn test_gc_multiple_allocations() {
         let mut arena = Arena::new(4096);
@@ -1,9 +1,12 @@
 //! GC root handles for protecting values during allocation
 //!
 //! GC roots ensure that values are not collected during operations that
-//! might trigger garbage collection.
+//! might trigger garbage collection. [`Persistent`] handles keep values alive
+//! across calls for as long as the host holds on to them.
 
+use super::gc::GarbageCollector;
 use crate::value::JSValue;
+use alloc::vec::Vec;
 use core::marker::PhantomData;
 use core::ptr::NonNull;
 
@@ -78,3 +81,163 @@ impl<'ctx> GcRoot<'ctx> {
 //
 // For now, the roots will be managed manually by the Context.
 // This is safer than having Drop try to access a potentially moved Context.
+
+/// Host-held handle that keeps a value alive between calls
+///
+/// Unlike [`GcRoot`], a `Persistent` doesn't borrow the context, so timer
+/// queues and host caches can store one. Its value is read back through the
+/// context that created it, and is gone (`None`) once the handle is detached
+/// or the epoch it was created in has been retired with `bump_epoch`.
+///
+/// Handles aren't `Clone`: each one owns its root.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Persistent {
+    /// Slot in the context's table, None once detached
+    slot: Option<usize>,
+    /// Generation the slot had when this handle was made
+    generation: u32,
+}
+
+impl Persistent {
+    /// Returns true once the handle has been detached
+    ///
+    /// A handle whose epoch was retired isn't empty, but reads as `None`.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.slot.is_none()
+    }
+}
+
+/// One entry of the [`PersistentTable`]
+#[derive(Debug, Clone, Copy)]
+struct PersistentSlot {
+    /// The rooted value, None while the slot is free
+    value: Option<JSValue>,
+    /// Bumped whenever the slot is freed, so stale handles can't see a reuse
+    generation: u32,
+}
+
+/// Values rooted on behalf of [`Persistent`] handles
+#[derive(Debug, Default)]
+pub(crate) struct PersistentTable {
+    slots: Vec<PersistentSlot>,
+    /// Epoch new handles are created in
+    epoch: u32,
+}
+
+impl PersistentTable {
+    /// Roots `value` and returns a handle to it
+    pub(crate) fn insert(&mut self, gc: &mut GarbageCollector, value: JSValue) -> Persistent {
+        gc.add_root(value);
+
+        let index = match self.slots.iter().position(|slot| slot.value.is_none()) {
+            Some(index) => {
+                self.slots[index].value = Some(value);
+                index
+            }
+            None => {
+                self.slots.push(PersistentSlot { value: Some(value), generation: 0 });
+                self.slots.len() - 1
+            }
+        };
+
+        Persistent { slot: Some(index), generation: self.slots[index].generation }
+    }
+
+    /// Returns the value behind `handle`, if it's still alive
+    pub(crate) fn get(&self, handle: &Persistent) -> Option<JSValue> {
+        let slot = self.slots.get(handle.slot?)?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+        slot.value
+    }
+
+    /// Unroots the value behind `handle` and empties the handle
+    pub(crate) fn detach(&mut self, gc: &mut GarbageCollector, handle: &mut Persistent) {
+        if let (Some(index), Some(_)) = (handle.slot, self.get(handle)) {
+            self.free(gc, index);
+        }
+        handle.slot = None;
+    }
+
+    /// Unroots every value created before now and starts a new epoch
+    ///
+    /// Every live value belongs to the current epoch, since older ones were
+    /// freed by earlier bumps. Returns the new epoch.
+    pub(crate) fn bump_epoch(&mut self, gc: &mut GarbageCollector) -> u32 {
+        for index in 0..self.slots.len() {
+            self.free(gc, index);
+        }
+        self.epoch = self.epoch.wrapping_add(1);
+        self.epoch
+    }
+
+    /// Number of live handles
+    pub(crate) fn len(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.value.is_some()).count()
+    }
+
+    /// Unroots slot `index`, invalidating the handles that point at it
+    fn free(&mut self, gc: &mut GarbageCollector, index: usize) {
+        let slot = &mut self.slots[index];
+        if let Some(value) = slot.value.take() {
+            gc.remove_root(value);
+            slot.generation = slot.generation.wrapping_add(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_persistent_detach() {
+        let mut gc = GarbageCollector::new();
+        let mut table = PersistentTable::default();
+
+        let mut handle = table.insert(&mut gc, JSValue::from_int(7));
+        assert_eq!(table.get(&handle), Some(JSValue::from_int(7)));
+
+        table.detach(&mut gc, &mut handle);
+        assert!(handle.is_empty());
+        assert_eq!(table.get(&handle), None);
+        assert_eq!(table.len(), 0);
+
+        // Detaching twice is harmless
+        table.detach(&mut gc, &mut handle);
+    }
+
+    #[test]
+    fn test_persistent_slot_reuse() {
+        let mut gc = GarbageCollector::new();
+        let mut table = PersistentTable::default();
+
+        let old = table.insert(&mut gc, JSValue::from_int(1));
+        table.bump_epoch(&mut gc);
+
+        // The new handle takes over the freed slot; the old one stays dead
+        let new = table.insert(&mut gc, JSValue::from_int(2));
+        assert_eq!(table.get(&old), None);
+        assert_eq!(table.get(&new), Some(JSValue::from_int(2)));
+    }
+
+    #[test]
+    fn test_persistent_epochs() {
+        let mut gc = GarbageCollector::new();
+        let mut table = PersistentTable::default();
+
+        let first = table.insert(&mut gc, JSValue::from_int(1));
+        assert_eq!(table.bump_epoch(&mut gc), 1);
+        let second = table.insert(&mut gc, JSValue::from_int(2));
+
+        assert_eq!(table.get(&first), None);
+        assert!(!first.is_empty());
+        assert_eq!(table.get(&second), Some(JSValue::from_int(2)));
+
+        table.bump_epoch(&mut gc);
+        assert_eq!(table.get(&second), None);
+        assert_eq!(table.len(), 0);
+    }
+}
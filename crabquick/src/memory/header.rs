@@ -26,6 +26,8 @@ pub enum MemTag {
     VarRef = 8,
     /// C function data
     CFunctionData = 9,
+    /// Array class data (length + dense element storage)
+    ArrayData = 10,
     // TODO: Add more tags as needed
 }
 
@@ -34,7 +36,11 @@ pub enum MemTag {
 /// Packed into a u32:
 /// - Bits 0-3: Memory tag (MemTag, values 0-15)
 /// - Bit 4: GC mark bit
-/// - Bits 5-31: Reserved for future use (size, flags, etc.)
+/// - Bit 5: Movable flag, set on byte arrays that hold script data rather
+///   than bytecode
+/// - Bits 8-23: Allocation origin code (see `AllocOrigin`, written only
+///   with the `alloc-origin` feature)
+/// - Other bits: Reserved for future use
 ///
 /// Note: The actual size of the allocation is stored separately
 /// in the allocator's metadata or can be tracked externally.
@@ -50,6 +56,9 @@ pub struct MemBlockHeader {
 impl MemBlockHeader {
     const MTAG_MASK: u32 = 0xF;  // 4 bits for mtag (0-15)
     const GC_MARK_BIT: u32 = 1 << 4;  // Move GC mark bit to bit 4
+    const MOVABLE_BIT: u32 = 1 << 5;
+    const ORIGIN_SHIFT: u32 = 8;
+    const ORIGIN_MASK: u32 = 0xFFFF << Self::ORIGIN_SHIFT;
 
     /// Creates a new header with the specified memory tag and size
     pub fn new(mtag: MemTag, size: usize) -> Self {
@@ -100,10 +109,35 @@ impl MemBlockHeader {
         }
     }
 
-    /// Clears all flags
+    /// Returns true if a collection during a run may move or free this
+    /// block even though it is a byte array
+    #[inline]
+    pub fn is_movable(self) -> bool {
+        (self.data & Self::MOVABLE_BIT) != 0
+    }
+
+    /// Marks a byte array as data, which nothing reads through raw slices
+    #[inline]
+    pub fn set_movable(&mut self) {
+        self.data |= Self::MOVABLE_BIT;
+    }
+
+    /// Returns the allocation origin code (0 unless recorded)
+    #[inline]
+    pub fn origin_code(self) -> u16 {
+        ((self.data & Self::ORIGIN_MASK) >> Self::ORIGIN_SHIFT) as u16
+    }
+
+    /// Sets the allocation origin code
+    #[inline]
+    pub fn set_origin_code(&mut self, code: u16) {
+        self.data = (self.data & !Self::ORIGIN_MASK) | ((code as u32) << Self::ORIGIN_SHIFT);
+    }
+
+    /// Clears all flags, keeping the tag and origin
     #[inline]
     pub fn clear_flags(&mut self) {
-        self.data &= Self::MTAG_MASK;
+        self.data &= Self::MTAG_MASK | Self::ORIGIN_MASK;
     }
 }
 
@@ -141,6 +175,21 @@ mod tests {
         assert!(!header.gc_mark());
     }
 
+    #[test]
+    fn test_origin_code() {
+        let mut header = MemBlockHeader::new(MemTag::String, 64);
+        assert_eq!(header.origin_code(), 0);
+
+        header.set_origin_code(0x2AB);
+        header.set_gc_mark(true);
+        assert_eq!(header.origin_code(), 0x2AB);
+        assert_eq!(header.mtag(), MemTag::String);
+
+        header.clear_flags();
+        assert!(!header.gc_mark());
+        assert_eq!(header.origin_code(), 0x2AB);
+    }
+
     #[test]
     fn test_size() {
         let mut header = MemBlockHeader::new(MemTag::String, 256);
@@ -7,16 +7,22 @@
 //!
 //! - **Arena**: Bump allocator with heap growing upward and stack downward
 //! - **GC**: Mark-and-compact garbage collector with pointer threading
-//! - **Handles**: GC root handles for protecting values during allocation
+//! - **Handles**: GC root handles for protecting values during allocation,
+//!   and persistent handles the host can hold between calls
 //! - **Headers**: Memory block headers with metadata and GC mark bits
+//! - **Census**: Counts of heap blocks by kind, for finding what a heap grows with
 
 pub mod allocator;
+pub mod census;
 pub mod gc;
 pub mod handle;
 pub mod header;
 
 // Re-exports
-pub use allocator::{Arena, HeapIndex};
+pub use allocator::{Arena, HeapIndex, HeapSizeError};
 pub use gc::GarbageCollector;
-pub use handle::GcRoot;
+pub use handle::{GcRoot, Persistent};
 pub use header::{MemBlockHeader, MemTag};
+pub use census::CensusEntry;
+#[cfg(feature = "alloc-origin")]
+pub use census::AllocOrigin;
@@ -1,43 +1,78 @@
 //! JavaScript function implementation
 
-use crate::value::JSValue;
+use crate::value::{JSAtom, JSValue};
 use crate::context::Context;
 
 /// Native function type
 pub type NativeFn = fn(&mut Context, JSValue, &[JSValue]) -> Result<JSValue, JSValue>;
 
+/// Where a function's bytecode lives
+#[derive(Debug, Clone, Copy)]
+pub enum FunctionCode {
+    /// Copied into a JSByteArray on the context's heap
+    Heap(crate::memory::HeapIndex),
+    /// A function nested in a unit on the heap, read in place
+    HeapSlice {
+        /// The JSByteArray holding the enclosing unit
+        unit: crate::memory::HeapIndex,
+        /// Offset of the function's unit in the array
+        start: u32,
+        /// Length of the function's unit
+        len: u32,
+    },
+    /// Read in place from a `'static` script, shared by every engine running it
+    Static(&'static [u8]),
+}
+
+impl FunctionCode {
+    /// Returns the byte array the code lives in, if it's on the heap
+    pub fn heap_index(&self) -> Option<crate::memory::HeapIndex> {
+        match *self {
+            FunctionCode::Heap(index) | FunctionCode::HeapSlice { unit: index, .. } => Some(index),
+            FunctionCode::Static(_) => None,
+        }
+    }
+}
+
 /// Bytecode function object
 /// Stores a reference to compiled bytecode and metadata
 #[repr(C)]
 pub struct JSBytecodeFunction {
-    /// Index to the bytecode array in the heap
-    pub bytecode_index: crate::memory::HeapIndex,
+    /// The function's bytecode
+    pub code: FunctionCode,
     /// Number of parameters
     pub param_count: u8,
     /// Number of local variable slots (including parameters)
     pub local_count: u8,
     /// Reserved for future use
     _reserved: u16,
+    /// Own property table (`prototype`, `f.x = ...`), or null until the
+    /// first property is added
+    pub props: crate::memory::HeapIndex,
+    /// The name the function was declared with, or null if anonymous
+    pub name: JSAtom,
 }
 
 impl JSBytecodeFunction {
     /// Creates a new bytecode function
     pub fn new(
-        bytecode_index: crate::memory::HeapIndex,
+        code: FunctionCode,
         param_count: u8,
         local_count: u8,
     ) -> Self {
         JSBytecodeFunction {
-            bytecode_index,
+            code,
             param_count,
             local_count,
             _reserved: 0,
+            props: crate::memory::HeapIndex::null(),
+            name: JSAtom::null(),
         }
     }
 
-    /// Returns the bytecode index
-    pub fn bytecode_index(&self) -> crate::memory::HeapIndex {
-        self.bytecode_index
+    /// Returns the function's bytecode
+    pub fn code(&self) -> FunctionCode {
+        self.code
     }
 
     /// Returns the parameter count
@@ -123,8 +158,8 @@ impl Default for JSVarRef {
 /// to JSVarRef objects for each captured variable.
 #[repr(C)]
 pub struct JSClosure {
-    /// HeapIndex pointing to the function's bytecode (not a function table index!)
-    pub bytecode_index: crate::memory::HeapIndex,
+    /// The function's bytecode (not a function table index!)
+    pub code: FunctionCode,
     /// Number of parameters
     pub param_count: u8,
     /// Number of local variables (including parameters)
@@ -134,10 +169,35 @@ pub struct JSClosure {
     /// For named function expressions: slot index where the function should be stored
     /// 0xFF means no self-reference needed
     pub self_name_slot: u8,
+    /// Function kind flags (`JSClosure::FLAG_ARROW`)
+    pub flags: u8,
+    /// Own property table, or null until the first property is added
+    pub props: crate::memory::HeapIndex,
+    /// The name the function was declared with, or null if anonymous
+    pub name: JSAtom,
+    /// For arrow functions: the `this` of the scope that created the closure
+    pub lexical_this: JSValue,
     // Followed by: [HeapIndex; var_ref_count] - the var_refs array
 }
 
 impl JSClosure {
+    /// Set for arrow functions, which bind `this` lexically and can't be constructed
+    pub const FLAG_ARROW: u8 = 1 << 0;
+
+    /// Returns true if this closure is an arrow function
+    pub fn is_arrow(&self) -> bool {
+        self.flags & Self::FLAG_ARROW != 0
+    }
+
+    /// Returns the `this` a call should see, given the caller's receiver
+    pub fn this_for_call(&self, this_val: JSValue) -> JSValue {
+        if self.is_arrow() {
+            self.lexical_this
+        } else {
+            this_val
+        }
+    }
+
     /// Returns the size needed for a closure with N var refs
     pub fn alloc_size(var_ref_count: usize) -> usize {
         core::mem::size_of::<JSClosure>()
@@ -162,7 +222,7 @@ impl JSClosure {
 
     /// Gets the HeapIndex for a captured variable
     pub fn get_var_ref(&self, idx: usize) -> crate::memory::HeapIndex {
-        assert!(idx < self.var_ref_count as usize);
+        crate::fatal::internal_assert!(idx < self.var_ref_count as usize, "object", "Var ref index out of bounds");
         unsafe {
             *self.var_refs_ptr().add(idx)
         }
@@ -170,7 +230,7 @@ impl JSClosure {
 
     /// Sets the HeapIndex for a captured variable
     pub fn set_var_ref(&mut self, idx: usize, heap_idx: crate::memory::HeapIndex) {
-        assert!(idx < self.var_ref_count as usize);
+        crate::fatal::internal_assert!(idx < self.var_ref_count as usize, "object", "Var ref index out of bounds");
         unsafe {
             *self.var_refs_ptr_mut().add(idx) = heap_idx;
         }
@@ -180,11 +240,15 @@ impl JSClosure {
 impl Default for JSClosure {
     fn default() -> Self {
         JSClosure {
-            bytecode_index: crate::memory::HeapIndex(0),
+            code: FunctionCode::Heap(crate::memory::HeapIndex(0)),
             param_count: 0,
             local_count: 0,
             var_ref_count: 0,
             self_name_slot: 0xFF,  // 0xFF means no self-reference
+            flags: 0,
+            props: crate::memory::HeapIndex::null(),
+            name: JSAtom::null(),
+            lexical_this: JSValue::undefined(),
         }
     }
 }
@@ -195,6 +259,10 @@ pub struct JSCFunction {
     pub func_ptr: NativeFn,
     /// Argument count (for Function.length)
     pub length: u16,
+    /// Own property table, or null until the first property is added
+    pub props: crate::memory::HeapIndex,
+    /// The builtin or host name the function was installed under, or null
+    pub name: JSAtom,
 }
 
 impl JSCFunction {
@@ -203,6 +271,8 @@ impl JSCFunction {
         JSCFunction {
             func_ptr,
             length,
+            props: crate::memory::HeapIndex::null(),
+            name: JSAtom::null(),
         }
     }
 
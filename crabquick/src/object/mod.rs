@@ -14,5 +14,5 @@ pub use class::JSClassID;
 pub use object::{JSObject, JSArrayData};
 pub use property::{Property, PropertyFlags, PropertyTable, PropertyTableHeader};
 pub use array::JSArray;
-pub use function::{JSFunction, JSClosure};
+pub use function::{JSFunction, JSClosure, FunctionCode};
 pub use string::JSString;
@@ -41,6 +41,8 @@ impl JSObject {
     const FLAG_EXTENSIBLE: u32 = 1 << 8;  // Object is extensible (can add properties)
     const FLAG_SEALED: u32 = 1 << 9;      // Object is sealed (cannot add/delete properties)
     const FLAG_FROZEN: u32 = 1 << 10;     // Object is frozen (cannot modify)
+    const ERROR_KIND_SHIFT: u32 = 12;     // Error kind code + 1 (0 = not an engine error)
+    const ERROR_KIND_MASK: u32 = 0xF << 12;
 
     /// Creates a new object with the specified class ID
     #[inline]
@@ -164,6 +166,23 @@ impl JSObject {
         self.header &= !Self::FLAG_EXTENSIBLE;
     }
 
+    /// Returns the error kind code stored with [`set_error_kind_code`](Self::set_error_kind_code)
+    ///
+    /// The code lives in the header rather than a property, so scripts can
+    /// neither read nor forge it.
+    #[inline]
+    pub fn error_kind_code(&self) -> Option<u8> {
+        let stored = (self.header & Self::ERROR_KIND_MASK) >> Self::ERROR_KIND_SHIFT;
+        (stored as u8).checked_sub(1)
+    }
+
+    /// Marks the object as an error of the kind with numeric `code` (below 15)
+    #[inline]
+    pub fn set_error_kind_code(&mut self, code: u8) {
+        let stored = (u32::from(code) + 1) << Self::ERROR_KIND_SHIFT;
+        self.header = (self.header & !Self::ERROR_KIND_MASK) | (stored & Self::ERROR_KIND_MASK);
+    }
+
     /// Returns true if this is a plain object
     #[inline]
     pub fn is_plain_object(&self) -> bool {
@@ -280,6 +299,23 @@ mod tests {
         assert!(obj.is_function());
     }
 
+    #[test]
+    fn test_object_error_kind_code() {
+        let mut obj = JSObject::new_plain(JSValue::null());
+        assert_eq!(obj.error_kind_code(), None);
+
+        obj.set_error_kind_code(0);
+        assert_eq!(obj.error_kind_code(), Some(0));
+        obj.set_error_kind_code(10);
+        assert_eq!(obj.error_kind_code(), Some(10));
+
+        // The other header bits are untouched
+        obj.freeze();
+        obj.set_class_id(JSClassID::Array);
+        assert_eq!(obj.error_kind_code(), Some(10));
+        assert!(obj.is_frozen());
+    }
+
     #[test]
     fn test_object_prototype() {
         let mut obj = JSObject::new_plain(JSValue::null());
@@ -290,6 +290,11 @@ impl PropertyTableHeader {
     /// Threshold for switching from linear to hash table
     const HASH_THRESHOLD: u32 = 8;
 
+    /// Capacity of the table an object gets with its first property
+    ///
+    /// Tables double once full, so most objects stay this small.
+    pub const INITIAL_CAPACITY: u32 = 4;
+
     /// Creates a new property table header
     #[inline]
     pub fn new(capacity: u32) -> Self {
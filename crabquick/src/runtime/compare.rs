@@ -2,7 +2,10 @@
 
 use crate::value::JSValue;
 use crate::context::Context;
-use crate::runtime::conversion::to_number;
+use crate::runtime::conversion::{object_string, to_number};
+use crate::util::strtod::parse_number;
+use crate::util::Fnv64;
+use core::cmp::Ordering;
 
 /// Strict equality (===) (ES5 11.9.6)
 ///
@@ -74,95 +77,66 @@ pub fn strict_equal(ctx: &Context, left: JSValue, right: JSValue) -> bool {
 ///
 /// # Rules
 /// - Same type: use strict equality
-/// - null == undefined → true
-/// - Number == String → convert string to number
+/// - null == undefined → true, and neither equals anything else
 /// - Boolean == anything → convert boolean to number first
-/// - Object == primitive → convert object to primitive (not fully implemented)
+/// - Number == String → convert string to number
+/// - Object == Number or String → convert the object to a primitive, through
+///   the builtin `toString`, and compare again
+///
+/// NaN equals nothing, itself included.
 pub fn abstract_equal(ctx: &Context, left: JSValue, right: JSValue) -> bool {
-    // If same type, use strict equality
-    if same_type(ctx, left, right) {
+    let left_type = type_of(ctx, left);
+    let right_type = type_of(ctx, right);
+    if left_type == right_type {
         return strict_equal(ctx, left, right);
     }
 
-    // null == undefined
-    if (left.is_null() && right.is_undefined()) || (left.is_undefined() && right.is_null()) {
-        return true;
-    }
-
-    // Number == String: convert string to number
-    let left_is_num = left.to_int().is_some() || ctx.get_number(left).is_some();
-    let right_is_num = right.to_int().is_some() || ctx.get_number(right).is_some();
-    let left_is_str = ctx.get_string(left).is_some();
-    let right_is_str = ctx.get_string(right).is_some();
-
-    if left_is_num && right_is_str {
-        let left_num = to_number(ctx, left);
-        let right_num = to_number(ctx, right);
-        return compare_numbers(left_num, right_num);
-    }
-
-    if left_is_str && right_is_num {
-        let left_num = to_number(ctx, left);
-        let right_num = to_number(ctx, right);
-        return compare_numbers(left_num, right_num);
-    }
-
-    // Boolean == anything: convert boolean to number
-    if left.is_bool() {
-        let left_num = to_number(ctx, left);
-        let left_as_num = if left_num == 0.0 {
-            JSValue::from_int(0)
-        } else {
-            JSValue::from_int(1)
-        };
-        return abstract_equal(ctx, left_as_num, right);
-    }
-
-    if right.is_bool() {
-        let right_num = to_number(ctx, right);
-        let right_as_num = if right_num == 0.0 {
-            JSValue::from_int(0)
-        } else {
-            JSValue::from_int(1)
-        };
-        return abstract_equal(ctx, left, right_as_num);
+    match (left_type, right_type) {
+        (Type::Undefined | Type::Null, other) | (other, Type::Undefined | Type::Null) => {
+            matches!(other, Type::Undefined | Type::Null)
+        }
+        (Type::Boolean, _) => abstract_equal(ctx, JSValue::from_int(i32::from(left.to_bool() == Some(true))), right),
+        (_, Type::Boolean) => abstract_equal(ctx, left, JSValue::from_int(i32::from(right.to_bool() == Some(true)))),
+        (Type::Object, _) => string_equal(ctx, &object_string(ctx, left), right),
+        (_, Type::Object) => string_equal(ctx, &object_string(ctx, right), left),
+        // One number and one string
+        _ => compare_numbers(to_number(ctx, left), to_number(ctx, right)),
     }
-
-    // Object == primitive: ToPrimitive (not fully implemented)
-    // For now, objects are only equal to themselves
-    false
 }
 
-/// Checks if two values have the same type
-fn same_type(ctx: &Context, left: JSValue, right: JSValue) -> bool {
-    if left.is_undefined() && right.is_undefined() {
-        return true;
-    }
-    if left.is_null() && right.is_null() {
-        return true;
-    }
-    if left.is_bool() && right.is_bool() {
-        return true;
-    }
-
-    // Both numbers
-    let left_is_num = left.to_int().is_some() || ctx.get_number(left).is_some();
-    let right_is_num = right.to_int().is_some() || ctx.get_number(right).is_some();
-    if left_is_num && right_is_num {
-        return true;
-    }
+/// A value's type, as abstract equality tells them apart (ES5 8)
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Type {
+    Undefined,
+    Null,
+    Boolean,
+    Number,
+    String,
+    Object,
+}
 
-    // Both strings
-    if ctx.get_string(left).is_some() && ctx.get_string(right).is_some() {
-        return true;
+fn type_of(ctx: &Context, value: JSValue) -> Type {
+    if value.is_undefined() {
+        Type::Undefined
+    } else if value.is_null() {
+        Type::Null
+    } else if value.is_bool() {
+        Type::Boolean
+    } else if ctx.get_number(value).is_some() {
+        Type::Number
+    } else if ctx.get_string(value).is_some() {
+        Type::String
+    } else {
+        Type::Object
     }
+}
 
-    // Both objects
-    if left.is_ptr() && right.is_ptr() {
-        return true;
+/// Compares an object's primitive string with a number or a string
+fn string_equal(ctx: &Context, s: &str, other: JSValue) -> bool {
+    match ctx.get_string(other) {
+        Some(other) => s == other,
+        None => compare_numbers(parse_number(s).unwrap_or(f64::NAN), to_number(ctx, other)),
     }
-
-    false
 }
 
 /// Compares two numbers for equality (handles NaN)
@@ -174,16 +148,69 @@ fn compare_numbers(left: f64, right: f64) -> bool {
     left == right
 }
 
+/// Abstract relational comparison (ES5 11.8.5)
+///
+/// Two strings compare by UTF-16 code units; anything else compares as
+/// numbers. Returns None when either side converts to NaN, which makes
+/// `<`, `<=`, `>` and `>=` all false. Operands are read in place, so
+/// comparing never allocates.
+pub fn compare(ctx: &Context, left: JSValue, right: JSValue) -> Option<Ordering> {
+    if let (Some(a), Some(b)) = (left.to_int(), right.to_int()) {
+        return Some(a.cmp(&b));
+    }
+    if let (Some(a), Some(b)) = (ctx.get_string(left), ctx.get_string(right)) {
+        return Some(a.encode_utf16().cmp(b.encode_utf16()));
+    }
+    to_number(ctx, left).partial_cmp(&to_number(ctx, right))
+}
+
 /// Less than operator
 pub fn less_than(ctx: &Context, left: JSValue, right: JSValue) -> bool {
-    // Convert both to numbers
-    let left_num = to_number(ctx, left);
-    let right_num = to_number(ctx, right);
+    compare(ctx, left, right) == Some(Ordering::Less)
+}
 
-    // NaN comparisons are always false
-    if left_num.is_nan() || right_num.is_nan() {
-        return false;
+/// SameValue (ES5 9.12)
+///
+/// Like strict equality, except NaN equals itself and +0 and -0 differ.
+/// Strings compare by content; every other heap value by identity.
+pub fn same_value(ctx: &Context, left: JSValue, right: JSValue) -> bool {
+    if let (Some(l), Some(r)) = (ctx.get_number(left), ctx.get_number(right)) {
+        return number_bits(l) == number_bits(r);
     }
+    if let (Some(l), Some(r)) = (ctx.get_string(left), ctx.get_string(right)) {
+        return l == r;
+    }
+    left.as_raw() == right.as_raw()
+}
 
-    left_num < right_num
+/// Hashes a value consistently with [`same_value`]
+///
+/// Numbers hash by their value (inline and boxed forms agree), strings by
+/// their content and everything else by identity. An object's identity is
+/// its heap index, which compaction doesn't change, so the hash holds for
+/// as long as the object stays alive. Once it's collected the index may be
+/// handed to a new object.
+pub fn value_hash(ctx: &Context, value: JSValue) -> u64 {
+    let mut hash = Fnv64::new();
+    if let Some(n) = ctx.get_number(value) {
+        hash.write(&[0]);
+        hash.write(&number_bits(n).to_le_bytes());
+    } else if let Some(s) = ctx.get_string(value) {
+        hash.write(&[1]);
+        hash.write(s.as_bytes());
+    } else {
+        hash.write(&[2]);
+        hash.write(&(value.as_raw() as u64).to_le_bytes());
+    }
+    hash.finish()
+}
+
+/// Returns the bits SameValue compares a number by, with every NaN folded
+/// into one
+pub(crate) fn number_bits(n: f64) -> u64 {
+    if n.is_nan() {
+        f64::NAN.to_bits()
+    } else {
+        n.to_bits()
+    }
 }
@@ -2,6 +2,10 @@
 
 use crate::value::JSValue;
 use crate::context::Context;
+use crate::builtins::error::ErrorKind;
+
+/// Largest integer a double represents exactly (2^53 - 1)
+pub const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
 
 /// Converts a value to a number (ES5 9.3 ToNumber)
 ///
@@ -10,7 +14,8 @@ use crate::context::Context;
 /// - null → 0
 /// - true → 1, false → 0
 /// - Number → return as-is
-/// - String → parse as number (empty string → 0, "123" → 123, "abc" → NaN)
+/// - String → parse as number (empty string → 0, "123" → 123, "abc" → NaN),
+///   see [`parse_number`](crate::util::strtod::parse_number)
 /// - Object → convert to primitive first (not implemented yet)
 pub fn to_number(ctx: &Context, value: JSValue) -> f64 {
     // undefined → NaN
@@ -40,51 +45,130 @@ pub fn to_number(ctx: &Context, value: JSValue) -> f64 {
 
     // String
     if let Some(s) = ctx.get_string(value) {
-        return string_to_number(s);
+        return crate::util::strtod::parse_number(s).unwrap_or(f64::NAN);
     }
 
     // Object or other types → NaN (ToPrimitive not implemented yet)
     f64::NAN
 }
 
-/// Converts a string to a number following JavaScript rules
-fn string_to_number(s: &str) -> f64 {
-    // Trim whitespace
-    let s = s.trim();
+/// Converts a value to a 32-bit signed integer (ES5 9.5 ToInt32)
+///
+/// Converts to number first, then applies modulo 2^32 and maps to signed range
+pub fn to_int32(ctx: &Context, value: JSValue) -> i32 {
+    to_uint32(ctx, value) as i32
+}
 
-    // Empty string → 0
-    if s.is_empty() {
-        return 0.0;
+/// Converts a value to a 32-bit unsigned integer (ES5 9.6 ToUint32)
+///
+/// NaN and infinities become 0; anything else is truncated and wrapped
+/// modulo 2^32, so `-1` becomes `0xFFFFFFFF`.
+pub fn to_uint32(ctx: &Context, value: JSValue) -> u32 {
+    if let Some(i) = value.to_int() {
+        return i as u32;
     }
 
-    // Try to parse as f64
-    match s.parse::<f64>() {
-        Ok(n) => n,
-        Err(_) => {
-            // Special cases that Rust's parse doesn't handle
-            match s {
-                "Infinity" | "+Infinity" => f64::INFINITY,
-                "-Infinity" => f64::NEG_INFINITY,
-                _ => f64::NAN,
-            }
-        }
+    let number = to_number(ctx, value);
+    if !number.is_finite() {
+        return 0;
     }
+
+    // fmod keeps the result within ±2^32, where the i64 cast is exact and
+    // the u32 cast wraps negatives into range
+    libm::fmod(libm::trunc(number), 4_294_967_296.0) as i64 as u32
 }
 
-/// Converts a value to a 32-bit signed integer (ES5 9.5 ToInt32)
+/// Converts a value to an integer (ES2020 7.1.5 ToIntegerOrInfinity)
 ///
-/// Converts to number first, then applies modulo 2^32 and maps to signed range
-pub fn to_int32(ctx: &Context, value: JSValue) -> i32 {
+/// # Rules
+/// - NaN → 0
+/// - +Infinity, -Infinity → returned as-is
+/// - Anything else → truncated toward zero, with -0 becoming 0
+pub fn to_integer_or_infinity(ctx: &Context, value: JSValue) -> f64 {
+    if let Some(i) = value.to_int() {
+        return i as f64;
+    }
+
     let number = to_number(ctx, value);
+    if number.is_nan() {
+        return 0.0;
+    }
 
-    // NaN, Infinity, -Infinity → 0
-    if !number.is_finite() {
-        return 0;
+    // Adding 0 turns -0 into +0
+    libm::trunc(number) + 0.0
+}
+
+/// Converts a value to an array-like length (ES2020 7.1.20 ToLength)
+///
+/// Clamps [`to_integer_or_infinity`] into 0..=2^53-1.
+pub fn to_length(ctx: &Context, value: JSValue) -> u64 {
+    let length = to_integer_or_infinity(ctx, value);
+    if length <= 0.0 {
+        0
+    } else {
+        length.min(MAX_SAFE_INTEGER) as u64
     }
+}
 
-    // Apply modulo 2^32 and convert to signed
-    let int32bit = (number as i64) as i32;
-    int32bit
+/// Throws a TypeError for undefined and null (ES2020 7.2.1 RequireObjectCoercible)
+///
+/// `method` names the builtin in the message, e.g. `String.prototype.slice`.
+pub fn require_object_coercible(ctx: &mut Context, value: JSValue, method: &str) -> Result<JSValue, JSValue> {
+    if value.is_undefined() || value.is_null() {
+        let message = alloc::format!("{} called on null or undefined", method);
+        return Err(ctx.new_error(ErrorKind::Type, &message));
+    }
+    Ok(value)
+}
+
+/// Converts a value to an object (ES2020 7.1.18 ToObject)
+///
+/// Throws a TypeError for undefined and null. Primitives come back
+/// unchanged: property lookups on them already go through their
+/// constructor's prototype, so there's no wrapper object to build.
+pub fn to_object(ctx: &mut Context, value: JSValue) -> Result<JSValue, JSValue> {
+    if value.is_undefined() || value.is_null() {
+        return Err(ctx.new_error(ErrorKind::Type, "Cannot convert undefined or null to object"));
+    }
+    Ok(value)
+}
+
+/// Which method ToPrimitive tries first (ES5 8.12.8 [[DefaultValue]])
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreferredType {
+    /// `toString`, then `valueOf`
+    String,
+    /// `valueOf`, then `toString`; also what `+` and `==` use, having no hint
+    Number,
+}
+
+/// Converts a value to a primitive (ES5 9.1 ToPrimitive)
+///
+/// Primitives come back unchanged. An object's `toString` and `valueOf`
+/// are called in the order `hint` gives, skipping any that aren't
+/// callable, and the first primitive either returns wins. Script methods
+/// run through [`Context::call_function`], so they may throw; if neither
+/// gives a primitive, this throws a TypeError.
+pub fn to_primitive(ctx: &mut Context, value: JSValue, hint: PreferredType) -> Result<JSValue, JSValue> {
+    use crate::value::atoms;
+
+    if ctx.get_object(value).is_none() {
+        return Ok(value);
+    }
+    let methods = match hint {
+        PreferredType::String => [atoms::TO_STRING, atoms::VALUE_OF],
+        PreferredType::Number => [atoms::VALUE_OF, atoms::TO_STRING],
+    };
+    for method in methods {
+        let func = ctx.get_property(value, method).unwrap_or(JSValue::undefined());
+        if ctx.is_callable(func) {
+            let result = ctx.call_function(func, value, &[])?;
+            if ctx.get_object(result).is_none() {
+                return Ok(result);
+            }
+        }
+    }
+    Err(ctx.new_error(ErrorKind::Type, "Cannot convert object to primitive value"))
 }
 
 /// Converts a value to a string (ES5 9.8 ToString)
@@ -95,7 +179,8 @@ pub fn to_int32(ctx: &Context, value: JSValue) -> i32 {
 /// - true → "true", false → "false"
 /// - Number → format as string
 /// - String → return as-is
-/// - Object → call toString() (not implemented yet)
+/// - Object → what the builtin `toString` gives (see [`to_primitive`] for
+///   calling the object's own)
 pub fn to_string(ctx: &Context, value: JSValue) -> alloc::string::String {
     use alloc::string::ToString;
     use alloc::format;
@@ -134,34 +219,53 @@ pub fn to_string(ctx: &Context, value: JSValue) -> alloc::string::String {
         return alloc::string::String::from(s);
     }
 
-    // Object or other types → "[object Object]" (ToPrimitive not implemented yet)
-    alloc::string::String::from("[object Object]")
+    // Object or other types
+    object_string(ctx, value)
 }
 
-/// Converts a number to a string following JavaScript rules
-fn number_to_string(n: f64) -> alloc::string::String {
-    use alloc::string::ToString;
-    use alloc::format;
-
-    if n.is_nan() {
-        return alloc::string::String::from("NaN");
-    }
+/// The string an object converts to when its `toString` is the builtin one
+///
+/// This is ToPrimitive's string path without running script code: arrays
+/// join their elements with commas (null, undefined and arrays already being
+/// joined add nothing), and any other object gives `[object Object]`.
+/// Arrays nested past `Context::max_nesting_depth` add nothing either, as
+/// there's no way to throw from here.
+pub(crate) fn object_string(ctx: &Context, value: JSValue) -> alloc::string::String {
+    let mut out = alloc::string::String::new();
+    join_elements(ctx, value, &mut alloc::vec::Vec::new(), &mut out);
+    out
+}
 
-    if n.is_infinite() {
-        return if n > 0.0 {
-            alloc::string::String::from("Infinity")
-        } else {
-            alloc::string::String::from("-Infinity")
-        };
+/// Appends the string form of `value` to `out`; `seen` holds the arrays
+/// being joined around it
+fn join_elements(ctx: &Context, value: JSValue, seen: &mut alloc::vec::Vec<JSValue>, out: &mut alloc::string::String) {
+    if !ctx.is_array(value) {
+        out.push_str("[object Object]");
+        return;
     }
-
-    if n == 0.0 {
-        return alloc::string::String::from("0");
+    seen.push(value);
+    for index in 0..ctx.array_length(value).unwrap_or(0) {
+        if index > 0 {
+            out.push(',');
+        }
+        let element = crate::value::JSAtom::from_index(index).and_then(|key| ctx.get_property(value, key));
+        match element {
+            None => {}
+            Some(element) if element.is_undefined() || element.is_null() || seen.contains(&element) => {}
+            Some(element) if ctx.get_object(element).is_none() => out.push_str(&to_string(ctx, element)),
+            Some(element) if ctx.enter_nesting() => {
+                join_elements(ctx, element, seen, out);
+                ctx.leave_nesting();
+            }
+            Some(_) => {}
+        }
     }
+    seen.pop();
+}
 
-    // For now, use simple formatting
-    // A full implementation would need to handle exponential notation properly
-    format!("{}", n)
+/// Converts a number to a string following JavaScript rules
+fn number_to_string(n: f64) -> alloc::string::String {
+    crate::util::format_number(n)
 }
 
 /// Converts a value to a boolean (ES5 9.2 ToBoolean)
@@ -206,3 +310,126 @@ pub fn to_boolean(ctx: &Context, value: JSValue) -> bool {
     // Default to false for unknown types
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(ctx: &mut Context, n: f64) -> JSValue {
+        ctx.new_number(n).unwrap()
+    }
+
+    #[test]
+    fn test_to_int32_and_uint32() {
+        let mut ctx = Context::new(8192);
+
+        for (input, int32, uint32) in [
+            (f64::NAN, 0, 0),
+            (f64::INFINITY, 0, 0),
+            (-1.0, -1, u32::MAX),
+            (-1.9, -1, u32::MAX),
+            (2_147_483_648.0, i32::MIN, 0x8000_0000),
+            (4_294_967_295.0, -1, u32::MAX),
+            (4_294_967_296.0, 0, 0),
+            (4_294_967_297.5, 1, 1),
+            (-4_294_967_297.0, -1, u32::MAX),
+            (3_735_928_559.0, 0xDEAD_BEEFu32 as i32, 0xDEAD_BEEF),
+            (1e20, 1_661_992_960, 1_661_992_960),
+        ] {
+            let value = number(&mut ctx, input);
+            assert_eq!(to_int32(&ctx, value), int32, "{}", input);
+            assert_eq!(to_uint32(&ctx, value), uint32, "{}", input);
+        }
+
+        for (input, expected) in [("0xff", 255), (" 0XDEADBEEF ", 0xDEAD_BEEF), ("0b101", 5), ("0o17", 15), ("0x", 0), ("0xfg", 0), ("-0x1", 0)] {
+            let s = ctx.new_string(input).unwrap();
+            assert_eq!(to_uint32(&ctx, s), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_to_integer_or_infinity() {
+        let mut ctx = Context::new(8192);
+
+        for (input, expected) in [
+            (f64::NAN, 0.0),
+            (f64::INFINITY, f64::INFINITY),
+            (f64::NEG_INFINITY, f64::NEG_INFINITY),
+            (0.0, 0.0),
+            (-0.0, 0.0),
+            (0.5, 0.0),
+            (-0.5, 0.0),
+            (1.9, 1.0),
+            (-1.9, -1.0),
+            (-7.0, -7.0),
+            (4_294_967_297.5, 4_294_967_297.0),
+            (1e300, 1e300),
+        ] {
+            let value = number(&mut ctx, input);
+            let result = to_integer_or_infinity(&ctx, value);
+            assert_eq!(result, expected, "{}", input);
+            assert!(result.is_sign_positive() || result < 0.0, "{} gave -0", input);
+        }
+
+        assert_eq!(to_integer_or_infinity(&ctx, JSValue::from_int(-3)), -3.0);
+        assert_eq!(to_integer_or_infinity(&ctx, JSValue::undefined()), 0.0);
+        assert_eq!(to_integer_or_infinity(&ctx, JSValue::null()), 0.0);
+        assert_eq!(to_integer_or_infinity(&ctx, JSValue::bool(true)), 1.0);
+        let s = ctx.new_string(" -2.7 ").unwrap();
+        assert_eq!(to_integer_or_infinity(&ctx, s), -2.0);
+        let s = ctx.new_string("abc").unwrap();
+        assert_eq!(to_integer_or_infinity(&ctx, s), 0.0);
+    }
+
+    #[test]
+    fn test_to_length() {
+        let mut ctx = Context::new(8192);
+
+        for (input, expected) in [
+            (f64::NAN, 0),
+            (f64::INFINITY, MAX_SAFE_INTEGER as u64),
+            (f64::NEG_INFINITY, 0),
+            (-0.0, 0),
+            (-1.0, 0),
+            (-0.5, 0),
+            (2.9, 2),
+            (MAX_SAFE_INTEGER + 2.0, MAX_SAFE_INTEGER as u64),
+        ] {
+            let value = number(&mut ctx, input);
+            assert_eq!(to_length(&ctx, value), expected, "{}", input);
+        }
+
+        assert_eq!(to_length(&ctx, JSValue::from_int(5)), 5);
+        assert_eq!(to_length(&ctx, JSValue::undefined()), 0);
+    }
+
+    #[test]
+    fn test_require_object_coercible() {
+        let mut ctx = Context::new(16384);
+
+        assert_eq!(require_object_coercible(&mut ctx, JSValue::from_int(1), "f"), Ok(JSValue::from_int(1)));
+        let s = ctx.new_string("").unwrap();
+        assert_eq!(require_object_coercible(&mut ctx, s, "f"), Ok(s));
+
+        for value in [JSValue::undefined(), JSValue::null()] {
+            let err = require_object_coercible(&mut ctx, value, "String.prototype.at").unwrap_err();
+            assert_eq!(ctx.error_kind(err), Some(ErrorKind::Type));
+            let message = ctx.get_property(err, crate::value::atoms::MESSAGE).unwrap();
+            assert_eq!(ctx.get_string(message), Some("String.prototype.at called on null or undefined"));
+        }
+    }
+
+    #[test]
+    fn test_to_object() {
+        let mut ctx = Context::new(16384);
+
+        let obj = ctx.new_object().unwrap();
+        assert_eq!(to_object(&mut ctx, obj), Ok(obj));
+        assert_eq!(to_object(&mut ctx, JSValue::bool(false)), Ok(JSValue::bool(false)));
+
+        for value in [JSValue::undefined(), JSValue::null()] {
+            let err = to_object(&mut ctx, value).unwrap_err();
+            assert_eq!(ctx.error_kind(err), Some(ErrorKind::Type));
+        }
+    }
+}
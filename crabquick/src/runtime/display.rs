@@ -0,0 +1,322 @@
+//! Value display formatting
+//!
+//! Renders values the way Node's console does, for console.log and for
+//! echoing results in the CLI and REPL: `[ 1, 2, 'three' ]`,
+//! `{ a: 1, b: { c: 2 } }`, `[Function: name]`, `Uint8Array(2) [ 1, 2 ]`.
+//! Everything goes on one line.
+
+use crate::context::{Context, PropertyLookupResult};
+use crate::value::{atoms, JSAtom, JSValue};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// Nesting depth past which objects print as `[Object]` or `[Array]`
+const MAX_DEPTH: usize = 4;
+
+/// Most array elements shown before the rest are summarized
+const MAX_ARRAY_ITEMS: u32 = 100;
+
+/// Formats a value for display
+///
+/// Strings come out bare at the top level and quoted inside objects and
+/// arrays; errors print as `Name: message`.
+pub fn format_value(ctx: &Context, value: JSValue) -> String {
+    if let Some(s) = ctx.get_string(value) {
+        return String::from(s);
+    }
+    if let Some(message) = error_message(ctx, value) {
+        return message;
+    }
+
+    let mut out = String::new();
+    let mut seen = Vec::new();
+    write_value(ctx, value, 0, &mut seen, &mut out);
+    out
+}
+
+/// Formats a value the way it appears inside an array, with strings quoted
+///
+/// Tells `1` from `'1'`, and `0` from `-0`, as assertion messages need to.
+pub fn format_quoted(ctx: &Context, value: JSValue) -> String {
+    if ctx.get_number(value).is_some_and(|n| n == 0.0 && n.is_sign_negative()) {
+        return String::from("-0");
+    }
+    let mut out = String::new();
+    write_value(ctx, value, 0, &mut Vec::new(), &mut out);
+    out
+}
+
+/// Appends `value`, nested `depth` levels deep; `seen` holds the objects
+/// being printed around it, to catch cycles
+fn write_value(ctx: &Context, value: JSValue, depth: usize, seen: &mut Vec<JSValue>, out: &mut String) {
+    if value.is_undefined() {
+        out.push_str("undefined");
+    } else if value.is_null() {
+        out.push_str("null");
+    } else if let Some(b) = value.to_bool() {
+        out.push_str(if b { "true" } else { "false" });
+    } else if let Some(i) = value.to_int() {
+        out.push_str(&alloc::format!("{}", i));
+    } else if let Some(n) = ctx.get_number(value) {
+        out.push_str(&crate::util::format_number(n));
+    } else if let Some(s) = ctx.get_string(value) {
+        write_quoted(s, out);
+    } else if is_function(ctx, value) {
+        match function_name(ctx, value) {
+            Some(name) => {
+                out.push_str("[Function: ");
+                out.push_str(&name);
+                out.push(']');
+            }
+            None => out.push_str("[Function (anonymous)]"),
+        }
+    } else if let Some(message) = error_message(ctx, value) {
+        out.push('[');
+        out.push_str(&message);
+        out.push(']');
+    } else if let Some(bytes) = ctx.uint8array_data(value) {
+        write_bytes(bytes, out);
+    } else if seen.contains(&value) {
+        out.push_str("[Circular]");
+    } else if depth > MAX_DEPTH {
+        out.push_str(if ctx.is_array(value) { "[Array]" } else { "[Object]" });
+    } else {
+        seen.push(value);
+        if ctx.is_array(value) {
+            write_array(ctx, value, depth, seen, out);
+        } else {
+            write_object(ctx, value, depth, seen, out);
+        }
+        seen.pop();
+    }
+}
+
+/// Appends an array's elements, collapsing runs of holes
+fn write_array(ctx: &Context, value: JSValue, depth: usize, seen: &mut Vec<JSValue>, out: &mut String) {
+    let len = ctx.array_length(value).unwrap_or(0);
+    let shown = len.min(MAX_ARRAY_ITEMS);
+
+    let mut items = Vec::new();
+    let mut holes = 0;
+    for index in 0..shown {
+        let element = JSAtom::from_index(index).and_then(|key| ctx.get_property(value, key));
+        let Some(element) = element else {
+            holes += 1;
+            continue;
+        };
+        if holes > 0 {
+            items.push(empty_items(holes));
+            holes = 0;
+        }
+        let mut item = String::new();
+        write_value(ctx, element, depth + 1, seen, &mut item);
+        items.push(item);
+    }
+    if holes > 0 {
+        items.push(empty_items(holes));
+    }
+    if len > shown {
+        let more = len - shown;
+        items.push(alloc::format!("... {more} more item{}", if more == 1 { "" } else { "s" }));
+    }
+
+    write_list('[', ']', &items, out);
+}
+
+/// Appends a `Uint8Array` as `Uint8Array(n) [ ... ]`
+fn write_bytes(bytes: &[u8], out: &mut String) {
+    let shown = bytes.len().min(MAX_ARRAY_ITEMS as usize);
+    let mut items: Vec<String> = bytes[..shown].iter().map(|b| alloc::format!("{b}")).collect();
+    if bytes.len() > shown {
+        let more = bytes.len() - shown;
+        items.push(alloc::format!("... {more} more item{}", if more == 1 { "" } else { "s" }));
+    }
+
+    let _ = write!(out, "Uint8Array({}) ", bytes.len());
+    write_list('[', ']', &items, out);
+}
+
+/// Appends an object's own enumerable properties
+fn write_object(ctx: &Context, value: JSValue, depth: usize, seen: &mut Vec<JSValue>, out: &mut String) {
+    let mut items = Vec::new();
+    for key in ctx.own_enumerable_keys(value) {
+        let mut item = String::new();
+        write_key(ctx, key, &mut item);
+        item.push_str(": ");
+        match ctx.find_property_with_accessor(value, key) {
+            PropertyLookupResult::Value(v) => write_value(ctx, v, depth + 1, seen, &mut item),
+            PropertyLookupResult::Getter(_) => item.push_str("[Getter]"),
+            PropertyLookupResult::Setter(_) => item.push_str("[Setter]"),
+            PropertyLookupResult::GetterSetter(_, _) => item.push_str("[Getter/Setter]"),
+            PropertyLookupResult::NotFound => item.push_str("undefined"),
+        }
+        items.push(item);
+    }
+
+    write_list('{', '}', &items, out);
+}
+
+/// Appends `items` between brackets, as `[ a, b ]`, or `[]` when empty
+fn write_list(open: char, close: char, items: &[String], out: &mut String) {
+    out.push(open);
+    if !items.is_empty() {
+        out.push(' ');
+        out.push_str(&items.join(", "));
+        out.push(' ');
+    }
+    out.push(close);
+}
+
+/// Describes a run of holes in an array
+fn empty_items(count: u32) -> String {
+    alloc::format!("<{} empty item{}>", count, if count == 1 { "" } else { "s" })
+}
+
+/// Appends a property key, quoted unless it is an identifier
+fn write_key(ctx: &Context, key: JSAtom, out: &mut String) {
+    let name = ctx.atom_name(key).unwrap_or_default();
+    let mut chars = name.chars();
+    let is_identifier = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        out.push_str(&name);
+    } else {
+        write_quoted(&name, out);
+    }
+}
+
+/// Appends a string in single quotes, escaped the way a literal would be
+///
+/// Other control characters, such as an embedded NUL, come out as `\xNN`
+/// so they show up instead of reaching the terminal raw.
+fn write_quoted(s: &str, out: &mut String) {
+    out.push('\'');
+    for c in s.chars() {
+        match c {
+            '\'' => out.push_str("\\'"),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_ascii_control() => {
+                let _ = write!(out, "\\x{:02X}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('\'');
+}
+
+/// Returns true for closures, native functions and bound functions
+fn is_function(ctx: &Context, value: JSValue) -> bool {
+    ctx.is_closure(value)
+        || ctx.get_native_function(value).is_some()
+        || ctx.get_bytecode_function(value).is_some()
+        || ctx.get_property(value, atoms::IS_BOUND_FUNCTION).and_then(|v| v.to_bool()) == Some(true)
+}
+
+/// Returns a function's `name`, if it has a non-empty one
+///
+/// Functions only get a `name` property once it's read, so until then this
+/// is the name the function was created with.
+fn function_name(ctx: &Context, value: JSValue) -> Option<String> {
+    let name = match ctx.get_property(value, atoms::NAME) {
+        Some(name) => ctx.get_string(name).map(String::from),
+        None => ctx.function_name(value).and_then(|atom| ctx.atom_name(atom)).map(alloc::borrow::Cow::into_owned),
+    };
+    name.filter(|name| !name.is_empty())
+}
+
+/// Renders an error object as `Name: message`
+///
+/// Returns None for anything that doesn't have Error.prototype on its
+/// prototype chain.
+fn error_message(ctx: &Context, value: JSValue) -> Option<String> {
+    let error_proto = ctx.get_error_prototype(crate::builtins::error::ErrorType::Error);
+    if error_proto.is_null() {
+        return None;
+    }
+
+    let mut proto = ctx.get_object(value)?.prototype();
+    let mut depth = 0;
+    while proto != error_proto {
+        proto = ctx.get_object(proto)?.prototype();
+        depth += 1;
+        if depth > ctx.max_prototype_depth() {
+            return None;
+        }
+    }
+
+    let name = ctx.get_property(value, atoms::NAME).and_then(|v| ctx.get_string(v).map(String::from));
+    let message = ctx.get_property(value, atoms::MESSAGE).and_then(|v| ctx.get_string(v).map(String::from));
+    Some(match (name, message) {
+        (Some(name), Some(message)) if !message.is_empty() => alloc::format!("{}: {}", name, message),
+        (Some(name), _) => name,
+        (None, Some(message)) => message,
+        (None, None) => String::from("Error"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Engine;
+
+    fn show(source: &str) -> String {
+        let mut engine = Engine::new(65536);
+        let value = engine.eval(source).unwrap();
+        engine.value_to_string(value)
+    }
+
+    #[test]
+    fn test_format_primitives() {
+        assert_eq!(show("'top'"), "top");
+        assert_eq!(show("1.5"), "1.5");
+        assert_eq!(show("null"), "null");
+        assert_eq!(show("[true, undefined, 'it\\'s']"), "[ true, undefined, 'it\\'s' ]");
+        assert_eq!(show("'a\\0b'"), "a\0b");
+        assert_eq!(show("['a\\0b', '\\x7f']"), "[ 'a\\x00b', '\\x7F' ]");
+    }
+
+    #[test]
+    fn test_format_objects() {
+        assert_eq!(show("[1, 2, 'three']"), "[ 1, 2, 'three' ]");
+        assert_eq!(show("({ a: 1, b: { c: 2 } })"), "{ a: 1, b: { c: 2 } }");
+        assert_eq!(show("({})"), "{}");
+        assert_eq!(show("[]"), "[]");
+        assert_eq!(show("({ 'my-key': [{}] })"), "{ 'my-key': [ {} ] }");
+    }
+
+    #[test]
+    fn test_format_depth_and_cycles() {
+        assert_eq!(show("({ a: { b: { c: { d: { e: { f: 1 } } } } } })"), "{ a: { b: { c: { d: { e: [Object] } } } } }");
+        assert_eq!(show("var o = { n: 1 }; o.self = o; o"), "{ n: 1, self: [Circular] }");
+        assert_eq!(show("var a = [1]; a.push(a); a"), "[ 1, [Circular] ]");
+    }
+
+    #[test]
+    fn test_format_functions_and_errors() {
+        assert_eq!(show("(function () {})"), "[Function (anonymous)]");
+        assert_eq!(show("[function () {}]"), "[ [Function (anonymous)] ]");
+        assert_eq!(show("function foo() {} foo"), "[Function: foo]");
+        assert_eq!(show("(function bar() {})"), "[Function: bar]");
+        assert_eq!(show("function outer() { return function inner(x) { return x; }; } [outer()]"), "[ [Function: inner] ]");
+        assert_eq!(show("Math.max"), "[Function: max]");
+        assert_eq!(show("({ log: console.log })"), "{ log: [Function: log] }");
+        assert_eq!(show("new TypeError('bad')"), "TypeError: bad");
+        assert_eq!(show("({ e: new Error('x') })"), "{ e: [Error: x] }");
+    }
+
+    #[test]
+    fn test_format_uint8array() {
+        assert_eq!(show("new Uint8Array([1, 2, 3, 4])"), "Uint8Array(4) [ 1, 2, 3, 4 ]");
+        assert_eq!(show("({ buf: new Uint8Array(0) })"), "{ buf: Uint8Array(0) [] }");
+        assert_eq!(show("new Uint8Array(102)").split(", ").last(), Some("... 2 more items ]"));
+    }
+
+    #[test]
+    fn test_format_holes() {
+        assert_eq!(show("var a = [1]; a[300] = 2; a.length = 4; a"), "[ 1, <3 empty items> ]");
+    }
+}
@@ -4,87 +4,74 @@
 //! isNaN, isFinite, and global constants like undefined, NaN, Infinity.
 
 use crate::context::Context;
+use crate::runtime::conversion::to_string;
+use crate::util::strtod::{decimal_prefix_len, trim_js_whitespace_start};
 use crate::value::JSValue;
 use alloc::string::String;
 use core::str::FromStr;
 
 /// parseInt() - Parses a string and returns an integer
 ///
+/// Leading whitespace and a sign are skipped, and a `0x` prefix switches
+/// to radix 16 unless another radix was given. Digits are read up to the
+/// first character that isn't one; if there are none the result is NaN.
+///
 /// # Arguments
 ///
 /// * `ctx` - JavaScript execution context
 /// * `string` - The string to parse
-/// * `radix` - Optional radix (2-36), defaults to 10 (or 16 for "0x" prefix)
+/// * `radix` - Optional radix (2-36); 0 or None means 10 (or 16 for "0x")
 ///
 /// # Returns
 ///
 /// Parsed integer or NaN if parsing fails
 pub fn parse_int(ctx: &mut Context, string: JSValue, radix: Option<i32>) -> JSValue {
-    use crate::runtime::conversion::to_string;
-
-    // Convert to string first
     let s = to_string(ctx, string);
-    let s = s.trim_start();
-
-    // Handle empty string after trimming
-    if s.is_empty() {
-        return ctx.new_number(f64::NAN).unwrap_or(JSValue::undefined());
-    }
+    let n = parse_int_str(&s, radix.unwrap_or(0));
+    ctx.new_number(n).unwrap_or(JSValue::undefined())
+}
 
-    // Parse sign
-    let (sign, s) = if s.starts_with('-') {
-        (-1.0, &s[1..])
-    } else if s.starts_with('+') {
-        (1.0, &s[1..])
-    } else {
-        (1.0, s)
+/// The parsing half of [`parse_int`]
+fn parse_int_str(s: &str, radix: i32) -> f64 {
+    let s = trim_js_whitespace_start(s);
+    let (sign, s) = match s.as_bytes().first() {
+        Some(b'-') => (-1.0, &s[1..]),
+        Some(b'+') => (1.0, &s[1..]),
+        _ => (1.0, s),
     };
 
-    // Determine radix
-    let mut actual_radix = radix.unwrap_or(0);
-    let mut s = s;
-
-    // Handle "0x" or "0X" prefix for hex
-    if actual_radix == 0 || actual_radix == 16 {
-        if s.len() >= 2 && (s.starts_with("0x") || s.starts_with("0X")) {
-            actual_radix = 16;
-            s = &s[2..];
-        }
-    }
-
-    // Default to radix 10 if not specified
-    if actual_radix == 0 {
-        actual_radix = 10;
-    }
-
-    // Validate radix range
-    if actual_radix < 2 || actual_radix > 36 {
-        return ctx.new_number(f64::NAN).unwrap_or(JSValue::undefined());
-    }
-
-    // Parse digits until we hit an invalid character
-    let mut result = 0i64;
-    let mut parsed_any = false;
-
-    for c in s.chars() {
-        if let Some(digit) = c.to_digit(actual_radix as u32) {
-            result = result.saturating_mul(actual_radix as i64).saturating_add(digit as i64);
-            parsed_any = true;
-        } else {
-            break; // Stop at first invalid character (JavaScript behavior)
-        }
-    }
+    let (radix, s) = match radix {
+        0 | 16 => match s.get(..2) {
+            Some("0x" | "0X") => (16, &s[2..]),
+            _ => (if radix == 0 { 10 } else { radix as u32 }, s),
+        },
+        2..=36 => (radix as u32, s),
+        _ => return f64::NAN,
+    };
 
-    // If no valid digits were parsed, return NaN
-    if !parsed_any {
-        return ctx.new_number(f64::NAN).unwrap_or(JSValue::undefined());
+    let len = s.bytes().take_while(|b| (*b as char).is_digit(radix)).count();
+    if len == 0 {
+        return f64::NAN;
     }
+    let digits = &s[..len];
 
-    ctx.new_number(result as f64 * sign).unwrap_or(JSValue::undefined())
+    // Decimal digits go through the float parser so long ones round correctly
+    let n = if radix == 10 {
+        f64::from_str(digits).unwrap_or(f64::NAN)
+    } else {
+        digits.chars().fold(0.0, |acc, c| {
+            acc * f64::from(radix) + f64::from(c.to_digit(radix).unwrap_or(0))
+        })
+    };
+    sign * n
 }
 
 /// parseFloat() - Parses a string and returns a floating point number
 ///
+/// Leading whitespace is skipped and the longest decimal literal (or
+/// `Infinity`) at the start is read; the rest is ignored. The result is
+/// NaN if there's no number at the start.
+///
 /// # Arguments
 ///
 /// * `ctx` - JavaScript execution context
@@ -94,75 +81,28 @@ pub fn parse_int(ctx: &mut Context, string: JSValue, radix: Option<i32>) -> JSVa
 ///
 /// Parsed number or NaN if parsing fails
 pub fn parse_float(ctx: &mut Context, string: JSValue) -> JSValue {
-    use crate::runtime::conversion::to_string;
-
-    // Convert to string first
     let s = to_string(ctx, string);
-    let s = s.trim_start();
-
-    // Handle empty string
-    if s.is_empty() {
-        return ctx.new_number(f64::NAN).unwrap_or(JSValue::undefined());
-    }
-
-    // Try to parse as much as possible (JavaScript parseFloat is lenient)
-    // It parses until it hits an invalid character
-    let mut end_idx = 0;
-    let mut has_dot = false;
-    let mut has_e = false;
-    let mut chars = s.chars().peekable();
-
-    // Handle sign
-    if let Some(&c) = chars.peek() {
-        if c == '+' || c == '-' {
-            end_idx += 1;
-            chars.next();
-        }
-    }
-
-    // Parse number
-    while let Some(&c) = chars.peek() {
-        if c.is_ascii_digit() {
-            end_idx += 1;
-            chars.next();
-        } else if c == '.' && !has_dot && !has_e {
-            has_dot = true;
-            end_idx += 1;
-            chars.next();
-        } else if (c == 'e' || c == 'E') && !has_e {
-            has_e = true;
-            end_idx += 1;
-            chars.next();
-            // Handle sign after 'e'
-            if let Some(&next_c) = chars.peek() {
-                if next_c == '+' || next_c == '-' {
-                    end_idx += 1;
-                    chars.next();
-                }
-            }
-        } else {
-            break;
-        }
-    }
+    let n = parse_float_str(&s);
+    ctx.new_number(n).unwrap_or(JSValue::undefined())
+}
 
-    // Parse the valid portion
-    let parse_str = &s[..end_idx];
-    if parse_str.is_empty() || parse_str == "+" || parse_str == "-" {
-        return ctx.new_number(f64::NAN).unwrap_or(JSValue::undefined());
+/// The parsing half of [`parse_float`]
+fn parse_float_str(s: &str) -> f64 {
+    let s = trim_js_whitespace_start(s);
+    let len = decimal_prefix_len(s);
+    if len > 0 {
+        return f64::from_str(&s[..len]).unwrap_or(f64::NAN);
     }
 
-    match f64::from_str(parse_str) {
-        Ok(n) => ctx.new_number(n).unwrap_or(JSValue::undefined()),
-        Err(_) => {
-            // Handle special JavaScript values
-            if s.starts_with("Infinity") || s.starts_with("+Infinity") {
-                ctx.new_number(f64::INFINITY).unwrap_or(JSValue::undefined())
-            } else if s.starts_with("-Infinity") {
-                ctx.new_number(f64::NEG_INFINITY).unwrap_or(JSValue::undefined())
-            } else {
-                ctx.new_number(f64::NAN).unwrap_or(JSValue::undefined())
-            }
-        }
+    let (sign, rest) = match s.as_bytes().first() {
+        Some(b'-') => (-1.0, &s[1..]),
+        Some(b'+') => (1.0, &s[1..]),
+        _ => (1.0, s),
+    };
+    if rest.starts_with("Infinity") {
+        sign * f64::INFINITY
+    } else {
+        f64::NAN
     }
 }
 
@@ -400,6 +340,35 @@ mod tests {
         assert_eq!(ctx.get_number(result), Some(-2.5));
     }
 
+    #[test]
+    fn test_parse_int_edge_cases() {
+        assert_eq!(parse_int_str("  0x1F", 0), 31.0);
+        assert_eq!(parse_int_str("-0X1f", 16), -31.0);
+        assert_eq!(parse_int_str("0x1F", 10), 0.0);
+        assert_eq!(parse_int_str("\u{FEFF}\n+12px", 0), 12.0);
+        assert_eq!(parse_int_str("ff", 16), 255.0);
+        assert_eq!(parse_int_str("z", 36), 35.0);
+        assert_eq!(parse_int_str("123456789012345678901234567890", 0), 1.2345678901234568e29);
+        assert!(parse_int_str("-0", 0).is_sign_negative());
+        for nothing in ["", "  ", "-", "0x", "px", "z"] {
+            assert!(parse_int_str(nothing, 0).is_nan(), "{:?}", nothing);
+        }
+        assert!(parse_int_str("1", 37).is_nan());
+        assert!(parse_int_str("1", 1).is_nan());
+    }
+
+    #[test]
+    fn test_parse_float_edge_cases() {
+        assert_eq!(parse_float_str("  -1.5e3abc"), -1500.0);
+        assert_eq!(parse_float_str(".5"), 0.5);
+        assert_eq!(parse_float_str("1e"), 1.0);
+        assert_eq!(parse_float_str("+3."), 3.0);
+        assert_eq!(parse_float_str("-Infinityx"), f64::NEG_INFINITY);
+        assert!(parse_float_str(".").is_nan());
+        assert!(parse_float_str("e5").is_nan());
+        assert!(parse_float_str("infinity").is_nan());
+    }
+
     #[test]
     fn test_is_nan() {
         let mut ctx = Context::new(4096);
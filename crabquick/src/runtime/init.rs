@@ -5,11 +5,57 @@
 //! global functions.
 
 use crate::context::Context;
-use crate::value::{JSValue, JSAtom};
+use crate::value::{atoms, JSValue, JSAtom};
 use crate::object::PropertyFlags;
 use crate::builtins;
 use alloc::string::ToString;
 
+/// Heap bytes [`init_runtime`] allocates on top of the global object
+///
+/// Measured per builtin group on a 64-bit target; `test_runtime_heap_size`
+/// fails if the builtins outgrow it.
+pub const RUNTIME_HEAP_SIZE: usize = 8_424
+    + if cfg!(feature = "builtin-math") { 1_664 } else { 0 }
+    + if cfg!(feature = "builtin-json") { 312 } else { 0 }
+    + if cfg!(feature = "builtin-string-extras") { 1_496 } else { 0 }
+    + if cfg!(feature = "builtin-console") { 392 } else { 0 }
+    + if cfg!(feature = "builtin-typed-array") { 352 } else { 0 }
+    + if cfg!(feature = "builtin-date") { 176 } else { 0 };
+
+/// Properties [`init_runtime`] installs on the global object
+///
+/// The default for [`ContextConfig::expected_globals`], so the global table
+/// is allocated at its final size; `test_builtin_globals` fails if the
+/// builtins outgrow it.
+///
+/// [`ContextConfig::expected_globals`]: crate::ContextConfig::expected_globals
+pub const BUILTIN_GLOBALS: usize = 24
+    + if cfg!(feature = "builtin-math") { 1 } else { 0 }
+    + if cfg!(feature = "builtin-json") { 1 } else { 0 }
+    + if cfg!(feature = "builtin-console") { 1 } else { 0 }
+    + if cfg!(feature = "builtin-typed-array") { 1 } else { 0 }
+    + if cfg!(feature = "builtin-date") { 1 } else { 0 };
+
+/// Extends the `engine` object before it is frozen
+///
+/// Gets the object itself; hosts add their own entries with
+/// [`Context::add_property`], such as a board revision or the sensors
+/// wired up.
+pub type EngineInfoHook = fn(&mut Context, JSValue) -> Result<(), JSValue>;
+
+/// Entries of `engine.features`: optional builtins, then language
+/// capabilities the engine doesn't have yet
+const FEATURES: [(&str, bool); 8] = [
+    ("math", cfg!(feature = "builtin-math")),
+    ("json", cfg!(feature = "builtin-json")),
+    ("stringExtras", cfg!(feature = "builtin-string-extras")),
+    ("console", cfg!(feature = "builtin-console")),
+    ("typedArrays", cfg!(feature = "builtin-typed-array")),
+    ("date", cfg!(feature = "builtin-date")),
+    ("regexp", false),
+    ("promises", false),
+];
+
 /// Initialize the JavaScript runtime environment
 ///
 /// This function sets up the global object with all built-in constructors,
@@ -29,6 +75,12 @@ use alloc::string::ToString;
 /// built-in objects. Full ECMAScript compliance would require much more
 /// extensive initialization.
 pub fn init_runtime(ctx: &mut Context) -> Result<JSValue, JSValue> {
+    init_runtime_with(ctx, None)
+}
+
+/// Initialize the JavaScript runtime environment, letting `engine_info`
+/// extend the `engine` object before it is frozen
+pub fn init_runtime_with(ctx: &mut Context, engine_info: Option<EngineInfoHook>) -> Result<JSValue, JSValue> {
     // Get the global object (should already be created in Context::new())
     let global = ctx.global_object();
     if global.is_null() {
@@ -47,37 +99,54 @@ pub fn init_runtime(ctx: &mut Context) -> Result<JSValue, JSValue> {
     install_function_constructor(ctx, global)?;
 
     // Install Math object
+    #[cfg(feature = "builtin-math")]
     install_math_object(ctx, global)?;
 
     // Install JSON object
+    #[cfg(feature = "builtin-json")]
     install_json_object(ctx, global)?;
 
+    // Install Uint8Array
+    #[cfg(feature = "builtin-typed-array")]
+    install_uint8array_constructor(ctx, global)?;
+
+    // Install Date
+    #[cfg(feature = "builtin-date")]
+    install_date_object(ctx, global)?;
+
     // Install Error constructors
     install_error_constructors(ctx, global)?;
 
+    // Reserve the out-of-memory error while there's still room for it
+    ctx.preallocate_out_of_memory_error()?;
+
     // Install console object
+    #[cfg(feature = "builtin-console")]
     install_console_object(ctx, global)?;
 
     // Install global functions
     install_global_functions(ctx, global)?;
 
+    // Install the engine object
+    install_engine_object(ctx, global, engine_info)?;
+
     Ok(global)
 }
 
 /// Install global constants (undefined, NaN, Infinity)
 fn install_global_constants(ctx: &mut Context, global: JSValue) -> Result<(), JSValue> {
     // undefined
-    set_property(ctx, global, "undefined", JSValue::undefined())?;
+    set_property(ctx, global, atoms::UNDEFINED, JSValue::undefined())?;
 
     // NaN
     let nan = ctx.new_number(f64::NAN)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "NaN", nan)?;
+    set_property(ctx, global, atoms::NAN, nan)?;
 
     // Infinity
     let infinity = ctx.new_number(f64::INFINITY)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "Infinity", infinity)?;
+    set_property(ctx, global, atoms::INFINITY, infinity)?;
 
     Ok(())
 }
@@ -96,54 +165,53 @@ fn install_object_constructor(ctx: &mut Context, global: JSValue) -> Result<(),
     // Install Object.prototype methods
     let has_own_prop_fn = ctx.new_native_function(native_functions::object_has_own_property_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, object_proto, "hasOwnProperty", has_own_prop_fn)?;
+    set_property(ctx, object_proto, atoms::HAS_OWN_PROPERTY, has_own_prop_fn)?;
 
     let to_string_fn = ctx.new_native_function(native_functions::object_to_string_native, 0)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, object_proto, "toString", to_string_fn)?;
+    set_property(ctx, object_proto, atoms::TO_STRING, to_string_fn)?;
 
     // Create Object constructor (now inherits from Object.prototype)
-    let object_ctor = ctx.new_object()
-        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    let object_ctor = new_sized_object(ctx, 9)?;
 
     // Set Object.prototype
-    set_property(ctx, object_ctor, "prototype", object_proto)?;
+    set_property(ctx, object_ctor, atoms::PROTOTYPE, object_proto)?;
 
     // Install Object static methods
     let keys_fn = ctx.new_native_function(native_functions::object_keys_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, object_ctor, "keys", keys_fn)?;
+    set_property(ctx, object_ctor, atoms::KEYS, keys_fn)?;
 
     let values_fn = ctx.new_native_function(native_functions::object_values_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, object_ctor, "values", values_fn)?;
+    set_property(ctx, object_ctor, atoms::VALUES, values_fn)?;
 
     let entries_fn = ctx.new_native_function(native_functions::object_entries_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, object_ctor, "entries", entries_fn)?;
+    set_property(ctx, object_ctor, atoms::ENTRIES, entries_fn)?;
 
     let assign_fn = ctx.new_native_function(native_functions::object_assign_native, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, object_ctor, "assign", assign_fn)?;
+    set_property(ctx, object_ctor, atoms::ASSIGN, assign_fn)?;
 
     let create_fn = ctx.new_native_function(native_functions::object_create_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, object_ctor, "create", create_fn)?;
+    set_property(ctx, object_ctor, atoms::CREATE, create_fn)?;
 
     let get_proto_fn = ctx.new_native_function(native_functions::object_get_prototype_of_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, object_ctor, "getPrototypeOf", get_proto_fn)?;
+    set_property(ctx, object_ctor, atoms::GET_PROTOTYPE_OF, get_proto_fn)?;
 
     let set_proto_fn = ctx.new_native_function(native_functions::object_set_prototype_of_native, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, object_ctor, "setPrototypeOf", set_proto_fn)?;
+    set_property(ctx, object_ctor, atoms::SET_PROTOTYPE_OF, set_proto_fn)?;
 
     let define_prop_fn = ctx.new_native_function(native_functions::object_define_property_native, 3)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, object_ctor, "defineProperty", define_prop_fn)?;
+    set_property(ctx, object_ctor, atoms::DEFINE_PROPERTY, define_prop_fn)?;
 
     // Set Object on global
-    set_property(ctx, global, "Object", object_ctor)?;
+    set_property(ctx, global, atoms::OBJECT, object_ctor)?;
 
     Ok(())
 }
@@ -153,117 +221,121 @@ fn install_array_constructor(ctx: &mut Context, global: JSValue) -> Result<(), J
     use crate::builtins::native_functions;
 
     // Create Array.prototype
-    let array_proto = ctx.new_object()
-        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    let array_proto = new_sized_object(ctx, 24)?;
+    ctx.set_array_prototype(array_proto);
 
     // Install Array.prototype methods
     let push_fn = ctx.new_native_function(native_functions::array_push_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "push", push_fn)?;
+    set_property(ctx, array_proto, atoms::PUSH, push_fn)?;
 
     let pop_fn = ctx.new_native_function(native_functions::array_pop_native, 0)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "pop", pop_fn)?;
+    set_property(ctx, array_proto, atoms::POP, pop_fn)?;
 
     let shift_fn = ctx.new_native_function(native_functions::array_shift_native, 0)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "shift", shift_fn)?;
+    set_property(ctx, array_proto, atoms::SHIFT, shift_fn)?;
 
     let unshift_fn = ctx.new_native_function(native_functions::array_unshift_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "unshift", unshift_fn)?;
+    set_property(ctx, array_proto, atoms::UNSHIFT, unshift_fn)?;
 
     let slice_fn = ctx.new_native_function(native_functions::array_slice_native, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "slice", slice_fn)?;
+    set_property(ctx, array_proto, atoms::SLICE, slice_fn)?;
 
     let splice_fn = ctx.new_native_function(native_functions::array_splice_native, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "splice", splice_fn)?;
+    set_property(ctx, array_proto, atoms::SPLICE, splice_fn)?;
 
     let concat_fn = ctx.new_native_function(native_functions::array_concat_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "concat", concat_fn)?;
+    set_property(ctx, array_proto, atoms::CONCAT, concat_fn)?;
 
     let index_of_fn = ctx.new_native_function(native_functions::array_index_of_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "indexOf", index_of_fn)?;
+    set_property(ctx, array_proto, atoms::INDEX_OF, index_of_fn)?;
 
     let includes_fn = ctx.new_native_function(native_functions::array_includes_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "includes", includes_fn)?;
+    set_property(ctx, array_proto, atoms::INCLUDES, includes_fn)?;
 
     let join_fn = ctx.new_native_function(native_functions::array_join_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "join", join_fn)?;
+    set_property(ctx, array_proto, atoms::JOIN, join_fn)?;
 
     let reverse_fn = ctx.new_native_function(native_functions::array_reverse_native, 0)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "reverse", reverse_fn)?;
+    set_property(ctx, array_proto, atoms::REVERSE, reverse_fn)?;
 
     // Callback methods
     let for_each_fn = ctx.new_native_function(native_functions::array_for_each_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "forEach", for_each_fn)?;
+    set_property(ctx, array_proto, atoms::FOR_EACH, for_each_fn)?;
 
     let map_fn = ctx.new_native_function(native_functions::array_map_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "map", map_fn)?;
+    set_property(ctx, array_proto, atoms::MAP, map_fn)?;
 
     let filter_fn = ctx.new_native_function(native_functions::array_filter_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "filter", filter_fn)?;
+    set_property(ctx, array_proto, atoms::FILTER, filter_fn)?;
 
     let reduce_fn = ctx.new_native_function(native_functions::array_reduce_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "reduce", reduce_fn)?;
+    set_property(ctx, array_proto, atoms::REDUCE, reduce_fn)?;
 
     let find_fn = ctx.new_native_function(native_functions::array_find_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "find", find_fn)?;
+    set_property(ctx, array_proto, atoms::FIND, find_fn)?;
 
     let find_index_fn = ctx.new_native_function(native_functions::array_find_index_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "findIndex", find_index_fn)?;
+    set_property(ctx, array_proto, atoms::FIND_INDEX, find_index_fn)?;
 
     let some_fn = ctx.new_native_function(native_functions::array_some_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "some", some_fn)?;
+    set_property(ctx, array_proto, atoms::SOME, some_fn)?;
 
     let every_fn = ctx.new_native_function(native_functions::array_every_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "every", every_fn)?;
+    set_property(ctx, array_proto, atoms::EVERY, every_fn)?;
 
     let last_index_of_fn = ctx.new_native_function(native_functions::array_last_index_of_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "lastIndexOf", last_index_of_fn)?;
+    set_property(ctx, array_proto, atoms::LAST_INDEX_OF, last_index_of_fn)?;
 
     let reduce_right_fn = ctx.new_native_function(native_functions::array_reduce_right_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "reduceRight", reduce_right_fn)?;
+    set_property(ctx, array_proto, atoms::REDUCE_RIGHT, reduce_right_fn)?;
 
     let sort_fn = ctx.new_native_function(native_functions::array_sort_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "sort", sort_fn)?;
+    set_property(ctx, array_proto, atoms::SORT, sort_fn)?;
+
+    let at_fn = ctx.new_native_function(native_functions::array_at_native, 1)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, array_proto, atoms::AT, at_fn)?;
 
     let to_string_fn = ctx.new_native_function(native_functions::array_to_string_native, 0)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_proto, "toString", to_string_fn)?;
+    set_property(ctx, array_proto, atoms::TO_STRING, to_string_fn)?;
 
     // Create Array constructor (placeholder)
     let array_ctor = ctx.new_object()
         .map_err(|_| make_error(ctx, "Out of memory"))?;
 
     // Set Array.prototype
-    set_property(ctx, array_ctor, "prototype", array_proto)?;
+    set_property(ctx, array_ctor, atoms::PROTOTYPE, array_proto)?;
 
     // Install Array.isArray as static method on constructor
     let is_array_fn = ctx.new_native_function(native_functions::array_is_array_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, array_ctor, "isArray", is_array_fn)?;
+    set_property(ctx, array_ctor, atoms::IS_ARRAY, is_array_fn)?;
 
     // Set Array on global
-    set_property(ctx, global, "Array", array_ctor)?;
+    set_property(ctx, global, atoms::ARRAY, array_ctor)?;
 
     Ok(())
 }
@@ -273,104 +345,134 @@ fn install_string_constructor(ctx: &mut Context, global: JSValue) -> Result<(),
     use crate::builtins::native_functions;
 
     // Create String.prototype
-    let string_proto = ctx.new_object()
-        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    let string_proto = new_sized_object(ctx, 5 + STRING_EXTRAS.0)?;
 
     // Install String.prototype methods
     let char_at_fn = ctx.new_native_function(native_functions::string_char_at_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "charAt", char_at_fn)?;
+    set_property(ctx, string_proto, atoms::CHAR_AT, char_at_fn)?;
 
     let char_code_at_fn = ctx.new_native_function(native_functions::string_char_code_at_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "charCodeAt", char_code_at_fn)?;
+    set_property(ctx, string_proto, atoms::CHAR_CODE_AT, char_code_at_fn)?;
 
     let slice_fn = ctx.new_native_function(native_functions::string_slice_native, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "slice", slice_fn)?;
+    set_property(ctx, string_proto, atoms::SLICE, slice_fn)?;
 
     let substring_fn = ctx.new_native_function(native_functions::string_substring_native, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "substring", substring_fn)?;
+    set_property(ctx, string_proto, atoms::SUBSTRING, substring_fn)?;
 
     let index_of_fn = ctx.new_native_function(native_functions::string_index_of_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "indexOf", index_of_fn)?;
+    set_property(ctx, string_proto, atoms::INDEX_OF, index_of_fn)?;
+
+    // Create String constructor, which scripts call to convert values
+    let string_ctor = ctx.new_native_function(native_functions::string_constructor_native, 1)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    ctx.reserve_properties(string_ctor, 1 + STRING_EXTRAS.1)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+
+    #[cfg(feature = "builtin-string-extras")]
+    install_string_extras(ctx, string_proto, string_ctor)?;
+
+    // Set String.prototype
+    set_property(ctx, string_ctor, atoms::PROTOTYPE, string_proto)?;
+
+    // Set String on global
+    set_property(ctx, global, atoms::STRING, string_ctor)?;
+
+    Ok(())
+}
+
+/// Methods [`install_string_extras`] adds to String.prototype and String
+const STRING_EXTRAS: (usize, usize) = if cfg!(feature = "builtin-string-extras") { (17, 2) } else { (0, 0) };
+
+/// Install the String methods behind the `builtin-string-extras` feature
+///
+/// Everything past charAt/charCodeAt/indexOf/slice/substring, plus the
+/// String.fromCharCode / String.fromCodePoint statics.
+#[cfg(feature = "builtin-string-extras")]
+fn install_string_extras(ctx: &mut Context, proto: JSValue, ctor: JSValue) -> Result<(), JSValue> {
+    use crate::builtins::native_functions;
+
+    let at_fn = ctx.new_native_function(native_functions::string_at_native, 1)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, proto, atoms::AT, at_fn)?;
 
     let last_index_of_fn = ctx.new_native_function(native_functions::string_last_index_of_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "lastIndexOf", last_index_of_fn)?;
+    set_property(ctx, proto, atoms::LAST_INDEX_OF, last_index_of_fn)?;
 
     let to_lower_case_fn = ctx.new_native_function(native_functions::string_to_lower_case_native, 0)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "toLowerCase", to_lower_case_fn)?;
+    set_property(ctx, proto, atoms::TO_LOWER_CASE, to_lower_case_fn)?;
 
     let to_upper_case_fn = ctx.new_native_function(native_functions::string_to_upper_case_native, 0)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "toUpperCase", to_upper_case_fn)?;
+    set_property(ctx, proto, atoms::TO_UPPER_CASE, to_upper_case_fn)?;
 
     let split_fn = ctx.new_native_function(native_functions::string_split_native, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "split", split_fn)?;
+    set_property(ctx, proto, atoms::SPLIT, split_fn)?;
 
     let trim_fn = ctx.new_native_function(native_functions::string_trim_native, 0)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "trim", trim_fn)?;
+    set_property(ctx, proto, atoms::TRIM, trim_fn)?;
 
     let trim_start_fn = ctx.new_native_function(native_functions::string_trim_start_native, 0)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "trimStart", trim_start_fn)?;
+    set_property(ctx, proto, atoms::TRIM_START, trim_start_fn)?;
 
     let trim_end_fn = ctx.new_native_function(native_functions::string_trim_end_native, 0)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "trimEnd", trim_end_fn)?;
+    set_property(ctx, proto, atoms::TRIM_END, trim_end_fn)?;
 
     let replace_fn = ctx.new_native_function(native_functions::string_replace_native, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "replace", replace_fn)?;
+    set_property(ctx, proto, atoms::REPLACE, replace_fn)?;
 
     let replace_all_fn = ctx.new_native_function(native_functions::string_replace_all_native, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "replaceAll", replace_all_fn)?;
+    set_property(ctx, proto, atoms::REPLACE_ALL, replace_all_fn)?;
 
     let includes_fn = ctx.new_native_function(native_functions::string_includes_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "includes", includes_fn)?;
+    set_property(ctx, proto, atoms::INCLUDES, includes_fn)?;
 
     let starts_with_fn = ctx.new_native_function(native_functions::string_starts_with_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "startsWith", starts_with_fn)?;
+    set_property(ctx, proto, atoms::STARTS_WITH, starts_with_fn)?;
 
     let ends_with_fn = ctx.new_native_function(native_functions::string_ends_with_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "endsWith", ends_with_fn)?;
+    set_property(ctx, proto, atoms::ENDS_WITH, ends_with_fn)?;
 
-    let concat_fn = ctx.new_native_function(native_functions::string_concat_native, 1)
+    let pad_start_fn = ctx.new_native_function(native_functions::string_pad_start_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "concat", concat_fn)?;
+    set_property(ctx, proto, atoms::PAD_START, pad_start_fn)?;
 
-    let code_point_at_fn = ctx.new_native_function(native_functions::string_code_point_at_native, 1)
+    let pad_end_fn = ctx.new_native_function(native_functions::string_pad_end_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_proto, "codePointAt", code_point_at_fn)?;
+    set_property(ctx, proto, atoms::PAD_END, pad_end_fn)?;
 
-    // Create String constructor
-    let string_ctor = ctx.new_object()
+    let concat_fn = ctx.new_native_function(native_functions::string_concat_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, proto, atoms::CONCAT, concat_fn)?;
 
-    // Add static methods to String constructor
+    let code_point_at_fn = ctx.new_native_function(native_functions::string_code_point_at_native, 1)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, proto, atoms::CODE_POINT_AT, code_point_at_fn)?;
+
+    // Static methods on the String constructor
     let from_char_code_fn = ctx.new_native_function(native_functions::string_from_char_code_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_ctor, "fromCharCode", from_char_code_fn)?;
+    set_property(ctx, ctor, atoms::FROM_CHAR_CODE, from_char_code_fn)?;
 
     let from_code_point_fn = ctx.new_native_function(native_functions::string_from_code_point_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, string_ctor, "fromCodePoint", from_code_point_fn)?;
-
-    // Set String.prototype
-    set_property(ctx, string_ctor, "prototype", string_proto)?;
-
-    // Set String on global
-    set_property(ctx, global, "String", string_ctor)?;
+    set_property(ctx, ctor, atoms::FROM_CODE_POINT, from_code_point_fn)?;
 
     Ok(())
 }
@@ -386,64 +488,63 @@ fn install_number_constructor(ctx: &mut Context, global: JSValue) -> Result<(),
     // Install Number.prototype methods
     let to_fixed_fn = ctx.new_native_function(native_functions::number_to_fixed_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, number_proto, "toFixed", to_fixed_fn)?;
+    set_property(ctx, number_proto, atoms::TO_FIXED, to_fixed_fn)?;
 
     let to_string_fn = ctx.new_native_function(native_functions::number_to_string_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, number_proto, "toString", to_string_fn)?;
+    set_property(ctx, number_proto, atoms::TO_STRING, to_string_fn)?;
 
     // Create Number constructor
-    let number_ctor = ctx.new_object()
-        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    let number_ctor = new_sized_object(ctx, 11)?;
 
     // Set Number.prototype
-    set_property(ctx, number_ctor, "prototype", number_proto)?;
+    set_property(ctx, number_ctor, atoms::PROTOTYPE, number_proto)?;
 
     // Install Number constants
     let max_value = ctx.new_number(f64::MAX)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, number_ctor, "MAX_VALUE", max_value)?;
+    set_property(ctx, number_ctor, atoms::MAX_VALUE, max_value)?;
 
     let min_value = ctx.new_number(f64::MIN_POSITIVE)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, number_ctor, "MIN_VALUE", min_value)?;
+    set_property(ctx, number_ctor, atoms::MIN_VALUE, min_value)?;
 
     let nan_value = ctx.new_number(f64::NAN)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, number_ctor, "NaN", nan_value)?;
+    set_property(ctx, number_ctor, atoms::NAN, nan_value)?;
 
     let pos_inf = ctx.new_number(f64::INFINITY)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, number_ctor, "POSITIVE_INFINITY", pos_inf)?;
+    set_property(ctx, number_ctor, atoms::POSITIVE_INFINITY, pos_inf)?;
 
     let neg_inf = ctx.new_number(f64::NEG_INFINITY)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, number_ctor, "NEGATIVE_INFINITY", neg_inf)?;
+    set_property(ctx, number_ctor, atoms::NEGATIVE_INFINITY, neg_inf)?;
 
     // Install Number static methods
     let is_nan_fn = ctx.new_native_function(native_functions::number_is_nan_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, number_ctor, "isNaN", is_nan_fn)?;
+    set_property(ctx, number_ctor, atoms::IS_NA_N, is_nan_fn)?;
 
     let is_finite_fn = ctx.new_native_function(native_functions::number_is_finite_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, number_ctor, "isFinite", is_finite_fn)?;
+    set_property(ctx, number_ctor, atoms::IS_FINITE, is_finite_fn)?;
 
     let is_integer_fn = ctx.new_native_function(native_functions::number_is_integer_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, number_ctor, "isInteger", is_integer_fn)?;
+    set_property(ctx, number_ctor, atoms::IS_INTEGER, is_integer_fn)?;
 
     // Number.parseInt and Number.parseFloat (same as global)
     let parse_int_fn = ctx.new_native_function(native_functions::parse_int_native, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, number_ctor, "parseInt", parse_int_fn)?;
+    set_property(ctx, number_ctor, atoms::PARSE_INT, parse_int_fn)?;
 
     let parse_float_fn = ctx.new_native_function(native_functions::parse_float_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, number_ctor, "parseFloat", parse_float_fn)?;
+    set_property(ctx, number_ctor, atoms::PARSE_FLOAT, parse_float_fn)?;
 
     // Set Number on global
-    set_property(ctx, global, "Number", number_ctor)?;
+    set_property(ctx, global, atoms::NUMBER, number_ctor)?;
 
     Ok(())
 }
@@ -459,10 +560,10 @@ fn install_boolean_constructor(ctx: &mut Context, global: JSValue) -> Result<(),
         .map_err(|_| make_error(ctx, "Out of memory"))?;
 
     // Set Boolean.prototype
-    set_property(ctx, boolean_ctor, "prototype", boolean_proto)?;
+    set_property(ctx, boolean_ctor, atoms::PROTOTYPE, boolean_proto)?;
 
     // Set Boolean on global
-    set_property(ctx, global, "Boolean", boolean_ctor)?;
+    set_property(ctx, global, atoms::BOOLEAN, boolean_ctor)?;
 
     Ok(())
 }
@@ -481,130 +582,231 @@ fn install_function_constructor(ctx: &mut Context, global: JSValue) -> Result<()
     // Install Function.prototype methods
     let call_fn = ctx.new_native_function(native_functions::function_call_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, function_proto, "call", call_fn)?;
+    set_property(ctx, function_proto, atoms::CALL, call_fn)?;
 
     let apply_fn = ctx.new_native_function(native_functions::function_apply_native, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, function_proto, "apply", apply_fn)?;
+    set_property(ctx, function_proto, atoms::APPLY, apply_fn)?;
 
     let bind_fn = ctx.new_native_function(native_functions::function_bind_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, function_proto, "bind", bind_fn)?;
+    set_property(ctx, function_proto, atoms::BIND, bind_fn)?;
 
     // Create Function constructor (placeholder)
     let function_ctor = ctx.new_object()
         .map_err(|_| make_error(ctx, "Out of memory"))?;
 
     // Set Function.prototype
-    set_property(ctx, function_ctor, "prototype", function_proto)?;
+    set_property(ctx, function_ctor, atoms::PROTOTYPE, function_proto)?;
 
     // Set Function on global
-    set_property(ctx, global, "Function", function_ctor)?;
+    set_property(ctx, global, atoms::FUNCTION, function_ctor)?;
 
     Ok(())
 }
 
 /// Install Math object
+#[cfg(feature = "builtin-math")]
 fn install_math_object(ctx: &mut Context, global: JSValue) -> Result<(), JSValue> {
     use crate::builtins::native_functions;
 
     // Create Math object
-    let math = ctx.new_object()
-        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    let math = new_sized_object(ctx, 21)?;
 
     // Install Math constants
     let pi = ctx.new_number(core::f64::consts::PI)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, math, "PI", pi)?;
+    set_property(ctx, math, atoms::PI, pi)?;
 
     let e = ctx.new_number(core::f64::consts::E)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, math, "E", e)?;
+    set_property(ctx, math, atoms::E, e)?;
 
     let ln2 = ctx.new_number(core::f64::consts::LN_2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, math, "LN2", ln2)?;
+    set_property(ctx, math, atoms::LN2, ln2)?;
 
     let ln10 = ctx.new_number(core::f64::consts::LN_10)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, math, "LN10", ln10)?;
+    set_property(ctx, math, atoms::LN10, ln10)?;
 
     let sqrt2 = ctx.new_number(core::f64::consts::SQRT_2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, math, "SQRT2", sqrt2)?;
+    set_property(ctx, math, atoms::SQRT2, sqrt2)?;
 
     // Install Math methods as native functions
     let abs_fn = ctx.new_native_function(native_functions::math_abs, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, math, "abs", abs_fn)?;
+    set_property(ctx, math, atoms::ABS, abs_fn)?;
 
     let floor_fn = ctx.new_native_function(native_functions::math_floor, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, math, "floor", floor_fn)?;
+    set_property(ctx, math, atoms::FLOOR, floor_fn)?;
 
     let ceil_fn = ctx.new_native_function(native_functions::math_ceil, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, math, "ceil", ceil_fn)?;
+    set_property(ctx, math, atoms::CEIL, ceil_fn)?;
 
     let round_fn = ctx.new_native_function(native_functions::math_round, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, math, "round", round_fn)?;
+    set_property(ctx, math, atoms::ROUND, round_fn)?;
 
     let min_fn = ctx.new_native_function(native_functions::math_min, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, math, "min", min_fn)?;
+    set_property(ctx, math, atoms::MIN, min_fn)?;
 
     let max_fn = ctx.new_native_function(native_functions::math_max, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, math, "max", max_fn)?;
+    set_property(ctx, math, atoms::MAX, max_fn)?;
 
     let pow_fn = ctx.new_native_function(native_functions::math_pow, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, math, "pow", pow_fn)?;
+    set_property(ctx, math, atoms::POW, pow_fn)?;
 
     let sqrt_fn = ctx.new_native_function(native_functions::math_sqrt, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, math, "sqrt", sqrt_fn)?;
+    set_property(ctx, math, atoms::SQRT, sqrt_fn)?;
+
+    let trunc_fn = ctx.new_native_function(native_functions::math_trunc, 1)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, math, atoms::TRUNC, trunc_fn)?;
+
+    let sign_fn = ctx.new_native_function(native_functions::math_sign, 1)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, math, atoms::SIGN, sign_fn)?;
+
+    let log_fn = ctx.new_native_function(native_functions::math_log, 1)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, math, atoms::LOG, log_fn)?;
+
+    let exp_fn = ctx.new_native_function(native_functions::math_exp, 1)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, math, atoms::EXP, exp_fn)?;
+
+    let sin_fn = ctx.new_native_function(native_functions::math_sin, 1)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, math, atoms::SIN, sin_fn)?;
+
+    let cos_fn = ctx.new_native_function(native_functions::math_cos, 1)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, math, atoms::COS, cos_fn)?;
+
+    let atan2_fn = ctx.new_native_function(native_functions::math_atan2, 2)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, math, atoms::ATAN2, atan2_fn)?;
+
+    let random_fn = ctx.new_native_function(native_functions::math_random, 0)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, math, atoms::RANDOM, random_fn)?;
 
     // Set Math on global
-    set_property(ctx, global, "Math", math)?;
+    set_property(ctx, global, atoms::MATH, math)?;
+
+    Ok(())
+}
+
+/// Install `Uint8Array` constructor and `Uint8Array.prototype`
+#[cfg(feature = "builtin-typed-array")]
+fn install_uint8array_constructor(ctx: &mut Context, global: JSValue) -> Result<(), JSValue> {
+    use crate::builtins::typed_array;
+
+    // Create Uint8Array.prototype
+    let uint8array_proto = ctx.new_object()
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    ctx.set_uint8array_prototype(uint8array_proto);
+
+    let set_fn = ctx.new_native_function(typed_array::uint8array_set_native, 2)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, uint8array_proto, atoms::SET, set_fn)?;
+
+    let subarray_fn = ctx.new_native_function(typed_array::uint8array_subarray_native, 2)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, uint8array_proto, atoms::SUBARRAY, subarray_fn)?;
+
+    // Create Uint8Array constructor, which builds its own result
+    let uint8array_ctor = ctx.new_native_function(typed_array::uint8array_constructor, 1)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, global, atoms::UINT8_ARRAY, uint8array_ctor)?;
+
+    Ok(())
+}
+
+/// Install the `Date` object, which only has `Date.now` so far
+#[cfg(feature = "builtin-date")]
+fn install_date_object(ctx: &mut Context, global: JSValue) -> Result<(), JSValue> {
+    use crate::builtins::date;
+
+    let date = new_sized_object(ctx, 1)?;
+    let now_fn = ctx.new_native_function(date::date_now, 0)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, date, atoms::NOW, now_fn)?;
+    set_property(ctx, global, atoms::DATE, date)?;
 
     Ok(())
 }
 
 /// Install Error constructors
 fn install_error_constructors(ctx: &mut Context, global: JSValue) -> Result<(), JSValue> {
+    use crate::builtins::error::ErrorType;
     use crate::builtins::native_functions;
 
+    // Create Error.prototype, which the native error prototypes inherit from
+    let error_proto = ctx.new_object()
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    ctx.set_error_prototype(ErrorType::Error, error_proto);
+    for error_type in [
+        ErrorType::TypeError,
+        ErrorType::ReferenceError,
+        ErrorType::RangeError,
+        ErrorType::SyntaxError,
+        ErrorType::EvalError,
+    ] {
+        let proto = ctx.new_object_with_proto(error_proto)
+            .map_err(|_| make_error(ctx, "Out of memory"))?;
+        ctx.set_error_prototype(error_type, proto);
+    }
+
     // Create Error constructor
     let error_ctor = ctx.new_native_function(native_functions::error_constructor, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "Error", error_ctor)?;
+    set_property(ctx, global, atoms::ERROR, error_ctor)?;
+    ctx.set_error_constructor(ErrorType::Error, error_ctor);
 
     // Create TypeError constructor
     let type_error_ctor = ctx.new_native_function(native_functions::type_error_constructor, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "TypeError", type_error_ctor)?;
+    set_property(ctx, global, atoms::TYPE_ERROR, type_error_ctor)?;
+    ctx.set_error_constructor(ErrorType::TypeError, type_error_ctor);
 
     // Create ReferenceError constructor
     let ref_error_ctor = ctx.new_native_function(native_functions::reference_error_constructor, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "ReferenceError", ref_error_ctor)?;
+    set_property(ctx, global, atoms::REFERENCE_ERROR, ref_error_ctor)?;
+    ctx.set_error_constructor(ErrorType::ReferenceError, ref_error_ctor);
 
     // Create RangeError constructor
     let range_error_ctor = ctx.new_native_function(native_functions::range_error_constructor, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "RangeError", range_error_ctor)?;
+    set_property(ctx, global, atoms::RANGE_ERROR, range_error_ctor)?;
+    ctx.set_error_constructor(ErrorType::RangeError, range_error_ctor);
 
     // Create SyntaxError constructor
     let syntax_error_ctor = ctx.new_native_function(native_functions::syntax_error_constructor, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "SyntaxError", syntax_error_ctor)?;
+    set_property(ctx, global, atoms::SYNTAX_ERROR, syntax_error_ctor)?;
+    ctx.set_error_constructor(ErrorType::SyntaxError, syntax_error_ctor);
+
+    // Create EvalError constructor
+    let eval_error_ctor = ctx.new_native_function(native_functions::eval_error_constructor, 1)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_property(ctx, global, atoms::EVAL_ERROR, eval_error_ctor)?;
+    ctx.set_error_constructor(ErrorType::EvalError, eval_error_ctor);
 
     Ok(())
 }
 
 /// Install JSON object
+#[cfg(feature = "builtin-json")]
 fn install_json_object(ctx: &mut Context, global: JSValue) -> Result<(), JSValue> {
     use crate::builtins::native_functions;
 
@@ -615,20 +817,21 @@ fn install_json_object(ctx: &mut Context, global: JSValue) -> Result<(), JSValue
     // JSON.parse
     let parse_fn = ctx.new_native_function(native_functions::json_parse_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, json, "parse", parse_fn)?;
+    set_property(ctx, json, atoms::PARSE, parse_fn)?;
 
     // JSON.stringify
     let stringify_fn = ctx.new_native_function(native_functions::json_stringify_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, json, "stringify", stringify_fn)?;
+    set_property(ctx, json, atoms::STRINGIFY, stringify_fn)?;
 
     // Set JSON on global
-    set_property(ctx, global, "JSON", json)?;
+    set_property(ctx, global, atoms::JSON, json)?;
 
     Ok(())
 }
 
 /// Install console object
+#[cfg(feature = "builtin-console")]
 fn install_console_object(ctx: &mut Context, global: JSValue) -> Result<(), JSValue> {
     use crate::builtins::native_functions;
 
@@ -639,26 +842,65 @@ fn install_console_object(ctx: &mut Context, global: JSValue) -> Result<(), JSVa
     // Install console methods as native functions
     let log_fn = ctx.new_native_function(native_functions::console_log_native, 0)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, console, "log", log_fn)?;
+    set_property(ctx, console, atoms::LOG, log_fn)?;
 
     let error_fn = ctx.new_native_function(native_functions::console_error_native, 0)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, console, "error", error_fn)?;
+    set_property(ctx, console, atoms::ERROR_LOWER, error_fn)?;
 
     let warn_fn = ctx.new_native_function(native_functions::console_warn_native, 0)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, console, "warn", warn_fn)?;
+    set_property(ctx, console, atoms::WARN, warn_fn)?;
 
     let info_fn = ctx.new_native_function(native_functions::console_info_native, 0)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, console, "info", info_fn)?;
+    set_property(ctx, console, atoms::INFO, info_fn)?;
 
     // Set console on global
-    set_property(ctx, global, "console", console)?;
+    set_property(ctx, global, atoms::CONSOLE, console)?;
 
     Ok(())
 }
 
+/// Install the frozen `engine` object describing this build
+///
+/// `engine.features` holds a boolean per entry of [`FEATURES`], so scripts
+/// can test `engine.features.json` instead of probing with try/catch, and
+/// `engine.limits` the arena size and call depth. Entries are enumerable so
+/// for-in lists them; the global itself isn't.
+fn install_engine_object(ctx: &mut Context, global: JSValue, extend: Option<EngineInfoHook>) -> Result<(), JSValue> {
+    let engine = ctx.new_object()
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+
+    let version = ctx.new_string(env!("CARGO_PKG_VERSION"))
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    set_info(ctx, engine, atoms::VERSION, version)?;
+
+    let features = new_sized_object(ctx, FEATURES.len())?;
+    for (name, enabled) in FEATURES {
+        let key = string_to_atom(ctx, name);
+        set_info(ctx, features, key, JSValue::bool(enabled))?;
+    }
+    set_info(ctx, engine, atoms::FEATURES, features)?;
+
+    let limits = new_sized_object(ctx, 2)?;
+    let arena_size = ctx.new_number(ctx.arena_size() as f64)
+        .map_err(|_| make_error(ctx, "Out of memory"))?;
+    let key = string_to_atom(ctx, "arenaSize");
+    set_info(ctx, limits, key, arena_size)?;
+    let key = string_to_atom(ctx, "maxCallDepth");
+    set_info(ctx, limits, key, JSValue::from_int(ctx.call_depth() as i32))?;
+    set_info(ctx, engine, atoms::LIMITS, limits)?;
+
+    if let Some(extend) = extend {
+        extend(ctx, engine)?;
+    }
+    for obj in [features, limits, engine] {
+        ctx.freeze(obj);
+    }
+    set_property(ctx, global, atoms::ENGINE, engine)
+}
+
 /// Install global functions (parseInt, parseFloat, isNaN, isFinite)
 fn install_global_functions(ctx: &mut Context, global: JSValue) -> Result<(), JSValue> {
     use crate::builtins::native_functions;
@@ -666,66 +908,127 @@ fn install_global_functions(ctx: &mut Context, global: JSValue) -> Result<(), JS
     // parseInt
     let parse_int_fn = ctx.new_native_function(native_functions::parse_int_native, 2)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "parseInt", parse_int_fn)?;
+    set_property(ctx, global, atoms::PARSE_INT, parse_int_fn)?;
 
     // parseFloat
     let parse_float_fn = ctx.new_native_function(native_functions::parse_float_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "parseFloat", parse_float_fn)?;
+    set_property(ctx, global, atoms::PARSE_FLOAT, parse_float_fn)?;
 
     // isNaN
     let is_nan_fn = ctx.new_native_function(native_functions::is_nan_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "isNaN", is_nan_fn)?;
+    set_property(ctx, global, atoms::IS_NA_N, is_nan_fn)?;
 
     // isFinite
     let is_finite_fn = ctx.new_native_function(native_functions::is_finite_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "isFinite", is_finite_fn)?;
+    set_property(ctx, global, atoms::IS_FINITE, is_finite_fn)?;
 
     // encodeURI
     let encode_uri_fn = ctx.new_native_function(native_functions::encode_uri_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "encodeURI", encode_uri_fn)?;
+    set_property(ctx, global, atoms::ENCODE_URI, encode_uri_fn)?;
 
     // decodeURI
     let decode_uri_fn = ctx.new_native_function(native_functions::decode_uri_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "decodeURI", decode_uri_fn)?;
+    set_property(ctx, global, atoms::DECODE_URI, decode_uri_fn)?;
 
     // encodeURIComponent
     let encode_uri_comp_fn = ctx.new_native_function(native_functions::encode_uri_component_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "encodeURIComponent", encode_uri_comp_fn)?;
+    set_property(ctx, global, atoms::ENCODE_URI_COMPONENT, encode_uri_comp_fn)?;
 
     // decodeURIComponent
     let decode_uri_comp_fn = ctx.new_native_function(native_functions::decode_uri_component_native, 1)
         .map_err(|_| make_error(ctx, "Out of memory"))?;
-    set_property(ctx, global, "decodeURIComponent", decode_uri_comp_fn)?;
+    set_property(ctx, global, atoms::DECODE_URI_COMPONENT, decode_uri_comp_fn)?;
 
     Ok(())
 }
 
 // ========== Helper Functions ==========
 
+/// Reports a global left out of this build by a disabled `builtin-*` feature
+///
+/// Returns the global's name and the feature that would install it, so a
+/// lookup miss can say why the name is missing.
+pub fn disabled_builtin(atom: JSAtom) -> Option<(&'static str, &'static str)> {
+    match atom {
+        #[cfg(not(feature = "builtin-math"))]
+        atoms::MATH => Some(("Math", "builtin-math")),
+        #[cfg(not(feature = "builtin-json"))]
+        atoms::JSON => Some(("JSON", "builtin-json")),
+        #[cfg(not(feature = "builtin-console"))]
+        atoms::CONSOLE => Some(("console", "builtin-console")),
+        #[cfg(not(feature = "builtin-typed-array"))]
+        atoms::UINT8_ARRAY => Some(("Uint8Array", "builtin-typed-array")),
+        #[cfg(not(feature = "builtin-date"))]
+        atoms::DATE => Some(("Date", "builtin-date")),
+        _ => None,
+    }
+}
+
 /// Set a property on an object (convenience wrapper)
-fn set_property(ctx: &mut Context, obj: JSValue, key: &str, value: JSValue) -> Result<(), JSValue> {
-    // Create an atom for the key
-    // For now we use a simple hash of the string
-    let atom = string_to_atom(key);
+///
+/// Builtins are non-enumerable, as in JS, so for-in and `Object.keys`
+/// skip them. A native function is named after the first key it's
+/// installed under.
+fn set_property(ctx: &mut Context, obj: JSValue, key: JSAtom, value: JSValue) -> Result<(), JSValue> {
+    #[cfg(feature = "alloc-origin")]
+    ctx.note_builtin_origin(value, key);
+    ctx.set_function_name(value, key);
+    let mut flags = PropertyFlags::default();
+    flags.set_enumerable(false);
+    ctx.add_property(obj, key, value, flags)
+        .map_err(|_| make_error(ctx, "Out of memory setting property"))
+}
 
-    ctx.add_property(obj, atom, value, PropertyFlags::default())
+/// Create an object whose property table holds exactly `capacity` entries
+///
+/// For builtins with a known set of properties, whose tables then never
+/// grow and leave smaller ones behind as garbage.
+fn new_sized_object(ctx: &mut Context, capacity: usize) -> Result<JSValue, JSValue> {
+    ctx.new_object_with_capacity(capacity)
+        .map_err(|_| make_error(ctx, "Out of memory"))
+}
+
+/// Set an enumerable entry of the `engine` object or one of its parts
+fn set_info(ctx: &mut Context, obj: JSValue, key: JSAtom, value: JSValue) -> Result<(), JSValue> {
+    ctx.add_property(obj, key, value, PropertyFlags::default())
         .map_err(|_| make_error(ctx, "Out of memory setting property"))
 }
 
-/// Convert a string to an atom (simplified - just hash the string)
-pub fn string_to_atom(s: &str) -> JSAtom {
-    // Simple hash function
-    let mut hash: u32 = 5381;
-    for byte in s.bytes() {
-        hash = hash.wrapping_mul(33).wrapping_add(byte as u32);
+/// Convert a string to an atom
+///
+/// Well-known names resolve to their fixed ids from [`atoms`] and
+/// canonical array indices ("0", "42", but not "042") become index atoms.
+/// Other names are interned in the context's atom table, so each distinct
+/// name gets its own atom.
+///
+/// [`atoms`]: crate::value::atoms
+pub fn string_to_atom(ctx: &mut Context, s: &str) -> JSAtom {
+    ctx.intern_atom(s)
+}
+
+/// Returns the index atom for a canonical array index
+///
+/// Returns None for any other string.
+pub fn index_atom(s: &str) -> Option<JSAtom> {
+    parse_array_index(s).and_then(JSAtom::from_index)
+}
+
+/// Parses a canonical array index (no sign, no leading zeros)
+fn parse_array_index(s: &str) -> Option<u32> {
+    let bytes = s.as_bytes();
+    if bytes.is_empty() || bytes.len() > 10 || (bytes[0] == b'0' && bytes.len() > 1) {
+        return None;
+    }
+    if !bytes.iter().all(u8::is_ascii_digit) {
+        return None;
     }
-    JSAtom::from_id(hash)
+    s.parse().ok()
 }
 
 /// Create an error value
@@ -739,7 +1042,7 @@ mod tests {
 
     #[test]
     fn test_init_runtime() {
-        let mut ctx = Context::new(32768); // 32KB for property tables
+        let mut ctx = Context::new(crate::Engine::MIN_HEAP_SIZE);
         let result = init_runtime(&mut ctx);
         assert!(result.is_ok());
     }
@@ -761,6 +1064,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "builtin-math")]
     fn test_install_math() {
         let mut ctx = Context::new(16384);
         let global = ctx.new_object().unwrap();
@@ -768,13 +1072,93 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_runtime_heap_size() {
+        let mut ctx = Context::new(Context::MIN_MEMORY_SIZE + RUNTIME_HEAP_SIZE);
+        assert!(init_runtime(&mut ctx).is_ok());
+        assert!(ctx.memory_usage() <= Context::MIN_MEMORY_SIZE + RUNTIME_HEAP_SIZE);
+    }
+
+    #[test]
+    fn test_builtin_globals() {
+        let mut ctx = Context::new(Context::MIN_MEMORY_SIZE + RUNTIME_HEAP_SIZE);
+        let global = init_runtime(&mut ctx).unwrap();
+        assert_eq!(ctx.own_property_count(global), BUILTIN_GLOBALS);
+        assert_eq!(ctx.own_property_capacity(global), BUILTIN_GLOBALS);
+
+        // Every table was allocated at its final size, so none was left behind
+        let stats = ctx.gc().unwrap();
+        assert_eq!(stats.objects_freed, 0);
+        assert_eq!(stats.bytes_after, stats.bytes_before);
+    }
+
+    #[test]
+    fn test_engine_object() {
+        let mut ctx = Context::new(Context::MIN_MEMORY_SIZE + RUNTIME_HEAP_SIZE);
+        let global = init_runtime(&mut ctx).unwrap();
+        let engine = ctx.get_property(global, atoms::ENGINE).unwrap();
+        let version = ctx.get_property(engine, atoms::VERSION).unwrap();
+        assert_eq!(ctx.get_string(version), Some(env!("CARGO_PKG_VERSION")));
+
+        // Each feature mirrors its cargo feature
+        let features = ctx.get_property(engine, atoms::FEATURES).unwrap();
+        let json = string_to_atom(&mut ctx, "json");
+        assert_eq!(ctx.get_property(features, json), Some(JSValue::bool(cfg!(feature = "builtin-json"))));
+        let console = string_to_atom(&mut ctx, "console");
+        assert_eq!(ctx.get_property(features, console), Some(JSValue::bool(cfg!(feature = "builtin-console"))));
+
+        let limits = ctx.get_property(engine, atoms::LIMITS).unwrap();
+        for obj in [engine, features, limits] {
+            assert!(ctx.get_object(obj).unwrap().is_frozen());
+        }
+        ctx.set_property(features, json, JSValue::from_int(1)).unwrap();
+        assert_eq!(ctx.get_property(features, json), Some(JSValue::bool(cfg!(feature = "builtin-json"))));
+        assert!(!ctx.delete_property(engine, atoms::VERSION));
+        assert!(ctx.get_property(global, atoms::ENGINE).is_some());
+    }
+
+    #[test]
+    fn test_disabled_builtin() {
+        assert_eq!(disabled_builtin(atoms::MATH).is_some(), cfg!(not(feature = "builtin-math")));
+        assert_eq!(disabled_builtin(atoms::JSON).is_some(), cfg!(not(feature = "builtin-json")));
+        assert_eq!(disabled_builtin(atoms::CONSOLE).is_some(), cfg!(not(feature = "builtin-console")));
+        assert_eq!(disabled_builtin(atoms::UINT8_ARRAY).is_some(), cfg!(not(feature = "builtin-typed-array")));
+        assert_eq!(disabled_builtin(atoms::DATE).is_some(), cfg!(not(feature = "builtin-date")));
+        assert_eq!(disabled_builtin(atoms::OBJECT), None);
+    }
+
     #[test]
     fn test_string_to_atom() {
-        let atom1 = string_to_atom("test");
-        let atom2 = string_to_atom("test");
-        let atom3 = string_to_atom("other");
+        let mut ctx = Context::new(crate::Engine::MIN_HEAP_SIZE);
+        let atom1 = string_to_atom(&mut ctx, "test");
+        let atom2 = string_to_atom(&mut ctx, "test");
+        let atom3 = string_to_atom(&mut ctx, "other");
 
         assert_eq!(atom1.id(), atom2.id());
         assert_ne!(atom1.id(), atom3.id());
     }
+
+    #[test]
+    fn test_string_to_atom_index() {
+        let mut ctx = Context::new(crate::Engine::MIN_HEAP_SIZE);
+        assert_eq!(string_to_atom(&mut ctx, "0"), JSAtom::from_index(0).unwrap());
+        assert_eq!(string_to_atom(&mut ctx, "42").to_index(), Some(42));
+
+        // Non-canonical forms are ordinary property names
+        for name in ["042", "-1", "1.5", "1e3", " 1", "", "99999999999"] {
+            assert!(!string_to_atom(&mut ctx, name).is_index(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_string_to_atom_well_known() {
+        let mut ctx = Context::new(crate::Engine::MIN_HEAP_SIZE);
+        assert_eq!(atoms::LENGTH, atoms::LENGTH);
+        assert_eq!(atoms::PROTOTYPE, atoms::PROTOTYPE);
+
+        // Other names never land in the reserved range
+        for name in ["", "a", "test", "gpio", "0", "__hidden__"] {
+            assert!(string_to_atom(&mut ctx, name).id() >= atoms::FIRST_DYNAMIC_ID, "{:?}", name);
+        }
+    }
 }
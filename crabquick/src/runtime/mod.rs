@@ -5,13 +5,15 @@ pub mod operators;
 pub mod compare;
 pub mod globals;
 pub mod init;
+pub mod display;
 
 // Re-exports
-pub use conversion::{to_number, to_int32, to_string, to_boolean};
-pub use operators::{add, subtract, multiply, divide};
-pub use compare::{strict_equal, abstract_equal, less_than};
+pub use conversion::{to_number, to_int32, to_uint32, to_string, to_boolean, to_primitive, PreferredType};
+pub use operators::{add, subtract, multiply, divide, remainder, exponentiate, negate, increment};
+pub use compare::{strict_equal, abstract_equal, compare, less_than, same_value, value_hash};
 pub use globals::{parse_int, parse_float, is_nan, is_finite};
-pub use init::init_runtime;
+pub use init::{init_runtime, init_runtime_with, EngineInfoHook};
+pub use display::format_value;
 
 #[cfg(test)]
 mod tests {
@@ -20,7 +22,7 @@ mod tests {
 
     #[test]
     fn test_init_runtime() {
-        let mut ctx = Context::new(32768); // 32KB for property tables
+        let mut ctx = Context::new(crate::Engine::MIN_HEAP_SIZE);
         let result = init_runtime(&mut ctx);
         assert!(result.is_ok());
     }
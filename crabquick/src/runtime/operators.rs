@@ -1,15 +1,41 @@
 //! Operator implementations
+//!
+//! Operands go through `to_number`, so `"5" % 2` is 1 and `-"3"` is -3.
+//! When both operands are inline integers and the result is one too, the
+//! result is built directly, without a trip through f64 or a heap box; loop
+//! counters never allocate.
 
 use crate::value::JSValue;
 use crate::context::Context;
+use crate::memory::allocator::OutOfMemory;
 use crate::runtime::conversion::{to_number, to_string};
 
+/// Smallest integer stored inline (-2^30)
+const MIN_INLINE: i64 = -0x4000_0000;
+
+/// Largest integer stored inline (2^30 - 1)
+const MAX_INLINE: i64 = 0x3FFF_FFFF;
+
+/// Returns `n` as an inline integer, if it is in range
+fn inline_int(n: i64) -> Option<JSValue> {
+    (MIN_INLINE..=MAX_INLINE).contains(&n).then(|| JSValue::from_int(n as i32))
+}
+
+/// Returns both operands if they are inline integers
+fn int_operands(left: JSValue, right: JSValue) -> Option<(i64, i64)> {
+    Some((i64::from(left.to_int()?), i64::from(right.to_int()?)))
+}
+
 /// Addition operator (ES5 11.6.1)
 ///
 /// # Rules
 /// - If either operand is a string, convert both to strings and concatenate
 /// - Otherwise, convert both to numbers and add
-pub fn add(ctx: &mut Context, left: JSValue, right: JSValue) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
+pub fn add(ctx: &mut Context, left: JSValue, right: JSValue) -> Result<JSValue, OutOfMemory> {
+    if let Some(sum) = int_operands(left, right).and_then(|(l, r)| inline_int(l + r)) {
+        return Ok(sum);
+    }
+
     // Check if either operand is a string
     let is_left_string = ctx.get_string(left).is_some();
     let is_right_string = ctx.get_string(right).is_some();
@@ -18,11 +44,7 @@ pub fn add(ctx: &mut Context, left: JSValue, right: JSValue) -> Result<JSValue,
         // String concatenation
         let left_str = to_string(ctx, left);
         let right_str = to_string(ctx, right);
-
-        let mut result = left_str;
-        result.push_str(&right_str);
-
-        ctx.new_string(&result)
+        ctx.new_string_from_parts(&[&left_str, &right_str])
     } else {
         // Numeric addition
         let left_num = to_number(ctx, left);
@@ -36,7 +58,11 @@ pub fn add(ctx: &mut Context, left: JSValue, right: JSValue) -> Result<JSValue,
 /// Subtraction operator
 ///
 /// Converts both operands to numbers and subtracts
-pub fn subtract(ctx: &mut Context, left: JSValue, right: JSValue) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
+pub fn subtract(ctx: &mut Context, left: JSValue, right: JSValue) -> Result<JSValue, OutOfMemory> {
+    if let Some(diff) = int_operands(left, right).and_then(|(l, r)| inline_int(l - r)) {
+        return Ok(diff);
+    }
+
     let left_num = to_number(ctx, left);
     let right_num = to_number(ctx, right);
     let diff = left_num - right_num;
@@ -47,7 +73,15 @@ pub fn subtract(ctx: &mut Context, left: JSValue, right: JSValue) -> Result<JSVa
 /// Multiplication operator
 ///
 /// Converts both operands to numbers and multiplies
-pub fn multiply(ctx: &mut Context, left: JSValue, right: JSValue) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
+pub fn multiply(ctx: &mut Context, left: JSValue, right: JSValue) -> Result<JSValue, OutOfMemory> {
+    // A zero product with a negative operand is -0, which needs a box
+    let fast = int_operands(left, right)
+        .filter(|&(l, r)| (l * r) != 0 || (l >= 0 && r >= 0))
+        .and_then(|(l, r)| inline_int(l * r));
+    if let Some(product) = fast {
+        return Ok(product);
+    }
+
     let left_num = to_number(ctx, left);
     let right_num = to_number(ctx, right);
     let product = left_num * right_num;
@@ -58,10 +92,146 @@ pub fn multiply(ctx: &mut Context, left: JSValue, right: JSValue) -> Result<JSVa
 /// Division operator
 ///
 /// Converts both operands to numbers and divides
-pub fn divide(ctx: &mut Context, left: JSValue, right: JSValue) -> Result<JSValue, crate::memory::allocator::OutOfMemory> {
+pub fn divide(ctx: &mut Context, left: JSValue, right: JSValue) -> Result<JSValue, OutOfMemory> {
+    // Only exact quotients, and not 0 / -n, which is -0
+    let fast = int_operands(left, right)
+        .filter(|&(l, r)| r != 0 && l % r == 0 && (l != 0 || r > 0))
+        .and_then(|(l, r)| inline_int(l / r));
+    if let Some(quotient) = fast {
+        return Ok(quotient);
+    }
+
     let left_num = to_number(ctx, left);
     let right_num = to_number(ctx, right);
     let quotient = left_num / right_num;
 
     ctx.new_number(quotient)
 }
+
+/// Remainder operator
+///
+/// Converts both operands to numbers; the result takes the sign of the
+/// dividend, as C's `fmod` does
+pub fn remainder(ctx: &mut Context, left: JSValue, right: JSValue) -> Result<JSValue, OutOfMemory> {
+    // -4 % 2 is -0, which needs a box
+    let fast = int_operands(left, right)
+        .filter(|&(l, r)| r != 0 && (l >= 0 || l % r != 0))
+        .and_then(|(l, r)| inline_int(l % r));
+    if let Some(rem) = fast {
+        return Ok(rem);
+    }
+
+    let left_num = to_number(ctx, left);
+    let right_num = to_number(ctx, right);
+
+    ctx.new_number(libm::fmod(left_num, right_num))
+}
+
+/// Exponentiation operator
+///
+/// Converts both operands to numbers. A NaN exponent, or `1 ** ±Infinity`,
+/// is NaN, unlike C's `pow`.
+pub fn exponentiate(ctx: &mut Context, left: JSValue, right: JSValue) -> Result<JSValue, OutOfMemory> {
+    let base = to_number(ctx, left);
+    let exponent = to_number(ctx, right);
+    let result = if exponent.is_nan() || (base.abs() == 1.0 && exponent.is_infinite()) {
+        f64::NAN
+    } else {
+        libm::pow(base, exponent)
+    };
+
+    ctx.new_number(result)
+}
+
+/// Unary minus operator
+///
+/// Converts the operand to a number and negates it
+pub fn negate(ctx: &mut Context, value: JSValue) -> Result<JSValue, OutOfMemory> {
+    // -0 needs a box
+    if let Some(neg) = value.to_int().filter(|&i| i != 0).and_then(|i| inline_int(-i64::from(i))) {
+        return Ok(neg);
+    }
+
+    ctx.new_number(-to_number(ctx, value))
+}
+
+/// Adds `delta` (1 or -1) to a value converted to a number, for `++` and `--`
+pub fn increment(ctx: &mut Context, value: JSValue, delta: i32) -> Result<JSValue, OutOfMemory> {
+    if let Some(n) = value.to_int().and_then(|i| inline_int(i64::from(i) + i64::from(delta))) {
+        return Ok(n);
+    }
+
+    ctx.new_number(to_number(ctx, value) + f64::from(delta))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn num(ctx: &Context, value: JSValue) -> f64 {
+        to_number(ctx, value)
+    }
+
+    #[test]
+    fn test_int_fast_paths_stay_inline() {
+        let mut ctx = Context::new(8192);
+        let (a, b) = (JSValue::from_int(7), JSValue::from_int(-2));
+        assert_eq!(add(&mut ctx, a, b).unwrap().to_int(), Some(5));
+        assert_eq!(subtract(&mut ctx, a, b).unwrap().to_int(), Some(9));
+        assert_eq!(multiply(&mut ctx, a, b).unwrap().to_int(), Some(-14));
+        assert_eq!(remainder(&mut ctx, a, b).unwrap().to_int(), Some(1));
+        assert_eq!(divide(&mut ctx, JSValue::from_int(8), b).unwrap().to_int(), Some(-4));
+        assert_eq!(negate(&mut ctx, a).unwrap().to_int(), Some(-7));
+        assert_eq!(increment(&mut ctx, b, 1).unwrap().to_int(), Some(-1));
+
+        // Results past the inline range and fractions are boxed
+        let max = JSValue::from_int(MAX_INLINE as i32);
+        let sum = add(&mut ctx, max, JSValue::from_int(1)).unwrap();
+        assert_eq!((sum.to_int(), num(&ctx, sum)), (None, 1_073_741_824.0));
+        let neg = negate(&mut ctx, JSValue::from_int(MIN_INLINE as i32)).unwrap();
+        assert_eq!(num(&ctx, neg), 1_073_741_824.0);
+        let quotient = divide(&mut ctx, a, JSValue::from_int(2)).unwrap();
+        assert_eq!(num(&ctx, quotient), 3.5);
+    }
+
+    #[test]
+    fn test_negative_zero_results() {
+        let mut ctx = Context::new(8192);
+        let zero = JSValue::from_int(0);
+        let results = [
+            multiply(&mut ctx, JSValue::from_int(-3), zero).unwrap(),
+            divide(&mut ctx, zero, JSValue::from_int(-3)).unwrap(),
+            remainder(&mut ctx, JSValue::from_int(-4), JSValue::from_int(2)).unwrap(),
+            negate(&mut ctx, zero).unwrap(),
+        ];
+        for result in results {
+            let n = num(&ctx, result);
+            assert!(n == 0.0 && n.is_sign_negative());
+        }
+    }
+
+    #[test]
+    fn test_operands_are_converted() {
+        let mut ctx = Context::new(8192);
+        let five = ctx.new_string("5").unwrap();
+        let three = ctx.new_string(" 3 ").unwrap();
+        let (zero, one, two) = (JSValue::from_int(0), JSValue::from_int(1), JSValue::from_int(2));
+        let inf = ctx.new_number(f64::INFINITY).unwrap();
+        let results = [
+            remainder(&mut ctx, five, two).unwrap(),
+            negate(&mut ctx, three).unwrap(),
+            exponentiate(&mut ctx, five, two).unwrap(),
+            increment(&mut ctx, five, -1).unwrap(),
+            add(&mut ctx, JSValue::bool(true), JSValue::null()).unwrap(),
+        ];
+        let results = results.map(|result| num(&ctx, result));
+        assert_eq!(results, [1.0, -3.0, 25.0, 4.0, 1.0]);
+
+        let nans = [
+            remainder(&mut ctx, two, zero).unwrap(),
+            increment(&mut ctx, JSValue::undefined(), 1).unwrap(),
+            exponentiate(&mut ctx, one, inf).unwrap(),
+        ];
+        assert!(nans.iter().all(|&result| num(&ctx, result).is_nan()));
+    }
+}
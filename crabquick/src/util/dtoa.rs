@@ -1,7 +1,374 @@
 //! Number to string conversion (dtoa)
+//!
+//! Every place a number becomes text (ToString, property keys, JSON,
+//! console output) goes through [`format_number_with`], so the special
+//! values print the same way everywhere. `toString(radix)` and `toFixed`
+//! use [`format_radix`] and [`format_fixed`], which write into a
+//! [`NumberBuf`] so short results don't allocate.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// How special values (NaN, the infinities) are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberFormat {
+    /// Number::toString: "NaN", "Infinity" and "-Infinity"
+    Js,
+    /// JSON.stringify: non-finite values become "null"
+    Json,
+}
 
 /// Formats a number as a string
-pub fn format_number(_num: f64) -> alloc::string::String {
-    // TODO: Implement efficient number formatting
-    alloc::string::String::new()
+///
+/// Follows JavaScript's Number::toString: the shortest digit string that
+/// round-trips, in plain notation for exponents in [-7, 21) and exponential
+/// notation (`1e+21`, `1.5e-7`) outside it. NaN and the infinities use
+/// their JS names and both zeros print as "0".
+pub fn format_number(num: f64) -> String {
+    format_number_with(num, NumberFormat::Js)
+}
+
+/// Formats a number as a string, writing special values per `format`
+///
+/// Finite numbers are formatted identically in every format; in particular
+/// -0 is "0" both as a string and in JSON.
+pub fn format_number_with(num: f64, format: NumberFormat) -> String {
+    if !num.is_finite() && format == NumberFormat::Json {
+        return String::from("null");
+    }
+    if num.is_nan() {
+        return String::from("NaN");
+    }
+    if num == 0.0 {
+        return String::from("0");
+    }
+    if num.is_infinite() {
+        return String::from(if num > 0.0 { "Infinity" } else { "-Infinity" });
+    }
+
+    // `{:e}` yields the shortest round-trip digits, e.g. "-1.2345e3"
+    let sci = format!("{:e}", num);
+    let (mantissa, exp) = sci.split_once('e').unwrap_or((sci.as_str(), "0"));
+    let exp: i32 = exp.parse().unwrap_or(0);
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", mantissa),
+    };
+
+    let mut digits = String::with_capacity(mantissa.len());
+    digits.extend(mantissa.chars().filter(|c| *c != '.'));
+    let k = digits.len() as i32;
+    // The value is 0.<digits> * 10^n
+    let n = exp + 1;
+
+    let mut out = String::with_capacity(k as usize + 8);
+    out.push_str(sign);
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.extend(core::iter::repeat('0').take((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        out.push_str(&digits[..n as usize]);
+        out.push('.');
+        out.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.extend(core::iter::repeat('0').take((-n) as usize));
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        out.push('e');
+        out.push(if n > 0 { '+' } else { '-' });
+        out.push_str(&format!("{}", (n - 1).abs()));
+    }
+    out
+}
+
+/// Text of a formatted number
+///
+/// Up to [`NumberBuf::INLINE_LEN`] bytes are kept inline; longer results,
+/// such as the binary digits of a small fraction, move to the heap.
+pub struct NumberBuf {
+    inline: [u8; Self::INLINE_LEN],
+    len: usize,
+    spill: Vec<u8>,
+}
+
+impl NumberBuf {
+    /// Bytes kept without allocating; enough for any `toFixed` result
+    pub const INLINE_LEN: usize = 128;
+
+    /// Creates an empty buffer
+    pub const fn new() -> Self {
+        NumberBuf { inline: [0; Self::INLINE_LEN], len: 0, spill: Vec::new() }
+    }
+
+    /// Returns the text written so far
+    pub fn as_str(&self) -> &str {
+        // Only ASCII is ever written
+        core::str::from_utf8(self.bytes()).unwrap_or("")
+    }
+
+    fn bytes(&self) -> &[u8] {
+        if self.spill.is_empty() { &self.inline[..self.len] } else { &self.spill }
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        if self.spill.is_empty() { &mut self.inline[..self.len] } else { &mut self.spill }
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.spill.is_empty() && self.len < Self::INLINE_LEN {
+            self.inline[self.len] = byte;
+            self.len += 1;
+            return;
+        }
+        if self.spill.is_empty() {
+            self.spill.extend_from_slice(&self.inline[..self.len]);
+        }
+        self.spill.push(byte);
+    }
+
+    fn pop(&mut self) -> Option<u8> {
+        if self.spill.is_empty() {
+            self.len = self.len.checked_sub(1)?;
+            Some(self.inline[self.len])
+        } else {
+            self.spill.pop()
+        }
+    }
+}
+
+impl Default for NumberBuf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Write for NumberBuf {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        s.bytes().for_each(|byte| self.push(byte));
+        Ok(())
+    }
+}
+
+/// Digit characters for radixes up to 36
+const RADIX_DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// Formats a number in `radix` (2 to 36), as `Number.prototype.toString`
+///
+/// Fractions get as many digits as it takes to tell the number apart from
+/// its neighbouring doubles, rounding the last one, so `(0.5).toString(2)`
+/// is "0.1" and `(0.1).toString(3)` stops after 34 digits. Integer digits
+/// past double precision are written as zeros.
+pub fn format_radix(num: f64, radix: u32, out: &mut NumberBuf) {
+    if !num.is_finite() || num == 0.0 {
+        out.write_str(&format_number(num)).ok();
+        return;
+    }
+    let radix_f = f64::from(radix);
+    let value = num.abs();
+    let mut integer = libm::floor(value);
+    let mut fraction = value - integer;
+
+    // Half the gap to the next double: digits below that are noise
+    let mut delta = (0.5 * (f64::from_bits(value.to_bits() + 1) - value)).max(f64::from_bits(1));
+    if fraction >= delta {
+        out.push(b'.');
+        loop {
+            fraction *= radix_f;
+            delta *= radix_f;
+            let digit = fraction as usize;
+            out.push(RADIX_DIGITS[digit]);
+            fraction -= digit as f64;
+            if (fraction > 0.5 || (fraction == 0.5 && digit & 1 == 1)) && fraction + delta > 1.0 {
+                // Round up, carrying into the integer part past a run of top digits
+                loop {
+                    match out.pop() {
+                        Some(b'.') => {
+                            integer += 1.0;
+                            break;
+                        }
+                        Some(c) => {
+                            let digit = RADIX_DIGITS.iter().position(|&d| d == c).unwrap_or(0);
+                            if digit + 1 < radix as usize {
+                                out.push(RADIX_DIGITS[digit + 1]);
+                                break;
+                            }
+                        }
+                        None => break,
+                    }
+                }
+                break;
+            }
+            if fraction < delta {
+                break;
+            }
+        }
+    }
+    let fraction_len = out.bytes().len();
+
+    // Integer digits come out least significant first
+    const EXACT_LIMIT: f64 = 9007199254740992.0; // 2^53
+    while integer / radix_f >= EXACT_LIMIT {
+        integer /= radix_f;
+        out.push(b'0');
+    }
+    loop {
+        let remainder = integer % radix_f;
+        out.push(RADIX_DIGITS[remainder as usize]);
+        integer = (integer - remainder) / radix_f;
+        if integer <= 0.0 {
+            break;
+        }
+    }
+    if num < 0.0 {
+        out.push(b'-');
+    }
+
+    // [.fraction][reversed sign and integer] -> [sign integer][.fraction]
+    let bytes = out.bytes_mut();
+    bytes[fraction_len..].reverse();
+    bytes.rotate_left(fraction_len);
+}
+
+/// Formats a number with `digits` digits after the point, as
+/// `Number.prototype.toFixed`
+///
+/// Rounds the exact value of the double, with halfway cases going away from
+/// zero, so `(1.005).toFixed(2)` is "1.00" (1.005 is stored as slightly
+/// less) and `(0.125).toFixed(2)` is "0.13". Numbers of 1e21 and up use
+/// their ordinary string form.
+pub fn format_fixed(num: f64, digits: usize, out: &mut NumberBuf) {
+    if !num.is_finite() || num.abs() >= 1e21 {
+        out.write_str(&format_number(num)).ok();
+        return;
+    }
+    // -0 prints as 0
+    let num = if num == 0.0 { 0.0 } else { num };
+
+    // A double with k binary fraction digits has exactly k decimal ones,
+    // the last of them a 5: it's halfway between two results when k is one
+    // more than the digits asked for. Rust's formatting rounds those ties
+    // to even, so write the exact value and round it up by hand.
+    if fraction_bits(num) as usize != digits + 1 {
+        write!(out, "{:.*}", digits, num).ok();
+        return;
+    }
+    write!(out, "{:.*}", digits + 1, num).ok();
+    out.pop();
+    if digits == 0 {
+        out.pop();
+    }
+    let bytes = out.bytes_mut();
+    for byte in bytes.iter_mut().rev() {
+        match *byte {
+            b'9' => *byte = b'0',
+            b'0'..=b'8' => {
+                *byte += 1;
+                return;
+            }
+            _ => {}
+        }
+    }
+    // Carried out of the top digit: 9.5 -> 10
+    let start = usize::from(num < 0.0);
+    out.push(b'0');
+    let bytes = out.bytes_mut();
+    bytes[start..].rotate_right(1);
+    bytes[start] = b'1';
+}
+
+/// Returns how many binary digits a double has after the point
+fn fraction_bits(num: f64) -> u32 {
+    let bits = num.to_bits();
+    let biased = ((bits >> 52) & 0x7FF) as i32;
+    let mut mantissa = bits & ((1 << 52) - 1);
+    let exponent = if biased == 0 {
+        -1074
+    } else {
+        mantissa |= 1 << 52;
+        biased - 1075
+    };
+    if mantissa == 0 {
+        return 0;
+    }
+    let exponent = exponent + mantissa.trailing_zeros() as i32;
+    if exponent >= 0 { 0 } else { exponent.unsigned_abs() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(0.0), "0");
+        assert_eq!(format_number(-0.0), "0");
+        assert_eq!(format_number(42.0), "42");
+        assert_eq!(format_number(-3.14), "-3.14");
+        assert_eq!(format_number(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(format_number(1e21), "1e+21");
+        assert_eq!(format_number(123e18), "123000000000000000000");
+        assert_eq!(format_number(0.000001), "0.000001");
+        assert_eq!(format_number(1.5e-7), "1.5e-7");
+        assert_eq!(format_number(f64::NAN), "NaN");
+        assert_eq!(format_number(f64::NEG_INFINITY), "-Infinity");
+    }
+
+    #[test]
+    fn test_format_number_json() {
+        assert_eq!(format_number_with(f64::NAN, NumberFormat::Json), "null");
+        assert_eq!(format_number_with(f64::INFINITY, NumberFormat::Json), "null");
+        assert_eq!(format_number_with(f64::NEG_INFINITY, NumberFormat::Json), "null");
+        assert_eq!(format_number_with(-0.0, NumberFormat::Json), "0");
+        assert_eq!(format_number_with(1e21, NumberFormat::Json), "1e+21");
+    }
+
+    fn radix(num: f64, radix: u32) -> String {
+        let mut out = NumberBuf::new();
+        format_radix(num, radix, &mut out);
+        String::from(out.as_str())
+    }
+
+    fn fixed(num: f64, digits: usize) -> String {
+        let mut out = NumberBuf::new();
+        format_fixed(num, digits, &mut out);
+        String::from(out.as_str())
+    }
+
+    #[test]
+    fn test_format_radix() {
+        assert_eq!(radix(255.0, 16), "ff");
+        assert_eq!(radix(-255.0, 2), "-11111111");
+        assert_eq!(radix(0.5, 2), "0.1");
+        assert_eq!(radix(-0.0, 2), "0");
+        assert_eq!(radix(35.0, 36), "z");
+        assert_eq!(radix(3.75, 16), "3.c");
+        assert_eq!(radix(0.1, 2), "0.0001100110011001100110011001100110011001100110011001101");
+        assert_eq!(radix(2f64.powi(60), 16), "1000000000000000");
+        assert_eq!(radix(f64::NAN, 16), "NaN");
+        // 2^-1074 has over a thousand binary digits
+        assert_eq!(radix(f64::from_bits(1), 2).len(), 1076);
+    }
+
+    #[test]
+    fn test_format_fixed() {
+        assert_eq!(fixed(3.14159, 2), "3.14");
+        assert_eq!(fixed(1.005, 2), "1.00");
+        assert_eq!(fixed(0.125, 2), "0.13");
+        assert_eq!(fixed(2.5, 0), "3");
+        assert_eq!(fixed(-2.5, 0), "-3");
+        assert_eq!(fixed(9.5, 0), "10");
+        assert_eq!(fixed(-99.5, 0), "-100");
+        assert_eq!(fixed(-0.0, 2), "0.00");
+        assert_eq!(fixed(42.0, 3), "42.000");
+        assert_eq!(fixed(1e21, 2), "1e+21");
+        assert_eq!(fixed(0.1, 100).len(), 102);
+    }
 }
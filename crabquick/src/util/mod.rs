@@ -4,8 +4,11 @@ pub mod dtoa;
 pub mod strtod;
 pub mod utf8;
 pub mod bitpack;
+pub mod suggest;
+mod fnv;
 
 // Re-exports
-pub use dtoa::format_number;
+pub use dtoa::{format_number, format_number_with, format_fixed, format_radix, NumberBuf, NumberFormat};
 pub use strtod::parse_number;
-pub use utf8::{is_utf8_char_boundary, count_utf8_chars};
+pub(crate) use fnv::Fnv64;
+pub use utf8::{is_utf8_char_boundary, count_utf8_chars, byte_to_char_index, char_to_byte_index};
@@ -1,7 +1,128 @@
 //! String to number conversion (strtod)
 
-/// Parses a number from a string
-pub fn parse_number(_s: &str) -> Result<f64, ()> {
-    // TODO: Implement efficient number parsing
-    Ok(0.0)
+/// Parses a whole string as a number, the way `Number(s)` does
+///
+/// Surrounding JS whitespace is skipped and an empty string is 0. Accepts a
+/// decimal literal with an optional sign, `Infinity` with an optional sign,
+/// and unsigned `0x`, `0o` and `0b` integers. Anything else, including Rust
+/// spellings such as `inf` and `NaN`, is an error.
+pub fn parse_number(s: &str) -> Result<f64, ()> {
+    let s = s.trim_matches(is_js_whitespace);
+    if s.is_empty() {
+        return Ok(0.0);
+    }
+
+    let prefixed = [("0x", 16), ("0X", 16), ("0o", 8), ("0O", 8), ("0b", 2), ("0B", 2)]
+        .into_iter()
+        .find_map(|(prefix, radix)| s.strip_prefix(prefix).map(|digits| (digits, radix)));
+    if let Some((digits, radix)) = prefixed {
+        if digits.is_empty() {
+            return Err(());
+        }
+        return digits.chars().try_fold(0.0, |acc, c| {
+            c.to_digit(radix).map(|digit| acc * f64::from(radix) + f64::from(digit))
+        }).ok_or(());
+    }
+
+    if decimal_prefix_len(s) == s.len() {
+        return s.parse::<f64>().map_err(|_| ());
+    }
+    match s {
+        "Infinity" | "+Infinity" => Ok(f64::INFINITY),
+        "-Infinity" => Ok(f64::NEG_INFINITY),
+        _ => Err(()),
+    }
+}
+
+/// Returns true for the characters JS skips around numbers in strings
+///
+/// These are the WhiteSpace and LineTerminator characters of the spec: ASCII
+/// blanks, NBSP, the byte order mark, the Unicode space separators and the
+/// line and paragraph separators.
+pub fn is_js_whitespace(c: char) -> bool {
+    matches!(
+        c,
+        '\t' | '\n' | '\u{0B}' | '\u{0C}' | '\r' | ' ' | '\u{A0}' | '\u{1680}'
+            | '\u{2000}'..='\u{200A}' | '\u{2028}' | '\u{2029}' | '\u{202F}' | '\u{205F}'
+            | '\u{3000}' | '\u{FEFF}'
+    )
+}
+
+/// Strips leading JS whitespace (see [`is_js_whitespace`])
+pub fn trim_js_whitespace_start(s: &str) -> &str {
+    s.trim_start_matches(is_js_whitespace)
+}
+
+/// Returns the length of the decimal literal at the start of `s`
+///
+/// Matches an optional sign, digits with at most one `.` (at least one
+/// digit overall) and an exponent if digits follow the `e`, so "1e" and
+/// "1e+" stop before the `e`. Returns 0 if `s` doesn't start with a number.
+/// `Infinity` is not matched.
+pub fn decimal_prefix_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let digits_from = |start: usize| bytes[start..].iter().take_while(|b| b.is_ascii_digit()).count();
+
+    let mut end = usize::from(matches!(bytes.first(), Some(b'+' | b'-')));
+    let integer = digits_from(end);
+    end += integer;
+    let mut fraction = 0;
+    if bytes.get(end) == Some(&b'.') {
+        fraction = digits_from(end + 1);
+        if integer + fraction > 0 {
+            end += 1 + fraction;
+        }
+    }
+    if integer + fraction == 0 {
+        return 0;
+    }
+
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-')));
+        let exponent = digits_from(end + 1 + sign);
+        if exponent > 0 {
+            end += 1 + sign + exponent;
+        }
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimal_prefix_len() {
+        assert_eq!(decimal_prefix_len("3.14abc"), 4);
+        assert_eq!(decimal_prefix_len("-.5"), 3);
+        assert_eq!(decimal_prefix_len("1."), 2);
+        assert_eq!(decimal_prefix_len("1e5x"), 3);
+        assert_eq!(decimal_prefix_len("1e"), 1);
+        assert_eq!(decimal_prefix_len("2E-+3"), 1);
+        assert_eq!(decimal_prefix_len("."), 0);
+        assert_eq!(decimal_prefix_len("-"), 0);
+        assert_eq!(decimal_prefix_len("Infinity"), 0);
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number(" \u{FEFF}42\n"), Ok(42.0));
+        assert_eq!(parse_number(""), Ok(0.0));
+        assert_eq!(parse_number("  "), Ok(0.0));
+        assert_eq!(parse_number("-.5"), Ok(-0.5));
+        assert_eq!(parse_number("1e3"), Ok(1000.0));
+        assert_eq!(parse_number("0x1F"), Ok(31.0));
+        assert_eq!(parse_number("0b101"), Ok(5.0));
+        assert_eq!(parse_number("-Infinity"), Ok(f64::NEG_INFINITY));
+        for bad in ["abc", "1e", "12px", "0x", "-0x10", "0b2", "inf", "NaN", "infinity", "1 2"] {
+            assert_eq!(parse_number(bad), Err(()), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_trim_js_whitespace() {
+        assert_eq!(trim_js_whitespace_start("\u{FEFF}\u{A0}\n 12 "), "12 ");
+        // NEL is Unicode whitespace but not JS whitespace
+        assert_eq!(trim_js_whitespace_start("\u{85}1"), "\u{85}1");
+    }
 }
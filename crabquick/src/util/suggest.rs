@@ -0,0 +1,114 @@
+//! Near-name suggestions for error messages
+//!
+//! When a script reads a name that doesn't exist, the error can point at a
+//! name that does and is spelled almost the same ("did you mean
+//! 'deviceId'?"). The search is bounded: only so many candidates are
+//! looked at, long names are skipped, and each comparison gives up as soon
+//! as the distance passes the threshold, so a typo in a script with a big
+//! global object still throws quickly.
+
+use alloc::vec::Vec;
+
+/// Most candidates compared for one suggestion
+pub const MAX_CANDIDATES: usize = 1024;
+
+/// Longest name, in bytes, that takes part in a suggestion
+pub const MAX_NAME_LEN: usize = 64;
+
+/// Returns the candidate closest to `name`, if one is close enough
+///
+/// Names within an edit distance of a quarter of their length (at most 2)
+/// count as close, so one-letter names never get a suggestion. Ties go to
+/// the first candidate; `name` itself is never suggested.
+pub fn closest_name<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    if name.len() > MAX_NAME_LEN {
+        return None;
+    }
+    let threshold = ((name.len() + 2) / 4).min(2);
+    if threshold == 0 {
+        return None;
+    }
+
+    let mut best: Option<(&str, usize)> = None;
+    for candidate in candidates.into_iter().take(MAX_CANDIDATES) {
+        if candidate == name || candidate.is_empty() || candidate.len() > MAX_NAME_LEN {
+            continue;
+        }
+        let limit = best.map_or(threshold, |(_, distance)| distance - 1);
+        if let Some(distance) = edit_distance_within(name, candidate, limit) {
+            best = Some((candidate, distance));
+            if distance == 1 {
+                break;
+            }
+        }
+    }
+    best.map(|(candidate, _)| candidate)
+}
+
+/// Returns the edit distance between `a` and `b` if it's at most `max`
+///
+/// Counts single-character insertions, deletions, substitutions and swaps
+/// of two neighbouring characters, so `deviceID` is one edit from
+/// `deviceId` and `cuont` one edit from `count`. Case changes count as
+/// edits.
+pub fn edit_distance_within(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    // Three rows of the distance matrix: two back (for swaps), previous, current
+    let mut before: Vec<usize> = Vec::new();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut row = alloc::vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        row[0] = i;
+        let mut row_min = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (prev[j] + 1).min(row[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(before[j - 2] + 1);
+            }
+            row[j] = distance;
+            row_min = row_min.min(distance);
+        }
+        if row_min > max {
+            return None;
+        }
+        before = core::mem::replace(&mut prev, core::mem::replace(&mut row, before));
+        row.resize(b.len() + 1, 0);
+    }
+
+    Some(prev[b.len()]).filter(|&distance| distance <= max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance_within("count", "count", 2), Some(0));
+        assert_eq!(edit_distance_within("cout", "count", 2), Some(1));
+        assert_eq!(edit_distance_within("cuont", "count", 2), Some(1));
+        assert_eq!(edit_distance_within("deviceID", "deviceId", 2), Some(1));
+        assert_eq!(edit_distance_within("kitten", "sitting", 3), Some(3));
+        assert_eq!(edit_distance_within("kitten", "sitting", 2), None);
+        assert_eq!(edit_distance_within("a", "abcd", 2), None);
+    }
+
+    #[test]
+    fn test_closest_name() {
+        let names = ["deviceId", "deviceName", "Math"];
+        assert_eq!(closest_name("deviceID", names), Some("deviceId"));
+        assert_eq!(closest_name("Mth", names), Some("Math"));
+        assert_eq!(closest_name("deviceId", names), None);
+        assert_eq!(closest_name("zebra", names), None);
+        // Too short to guess at
+        assert_eq!(closest_name("x", ["y"]), None);
+        // The closer of two near names wins
+        assert_eq!(closest_name("deviceNme", ["deviceNo", "deviceName"]), Some("deviceName"));
+    }
+}
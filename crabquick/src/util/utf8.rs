@@ -1,25 +1,61 @@
 //! UTF-8 utilities
+//!
+//! Strings are indexed by character (Unicode scalar value), so these map
+//! between character indices and byte offsets in well-formed UTF-8.
 
-/// Checks if a byte position is a UTF-8 character boundary
-pub fn is_utf8_char_boundary(_byte: u8) -> bool {
-    // TODO: Implement UTF-8 boundary check
-    true
+/// Checks if a byte starts a UTF-8 character (is not a continuation byte)
+pub fn is_utf8_char_boundary(byte: u8) -> bool {
+    (byte as i8) >= -0x40
 }
 
 /// Counts the number of UTF-8 characters in a byte slice
-pub fn count_utf8_chars(_bytes: &[u8]) -> usize {
-    // TODO: Implement UTF-8 character counting
-    0
+pub fn count_utf8_chars(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|&&b| is_utf8_char_boundary(b)).count()
 }
 
 /// Converts a byte index to a character index
-pub fn byte_to_char_index(_bytes: &[u8], _byte_index: usize) -> usize {
-    // TODO: Implement conversion
-    0
+///
+/// A byte index inside a character maps to the character containing it;
+/// indices past the end map to the character count.
+pub fn byte_to_char_index(bytes: &[u8], byte_index: usize) -> usize {
+    let end = byte_index.min(bytes.len());
+    let before = count_utf8_chars(&bytes[..end]);
+    match bytes.get(end) {
+        Some(&b) if end > 0 && !is_utf8_char_boundary(b) => before - 1,
+        _ => before,
+    }
 }
 
 /// Converts a character index to a byte index
-pub fn char_to_byte_index(_bytes: &[u8], _char_index: usize) -> usize {
-    // TODO: Implement conversion
-    0
+///
+/// Indices past the last character map to the byte length.
+pub fn char_to_byte_index(bytes: &[u8], char_index: usize) -> usize {
+    bytes.iter()
+        .enumerate()
+        .filter(|(_, &b)| is_utf8_char_boundary(b))
+        .nth(char_index)
+        .map_or(bytes.len(), |(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_char_indices() {
+        let s = "aé€x".as_bytes(); // 1 + 2 + 3 + 1 bytes
+        assert_eq!(count_utf8_chars(s), 4);
+        assert!(is_utf8_char_boundary(s[1]));
+        assert!(!is_utf8_char_boundary(s[2]));
+
+        assert_eq!(char_to_byte_index(s, 0), 0);
+        assert_eq!(char_to_byte_index(s, 2), 3);
+        assert_eq!(char_to_byte_index(s, 3), 6);
+        assert_eq!(char_to_byte_index(s, 4), 7);
+        assert_eq!(char_to_byte_index(s, 9), 7);
+
+        assert_eq!(byte_to_char_index(s, 3), 2);
+        assert_eq!(byte_to_char_index(s, 4), 2);
+        assert_eq!(byte_to_char_index(s, 7), 4);
+    }
 }
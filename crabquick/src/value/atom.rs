@@ -5,25 +5,68 @@
 //! fast equality comparison by comparing atom IDs instead of string contents.
 
 extern crate alloc;
+use alloc::string::String;
 use alloc::vec::Vec;
 use core::fmt;
 
-use crate::memory::HeapIndex;
+use super::atoms;
 
 /// Atom identifier
 ///
 /// An atom is a reference to an interned string.
 /// Atoms can be compared for equality by comparing their IDs.
+///
+/// Array indices ("0", "1", ...) are encoded directly in the id with the top
+/// bit set, so integer keys never go through string hashing.
+///
+/// Other dynamic atoms are numbered by the context's [`AtomTable`] in the
+/// order their names are first interned. They are permanent and take no
+/// heap space, so creating and dropping properties under fresh names never
+/// grows the heap; the only per-name cost is the table entry, bounded by
+/// `Context::set_max_property_names`.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct JSAtom(u32);
 
 impl JSAtom {
+    /// Tag bit marking an atom as an integer index
+    pub const INDEX_TAG: u32 = 1 << 31;
+
+    /// Largest index that can be stored in an index atom
+    pub const MAX_INDEX: u32 = u32::MAX - 1 - Self::INDEX_TAG;
+
     /// Creates a new atom from an ID
     #[inline]
     pub const fn from_id(id: u32) -> Self {
         JSAtom(id)
     }
 
+    /// Creates an atom for an integer index
+    ///
+    /// Returns None if `index` is larger than [`JSAtom::MAX_INDEX`].
+    #[inline]
+    pub const fn from_index(index: u32) -> Option<Self> {
+        if index > Self::MAX_INDEX {
+            return None;
+        }
+        Some(JSAtom(index | Self::INDEX_TAG))
+    }
+
+    /// Returns the integer index if this is an index atom
+    #[inline]
+    pub const fn to_index(&self) -> Option<u32> {
+        if self.0 & Self::INDEX_TAG != 0 && !self.is_null() {
+            Some(self.0 & !Self::INDEX_TAG)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if this atom is an integer index
+    #[inline]
+    pub const fn is_index(&self) -> bool {
+        self.to_index().is_some()
+    }
+
     /// Returns the atom ID
     #[inline]
     pub const fn id(&self) -> u32 {
@@ -43,27 +86,31 @@ impl JSAtom {
     }
 }
 
-/// Entry in the atom table
-#[derive(Clone)]
+/// Entry in the atom table's hash index
+#[derive(Clone, Copy)]
 struct AtomEntry {
-    /// Heap index of the interned JSString
-    string_index: HeapIndex,
-    /// Cached hash value
+    /// Hash of the name (see [`AtomTable::hash`])
     hash: u32,
-    /// Reference count (for GC)
-    ref_count: u32,
+    /// Position of the name in the table
+    slot: u32,
 }
 
 /// Atom table for string interning
 ///
-/// The atom table maintains a sorted array of unique strings.
-/// Strings are stored on the heap as JSString objects, and the table
-/// stores heap indices along with cached hashes for fast lookup.
+/// Every dynamic atom stands for exactly one name: the table stores the
+/// names in the order they were interned, and an atom's id is
+/// [`FIRST_DYNAMIC_ID`](super::atoms::FIRST_DYNAMIC_ID) plus its position.
+/// A hash of each name is kept in a sorted index for lookup; names whose
+/// hashes collide are told apart by comparing their bytes, so two different
+/// names never share an atom.
 ///
-/// The table is kept sorted by hash (and then by string content for collisions)
-/// to enable binary search.
+/// Well-known names (see [`atoms`](super::atoms)) are never stored in the
+/// table: they resolve to their fixed ids. Atoms are permanent; the context
+/// bounds how many names are recorded with `Context::set_max_property_names`.
 pub struct AtomTable {
-    /// Sorted array of atom entries
+    /// Interned names, in atom id order
+    names: Vec<String>,
+    /// One entry per name, sorted by hash
     entries: Vec<AtomEntry>,
 }
 
@@ -71,6 +118,7 @@ impl AtomTable {
     /// Creates a new empty atom table
     pub fn new() -> Self {
         AtomTable {
+            names: Vec::new(),
             entries: Vec::new(),
         }
     }
@@ -78,6 +126,7 @@ impl AtomTable {
     /// Creates a new atom table with the specified capacity
     pub fn with_capacity(capacity: usize) -> Self {
         AtomTable {
+            names: Vec::with_capacity(capacity),
             entries: Vec::with_capacity(capacity),
         }
     }
@@ -85,173 +134,89 @@ impl AtomTable {
     /// Returns the number of atoms in the table
     #[inline]
     pub fn len(&self) -> usize {
-        self.entries.len()
+        self.names.len()
     }
 
     /// Returns true if the table is empty
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.entries.is_empty()
+        self.names.is_empty()
     }
 
-    /// Looks up an atom by string and hash
-    ///
-    /// Returns Some(atom) if found, None otherwise.
-    ///
-    /// # Safety
-    ///
-    /// The caller must provide a valid arena reference to access strings.
-    pub unsafe fn lookup(
-        &self,
-        string_bytes: &[u8],
-        hash: u32,
-        arena: &crate::memory::Arena,
-    ) -> Option<JSAtom> {
-        // Binary search by hash
-        let mut left = 0;
-        let mut right = self.entries.len();
-
-        while left < right {
-            let mid = (left + right) / 2;
-            let entry = &self.entries[mid];
-
-            match entry.hash.cmp(&hash) {
-                core::cmp::Ordering::Less => left = mid + 1,
-                core::cmp::Ordering::Greater => right = mid,
-                core::cmp::Ordering::Equal => {
-                    // Hash matches, compare string contents
-                    let string: &crate::value::JSString = arena.get(entry.string_index);
-                    let stored_bytes = string.as_bytes();
-
-                    if stored_bytes == string_bytes {
-                        return Some(JSAtom::from_id(mid as u32));
-                    }
-
-                    // Hash collision, search nearby entries
-                    // Search left
-                    let mut i = mid;
-                    while i > 0 {
-                        i -= 1;
-                        let e = &self.entries[i];
-                        if e.hash != hash {
-                            break;
-                        }
-                        let s: &crate::value::JSString = arena.get(e.string_index);
-                        if s.as_bytes() == string_bytes {
-                            return Some(JSAtom::from_id(i as u32));
-                        }
-                    }
-
-                    // Search right
-                    let mut i = mid + 1;
-                    while i < self.entries.len() {
-                        let e = &self.entries[i];
-                        if e.hash != hash {
-                            break;
-                        }
-                        let s: &crate::value::JSString = arena.get(e.string_index);
-                        if s.as_bytes() == string_bytes {
-                            return Some(JSAtom::from_id(i as u32));
-                        }
-                        i += 1;
-                    }
-
-                    return None;
-                }
-            }
-        }
-
-        None
+    /// Hashes a name for the table's index (djb2)
+    pub fn hash(name: &str) -> u32 {
+        name.bytes().fold(5381u32, |hash, byte| hash.wrapping_mul(33).wrapping_add(byte as u32))
     }
 
-    /// Interns a string, returning its atom
+    /// Looks up the atom for a name without interning it
     ///
-    /// If the string already exists, returns the existing atom.
-    /// Otherwise, adds it to the table and returns a new atom.
-    ///
-    /// # Safety
-    ///
-    /// The caller must provide a valid string index.
-    pub unsafe fn intern(&mut self, string_index: HeapIndex, hash: u32) -> JSAtom {
-        // Find insertion point by binary search
-        let mut left = 0;
-        let mut right = self.entries.len();
-
-        while left < right {
-            let mid = (left + right) / 2;
-            let entry = &self.entries[mid];
-
-            if entry.hash < hash {
-                left = mid + 1;
-            } else {
-                right = mid;
-            }
+    /// Returns None if the name is neither well-known nor in the table.
+    pub fn lookup(&self, name: &str) -> Option<JSAtom> {
+        if let Some(atom) = atoms::lookup(name) {
+            return Some(atom);
         }
-
-        // Insert at position 'left'
-        let entry = AtomEntry {
-            string_index,
-            hash,
-            ref_count: 1,
-        };
-
-        self.entries.insert(left, entry);
-        JSAtom::from_id(left as u32)
+        let hash = Self::hash(name);
+        let first = self.entries.partition_point(|entry| entry.hash < hash);
+        self.entries[first..]
+            .iter()
+            .take_while(|entry| entry.hash == hash)
+            .find(|entry| self.names[entry.slot as usize] == name)
+            .map(|entry| Self::atom_at(entry.slot as usize))
     }
 
-    /// Gets the string index for an atom
+    /// Interns a name, returning its atom
     ///
-    /// Returns None if the atom is invalid.
-    #[inline]
-    pub fn get_string_index(&self, atom: JSAtom) -> Option<HeapIndex> {
-        if atom.is_null() {
-            return None;
-        }
-
-        self.entries.get(atom.id() as usize).map(|e| e.string_index)
-    }
-
-    /// Increments the reference count for an atom
-    pub fn add_ref(&mut self, atom: JSAtom) {
-        if let Some(entry) = self.entries.get_mut(atom.id() as usize) {
-            entry.ref_count = entry.ref_count.saturating_add(1);
+    /// Well-known names return their fixed atom without touching the table.
+    /// A name already in the table returns the atom it was given; otherwise
+    /// the name is added under the next id.
+    pub fn intern(&mut self, name: &str) -> JSAtom {
+        if let Some(atom) = self.lookup(name) {
+            return atom;
         }
+        let slot = self.names.len();
+        let hash = Self::hash(name);
+        let position = self.entries.partition_point(|entry| entry.hash <= hash);
+        self.entries.insert(position, AtomEntry { hash, slot: slot as u32 });
+        self.names.push(String::from(name));
+        Self::atom_at(slot)
     }
 
-    /// Decrements the reference count for an atom
+    /// Returns the name an atom stands for
     ///
-    /// Returns true if the ref count reached zero (atom can be freed).
-    pub fn remove_ref(&mut self, atom: JSAtom) -> bool {
-        if let Some(entry) = self.entries.get_mut(atom.id() as usize) {
-            entry.ref_count = entry.ref_count.saturating_sub(1);
-            entry.ref_count == 0
-        } else {
-            false
+    /// Returns None for index atoms, the null atom and atoms this table
+    /// didn't hand out.
+    pub fn name(&self, atom: JSAtom) -> Option<&str> {
+        if let Some(name) = atoms::name(atom) {
+            return Some(name);
         }
+        self.names.get(Self::slot(atom)?).map(String::as_str)
     }
 
-    /// Removes an atom from the table
-    ///
-    /// This should only be called when the ref count is zero.
-    pub fn remove(&mut self, atom: JSAtom) {
-        if !atom.is_null() && (atom.id() as usize) < self.entries.len() {
-            self.entries.remove(atom.id() as usize);
-        }
+    /// Returns the atom for the entry at `slot`
+    #[inline]
+    fn atom_at(slot: usize) -> JSAtom {
+        JSAtom::from_id(atoms::FIRST_DYNAMIC_ID + slot as u32)
     }
 
-    /// Clears all atoms with zero ref count
+    /// Returns the entry slot of a dynamic atom
     ///
-    /// This is called during GC to clean up unused atoms.
-    pub fn gc_sweep(&mut self) {
-        self.entries.retain(|entry| entry.ref_count > 0);
+    /// Returns None for the null atom, index atoms and well-known atoms.
+    #[inline]
+    fn slot(atom: JSAtom) -> Option<usize> {
+        if atom.is_null() || atom.is_index() {
+            return None;
+        }
+        atom.id()
+            .checked_sub(atoms::FIRST_DYNAMIC_ID)
+            .map(|slot| slot as usize)
     }
 
-    /// Returns an iterator over all atoms
-    pub fn iter(&self) -> impl Iterator<Item = (JSAtom, HeapIndex)> + '_ {
-        self.entries
+    /// Returns an iterator over all atoms and their names, in id order
+    pub fn iter(&self) -> impl Iterator<Item = (JSAtom, &str)> + '_ {
+        self.names
             .iter()
             .enumerate()
-            .map(|(i, entry)| (JSAtom::from_id(i as u32), entry.string_index))
+            .map(|(i, name)| (Self::atom_at(i), name.as_str()))
     }
 }
 
@@ -265,7 +230,7 @@ impl fmt::Debug for AtomTable {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("AtomTable")
             .field("len", &self.len())
-            .field("capacity", &self.entries.capacity())
+            .field("capacity", &self.names.capacity())
             .finish()
     }
 }
@@ -298,6 +263,22 @@ mod tests {
         assert_ne!(atom1, atom3);
     }
 
+    #[test]
+    fn test_atom_index() {
+        let atom = JSAtom::from_index(5).unwrap();
+        assert!(atom.is_index());
+        assert_eq!(atom.to_index(), Some(5));
+        assert_ne!(atom, JSAtom::from_id(5));
+
+        let max = JSAtom::from_index(JSAtom::MAX_INDEX).unwrap();
+        assert_eq!(max.to_index(), Some(JSAtom::MAX_INDEX));
+        assert!(!max.is_null());
+        assert_eq!(JSAtom::from_index(JSAtom::MAX_INDEX + 1), None);
+
+        assert!(!JSAtom::null().is_index());
+        assert!(!JSAtom::from_id(42).is_index());
+    }
+
     #[test]
     fn test_atom_table_creation() {
         let table = AtomTable::new();
@@ -309,80 +290,62 @@ mod tests {
     fn test_atom_table_capacity() {
         let table = AtomTable::with_capacity(100);
         assert_eq!(table.len(), 0);
-        assert!(table.entries.capacity() >= 100);
+        assert!(table.names.capacity() >= 100);
     }
 
     #[test]
     fn test_atom_intern() {
         let mut table = AtomTable::new();
-        let idx1 = HeapIndex::from_usize(0);
-        let idx2 = HeapIndex::from_usize(8);
-
-        unsafe {
-            let atom1 = table.intern(idx1, 12345);
-            assert_eq!(table.len(), 1);
-            assert_eq!(atom1.id(), 0);
 
-            let atom2 = table.intern(idx2, 67890);
-            assert_eq!(table.len(), 2);
-            assert_eq!(atom2.id(), 1);
-        }
+        let atom1 = table.intern("a");
+        assert_eq!(atom1.id(), atoms::FIRST_DYNAMIC_ID);
+        let atom2 = table.intern("b");
+        assert_eq!(atom2.id(), atoms::FIRST_DYNAMIC_ID + 1);
+        assert_eq!(table.intern("a"), atom1);
+        assert_eq!(table.len(), 2);
+
+        assert_eq!(table.lookup("b"), Some(atom2));
+        assert_eq!(table.lookup("c"), None);
+        assert_eq!(table.name(atom1), Some("a"));
+        assert_eq!(table.name(JSAtom::from_id(atoms::FIRST_DYNAMIC_ID + 2)), None);
+        assert_eq!(table.name(JSAtom::null()), None);
     }
 
     #[test]
-    fn test_atom_get_string_index() {
-        let mut table = AtomTable::new();
-        let idx = HeapIndex::from_usize(100);
-
-        unsafe {
-            let atom = table.intern(idx, 12345);
-            assert_eq!(table.get_string_index(atom), Some(idx));
-        }
+    fn test_atom_hash_collisions_stay_distinct() {
+        // djb2 multiplies by 33, so raising one byte by 1 and lowering the
+        // next by 33 gives the same hash
+        assert_eq!(AtomTable::hash("aa"), AtomTable::hash("b@"));
+        assert_eq!(AtomTable::hash("xaay"), AtomTable::hash("xb@y"));
 
-        let null_atom = JSAtom::null();
-        assert_eq!(table.get_string_index(null_atom), None);
+        let mut table = AtomTable::new();
+        let aa = table.intern("aa");
+        let b = table.intern("b@");
+        assert_ne!(aa, b);
+        assert_eq!(table.intern("b@"), b);
+        assert_eq!(table.lookup("aa"), Some(aa));
+        assert_eq!(table.name(aa), Some("aa"));
+        assert_eq!(table.name(b), Some("b@"));
+        assert_eq!(table.lookup("xaay"), None);
     }
 
     #[test]
-    fn test_atom_ref_counting() {
+    fn test_atom_iter() {
         let mut table = AtomTable::new();
-        let idx = HeapIndex::from_usize(0);
-
-        unsafe {
-            let atom = table.intern(idx, 12345);
-
-            // Initial ref count is 1
-            table.add_ref(atom);
-            assert_eq!(table.entries[atom.id() as usize].ref_count, 2);
-
-            // Remove ref - still has refs (count goes to 1)
-            assert!(!table.remove_ref(atom));
-            assert_eq!(table.entries[atom.id() as usize].ref_count, 1);
-
-            // Remove ref - returns true when count reaches zero
-            assert!(table.remove_ref(atom));
-            assert_eq!(table.entries[atom.id() as usize].ref_count, 0);
-        }
+        let z = table.intern("z");
+        let a = table.intern("a");
+        let names: Vec<_> = table.iter().collect();
+        assert_eq!(names, [(z, "z"), (a, "a")]);
     }
 
     #[test]
-    fn test_atom_gc_sweep() {
+    fn test_atom_intern_well_known() {
         let mut table = AtomTable::new();
 
-        unsafe {
-            let atom1 = table.intern(HeapIndex::from_usize(0), 111);
-            let atom2 = table.intern(HeapIndex::from_usize(8), 222);
-            let _atom3 = table.intern(HeapIndex::from_usize(16), 333);
-
-            // Set ref counts
-            table.entries[atom1.id() as usize].ref_count = 1;
-            table.entries[atom2.id() as usize].ref_count = 0;
-            table.entries[2].ref_count = 1;
+        assert_eq!(table.intern("length"), atoms::LENGTH);
+        assert_eq!(table.name(atoms::LENGTH), Some("length"));
 
-            table.gc_sweep();
-
-            // atom2 should be removed
-            assert_eq!(table.len(), 2);
-        }
+        // Well-known names aren't stored in the table
+        assert!(table.is_empty());
     }
 }
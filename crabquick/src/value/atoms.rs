@@ -0,0 +1,275 @@
+//! Well-known atoms
+//!
+//! Property names used by the runtime itself ("length", "prototype", the
+//! built-in constructor and method names, ...) are assigned fixed atom ids
+//! below [`FIRST_DYNAMIC_ID`]. Resolving one of these names never hashes the
+//! string, and the runtime can refer to them as constants such as
+//! [`LENGTH`] instead of scattering string literals around.
+//!
+//! All other names get hashed atom ids at or above [`FIRST_DYNAMIC_ID`], so
+//! the two ranges never overlap.
+
+use super::atom::JSAtom;
+
+/// First atom id available to names that aren't in the well-known table
+pub const FIRST_DYNAMIC_ID: u32 = 256;
+
+macro_rules! well_known_atoms {
+    ($($ident:ident = $name:literal,)*) => {
+        /// Dense numbering of the well-known atoms, in declaration order
+        #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+        #[repr(u32)]
+        enum Id {
+            $($ident,)*
+            Count,
+        }
+
+        $(
+            #[doc = concat!("The `", $name, "` atom")]
+            pub const $ident: JSAtom = JSAtom::from_id(Id::$ident as u32);
+        )*
+
+        /// Number of well-known atoms
+        pub const COUNT: usize = Id::Count as usize;
+
+        /// Names of the well-known atoms, indexed by atom id
+        static NAMES: [&str; COUNT] = [$($name,)*];
+
+        /// Returns the well-known atom for `name`, if there is one
+        pub fn lookup(name: &str) -> Option<JSAtom> {
+            match name {
+                $($name => Some($ident),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+well_known_atoms! {
+    // Common property names
+    LENGTH = "length",
+    PROTOTYPE = "prototype",
+    CONSTRUCTOR = "constructor",
+    NAME = "name",
+    MESSAGE = "message",
+    STACK = "stack",
+    LINE_NUMBER = "lineNumber",
+    VALUE = "value",
+    WRITABLE = "writable",
+    ENUMERABLE = "enumerable",
+    CONFIGURABLE = "configurable",
+    GET = "get",
+    SET = "set",
+    PROTO = "__proto__",
+    VALUE_OF = "valueOf",
+    TO_STRING = "toString",
+    ARGUMENTS = "arguments",
+
+    // Hidden internal properties
+    IS_BOUND_FUNCTION = "__isBoundFunction__",
+    BOUND_TARGET = "__boundTarget__",
+    BOUND_THIS = "__boundThis__",
+    BOUND_ARGS = "__boundArgs__",
+    EXIT_CODE = "__exitCode__",
+
+    // Global constants
+    UNDEFINED = "undefined",
+    NAN = "NaN",
+    INFINITY = "Infinity",
+
+    // Global constructors and namespaces
+    OBJECT = "Object",
+    ARRAY = "Array",
+    STRING = "String",
+    NUMBER = "Number",
+    BOOLEAN = "Boolean",
+    FUNCTION = "Function",
+    MATH = "Math",
+    JSON = "JSON",
+    CONSOLE = "console",
+    ERROR = "Error",
+    EVAL_ERROR = "EvalError",
+    RANGE_ERROR = "RangeError",
+    REFERENCE_ERROR = "ReferenceError",
+    SYNTAX_ERROR = "SyntaxError",
+    TYPE_ERROR = "TypeError",
+
+    // Object
+    HAS_OWN_PROPERTY = "hasOwnProperty",
+    KEYS = "keys",
+    VALUES = "values",
+    ENTRIES = "entries",
+    ASSIGN = "assign",
+    CREATE = "create",
+    GET_PROTOTYPE_OF = "getPrototypeOf",
+    SET_PROTOTYPE_OF = "setPrototypeOf",
+    DEFINE_PROPERTY = "defineProperty",
+    IS_ARRAY = "isArray",
+
+    // Array.prototype
+    PUSH = "push",
+    POP = "pop",
+    SHIFT = "shift",
+    UNSHIFT = "unshift",
+    SLICE = "slice",
+    SPLICE = "splice",
+    CONCAT = "concat",
+    INDEX_OF = "indexOf",
+    LAST_INDEX_OF = "lastIndexOf",
+    INCLUDES = "includes",
+    JOIN = "join",
+    REVERSE = "reverse",
+    FOR_EACH = "forEach",
+    MAP = "map",
+    FILTER = "filter",
+    REDUCE = "reduce",
+    REDUCE_RIGHT = "reduceRight",
+    FIND = "find",
+    FIND_INDEX = "findIndex",
+    SOME = "some",
+    EVERY = "every",
+    SORT = "sort",
+    AT = "at",
+
+    // String
+    CHAR_AT = "charAt",
+    CHAR_CODE_AT = "charCodeAt",
+    CODE_POINT_AT = "codePointAt",
+    SUBSTRING = "substring",
+    TO_LOWER_CASE = "toLowerCase",
+    TO_UPPER_CASE = "toUpperCase",
+    SPLIT = "split",
+    TRIM = "trim",
+    TRIM_START = "trimStart",
+    TRIM_END = "trimEnd",
+    REPLACE = "replace",
+    REPLACE_ALL = "replaceAll",
+    STARTS_WITH = "startsWith",
+    ENDS_WITH = "endsWith",
+    PAD_START = "padStart",
+    PAD_END = "padEnd",
+    FROM_CHAR_CODE = "fromCharCode",
+    FROM_CODE_POINT = "fromCodePoint",
+
+    // Number
+    TO_FIXED = "toFixed",
+    MAX_VALUE = "MAX_VALUE",
+    MIN_VALUE = "MIN_VALUE",
+    POSITIVE_INFINITY = "POSITIVE_INFINITY",
+    NEGATIVE_INFINITY = "NEGATIVE_INFINITY",
+    IS_NA_N = "isNaN",
+    IS_FINITE = "isFinite",
+    IS_INTEGER = "isInteger",
+    PARSE_INT = "parseInt",
+    PARSE_FLOAT = "parseFloat",
+
+    // Function.prototype
+    CALL = "call",
+    APPLY = "apply",
+    BIND = "bind",
+
+    // Math
+    PI = "PI",
+    E = "E",
+    LN2 = "LN2",
+    LN10 = "LN10",
+    SQRT2 = "SQRT2",
+    ABS = "abs",
+    FLOOR = "floor",
+    CEIL = "ceil",
+    ROUND = "round",
+    MIN = "min",
+    MAX = "max",
+    POW = "pow",
+    SQRT = "sqrt",
+    TRUNC = "trunc",
+    SIGN = "sign",
+    EXP = "exp",
+    SIN = "sin",
+    COS = "cos",
+    ATAN2 = "atan2",
+    RANDOM = "random",
+
+    // JSON and console
+    PARSE = "parse",
+    STRINGIFY = "stringify",
+    LOG = "log",
+    ERROR_LOWER = "error",
+    WARN = "warn",
+    INFO = "info",
+
+    // URI functions
+    ENCODE_URI = "encodeURI",
+    DECODE_URI = "decodeURI",
+    ENCODE_URI_COMPONENT = "encodeURIComponent",
+    DECODE_URI_COMPONENT = "decodeURIComponent",
+
+    // Typed arrays
+    UINT8_ARRAY = "Uint8Array",
+    SUBARRAY = "subarray",
+
+    // Date
+    DATE = "Date",
+    NOW = "now",
+
+    // Engine info
+    ENGINE = "engine",
+    VERSION = "version",
+    FEATURES = "features",
+    LIMITS = "limits",
+}
+
+// Dynamic atom ids start above the reserved range
+const _: () = assert!(COUNT <= FIRST_DYNAMIC_ID as usize);
+
+/// Returns the well-known atom for a name given as raw bytes
+#[inline]
+pub fn lookup_bytes(name: &[u8]) -> Option<JSAtom> {
+    core::str::from_utf8(name).ok().and_then(lookup)
+}
+
+/// Returns true if `atom` is one of the well-known atoms
+#[inline]
+pub const fn is_well_known(atom: JSAtom) -> bool {
+    (atom.id() as usize) < COUNT
+}
+
+/// Returns the name of a well-known atom
+///
+/// Returns None for dynamic atoms, whose names aren't known statically.
+#[inline]
+pub fn name(atom: JSAtom) -> Option<&'static str> {
+    NAMES.get(atom.id() as usize).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeSet;
+
+    #[test]
+    fn test_well_known_ids_are_dense() {
+        for (id, name) in NAMES.iter().enumerate() {
+            let atom = lookup(name).unwrap();
+            assert_eq!(atom.id() as usize, id, "{}", name);
+            assert_eq!(super::name(atom), Some(*name));
+            assert!(is_well_known(atom));
+        }
+    }
+
+    #[test]
+    fn test_well_known_names_are_unique() {
+        let unique: BTreeSet<&str> = NAMES.iter().copied().collect();
+        assert_eq!(unique.len(), COUNT);
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup("length"), Some(LENGTH));
+        assert_eq!(lookup("prototype"), Some(PROTOTYPE));
+        assert_eq!(lookup_bytes(b"push"), Some(PUSH));
+        assert_eq!(lookup("notAWellKnownName"), None);
+        assert_eq!(lookup_bytes(&[0xff, 0xfe]), None);
+        assert_eq!(name(JSAtom::from_id(FIRST_DYNAMIC_ID)), None);
+    }
+}
@@ -47,18 +47,20 @@ impl JSFloat64 {
     /// Checks if a value can be represented as an inline integer
     ///
     /// Returns true if the value is a whole number in the range that fits
-    /// in a 31-bit signed integer (JSValue can inline these).
+    /// in a 31-bit signed integer (JSValue can inline these). -0 has no
+    /// integer form and stays boxed.
     pub fn can_inline(value: f64) -> bool {
-        // Check if it's a whole number
-        if libm::fmod(value, 1.0) != 0.0 {
-            return false;
-        }
-
-        // Check if it fits in 31 bits
+        // Check if it fits in 31 bits (false for NaN)
         const MIN: f64 = -0x4000_0000 as f64; // -2^30
         const MAX: f64 = 0x3FFF_FFFF as f64;  // 2^30 - 1
+        if !(MIN..=MAX).contains(&value) {
+            return false;
+        }
 
-        value >= MIN && value <= MAX
+        // Whole numbers survive the round trip through i32 bit for bit,
+        // and -0 comes back as +0. The cast is much cheaper than fmod, and
+        // every new number goes through here.
+        f64::from(value as i32).to_bits() == value.to_bits()
     }
 
     /// Converts to i32 if possible
@@ -155,6 +157,7 @@ mod tests {
         assert!(!JSFloat64::can_inline(f64::NAN));
         assert!(!JSFloat64::can_inline(f64::INFINITY));
         assert!(!JSFloat64::can_inline(f64::NEG_INFINITY));
+        assert!(!JSFloat64::can_inline(-0.0));
     }
 
     #[test]
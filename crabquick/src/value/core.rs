@@ -36,6 +36,7 @@ impl JSValue {
     const VAL_FALSE: usize = (2 << 3) | Self::TAG_SPECIAL;
     const VAL_TRUE: usize = (3 << 3) | Self::TAG_SPECIAL;
     const VAL_EXCEPTION: usize = (4 << 3) | Self::TAG_SPECIAL;
+    const VAL_HOLE: usize = (5 << 3) | Self::TAG_SPECIAL;
 
     /// Creates a JSValue from a 32-bit signed integer
     ///
@@ -92,6 +93,14 @@ impl JSValue {
         JSValue(Self::VAL_EXCEPTION)
     }
 
+    /// Creates the marker for a missing element in an array's element store
+    ///
+    /// Never reaches scripts: reading a hole finds no element.
+    #[inline]
+    pub(crate) const fn hole() -> Self {
+        JSValue(Self::VAL_HOLE)
+    }
+
     /// Returns true if this value is null
     #[inline]
     pub const fn is_null(self) -> bool {
@@ -116,6 +125,12 @@ impl JSValue {
         self.0 == Self::VAL_EXCEPTION
     }
 
+    /// Returns true if this value is the array hole marker
+    #[inline]
+    pub(crate) const fn is_hole(self) -> bool {
+        self.0 == Self::VAL_HOLE
+    }
+
     /// Extracts a boolean value
     ///
     /// Returns None if the value is not a boolean.
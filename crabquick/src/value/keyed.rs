@@ -0,0 +1,69 @@
+//! Values usable as keys in host-side collections
+
+use alloc::string::String;
+use core::hash::{Hash, Hasher};
+
+use crate::context::Context;
+use crate::runtime::compare::number_bits;
+use super::JSValue;
+
+/// A JSValue with SameValue equality and hashing
+///
+/// JSValue's own `==` compares raw bits, so two strings with the same
+/// content differ. `KeyedValue` captures what SameValue compares by when
+/// it's built, so it can go into a `HashMap` or `HashSet` without a
+/// Context at hand:
+///
+/// - numbers by value, with every NaN equal and +0 distinct from -0
+/// - strings by content (copied, so the key outlives the string)
+/// - everything else by identity
+///
+/// Identity is the object's heap index, which stays put across
+/// compaction. Once an object is collected its index can be reused, so
+/// keep objects used as keys alive, e.g. through [`Context::persist`].
+#[derive(Clone, Debug)]
+pub struct KeyedValue {
+    value: JSValue,
+    hash: u64,
+    key: Key,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Key {
+    Number(u64),
+    String(String),
+    Identity(usize),
+}
+
+impl KeyedValue {
+    /// Captures the key of a value
+    pub fn new(ctx: &Context, value: JSValue) -> Self {
+        let key = if let Some(n) = ctx.get_number(value) {
+            Key::Number(number_bits(n))
+        } else if let Some(s) = ctx.get_string(value) {
+            Key::String(String::from(s))
+        } else {
+            Key::Identity(value.as_raw())
+        };
+        KeyedValue { value, hash: ctx.value_hash(value), key }
+    }
+
+    /// Returns the wrapped value
+    pub fn value(&self) -> JSValue {
+        self.value
+    }
+}
+
+impl PartialEq for KeyedValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for KeyedValue {}
+
+impl Hash for KeyedValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
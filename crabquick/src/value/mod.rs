@@ -6,15 +6,21 @@
 //! - Arrays (JSValueArray, JSByteArray)
 //! - Boxed Float64 values
 //! - Atoms (interned strings for property names)
+//! - Well-known atoms with fixed ids (`atoms::LENGTH`, ...)
+//! - KeyedValue (SameValue keys for host-side collections)
 
 mod core;
 pub mod string;
 pub mod array;
 pub mod boxed;
 pub mod atom;
+pub mod atoms;
+mod keyed;
 
 pub use core::JSValue;
 pub use string::{JSString, JSStringHeader};
+pub(crate) use string::JSStringWriter;
 pub use array::{JSValueArray, JSValueArrayHeader, JSByteArray, JSByteArrayHeader};
 pub use boxed::JSFloat64;
 pub use atom::{JSAtom, AtomTable};
+pub use keyed::KeyedValue;
@@ -5,6 +5,12 @@
 //! - ASCII-only (for fast case conversion and length calculations)
 //! - Numeric (for fast parseInt-style conversions)
 //! - Interned (unique strings stored in atom table)
+//!
+//! Strings never change once built. [`JSStringWriter`] writes a new string's
+//! bytes exactly once and then seals its header; after that the string only
+//! hands out shared references to its bytes, so a hash cached in the header,
+//! or anything else keyed on the contents, stays valid. Debug builds assert
+//! on mutable access to a sealed string.
 
 use core::fmt;
 use core::str;
@@ -23,7 +29,8 @@ pub struct JSStringHeader {
     /// - Bit 0: is_ascii (true if all chars are ASCII)
     /// - Bit 1: is_numeric (true if string looks like a number)
     /// - Bit 2: hash_valid (true if hash has been computed)
-    /// - Bits 3-31: length in bytes (UTF-8 byte length, max 2^29 bytes)
+    /// - Bit 3: is_sealed (true once the bytes are written)
+    /// - Bits 4-31: length in bytes (UTF-8 byte length, max 2^28 bytes)
     flags_and_len: u32,
     /// Hash value (cached when computed)
     hash: u32,
@@ -33,8 +40,9 @@ impl JSStringHeader {
     const IS_ASCII_BIT: u32 = 1 << 0;
     const IS_NUMERIC_BIT: u32 = 1 << 1;
     const HASH_VALID_BIT: u32 = 1 << 2;
-    const LEN_SHIFT: u32 = 3;
-    const LEN_MASK: u32 = !0x7; // All bits except lower 3
+    const SEALED_BIT: u32 = 1 << 3;
+    const LEN_SHIFT: u32 = 4;
+    const LEN_MASK: u32 = !0xF; // All bits except lower 4
 
     /// Creates a new string header
     pub fn new(len: usize, is_ascii: bool, is_numeric: bool) -> Self {
@@ -73,6 +81,7 @@ impl JSStringHeader {
     /// Sets the ASCII flag
     #[inline]
     pub fn set_ascii(&mut self, is_ascii: bool) {
+        debug_assert!(!self.is_sealed(), "flags changed on a sealed string");
         if is_ascii {
             self.flags_and_len |= Self::IS_ASCII_BIT;
         } else {
@@ -89,6 +98,7 @@ impl JSStringHeader {
     /// Sets the numeric flag
     #[inline]
     pub fn set_numeric(&mut self, is_numeric: bool) {
+        debug_assert!(!self.is_sealed(), "flags changed on a sealed string");
         if is_numeric {
             self.flags_and_len |= Self::IS_NUMERIC_BIT;
         } else {
@@ -96,6 +106,18 @@ impl JSStringHeader {
         }
     }
 
+    /// Returns true once the string's bytes are written and can't change
+    #[inline]
+    pub fn is_sealed(&self) -> bool {
+        (self.flags_and_len & Self::SEALED_BIT) != 0
+    }
+
+    /// Marks the string's bytes as final
+    #[inline]
+    pub(crate) fn seal(&mut self) {
+        self.flags_and_len |= Self::SEALED_BIT;
+    }
+
     /// Returns true if hash has been computed
     #[inline]
     pub fn hash_valid(&self) -> bool {
@@ -151,8 +173,12 @@ impl JSString {
     }
 
     /// Returns the mutable header
+    ///
+    /// Only for strings still being built; debug builds panic on a sealed
+    /// one.
     #[inline]
     pub fn header_mut(&mut self) -> &mut JSStringHeader {
+        debug_assert!(!self.header.is_sealed(), "mutable access to a sealed string");
         &mut self.header
     }
 
@@ -168,6 +194,18 @@ impl JSString {
         core::slice::from_raw_parts(ptr, self.header.len())
     }
 
+    /// Returns the UTF-8 bytes for writing, while the string is being built
+    ///
+    /// # Safety
+    ///
+    /// Same as [`as_bytes`](Self::as_bytes).
+    #[inline]
+    unsafe fn bytes_mut(&mut self) -> &mut [u8] {
+        debug_assert!(!self.header.is_sealed(), "mutable access to a sealed string");
+        let ptr = (self as *mut Self as *mut u8).add(size_of::<JSStringHeader>());
+        core::slice::from_raw_parts_mut(ptr, self.header.len())
+    }
+
     /// Returns the string as a &str
     ///
     /// # Safety
@@ -196,7 +234,11 @@ impl JSString {
     }
 
     /// Returns the hash, computing it if needed
+    ///
+    /// The hash is cached in the header, which is only sound because sealed
+    /// bytes never change.
     pub fn hash(&mut self) -> u32 {
+        debug_assert!(self.header.is_sealed(), "hashing a string that isn't sealed");
         if !self.header.hash_valid() {
             let h = self.compute_hash();
             self.header.set_hash(h);
@@ -253,6 +295,51 @@ impl JSString {
     }
 }
 
+/// Writes a new string into its freshly allocated block, then seals it
+///
+/// Append exactly the number of bytes given to [`new`](Self::new), in as
+/// many pieces as convenient, and call [`finish`](Self::finish) to set the
+/// flags and seal the header.
+pub(crate) struct JSStringWriter<'a> {
+    string: &'a mut JSString,
+    written: usize,
+}
+
+impl<'a> JSStringWriter<'a> {
+    /// Starts a `len`-byte string in `string`'s block
+    ///
+    /// # Safety
+    ///
+    /// The block must have room for [`JSString::alloc_size`]`(len)` bytes,
+    /// and nothing may read the string before it's finished.
+    pub(crate) unsafe fn new(string: &'a mut JSString, len: usize) -> Self {
+        string.header = JSStringHeader::new(len, false, false);
+        JSStringWriter { string, written: 0 }
+    }
+
+    /// Appends `s`; panics if it doesn't fit in the length promised
+    pub(crate) fn push_str(&mut self, s: &str) {
+        let end = self.written + s.len();
+        assert!(end <= self.string.header.len(), "string written past its length");
+        // SAFETY: new() was promised room for the whole length, and the
+        // check above keeps the copy inside it
+        unsafe { self.string.bytes_mut()[self.written..end].copy_from_slice(s.as_bytes()) };
+        self.written = end;
+    }
+
+    /// Sets the flags and seals the string, which must be fully written
+    pub(crate) fn finish(self) {
+        assert_eq!(self.written, self.string.header.len(), "string finished before all its bytes were written");
+        // SAFETY: every byte was written from a &str, so they're valid UTF-8
+        let bytes = unsafe { self.string.as_bytes() };
+        let is_ascii = JSString::check_ascii(bytes);
+        let is_numeric = JSString::check_numeric(bytes);
+        self.string.header.set_ascii(is_ascii);
+        self.string.header.set_numeric(is_numeric);
+        self.string.header.seal();
+    }
+}
+
 impl fmt::Debug for JSString {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         unsafe {
@@ -313,6 +400,44 @@ mod tests {
         assert!(!JSString::check_numeric(b"12a"));
     }
 
+    #[test]
+    fn test_new_strings_are_sealed() {
+        use crate::context::Context;
+
+        let mut ctx = Context::new(8192);
+        let value = ctx.new_string_from_parts(&["ab", "", "12"]).unwrap();
+        assert_eq!(ctx.get_string(value), Some("ab12"));
+
+        let string: &mut JSString = unsafe { ctx.arena_mut().get_mut(value.to_ptr().unwrap()) };
+        assert!(string.header().is_sealed());
+        assert!(string.header().is_ascii());
+        assert!(!string.header().is_numeric());
+        assert_eq!(string.hash(), string.compute_hash());
+        assert_eq!(string.hash_cached(), Some(string.compute_hash()));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "mutable access to a sealed string")]
+    fn test_sealed_string_rejects_mutation() {
+        use crate::context::Context;
+
+        let mut ctx = Context::new(8192);
+        let value = ctx.new_string("key").unwrap();
+        let string: &mut JSString = unsafe { ctx.arena_mut().get_mut(value.to_ptr().unwrap()) };
+        string.header_mut().set_numeric(true);
+    }
+
+    #[test]
+    #[should_panic(expected = "string written past its length")]
+    fn test_writer_stays_in_bounds() {
+        let mut block = [0u64; 2];
+        let string = unsafe { &mut *(block.as_mut_ptr() as *mut JSString) };
+        let mut writer = unsafe { JSStringWriter::new(string, 4) };
+        writer.push_str("abc");
+        writer.push_str("de");
+    }
+
     #[test]
     fn test_header_size() {
         // Header is 8 bytes (u32 + u32)
@@ -2,27 +2,39 @@
 //!
 //! This module implements the main bytecode execution loop and opcode handlers.
 
+use alloc::rc::Rc;
 use alloc::vec::Vec;
 use alloc::string::{String, ToString};
-use alloc::collections::BTreeMap;
+use core::cmp::Ordering;
+use crate::builtins::error::ErrorKind;
 use crate::bytecode::{BytecodeReader, Opcode, Operand};
+use crate::compiler::codegen::FUNC_FLAG_ARROW;
 use crate::context::Context;
 use crate::memory::HeapIndex;
-use crate::value::JSValue;
+use crate::object::function::{FunctionCode, JSClosure};
+use crate::value::{atoms, JSValue};
+use crate::runtime::conversion::{to_int32, to_uint32};
+use super::module::{Module, ModuleCache};
 use super::stack::{
     ValueStack, CallStack, StackFrame,
     StackOverflow, StackUnderflow, CallStackOverflow,
 };
 
-/// Function entry from function table
-#[derive(Debug, Clone)]
-struct FunctionEntry {
-    bytecode_index: HeapIndex,
-    param_count: u8,
-    local_count: u8,
-    /// For named function expressions: slot index where the function self-reference should be stored
-    /// 0xFF means no self-reference needed
-    self_name_slot: u8,
+/// Exception handler installed by a try block
+#[derive(Debug, Clone, Copy)]
+struct CatchHandler {
+    /// Code the handler was installed by, as `BytecodeReader::code_id`
+    ///
+    /// `catch_pc` is only meaningful in that code.
+    code: (usize, usize),
+    /// Where execution resumes, with the exception pushed
+    catch_pc: usize,
+    /// Value stack depth when the try block was entered
+    stack_len: usize,
+    /// Number of active for-in loops when the try block was entered
+    for_in_len: usize,
+    /// Number of active for-of loops when the try block was entered
+    for_of_len: usize,
 }
 
 /// Virtual machine state
@@ -33,23 +45,40 @@ pub struct VM {
     call_stack: CallStack,
     /// Current exception value (if any)
     exception: Option<JSValue>,
-    /// Constant pool for current function
-    constants: Vec<JSValue>,
-    /// Tracks which constants are f64 bits (true) vs JSValue (false)
-    const_is_f64: Vec<bool>,
-    /// Atom table (index -> string)
-    atom_table: Vec<String>,
-    /// Function table (precompiled functions)
-    function_table: Vec<FunctionEntry>,
+    /// Active try block handlers, innermost last
+    ///
+    /// Each run loop only unwinds to handlers it installed itself.
+    handlers: Vec<CatchHandler>,
+    /// Number of handlers owned by the run loops below the current one
+    handler_base: usize,
+    /// Tables of the unit the current function belongs to
+    module: Rc<Module>,
+    /// Every unit parsed so far, so calls don't parse their callee again
+    modules: ModuleCache,
     /// Promoted var_refs for current frame: (frame_sp, local_slot) -> var_ref_idx
     /// This ensures multiple closures share the same var_ref for the same captured variable
     promoted_var_refs: Vec<(usize, usize, HeapIndex)>,
-    /// For-in iterator state: (keys, current_index)
-    for_in_state: Vec<(Vec<String>, usize)>,
-    /// For-of iterator state: (values, current_index)
-    for_of_state: Vec<(Vec<JSValue>, usize)>,
-    /// Reverse mapping from atom hash to string (for for...in enumeration)
-    atom_hash_to_string: BTreeMap<u32, String>,
+    /// For-in iterator state: (object, keys, next_index)
+    for_in_state: Vec<(JSValue, Vec<crate::value::JSAtom>, usize)>,
+    /// For-of iterator state: (iterable, next_index)
+    ///
+    /// The index counts elements for arrays and bytes for strings.
+    for_of_state: Vec<(JSValue, usize)>,
+    /// Instructions started over the VM's lifetime; `reset` keeps it
+    instructions: u64,
+    /// Value of `instructions` past which the current run times out
+    step_deadline: u64,
+    /// Instructions left before the interrupt handler is next polled
+    interrupt_countdown: u32,
+    /// Line table of the running top-level script
+    ///
+    /// Functions find theirs in their own code, which their readers span.
+    script_lines: Vec<u8>,
+    /// Heap-resident top-level scripts being run, innermost last
+    ///
+    /// Nothing else refers to a script's code while it runs, so collections
+    /// root it from here.
+    scripts: Vec<HeapIndex>,
 }
 
 /// VM execution result
@@ -70,187 +99,195 @@ pub enum VMError {
 }
 
 impl VM {
+    /// Call depth a VM from [`VM::new`] allows
+    pub const DEFAULT_CALL_DEPTH: usize = 100;
+
+    /// Value stack slots a VM from [`VM::new`] has
+    pub const DEFAULT_VALUE_STACK_SLOTS: usize = 1000;
+
     /// Creates a new VM with default stack sizes
     pub fn new() -> Self {
-        Self::with_stack_sizes(1000, 100)
+        Self::with_stack_sizes(Self::DEFAULT_VALUE_STACK_SLOTS, Self::DEFAULT_CALL_DEPTH)
     }
 
     /// Creates a new VM with specified stack sizes
+    ///
+    /// Both stacks are reserved in full on the Rust heap, outside the
+    /// context's arena, and capped at `ValueStack::MAX_SLOTS` and
+    /// `CallStack::MAX_CALL_DEPTH`.
     pub fn with_stack_sizes(value_stack_size: usize, call_stack_depth: usize) -> Self {
         VM {
             value_stack: ValueStack::new(value_stack_size),
             call_stack: CallStack::new(call_stack_depth),
             exception: None,
-            constants: Vec::new(),
-            const_is_f64: Vec::new(),
-            atom_table: Vec::new(),
-            function_table: Vec::new(),
+            handlers: Vec::new(),
+            handler_base: 0,
+            module: Rc::default(),
+            modules: ModuleCache::default(),
             promoted_var_refs: Vec::new(),
             for_in_state: Vec::new(),
             for_of_state: Vec::new(),
-            atom_hash_to_string: BTreeMap::new(),
+            instructions: 0,
+            step_deadline: u64::MAX,
+            interrupt_countdown: 0,
+            script_lines: Vec::new(),
+            scripts: Vec::new(),
         }
     }
 
-    /// Executes bytecode in the given context
+    /// Clears all execution state, keeping the allocated capacity
     ///
-    /// Returns the result value or an exception.
-    pub fn execute(
-        &mut self,
-        ctx: &mut Context,
-        bytecode_index: HeapIndex,
-    ) -> VMResult {
-        // Get the bytecode array - check first without holding borrow during error
-        let bytecode_ptr: *const crate::value::JSByteArray = match ctx.get_byte_array(bytecode_index) {
-            Some(b) => b as *const _,
-            None => return Err(self.throw_error(ctx, "Invalid bytecode")),
-        };
-
-        // SAFETY: bytecode_ptr is valid as long as we don't modify the arena
-        let bytecode_slice = unsafe { (*bytecode_ptr).as_slice() };
-
-        // Parse constant pool and atom table from bytecode
-        // Format: [constant_count: u16][(type: u8, value: usize)...]
-        //         [atom_count: u16][(len: u16, string_bytes)...]
-        //         [bytecode...]
-        // Type: 0 = f64 bits, 1 = JSValue
-        if bytecode_slice.len() < 2 {
-            return Err(self.throw_error(ctx, "Invalid bytecode format"));
-        }
-
-        let mut offset = 0;
-
-        // Read constant count
-        let const_count = u16::from_le_bytes([bytecode_slice[offset], bytecode_slice[offset + 1]]) as usize;
-        offset += 2;
-
-        let const_entry_size = 1 + core::mem::size_of::<usize>(); // type byte + usize value
-
-        if bytecode_slice.len() < offset + const_count * const_entry_size {
-            return Err(self.throw_error(ctx, "Invalid bytecode: truncated constant pool"));
-        }
-
-        // Read constants
-        self.constants.clear();
-        self.constants.reserve(const_count);
-        self.const_is_f64.clear();
-        self.const_is_f64.reserve(const_count);
-
-        for i in 0..const_count {
-            let const_type = bytecode_slice[offset];
-            offset += 1;
+    /// Lets one VM run many scripts without reallocating its stacks and
+    /// tables each time.
+    pub fn reset(&mut self) {
+        self.value_stack.clear();
+        self.call_stack.clear();
+        self.exception = None;
+        self.handlers.clear();
+        self.handler_base = 0;
+        self.script_lines.clear();
+        self.scripts.clear();
+        self.module = Rc::default();
+        self.modules.clear();
+        self.promoted_var_refs.clear();
+        self.for_in_state.clear();
+        self.for_of_state.clear();
+    }
 
-            let mut bytes = [0u8; core::mem::size_of::<usize>()];
-            bytes.copy_from_slice(&bytecode_slice[offset..offset + core::mem::size_of::<usize>()]);
-            offset += core::mem::size_of::<usize>();
+    /// Number of instructions started since the VM was created
+    ///
+    /// Hosts diff two readings to measure one run.
+    pub fn instructions_executed(&self) -> u64 {
+        self.instructions
+    }
 
-            let raw = usize::from_le_bytes(bytes);
-            let value = unsafe { core::mem::transmute::<usize, JSValue>(raw) };
-            self.constants.push(value);
-            self.const_is_f64.push(const_type == 0);
+    /// Starts the step budget of a run, unless one is already under way
+    fn start_run(&mut self, ctx: &Context) {
+        if self.call_stack.is_empty() {
+            ctx.take_prototype_links();
+            self.step_deadline = match ctx.max_steps() {
+                0 => u64::MAX,
+                steps => self.instructions.saturating_add(steps),
+            };
         }
+    }
 
-        // Read atom count
-        if bytecode_slice.len() < offset + 2 {
-            return Err(self.throw_error(ctx, "Invalid bytecode: missing atom count"));
+    /// Counts an instruction against the run's step budget, polling the
+    /// host's interrupt handler every `Context::interrupt_interval` steps
+    ///
+    /// The errors are a `Timeout` and an `Interrupted`, which
+    /// `catch_exception` won't hand to a script, so they unwind every frame
+    /// back to the host.
+    fn count_step(&mut self, ctx: &mut Context) -> Result<(), JSValue> {
+        // Prototype links the last instruction's lookups followed cost a
+        // step each, so a storm of deep misses still runs out of budget
+        let links = ctx.take_prototype_links();
+        self.step_deadline = self.step_deadline.saturating_sub(links);
+        self.instructions += 1;
+        if self.instructions > self.step_deadline {
+            let msg = alloc::format!("Script exceeded its budget of {} steps", ctx.max_steps());
+            return Err(self.throw_error_kind(ctx, ErrorKind::Timeout, &msg));
         }
-        let atom_count = u16::from_le_bytes([bytecode_slice[offset], bytecode_slice[offset + 1]]) as usize;
-        offset += 2;
-
-        // Read atom strings
-        self.atom_table.clear();
-        self.atom_table.reserve(atom_count);
-
-        for _ in 0..atom_count {
-            if bytecode_slice.len() < offset + 2 {
-                return Err(self.throw_error(ctx, "Invalid bytecode: truncated atom table"));
-            }
-            let len = u16::from_le_bytes([bytecode_slice[offset], bytecode_slice[offset + 1]]) as usize;
-            offset += 2;
 
-            if bytecode_slice.len() < offset + len {
-                return Err(self.throw_error(ctx, "Invalid bytecode: truncated atom string"));
+        let spent = u32::try_from(links).unwrap_or(u32::MAX).saturating_add(1);
+        self.interrupt_countdown = self.interrupt_countdown.saturating_sub(spent);
+        if self.interrupt_countdown == 0 {
+            self.interrupt_countdown = ctx.interrupt_interval();
+            if ctx.interrupt_requested() {
+                return Err(self.throw_error_kind(ctx, ErrorKind::Interrupted, "Interrupted by the host"));
             }
-            let string_bytes = &bytecode_slice[offset..offset + len];
-            offset += len;
-
-            let string = core::str::from_utf8(string_bytes)
-                .map_err(|_| self.throw_error(ctx, "Invalid UTF-8 in atom table"))?;
-            self.atom_table.push(string.to_string());
-        }
-
-        // Read function table
-        if bytecode_slice.len() < offset + 2 {
-            return Err(self.throw_error(ctx, "Invalid bytecode: missing function count"));
         }
-        let func_count = u16::from_le_bytes([bytecode_slice[offset], bytecode_slice[offset + 1]]) as usize;
-        offset += 2;
-
-        self.function_table.clear();
-        self.function_table.reserve(func_count);
-
-        for _ in 0..func_count {
-            // Read param_count (u8), local_count (u8), self_name_slot (u8), bytecode_len (u32), then bytecode bytes
-            if bytecode_slice.len() < offset + 7 {
-                return Err(self.throw_error(ctx, "Invalid bytecode: truncated function table"));
-            }
-
-            let param_count = bytecode_slice[offset];
-            offset += 1;
-            let local_count = bytecode_slice[offset];
-            offset += 1;
-            let self_name_slot = bytecode_slice[offset];
-            offset += 1;
-
-            let mut len_bytes = [0u8; 4];
-            len_bytes.copy_from_slice(&bytecode_slice[offset..offset + 4]);
-            offset += 4;
-            let bytecode_len = u32::from_le_bytes(len_bytes) as usize;
-
-            if bytecode_slice.len() < offset + bytecode_len {
-                return Err(self.throw_error(ctx, "Invalid bytecode: truncated function bytecode"));
-            }
+        Ok(())
+    }
 
-            let func_bytecode = &bytecode_slice[offset..offset + bytecode_len];
-            offset += bytecode_len;
+    /// Number of values on the value stack
+    pub fn stack_depth(&self) -> usize {
+        self.value_stack.len()
+    }
 
-            // Allocate a ByteArray for this function's bytecode
-            let func_bc_index = ctx.alloc_byte_array(bytecode_len)
-                .map_err(|_| self.throw_error(ctx, "Out of memory allocating function bytecode"))?;
+    /// Calls a function with this VM available for closures
+    ///
+    /// Native and bound functions are called directly; closures run on this
+    /// VM's stacks. Fails while a script is running: natives call back into
+    /// script through `Context::call_function` instead.
+    pub fn call_function(
+        &mut self,
+        ctx: &mut Context,
+        func: JSValue,
+        this_val: JSValue,
+        args: &[JSValue],
+    ) -> VMResult {
+        if let Err(err) = ctx.check_idle("call_function") {
+            return Err(self.throw_error(ctx, &err.to_string()));
+        }
+        self.start_run(ctx);
+        let vm_ptr = core::ptr::NonNull::new(self as *mut VM as *mut u8).unwrap();
+        ctx.set_reentrant_call(vm_ptr, Self::reentrant_call_handler);
+        ctx.enter_run();
+        let result = ctx.call_function(func, this_val, args);
+        ctx.leave_run();
+        ctx.clear_reentrant_call();
+        result
+    }
 
-            // Copy the bytecode to the allocated array
-            unsafe {
-                if let Some(bc_array) = ctx.get_byte_array_mut(func_bc_index) {
-                    let slice = bc_array.as_full_mut_slice();
-                    slice[..bytecode_len].copy_from_slice(func_bytecode);
-                    bc_array.header_mut().set_count(bytecode_len);
-                }
-            }
+    /// Executes bytecode in the given context
+    ///
+    /// Returns the result value or an exception.
+    pub fn execute(
+        &mut self,
+        ctx: &mut Context,
+        bytecode_index: HeapIndex,
+    ) -> VMResult {
+        self.execute_code(ctx, FunctionCode::Heap(bytecode_index))
+    }
 
-            self.function_table.push(FunctionEntry {
-                bytecode_index: func_bc_index,
-                param_count,
-                local_count,
-                self_name_slot,
-            });
+    /// Executes a script's top-level code
+    ///
+    /// `FunctionCode::Static` scripts are read in place, so only runtime state
+    /// (closures, var refs, objects) costs heap space. Nested functions are
+    /// never copied: they point back into the script they were declared in.
+    /// Fails with an internal error if a script is already running.
+    pub fn execute_code(
+        &mut self,
+        ctx: &mut Context,
+        code: FunctionCode,
+    ) -> VMResult {
+        if let Err(err) = ctx.check_idle("execute_code") {
+            return Err(self.throw_error(ctx, &err.to_string()));
         }
+        let bytecode_slice = match Self::code_bytes(ctx, code) {
+            Some(bytes) => bytes,
+            None => return Err(self.throw_error(ctx, "Invalid bytecode")),
+        };
+        let module = self.modules.get(ctx, code, bytecode_slice)
+            .map_err(|msg| self.throw_error(ctx, msg))?;
+        let outer_lines = core::mem::replace(&mut self.script_lines, bytecode_slice[module.lines.clone()].to_vec());
+        let code_start = module.code_start;
+        let outer_module = core::mem::replace(&mut self.module, module);
 
-        // Get the actual bytecode after the constant pool, atom table, and function table
-        let code_slice = &bytecode_slice[offset..];
+        // Get the actual bytecode after the constant pool, atom table, function table and line table
+        let code_slice = &bytecode_slice[code_start..];
 
         // Create a bytecode reader
         let mut reader = BytecodeReader::new(code_slice);
 
-        // Create initial stack frame
+        // Create initial stack frame; the script's locals start above
+        // anything already on the stack
+        let base_sp = self.value_stack.len();
+        let for_in_depth = self.for_in_state.len();
+        let for_of_depth = self.for_of_state.len();
+        self.start_run(ctx);
         let frame = StackFrame::new(
             JSValue::undefined(), // func
-            0,                     // sp
+            base_sp,               // sp
             0,                     // argc
             JSValue::undefined(),  // this
         );
 
         if self.call_stack.push(frame).is_err() {
-            return Err(self.throw_error(ctx, "Call stack overflow"));
+            self.script_lines = outer_lines;
+            self.module = outer_module;
+            return Err(self.stack_overflow(ctx));
         }
 
         // Set up reentrant call mechanism so native functions can call closures
@@ -258,13 +295,30 @@ impl VM {
         ctx.set_reentrant_call(vm_ptr, Self::reentrant_call_handler);
 
         // Main execution loop
+        #[cfg(feature = "alloc-origin")]
+        let outer_origin = ctx.set_alloc_origin(crate::memory::AllocOrigin::Host);
+        self.scripts.extend(code.heap_index());
+        ctx.enter_run();
         let result = self.run_loop(ctx, &mut reader);
+        ctx.leave_run();
+        if code.heap_index().is_some() {
+            self.scripts.pop();
+        }
+        #[cfg(feature = "alloc-origin")]
+        ctx.set_alloc_origin(outer_origin);
+        self.script_lines = outer_lines;
+        self.module = outer_module;
 
         // Clear reentrant call mechanism
         ctx.clear_reentrant_call();
 
-        // Pop the frame
+        // Pop the frame and drop whatever the script left behind, even if
+        // it stopped mid-expression
         let _ = self.call_stack.pop();
+        self.value_stack.truncate(base_sp);
+        self.promoted_var_refs.retain(|(sp, _, _)| *sp != base_sp);
+        self.for_in_state.truncate(for_in_depth);
+        self.for_of_state.truncate(for_of_depth);
 
         result
     }
@@ -281,7 +335,12 @@ impl VM {
         args: &[JSValue],
     ) -> Result<JSValue, JSValue> {
         let vm = &mut *(vm_ptr.as_ptr() as *mut VM);
-        vm.call_function_internal(ctx, func, this_val, args)
+        #[cfg(feature = "alloc-origin")]
+        let outer_origin = ctx.set_alloc_origin(crate::memory::AllocOrigin::Host);
+        let result = vm.call_function_internal(ctx, func, this_val, args);
+        #[cfg(feature = "alloc-origin")]
+        ctx.set_alloc_origin(outer_origin);
+        result
     }
 
     /// Internal function call used by reentrant handler
@@ -300,29 +359,13 @@ impl VM {
             };
 
             // Get closure info
-            let (bytecode_index, param_count, local_count, self_name_slot) = match ctx.get_closure(closure_idx) {
-                Some(closure) => (closure.bytecode_index, closure.param_count as usize, closure.local_count as usize, closure.self_name_slot),
+            let (code, param_count, local_count, self_name_slot, this_val) = match ctx.get_closure(closure_idx) {
+                Some(closure) => (closure.code, closure.param_count as usize, closure.local_count as usize, closure.self_name_slot, closure.this_for_call(this_val)),
                 None => return Err(self.throw_error(ctx, "Invalid closure")),
             };
 
-            // Prepare args - pad if needed
-            let mut padded_args: Vec<JSValue> = args.to_vec();
-            while padded_args.len() < param_count {
-                padded_args.push(JSValue::undefined());
-            }
-
-            // Push arguments onto the stack as locals
-            let base_sp = self.value_stack.len();
-            for arg in &padded_args {
-                self.value_stack.push(*arg)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-            }
-
-            // Reserve space for additional locals
-            for _ in param_count..local_count {
-                self.value_stack.push(JSValue::undefined())
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-            }
+            // Reserve the frame's window: parameters, then locals
+            let base_sp = self.reserve_frame(ctx, args, param_count, local_count)?;
 
             // For named function expressions, set the function self-reference
             if self_name_slot != 0xFF {
@@ -331,12 +374,13 @@ impl VM {
             }
 
             // Push a call frame with this_val
-            let frame = StackFrame::new_closure(func, base_sp, padded_args.len() as u16, this_val, closure_idx);
+            let frame = StackFrame::new_closure(func, base_sp, args.len() as u16, this_val, closure_idx)
+                .with_window(param_count, local_count.max(param_count));
             self.call_stack.push(frame)
-                .map_err(|_| self.throw_error(ctx, "Call stack overflow"))?;
+                .map_err(|_| self.stack_overflow(ctx))?;
 
             // Execute the function with closure context
-            let result = self.execute_bytecode_function(ctx, bytecode_index, base_sp, local_count, Some(closure_idx));
+            let result = self.execute_bytecode_function(ctx, code, base_sp, Some(closure_idx));
 
             // Pop the call frame
             let _ = self.call_stack.pop();
@@ -350,39 +394,24 @@ impl VM {
             result
         } else if let Some(bc_func) = ctx.get_bytecode_function(func) {
             // Bytecode function (not a closure)
-            let func_bc_index = bc_func.bytecode_index();
+            let func_code = bc_func.code();
             let param_count = bc_func.param_count() as usize;
             let local_count = bc_func.local_count() as usize;
 
-            // Prepare args
-            let mut padded_args: Vec<JSValue> = args.to_vec();
-            while padded_args.len() < param_count {
-                padded_args.push(JSValue::undefined());
-            }
-
-            let base_sp = self.value_stack.len();
-            for arg in &padded_args {
-                self.value_stack.push(*arg)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-            }
-
-            for _ in param_count..local_count {
-                self.value_stack.push(JSValue::undefined())
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-            }
-
-            let frame = StackFrame::new(func, base_sp, padded_args.len() as u16, this_val);
+            let base_sp = self.reserve_frame(ctx, args, param_count, local_count)?;
+            let frame = StackFrame::new(func, base_sp, args.len() as u16, this_val)
+                .with_window(param_count, local_count.max(param_count));
             self.call_stack.push(frame)
-                .map_err(|_| self.throw_error(ctx, "Call stack overflow"))?;
+                .map_err(|_| self.stack_overflow(ctx))?;
 
-            let result = self.execute_bytecode_function(ctx, func_bc_index, base_sp, local_count, None);
+            let result = self.execute_bytecode_function(ctx, func_code, base_sp, None);
 
             let _ = self.call_stack.pop();
             self.value_stack.truncate(base_sp);
 
             result
         } else {
-            Err(self.throw_error(ctx, "Not a callable function"))
+            Err(self.throw_error_kind(ctx, ErrorKind::Type, "Not a callable function"))
         }
     }
 
@@ -391,6 +420,20 @@ impl VM {
         &mut self,
         ctx: &mut Context,
         reader: &mut BytecodeReader,
+    ) -> VMResult {
+        let handler_base = self.handlers.len();
+        let outer_base = core::mem::replace(&mut self.handler_base, handler_base);
+        let result = self.run_loop_inner(ctx, reader, handler_base);
+        self.handlers.truncate(handler_base);
+        self.handler_base = outer_base;
+        result
+    }
+
+    fn run_loop_inner(
+        &mut self,
+        ctx: &mut Context,
+        reader: &mut BytecodeReader,
+        handler_base: usize,
     ) -> VMResult {
         loop {
             // Check if we have a pending exception
@@ -400,40 +443,182 @@ impl VM {
 
             // Decode next instruction
             let pc = reader.pc();
-            let instruction = match reader.decode() {
-                Some(inst) => inst,
-                None => {
-                    // End of bytecode - return undefined
-                    return Ok(JSValue::undefined());
-                }
+            let instruction = match reader.try_decode() {
+                Ok(Some(inst)) => inst,
+                // End of bytecode - return undefined
+                Ok(None) => return Ok(JSValue::undefined()),
+                Err(err) => return Err(self.throw_error(ctx, &err.to_string())),
             };
 
+            crate::fatal::record_location(pc, instruction.opcode);
+            self.count_step(ctx)?;
+
+            if ctx.should_collect() {
+                self.collect_garbage(ctx);
+            }
+
             // Update PC in current frame
             if let Ok(frame) = self.call_stack.current_mut() {
                 frame.pc = pc;
             }
 
             // Execute the instruction
-            match self.execute_instruction(ctx, reader, &instruction) {
+            match self.execute_with_retry(ctx, reader, &instruction) {
                 Ok(Some(ret)) => return Ok(ret), // Return instruction
                 Ok(None) => continue,              // Normal continuation
                 Err(e) => {
-                    // Check if we have an exception handler
-                    if let Ok(frame) = self.call_stack.current() {
-                        if let Some(catch_pc) = frame.catch_offset {
-                            // Jump to exception handler
-                            reader.set_pc(catch_pc);
-                            self.value_stack.push(e)
-                                .map_err(|_| self.throw_error(ctx, "Stack overflow in exception handler"))?;
-                            continue;
-                        }
-                    }
-                    return Err(e);
+                    Self::note_error_line(ctx, e, || crate::compiler::debug::line_at(&self.script_lines, pc));
+                    self.catch_exception(ctx, reader, handler_base, e)?
                 }
             }
         }
     }
 
+    /// Collects garbage between instructions, rooting everything the VM holds
+    ///
+    /// Every value a script can still reach is on the value stack, in a call
+    /// frame or in loop state at this point; running code is rooted through
+    /// its function or `scripts` and pinned, since the readers of running
+    /// functions point into it. The constant pools hold
+    /// only number bits, so they need no rooting. Compaction moves blocks but
+    /// values refer to them by index, so the stacks stay valid without being
+    /// rewritten.
+    fn collect_garbage(&mut self, ctx: &mut Context) {
+        let mut roots: Vec<JSValue> = self.value_stack.as_slice().to_vec();
+        for frame in self.call_stack.frames() {
+            roots.push(frame.func);
+            roots.push(frame.this);
+            if let Some(closure) = frame.closure {
+                roots.push(JSValue::from_ptr(closure));
+            }
+        }
+        roots.extend(self.scripts.iter().map(|&script| JSValue::from_ptr(script)));
+        roots.extend(self.exception);
+        roots.extend(self.promoted_var_refs.iter().map(|&(_, _, var_ref)| JSValue::from_ptr(var_ref)));
+        roots.extend(self.for_in_state.iter().map(|(obj, _, _)| *obj));
+        roots.extend(self.for_of_state.iter().map(|(iterable, _)| *iterable));
+
+        ctx.collect_garbage(&roots, true);
+    }
+
+    /// Executes an instruction, collecting garbage and running it once more
+    /// if it fails for want of heap
+    ///
+    /// Only opcodes from [`retry_operands`](Self::retry_operands) are
+    /// retried: their operands are saved beforehand and put back on the
+    /// stack, where the collection roots them. If the second attempt runs
+    /// out too, the instruction throws an out-of-memory error.
+    fn execute_with_retry(
+        &mut self,
+        ctx: &mut Context,
+        reader: &mut BytecodeReader,
+        instruction: &crate::bytecode::Instruction,
+    ) -> Result<Option<JSValue>, JSValue> {
+        #[cfg(feature = "alloc-origin")]
+        ctx.set_alloc_origin(crate::memory::AllocOrigin::Opcode(instruction.opcode as u8));
+        let depth = self.value_stack.len();
+        let operands = match Self::retry_operands(instruction.opcode) {
+            Some(operands) if operands <= depth && ctx.can_collect() => operands,
+            _ => return self.execute_instruction(ctx, reader, instruction),
+        };
+        let mut saved = [JSValue::undefined(); 2];
+        saved[..operands].copy_from_slice(&self.value_stack.as_slice()[depth - operands..]);
+        let failures = ctx.alloc_failures();
+
+        let result = self.execute_instruction(ctx, reader, instruction);
+        if result.is_ok() || ctx.alloc_failures() == failures {
+            return result;
+        }
+
+        self.value_stack.truncate(depth - operands);
+        for &value in &saved[..operands] {
+            self.value_stack.push(value).ok();
+        }
+        self.collect_garbage(ctx);
+
+        let failures = ctx.alloc_failures();
+        let result = self.execute_instruction(ctx, reader, instruction);
+        if result.is_err() && ctx.alloc_failures() != failures {
+            return Err(self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"));
+        }
+        result
+    }
+
+    /// Number of stack operands an opcode consumes, for opcodes that can be
+    /// run again after running out of memory
+    ///
+    /// These allocate before any other side effect and never call script
+    /// code, so a failed attempt changes nothing but the value stack.
+    /// Property writes are left out, since they can run setters.
+    fn retry_operands(opcode: Opcode) -> Option<usize> {
+        use Opcode::*;
+
+        match opcode {
+            PushI32 | PushConst8 | PushConst16 | PushEmptyString | PushAtomString8 | PushAtomString16
+            | PushFunc8 | PushFunc | FClosure | FClosureVarArgs | Object => Some(0),
+            Plus | Neg | Inc | Dec | PostInc | PostDec | Not | TypeOf => Some(1),
+            Add | Sub | Mul | Div | Mod | Pow | And | Or | Xor | Shl | Sar | Shr => Some(2),
+            _ => None,
+        }
+    }
+
+    /// Records the line an error was thrown from as its `lineNumber`
+    ///
+    /// The first run loop the error passes through sets it, so it names the
+    /// line of the `throw` (or of the failing operation) rather than that of
+    /// a caller's call. Error objects are those with an own `stack`, as all
+    /// builtin errors have; other thrown values are left alone. `line` is
+    /// only worked out for those.
+    fn note_error_line(ctx: &mut Context, exc: JSValue, line: impl FnOnce() -> Option<u32>) {
+        if matches!(ctx.error_kind(exc), Some(ErrorKind::OutOfMemory | ErrorKind::ExitRequested))
+            || ctx.find_own_property(exc, atoms::STACK).is_none()
+            || ctx.find_own_property(exc, atoms::LINE_NUMBER).is_some()
+        {
+            return;
+        }
+        if let Some(line) = line() {
+            let mut flags = crate::object::PropertyFlags::default();
+            flags.set_enumerable(false);
+            let _ = ctx.add_property(exc, atoms::LINE_NUMBER, JSValue::from_int(line as i32), flags);
+        }
+    }
+
+        /// Transfers an exception to the innermost handler above `handler_base`
+    ///
+    /// The handler is consumed, so a throw from the catch block propagates
+    /// outward. Returns the exception if this run loop has no handler left,
+    /// or if it is an exit request, a timeout or an interrupt, which only
+    /// the host may handle.
+    fn catch_exception(
+        &mut self,
+        ctx: &mut Context,
+        reader: &mut BytecodeReader,
+        handler_base: usize,
+        exc: JSValue,
+    ) -> Result<(), JSValue> {
+        if self.handlers.len() <= handler_base
+            || matches!(ctx.error_kind(exc), Some(ErrorKind::ExitRequested | ErrorKind::Timeout | ErrorKind::Interrupted))
+        {
+            return Err(exc);
+        }
+        let handler = match self.handlers.pop() {
+            Some(handler) => handler,
+            None => return Err(exc),
+        };
+        if handler.code != reader.code_id() {
+            crate::fatal::fatal("vm", "Catch handler installed by another function");
+        }
+
+        // Drop whatever the try block left half-evaluated
+        self.value_stack.truncate(handler.stack_len);
+        self.for_in_state.truncate(handler.for_in_len);
+        self.for_of_state.truncate(handler.for_of_len);
+        reader.set_pc(handler.catch_pc)
+            .unwrap_or_else(|_| crate::fatal::fatal("vm", "Catch handler outside its code"));
+        self.value_stack.push(exc)
+            .map_err(|_| self.throw_error_kind(ctx, ErrorKind::StackOverflow, "Stack overflow in exception handler"))
+    }
+
     /// Executes a single instruction
     ///
     /// Returns:
@@ -475,7 +660,7 @@ impl VM {
                 self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 self.value_stack.push(top)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -490,7 +675,7 @@ impl VM {
                 self.value_stack.push(c).ok();
                 self.value_stack.push(a).ok();
                 self.value_stack.push(b)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -508,7 +693,7 @@ impl VM {
                 self.value_stack.push(a).ok();
                 self.value_stack.push(b).ok();
                 self.value_stack.push(c)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -543,32 +728,32 @@ impl VM {
             // ===== Push Operations =====
             Undefined => {
                 self.value_stack.push(JSValue::undefined())
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             Null => {
                 self.value_stack.push(JSValue::null())
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             PushFalse => {
                 self.value_stack.push(JSValue::bool(false))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             PushTrue => {
                 self.value_stack.push(JSValue::bool(true))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             PushI8 => {
                 if let Operand::I8(val) = instruction.operand {
                     self.value_stack.push(JSValue::from_int(val as i32))
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand"))
@@ -578,7 +763,7 @@ impl VM {
             PushI16 => {
                 if let Operand::I16(val) = instruction.operand {
                     self.value_stack.push(JSValue::from_int(val as i32))
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand"))
@@ -588,7 +773,7 @@ impl VM {
             PushI32 => {
                 if let Operand::I32(val) = instruction.operand {
                     self.value_stack.push(JSValue::from_int(val))
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand"))
@@ -599,7 +784,7 @@ impl VM {
                 if let Operand::Const8(idx) = instruction.operand {
                     let value = self.get_constant(ctx, idx as u16)?;
                     self.value_stack.push(value)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for PushConst8"))
@@ -610,7 +795,7 @@ impl VM {
                 if let Operand::Const16(idx) = instruction.operand {
                     let value = self.get_constant(ctx, idx)?;
                     self.value_stack.push(value)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for PushConst16"))
@@ -619,63 +804,63 @@ impl VM {
 
             PushMinus1 => {
                 self.value_stack.push(JSValue::from_int(-1))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             Push0 => {
                 self.value_stack.push(JSValue::from_int(0))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             Push1 => {
                 self.value_stack.push(JSValue::from_int(1))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             Push2 => {
                 self.value_stack.push(JSValue::from_int(2))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             Push3 => {
                 self.value_stack.push(JSValue::from_int(3))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             Push4 => {
                 self.value_stack.push(JSValue::from_int(4))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             Push5 => {
                 self.value_stack.push(JSValue::from_int(5))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             Push6 => {
                 self.value_stack.push(JSValue::from_int(6))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             Push7 => {
                 self.value_stack.push(JSValue::from_int(7))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             PushEmptyString => {
                 let val = ctx.new_string("")
-                    .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                    .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
                 self.value_stack.push(val)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -684,52 +869,54 @@ impl VM {
                     .map(|f| f.this)
                     .unwrap_or(JSValue::undefined());
                 self.value_stack.push(this_val)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             PushNaN => {
                 let val = ctx.new_number(f64::NAN)
-                    .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                    .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
                 self.value_stack.push(val)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             PushInfinity => {
                 let val = ctx.new_number(f64::INFINITY)
-                    .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                    .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
                 self.value_stack.push(val)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             PushNegInfinity => {
                 let val = ctx.new_number(f64::NEG_INFINITY)
-                    .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                    .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
                 self.value_stack.push(val)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             PushFunc8 => {
                 if let Operand::U8(func_idx) = instruction.operand {
                     // Get function from function table
-                    if (func_idx as usize) >= self.function_table.len() {
+                    if (func_idx as usize) >= self.module.function_table.len() {
                         return Err(self.throw_error(ctx, "Function index out of bounds"));
                     }
 
-                    let func_entry = &self.function_table[func_idx as usize];
+                    let func_entry = &self.module.function_table[func_idx as usize];
 
                     // Create a bytecode function object
+                    let name = func_entry.name;
                     let func_val = ctx.new_bytecode_function(
-                        func_entry.bytecode_index,
+                        func_entry.code,
                         func_entry.param_count,
                         func_entry.local_count,
-                    ).map_err(|_| self.throw_error(ctx, "Out of memory creating function"))?;
+                    ).map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory creating function"))?;
+                    ctx.set_function_name(func_val, name);
 
                     self.value_stack.push(func_val)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for PushFunc8"))
@@ -739,21 +926,23 @@ impl VM {
             PushFunc => {
                 if let Operand::U16(func_idx) = instruction.operand {
                     // Get function from function table
-                    if (func_idx as usize) >= self.function_table.len() {
+                    if (func_idx as usize) >= self.module.function_table.len() {
                         return Err(self.throw_error(ctx, "Function index out of bounds"));
                     }
 
-                    let func_entry = &self.function_table[func_idx as usize];
+                    let func_entry = &self.module.function_table[func_idx as usize];
 
                     // Create a bytecode function object
+                    let name = func_entry.name;
                     let func_val = ctx.new_bytecode_function(
-                        func_entry.bytecode_index,
+                        func_entry.code,
                         func_entry.param_count,
                         func_entry.local_count,
-                    ).map_err(|_| self.throw_error(ctx, "Out of memory creating function"))?;
+                    ).map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory creating function"))?;
+                    ctx.set_function_name(func_val, name);
 
                     self.value_stack.push(func_val)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for PushFunc"))
@@ -766,16 +955,18 @@ impl VM {
                 // The operand is the function index (Const8 format)
                 if let Operand::Const8(func_idx) = instruction.operand {
                     // Get function from function table
-                    if (func_idx as usize) >= self.function_table.len() {
+                    if (func_idx as usize) >= self.module.function_table.len() {
                         return Err(self.throw_error(ctx, "Function index out of bounds"));
                     }
 
-                    // Get the function entry to extract bytecode_index, param_count, local_count, self_name_slot
-                    let func_entry = &self.function_table[func_idx as usize];
-                    let bytecode_index = func_entry.bytecode_index;
+                    // Get the function entry to extract code, param_count, local_count, self_name_slot
+                    let func_entry = &self.module.function_table[func_idx as usize];
+                    let code = func_entry.code;
                     let param_count = func_entry.param_count;
                     let local_count = func_entry.local_count;
                     let self_name_slot = func_entry.self_name_slot;
+                    let name = func_entry.name;
+                    let is_arrow = func_entry.flags & FUNC_FLAG_ARROW != 0;
 
                     // Get the captured var count from the next byte
                     // The compiler will emit: FClosure func_idx, captured_count, [var_ref indices...]
@@ -833,23 +1024,35 @@ impl VM {
                                         self.promoted_var_refs.push((base_sp, parent_idx, var_ref_idx));
                                         var_refs.push(var_ref_idx);
                                     }
-                                    Err(_) => return Err(self.throw_error(ctx, "Out of memory")),
+                                    Err(_) => return Err(self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory")),
                                 }
                             }
                         }
                     }
 
-                    // Allocate the closure object with bytecode_index (not func table index!)
-                    let closure_idx = match ctx.alloc_closure_with_self_name(bytecode_index, param_count, local_count, &var_refs, self_name_slot) {
+                    // Allocate the closure object with the function's code (not func table index!)
+                    let closure_idx = match ctx.alloc_closure_with_self_name(code, param_count, local_count, &var_refs, self_name_slot) {
                         Ok(idx) => idx,
-                        Err(_) => return Err(self.throw_error(ctx, "Out of memory creating closure")),
+                        Err(_) => return Err(self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory creating closure")),
                     };
 
+                    // Arrow functions keep the `this` of the scope creating them
+                    if is_arrow {
+                        let this_val = self.call_stack.current()
+                            .map(|f| f.this)
+                            .unwrap_or(JSValue::undefined());
+                        if let Some(closure) = ctx.get_closure_mut(closure_idx) {
+                            closure.flags |= JSClosure::FLAG_ARROW;
+                            closure.lexical_this = this_val;
+                        }
+                    }
+
                     // Push closure as a JSValue
                     let closure_val = JSValue::from_ptr(closure_idx);
+                    ctx.set_function_name(closure_val, name);
                     match self.value_stack.push(closure_val) {
                         Ok(()) => Ok(None),
-                        Err(_) => Err(self.throw_error(ctx, "Stack overflow")),
+                        Err(_) => Err(self.stack_overflow(ctx)),
                     }
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for FClosure"))
@@ -887,7 +1090,7 @@ impl VM {
 
                     match self.value_stack.push(value) {
                         Ok(()) => Ok(None),
-                        Err(_) => Err(self.throw_error(ctx, "Stack overflow")),
+                        Err(_) => Err(self.stack_overflow(ctx)),
                     }
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for GetVarRef"))
@@ -979,15 +1182,15 @@ impl VM {
             PushAtomString8 => {
                 if let Operand::Atom8(atom_idx) = instruction.operand {
                     // Get string from atom table
-                    if (atom_idx as usize) >= self.atom_table.len() {
+                    if (atom_idx as usize) >= self.module.atom_table.len() {
                         return Err(self.throw_error(ctx, "Atom index out of bounds"));
                     }
 
-                    let string = &self.atom_table[atom_idx as usize];
+                    let string = &self.module.atom_table[atom_idx as usize];
                     let val = ctx.new_string(string)
-                        .map_err(|_| self.throw_error(ctx, "Out of memory creating string"))?;
+                        .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory creating string"))?;
                     self.value_stack.push(val)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for PushAtomString8"))
@@ -997,15 +1200,15 @@ impl VM {
             PushAtomString16 => {
                 if let Operand::Atom16(atom_idx) = instruction.operand {
                     // Get string from atom table
-                    if (atom_idx as usize) >= self.atom_table.len() {
+                    if (atom_idx as usize) >= self.module.atom_table.len() {
                         return Err(self.throw_error(ctx, "Atom index out of bounds"));
                     }
 
-                    let string = &self.atom_table[atom_idx as usize];
+                    let string = &self.module.atom_table[atom_idx as usize];
                     let val = ctx.new_string(string)
-                        .map_err(|_| self.throw_error(ctx, "Out of memory creating string"))?;
+                        .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory creating string"))?;
                     self.value_stack.push(val)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for PushAtomString16"))
@@ -1014,13 +1217,14 @@ impl VM {
 
             // ===== Arithmetic Operations =====
             Add => {
+                self.operands_to_primitive(ctx, false)?;
                 let b = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let a = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_add(ctx, a, b)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1031,7 +1235,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_sub(ctx, a, b)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1042,7 +1246,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_mul(ctx, a, b)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1053,7 +1257,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_div(ctx, a, b)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1064,7 +1268,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_mod(ctx, a, b)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1075,7 +1279,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_pow(ctx, a, b)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1084,7 +1288,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.to_number(ctx, a)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1093,7 +1297,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_neg(ctx, a)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1102,7 +1306,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_inc(ctx, a)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1111,7 +1315,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_dec(ctx, a)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1121,7 +1325,7 @@ impl VM {
                 // Post-increment returns original value, then increments
                 let num = self.to_number(ctx, a)?;
                 self.value_stack.push(num)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1131,7 +1335,7 @@ impl VM {
                 // Post-decrement returns original value, then decrements
                 let num = self.to_number(ctx, a)?;
                 self.value_stack.push(num)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1143,7 +1347,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_lt(ctx, a, b)?;
                 self.value_stack.push(JSValue::bool(result))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1154,7 +1358,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_lte(ctx, a, b)?;
                 self.value_stack.push(JSValue::bool(result))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1165,7 +1369,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_gt(ctx, a, b)?;
                 self.value_stack.push(JSValue::bool(result))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1176,29 +1380,31 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_gte(ctx, a, b)?;
                 self.value_stack.push(JSValue::bool(result))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             Eq => {
+                self.operands_to_primitive(ctx, true)?;
                 let b = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let a = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_eq(ctx, a, b);
                 self.value_stack.push(JSValue::bool(result))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
             Neq => {
+                self.operands_to_primitive(ctx, true)?;
                 let b = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let a = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = !self.op_eq(ctx, a, b);
                 self.value_stack.push(JSValue::bool(result))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1209,7 +1415,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_strict_eq(ctx, a, b);
                 self.value_stack.push(JSValue::bool(result))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1220,7 +1426,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = !self.op_strict_eq(ctx, a, b);
                 self.value_stack.push(JSValue::bool(result))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1230,7 +1436,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = !self.to_boolean(ctx, a);
                 self.value_stack.push(JSValue::bool(result))
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1241,7 +1447,7 @@ impl VM {
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                     if !self.to_boolean(ctx, a) {
                         // Short-circuit: jump and keep 'a' on stack
-                        reader.set_pc((reader.pc() as i32 + offset) as usize);
+                        reader.jump_relative(offset).map_err(|err| self.throw_error(ctx, &err.to_string()))?;
                     } else {
                         // Continue: pop 'a', evaluate 'b'
                         self.value_stack.pop().ok();
@@ -1259,7 +1465,7 @@ impl VM {
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                     if self.to_boolean(ctx, a) {
                         // Short-circuit: jump and keep 'a' on stack
-                        reader.set_pc((reader.pc() as i32 + offset) as usize);
+                        reader.jump_relative(offset).map_err(|err| self.throw_error(ctx, &err.to_string()))?;
                     } else {
                         // Continue: pop 'a', evaluate 'b'
                         self.value_stack.pop().ok();
@@ -1277,7 +1483,7 @@ impl VM {
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                     if !a.is_null() && !a.is_undefined() {
                         // Not nullish: jump and keep 'a' on stack
-                        reader.set_pc((reader.pc() as i32 + offset) as usize);
+                        reader.jump_relative(offset).map_err(|err| self.throw_error(ctx, &err.to_string()))?;
                     } else {
                         // Nullish: pop 'a', evaluate 'b'
                         self.value_stack.pop().ok();
@@ -1294,7 +1500,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_bit_not(ctx, a)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1305,7 +1511,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_bit_and(ctx, a, b)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1316,7 +1522,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_bit_or(ctx, a, b)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1327,7 +1533,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_bit_xor(ctx, a, b)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1338,7 +1544,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_shl(ctx, a, b)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1349,7 +1555,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_sar(ctx, a, b)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1360,7 +1566,7 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let result = self.op_shr(ctx, a, b)?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1370,7 +1576,7 @@ impl VM {
                     let cond = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                     if !self.to_boolean(ctx, cond) {
-                        reader.set_pc((reader.pc() as i32 + offset) as usize);
+                        reader.jump_relative(offset).map_err(|err| self.throw_error(ctx, &err.to_string()))?;
                     }
                     Ok(None)
                 } else {
@@ -1383,7 +1589,7 @@ impl VM {
                     let cond = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                     if self.to_boolean(ctx, cond) {
-                        reader.set_pc((reader.pc() as i32 + offset) as usize);
+                        reader.jump_relative(offset).map_err(|err| self.throw_error(ctx, &err.to_string()))?;
                     }
                     Ok(None)
                 } else {
@@ -1393,7 +1599,7 @@ impl VM {
 
             Goto => {
                 if let Operand::Label(offset) = instruction.operand {
-                    reader.set_pc((reader.pc() as i32 + offset) as usize);
+                    reader.jump_relative(offset).map_err(|err| self.throw_error(ctx, &err.to_string()))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand"))
@@ -1421,20 +1627,24 @@ impl VM {
                 // Push the current exception onto the stack
                 if let Some(exc) = self.exception.take() {
                     self.value_stack.push(exc)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                 } else {
                     self.value_stack.push(JSValue::undefined())
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                 }
                 Ok(None)
             }
 
             PushCatchOffset => {
                 if let Operand::Label(offset) = instruction.operand {
-                    let catch_pc = (reader.pc() as i32 + offset) as usize;
-                    if let Ok(frame) = self.call_stack.current_mut() {
-                        frame.set_catch_offset(catch_pc);
-                    }
+                    let catch_pc = reader.relative_pc(offset).map_err(|err| self.throw_error(ctx, &err.to_string()))?;
+                    self.handlers.push(CatchHandler {
+                        code: reader.code_id(),
+                        catch_pc,
+                        stack_len: self.value_stack.len(),
+                        for_in_len: self.for_in_state.len(),
+                        for_of_len: self.for_of_state.len(),
+                    });
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand"))
@@ -1442,9 +1652,12 @@ impl VM {
             }
 
             ClearCatchOffset => {
-                // Clear the catch offset so exceptions aren't caught by this try block
-                if let Ok(frame) = self.call_stack.current_mut() {
-                    frame.clear_catch_offset();
+                // Remove the innermost handler once its try block is left,
+                // but never one that belongs to a caller's run loop
+                let owned = self.handlers.len() > self.handler_base
+                    && self.handlers.last().map(|h| h.code) == Some(reader.code_id());
+                if owned {
+                    self.handlers.pop();
                 }
                 Ok(None)
             }
@@ -1460,9 +1673,9 @@ impl VM {
                 if let Operand::U8(_count) = instruction.operand {
                     // Create a new object
                     let obj = ctx.new_object()
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                        .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
                     self.value_stack.push(obj)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand"))
@@ -1471,26 +1684,12 @@ impl VM {
 
             Array => {
                 if let Operand::U8(_count) = instruction.operand {
-                    // Get Array.prototype for proper inheritance
-                    let array_atom = crate::runtime::init::string_to_atom("Array");
-                    let proto_atom = crate::runtime::init::string_to_atom("prototype");
-                    let array_proto = ctx.get_global_property(array_atom)
-                        .and_then(|arr_ctor| ctx.get_property(arr_ctor, proto_atom))
-                        .unwrap_or(JSValue::null());
-
                     // Create a new array with Array.prototype
-                    let arr = ctx.new_object_with_proto(array_proto)
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
-
-                    // Initialize length to 0
-                    let length_atom = crate::runtime::init::string_to_atom("length");
-                    let zero = ctx.new_number(0.0)
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
-                    ctx.add_property(arr, length_atom, zero, crate::object::PropertyFlags::default())
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                    let arr = ctx.new_array()
+                        .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
 
                     self.value_stack.push(arr)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand"))
@@ -1503,89 +1702,45 @@ impl VM {
                 let obj = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
-                // Get all enumerable property keys
+                // Snapshot the enumerable keys; ForInNext walks them
                 let keys = self.get_enumerable_keys(ctx, obj);
-
-                // Push first key (or undefined if empty)
-                if keys.is_empty() {
-                    self.value_stack.push(JSValue::undefined())
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                } else {
-                    let first_key = ctx.new_string(&keys[0])
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
-                    self.value_stack.push(first_key)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                }
-
-                // Store iterator state (keys, index) - will be used by ForInNext
-                self.for_in_state.push((keys, 0));
+                self.for_in_state.push((obj, keys, 0));
+                self.push_next_for_in_key(ctx)?;
                 Ok(None)
             }
 
             ForInNext => {
-                // Get current iterator state
-                if let Some((keys, ref mut index)) = self.for_in_state.last_mut() {
-                    *index += 1;
-                    if *index < keys.len() {
-                        // Get next key
-                        let key_str = keys[*index].clone();
-                        let key = ctx.new_string(&key_str)
-                            .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
-                        self.value_stack.push(key)
-                            .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                    } else {
-                        // No more keys - push undefined to signal end
-                        self.for_in_state.pop();
-                        self.value_stack.push(JSValue::undefined())
-                            .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                    }
-                } else {
-                    self.value_stack.push(JSValue::undefined())
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                }
+                self.push_next_for_in_key(ctx)?;
+                Ok(None)
+            }
+
+            ForInEnd => {
+                self.for_in_state.pop();
                 Ok(None)
             }
 
             ForOfStart => {
-                // Pop the iterable
+                // Pop the iterable; only arrays and strings can be iterated
                 let iterable = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
-                // Get array elements (for now, only support arrays)
-                let values = self.get_iterable_values(ctx, iterable);
-
-                // Store iterator state
-                self.for_of_state.push((values.clone(), 0));
-
-                // Push first value (or undefined if empty)
-                if values.is_empty() {
-                    self.value_stack.push(JSValue::undefined())
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                } else {
-                    self.value_stack.push(values[0])
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                if !ctx.is_array(iterable) && ctx.get_string(iterable).is_none() {
+                    let type_name = if iterable.is_null() { "null" } else { self.typeof_value(ctx, iterable) };
+                    let msg = alloc::format!("{} is not iterable", type_name);
+                    return Err(self.throw_error_kind(ctx, ErrorKind::Type, &msg));
                 }
+
+                self.for_of_state.push((iterable, 0));
                 Ok(None)
             }
 
             ForOfNext => {
-                // Get current iterator state
-                if let Some((values, ref mut index)) = self.for_of_state.last_mut() {
-                    *index += 1;
-                    if *index < values.len() {
-                        let val = values[*index];
-                        self.value_stack.push(val)
-                            .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                    } else {
-                        // No more values - push undefined to signal end
-                        self.for_of_state.pop();
-                        self.value_stack.push(JSValue::undefined())
-                            .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                    }
-                } else {
-                    self.value_stack.push(JSValue::undefined())
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                }
+                self.push_next_for_of_value(ctx)?;
+                Ok(None)
+            }
+
+            ForOfEnd => {
+                self.for_of_state.pop();
                 Ok(None)
             }
 
@@ -1594,9 +1749,9 @@ impl VM {
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let type_str = self.typeof_value(ctx, val);
                 let result = ctx.new_string(type_str)
-                    .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                    .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
                 self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1605,7 +1760,7 @@ impl VM {
                 self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 self.value_stack.push(JSValue::undefined())
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
@@ -1616,10 +1771,9 @@ impl VM {
             GetGlobal8 => {
                 if let Operand::Atom8(atom_idx) = instruction.operand {
                     let atom = self.get_atom_from_table(atom_idx as usize)?;
-                    let value = ctx.get_global_property(atom)
-                        .unwrap_or(JSValue::undefined());
+                    let value = self.get_global(ctx, atom)?;
                     self.value_stack.push(value)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for GetGlobal8"))
@@ -1629,23 +1783,34 @@ impl VM {
             GetGlobal16 => {
                 if let Operand::Atom16(atom_idx) = instruction.operand {
                     let atom = self.get_atom_from_table(atom_idx as usize)?;
-                    let value = ctx.get_global_property(atom)
-                        .unwrap_or(JSValue::undefined());
+                    let value = self.get_global(ctx, atom)?;
                     self.value_stack.push(value)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for GetGlobal16"))
                 }
             }
 
+            GetGlobalUndef => {
+                if let Operand::Atom16(atom_idx) = instruction.operand {
+                    let atom = self.get_atom_from_table(atom_idx as usize)?;
+                    let value = ctx.get_global_property(atom).unwrap_or(JSValue::undefined());
+                    self.value_stack.push(value)
+                        .map_err(|_| self.stack_overflow(ctx))?;
+                    Ok(None)
+                } else {
+                    Err(self.throw_error(ctx, "Invalid operand for GetGlobalUndef"))
+                }
+            }
+
             PutGlobal8 => {
                 if let Operand::Atom8(atom_idx) = instruction.operand {
                     let atom = self.get_atom_from_table(atom_idx as usize)?;
                     let value = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                     ctx.set_global_property(atom, value)
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                        .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for PutGlobal8"))
@@ -1658,7 +1823,7 @@ impl VM {
                     let value = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                     ctx.set_global_property(atom, value)
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                        .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for PutGlobal16"))
@@ -1671,7 +1836,7 @@ impl VM {
                     let value = self.value_stack.peek()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                     ctx.set_global_property(atom, value)
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                        .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for SetGlobal8"))
@@ -1684,7 +1849,7 @@ impl VM {
                     let value = self.value_stack.peek()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                     ctx.set_global_property(atom, value)
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                        .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for SetGlobal16"))
@@ -1693,6 +1858,9 @@ impl VM {
 
             // ===== Function Calls =====
             Call => {
+                // Plain calls pass `undefined` as `this`, as strict mode
+                // does; there's no fallback to the global object. Arrow
+                // functions ignore it and keep the `this` they were made with.
                 if let Operand::U8(argc) = instruction.operand {
                     let argc = argc as u16;
                     // Stack layout: [func, arg1, arg2, ..., argN]
@@ -1718,29 +1886,14 @@ impl VM {
                             None => return Err(self.throw_error(ctx, "Invalid closure value")),
                         };
 
-                        // Get closure info - now uses bytecode_index directly!
-                        let (bytecode_index, param_count, local_count, self_name_slot) = match ctx.get_closure(closure_idx) {
-                            Some(closure) => (closure.bytecode_index, closure.param_count as usize, closure.local_count as usize, closure.self_name_slot),
+                        // Get closure info - the closure carries its code directly
+                        let (code, param_count, local_count, self_name_slot, this_val) = match ctx.get_closure(closure_idx) {
+                            Some(closure) => (closure.code, closure.param_count as usize, closure.local_count as usize, closure.self_name_slot, closure.this_for_call(JSValue::undefined())),
                             None => return Err(self.throw_error(ctx, "Invalid closure")),
                         };
 
-                        // Pad args if needed (undefined for missing params)
-                        while args.len() < param_count {
-                            args.push(JSValue::undefined());
-                        }
-
-                        // Push arguments onto the stack as locals
-                        let base_sp = self.value_stack.len();
-                        for arg in &args {
-                            self.value_stack.push(*arg)
-                                .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                        }
-
-                        // Reserve space for additional locals
-                        for _ in param_count..local_count {
-                            self.value_stack.push(JSValue::undefined())
-                                .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                        }
+                        // Reserve the frame's window: parameters, then locals
+                        let base_sp = self.reserve_frame(ctx, &args, param_count, local_count)?;
 
                         // For named function expressions, set the function self-reference
                         if self_name_slot != 0xFF {
@@ -1749,12 +1902,13 @@ impl VM {
                         }
 
                         // Push a call frame to track base_sp for nested closures
-                        let frame = StackFrame::new_closure(func, base_sp, args.len() as u16, JSValue::undefined(), closure_idx);
+                        let frame = StackFrame::new_closure(func, base_sp, args.len() as u16, this_val, closure_idx)
+                            .with_window(param_count, local_count.max(param_count));
                         self.call_stack.push(frame)
-                            .map_err(|_| self.throw_error(ctx, "Call stack overflow"))?;
+                            .map_err(|_| self.stack_overflow(ctx))?;
 
                         // Execute the function with closure context
-                        let result = self.execute_bytecode_function(ctx, bytecode_index, base_sp, local_count, Some(closure_idx));
+                        let result = self.execute_bytecode_function(ctx, code, base_sp, Some(closure_idx));
 
                         // Pop the call frame
                         let _ = self.call_stack.pop();
@@ -1767,40 +1921,26 @@ impl VM {
 
                         // Push result
                         self.value_stack.push(result)
-                            .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                            .map_err(|_| self.stack_overflow(ctx))?;
                         Ok(None)
                     } else if let Some(bc_func) = ctx.get_bytecode_function(func) {
                         // This is a bytecode function - execute it within the VM
                         // Get the function's bytecode
-                        let func_bc_index = bc_func.bytecode_index();
+                        let func_code = bc_func.code();
                         let param_count = bc_func.param_count() as usize;
                         let local_count = bc_func.local_count() as usize;
 
-                        // Pad args if needed (undefined for missing params)
-                        while args.len() < param_count {
-                            args.push(JSValue::undefined());
-                        }
-
-                        // Push arguments onto the stack as locals (simplified for now)
-                        let base_sp = self.value_stack.len();
-                        for arg in &args {
-                            self.value_stack.push(*arg)
-                                .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                        }
-
-                        // Reserve space for additional locals
-                        for _ in param_count..local_count {
-                            self.value_stack.push(JSValue::undefined())
-                                .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                        }
+                        // Reserve the frame's window: parameters, then locals
+                        let base_sp = self.reserve_frame(ctx, &args, param_count, local_count)?;
 
                         // Push a call frame to track base_sp for closures
-                        let frame = StackFrame::new(func, base_sp, argc, JSValue::undefined());
+                        let frame = StackFrame::new(func, base_sp, argc, JSValue::undefined())
+                            .with_window(param_count, local_count.max(param_count));
                         self.call_stack.push(frame)
-                            .map_err(|_| self.throw_error(ctx, "Call stack overflow"))?;
+                            .map_err(|_| self.stack_overflow(ctx))?;
 
                         // Execute the function (no closure context)
-                        let result = self.execute_bytecode_function(ctx, func_bc_index, base_sp, local_count, None);
+                        let result = self.execute_bytecode_function(ctx, func_code, base_sp, None);
 
                         // Pop the call frame
                         let _ = self.call_stack.pop();
@@ -1813,7 +1953,7 @@ impl VM {
 
                         // Push result
                         self.value_stack.push(result)
-                            .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                            .map_err(|_| self.stack_overflow(ctx))?;
                         Ok(None)
                     } else {
                         // Not a bytecode function - try native function
@@ -1821,7 +1961,7 @@ impl VM {
 
                         // Push result
                         self.value_stack.push(result)
-                            .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                            .map_err(|_| self.stack_overflow(ctx))?;
                         Ok(None)
                     }
                 } else {
@@ -1847,8 +1987,8 @@ impl VM {
                     let func = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
-                    // Pop object (this)
-                    let _this = self.value_stack.pop()
+                    // Pop the receiver, which becomes the callee's `this`
+                    let this = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
                     // Handle closures first
@@ -1858,25 +1998,13 @@ impl VM {
                             None => return Err(self.throw_error(ctx, "Invalid closure value")),
                         };
 
-                        let (bytecode_index, param_count, local_count, self_name_slot) = match ctx.get_closure(closure_idx) {
-                            Some(closure) => (closure.bytecode_index, closure.param_count as usize, closure.local_count as usize, closure.self_name_slot),
+                        let (code, param_count, local_count, self_name_slot, this_val) = match ctx.get_closure(closure_idx) {
+                            Some(closure) => (closure.code, closure.param_count as usize, closure.local_count as usize, closure.self_name_slot, closure.this_for_call(this)),
                             None => return Err(self.throw_error(ctx, "Invalid closure")),
                         };
 
-                        while args.len() < param_count {
-                            args.push(JSValue::undefined());
-                        }
-
-                        let base_sp = self.value_stack.len();
-                        for arg in &args {
-                            self.value_stack.push(*arg)
-                                .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                        }
-
-                        for _ in param_count..local_count {
-                            self.value_stack.push(JSValue::undefined())
-                                .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                        }
+                        // Reserve the frame's window: parameters, then locals
+                        let base_sp = self.reserve_frame(ctx, &args, param_count, local_count)?;
 
                         // For named function expressions, set the function self-reference
                         if self_name_slot != 0xFF {
@@ -1884,58 +2012,48 @@ impl VM {
                                 .map_err(|_| self.throw_error(ctx, "Invalid self_name_slot"))?;
                         }
 
-                        let frame = StackFrame::new_closure(func, base_sp, args.len() as u16, JSValue::undefined(), closure_idx);
+                        let frame = StackFrame::new_closure(func, base_sp, args.len() as u16, this_val, closure_idx)
+                            .with_window(param_count, local_count.max(param_count));
                         self.call_stack.push(frame)
-                            .map_err(|_| self.throw_error(ctx, "Call stack overflow"))?;
+                            .map_err(|_| self.stack_overflow(ctx))?;
 
-                        let result = self.execute_bytecode_function(ctx, bytecode_index, base_sp, local_count, Some(closure_idx));
+                        let result = self.execute_bytecode_function(ctx, code, base_sp, Some(closure_idx));
 
                         let _ = self.call_stack.pop();
                         let result = result?;
 
                         self.value_stack.truncate(base_sp);
                         self.value_stack.push(result)
-                            .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                            .map_err(|_| self.stack_overflow(ctx))?;
                         Ok(None)
                     } else if let Some(bc_func) = ctx.get_bytecode_function(func) {
                         // Bytecode function
-                        let func_bc_index = bc_func.bytecode_index();
+                        let func_code = bc_func.code();
                         let param_count = bc_func.param_count() as usize;
                         let local_count = bc_func.local_count() as usize;
 
-                        while args.len() < param_count {
-                            args.push(JSValue::undefined());
-                        }
-
-                        let base_sp = self.value_stack.len();
-                        for arg in &args {
-                            self.value_stack.push(*arg)
-                                .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                        }
-
-                        for _ in param_count..local_count {
-                            self.value_stack.push(JSValue::undefined())
-                                .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                        }
+                        // Reserve the frame's window: parameters, then locals
+                        let base_sp = self.reserve_frame(ctx, &args, param_count, local_count)?;
 
-                        let frame = StackFrame::new(func, base_sp, argc, JSValue::undefined());
+                        let frame = StackFrame::new(func, base_sp, argc, this)
+                            .with_window(param_count, local_count.max(param_count));
                         self.call_stack.push(frame)
-                            .map_err(|_| self.throw_error(ctx, "Call stack overflow"))?;
+                            .map_err(|_| self.stack_overflow(ctx))?;
 
-                        let result = self.execute_bytecode_function(ctx, func_bc_index, base_sp, local_count, None);
+                        let result = self.execute_bytecode_function(ctx, func_code, base_sp, None);
 
                         let _ = self.call_stack.pop();
                         let result = result?;
 
                         self.value_stack.truncate(base_sp);
                         self.value_stack.push(result)
-                            .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                            .map_err(|_| self.stack_overflow(ctx))?;
                         Ok(None)
                     } else {
                         // Native function - use ctx.call_function
-                        let result = ctx.call_function(func, _this, &args)?;
+                        let result = ctx.call_function(func, this, &args)?;
                         self.value_stack.push(result)
-                            .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                            .map_err(|_| self.stack_overflow(ctx))?;
                         Ok(None)
                     }
                 } else {
@@ -1943,6 +2061,59 @@ impl VM {
                 }
             }
 
+            CallConstructor => {
+                if let Operand::U8(argc) = instruction.operand {
+                    // Stack layout: [func, arg1, arg2, ..., argN]
+                    let mut args = alloc::vec::Vec::new();
+                    for _ in 0..argc {
+                        let arg = self.value_stack.pop()
+                            .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
+                        args.push(arg);
+                    }
+                    args.reverse();
+
+                    let func = self.value_stack.pop()
+                        .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
+
+                    // Native constructors (Error, ...) and functions bound to
+                    // them, such as registered error classes, build their own
+                    // result
+                    let is_native = ctx.get_native_function(func).is_some()
+                        || (ctx.is_bound_function(func)
+                            && ctx.get_property(func, atoms::BOUND_TARGET).is_some_and(|target| ctx.get_native_function(target).is_some()));
+                    if !is_native && !ctx.is_constructor(func) {
+                        return Err(self.throw_error_kind(ctx, ErrorKind::Type, "Not a constructor"));
+                    }
+
+                    // The new object inherits from func.prototype when that is an
+                    // object, and from Object.prototype otherwise
+                    self.prepare_property_read(ctx, func, atoms::PROTOTYPE)?;
+                    let proto = ctx.get_property(func, atoms::PROTOTYPE)
+                        .filter(|p| ctx.get_object(*p).is_some());
+                    let obj = match proto {
+                        Some(proto) => ctx.new_object_with_proto(proto),
+                        None => ctx.new_object(),
+                    }.map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
+
+                    let result = if is_native {
+                        ctx.call_function(func, obj, &args)?
+                    } else {
+                        self.call_function_internal(ctx, func, obj, &args)?
+                    };
+
+                    // An explicitly returned object replaces the new one
+                    let returned_object = !result.is_null()
+                        && ctx.get_number(result).is_none()
+                        && matches!(self.typeof_value(ctx, result), "object" | "function");
+                    let value = if returned_object { result } else { obj };
+                    self.value_stack.push(value)
+                        .map_err(|_| self.stack_overflow(ctx))?;
+                    Ok(None)
+                } else {
+                    Err(self.throw_error(ctx, "Invalid operand for CallConstructor"))
+                }
+            }
+
             // ===== Property Access =====
             GetField => {
                 if let Operand::U16(atom_idx) = instruction.operand {
@@ -1950,11 +2121,18 @@ impl VM {
                     let obj = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
+                    self.check_object_coercible(ctx, obj, "read")?;
+
                     // Get property atom
                     let atom = self.get_atom_from_table(atom_idx as usize)?;
+                    let prepared = self.prepare_property_read(ctx, obj, atom)?;
 
                     // Look up property with accessor info
-                    let value = match ctx.find_property_with_accessor(obj, atom) {
+                    let lookup = prepared.map_or_else(
+                        || ctx.find_property_with_accessor(obj, atom),
+                        crate::context::PropertyLookupResult::Value,
+                    );
+                    let value = match lookup {
                         crate::context::PropertyLookupResult::NotFound => JSValue::undefined(),
                         crate::context::PropertyLookupResult::Value(v) => v,
                         crate::context::PropertyLookupResult::Getter(getter) |
@@ -1971,7 +2149,7 @@ impl VM {
 
                     // Push result
                     self.value_stack.push(value)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for GetField"))
@@ -1984,11 +2162,18 @@ impl VM {
                     let obj = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
+                    self.check_object_coercible(ctx, obj, "read")?;
+
                     // Get property atom
                     let atom = self.get_atom_from_table(atom_idx as usize)?;
+                    let prepared = self.prepare_property_read(ctx, obj, atom)?;
 
                     // Look up property with accessor info
-                    let value = match ctx.find_property_with_accessor(obj, atom) {
+                    let lookup = prepared.map_or_else(
+                        || ctx.find_property_with_accessor(obj, atom),
+                        crate::context::PropertyLookupResult::Value,
+                    );
+                    let value = match lookup {
                         crate::context::PropertyLookupResult::NotFound => JSValue::undefined(),
                         crate::context::PropertyLookupResult::Value(v) => v,
                         crate::context::PropertyLookupResult::Getter(getter) |
@@ -2005,7 +2190,7 @@ impl VM {
 
                     // Push result
                     self.value_stack.push(value)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for GetField8"))
@@ -2020,12 +2205,15 @@ impl VM {
                     let obj = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
+                    self.check_object_coercible(ctx, obj, "set")?;
+
                     // Get property atom
                     let atom = self.get_atom_from_table(atom_idx as usize)?;
 
                     // Set property on object
-                    ctx.add_property(obj, atom, value, crate::object::PropertyFlags::default())
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                    let stored = self.array_length_value(ctx, obj, atom, value)?;
+                    ctx.set_property(obj, atom, stored)
+                        .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
 
                     Ok(None)
                 } else {
@@ -2041,12 +2229,15 @@ impl VM {
                     let obj = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
+                    self.check_object_coercible(ctx, obj, "set")?;
+
                     // Get property atom
                     let atom = self.get_atom_from_table(atom_idx as usize)?;
 
                     // Set property on object
-                    ctx.add_property(obj, atom, value, crate::object::PropertyFlags::default())
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                    let stored = self.array_length_value(ctx, obj, atom, value)?;
+                    ctx.set_property(obj, atom, stored)
+                        .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
 
                     Ok(None)
                 } else {
@@ -2062,16 +2253,19 @@ impl VM {
                     let obj = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
+                    self.check_object_coercible(ctx, obj, "set")?;
+
                     // Get property atom
                     let atom = self.get_atom_from_table(atom_idx as usize)?;
 
                     // Set property on object
-                    ctx.add_property(obj, atom, value, crate::object::PropertyFlags::default())
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                    let stored = self.array_length_value(ctx, obj, atom, value)?;
+                    ctx.set_property(obj, atom, stored)
+                        .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
 
                     // Push value back (SetField returns the assigned value)
                     self.value_stack.push(value)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
 
                     Ok(None)
                 } else {
@@ -2081,10 +2275,11 @@ impl VM {
 
             DefineGetter => {
                 if let Operand::U16(atom_idx) = instruction.operand {
-                    // Stack: [obj, getter] -> [obj]
+                    // Stack: [obj, getter] -> [], like PutField; the literal's
+                    // code dup'd obj beforehand
                     let getter = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
-                    let obj = self.value_stack.peek()
+                    let obj = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
                     // Get property atom
@@ -2092,7 +2287,7 @@ impl VM {
 
                     // Define getter on object
                     ctx.define_getter(obj, atom, getter)
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                        .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
 
                     Ok(None)
                 } else {
@@ -2102,10 +2297,11 @@ impl VM {
 
             DefineSetter => {
                 if let Operand::U16(atom_idx) = instruction.operand {
-                    // Stack: [obj, setter] -> [obj]
+                    // Stack: [obj, setter] -> [], like PutField; the literal's
+                    // code dup'd obj beforehand
                     let setter = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
-                    let obj = self.value_stack.peek()
+                    let obj = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
                     // Get property atom
@@ -2113,7 +2309,7 @@ impl VM {
 
                     // Define setter on object
                     ctx.define_setter(obj, atom, setter)
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                        .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
 
                     Ok(None)
                 } else {
@@ -2121,20 +2317,26 @@ impl VM {
                 }
             }
 
+            CopyDataProperties => {
+                // Stack: [obj, source] -> [], for `{...source}`; the
+                // literal's code dup'd obj beforehand. Both stay on the
+                // stack while getters run, which keeps them rooted.
+                let source = self.value_stack.peek_at(0)
+                    .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
+                let obj = self.value_stack.peek_at(1)
+                    .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
+                crate::builtins::object::copy_own_enumerable(ctx, obj, source, crate::builtins::object::CopyMode::Define)?;
+                self.value_stack.truncate(self.value_stack.len() - 2);
+                Ok(None)
+            }
+
             // ===== Local Variable Access =====
             GetLoc => {
                 if let Operand::U8(idx) = instruction.operand {
-                    // Get local variable from the value stack
-                    // At top level, locals start at the base of the current frame
-                    let base_sp = match self.call_stack.current() {
-                        Ok(frame) => frame.sp,
-                        Err(_) => return Err(self.throw_error(ctx, "No call frame")),
-                    };
-
-                    let local_val = self.value_stack.get(base_sp + idx as usize)
-                        .map_err(|_| self.throw_error(ctx, "Invalid local variable index"))?;
+                    // Get local variable from the current frame's window
+                    let local_val = self.get_local(ctx, idx)?;
                     self.value_stack.push(local_val)
-                        .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                        .map_err(|_| self.stack_overflow(ctx))?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for GetLoc"))
@@ -2145,46 +2347,18 @@ impl VM {
                 if let Operand::U8(idx) = instruction.operand {
                     let val = self.value_stack.pop()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
-
-                    let base_sp = match self.call_stack.current() {
-                        Ok(frame) => frame.sp,
-                        Err(_) => return Err(self.throw_error(ctx, "No call frame")),
-                    };
-                    let target_idx = base_sp + idx as usize;
-
-                    // Ensure we have enough space for this local
-                    while self.value_stack.len() <= target_idx {
-                        self.value_stack.push(JSValue::undefined())
-                            .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                    }
-
-                    self.value_stack.set(target_idx, val)
-                        .map_err(|_| self.throw_error(ctx, "Invalid local variable index"))?;
-                    Ok(None)
-                } else {
-                    Err(self.throw_error(ctx, "Invalid operand for PutLoc"))
-                }
-            }
+                    self.set_local(ctx, idx, val)?;
+                    Ok(None)
+                } else {
+                    Err(self.throw_error(ctx, "Invalid operand for PutLoc"))
+                }
+            }
 
             SetLoc => {
                 if let Operand::U8(idx) = instruction.operand {
                     let val = self.value_stack.peek()
                         .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
-
-                    let base_sp = match self.call_stack.current() {
-                        Ok(frame) => frame.sp,
-                        Err(_) => return Err(self.throw_error(ctx, "No call frame")),
-                    };
-                    let target_idx = base_sp + idx as usize;
-
-                    // Ensure we have enough space for this local
-                    while self.value_stack.len() <= target_idx {
-                        self.value_stack.push(JSValue::undefined())
-                            .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                    }
-
-                    self.value_stack.set(target_idx, val)
-                        .map_err(|_| self.throw_error(ctx, "Invalid local variable index"))?;
+                    self.set_local(ctx, idx, val)?;
                     Ok(None)
                 } else {
                     Err(self.throw_error(ctx, "Invalid operand for SetLoc"))
@@ -2192,220 +2366,144 @@ impl VM {
             }
 
             // ===== Object/Array Creation =====
-            // Note: Array opcode already handled above, this is dead code
-            // Keeping for completeness but should be cleaned up
-
-            // ===== Array/Object Element Access =====
-            GetArrayEl => {
-                // Stack: [obj, index] -> [value]
-                let index = self.value_stack.pop()
+            Delete => {
+                // Stack: [obj, key] -> [deleted]
+                let key = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
                 let obj = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
-                // Convert index to number
-                let idx_num = if let Some(i) = index.to_int() {
-                    i as f64
-                } else if let Some(n) = ctx.get_number(index) {
-                    n
-                } else {
-                    0.0
-                };
-
-                // Convert number to property key (toString)
-                let key_str = alloc::format!("{}", idx_num as i32);
-
-                // Create atom for the property key
-                let mut hash: u32 = 5381;
-                for byte in key_str.bytes() {
-                    hash = hash.wrapping_mul(33).wrapping_add(byte as u32);
-                }
-                let key = crate::value::JSAtom::from_id(hash);
+                self.check_object_coercible(ctx, obj, "delete")?;
+                let key = self.to_property_key(ctx, key, false)?;
+                // Primitives have no own properties to remove
+                let deleted = key.is_null() || ctx.delete_property(obj, key);
 
-                // Get the property
-                let value = ctx.get_property(obj, key)
-                    .unwrap_or(JSValue::undefined());
-
-                self.value_stack.push(value)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                self.value_stack.push(JSValue::bool(deleted))
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
-            PutArrayEl => {
-                // Stack: [obj, index, value] -> [obj]
-                let value = self.value_stack.pop()
+            In => {
+                // Stack: [key, obj] -> [found]
+                let obj = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
-                let index = self.value_stack.pop()
+                let key = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
-                let obj = self.value_stack.peek()
-                    .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
-
-                // Convert index to number
-                let idx_num = if let Some(i) = index.to_int() {
-                    i as f64
-                } else if let Some(n) = ctx.get_number(index) {
-                    n
-                } else {
-                    0.0
-                };
-
-                // Convert number to property key (toString)
-                let key_str = alloc::format!("{}", idx_num as i32);
 
-                // Create atom for the property key
-                let mut hash: u32 = 5381;
-                for byte in key_str.bytes() {
-                    hash = hash.wrapping_mul(33).wrapping_add(byte as u32);
+                if obj.is_null() || !matches!(self.typeof_value(ctx, obj), "object" | "function") {
+                    let msg = alloc::format!("cannot use 'in' operator to search in {}", self.typeof_value(ctx, obj));
+                    return Err(self.throw_error_kind(ctx, ErrorKind::Type, &msg));
                 }
-                let key = crate::value::JSAtom::from_id(hash);
-
-                // Set the property
-                ctx.add_property(obj, key, value, crate::object::PropertyFlags::default())
-                    .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
-
-                // Update length property if this is a numeric index
-                if idx_num >= 0.0 && idx_num == libm::floor(idx_num) {
-                    let length_atom = crate::runtime::init::string_to_atom("length");
-
-                    // Get current length (defaults to 0)
-                    let current_length = ctx.get_property(obj, length_atom)
-                        .and_then(|v| ctx.get_number(v))
-                        .unwrap_or(0.0);
-
-                    // New length should be max of current and idx + 1
-                    let new_length = f64::max(current_length, idx_num + 1.0);
-
-                    let new_length_val = ctx.new_number(new_length)
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
+                let key = self.to_property_key(ctx, key, false)?;
+                let found = !key.is_null() && ctx.has_property(obj, key);
 
-                    // Always add the property (this creates a duplicate, but get_property
-                    // should find the most recent one if the hash table is searched properly)
-                    // TODO: Implement proper property update mechanism
-                    ctx.add_property(obj, length_atom, new_length_val, crate::object::PropertyFlags::default())
-                        .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
-                }
-
-                // Leave obj on stack
+                self.value_stack.push(JSValue::bool(found))
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
-            // ===== Increment/Decrement Operators =====
-            Inc => {
-                // ++x: pop value, increment, push result
-                let val = self.value_stack.pop()
+            Instanceof => {
+                // Stack: [value, ctor] -> [is_instance]
+                let ctor = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
-
-                let num = if let Some(i) = val.to_int() {
-                    i as f64 + 1.0
-                } else if let Some(f) = ctx.get_number(val) {
-                    f + 1.0
-                } else {
-                    f64::NAN
-                };
-
-                let result = ctx.new_number(num)
-                    .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
-                self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
-                Ok(None)
-            }
-
-            Dec => {
-                // --x: pop value, decrement, push result
-                let val = self.value_stack.pop()
+                let value = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
-                let num = if let Some(i) = val.to_int() {
-                    i as f64 - 1.0
-                } else if let Some(f) = ctx.get_number(val) {
-                    f - 1.0
-                } else {
-                    f64::NAN
+                if ctor.is_null() || !matches!(self.typeof_value(ctx, ctor), "object" | "function") {
+                    let msg = alloc::format!("right-hand side of 'instanceof' is {}, not a function", self.typeof_value(ctx, ctor));
+                    return Err(self.throw_error_kind(ctx, ErrorKind::Type, &msg));
+                }
+                self.prepare_property_read(ctx, ctor, atoms::PROTOTYPE)?;
+                let Some(proto) = ctx.instanceof_prototype(ctor) else {
+                    return Err(self.throw_error_kind(ctx, ErrorKind::Type, "right-hand side of 'instanceof' has no prototype object"));
                 };
+                let found = ctx.has_in_prototype_chain(value, proto);
 
-                let result = ctx.new_number(num)
-                    .map_err(|_| self.throw_error(ctx, "Out of memory"))?;
-                self.value_stack.push(result)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                self.value_stack.push(JSValue::bool(found))
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
-            PostInc => {
-                // x++: pop value, push original, increment and store
-                // Note: This needs special handling in codegen to work with lvalues
-                let val = self.value_stack.pop()
+            // Note: Array opcode already handled above, this is dead code
+            // Keeping for completeness but should be cleaned up
+
+            // ===== Array/Object Element Access =====
+            GetArrayEl => {
+                // Stack: [obj, index] -> [value]
+                let index = self.value_stack.pop()
+                    .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
+                let obj = self.value_stack.pop()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
-                let num = if let Some(i) = val.to_int() {
-                    i as f64
-                } else if let Some(f) = ctx.get_number(val) {
-                    f
-                } else {
-                    f64::NAN
-                };
+                self.check_object_coercible(ctx, obj, "read")?;
+                let key = self.to_property_key(ctx, index, false)?;
+                let prepared = self.prepare_property_read(ctx, obj, key)?;
 
-                // Push original value (this is what the expression returns)
-                self.value_stack.push(val)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                // Fast path: dense array element
+                let dense = key.to_index().and_then(|i| ctx.get_array_element(obj, i));
+                let value = prepared
+                    .or(dense)
+                    .or_else(|| ctx.get_property(obj, key))
+                    .unwrap_or(JSValue::undefined());
 
-                // The incremented value should be stored by the calling code
-                // For now, this is a simplified implementation
+                self.value_stack.push(value)
+                    .map_err(|_| self.stack_overflow(ctx))?;
                 Ok(None)
             }
 
-            PostDec => {
-                // x--: pop value, push original, decrement and store
-                let val = self.value_stack.pop()
+            PutArrayEl => {
+                // Stack: [obj, index, value] -> [obj]
+                let value = self.value_stack.pop()
+                    .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
+                let index = self.value_stack.pop()
+                    .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
+                let obj = self.value_stack.peek()
                     .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
 
-                let num = if let Some(i) = val.to_int() {
-                    i as f64
-                } else if let Some(f) = ctx.get_number(val) {
-                    f
-                } else {
-                    f64::NAN
-                };
+                self.check_object_coercible(ctx, obj, "set")?;
+                let key = self.to_property_key(ctx, index, true)?;
 
-                // Push original value (this is what the expression returns)
-                self.value_stack.push(val)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                // Arrays keep integer keys and length in their element storage
+                let value = self.array_length_value(ctx, obj, key, value)?;
+                ctx.set_property(obj, key, value)
+                    .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
 
-                // The decremented value should be stored by the calling code
+                // Leave obj on stack
                 Ok(None)
             }
 
             Arguments => {
                 // Create the arguments object - an array-like object containing all passed arguments
-                let (argc, base_sp) = if let Ok(frame) = self.call_stack.current() {
-                    (frame.argc as usize, frame.sp)
-                } else {
-                    (0, 0)
+                let frame = match self.call_stack.current() {
+                    Ok(frame) => frame.clone(),
+                    Err(_) => return Err(self.throw_error(ctx, "No call frame")),
                 };
+                let argc = frame.argc as usize;
 
                 // Create an object to hold the arguments
                 let args_obj = ctx.new_object()
-                    .map_err(|_| self.throw_error(ctx, "Out of memory creating arguments object"))?;
+                    .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory creating arguments object"))?;
 
                 // Add length property
-                let length_atom = crate::runtime::init::string_to_atom("length");
+                let length_atom = crate::value::atoms::LENGTH;
                 let length_val = JSValue::from_int(argc as i32);
                 ctx.add_property(args_obj, length_atom, length_val, crate::object::PropertyFlags::default())
                     .map_err(|_| self.throw_error(ctx, "Failed to set length"))?;
 
                 // Copy all arguments into the object with numeric keys
                 for i in 0..argc {
-                    let arg_val = self.value_stack.get(base_sp + i)
+                    let arg_val = self.value_stack.get(frame.arg_slot(i))
                         .map_err(|_| self.throw_error(ctx, "Invalid argument index"))?;
 
                     let idx_str = alloc::format!("{}", i);
-                    let idx_atom = crate::runtime::init::string_to_atom(&idx_str);
+                    let idx_atom = crate::runtime::init::string_to_atom(ctx, &idx_str);
                     ctx.add_property(args_obj, idx_atom, arg_val, crate::object::PropertyFlags::default())
                         .map_err(|_| self.throw_error(ctx, "Failed to set argument"))?;
                 }
 
                 // Push the arguments object onto the stack
                 self.value_stack.push(args_obj)
-                    .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                    .map_err(|_| self.stack_overflow(ctx))?;
 
                 Ok(None)
             }
@@ -2420,75 +2518,300 @@ impl VM {
         }
     }
 
-    /// Helper: Throws an error with the given message
+    /// Reserves a call's frame window at the top of the value stack
+    ///
+    /// Parameters come first, missing ones undefined, then the rest of the
+    /// function's locals. Arguments beyond the parameters go above the
+    /// window, where only `arguments` reads them. Returns the frame's base.
+    fn reserve_frame(&mut self, ctx: &mut Context, args: &[JSValue], param_count: usize, local_count: usize) -> Result<usize, JSValue> {
+        let base_sp = self.value_stack.len();
+        let window = local_count.max(param_count);
+        let (params, extra) = args.split_at(args.len().min(param_count));
+        let slots = params.iter().copied()
+            .chain(core::iter::repeat_n(JSValue::undefined(), window - params.len()))
+            .chain(extra.iter().copied());
+        for value in slots {
+            if self.value_stack.push(value).is_err() {
+                self.value_stack.truncate(base_sp);
+                return Err(self.stack_overflow(ctx));
+            }
+        }
+        Ok(base_sp)
+    }
+
+    /// Returns the value stack index of local `idx` in the current frame
+    ///
+    /// Throws a `ReferenceError` if `idx` falls outside the frame's window
+    /// rather than reaching into another frame's slots.
+    fn local_slot(&mut self, ctx: &mut Context, idx: u8) -> Result<usize, JSValue> {
+        let (base_sp, locals) = match self.call_stack.current() {
+            Ok(frame) => (frame.sp, frame.locals),
+            Err(_) => return Err(self.throw_error(ctx, "No call frame")),
+        };
+        let slot = base_sp + idx as usize;
+        if (idx as usize) < locals && slot < self.value_stack.len() {
+            Ok(slot)
+        } else {
+            Err(self.throw_error_kind(ctx, ErrorKind::Reference, &alloc::format!("Local slot {idx} is outside the frame")))
+        }
+    }
+
+    /// Converts object operands among the top two stack values to
+    /// primitives, in place (ToPrimitive with no hint)
+    ///
+    /// Both operands stay on the stack, where collections that happen
+    /// while a `valueOf` or `toString` runs can see them. For `==`
+    /// (`equality`), an object is only converted when the other operand
+    /// is a number, string or boolean.
+    fn operands_to_primitive(&mut self, ctx: &mut Context, equality: bool) -> Result<(), JSValue> {
+        use crate::runtime::conversion::{to_primitive, PreferredType};
+
+        let top = self.value_stack.len();
+        if top < 2 {
+            return Ok(());
+        }
+        for (slot, other) in [(top - 2, top - 1), (top - 1, top - 2)] {
+            let value = self.value_stack.get(slot)
+                .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
+            if ctx.get_object(value).is_none() {
+                continue;
+            }
+            if equality {
+                let other = self.value_stack.get(other)
+                    .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
+                if !(other.is_bool() || ctx.get_number(other).is_some() || ctx.get_string(other).is_some()) {
+                    continue;
+                }
+            }
+            let primitive = to_primitive(ctx, value, PreferredType::Number)?;
+            self.value_stack.set(slot, primitive)
+                .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
+        }
+        Ok(())
+    }
+
+    /// Reads local `idx` of the current frame
+    ///
+    /// Once a closure has captured the local, its var ref holds the value,
+    /// so the function sees what the closure writes.
+    fn get_local(&mut self, ctx: &mut Context, idx: u8) -> Result<JSValue, JSValue> {
+        let slot = self.local_slot(ctx, idx)?;
+        if let Some(var_ref) = self.promoted_var_ref(slot, idx) {
+            return match ctx.get_var_ref(var_ref) {
+                Some(var_ref) => Ok(var_ref.value()),
+                None => Err(self.throw_error(ctx, "Invalid var ref")),
+            };
+        }
+        self.value_stack.get(slot)
+            .map_err(|_| self.throw_error(ctx, "Invalid local variable index"))
+    }
+
+    /// Writes local `idx` of the current frame, and the var ref of any
+    /// closure that captured it
+    fn set_local(&mut self, ctx: &mut Context, idx: u8, val: JSValue) -> Result<(), JSValue> {
+        let slot = self.local_slot(ctx, idx)?;
+        if let Some(var_ref) = self.promoted_var_ref(slot, idx).and_then(|var_ref| ctx.get_var_ref_mut(var_ref)) {
+            var_ref.set_value(val);
+        }
+        self.value_stack.set(slot, val)
+            .map_err(|_| self.throw_error(ctx, "Invalid local variable index"))
+    }
+
+    /// Returns the var ref local `idx`, at value stack index `slot`, was
+    /// promoted to when a closure captured it
+    fn promoted_var_ref(&self, slot: usize, idx: u8) -> Option<HeapIndex> {
+        let idx = idx as usize;
+        self.promoted_var_refs.iter()
+            .find(|&&(base_sp, local, _)| local == idx && base_sp + idx == slot)
+            .map(|&(_, _, var_ref)| var_ref)
+    }
+
+    /// Helper: Throws an internal error with the given message
     fn throw_error(&mut self, ctx: &mut Context, msg: &str) -> JSValue {
-        // Create error string
-        let err_msg = ctx.new_string(msg).unwrap_or(JSValue::undefined());
-        err_msg
+        self.throw_error_kind(ctx, ErrorKind::Internal, msg)
+    }
+
+    /// Helper: Creates an error of the given kind to throw
+    fn throw_error_kind(&mut self, ctx: &mut Context, kind: ErrorKind, msg: &str) -> JSValue {
+        ctx.new_error(kind, msg)
+    }
+
+    /// Helper: Creates the `RangeError` for a full value or call stack
+    ///
+    /// Either stack filling up means the script recursed too deeply, so
+    /// both throw the same catchable error.
+    fn stack_overflow(&mut self, ctx: &mut Context) -> JSValue {
+        self.throw_error_kind(ctx, ErrorKind::StackOverflow, "Maximum call stack size exceeded")
+    }
+
+    /// Helper: Reads a global variable
+    ///
+    /// Missing globals throw a ReferenceError; for builtins compiled out by
+    /// a disabled feature, the message names the feature, and otherwise it
+    /// suggests a local or global spelled almost the same.
+    fn get_global(&mut self, ctx: &mut Context, atom: crate::value::JSAtom) -> Result<JSValue, JSValue> {
+        if let Some(value) = ctx.get_global_property(atom) {
+            return Ok(value);
+        }
+        let msg = match crate::runtime::init::disabled_builtin(atom) {
+            Some((name, feature)) => alloc::format!("{} is not defined (built without the `{}` feature)", name, feature),
+            None => {
+                let name = ctx.atom_name(atom).unwrap_or_default();
+                match self.suggest_name(ctx, atom, &name) {
+                    Some(suggestion) => alloc::format!("{} is not defined (did you mean '{}'?)", name, suggestion),
+                    None => alloc::format!("{} is not defined", name),
+                }
+            }
+        };
+        Err(self.throw_error_kind(ctx, ErrorKind::Reference, &msg))
+    }
+
+    /// Helper: Finds a name spelled like the missing global `name`
+    ///
+    /// Locals the compiler noted for the running unit come first, then the
+    /// global object's own properties.
+    fn suggest_name(&self, ctx: &Context, atom: crate::value::JSAtom, name: &str) -> Option<String> {
+        if let Some(&(_, local)) = self.module.name_hints.iter().find(|&&(global, _)| global == atom) {
+            return ctx.atom_name(local).map(|local| local.into_owned());
+        }
+        let globals: Vec<_> = ctx.own_property_names(ctx.global_object()).into_iter()
+            .take(crate::util::suggest::MAX_CANDIDATES)
+            .filter_map(|key| ctx.atom_name(key))
+            .collect();
+        crate::util::suggest::closest_name(name, globals.iter().map(|global| global.as_ref())).map(String::from)
+    }
+
+    /// Helper: Creates what a property read expects to find but that is
+    /// only made on demand: a constructor's `prototype` object and a
+    /// function's `name`
+    ///
+    /// Returns the value itself when reading it needs an allocation: a
+    /// string's character, which is counted in code points like `charAt`
+    /// does, or an array `length` past the inline integer range.
+    fn prepare_property_read(&mut self, ctx: &mut Context, obj: JSValue, key: crate::value::JSAtom) -> Result<Option<JSValue>, JSValue> {
+        let prepared = if key == atoms::PROTOTYPE {
+            ctx.ensure_function_prototype(obj).map(|()| None)
+        } else if key == atoms::NAME {
+            ctx.ensure_function_name(obj).map(|()| None)
+        } else if let Some(ch) = key.to_index().and_then(|index| ctx.get_string(obj)?.chars().nth(index as usize)) {
+            ctx.new_string(ch.encode_utf8(&mut [0; 4])).map(Some)
+        } else if key == atoms::LENGTH {
+            match ctx.array_length(obj).filter(|&length| i32::try_from(length).is_err()) {
+                Some(length) => ctx.new_number(f64::from(length)).map(Some),
+                None => Ok(None),
+            }
+        } else {
+            Ok(None)
+        };
+        prepared.map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))
+    }
+
+    /// Helper: Throws a TypeError if `obj` is undefined or null
+    ///
+    /// `action` is "read", "set" or "delete", giving messages like
+    /// "cannot read property of undefined".
+    fn check_object_coercible(&mut self, ctx: &mut Context, obj: JSValue, action: &str) -> Result<(), JSValue> {
+        let target = if obj.is_undefined() {
+            "undefined"
+        } else if obj.is_null() {
+            "null"
+        } else {
+            return Ok(());
+        };
+        let msg = alloc::format!("cannot {} property of {}", action, target);
+        Err(self.throw_error_kind(ctx, ErrorKind::Type, &msg))
+    }
+
+    /// Helper: Converts a value written to an array's `length`
+    ///
+    /// Returns the value to store, as a number. Throws a `RangeError` unless
+    /// the number is a valid length, one that `ToUint32` leaves unchanged.
+    /// Writes to any other property pass `value` through.
+    fn array_length_value(&mut self, ctx: &mut Context, obj: JSValue, key: crate::value::JSAtom, value: JSValue) -> Result<JSValue, JSValue> {
+        if key != atoms::LENGTH || !ctx.is_array(obj) {
+            return Ok(value);
+        }
+        let length = self.to_number(ctx, value)
+            .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
+        let number = ctx.get_number(length).unwrap_or(f64::NAN);
+        if f64::from(number as u32) != number {
+            return Err(self.throw_error_kind(ctx, ErrorKind::Range, "Invalid array length"));
+        }
+        Ok(length)
     }
 
     /// Helper: Gets a constant from the constant pool
     /// For f64 constants, creates a new heap-allocated number
     fn get_constant(&self, ctx: &mut Context, idx: u16) -> Result<JSValue, JSValue> {
-        if (idx as usize) >= self.constants.len() {
-            let err = ctx.new_string("Constant index out of bounds").unwrap_or(JSValue::undefined());
-            return Err(err);
+        if (idx as usize) >= self.module.constants.len() {
+            return Err(ctx.new_error(ErrorKind::Internal, "Constant index out of bounds"));
         }
 
-        let value = self.constants[idx as usize];
-        let is_f64 = self.const_is_f64.get(idx as usize).copied().unwrap_or(false);
+        let value = self.module.constants[idx as usize];
+        let is_f64 = self.module.const_is_f64.get(idx as usize).copied().unwrap_or(false);
 
         // Check if this is a raw f64 using the type flag
         if is_f64 {
             // It's raw f64 bits - convert to heap number
             let bits = value.as_raw() as u64;
             let f = f64::from_bits(bits);
-            let err = ctx.new_string("Out of memory").unwrap_or(JSValue::undefined());
-            return ctx.new_number(f).map_err(|_| err);
+            return ctx.new_number(f)
+                .map_err(|_| ctx.new_error(ErrorKind::OutOfMemory, "Out of memory"));
         }
 
         // For other values (ints, special values), just return as-is
         Ok(value)
     }
 
-    /// Helper: Gets an atom from the atom table and converts it to a JSAtom
-    /// Uses the same hash function as the runtime
-    /// Also stores the hash->string mapping for reverse lookup (for-in enumeration)
-    fn get_atom_from_table(&mut self, idx: usize) -> Result<crate::value::JSAtom, JSValue> {
-        if idx >= self.atom_table.len() {
-            return Err(JSValue::undefined());
+    /// Helper: Converts a computed member key (`obj[key]`) to an atom
+    ///
+    /// Non-negative integers map straight to index atoms; everything else is
+    /// converted to a string first, as in JS. Names over the context's length
+    /// limit throw a RangeError. With `define` set, a new name is recorded in
+    /// the context, subject to its quota of distinct names.
+    fn to_property_key(&mut self, ctx: &mut Context, key: JSValue, define: bool) -> Result<crate::value::JSAtom, JSValue> {
+        use crate::value::JSAtom;
+
+        let index = match key.to_int() {
+            Some(i) => u32::try_from(i).ok(),
+            None => ctx.get_number(key).and_then(|n| {
+                let in_range = n >= 0.0 && n <= JSAtom::MAX_INDEX as f64;
+                (in_range && n == libm::floor(n)).then_some(n as u32)
+            }),
+        };
+        if let Some(atom) = index.and_then(JSAtom::from_index) {
+            return Ok(atom);
         }
 
-        let name = &self.atom_table[idx];
-
-        // Use the same hash function as runtime/init.rs string_to_atom
-        let mut hash: u32 = 5381;
-        for byte in name.bytes() {
-            hash = hash.wrapping_mul(33).wrapping_add(byte as u32);
+        let name = crate::runtime::conversion::to_string(ctx, key);
+        if name.len() > ctx.max_property_name_len() {
+            return Err(self.throw_error_kind(ctx, ErrorKind::Range, "Property name too long"));
         }
 
-        // Store reverse mapping for for-in enumeration
-        if !self.atom_hash_to_string.contains_key(&hash) {
-            self.atom_hash_to_string.insert(hash, name.clone());
+        if !define {
+            // A name that was never interned can't be a property
+            return Ok(ctx.lookup_atom(&name).unwrap_or(JSAtom::null()));
         }
+        ctx.try_intern_atom(&name)
+            .ok_or_else(|| self.throw_error_kind(ctx, ErrorKind::Range, "Too many distinct property names"))
+    }
 
-        Ok(crate::value::JSAtom::from_id(hash))
+    /// Helper: Gets the resolved JSAtom for an atom table index
+    #[inline]
+    fn get_atom_from_table(&self, idx: usize) -> Result<crate::value::JSAtom, JSValue> {
+        self.module.atom_ids.get(idx).copied().ok_or(JSValue::undefined())
     }
 
     /// Type conversion and operator implementations will be added below...
     /// These are simplified versions for the initial implementation.
 
     fn to_number(&self, ctx: &mut Context, val: JSValue) -> Result<JSValue, JSValue> {
-        if let Some(i) = val.to_int() {
-            return Ok(JSValue::from_int(i));
-        }
-
-        if let Some(n) = ctx.get_number(val) {
-            return ctx.new_number(n).map_err(|_| JSValue::undefined());
+        use crate::runtime::conversion;
+        if val.is_int() || ctx.get_number(val).is_some() {
+            return Ok(val);
         }
-
-        // For other types, return 0 for now (simplified)
-        Ok(JSValue::from_int(0))
+        let n = conversion::to_number(ctx, val);
+        ctx.new_number(n).map_err(|_| JSValue::undefined())
     }
 
     fn to_boolean(&self, ctx: &Context, val: JSValue) -> bool {
@@ -2496,6 +2819,11 @@ impl VM {
         conversion::to_boolean(ctx, val)
     }
 
+    /// Returns `typeof val`
+    ///
+    /// Heap values are classified by their memory tag, and only a plain
+    /// object's own table is read, to spot a bound function: no getter
+    /// runs and nothing is converted or allocated.
     fn typeof_value(&self, ctx: &Context, val: JSValue) -> &'static str {
         if val.is_undefined() {
             "undefined"
@@ -2506,20 +2834,16 @@ impl VM {
         } else if val.is_int() {
             "number"
         } else if val.is_ptr() {
-            // Check memory tag for function types
             if let Some(index) = val.to_ptr() {
                 use crate::memory::MemTag;
-                unsafe {
-                    let header = ctx.arena().get_header(index);
-                    match header.mtag() {
-                        MemTag::CFunctionData | MemTag::ClosureData | MemTag::FunctionBytecode => {
-                            return "function";
-                        }
-                        MemTag::String => {
-                            return "string";
-                        }
-                        _ => {}
-                    }
+                // SAFETY: `val` is a live heap value, so `index` names a block
+                let mtag = unsafe { ctx.arena().get_header(index).mtag() };
+                match mtag {
+                    MemTag::CFunctionData | MemTag::ClosureData | MemTag::FunctionBytecode => return "function",
+                    MemTag::String => return "string",
+                    MemTag::Float64 => return "number",
+                    MemTag::Object if ctx.is_bound_function(val) => return "function",
+                    _ => {}
                 }
             }
             "object"
@@ -2529,133 +2853,120 @@ impl VM {
     }
 
     /// Get all enumerable property keys from an object (for for...in)
-    fn get_enumerable_keys(&self, ctx: &Context, obj: JSValue) -> Vec<String> {
-        use crate::runtime::init::string_to_atom;
-        let mut keys = Vec::new();
+    fn get_enumerable_keys(&self, ctx: &Context, obj: JSValue) -> Vec<crate::value::JSAtom> {
+        use crate::value::JSAtom;
 
-        // Handle primitives - they have no enumerable own properties
-        if obj.is_undefined() || obj.is_null() || obj.is_bool() || obj.is_int() {
-            return keys;
+        // Strings enumerate their character indices
+        if let Some(s) = ctx.get_string(obj) {
+            return (0..s.chars().count() as u32).filter_map(JSAtom::from_index).collect();
         }
 
-        // Handle heap-allocated objects
-        if let Some(index) = obj.to_ptr() {
-            use crate::memory::MemTag;
-            unsafe {
-                let header = ctx.arena().get_header(index);
-                match header.mtag() {
-                    MemTag::Float64 => {
-                        // Numbers have no enumerable keys
-                        return keys;
-                    }
-                    MemTag::String => {
-                        // Strings have indexed properties for each character
-                        if let Some(s) = ctx.get_string(obj) {
-                            for i in 0..s.len() {
-                                keys.push(i.to_string());
-                            }
-                        }
-                        return keys;
-                    }
-                    MemTag::Object => {
-                        // Check if it's an array-like object (has numeric indices and length)
-                        let length_atom = string_to_atom("length");
-                        if let Some(length) = ctx.get_property(obj, length_atom) {
-                            // Handle both tagged integers and Float64 numbers
-                            let len = length.to_int()
-                                .map(|i| i as i32)
-                                .or_else(|| ctx.get_number(length).map(|f| f as i32))
-                                .unwrap_or(0);
-                            if len > 0 {
-                                // This is an array-like object
-                                for i in 0..(len as usize) {
-                                    keys.push(i.to_string());
-                                }
-                                return keys;
-                            }
-                        }
+        // Objects enumerate their own keys, then inherited ones not already seen
+        let mut keys: Vec<JSAtom> = Vec::new();
+        let mut chain: Vec<JSValue> = Vec::new();
+        let mut current = obj;
+        while chain.len() <= ctx.max_prototype_depth() {
+            let Some(object) = ctx.get_object(current) else { break };
+            let proto = object.prototype();
+            for key in ctx.own_enumerable_keys(current) {
+                // Own properties shadow inherited ones, enumerable or not
+                let shadowed = chain.iter().any(|&closer| {
+                    ctx.find_own_property(closer, key).is_some()
+                        || key.to_index().is_some_and(|i| ctx.get_array_element(closer, i).is_some())
+                });
+                if !shadowed && !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+            chain.push(current);
+            current = proto;
+        }
 
-                        // For regular objects, enumerate properties using reverse hash mapping
-                        let js_obj: &crate::object::JSObject = ctx.arena().get(index);
-                        if js_obj.has_properties() {
-                            let props_index = js_obj.props_index();
-                            if let Some(props_table) = ctx.get_property_table(props_index) {
-                                let properties = props_table.properties();
-                                for prop in properties {
-                                    if prop.flags().is_enumerable() {
-                                        let atom_hash = prop.key().id();
-                                        // Look up the string from our reverse mapping
-                                        if let Some(key_str) = self.atom_hash_to_string.get(&atom_hash) {
-                                            keys.push(key_str.clone());
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        return keys;
-                    }
-                    _ => {}
+        keys
+    }
+
+    /// Helper: Pushes the next key of the innermost for-in loop
+    ///
+    /// Keys deleted since the loop started are skipped. Pushes undefined
+    /// once the keys run out; `ForInEnd` then discards the loop's state.
+    fn push_next_for_in_key(&mut self, ctx: &mut Context) -> Result<(), JSValue> {
+        let mut next = None;
+        if let Some((obj, keys, index)) = self.for_in_state.last_mut() {
+            while *index < keys.len() {
+                let key = keys[*index];
+                *index += 1;
+                let deleted = ctx.get_object(*obj).is_some() && matches!(
+                    ctx.find_property_with_accessor(*obj, key),
+                    crate::context::PropertyLookupResult::NotFound
+                );
+                if !deleted {
+                    next = Some(key);
+                    break;
                 }
             }
         }
 
-        keys
+        let value = match next {
+            Some(key) => {
+                let name = ctx.atom_name(key).unwrap_or_default().into_owned();
+                ctx.new_string(&name)
+                    .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?
+            }
+            None => JSValue::undefined(),
+        };
+        self.value_stack.push(value)
+            .map_err(|_| self.stack_overflow(ctx))
     }
 
-    /// Get all values from an iterable (for for...of)
-    fn get_iterable_values(&self, ctx: &mut Context, iterable: JSValue) -> Vec<JSValue> {
-        use crate::runtime::init::string_to_atom;
-        let mut values = Vec::new();
+    /// Pushes the innermost for-of loop's next value, then whether the loop
+    /// is done
+    ///
+    /// Arrays yield elements up to their current length, so elements pushed
+    /// during the loop are visited; strings yield one character at a time.
+    fn push_next_for_of_value(&mut self, ctx: &mut Context) -> Result<(), JSValue> {
+        let (iterable, index) = match self.for_of_state.last() {
+            Some(&state) => state,
+            None => return Err(self.throw_error(ctx, "for-of without an iterator")),
+        };
 
-        if iterable.is_undefined() || iterable.is_null() {
-            return values;
-        }
+        let next = if let Some(len) = ctx.array_length(iterable) {
+            (index < len as usize).then(|| {
+                let value = ctx.get_array_element(iterable, index as u32)
+                    .or_else(|| ctx.lookup_atom(&index.to_string()).and_then(|key| ctx.get_property(iterable, key)))
+                    .unwrap_or(JSValue::undefined());
+                (value, index + 1)
+            })
+        } else {
+            let ch = ctx.get_string(iterable).and_then(|s| {
+                let bytes = s.as_bytes();
+                let end = (index + 1..=bytes.len())
+                    .find(|&i| i == bytes.len() || crate::util::utf8::is_utf8_char_boundary(bytes[i]))?;
+                Some((String::from(&s[index..end]), end))
+            });
+            match ch {
+                Some((ch, end)) => {
+                    let value = ctx.new_string(&ch)
+                        .map_err(|_| self.throw_error_kind(ctx, ErrorKind::OutOfMemory, "Out of memory"))?;
+                    Some((value, end))
+                }
+                None => None,
+            }
+        };
 
-        if let Some(index) = iterable.to_ptr() {
-            use crate::memory::MemTag;
-            unsafe {
-                let header = ctx.arena().get_header(index);
-                match header.mtag() {
-                    MemTag::String => {
-                        // Strings iterate over characters
-                        // Collect chars first to avoid borrow issues
-                        let chars: Vec<char> = if let Some(s) = ctx.get_string(iterable) {
-                            s.chars().collect()
-                        } else {
-                            Vec::new()
-                        };
-                        for ch in chars {
-                            // Create a string for each character
-                            if let Ok(char_str) = ctx.new_string(&ch.to_string()) {
-                                values.push(char_str);
-                            }
-                        }
-                    }
-                    MemTag::Object => {
-                        // Check if it's an array-like object with length property
-                        let length_atom = string_to_atom("length");
-                        if let Some(length) = ctx.get_property(iterable, length_atom) {
-                            // Handle both tagged integers and Float64 numbers
-                            let len = length.to_int()
-                                .map(|i| i as i32)
-                                .or_else(|| ctx.get_number(length).map(|f| f as i32))
-                                .unwrap_or(0);
-                            for i in 0..(len as usize) {
-                                let idx_atom = string_to_atom(&i.to_string());
-                                if let Some(val) = ctx.get_property(iterable, idx_atom) {
-                                    values.push(val);
-                                } else {
-                                    values.push(JSValue::undefined());
-                                }
-                            }
-                        }
-                    }
-                    _ => {}
+        let (value, done) = match next {
+            Some((value, next_index)) => {
+                if let Some(state) = self.for_of_state.last_mut() {
+                    state.1 = next_index;
                 }
+                (value, false)
             }
-        }
+            None => (JSValue::undefined(), true),
+        };
 
-        values
+        self.value_stack.push(value)
+            .map_err(|_| self.stack_overflow(ctx))?;
+        self.value_stack.push(JSValue::bool(done))
+            .map_err(|_| self.stack_overflow(ctx))
     }
 
     // Arithmetic operators (with type coercion)
@@ -2680,54 +2991,51 @@ impl VM {
     }
 
     fn op_mod(&self, ctx: &mut Context, a: JSValue, b: JSValue) -> Result<JSValue, JSValue> {
-        let a_num = ctx.get_number(a).or_else(|| a.to_int().map(|i| i as f64)).unwrap_or(0.0);
-        let b_num = ctx.get_number(b).or_else(|| b.to_int().map(|i| i as f64)).unwrap_or(0.0);
-        ctx.new_number(a_num % b_num).map_err(|_| JSValue::undefined())
+        use crate::runtime::operators;
+        operators::remainder(ctx, a, b).map_err(|_| JSValue::undefined())
     }
 
     fn op_pow(&self, ctx: &mut Context, a: JSValue, b: JSValue) -> Result<JSValue, JSValue> {
-        let a_num = ctx.get_number(a).or_else(|| a.to_int().map(|i| i as f64)).unwrap_or(0.0);
-        let b_num = ctx.get_number(b).or_else(|| b.to_int().map(|i| i as f64)).unwrap_or(0.0);
-        ctx.new_number(libm::pow(a_num, b_num)).map_err(|_| JSValue::undefined())
+        use crate::runtime::operators;
+        operators::exponentiate(ctx, a, b).map_err(|_| JSValue::undefined())
     }
 
     fn op_neg(&self, ctx: &mut Context, a: JSValue) -> Result<JSValue, JSValue> {
-        let a_num = ctx.get_number(a).or_else(|| a.to_int().map(|i| i as f64)).unwrap_or(0.0);
-        ctx.new_number(-a_num).map_err(|_| JSValue::undefined())
+        use crate::runtime::operators;
+        operators::negate(ctx, a).map_err(|_| JSValue::undefined())
     }
 
     fn op_inc(&self, ctx: &mut Context, a: JSValue) -> Result<JSValue, JSValue> {
-        let a_num = ctx.get_number(a).or_else(|| a.to_int().map(|i| i as f64)).unwrap_or(0.0);
-        ctx.new_number(a_num + 1.0).map_err(|_| JSValue::undefined())
+        use crate::runtime::operators;
+        operators::increment(ctx, a, 1).map_err(|_| JSValue::undefined())
     }
 
     fn op_dec(&self, ctx: &mut Context, a: JSValue) -> Result<JSValue, JSValue> {
-        let a_num = ctx.get_number(a).or_else(|| a.to_int().map(|i| i as f64)).unwrap_or(0.0);
-        ctx.new_number(a_num - 1.0).map_err(|_| JSValue::undefined())
+        use crate::runtime::operators;
+        operators::increment(ctx, a, -1).map_err(|_| JSValue::undefined())
     }
 
     // Comparison operators (with type coercion)
+    //
+    // A NaN operand makes all four false, so `<=` can't be `!(b < a)`.
     fn op_lt(&self, ctx: &Context, a: JSValue, b: JSValue) -> Result<bool, JSValue> {
         use crate::runtime::compare;
-        Ok(compare::less_than(ctx, a, b))
+        Ok(compare::compare(ctx, a, b) == Some(Ordering::Less))
     }
 
     fn op_lte(&self, ctx: &Context, a: JSValue, b: JSValue) -> Result<bool, JSValue> {
         use crate::runtime::compare;
-        // a <= b is equivalent to !(a > b)
-        Ok(!compare::less_than(ctx, b, a))
+        Ok(matches!(compare::compare(ctx, a, b), Some(Ordering::Less | Ordering::Equal)))
     }
 
     fn op_gt(&self, ctx: &Context, a: JSValue, b: JSValue) -> Result<bool, JSValue> {
         use crate::runtime::compare;
-        // a > b is equivalent to b < a
-        Ok(compare::less_than(ctx, b, a))
+        Ok(compare::compare(ctx, a, b) == Some(Ordering::Greater))
     }
 
     fn op_gte(&self, ctx: &Context, a: JSValue, b: JSValue) -> Result<bool, JSValue> {
         use crate::runtime::compare;
-        // a >= b is equivalent to !(a < b)
-        Ok(!compare::less_than(ctx, a, b))
+        Ok(matches!(compare::compare(ctx, a, b), Some(Ordering::Greater | Ordering::Equal)))
     }
 
     fn op_eq(&self, ctx: &Context, a: JSValue, b: JSValue) -> bool {
@@ -2741,268 +3049,162 @@ impl VM {
     }
 
     // Bitwise operators
+    //
+    // Operands go through ToInt32/ToUint32, so boxed numbers and strings
+    // work; results outside the inline int range are boxed by new_number.
     fn op_bit_not(&self, ctx: &mut Context, a: JSValue) -> Result<JSValue, JSValue> {
-        let a_int = a.to_int().unwrap_or(0);
-        Ok(JSValue::from_int(!a_int))
+        let a_int = to_int32(ctx, a);
+        ctx.new_number(f64::from(!a_int)).map_err(|_| ctx.new_error(ErrorKind::OutOfMemory, "Out of memory"))
     }
 
     fn op_bit_and(&self, ctx: &mut Context, a: JSValue, b: JSValue) -> Result<JSValue, JSValue> {
-        let a_int = a.to_int().unwrap_or(0);
-        let b_int = b.to_int().unwrap_or(0);
-        Ok(JSValue::from_int(a_int & b_int))
+        let result = to_int32(ctx, a) & to_int32(ctx, b);
+        ctx.new_number(f64::from(result)).map_err(|_| ctx.new_error(ErrorKind::OutOfMemory, "Out of memory"))
     }
 
     fn op_bit_or(&self, ctx: &mut Context, a: JSValue, b: JSValue) -> Result<JSValue, JSValue> {
-        let a_int = a.to_int().unwrap_or(0);
-        let b_int = b.to_int().unwrap_or(0);
-        Ok(JSValue::from_int(a_int | b_int))
+        let result = to_int32(ctx, a) | to_int32(ctx, b);
+        ctx.new_number(f64::from(result)).map_err(|_| ctx.new_error(ErrorKind::OutOfMemory, "Out of memory"))
     }
 
     fn op_bit_xor(&self, ctx: &mut Context, a: JSValue, b: JSValue) -> Result<JSValue, JSValue> {
-        let a_int = a.to_int().unwrap_or(0);
-        let b_int = b.to_int().unwrap_or(0);
-        Ok(JSValue::from_int(a_int ^ b_int))
+        let result = to_int32(ctx, a) ^ to_int32(ctx, b);
+        ctx.new_number(f64::from(result)).map_err(|_| ctx.new_error(ErrorKind::OutOfMemory, "Out of memory"))
     }
 
     fn op_shl(&self, ctx: &mut Context, a: JSValue, b: JSValue) -> Result<JSValue, JSValue> {
-        let a_int = a.to_int().unwrap_or(0);
-        let b_int = (b.to_int().unwrap_or(0) & 0x1F) as u32;
-        Ok(JSValue::from_int(a_int << b_int))
+        let result = to_int32(ctx, a) << (to_uint32(ctx, b) & 0x1F);
+        ctx.new_number(f64::from(result)).map_err(|_| ctx.new_error(ErrorKind::OutOfMemory, "Out of memory"))
     }
 
     fn op_sar(&self, ctx: &mut Context, a: JSValue, b: JSValue) -> Result<JSValue, JSValue> {
-        let a_int = a.to_int().unwrap_or(0);
-        let b_int = (b.to_int().unwrap_or(0) & 0x1F) as u32;
-        Ok(JSValue::from_int(a_int >> b_int))
+        let result = to_int32(ctx, a) >> (to_uint32(ctx, b) & 0x1F);
+        ctx.new_number(f64::from(result)).map_err(|_| ctx.new_error(ErrorKind::OutOfMemory, "Out of memory"))
     }
 
     fn op_shr(&self, ctx: &mut Context, a: JSValue, b: JSValue) -> Result<JSValue, JSValue> {
-        let a_int = a.to_int().unwrap_or(0) as u32;
-        let b_int = (b.to_int().unwrap_or(0) & 0x1F) as u32;
-        Ok(JSValue::from_int((a_int >> b_int) as i32))
+        let result = to_uint32(ctx, a) >> (to_uint32(ctx, b) & 0x1F);
+        ctx.new_number(f64::from(result)).map_err(|_| ctx.new_error(ErrorKind::OutOfMemory, "Out of memory"))
     }
 
     /// Executes a bytecode function
     ///
-    /// This is a simplified implementation that executes the function's bytecode
-    /// with local variables stored on the value stack.
+    /// The function's locals are already on the value stack at `base_sp`.
+    /// Its unit's tables come from the module cache, so a call only swaps
+    /// the current module and starts a reader at the code.
     fn execute_bytecode_function(
         &mut self,
         ctx: &mut Context,
-        bytecode_index: HeapIndex,
+        code: FunctionCode,
         base_sp: usize,
-        local_count: usize,
         closure: Option<HeapIndex>,
     ) -> VMResult {
-        // Get the bytecode array
-        let bytecode_ptr: *const crate::value::JSByteArray = match ctx.get_byte_array(bytecode_index) {
-            Some(b) => b as *const _,
+        let unit = match Self::code_bytes(ctx, code) {
+            Some(bytes) => bytes,
             None => return Err(self.throw_error(ctx, "Invalid function bytecode")),
         };
+        let module = self.modules.get(ctx, code, unit)
+            .map_err(|msg| self.throw_error(ctx, msg))?;
 
-        // SAFETY: bytecode_ptr is valid as long as we don't modify the arena
-        let bytecode_slice = unsafe { (*bytecode_ptr).as_slice() };
-
-        // Parse the function's own constant pool and atom table
-        // (Each function has its own embedded tables)
-        // For now, we'll create a new bytecode reader from the raw code
-
-        // Skip the constant pool, atom table, and function table headers
-        // Since function bytecode is a complete standalone bytecode unit,
-        // we need to parse it like a mini-program
+        // The reader spans the whole unit, so line lookups can find the table
+        let mut reader = BytecodeReader::new(unit);
+        if let Err(err) = reader.set_pc(module.code_start) {
+            return Err(self.throw_error(ctx, &err.to_string()));
+        }
+        let outer_module = core::mem::replace(&mut self.module, module);
 
-        // This is complex - for now, let's use a simplified approach:
-        // Execute with a new reader but reuse our stacks
-        let mut reader = BytecodeReader::new(bytecode_slice);
+        let result = self.execute_function_code(ctx, &mut reader, closure);
 
-        // We need to offset all GetLoc/PutLoc operations by base_sp
-        // For now, let's execute the bytecode directly (simplified)
-        // The local variable operations will need to be adjusted
+        // Clean up promoted var_refs for this frame to prevent stale reuse
+        self.promoted_var_refs.retain(|(sp, _, _)| *sp != base_sp);
+        self.module = outer_module;
 
-        // Actually, we already have locals on the stack at base_sp
-        // So we just need to execute the bytecode and intercept GetLoc/SetLoc
+        result
+    }
 
-        // For simplicity, let's parse the minimal headers and execute
-        self.execute_function_bytecode(ctx, &mut reader, base_sp, closure)
+    /// Returns the bytes of a function's code
+    ///
+    /// Heap code is read through a raw pointer, which stays valid while the
+    /// function runs: collections between instructions pin byte arrays.
+    fn code_bytes<'c>(ctx: &Context, code: FunctionCode) -> Option<&'c [u8]> {
+        match code {
+            FunctionCode::Heap(index) => {
+                let array: *const crate::value::JSByteArray = ctx.get_byte_array(index)?;
+                // SAFETY: see above
+                Some(unsafe { (*array).as_slice() })
+            }
+            FunctionCode::HeapSlice { unit, start, len } => {
+                let array: *const crate::value::JSByteArray = ctx.get_byte_array(unit)?;
+                // SAFETY: see above
+                let bytes = unsafe { (*array).as_slice() };
+                bytes.get(start as usize..start as usize + len as usize)
+            }
+            FunctionCode::Static(bytes) => Some(bytes),
+        }
     }
 
-    /// Executes function bytecode with proper local variable handling
-    fn execute_function_bytecode(
+    /// Inner execution loop for function bytecode
+    fn execute_function_code(
         &mut self,
         ctx: &mut Context,
         reader: &mut BytecodeReader,
-        base_sp: usize,
         closure: Option<HeapIndex>,
     ) -> VMResult {
-        // Parse headers (constants, atoms, functions)
-        // Function bytecode has the same format as main bytecode:
-        // [const_count: u16][constants...][atom_count: u16][atoms...][func_count: u16][funcs...][code]
-
-        // Save the current tables so we can restore them after
-        let old_constants = core::mem::take(&mut self.constants);
-        let old_const_is_f64 = core::mem::take(&mut self.const_is_f64);
-        let old_atom_table = core::mem::take(&mut self.atom_table);
-        let old_function_table = core::mem::take(&mut self.function_table);
-
-        // Parse constant pool (same format as main bytecode: type byte + raw JSValue)
-        // Type: 0 = f64 bits, 1 = JSValue
-        let const_count = {
-            let byte0 = reader.read_u8().unwrap_or(0);
-            let byte1 = reader.read_u8().unwrap_or(0);
-            u16::from_le_bytes([byte0, byte1]) as usize
-        };
-
-        self.constants = alloc::vec::Vec::with_capacity(const_count);
-        self.const_is_f64 = alloc::vec::Vec::with_capacity(const_count);
-        for _ in 0..const_count {
-            let const_type = reader.read_u8().unwrap_or(0);
-            let mut value_bytes = [0u8; core::mem::size_of::<usize>()];
-            for i in 0..core::mem::size_of::<usize>() {
-                value_bytes[i] = reader.read_u8().unwrap_or(0);
-            }
-            let raw = usize::from_le_bytes(value_bytes);
-            let value = unsafe { core::mem::transmute::<usize, JSValue>(raw) };
-            self.constants.push(value);
-            self.const_is_f64.push(const_type == 0);
-        }
-
-        // Parse atom table
-        let atom_count = {
-            let byte0 = reader.read_u8().unwrap_or(0);
-            let byte1 = reader.read_u8().unwrap_or(0);
-            u16::from_le_bytes([byte0, byte1]) as usize
-        };
-
-        self.atom_table = alloc::vec::Vec::with_capacity(atom_count);
-        for _ in 0..atom_count {
-            let len = {
-                let byte0 = reader.read_u8().unwrap_or(0);
-                let byte1 = reader.read_u8().unwrap_or(0);
-                u16::from_le_bytes([byte0, byte1]) as usize
-            };
-            let mut name_bytes = alloc::vec::Vec::with_capacity(len);
-            for _ in 0..len {
-                name_bytes.push(reader.read_u8().unwrap_or(0));
-            }
-            let name = alloc::string::String::from_utf8(name_bytes)
-                .unwrap_or_else(|_| alloc::string::String::new());
-            self.atom_table.push(name);
-        }
-
-        // Parse function table
-        let func_count = {
-            let byte0 = reader.read_u8().unwrap_or(0);
-            let byte1 = reader.read_u8().unwrap_or(0);
-            u16::from_le_bytes([byte0, byte1]) as usize
-        };
-
-        self.function_table = alloc::vec::Vec::with_capacity(func_count);
-        for _ in 0..func_count {
-            let param_count = reader.read_u8().unwrap_or(0);
-            let local_count = reader.read_u8().unwrap_or(0);
-            let self_name_slot = reader.read_u8().unwrap_or(0xFF);
-            let bytecode_len = {
-                let mut bytes = [0u8; 4];
-                for i in 0..4 {
-                    bytes[i] = reader.read_u8().unwrap_or(0);
-                }
-                u32::from_le_bytes(bytes) as usize
-            };
-
-            // Allocate the bytecode on the heap
-            let bytecode_index = match ctx.alloc_byte_array(bytecode_len) {
-                Ok(idx) => idx,
-                Err(_) => {
-                    // Restore tables and return error
-                    self.constants = old_constants;
-                    self.const_is_f64 = old_const_is_f64;
-                    self.atom_table = old_atom_table;
-                    self.function_table = old_function_table;
-                    return Err(self.throw_error(ctx, "Out of memory loading function bytecode"));
-                }
-            };
-
-            // Read the bytecode directly into the allocated array
-            if let Some(array) = ctx.get_byte_array_mut(bytecode_index) {
-                // SAFETY: We just allocated the array with bytecode_len capacity
-                let slice = unsafe { array.as_full_mut_slice() };
-                for i in 0..bytecode_len {
-                    if i < slice.len() {
-                        slice[i] = reader.read_u8().unwrap_or(0);
-                    } else {
-                        reader.read_u8(); // Skip if array is too small (shouldn't happen)
-                    }
-                }
-                // Set the count so as_slice() works correctly
-                array.header_mut().set_count(bytecode_len);
-            } else {
-                // Skip the bytecode bytes
-                for _ in 0..bytecode_len {
-                    reader.read_u8();
-                }
-            }
-
-            self.function_table.push(FunctionEntry {
-                bytecode_index,
-                param_count,
-                local_count,
-                self_name_slot,
-            });
-        }
-
-        // Execute the actual code
-        let result = self.execute_function_code(ctx, reader, base_sp, closure);
-
-        // Clean up promoted var_refs for this frame to prevent stale reuse
-        self.promoted_var_refs.retain(|(sp, _, _)| *sp != base_sp);
-
-        // Restore the old tables
-        self.constants = old_constants;
-        self.const_is_f64 = old_const_is_f64;
-        self.atom_table = old_atom_table;
-        self.function_table = old_function_table;
-
+        let handler_base = self.handlers.len();
+        let outer_base = core::mem::replace(&mut self.handler_base, handler_base);
+        let for_in_depth = self.for_in_state.len();
+        let for_of_depth = self.for_of_state.len();
+        let result = self.execute_function_code_inner(ctx, reader, closure, handler_base);
+        self.handlers.truncate(handler_base);
+        self.handler_base = outer_base;
+        // A return from inside a for-in or for-of loop skips its end opcode
+        self.for_in_state.truncate(for_in_depth);
+        self.for_of_state.truncate(for_of_depth);
         result
     }
 
-    /// Inner execution loop for function bytecode
-    fn execute_function_code(
+    fn execute_function_code_inner(
         &mut self,
         ctx: &mut Context,
         reader: &mut BytecodeReader,
-        base_sp: usize,
         closure: Option<HeapIndex>,
+        handler_base: usize,
     ) -> VMResult {
         loop {
-            let instruction = match reader.decode() {
-                Some(inst) => inst,
-                None => return Ok(JSValue::undefined()),
+            let pc = reader.pc();
+            let instruction = match reader.try_decode() {
+                Ok(Some(inst)) => inst,
+                Ok(None) => return Ok(JSValue::undefined()),
+                Err(err) => return Err(self.throw_error(ctx, &err.to_string())),
             };
+            crate::fatal::record_location(pc, instruction.opcode);
+            self.count_step(ctx)?;
+
+            if ctx.should_collect() {
+                self.collect_garbage(ctx);
+            }
 
             // Handle local variable access and closure variable access specially
             match instruction.opcode {
                 Opcode::GetLoc => {
                     if let Operand::U8(idx) = instruction.operand {
-                        let local_val = self.value_stack.get(base_sp + idx as usize)
-                            .map_err(|_| self.throw_error(ctx, "Invalid local variable index"))?;
+                        let local_val = self.get_local(ctx, idx)?;
                         self.value_stack.push(local_val)
-                            .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                            .map_err(|_| self.stack_overflow(ctx))?;
                     }
                 }
                 Opcode::PutLoc => {
                     if let Operand::U8(idx) = instruction.operand {
                         let val = self.value_stack.pop()
                             .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
-                        self.value_stack.set(base_sp + idx as usize, val)
-                            .map_err(|_| self.throw_error(ctx, "Invalid local variable index"))?;
+                        self.set_local(ctx, idx, val)?;
                     }
                 }
                 Opcode::SetLoc => {
                     if let Operand::U8(idx) = instruction.operand {
                         let val = self.value_stack.peek()
                             .map_err(|_| self.throw_error(ctx, "Stack underflow"))?;
-                        self.value_stack.set(base_sp + idx as usize, val)
-                            .map_err(|_| self.throw_error(ctx, "Invalid local variable index"))?;
+                        self.set_local(ctx, idx, val)?;
                     }
                 }
                 Opcode::GetVarRef => {
@@ -3029,7 +3231,7 @@ impl VM {
                         };
 
                         self.value_stack.push(value)
-                            .map_err(|_| self.throw_error(ctx, "Stack overflow"))?;
+                            .map_err(|_| self.stack_overflow(ctx))?;
                     }
                 }
                 Opcode::PutVarRef => {
@@ -3096,21 +3298,16 @@ impl VM {
                 }
                 _ => {
                     // For other opcodes, execute normally
-                    match self.execute_instruction(ctx, reader, &instruction) {
+                    match self.execute_with_retry(ctx, reader, &instruction) {
                         Ok(Some(ret)) => return Ok(ret),
                         Ok(None) => continue,
                         Err(e) => {
-                            // Check if we have an exception handler in the current frame
-                            if let Ok(frame) = self.call_stack.current() {
-                                if let Some(catch_pc) = frame.catch_offset {
-                                    // Jump to exception handler
-                                    reader.set_pc(catch_pc);
-                                    self.value_stack.push(e)
-                                        .map_err(|_| self.throw_error(ctx, "Stack overflow in exception handler"))?;
-                                    continue;
-                                }
-                            }
-                            return Err(e);
+                            let unit = reader.bytecode();
+                            Self::note_error_line(ctx, e, || {
+                                let (table, code_start) = crate::compiler::debug::unit_line_table(unit)?;
+                                crate::compiler::debug::line_at(table, pc.checked_sub(code_start)?)
+                            });
+                            self.catch_exception(ctx, reader, handler_base, e)?
                         }
                     }
                 }
@@ -3151,11 +3348,13 @@ mod tests {
 
         let code = writer.finish();
 
-        // Add headers: [const_count: u16][constants...][atom_count: u16][atoms...][func_count: u16][funcs...][bytecode...]
+        // Add headers: [const_count: u16][constants...][atom_count: u16][atoms...][func_count: u16][funcs...][lines_len: u16][lines...][hint_count: u16][hints...][bytecode...]
         let mut bytecode = Vec::new();
         bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 constants
         bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 atoms
         bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 functions
+        bytecode.extend_from_slice(&0u16.to_le_bytes()); // no line table
+        bytecode.extend_from_slice(&0u16.to_le_bytes()); // no name hints
         bytecode.extend_from_slice(&code);
 
         let bc_index = ctx.alloc_byte_array(bytecode.len()).unwrap();
@@ -3191,6 +3390,8 @@ mod tests {
         bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 constants
         bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 atoms
         bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 functions
+        bytecode.extend_from_slice(&0u16.to_le_bytes()); // no line table
+        bytecode.extend_from_slice(&0u16.to_le_bytes()); // no name hints
         bytecode.extend_from_slice(&code);
 
         let bc_index = ctx.alloc_byte_array(bytecode.len()).unwrap();
@@ -3236,6 +3437,8 @@ mod tests {
         bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 constants
         bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 atoms
         bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 functions
+        bytecode.extend_from_slice(&0u16.to_le_bytes()); // no line table
+        bytecode.extend_from_slice(&0u16.to_le_bytes()); // no name hints
         bytecode.extend_from_slice(&code);
 
         let bc_index = ctx.alloc_byte_array(bytecode.len()).unwrap();
@@ -3250,4 +3453,44 @@ mod tests {
         let result = vm.execute(&mut ctx, bc_index).unwrap();
         assert_eq!(result.to_int(), Some(2));
     }
+
+    #[test]
+    fn test_completed_try_does_not_catch() {
+        let mut vm = VM::new();
+        let mut ctx = Context::new(4096);
+
+        // push_catch_offset catch; clear_catch_offset; clear_catch_offset
+        // (stray); push 1; throw; catch: return
+        let mut writer = BytecodeWriter::new();
+        writer.emit(&Instruction::with_label(Opcode::PushCatchOffset, 4));
+        writer.emit(&Instruction::new(Opcode::ClearCatchOffset));
+        writer.emit(&Instruction::new(Opcode::ClearCatchOffset));
+        writer.emit(&Instruction::new(Opcode::Push1));
+        writer.emit(&Instruction::new(Opcode::Throw));
+        writer.emit(&Instruction::new(Opcode::Return));
+
+        let code = writer.finish();
+
+        let mut bytecode = Vec::new();
+        bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 constants
+        bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 atoms
+        bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 functions
+        bytecode.extend_from_slice(&0u16.to_le_bytes()); // no line table
+        bytecode.extend_from_slice(&0u16.to_le_bytes()); // no name hints
+        bytecode.extend_from_slice(&code);
+
+        let bc_index = ctx.alloc_byte_array(bytecode.len()).unwrap();
+
+        unsafe {
+            let bc_array = ctx.get_byte_array_mut(bc_index).unwrap();
+            let slice = bc_array.as_full_mut_slice();
+            slice[..bytecode.len()].copy_from_slice(&bytecode);
+            bc_array.header_mut().set_count(bytecode.len());
+        }
+
+        let result = vm.execute(&mut ctx, bc_index);
+        assert!(result.is_err());
+        assert!(vm.handlers.is_empty());
+        assert_eq!(vm.handler_base, 0);
+    }
 }
@@ -11,6 +11,7 @@ pub mod interpreter;
 pub mod call;
 pub mod exception;
 pub mod stack;
+mod module;
 
 // Re-exports
 pub use interpreter::VM;
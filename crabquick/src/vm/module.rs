@@ -0,0 +1,290 @@
+//! Parsed bytecode units
+//!
+//! A unit's constant pool, atom table and function table are parsed into a
+//! [`Module`] the first time its code runs. Every later call into the unit,
+//! through any function or closure made from it, switches to the cached
+//! module instead of parsing the headers again.
+//!
+//! Nested functions don't get their own copy of their code: a function
+//! table entry points back into the unit it was declared in.
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::Range;
+use crate::context::Context;
+use crate::memory::HeapIndex;
+use crate::object::function::FunctionCode;
+use crate::value::{JSAtom, JSValue};
+
+/// Function entry from function table
+#[derive(Debug, Clone)]
+pub(super) struct FunctionEntry {
+    pub code: FunctionCode,
+    pub param_count: u8,
+    pub local_count: u8,
+    /// For named function expressions: slot index where the function self-reference should be stored
+    /// 0xFF means no self-reference needed
+    pub self_name_slot: u8,
+    /// Function table flags (`FUNC_FLAG_ARROW`)
+    pub flags: u8,
+    /// The function's declared name, or null if anonymous
+    pub name: JSAtom,
+}
+
+/// The tables of one bytecode unit
+///
+/// Format: `[const_count: u16][(type: u8, value: usize)...]`
+/// `[atom_count: u16][(len: u16, bytes)...]`
+/// `[func_count: u16][(param: u8, local: u8, self_slot: u8, flags: u8, name: u16, len: u32, unit)...]`
+/// `[lines_len: u16][lines...][hint_count: u16][(name: u16, suggestion: u16)...][code]`
+#[derive(Debug, Default)]
+pub(super) struct Module {
+    /// Constant pool
+    pub constants: Vec<JSValue>,
+    /// Tracks which constants are f64 bits (true) vs JSValue (false)
+    pub const_is_f64: Vec<bool>,
+    /// Atom table (index -> string)
+    pub atom_table: Vec<String>,
+    /// Resolved atoms for `atom_table`, so property opcodes never rehash names
+    ///
+    /// Atoms are interned in the context, so the main program and every
+    /// function resolve the same name to the same atom.
+    pub atom_ids: Vec<JSAtom>,
+    /// Function table (precompiled functions)
+    pub function_table: Vec<FunctionEntry>,
+    /// Where the line table sits in the unit
+    pub lines: Range<usize>,
+    /// Locals spelled like a global the unit reads: (global, local)
+    pub name_hints: Vec<(JSAtom, JSAtom)>,
+    /// Where the code starts in the unit
+    pub code_start: usize,
+}
+
+impl Module {
+    /// Parses the tables of `unit`, the bytes of `code`
+    pub fn parse(ctx: &mut Context, code: FunctionCode, unit: &[u8]) -> Result<Module, &'static str> {
+        let mut module = Module::default();
+        let mut offset = 0;
+
+        let const_count = read_u16(unit, &mut offset).ok_or("Invalid bytecode format")? as usize;
+        let const_entry_size = 1 + core::mem::size_of::<usize>(); // type byte + usize value
+        if unit.len() < offset + const_count * const_entry_size {
+            return Err("Invalid bytecode: truncated constant pool");
+        }
+        module.constants.reserve(const_count);
+        module.const_is_f64.reserve(const_count);
+        for _ in 0..const_count {
+            let const_type = unit[offset];
+            offset += 1;
+
+            let mut bytes = [0u8; core::mem::size_of::<usize>()];
+            bytes.copy_from_slice(&unit[offset..offset + core::mem::size_of::<usize>()]);
+            offset += core::mem::size_of::<usize>();
+
+            let raw = usize::from_le_bytes(bytes);
+            // SAFETY: JSValue is a transparent usize, so every bit pattern is
+            // a valid value. Constants only hold f64 bits or immediates, never
+            // a heap reference, which could name a block the heap doesn't have
+            let value = unsafe { core::mem::transmute::<usize, JSValue>(raw) };
+            if const_type != 0 && value.is_ptr() {
+                return Err("Invalid bytecode: heap reference in constant pool");
+            }
+            module.constants.push(value);
+            module.const_is_f64.push(const_type == 0);
+        }
+
+        let atom_count = read_u16(unit, &mut offset).ok_or("Invalid bytecode: missing atom count")? as usize;
+        module.atom_table.reserve(atom_count);
+        module.atom_ids.reserve(atom_count);
+        for _ in 0..atom_count {
+            let len = read_u16(unit, &mut offset).ok_or("Invalid bytecode: truncated atom table")? as usize;
+            let bytes = unit.get(offset..offset + len).ok_or("Invalid bytecode: truncated atom string")?;
+            offset += len;
+            let name = core::str::from_utf8(bytes).map_err(|_| "Invalid UTF-8 in atom table")?;
+            module.atom_ids.push(ctx.intern_atom(name));
+            module.atom_table.push(name.to_string());
+        }
+
+        let func_count = read_u16(unit, &mut offset).ok_or("Invalid bytecode: missing function count")? as usize;
+        module.function_table.reserve(func_count);
+        for _ in 0..func_count {
+            let header = unit.get(offset..offset + 10).ok_or("Invalid bytecode: truncated function table")?;
+            // The name is an index into the atom table, 0xFFFF if anonymous
+            let name = match u16::from_le_bytes([header[4], header[5]]) {
+                0xFFFF => JSAtom::null(),
+                index => *module.atom_ids.get(index as usize).ok_or("Invalid bytecode: bad function name")?,
+            };
+            let len = u32::from_le_bytes([header[6], header[7], header[8], header[9]]) as usize;
+            offset += 10;
+            if unit.len() < offset + len {
+                return Err("Invalid bytecode: truncated function bytecode");
+            }
+            module.function_table.push(FunctionEntry {
+                code: nested_code(code, offset, len),
+                param_count: header[0],
+                local_count: header[1],
+                self_name_slot: header[2],
+                flags: header[3],
+                name,
+            });
+            offset += len;
+        }
+
+        let lines_len = read_u16(unit, &mut offset).ok_or("Invalid bytecode: missing line table")? as usize;
+        if unit.len() < offset + lines_len {
+            return Err("Invalid bytecode: truncated line table");
+        }
+        module.lines = offset..offset + lines_len;
+        offset += lines_len;
+
+        let hint_count = read_u16(unit, &mut offset).ok_or("Invalid bytecode: missing name hints")?;
+        for _ in 0..hint_count {
+            let atom = |offset: &mut usize| {
+                read_u16(unit, offset).and_then(|index| module.atom_ids.get(index as usize).copied())
+            };
+            let hint = atom(&mut offset).zip(atom(&mut offset)).ok_or("Invalid bytecode: bad name hint")?;
+            module.name_hints.push(hint);
+        }
+        module.code_start = offset;
+
+        Ok(module)
+    }
+}
+
+/// Returns the code of the function at `start..start + len` in the unit of `code`
+fn nested_code(code: FunctionCode, start: usize, len: usize) -> FunctionCode {
+    match code {
+        FunctionCode::Heap(index) => FunctionCode::HeapSlice { unit: index, start: start as u32, len: len as u32 },
+        FunctionCode::HeapSlice { unit, start: outer, .. } => {
+            FunctionCode::HeapSlice { unit, start: outer + start as u32, len: len as u32 }
+        }
+        FunctionCode::Static(bytes) => FunctionCode::Static(&bytes[start..start + len]),
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: &mut usize) -> Option<u16> {
+    let value = u16::from_le_bytes([*bytes.get(*offset)?, *bytes.get(*offset + 1)?]);
+    *offset += 2;
+    Some(value)
+}
+
+/// Where a unit lives, as a cache key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ModuleKey {
+    Heap { unit: HeapIndex, start: u32, len: u32 },
+    Static { ptr: usize, len: usize },
+}
+
+impl ModuleKey {
+    fn of(code: FunctionCode) -> ModuleKey {
+        match code {
+            FunctionCode::Heap(unit) => ModuleKey::Heap { unit, start: 0, len: u32::MAX },
+            FunctionCode::HeapSlice { unit, start, len } => ModuleKey::Heap { unit, start, len },
+            FunctionCode::Static(bytes) => ModuleKey::Static { ptr: bytes.as_ptr() as usize, len: bytes.len() },
+        }
+    }
+}
+
+/// Parsed modules, keyed by where their unit lives
+///
+/// Heap units are only known by index, and a collection can free a unit
+/// and hand its index to new code, so heap entries are dropped whenever a
+/// collection has run since they were parsed. Running functions hold their
+/// module themselves and aren't affected.
+#[derive(Default)]
+pub(super) struct ModuleCache {
+    modules: BTreeMap<ModuleKey, Rc<Module>>,
+    /// `Context::gc_cycles` when the heap entries were parsed
+    gc_cycles: usize,
+}
+
+impl ModuleCache {
+    /// Returns the module for `code`, parsing `unit` (its bytes) on first use
+    pub fn get(&mut self, ctx: &mut Context, code: FunctionCode, unit: &[u8]) -> Result<Rc<Module>, &'static str> {
+        if self.gc_cycles != ctx.gc_cycles() {
+            self.gc_cycles = ctx.gc_cycles();
+            self.modules.retain(|key, _| matches!(key, ModuleKey::Static { .. }));
+        }
+
+        let key = ModuleKey::of(code);
+        if let Some(module) = self.modules.get(&key) {
+            return Ok(module.clone());
+        }
+        let module = Rc::new(Module::parse(ctx, code, unit)?);
+        self.modules.insert(key, module.clone());
+        Ok(module)
+    }
+
+    /// Forgets every parsed module
+    pub fn clear(&mut self) {
+        self.modules.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::LoadedScript;
+
+    #[test]
+    fn test_nested_functions_point_into_their_unit() {
+        let script = LoadedScript::compile("function a() { function b() { return 1; } return b; }").unwrap();
+        let bytes = script.bytecode();
+        let mut ctx = Context::new(65536);
+        let index = ctx.store_bytecode(bytes).unwrap();
+
+        let top = Module::parse(&mut ctx, FunctionCode::Heap(index), bytes).unwrap();
+        assert_eq!(top.function_table.len(), 1);
+        let FunctionCode::HeapSlice { unit, start, len } = top.function_table[0].code else {
+            panic!("nested function was copied");
+        };
+        assert_eq!(unit, index);
+        let a = &bytes[start as usize..(start + len) as usize];
+
+        // Offsets of a function nested in a nested function add up
+        let inner = Module::parse(&mut ctx, top.function_table[0].code, a).unwrap();
+        let FunctionCode::HeapSlice { unit, start: b_start, len: b_len } = inner.function_table[0].code else {
+            panic!("nested function was copied");
+        };
+        assert_eq!(unit, index);
+        assert!(b_start > start && b_start + b_len <= start + len);
+
+        let leaked: &'static [u8] = bytes.to_vec().leak();
+        let top = Module::parse(&mut ctx, FunctionCode::Static(leaked), leaked).unwrap();
+        let FunctionCode::Static(a) = top.function_table[0].code else {
+            panic!("static function moved to the heap");
+        };
+        assert_eq!(a, &bytes[start as usize..(start + len) as usize]);
+    }
+
+    #[test]
+    fn test_rejects_heap_references_in_constant_pool() {
+        let mut unit = Vec::new();
+        unit.extend_from_slice(&1u16.to_le_bytes());
+        unit.push(1);
+        unit.extend_from_slice(&JSValue::from_ptr(HeapIndex::from_usize(64)).as_raw().to_le_bytes());
+        let unit: &'static [u8] = unit.leak();
+
+        let mut ctx = Context::new(65536);
+        let err = Module::parse(&mut ctx, FunctionCode::Static(unit), unit).err();
+        assert_eq!(err, Some("Invalid bytecode: heap reference in constant pool"));
+    }
+
+    #[test]
+    fn test_cache_drops_heap_modules_after_collection() {
+        let script = LoadedScript::compile("1").unwrap();
+        let mut ctx = Context::new(65536);
+        let index = ctx.store_bytecode(script.bytecode()).unwrap();
+        ctx.add_root(JSValue::from_ptr(index));
+        let code = FunctionCode::Heap(index);
+
+        let mut cache = ModuleCache::default();
+        let first = cache.get(&mut ctx, code, script.bytecode()).unwrap();
+        assert!(Rc::ptr_eq(&first, &cache.get(&mut ctx, code, script.bytecode()).unwrap()));
+
+        ctx.gc().unwrap();
+        assert!(!Rc::ptr_eq(&first, &cache.get(&mut ctx, code, script.bytecode()).unwrap()));
+    }
+}
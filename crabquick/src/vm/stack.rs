@@ -7,13 +7,12 @@
 use crate::value::JSValue;
 use alloc::vec::Vec;
 
-/// Maximum stack size to prevent stack overflow
-const MAX_STACK_SIZE: usize = 10000;
-
 /// Virtual machine value stack
 ///
 /// The value stack is used for operand evaluation during bytecode execution.
 /// It grows upward and supports push/pop operations as well as indexed access.
+/// Room for every slot is reserved when the stack is created, so it never
+/// reallocates mid-run and its footprint is known up front.
 pub struct ValueStack {
     /// Stack storage
     values: Vec<JSValue>,
@@ -22,20 +21,26 @@ pub struct ValueStack {
 }
 
 impl ValueStack {
-    /// Creates a new value stack with the specified maximum size
+    /// Largest number of slots a value stack can have
+    pub const MAX_SLOTS: usize = 10000;
+
+    /// Creates a new value stack with the specified maximum size, capped at
+    /// [`MAX_SLOTS`](Self::MAX_SLOTS)
     pub fn new(max_size: usize) -> Self {
-        let actual_max = if max_size > MAX_STACK_SIZE {
-            MAX_STACK_SIZE
-        } else {
-            max_size
-        };
+        let actual_max = max_size.min(Self::MAX_SLOTS);
 
         ValueStack {
-            values: Vec::with_capacity(actual_max.min(256)),
+            values: Vec::with_capacity(actual_max),
             max_size: actual_max,
         }
     }
 
+    /// Returns the most values the stack can hold
+    #[inline]
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
     /// Returns the current stack size
     #[inline]
     pub fn len(&self) -> usize {
@@ -194,6 +199,12 @@ impl ValueStack {
 /// Stack frame for function calls
 ///
 /// Each function call creates a new stack frame that tracks the execution state.
+///
+/// A frame owns the value stack slots `[sp, sp + locals)`: its parameters,
+/// then the rest of its locals, all reserved before the call starts.
+/// Arguments beyond the declared parameters sit just above that window,
+/// below the frame's temporaries. The top-level script reserves its own
+/// slots as it starts, so its window is unbounded.
 #[derive(Debug, Clone)]
 pub struct StackFrame {
     /// Function being executed (JSValue pointing to function object)
@@ -204,10 +215,12 @@ pub struct StackFrame {
     pub sp: usize,
     /// Number of arguments
     pub argc: u16,
+    /// Number of declared parameters
+    pub params: usize,
+    /// Number of local slots in the window, parameters included
+    pub locals: usize,
     /// 'this' value
     pub this: JSValue,
-    /// Previous exception handler PC (for try/catch)
-    pub catch_offset: Option<usize>,
     /// Closure object (if this is a closure call)
     /// This HeapIndex points to a JSClosure object containing captured variable references
     pub closure: Option<crate::memory::HeapIndex>,
@@ -221,8 +234,9 @@ impl StackFrame {
             pc: 0,
             sp,
             argc,
+            params: 0,
+            locals: usize::MAX,
             this,
-            catch_offset: None,
             closure: None,
         }
     }
@@ -234,26 +248,33 @@ impl StackFrame {
             pc: 0,
             sp,
             argc,
+            params: 0,
+            locals: usize::MAX,
             this,
-            catch_offset: None,
             closure: Some(closure),
         }
     }
 
-    /// Sets the exception handler offset
-    #[inline]
-    pub fn set_catch_offset(&mut self, offset: usize) {
-        self.catch_offset = Some(offset);
+    /// Sets the frame's window: `params` parameters among `locals` slots
+    pub fn with_window(mut self, params: usize, locals: usize) -> Self {
+        self.params = params;
+        self.locals = locals;
+        self
     }
 
-    /// Clears the exception handler
-    #[inline]
-    pub fn clear_catch_offset(&mut self) {
-        self.catch_offset = None;
+    /// Returns the value stack index of argument `index`
+    pub fn arg_slot(&self, index: usize) -> usize {
+        if index < self.params {
+            self.sp + index
+        } else {
+            self.sp + self.locals + (index - self.params)
+        }
     }
 }
 
 /// Call stack for managing function calls
+///
+/// Like the value stack, it reserves room for every frame when created.
 pub struct CallStack {
     frames: Vec<StackFrame>,
     max_depth: usize,
@@ -261,22 +282,25 @@ pub struct CallStack {
 
 impl CallStack {
     /// Maximum recursion depth
-    const MAX_CALL_DEPTH: usize = 1000;
+    pub const MAX_CALL_DEPTH: usize = 1000;
 
-    /// Creates a new call stack
+    /// Creates a new call stack, its depth capped at
+    /// [`MAX_CALL_DEPTH`](Self::MAX_CALL_DEPTH)
     pub fn new(max_depth: usize) -> Self {
-        let actual_max = if max_depth > Self::MAX_CALL_DEPTH {
-            Self::MAX_CALL_DEPTH
-        } else {
-            max_depth
-        };
+        let actual_max = max_depth.min(Self::MAX_CALL_DEPTH);
 
         CallStack {
-            frames: Vec::with_capacity(actual_max.min(32)),
+            frames: Vec::with_capacity(actual_max),
             max_depth: actual_max,
         }
     }
 
+    /// Returns the deepest the call stack can grow
+    #[inline]
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
     /// Returns the current call depth
     #[inline]
     pub fn depth(&self) -> usize {
@@ -475,6 +499,23 @@ mod tests {
         assert!(stack.push(JSValue::from_int(4)).is_err());
     }
 
+    #[test]
+    fn test_stacks_reserve_their_full_size() {
+        let mut stack = ValueStack::new(500);
+        let storage = stack.values.as_ptr();
+        for i in 0..500 {
+            stack.push(JSValue::from_int(i)).unwrap();
+        }
+        assert!(stack.push(JSValue::from_int(0)).is_err());
+        assert_eq!(stack.values.as_ptr(), storage, "the stack reallocated");
+
+        let call_stack = CallStack::new(300);
+        assert!(call_stack.frames.capacity() >= 300);
+
+        assert_eq!(ValueStack::new(usize::MAX).max_size(), ValueStack::MAX_SLOTS);
+        assert_eq!(CallStack::new(usize::MAX).max_depth(), CallStack::MAX_CALL_DEPTH);
+    }
+
     #[test]
     fn test_value_stack_drop_n() {
         let mut stack = ValueStack::new(100);
@@ -509,6 +550,15 @@ mod tests {
         assert_eq!(call_stack.depth(), 1);
     }
 
+    #[test]
+    fn test_frame_window_arg_slots() {
+        // f(a, b) with four locals, called with three arguments at sp 10
+        let frame = StackFrame::new(JSValue::undefined(), 10, 3, JSValue::undefined()).with_window(2, 4);
+        assert_eq!(frame.arg_slot(0), 10);
+        assert_eq!(frame.arg_slot(1), 11);
+        assert_eq!(frame.arg_slot(2), 14);
+    }
+
     #[test]
     fn test_call_stack_overflow() {
         let mut call_stack = CallStack::new(2);
@@ -518,17 +568,4 @@ mod tests {
 
         assert!(call_stack.push(StackFrame::new(JSValue::undefined(), 0, 0, JSValue::undefined())).is_err());
     }
-
-    #[test]
-    fn test_stack_frame_catch_offset() {
-        let mut frame = StackFrame::new(JSValue::undefined(), 0, 0, JSValue::undefined());
-
-        assert!(frame.catch_offset.is_none());
-
-        frame.set_catch_offset(100);
-        assert_eq!(frame.catch_offset, Some(100));
-
-        frame.clear_catch_offset();
-        assert!(frame.catch_offset.is_none());
-    }
 }
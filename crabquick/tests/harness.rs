@@ -5,12 +5,23 @@
 
 #![cfg(test)]
 
-use crabquick::{Engine, JSValue};
+use crabquick::{Engine, JSValue, LoadedScript};
 
 /// Run a JavaScript snippet and return the result as a string
+///
+/// The snippet runs twice, once copied into the heap and once borrowed in
+/// place as a `'static` script, and both runs must agree.
 pub fn run_js(source: &str) -> Result<String, String> {
     let mut engine = Engine::new(65536);
-    engine.eval_as_string(source)
+    let owned = engine.eval_as_string(source);
+
+    if let Ok(script) = LoadedScript::compile(source) {
+        let bytes: &'static [u8] = Box::leak(script.bytecode().into());
+        let mut engine = Engine::new(65536);
+        let borrowed = engine.run_as_string(&LoadedScript::borrowed(bytes));
+        assert_eq!(owned, borrowed, "Owned and borrowed runs disagree\nSource: {}", source);
+    }
+    owned
 }
 
 /// Run a JavaScript snippet and expect success with a specific result
@@ -45,6 +56,27 @@ pub fn assert_js_error(source: &str) {
     }
 }
 
+/// Run a script of `test(name, fn)` blocks and expect every one to pass
+///
+/// The script gets the self-test globals (`test`, `assertEquals` and
+/// `assertThrows`), so checks live on the JS side and failures are named.
+pub fn assert_js_tests(source: &str) {
+    let mut engine = Engine::new(65536);
+    engine.enable_test_api().expect("test API should install");
+    if let Err(e) = engine.eval(source) {
+        panic!("JavaScript execution failed: {}\nSource: {}", e, source);
+    }
+
+    let results = engine.test_results();
+    assert!(!results.is_empty(), "No tests ran\nSource: {}", source);
+    let failures: Vec<String> = results
+        .iter()
+        .filter(|result| !result.passed)
+        .map(|result| format!("{}: {}", result.name, result.message.as_deref().unwrap_or("")))
+        .collect();
+    assert!(failures.is_empty(), "JavaScript tests failed:\n{}\nSource: {}", failures.join("\n"), source);
+}
+
 /// Run a JavaScript snippet and compare the numeric result
 pub fn assert_js_num(source: &str, expected: f64) {
     let result = run_js(source).expect("JavaScript execution failed");
@@ -3,6 +3,7 @@
 #![cfg(test)]
 
 use crate::harness::*;
+use crabquick::Engine;
 
 #[test]
 #[ignore]
@@ -88,17 +89,28 @@ fn test_empty_array() {
 }
 
 #[test]
-#[ignore]
 fn test_array_join() {
     let code = r#"
         var arr = [1, 2, 3];
         arr.join(",")
     "#;
     assert_js_eq(code, "1,2,3");
+    assert_js_eq("[1, 2, 3].join('-')", "1-2-3");
+    assert_js_eq("[1, , null, undefined, true, 1.5].join()", "1,,,,true,1.5");
+    assert_js_eq("[1, [2, [3]], []].join(';')", "1;2,3;");
+    assert_js_eq("[{toString: function () { return 'X'; }}, {}].join(' ')", "X [object Object]");
+    // An array inside itself is skipped rather than recursed into
+    assert_js_eq("var a = [1]; a.push(a); a.join('-')", "1-");
+    let code = r#"
+        var big = [];
+        for (var n = 0; n < 1000; n++) big.push(n % 10);
+        var s = big.join('');
+        [s.length, s.slice(0, 12)].join()
+    "#;
+    assert_js_eq(code, "1000,012345678901");
 }
 
 #[test]
-#[ignore]
 fn test_array_slice() {
     let code = r#"
         var arr = [1, 2, 3, 4, 5];
@@ -106,10 +118,14 @@ fn test_array_slice() {
         sliced.length
     "#;
     assert_js_eq(code, "2");
+    assert_js_eq("[1, 2, 3, 4].slice(-2).join()", "3,4");
+    assert_js_eq("[1, 2, 3, 4].slice(-10, -1).join()", "1,2,3");
+    assert_js_eq("[[1, 2, 3].slice(2, 1).length, [1].slice(5).length].join()", "0,0");
+    assert_js_eq("var s = [1, , 3].slice(0); [s.length, 1 in s].join()", "3,false");
+    assert_js_eq("var a = [1, 2]; var s = a.slice(); s.push(3); [a.length, s.join(), Array.isArray(s)].join()", "2,1,2,3,true");
 }
 
 #[test]
-#[ignore]
 fn test_array_concat() {
     let code = r#"
         var arr1 = [1, 2];
@@ -118,6 +134,75 @@ fn test_array_concat() {
         result.length
     "#;
     assert_js_eq(code, "4");
+    assert_js_eq("[1, [2]].concat(3, [4, 5]).length", "5");
+    // Only one level is flattened
+    assert_js_eq("var c = [1].concat([[2, 3]], 'x'); [c.length, c[1].length, c[2]].join()", "3,2,x");
+    assert_js_eq("var c = [1, , 3].concat([4, , 6]); [c.length, 1 in c, 4 in c, c[5]].join()", "6,false,false,6");
+    assert_js_eq("var a = [1]; var c = a.concat(); c.push(2); [a.length, c.length].join()", "1,2");
+}
+
+#[test]
+fn test_array_splice() {
+    // Each case prints the removed elements, the length and the contents
+    let splice = |setup: &str, call: &str, expected: &str| {
+        let code = format!("var a = {}; var r = a.splice({}); [r.join(), a.length, a.join()].join('|')", setup, call);
+        assert_js_eq(&code, expected);
+    };
+    splice("[1, 2, 3, 4, 5]", "1, 2", "2,3|3|1,4,5");
+    splice("[1, 2, 3]", "1, 0, 'x', 'y'", "|5|1,x,y,2,3");
+    splice("[1, 2, 3, 4]", "1, 2, 'x', 'y', 'z'", "2,3|5|1,x,y,z,4");
+    splice("[1, 2, 3, 4]", "1, 3, 'x'", "2,3,4|2|1,x");
+    splice("[1, 2, 3, 4]", "-2, 1", "3|3|1,2,4");
+    splice("[1, 2, 3, 4]", "-10, 1", "1|3|2,3,4");
+    splice("[1, 2, 3]", "3, 0, 4, 5", "|5|1,2,3,4,5");
+    splice("[1, 2, 3]", "1, 99", "2,3|1|1");
+    splice("[1, 2, 3]", "0", "1,2,3|0|");
+    splice("[1, 2, 3]", "", "|3|1,2,3");
+    splice("[]", "0, 0, 'a'", "|1|a");
+    // The array keeps working afterwards
+    assert_js_eq("var a = [1, 2, 3]; a.splice(1, 1); a.push(9); a.unshift(0); a.join()", "0,1,3,9");
+    assert_js_true("Array.isArray([1, 2].splice(0, 1))");
+}
+
+#[test]
+fn test_array_splice_holes() {
+    // Holes stay holes, in the removed elements and in what moves
+    assert_js_eq("var a = [1, , 3, , 5]; var r = a.splice(1, 2); [r.length, 0 in r, a.length, 1 in a, a.join()].join()", "2,false,3,false,1,,5");
+    assert_js_eq("var a = [1, 2]; a.length = 5; a.splice(0, 1, 'z', 'y'); [a.length, a[2], 3 in a].join()", "6,2,false");
+    assert_js_eq("var a = []; a[1000] = 'far'; var r = a.splice(0, 1); [a.length, a[999], r.length].join()", "1000,far,1");
+}
+
+#[test]
+fn test_sparse_elements() {
+    // Truncating drops elements kept in the property table too
+    assert_js_true("var a = []; a[1000] = 1; a.length = 0; a.length = 2000; a[1000] === undefined && !(1000 in a)");
+    assert_js_eq("var a = []; a[1000] = 1; a[5] = 2; a.length = 500; Object.keys(a).join()", "5");
+    // A sparse index the dense store grows over is listed once
+    assert_js_eq("var a = []; a[100] = 'x'; for (var i = 0; i < 100; i++) a[i] = i; Object.keys(a).length", "101");
+    assert_js_eq("var a = []; a[100] = 'x'; for (var i = 0; i <= 100; i++) a[i] = i; [Object.keys(a).length, a[100]].join()", "101,100");
+}
+
+#[test]
+fn test_invalid_length() {
+    for length in ["-1", "1.5", "4294967296", "NaN", "'abc'"] {
+        let code = format!("var a = [1, 2], r = 'no error'; try {{ a.length = {}; }} catch (e) {{ r = e.name + ',' + a.length; }} r", length);
+        assert_js_eq(&code, "RangeError,2");
+    }
+    assert_js_eq("var a = [1, 2, 3]; a['length'] = '1'; [a.length, a[1]].join()", "1,");
+    assert_js_eq("var a = []; a.length = 4294967295; a.length", "4294967295");
+    // Other objects can have any length
+    assert_js_eq("var o = {}; o.length = -1; o.length", "-1");
+}
+
+#[test]
+fn test_largest_indices() {
+    // Indices up to 2^32 - 2 are elements, whether or not they fit an index atom
+    assert_js_eq("var a = []; a[2147483647] = 'x'; [a.length, a[2147483647]].join()", "2147483648,x");
+    assert_js_eq("var a = []; a[4294967294] = 'x'; [a.length, a['4294967294']].join()", "4294967295,x");
+    assert_js_eq("var a = [1]; a[3000000000] = 'x'; a[5] = 'y'; Object.keys(a).join()", "0,5,3000000000");
+    assert_js_true("var a = []; a[3000000000] = 'x'; a.length = 10; a.length === 10 && !(3000000000 in a) && a[3000000000] === undefined");
+    // 2^32 - 1 isn't an index, so it's a plain property
+    assert_js_eq("var a = []; a[4294967295] = 'x'; [a.length, a[4294967295]].join()", "0,x");
 }
 
 #[test]
@@ -149,3 +234,91 @@ fn test_nested_arrays() {
     "#;
     assert_js_eq(code, "2");
 }
+
+#[test]
+fn test_at() {
+    assert_js_eq("[1, 2, 3].at(1.9)", "2");
+    assert_js_eq("[1, 2, 3].at(-1)", "3");
+    assert_js_eq("[1, 2, 3].at(NaN)", "1");
+    assert_js_eq("[1, 2, 3].at(3)", "undefined");
+    assert_js_eq("[1, 2, 3].at(-Infinity)", "undefined");
+}
+
+#[test]
+fn test_index_of_strict_equality() {
+    assert_js_eq("[NaN].indexOf(NaN)", "-1");
+    assert_js_eq("[NaN].lastIndexOf(NaN)", "-1");
+    assert_js_eq("[NaN].includes(NaN)", "true");
+    assert_js_eq(r#"["x", "ab"].indexOf("a" + "b")"#, "1");
+    assert_js_eq(r#"["ab", "x", "ab"].lastIndexOf("a" + "b")"#, "2");
+    assert_js_eq(r#"[1, "1"].indexOf("1")"#, "1");
+}
+
+#[test]
+fn test_index_of_from_index() {
+    assert_js_eq("[1, 2, 3].indexOf(3, -1)", "2");
+    assert_js_eq("[1, 2, 3].indexOf(1, -10)", "0");
+    assert_js_eq("[1, 2, 3].indexOf(2, 1.7)", "1");
+    assert_js_eq("[1, 2, 3].indexOf(1, 5)", "-1");
+    assert_js_eq("[1, 2, 1].lastIndexOf(1, -2)", "0");
+    assert_js_eq("[1, 2].lastIndexOf(1, -3)", "-1");
+    assert_js_eq("[1, 2, 3].lastIndexOf(3, 99)", "2");
+    assert_js_eq("[1, 1].lastIndexOf(1, undefined)", "0");
+}
+
+#[test]
+fn test_index_of_skips_holes() {
+    let code = r#"
+        var a = [];
+        a[200] = "x";
+        [a.indexOf(undefined), a.lastIndexOf(undefined), a.indexOf("x"), a.lastIndexOf("x", 150)].join()
+    "#;
+    assert_js_eq(code, "-1,-1,200,-1");
+}
+
+#[test]
+#[cfg(feature = "builtin-json")]
+fn test_deeply_nested_array_conversions() {
+    // Fifty thousand levels would take every walker far past the host's
+    // native stack; each one must stop at the nesting budget instead
+    let mut engine = Engine::new(16 << 20);
+    engine.eval("var deep = []; for (var i = 0; i < 50000; i++) { deep = [deep]; }").unwrap();
+
+    let outcome = |engine: &mut Engine, call: &str| {
+        engine.eval_as_string(&format!("var r; try {{ r = typeof ({}); }} catch (e) {{ r = e.name + ': ' + e.message; }} r", call))
+    };
+    assert_eq!(
+        outcome(&mut engine, "JSON.stringify(deep)").unwrap(),
+        "RangeError: Maximum nesting depth exceeded"
+    );
+    engine.eval("var open = '[', close = ']'; while (open.length < 50000) { open += open; close += close; }").unwrap();
+    assert_eq!(
+        outcome(&mut engine, "JSON.parse(open + close)").unwrap(),
+        "RangeError: Maximum nesting depth exceeded"
+    );
+
+    // Joining an array's elements stops at the same budget
+    assert_eq!(outcome(&mut engine, "'' + deep").unwrap(), "RangeError: Maximum nesting depth exceeded");
+    assert_eq!(outcome(&mut engine, "deep.join()").unwrap(), "RangeError: Maximum nesting depth exceeded");
+    assert_eq!(outcome(&mut engine, "deep == ''").unwrap(), "RangeError: Maximum nesting depth exceeded");
+
+    // String() and console.log have no deep walk to cut short, but must
+    // come back either way
+    assert!(outcome(&mut engine, "String(deep)").is_ok());
+    #[cfg(feature = "builtin-console")]
+    {
+        let report = engine.eval_captured("console.log(deep); 'logged'");
+        assert_eq!(report.json.as_deref(), Ok("\"logged\""));
+        assert_eq!(report.output.len(), 1);
+    }
+
+    // The engine is still usable, with the budget back at zero
+    assert_eq!(engine.eval_as_string("JSON.stringify([[[1]], {a: [2]}])").unwrap(), "[[[1]],{\"a\":[2]}]");
+
+    engine.set_max_nesting_depth(2);
+    assert_eq!(outcome(&mut engine, "JSON.stringify([[1]])").unwrap(), "string");
+    assert_eq!(
+        outcome(&mut engine, "JSON.stringify([[[1]]])").unwrap(),
+        "RangeError: Maximum nesting depth exceeded"
+    );
+}
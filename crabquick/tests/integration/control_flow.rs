@@ -123,7 +123,6 @@ fn test_for_loop_break() {
 }
 
 #[test]
-#[ignore]
 fn test_for_loop_continue() {
     let code = r#"
         var sum = 0;
@@ -204,3 +203,442 @@ fn test_fizzbuzz() {
     // Should contain Fizz, Buzz, and FizzBuzz
     assert_js_ok(code);
 }
+
+#[test]
+fn test_for_of_destructuring() {
+    let code = r#"
+        var prices = { apple: 3, pear: 4, plum: 5 };
+        var total = 0;
+        var names = "";
+        for (var [name, price] of Object.entries(prices)) {
+            names = names + name;
+            total = total + price;
+        }
+        names + " " + total
+    "#;
+    assert_js_eq(code, "applepearplum 12");
+}
+
+#[test]
+fn test_for_of_destructuring_in_function() {
+    let code = r#"
+        function dot(pairs) {
+            var sum = 0;
+            for (let [x, [y]] of pairs) {
+                sum = sum + x * y;
+            }
+            return sum;
+        }
+        dot([[1, [2]], [3, [4]], [5, [6]]])
+    "#;
+    assert_js_eq(code, "44");
+}
+
+#[test]
+fn test_do_while_loop() {
+    let code = r#"
+        var i = 0;
+        do {
+            i++;
+        } while (i < 3);
+        i
+    "#;
+    assert_js_eq(code, "3");
+}
+
+#[test]
+fn test_do_while_runs_body_once() {
+    let code = r#"
+        var runs = 0;
+        do {
+            runs++;
+        } while (false);
+        runs
+    "#;
+    assert_js_eq(code, "1");
+}
+
+#[test]
+fn test_do_while_break_continue() {
+    let code = r#"
+        function sumEven(limit) {
+            var i = 0;
+            var sum = 0;
+            do {
+                i++;
+                if (i > limit) break;
+                if (i % 2) continue;
+                sum += i;
+            } while (true);
+            return sum;
+        }
+        sumEven(6)
+    "#;
+    assert_js_eq(code, "12");
+}
+
+#[test]
+fn test_switch_fall_through() {
+    let code = r#"
+        var x = 2, r = 0;
+        switch (x) {
+            case 1: r = 10; break;
+            case 2: r = 20;
+            case 3: r += 1; break;
+        }
+        r
+    "#;
+    assert_js_eq(code, "21");
+}
+
+#[test]
+fn test_switch_default() {
+    let code = r#"
+        function name(n) {
+            switch (n) {
+                case 1: return "one";
+                default: return "many";
+                case 2: return "two";
+            }
+        }
+        name(1) + name(2) + name(3)
+    "#;
+    assert_js_eq(code, "onetwomany");
+}
+
+#[test]
+fn test_switch_strict_equality() {
+    let code = r#"
+        var r = "none";
+        switch ("1") {
+            case 1: r = "number"; break;
+            case "1": r = "string"; break;
+        }
+        r
+    "#;
+    assert_js_eq(code, "string");
+}
+
+#[test]
+fn test_continue_in_switch_targets_loop() {
+    let code = r#"
+        var i = 0, sum = 0;
+        do {
+            i++;
+            switch (i) {
+                case 2: continue;
+                case 3: break;
+            }
+            sum += i;
+        } while (i < 4);
+        sum
+    "#;
+    assert_js_eq(code, "8");
+}
+
+#[test]
+fn test_for_loop_continue_skips_to_update() {
+    let code = r#"
+        var s = 0;
+        for (var i = 0; i < 5; i++) {
+            if (i % 2) continue;
+            s += i;
+        }
+        s
+    "#;
+    assert_js_eq(code, "6");
+}
+
+#[test]
+fn test_while_loop_continue() {
+    let code = r#"
+        var i = 0, s = 0;
+        while (i < 5) {
+            i++;
+            if (i === 3) continue;
+            s += i;
+        }
+        s
+    "#;
+    assert_js_eq(code, "12");
+}
+
+#[test]
+fn test_continue_in_nested_loop_targets_inner() {
+    let code = r#"
+        var pairs = 0;
+        for (var i = 0; i < 3; i++) {
+            for (var j = 0; j < 3; j++) {
+                if (j === i) continue;
+                pairs++;
+            }
+        }
+        pairs
+    "#;
+    assert_js_eq(code, "6");
+}
+
+#[test]
+fn test_for_in_of_continue() {
+    let code = r#"
+        var keys = "";
+        for (var k in { a: 1, b: 2, c: 3 }) {
+            if (k === "b") continue;
+            keys += k;
+        }
+        var sum = 0;
+        for (var v of [1, 2, 3, 4]) {
+            if (v % 2 === 0) continue;
+            sum += v;
+        }
+        keys + sum
+    "#;
+    assert_js_eq(code, "ac4");
+}
+
+#[test]
+fn test_for_in() {
+    assert_js_eq("var k=''; for (var p in {a:1,b:2}) k+=p; k", "ab");
+    assert_js_eq("var k=''; for (var p in {b:1, 10:1, a:1, 2:1}) k+=p+','; k", "2,10,b,a,");
+    assert_js_eq("var k=''; for (var p in [5, 6]) k+=p+typeof p; k", "0string1string");
+    assert_js_eq("var k=''; for (var p in null) k+=p; for (var p in undefined) k+=p; k", "");
+}
+
+#[test]
+fn test_for_in_inherited_keys() {
+    let code = r#"
+        var o = Object.create({ inherited: 1, own: 2 });
+        o.own = 3;
+        var k = '';
+        for (var p in o) k += p + ',';
+        for (var p in {}) k += p;
+        k
+    "#;
+    assert_js_eq(code, "own,inherited,");
+}
+
+#[test]
+fn test_for_in_added_keys_do_not_crash() {
+    assert_js_eq("var k=''; var o={a:1}; for (var p in o) { o.z=1; k+=p; } k", "a");
+}
+
+#[test]
+fn test_for_in_early_exit() {
+    let code = r#"
+        function find(o) { for (var p in o) { if (p === 'b') return p; } }
+        var k = '';
+        for (var a in { x: 1, y: 1 }) {
+            for (var b in { p: 1, q: 1 }) { if (b === 'p') break; }
+            try { for (var c in { r: 1 }) throw c; } catch (e) { k += e; }
+            k += a + find({ a: 1, b: 1, c: 1 });
+        }
+        k
+    "#;
+    assert_js_eq(code, "rxbryb");
+}
+
+#[test]
+fn test_for_of() {
+    assert_js_eq("var s=0; for (var v of [1,2,3]) s+=v; s", "6");
+    assert_js_eq("var n=0; for (var v of [1, undefined, 3]) n++; n", "3");
+    assert_js_eq("var k=''; for (var c of 'aé😀b') k+=c+','; k", "a,é,😀,b,");
+    assert_js_eq("var a=[1,2]; var n=0; for (var v of a) { if (a.length < 4) a.push(v); n++; } n", "4");
+}
+
+#[test]
+fn test_for_of_non_iterable_throws() {
+    assert_js_eq("var r; try { for (var v of 5) {} } catch (e) { r = e.name + ': ' + e.message } r", "TypeError: number is not iterable");
+    assert_js_eq("var r; try { for (var v of {}) {} } catch (e) { r = e.message } r", "object is not iterable");
+    assert_js_eq("var r; try { for (var v of null) {} } catch (e) { r = e.message } r", "null is not iterable");
+}
+
+#[test]
+fn test_for_of_early_exit() {
+    let code = r#"
+        function find(a) { for (var v of a) { if (v > 1) return v; } }
+        var k = '';
+        for (var x of [1, 2]) {
+            for (var c of 'pq') { if (c === 'q') break; k += c; }
+            try { for (var y of [9]) throw y; } catch (e) { k += e; }
+            k += x + find([0, 5]);
+        }
+        k
+    "#;
+    assert_js_eq(code, "p96p97");
+}
+
+#[test]
+fn test_feature_detection_preamble() {
+    // Runs verbatim; logs "x" when the console builtin is enabled
+    assert_js_ok(r#"if (typeof console === "object" && console.log) { console.log("x") }"#);
+
+    let detect = r#"
+        var found = "no";
+        if (typeof console === "object" && console.log) { found = "yes"; }
+        found
+    "#;
+    let expected = if cfg!(feature = "builtin-console") { "yes" } else { "no" };
+    assert_js_eq(detect, expected);
+}
+
+#[test]
+fn test_feature_detection_preamble_false_branch() {
+    let shadowed = r#"
+        var console = undefined;
+        var found = "no";
+        if (typeof console === "object" && console.log) { found = "yes"; } else { found = "fallback"; }
+        found
+    "#;
+    assert_js_eq(shadowed, "fallback");
+
+    let missing = r#"
+        var found;
+        if (typeof logger === "object" && logger.log) { found = "yes"; } else { found = "fallback"; }
+        found
+    "#;
+    assert_js_eq(missing, "fallback");
+
+    let without_log = r#"
+        var console = {};
+        var found;
+        if (typeof console === "object" && console.log) { found = "yes"; } else { found = "fallback"; }
+        found
+    "#;
+    assert_js_eq(without_log, "fallback");
+}
+
+#[test]
+fn test_throw_after_completed_try_propagates() {
+    assert_js_error("try { 1; } catch (e) { 2; } throw 'late';");
+    assert_js_error("function f() { try { return 1; } catch (e) {} } f(); throw 'late';");
+    assert_js_error("for (var i = 0; i < 2; i++) { try { if (i) break; continue; } catch (e) {} } throw 'late';");
+
+    let code = r#"
+        function f(n) {
+            try { n = n + 1; } catch (e) { return 'swallowed'; }
+            for (var i = 0; i < 3; i++) { try { if (i == 1) break; } catch (e) { return 'swallowed'; } }
+            throw 'late' + n;
+        }
+        var r; try { r = f(1); } catch (e) { r = e; } r
+    "#;
+    assert_js_eq(code, "late2");
+}
+
+#[test]
+fn test_exception_in_nested_call_uses_own_handlers() {
+    // The callee's throw lands in the caller's catch block, not at the
+    // callee's offset of the caller's handler
+    let code = r#"
+        function inner(x) { if (x) throw 'in' + x; return 'ok'; }
+        function outer(x) {
+            var pad = 0; pad++; pad++; pad++;
+            try { return inner(x); } catch (e) { return 'outer:' + e; }
+        }
+        outer(0) + ',' + outer(1)
+    "#;
+    assert_js_eq(code, "ok,outer:in1");
+
+    // A callee with a completed try of its own, called from inside a try
+    let code = r#"
+        function inner() { try { 1; } catch (e) { return 'inner caught'; } throw 'escaped'; }
+        function outer() { try { inner(); } catch (e) { return 'outer:' + e; } }
+        outer()
+    "#;
+    assert_js_eq(code, "outer:escaped");
+
+    // Throws from callbacks run by native functions
+    let code = r#"
+        var log = '';
+        try { [1, 2].map(function (v) { try { 0; } catch (e) { log += 'no'; } if (v == 2) throw 'cb' + v; }); }
+        catch (e) { log += e; }
+        [3].forEach(function (v) { try { throw v; } catch (e) { log += ',' + e; } });
+        log
+    "#;
+    assert_js_eq(code, "cb2,3");
+}
+
+#[test]
+fn test_constant_branches_keep_semantics() {
+    // Names declared in a removed branch still exist, as undefined
+    assert_js_eq("if (false) { var a = 1; function f() {} } typeof a + ',' + typeof f", "undefined,undefined");
+    assert_js_eq("function g() { if (0) { var v = 1; } return v; } typeof g()", "undefined");
+
+    // A removed redeclaration doesn't reset the value
+    assert_js_eq("var x = 5; if (false) { var x = 1; } x", "5");
+    assert_js_eq("var y = 5; var y; y", "5");
+
+    // Short-circuits drop only the side that can't run
+    assert_js_eq("var n = 0; function bump() { n++; return n; } (false && bump()) + ',' + (true && bump()) + ',' + n", "false,1,1");
+    assert_js_eq("var obj = { v: 7, f: function () { return this === obj; } }; (true && obj.f)() + ',' + (1 ? obj : 0).f()", "false,true");
+
+    // A top-level constant guards its block
+    assert_js_eq("const DEBUG = false; var log = ''; if (DEBUG) { log += 'dbg'; } else { log += 'ok'; } log", "ok");
+    assert_js_eq("const LEVEL = 3; var out = ''; if (LEVEL > 2) out += 'hi'; while (LEVEL < 0) out += 'never'; out", "hi");
+    assert_js_eq("var s = 'run'; for (var i = 0; false; i++) { s = 'loop'; } s + i", "run0");
+    assert_js_eq("function h() { return 1; var late = 2; } h()", "1");
+}
+
+#[test]
+fn test_loop_conditions_do_not_allocate() {
+    // Comparisons push plain booleans and read boxed floats, strings and
+    // lengths in place, so a comparison-heavy loop allocates nothing per
+    // iteration. Float and string literals are boxed each time they're
+    // pushed, so the loop reads them from variables.
+    let mut engine = crabquick::Engine::new(1 << 20);
+    engine.eval(r#"
+        var arr = [1, 2, 3], s = 'abc', a = 'a', big = 1.5, small = 0.25, o = {}, none = null;
+        function spin(n) {
+            var i = 0, hits = 0;
+            while (i < n) {
+                if (i <= big && i >= small && big > i) hits++;
+                if (arr.length > 2 && s != null && o !== none && s) hits++;
+                if (none == undefined && i != big && a < s && s >= a) hits++;
+                i++;
+            }
+            for (; i > 0 && big < n;) i--;
+            do { i++; } while (i <= n && i != s);
+            return hits;
+        }
+    "#).unwrap();
+
+    let mut allocated = |source: &str| {
+        let before = engine.memory_stats();
+        engine.eval(source).unwrap();
+        let after = engine.memory_stats();
+        assert_eq!(after.gc_count, before.gc_count, "{}", source);
+        after.allocated_since_gc - before.allocated_since_gc
+    };
+    let few = allocated("spin(300)");
+    let many = allocated("spin(3000)");
+    assert_eq!(few, many);
+}
+
+#[test]
+fn test_integer_arithmetic_does_not_allocate() {
+    // Results that fit an inline integer are built without a heap box
+    let mut engine = crabquick::Engine::new(1 << 20);
+    engine.eval(r#"
+        function sum(n) {
+            var total = 0, odd = 0, scaled = 0;
+            for (var i = 0; i < n; i++) {
+                total = total + i;
+                odd += i % 2;
+                scaled = (i * 3 + total / 1) % 1000;
+                --scaled;
+            }
+            return total;
+        }
+    "#).unwrap();
+
+    let mut allocated = |source: &str| {
+        let before = engine.memory_stats();
+        engine.eval(source).unwrap();
+        let after = engine.memory_stats();
+        assert_eq!(after.gc_count, before.gc_count, "{}", source);
+        after.allocated_since_gc - before.allocated_since_gc
+    };
+    let few = allocated("sum(1000)");
+    let many = allocated("sum(10000)");
+    assert_eq!(few, many);
+    assert_eq!(engine.eval_as_string("sum(10000)").unwrap(), "49995000");
+}
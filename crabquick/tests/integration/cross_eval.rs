@@ -0,0 +1,80 @@
+//! Functions outliving the eval that defined them
+//!
+//! A REPL or an event loop defines handlers in one `eval` and calls them
+//! from later ones, or from the host. A function carries its own code
+//! (constants, atoms and nested functions included) and the variables it
+//! captured, so it stays callable for as long as something refers to it,
+//! collections in between included.
+
+#![cfg(test)]
+
+use crabquick::{Context, Engine, JSValue};
+
+fn eval(engine: &mut Engine, code: &str) -> String {
+    engine.eval_as_string(code).unwrap_or_else(|e| panic!("{code}: {e}"))
+}
+
+#[test]
+fn test_functions_called_from_later_evals() {
+    let mut engine = Engine::new(262144);
+    engine.eval("function f() { return 7 }").unwrap();
+    assert_eq!(eval(&mut engine, "f()"), "7");
+
+    // Expressions, methods and arrows stored in globals
+    engine.eval("var g = function (x) { return 'g' + x }; var o = { m() { return this.v }, v: 3 }; var h = (a, b) => a * b;").unwrap();
+    assert_eq!(eval(&mut engine, "g(1) + ',' + o.m() + ',' + h(6, 7)"), "g1,3,42");
+
+    // A function whose own constants and nested functions came with it
+    engine.eval("function fmt(n) { var parts = ['n', 'is']; return parts.map(function (p) { return p + ':' }).join('') + (n * 1.5) }").unwrap();
+    assert_eq!(eval(&mut engine, "fmt(3)"), "n:is:4.5");
+
+    // A later eval replaces it, and earlier references keep the old one
+    engine.eval("var oldF = f; function f() { return 8 }").unwrap();
+    assert_eq!(eval(&mut engine, "f() + ',' + oldF()"), "8,7");
+}
+
+#[test]
+fn test_captured_variables_persist() {
+    let mut engine = Engine::new(262144);
+    engine.eval("var counter = (function () { var n = 0; return function () { return ++n } })();").unwrap();
+    assert_eq!(eval(&mut engine, "counter(); counter()"), "2");
+    assert_eq!(eval(&mut engine, "counter()"), "3");
+
+    // Top-level lexical bindings captured by a function
+    engine.eval("let k = 10; const base = 'b'; function addK(x) { return base + (x + k) } var bump = function () { return ++k };").unwrap();
+    assert_eq!(eval(&mut engine, "bump(); addK(1)"), "b12");
+
+    // Closures made in a loop each keep their own variable
+    engine.eval("var fns = [1, 2, 3].map(function (x) { return function () { return x * 10 } });").unwrap();
+    assert_eq!(eval(&mut engine, "fns[0]() + fns[2]()"), "40");
+
+    // Collections between evals keep everything the closures captured
+    for round in 0..3 {
+        engine.eval(&format!("var junk{round} = [1, 2, 3].map(function (x) {{ return {{ v: x }} }});")).unwrap();
+        engine.gc();
+    }
+    assert_eq!(eval(&mut engine, "[counter(), addK(0), fns[1](), bump()].join()"), "4,b11,20,12");
+}
+
+#[test]
+fn test_host_calls_functions_from_earlier_evals() {
+    let mut engine = Engine::new(262144);
+    let make = engine.eval("(function (start) { var total = start; return function (x) { total += x; return total } })").unwrap();
+    let add = engine.call_function(make, &[JSValue::from_int(100)]).unwrap();
+    let add = engine.persist(add);
+    engine.gc();
+
+    for (delta, expected) in [(1, 101), (2, 103)] {
+        let add = engine.persistent_value(&add).unwrap();
+        assert_eq!(engine.call_function(add, &[JSValue::from_int(delta)]).unwrap().to_int(), Some(expected));
+    }
+
+    // Plain contexts run closures between evals too
+    let mut ctx = Context::new(262144);
+    crabquick::runtime::init_runtime(&mut ctx).unwrap();
+    let tick = ctx.eval("var n = 0; (function () { return ++n })", "a.js", 0).unwrap();
+    let key = ctx.intern_atom("tick");
+    ctx.set_global_property(key, tick).unwrap();
+    assert_eq!(ctx.call_function(tick, JSValue::undefined(), &[]).unwrap().to_int(), Some(1));
+    assert_eq!(ctx.eval("tick() + n", "b.js", 0).unwrap().to_int(), Some(4));
+}
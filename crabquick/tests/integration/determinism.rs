@@ -0,0 +1,90 @@
+//! Compiling the same source must give byte-identical bytecode
+//!
+//! Deployment pipelines diff or fingerprint compiled scripts to decide what
+//! to ship, so the output may depend only on the source, the compiler
+//! version and the enabled features: not on what was compiled before, on
+//! heap addresses, or on the thread doing the compiling. The corpus is the
+//! repository's example scripts plus a few of the larger test programs.
+
+#![cfg(test)]
+
+use crabquick::compiler::{compile, compile_from_line, compile_with_diagnostics};
+use crabquick::LoadedScript;
+use std::path::Path;
+use std::thread;
+
+/// Returns `(name, source)` for every script in the corpus, in a fixed order
+fn corpus() -> Vec<(String, String)> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let mut paths: Vec<_> = std::fs::read_dir(root.join("examples"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "js"))
+        .collect();
+    for name in ["test_language.js", "test_closure.js", "test_loop.js", "test_rect.js", "mandelbrot.js"] {
+        paths.push(root.join("tests").join(name));
+    }
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| (path.display().to_string(), std::fs::read_to_string(&path).unwrap()))
+        .collect()
+}
+
+fn compile_all(corpus: &[(String, String)]) -> Vec<Vec<u8>> {
+    corpus.iter().map(|(name, source)| compile(source).unwrap_or_else(|e| panic!("{name}: {e:?}"))).collect()
+}
+
+#[test]
+fn test_compiling_twice_gives_identical_bytes() {
+    let corpus = corpus();
+    assert!(corpus.len() > 100, "only {} scripts found", corpus.len());
+    let first = compile_all(&corpus);
+    let second = compile_all(&corpus);
+    for ((name, _), (a, b)) in corpus.iter().zip(first.iter().zip(&second)) {
+        assert!(a == b, "{name} compiled differently the second time");
+    }
+}
+
+#[test]
+fn test_bytes_do_not_depend_on_compile_order_or_heap_layout() {
+    let corpus = corpus();
+    let expected = compile_all(&corpus);
+
+    // Backwards, on other threads, with the allocator in a different state
+    let handles: Vec<_> = (0..3)
+        .map(|round| {
+            let corpus = corpus.clone();
+            thread::spawn(move || {
+                let mut ballast = Vec::new();
+                let mut results = Vec::new();
+                for (index, (_, source)) in corpus.iter().enumerate().rev() {
+                    ballast.push(vec![round as u8; (index * 37 + round * 101) % 4096]);
+                    if index % 3 == round {
+                        ballast.swap_remove(0);
+                    }
+                    results.push((index, compile(source).unwrap()));
+                }
+                results
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        for (index, bytes) in handle.join().unwrap() {
+            assert!(bytes == expected[index], "{} depends on how it was compiled", corpus[index].0);
+        }
+    }
+}
+
+#[test]
+fn test_equivalent_entry_points_agree() {
+    for (name, source) in corpus() {
+        let bytes = compile(source.as_str()).unwrap();
+        assert_eq!(compile_from_line(&source, 0).unwrap(), bytes, "{name}");
+        assert_eq!(compile_with_diagnostics(&source).unwrap().0, bytes, "{name}");
+        let script = LoadedScript::compile(&source).unwrap();
+        assert_eq!(script.bytecode(), bytes.as_slice(), "{name}");
+        assert_eq!(script.fingerprint(), LoadedScript::borrowed(&bytes).fingerprint(), "{name}");
+    }
+}
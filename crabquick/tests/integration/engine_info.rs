@@ -0,0 +1,38 @@
+//! The `engine` object
+//!
+//! A frozen global describing the build: `version`, a boolean per feature
+//! in `features`, and `limits`. Writes, new entries and deletes are all
+//! ignored.
+
+#![cfg(test)]
+
+use crate::harness::*;
+
+#[test]
+fn test_engine_version() {
+    assert_js_eq("engine.version", env!("CARGO_PKG_VERSION"));
+    assert_js_eq("typeof engine.limits.arenaSize", "number");
+    assert_js_eq("engine.limits.maxCallDepth > 0", "true");
+}
+
+#[test]
+fn test_engine_features() {
+    assert_js_eq("engine.features.json", &cfg!(feature = "builtin-json").to_string());
+    assert_js_eq("engine.features.typedArrays", &cfg!(feature = "builtin-typed-array").to_string());
+    assert_js_eq("engine.features.date", &cfg!(feature = "builtin-date").to_string());
+    assert_js_eq("engine.features.regexp || engine.features.promises", "false");
+    assert_js_eq("typeof engine.features.unknown", "undefined");
+    assert_js_eq("var n = 0; for (var k in engine.features) n++; n", "8");
+    assert_js_eq("var seen = false; for (var k in this) if (k === 'engine') seen = true; seen", "false");
+}
+
+#[test]
+fn test_engine_object_is_frozen() {
+    assert_js_eq("engine.version = 'x'; engine.version", env!("CARGO_PKG_VERSION"));
+    assert_js_eq("engine.features.json = 'x'; typeof engine.features.json", "boolean");
+    assert_js_eq("engine.extra = 1; engine.extra", "undefined");
+    assert_js_eq("engine.limits.extra = 1; engine.limits.extra", "undefined");
+    assert_js_eq("delete engine.features", "false");
+    assert_js_eq("delete engine.limits.arenaSize; typeof engine.limits.arenaSize", "number");
+    assert_js_eq("Object.defineProperty(engine, 'x', { value: 1 }); engine.x", "undefined");
+}
@@ -0,0 +1,152 @@
+//! Cross-checks between the ways scripts enumerate an object
+//!
+//! for-in, Object.keys, Object.entries, Object.values, JSON.stringify and
+//! Object.assign each walk properties on their own path. They must agree:
+//! the same own enumerable keys in the same order (integer keys ascending,
+//! then names in insertion order), no deleted keys, no non-enumerable keys,
+//! no array holes, and inherited keys only in for-in, after the own ones.
+//! Each test compares the paths against each other as well as against the
+//! expected keys, so a regression in one shows up as a divergence.
+
+#![cfg(test)]
+
+use crabquick::Context;
+
+use crate::harness::*;
+
+/// Builds `o`: integer keys added out of order, a deleted key, a
+/// non-enumerable key, an array with a hole, and a prototype with an
+/// enumerable value and an enumerable getter; `forInKeys` collects what
+/// for-in visits
+const TRICKY_OBJECT: &str = r#"
+    var proto = { inherited: 1, get lazy() { return 'L'; } };
+    var o = Object.create(proto);
+    o.b = 1;
+    o[10] = 'ten';
+    o.a = 2;
+    o[2] = 'two';
+    o.gone = 3;
+    delete o.gone;
+    Object.defineProperty(o, 'hidden', { value: 4, enumerable: false });
+    o.arr = [1, , 3];
+
+    function forInKeys(obj) {
+        var list = [];
+        for (var k in obj) list.push(k);
+        return list;
+    }
+"#;
+
+fn run_tricky(expr: &str) -> String {
+    run_js(&format!("{}\n{}", TRICKY_OBJECT, expr)).expect("script failed")
+}
+
+#[test]
+fn test_enumeration_key_order() {
+    assert_eq!(run_tricky("Object.keys(o).join()"), "2,10,b,a,arr");
+    assert_eq!(run_tricky("forInKeys(o).join()"), "2,10,b,a,arr,inherited,lazy");
+}
+
+#[test]
+#[cfg(feature = "builtin-json")]
+fn test_enumeration_paths_agree() {
+    // Every own-key path matches Object.keys exactly
+    let code = r#"
+        var keys = Object.keys(o).join();
+        [
+            forInKeys(o).filter(function (k) { return o.hasOwnProperty(k); }).join() === keys,
+            Object.entries(o).map(function (e) { return e[0]; }).join() === keys,
+            Object.keys(JSON.parse(JSON.stringify(o))).join() === keys,
+            Object.keys(Object.assign({}, o)).join() === keys,
+            Object.keys({ ...o }).join() === keys
+        ].join()
+    "#;
+    assert_eq!(run_tricky(code), "true,true,true,true,true");
+    // for-in lists the own keys first, then only inherited ones
+    let code = r#"
+        var own = Object.keys(o);
+        forInKeys(o).filter(function (k, i) { return i < own.length; }).join() === own.join()
+    "#;
+    assert_eq!(run_tricky(code), "true");
+}
+
+#[test]
+#[cfg(feature = "builtin-json")]
+fn test_enumeration_values_agree() {
+    let code = r#"
+        var fromKeys = Object.keys(o).map(function (k) { return o[k]; });
+        [
+            JSON.stringify(Object.values(o)) === JSON.stringify(fromKeys),
+            JSON.stringify(Object.assign({}, o)) === JSON.stringify(o),
+            JSON.stringify({ ...o }) === JSON.stringify(o),
+            Object.entries(o).every(function (e) { return e[1] === o[e[0]]; })
+        ].join()
+    "#;
+    assert_eq!(run_tricky(code), "true,true,true,true");
+    assert_eq!(run_tricky("JSON.stringify(o)"), r#"{"2":"two","10":"ten","b":1,"a":2,"arr":[1,null,3]}"#);
+}
+
+#[test]
+fn test_enumeration_skips_hidden_deleted_and_inherited() {
+    let code = r#"
+        var copy = Object.assign({}, o);
+        [
+            'hidden' in o, o.hidden, copy.hidden,
+            'gone' in o, 'inherited' in copy, o.lazy
+        ].join()
+    "#;
+    assert_eq!(run_tricky(code), "true,4,,false,false,L");
+}
+
+#[test]
+fn test_enumeration_skips_error_fields() {
+    assert_js_eq("Object.keys(new TypeError('x')).length", "0");
+    assert_js_eq("var e = new Error('x'); e.code = 7; Object.keys(e).join() + '|' + JSON.stringify(e)", r#"code|{"code":7}"#);
+    assert_js_eq(
+        "var k = []; try { null.x; } catch (e) { for (var p in e) k.push(p); k.push(e.name, e.lineNumber); } k.join()",
+        "TypeError,1",
+    );
+}
+
+#[test]
+fn test_enumeration_array_holes() {
+    let code = r#"
+        var a = o.arr;
+        [forInKeys(a).join(), Object.keys(a).join(), Object.entries(a).length, 1 in a, a.length].join('|')
+    "#;
+    assert_eq!(run_tricky(code), "0,2|0,2|2|false|3");
+    assert_js_eq("var a = [1, 2, 3]; delete a[1]; Object.keys(a).join() + '|' + a.join()", "0,2|1,,3");
+    assert_js_eq("[1, , ].length + ',' + Object.keys([, , 5]).join()", "2,2");
+}
+
+#[test]
+fn test_host_reads_object_fields() {
+    let mut ctx = Context::new(262144);
+    crabquick::runtime::init_runtime(&mut ctx).unwrap();
+    let config = ctx.eval("({host: 'dev1', port: 8080})", "config.js", 0).unwrap();
+
+    let mut host = None;
+    let mut port = None;
+    let keys = ctx.own_enumerable_keys(config);
+    for &key in &keys {
+        let value = ctx.get_property(config, key).unwrap();
+        match ctx.atom_to_str(key) {
+            Some("host") => host = ctx.get_string(value).map(String::from),
+            Some("port") => port = value.to_int(),
+            other => panic!("unexpected key {other:?}"),
+        }
+    }
+    assert_eq!(host.as_deref(), Some("dev1"));
+    assert_eq!(port, Some(8080));
+
+    // Internal slots stay hidden, and the keys outlive changes to the object
+    let bound = ctx.eval("var f = Math.max.bind(null, 1); f.tag = 1; f", "bound.js", 0).unwrap();
+    let names: Vec<_> = ctx.own_enumerable_keys(bound).into_iter().map(|key| ctx.atom_name(key).unwrap().into_owned()).collect();
+    assert_eq!(names, ["tag"]);
+
+    let holder = ctx.intern_atom("config");
+    ctx.set_global_property(holder, config).unwrap();
+    let config = ctx.eval("delete config.host; config.extra = true; config", "mutate.js", 0).unwrap();
+    assert_eq!(ctx.get_property(config, keys[0]), None);
+    assert_eq!(ctx.get_property(config, keys[1]).and_then(|v| v.to_int()), Some(8080));
+}
@@ -130,6 +130,22 @@ fn test_function_expression() {
     assert_js_eq(code, "9");
 }
 
+#[test]
+fn test_named_function_expression_recursion() {
+    assert_js_eq("var f = function fact(n){ return n<2 ? 1 : n*fact(n-1); }; f(5)", "120");
+
+    // The name is bound inside closures too, and a parameter shadows it
+    let code = r#"
+        function outer(k) {
+            var fib = function inner(n) { return n < 2 ? n * k : inner(n - 1) + inner(n - 2); };
+            return fib(10);
+        }
+        var same = function q(q) { return q; };
+        outer(1) + ',' + [3].map(function sq(x) { return x > 9 ? x : sq(x * x); })[0] + ',' + same(7)
+    "#;
+    assert_js_eq(code, "55,81,7");
+}
+
 #[test]
 #[ignore]
 fn test_nested_functions() {
@@ -168,3 +184,227 @@ fn test_function_without_return() {
     "#;
     assert_js_eq(code, "undefined");
 }
+
+#[test]
+fn test_conditional_arguments() {
+    let code = r#"
+        function show(a, b, c) {
+            return a + ',' + b + ',' + c;
+        }
+        var t = true;
+        show(t ? 1 : 2, 3, 4) + ' ' + show(5, t ? 6 : 7, 8) + ' ' + show(9, 10, t ? 11 : 12)
+    "#;
+    assert_js_eq(code, "1,3,4 5,6,8 9,10,11");
+}
+
+#[test]
+fn test_logical_arguments() {
+    let code = r#"
+        function show(a, b, c) {
+            return a + ',' + b + ',' + c;
+        }
+        var u;
+        show(0 && 1, 2, 3) + ' ' + show(4, 0 || 5, 6) + ' ' + show(7, 8, u ?? 9) + ' ' + show(null ?? 0, 1 && 2, '' || 'x')
+    "#;
+    assert_js_eq(code, "0,2,3 4,5,6 7,8,9 0,2,x");
+}
+
+#[test]
+fn test_nested_call_arguments() {
+    let code = r#"
+        function show(a, b, c) {
+            return a + ',' + b + ',' + c;
+        }
+        function pick(c, x, y) {
+            return c ? x : y;
+        }
+        show(pick(true, 1, 2), pick(false && true, 3, 4), pick(0 || 1, 5, 6))
+    "#;
+    assert_js_eq(code, "1,4,5");
+}
+
+#[test]
+fn test_conditional_callee() {
+    let code = r#"
+        function double(x) { return x * 2; }
+        function negate(x) { return -x; }
+        var useDouble = false;
+        (useDouble ? double : negate)(5) + (useDouble || double)(3)
+    "#;
+    assert_js_eq(code, "1");
+}
+
+#[test]
+fn test_argument_evaluation_order() {
+    let code = r#"
+        var log = '';
+        var count = 0;
+        function tick(name) {
+            count = count + 1;
+            log = log + name;
+            return count;
+        }
+        function show(a, b, c) {
+            return a + ',' + b + ',' + c;
+        }
+        var result = show(tick('a') > 5 ? tick('x') : tick('b'), false && tick('y'), tick('c') || tick('z'));
+        result + ' ' + log + ' ' + count
+    "#;
+    assert_js_eq(code, "2,false,3 abc 3");
+}
+
+#[test]
+fn test_nested_calls_keep_their_own_locals() {
+    // Each level writes the same local slots while its caller still has
+    // temporaries on the stack, and passes more arguments than declared
+    let code = r#"
+        function lvl3(x) {
+            var p = x * 100, q = p + 1;
+            p = p + q;
+            return p;
+        }
+        function lvl2(x) {
+            var p = x * 10, q = p + 2;
+            var r = [p, lvl3(p, 'extra', 'args'), q];
+            p = p + 1;
+            return r.join('/') + '|' + p + '|' + q;
+        }
+        function lvl1(x) {
+            var p = x, q = x + 3;
+            var s = '<' + lvl2(x, p, q) + '>' + (p + q) + ',' + lvl3(q, 9);
+            q = q * 2;
+            return s + ':' + p + ':' + q;
+        }
+        [lvl1(1), lvl1(2)].join(' ')
+    "#;
+    assert_js_eq(code, "<10/2001/12|11|12>5,801:1:8 <20/4001/22|21|22>7,1001:2:10");
+}
+
+#[test]
+fn test_closure_shares_captured_variables() {
+    // Writes on either side of a capture are seen by the other, whenever
+    // they happen
+    let code = r#"
+        function f() {
+            var count = 0;
+            var read = function () { return count; };
+            var bump = function () { count = count + 10; };
+            count = 1;
+            var before = read();
+            bump();
+            count++;
+            return [before, read(), count].join();
+        }
+        f()
+    "#;
+    assert_js_eq(code, "1,12,12");
+    assert_js_eq(
+        "function f() { var fns = []; for (var i = 0; i < 3; i++) fns.push(function () { return i }); return fns.map(function (g) { return g() }).join() } f()",
+        "3,3,3",
+    );
+}
+
+#[test]
+fn test_closures_survive_gc_stress() {
+    // Closures, their captured variables and the objects they return sit on
+    // the VM stacks and in frames while collections run between instructions
+    let code = r#"
+        function makeAdder(n) {
+            var label = "add" + n;
+            return function (x) { return { label: label, value: x + n }; };
+        }
+        var adders = [];
+        for (var i = 0; i < 40; i++) adders.push(makeAdder(i));
+        var total = 0, names = "";
+        for (var round = 0; round < 5; round++) {
+            for (var j = 0; j < adders.length; j++) {
+                var r = adders[j](round * 1.5);
+                total = total + r.value;
+                if (round === 4 && j % 10 === 0) names = names + r.label + ",";
+            }
+        }
+        var compose = function (f, g) { return function (x) { return f(g(x)); }; };
+        var inc = function (x) { return x + 1; }, dbl = function (x) { return [x * 2][0]; };
+        var h = compose(inc, compose(dbl, inc));
+        names + total + ":" + h(3) + ":" + [1, 2, 3].map(function (v) { return "v" + v; }).join("")
+    "#;
+    for every in [1, 7] {
+        let mut engine = crabquick::Engine::new(65536);
+        engine.set_gc_stress_interval(every);
+        let result = engine.eval_as_string(code).unwrap();
+        assert_eq!(result, "add0,add10,add20,add30,4500:9:v1v2v3", "collecting every {} allocations", every);
+        assert!(engine.memory_stats().gc_count > 100, "collecting every {} allocations", every);
+    }
+}
+
+#[test]
+fn test_call_memory_does_not_grow_with_call_count() {
+    // A call reuses its callee's parsed tables and reads nested functions
+    // in place, so running a function allocates nothing on the heap
+    let mut engine = crabquick::Engine::new(1 << 20);
+    engine.eval(r#"
+        function outer(n) {
+            if (n < 0) {
+                return function inner() { return function () { return n; }; };
+            }
+            return n + 1;
+        }
+        function fib(n) { return n < 2 ? n : fib(n - 1) + fib(n - 2); }
+        function run(calls) {
+            var t = 0;
+            for (var i = 0; i < calls; i++) t = outer(t);
+            return t + fib(calls / 100 + 10);
+        }
+    "#).unwrap();
+
+    let mut allocated = |source: &str| {
+        let before = engine.memory_stats();
+        engine.eval(source).unwrap();
+        let after = engine.memory_stats();
+        assert_eq!(after.gc_count, before.gc_count, "{}", source);
+        after.allocated_since_gc - before.allocated_since_gc
+    };
+    // Both counts are constant pool entries, so the two scripts compile
+    // to units of the same size
+    let few = allocated("run(300)");
+    let many = allocated("run(900)");
+    assert_eq!(few, many);
+}
+
+#[test]
+fn test_constructors_link_instances_to_prototype() {
+    let code = r#"
+        function Point(x, y) { this.x = x; this.y = y; }
+        Point.prototype.sum = function () { return this.x + this.y; };
+        var p = new Point(2, 3);
+        [p.sum(), p.constructor === Point, Object.getPrototypeOf(p) === Point.prototype, Object.keys(p).join('')].join()
+    "#;
+    assert_js_eq(code, "5,true,true,xy");
+    // Methods added later reach existing instances, and each constructor
+    // has its own prototype
+    let code = r#"
+        var Counter = function () { this.n = 0; };
+        var c = new Counter();
+        Counter.prototype.inc = function () { this.n += 1; return this.n; };
+        function Other() {}
+        c.inc(); c.inc();
+        [c.n, Counter.prototype === Other.prototype, 'inc' in new Other()].join()
+    "#;
+    assert_js_eq(code, "2,false,false");
+    // An assigned prototype replaces the default one
+    assert_js_eq("function F() {} F.prototype = { hi: function () { return 'hi'; } }; new F().hi()", "hi");
+    // The default prototype is non-enumerable, and arrows have none
+    assert_js_eq("function G() {} [Object.keys(G).length, Object.keys(G.prototype).length, (() => 1).prototype].join()", "0,0,");
+}
+
+#[test]
+fn test_function_names() {
+    assert_js_eq("function foo() {} foo.name", "foo");
+    assert_js_eq("var f = function bar() {}; f.name + ',' + f['name']", "bar,bar");
+    assert_js_eq("function outer() { function inner() {} return inner; } outer().name", "inner");
+    assert_js_eq("[Math.max.name, parseInt.name, [].push.name, TypeError.name, Object.keys.name].join()", "max,parseInt,push,TypeError,keys");
+    // Anonymous functions have an empty name
+    assert_js_eq("[(function () {}).name, (() => 1).name].join() === ','", "true");
+    // Read-only and non-enumerable
+    assert_js_eq("function g() {} g.name = 'other'; g.name + ',' + Object.keys(g).length", "g,0");
+}
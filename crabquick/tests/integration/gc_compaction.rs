@@ -0,0 +1,86 @@
+//! Globals surviving compaction between evals
+//!
+//! The collector slides live blocks down over dead ones, so an array that was
+//! allocated after garbage moves, along with its property table, its dense
+//! storage and the boxed numbers and strings inside it. The global table, the
+//! array's own references and host `Persistent` handles all have to follow.
+
+#![cfg(test)]
+
+use crabquick::{Engine, JSValue};
+
+fn eval(engine: &mut Engine, code: &str) -> String {
+    engine.eval_as_string(code).unwrap_or_else(|e| panic!("{code}: {e}"))
+}
+
+#[test]
+fn test_global_array_survives_compaction() {
+    let mut engine = Engine::new(Engine::MIN_HEAP_SIZE + 65536);
+
+    // Garbage first, so the array sits above blocks the collector will free
+    engine
+        .eval(
+            "var junk = []; for (var i = 0; i < 150; i++) junk.push([i + 0.25, 'j' + i]);
+             var g_data = []; for (var i = 0; i < 300; i++) g_data.push(i % 3 ? i + 0.5 : 'e' + i);
+             g_data.tag = 'kept'; junk = null;",
+        )
+        .unwrap();
+    let array = engine.eval("g_data").unwrap();
+    let handle = engine.persist(array);
+
+    // Each round leaves garbage behind, and a forced collection compacts it
+    for round in 0..4 {
+        engine
+            .eval(&format!("var tmp = []; for (var i = 0; i < 150; i++) tmp.push([i, 'r{round}-' + i]); tmp = null;"))
+            .unwrap();
+        let stats = engine.gc();
+        assert!(stats.objects_freed > 0, "round {round} freed nothing");
+        assert!(stats.bytes_after < stats.bytes_before, "round {round} didn't compact");
+    }
+
+    assert_eq!(eval(&mut engine, "g_data.length"), "300");
+    assert_eq!(eval(&mut engine, "g_data[0] + ',' + g_data[1] + ',' + g_data[297] + ',' + g_data[299] + ',' + g_data.tag"), "e0,1.5,e297,299.5,kept");
+    let expected: f64 = (0..300).filter(|i| i % 3 != 0).map(|i| f64::from(i) + 0.5).sum();
+    assert_eq!(
+        eval(&mut engine, "var sum = 0, strings = 0; for (var v of g_data) { if (typeof v === 'string') strings++; else sum += v; } sum + ',' + strings"),
+        format!("{expected},100")
+    );
+    assert_eq!(eval(&mut engine, "g_data.every(function (v, i) { return v === (i % 3 ? i + 0.5 : 'e' + i) })"), "true");
+
+    // The host's handle still names the same array
+    let held = engine.persistent_value(&handle).unwrap();
+    let same = engine.eval("(function (a) { return a === g_data && a.length })").unwrap();
+    assert_eq!(engine.call_function(same, &[held]).unwrap().to_int(), Some(300));
+
+    // The array keeps working after it moved
+    assert_eq!(eval(&mut engine, "g_data.push('last'); g_data.length + ':' + g_data[300]"), "301:last");
+}
+
+#[test]
+fn test_global_array_survives_automatic_collections() {
+    let mut engine = Engine::new(Engine::MIN_HEAP_SIZE + 32768);
+    engine.eval("var g_data = []; for (var i = 0; i < 100; i++) g_data.push([i, 'n' + i]);").unwrap();
+    let collections = engine.memory_stats().gc_count;
+
+    // Enough churn that the VM collects on its own while scripts run
+    for round in 0..8 {
+        engine.eval(&format!("for (var i = 0; i < 200; i++) var t = ['x{round}' + i, [i + 0.5]];")).unwrap();
+    }
+    assert!(engine.memory_stats().gc_count > collections, "scripts never collected");
+
+    assert_eq!(eval(&mut engine, "g_data.length"), "100");
+    assert_eq!(eval(&mut engine, "g_data.reduce(function (a, o) { return a + o[0] }, 0)"), "4950");
+    assert_eq!(engine.eval("g_data[42][0]").unwrap(), JSValue::from_int(42));
+    assert_eq!(eval(&mut engine, "g_data[99][1]"), "n99");
+}
+
+#[test]
+fn test_function_properties_survive_collections() {
+    let mut engine = Engine::new(Engine::MIN_HEAP_SIZE + 65536);
+    engine.eval("var f = function () { return 1 }; f.meta = { name: 'f', tags: ['a', 'b'] }; f.count = 2.5;").unwrap();
+    for round in 0..3 {
+        engine.eval(&format!("var tmp = []; for (var i = 0; i < 100; i++) tmp.push(['r{round}' + i]); tmp = null;")).unwrap();
+        engine.gc();
+    }
+    assert_eq!(eval(&mut engine, "f.meta.name + f.meta.tags.join('') + f.count + f()"), "fab2.51");
+}
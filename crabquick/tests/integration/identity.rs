@@ -0,0 +1,79 @@
+//! Function identity
+//!
+//! Scripts find and remove callbacks by comparing them: `indexOf(callback)`,
+//! removing a listener by passing the same function again. That only works
+//! if every read of a stored function gives back the same value. Each
+//! evaluation of a function expression makes a new function, but reading
+//! one through a variable, property, array element or copy never does.
+
+#![cfg(test)]
+
+use crate::harness::*;
+
+#[test]
+fn test_function_expressions_are_distinct() {
+    assert_js_false("function mk() { return function () {}; } mk() === mk()");
+    assert_js_false("var a = () => 1, b = () => 1; a === b");
+    assert_js_true("function f() {} f === f");
+}
+
+#[test]
+fn test_property_reads_keep_identity() {
+    let code = r#"
+        function f() {}
+        var o = { f: f, g: function () {}, h: () => 1, m() {} };
+        [o.f === o.f, o.f === f, o.g === o.g, o.h === o.h, o.m === o.m, o['g'] === o.g].join()
+    "#;
+    assert_js_eq(code, "true,true,true,true,true,true");
+    // Round trips through other objects, arrays and copies
+    let code = r#"
+        var h = () => 1;
+        var a = { x: h }, b = {}; b.y = a.x;
+        var list = [h];
+        [
+            b.y === h, list[0] === h, list.slice()[0] === h, [0].concat(list)[1] === h,
+            Object.values(a)[0] === h, Object.assign({}, a).x === h, { ...a }.x === h
+        ].join()
+    "#;
+    assert_js_eq(code, "true,true,true,true,true,true,true");
+    // Inherited methods are the same object on every instance
+    assert_js_true("[].push === [1].push && ''.slice === 'x'.slice && (function () {}).call === Math.max.call");
+}
+
+#[test]
+fn test_bound_function_identity() {
+    let code = r#"
+        function f() { return this.v; }
+        var b = f.bind({ v: 7 });
+        var holder = { b: b }, copy = { x: holder.b };
+        [copy.x === b, [b].indexOf(holder.b), copy.x(), b === f.bind({ v: 7 })].join()
+    "#;
+    assert_js_eq(code, "true,0,7,false");
+}
+
+#[test]
+fn test_listener_registry() {
+    let code = r#"
+        var listeners = [];
+        function on(fn) { if (listeners.indexOf(fn) < 0) listeners.push(fn); }
+        function off(fn) { listeners = listeners.filter(function (l) { return l !== fn; }); }
+        var handlers = { a: function () { return 'a'; }, b: () => 'b' };
+        on(handlers.a); on(handlers.b); on(handlers.a);
+        var before = listeners.length;
+        off(handlers.a);
+        [before, listeners.length, listeners[0]()].join()
+    "#;
+    assert_js_eq(code, "2,1,b");
+}
+
+#[test]
+fn test_function_properties() {
+    // Properties written through one reference are read through any other
+    assert_js_eq("function f() { return 1; } f.x = 2; var g = f; [g.x, f(), 'x' in f, f.hasOwnProperty('x')].join()", "2,1,true,true");
+    assert_js_eq("var g = () => 3; g.tag = 'x'; var o = { g: g }; [o.g === g, o.g(), o.g.tag].join()", "true,3,x");
+    // Each function has its own, and they shadow Function.prototype
+    assert_js_eq("function a() {} function b() {} a.n = 1; b.n = 2; a.call = 5; [a.n, b.n, a.call, typeof b.call].join()", "1,2,5,function");
+    assert_js_eq("var f = function () {}; f.k = 1; f.j = 2; delete f.k; Object.keys(f).join()", "j");
+    // Natives take properties too
+    assert_js_eq("Math.max.tag = 'm'; Math.max.tag + Math.max(1, 2)", "m2");
+}
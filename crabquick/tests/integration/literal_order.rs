@@ -0,0 +1,105 @@
+//! Key order and overwrites while building objects from literals, spread
+//! and Object.assign
+//!
+//! Two orders are in play and must not be confused. Writes happen in
+//! evaluation order (keys, values and spread sources left to right), so the
+//! last writer of a key wins. Enumeration afterwards lists integer keys
+//! ascending, then names in the order each was first created; overwriting
+//! a key never moves it. `{...a, 0: "x", ...b}` therefore takes `b[0]` but
+//! lists `0` first. Expected results are what V8 and SpiderMonkey produce.
+//!
+//! Integer keys are canonical numeric strings up to `JSAtom::MAX_INDEX`
+//! (2^31 - 2); larger ones, which other engines still treat as array
+//! indices, enumerate as names here, so the tests stay below that.
+
+#![cfg(test)]
+
+use crate::harness::*;
+
+#[test]
+fn test_literal_interleaved_and_duplicate_keys() {
+    assert_js_eq(
+        r#"var o = {b: 1, 2: "two", a: 2, 1: "one", 2: "TWO"}; Object.keys(o).join() + "|" + o[2]"#,
+        "1,2,b,a|TWO",
+    );
+    // The same key written as a number, a string and a float
+    assert_js_eq(r#"var o = {1: "a", "1": "b", 1.0: "c"}; Object.keys(o).join() + "|" + o[1]"#, "1|c");
+    // A duplicate name keeps its first position but takes the last value
+    assert_js_eq(r#"var o = {a: 1, b: 2, a: 3}; Object.keys(o).join() + "|" + o.a"#, "a,b|3");
+    // Only canonical numeric strings are integer keys
+    assert_js_eq(
+        r#"Object.keys({"01": 1, 1: 2, "-1": 3, "1.5": 4, 2147483646: 5, 1e21: 6, 0.5: 7}).join()"#,
+        "1,2147483646,01,-1,1.5,1e+21,0.5",
+    );
+}
+
+#[test]
+fn test_spread_overwrites_numeric_keys() {
+    // The later spread wins although `0` was written by the literal first
+    let code = r#"
+        var a = {0: "a0", x: "ax"}, b = {0: "b0", y: "by"};
+        var s = {...a, 0: "x", ...b};
+        Object.keys(s).join() + "|" + s[0] + "," + s.x + "," + s.y
+    "#;
+    assert_js_eq(code, "0,x,y|b0,ax,by");
+    // A literal key after the spread wins over it
+    assert_js_eq(r#"var o = {...{0: "s", k: 1}, 0: "lit"}; Object.keys(o).join() + "|" + o[0]"#, "0,k|lit");
+    // Integer keys from a spread sort in among the literal's own
+    let code = r#"
+        var t = {0: "lit", z: 1, ...{0: "spread", 10: "ten", 9: "nine"}};
+        Object.keys(t).join() + "|" + t[0]
+    "#;
+    assert_js_eq(code, "0,9,10,z|spread");
+    // Overwriting a name from a spread doesn't move it
+    assert_js_eq(r#"var o = {a: 1, 5: "x", b: 2, ...{a: 3, 5: "y"}}; Object.keys(o).join() + "|" + o.a + o[5]"#, "5,a,b|3y");
+    assert_js_eq(r#"var o = {z: 1, ...["p", "q"], 1: "r"}; Object.keys(o).join() + "|" + o[0] + o[1]"#, "0,1,z|pr");
+}
+
+#[test]
+fn test_literal_evaluation_order() {
+    // Computed keys and values run left to right, each key before its value
+    let code = r#"
+        var log = [];
+        function f(x) { log.push(x); return x; }
+        var o = {[f("b")]: f(1), [f(2)]: f(3), a: f(4), [f(0)]: f(5)};
+        log.join() + "|" + Object.keys(o).join()
+    "#;
+    assert_js_eq(code, "b,1,2,3,4,0,5|0,2,b,a");
+    assert_js_eq("var i = 0; var o = {[i++]: i, [i++]: i, 0: 'last'}; o[0] + o[1]", "last2");
+    // A spread reads its source's getters in the source's enumeration order
+    let code = r#"
+        var log = [];
+        var src = {get b() { log.push("b"); return 1; }, get 1() { log.push("1"); return 2; }};
+        var c = {...src};
+        log.join() + "|" + Object.keys(c).join() + "|" + c[1] + c.b
+    "#;
+    assert_js_eq(code, "1,b|1,b|21");
+    assert_js_error("({ get [1]() { return 1; } })");
+}
+
+#[test]
+fn test_assign_overwrites_numeric_keys() {
+    let code = r#"
+        var u = Object.assign({}, {b: 1, 1: "x"}, {1: "y", a: 2});
+        Object.keys(u).join() + "|" + u[1]
+    "#;
+    assert_js_eq(code, "1,b,a|y");
+    // Keys already on the target keep their place
+    let code = r#"
+        var t = Object.assign({a: 0, 3: 0}, {1: 1, a: 1, 3: 3});
+        Object.keys(t).join() + "|" + t.a + "," + t[3]
+    "#;
+    assert_js_eq(code, "1,3,a|1,3");
+}
+
+#[test]
+#[cfg(feature = "builtin-json")]
+fn test_constructed_order_survives_serialization() {
+    let code = r#"
+        var s = {b: 1, ...{0: "a0", x: "ax"}, 0: "x", ...{0: "b0", y: "by"}, 2: "two"};
+        var keys = [];
+        for (var k in s) keys.push(k);
+        keys.join() + "|" + JSON.stringify(s)
+    "#;
+    assert_js_eq(code, r#"0,2,b,x,y|{"0":"b0","2":"two","b":1,"x":"ax","y":"by"}"#);
+}
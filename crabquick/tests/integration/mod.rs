@@ -10,3 +10,14 @@ mod objects;
 mod arrays;
 mod strings;
 mod control_flow;
+mod scripts;
+mod registers;
+mod enumeration;
+mod identity;
+mod nul_bytes;
+mod typed_arrays;
+mod engine_info;
+mod literal_order;
+mod determinism;
+mod cross_eval;
+mod gc_compaction;
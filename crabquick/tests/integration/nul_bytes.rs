@@ -0,0 +1,62 @@
+//! Embedded NUL characters
+//!
+//! Strings are length-delimited all the way through, so a `\0` inside one is
+//! an ordinary character: it counts towards `length`, survives concatenation,
+//! slicing and JSON, and can be split on. Property names keep it too, so
+//! `'a\0b'` and `'a'` are different keys. A NUL outside a string or comment
+//! in the source is a SyntaxError, as for any other stray character.
+
+#![cfg(test)]
+
+use crate::harness::*;
+
+#[test]
+fn test_nul_in_literals() {
+    assert_js_eq("'a\\0b'.length", "3");
+    assert_js_eq("'a\\u0000b'.length", "3");
+    assert_js_eq("'a\\x00b'.charCodeAt(1)", "0");
+    assert_js_eq("`a\\0b${'\\0'}`.length", "4");
+    // A raw NUL inside a string literal in the source
+    assert_js_eq("'a\0b'.length", "3");
+    assert_js_true("'a\0b' === 'a\\u0000b'");
+    assert_js_error("var a = 1;\0");
+}
+
+#[test]
+fn test_nul_through_string_operations() {
+    assert_js_eq("('a\\0' + '\\0b').length", "4");
+    assert_js_eq("'x\\0y\\0z'.split('\\0').join('|')", "x|y|z");
+    assert_js_eq("'x\\0y'.indexOf('y')", "2");
+    assert_js_eq("'x\\0y'.slice(1).charCodeAt(0)", "0");
+    assert_js_true("'a\\0b' !== 'a'");
+    assert_js_true("'a\\0' > 'a'");
+}
+
+#[test]
+fn test_nul_in_property_names() {
+    let code = r#"
+        var o = {};
+        o['a\0b'] = 1;
+        o['a'] = 2;
+        o['a\0'] = 3;
+        [Object.keys(o).length, o['a\0b'], o.a, o['a\0'], 'a\0b' in o].join()
+    "#;
+    assert_js_eq(code, "3,1,2,3,true");
+}
+
+#[test]
+fn test_nul_through_json() {
+    assert_js_eq("JSON.stringify('a\\0b')", "\"a\\u0000b\"");
+    assert_js_true("JSON.parse(JSON.stringify('a\\0b')) === 'a\\0b'");
+    assert_js_true("JSON.parse('\"a\\\\u0000b\"') === 'a\\0b'");
+    let code = r#"
+        var o = JSON.parse(JSON.stringify({ 'k\0': 'v\0' }));
+        o['k\0'] === 'v\0'
+    "#;
+    assert_js_true(code);
+}
+
+#[test]
+fn test_nul_in_results() {
+    assert_js_eq("'a' + '\\0' + 'b'", "a\0b");
+}
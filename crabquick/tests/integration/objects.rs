@@ -61,7 +61,6 @@ fn test_object_nested() {
 }
 
 #[test]
-#[ignore]
 fn test_object_method() {
     let code = r#"
         var obj = {
@@ -75,6 +74,31 @@ fn test_object_method() {
     assert_js_eq(code, "10");
 }
 
+#[test]
+fn test_method_call_this() {
+    assert_js_eq("var o = {x: 42, get: function() { return this.x; }}; o.get()", "42");
+    assert_js_eq("var o = {x: 1}; o['m'] = function() { return this.x; }; o['m']()", "1");
+    assert_js_eq("var a = {x: 1, m: function() { return this.x; }}; var b = {x: 2, m: a.m}; a.m() + b.m() * 10", "21");
+
+    // Arrow functions see the `this` of the method they were made in
+    assert_js_eq("var o = {x: 42, get: function() { var f = () => this.x; return f(); }}; o.get()", "42");
+    assert_js_eq("var o = {x: 7, m: function() { var a = () => () => this; return a()() === this; }}; o.m()", "true");
+    assert_js_eq("var o = {x: 1, m: function() { return [1, 2].map(v => this.x + v).join(); }}; o.m()", "2,3");
+    assert_js_eq("var o = {m: function() { return () => this; }}; var f = o.m(); var p = {f: f}; p.f() === o", "true");
+
+    // Plain calls get undefined, even for functions read off an object
+    assert_js_eq("var o = {m: function() { return this; }}; var f = o.m; f() === undefined", "true");
+    assert_js_eq("var o = {m: function() { return (function() { return this; })(); }}; o.m() === undefined", "true");
+}
+
+#[test]
+fn test_colliding_property_names_stay_distinct() {
+    // "aa" and "b@" have the same djb2 hash
+    assert_js_eq("var o = {aa: 1}; o['b@'] = 2; o.aa + ':' + o['b@']", "1:2");
+    assert_js_eq("var o = {aa: 1}; o['b@'] === undefined", "true");
+    assert_js_eq("var o = {aa: 1}; o.hasOwnProperty('b@')", "false");
+}
+
 #[test]
 #[ignore]
 fn test_empty_object() {
@@ -87,17 +111,26 @@ fn test_empty_object() {
 }
 
 #[test]
-#[ignore]
 fn test_object_keys() {
     let code = r#"
         var obj = { a: 1, b: 2, c: 3 };
         Object.keys(obj).length
     "#;
     assert_js_eq(code, "3");
+    assert_js_eq("Object.entries({a: 1, b: 2})[1][0]", "b");
+    assert_js_eq("var e = Object.entries({a: 1, b: 2}); [e.length, Array.isArray(e[0]), e[0][1]].join()", "2,true,1");
+    assert_js_eq("Object.values({b: 1, 2: 'x', 1: 'y', a: 2}).join()", "y,x,1,2");
+    assert_js_eq(
+        "var o = {v: 1}; Object.defineProperty(o, 'h', {value: 2, enumerable: false}); [Object.keys(o).join(), Object.values(o).join()].join('|')",
+        "v|1",
+    );
+    // Primitives act as the objects they box to
+    assert_js_eq("[Object.keys('ab').join(), Object.values('ab').join(), Object.entries('hi')[1].join()].join('|')", "0,1|a,b|1,i");
+    assert_js_eq("[Object.keys(5).length, Object.values(true).length].join()", "0,0");
+    assert_js_error("Object.values(null)");
 }
 
 #[test]
-#[ignore]
 fn test_object_prototype() {
     let code = r#"
         var proto = { x: 10 };
@@ -106,3 +139,224 @@ fn test_object_prototype() {
     "#;
     assert_js_eq(code, "10");
 }
+
+#[test]
+fn test_prototype_chain_lookup() {
+    // Inherited methods run with the receiver as `this`, however deep
+    let code = r#"
+        var base = { greet: function () { return 'hi ' + this.name; }, kind: 'base' };
+        var mid = Object.create(base);
+        var leaf = Object.create(mid); leaf.name = 'leaf';
+        [leaf.greet(), leaf['kind'], Object.getPrototypeOf(leaf) === mid, Object.getPrototypeOf(mid) === base].join()
+    "#;
+    assert_js_eq(code, "hi leaf,base,true,true");
+    // Writes go to the receiver and shadow the inherited value
+    let code = r#"
+        var base = { kind: 'base' };
+        var o = Object.create(base);
+        o.kind = 'own';
+        var p = Object.create(base);
+        [o.kind, base.kind, p.kind, o.hasOwnProperty('kind'), p.hasOwnProperty('kind'), 'kind' in p].join()
+    "#;
+    assert_js_eq(code, "own,base,base,true,false,true");
+    assert_js_eq("var a = [1]; a.push(2); [a.length, a.hasOwnProperty('push'), ({}).hasOwnProperty('toString')].join()", "2,false,false");
+    assert_js_eq("var o = Object.create(null); [o.toString, Object.getPrototypeOf(o)].join()", ",");
+}
+
+#[test]
+fn test_prototype_cycles_are_refused() {
+    assert_js_error("var a = {}; var b = Object.create(a); Object.setPrototypeOf(a, b)");
+    assert_js_error("var a = {}; Object.setPrototypeOf(a, a)");
+    assert_js_error("Object.setPrototypeOf({}, 5)");
+    assert_js_eq("var a = {}; Object.setPrototypeOf(a, {x: 1}); var r = [a.x]; Object.setPrototypeOf(a, null); r.push(a.x); r.join()", "1,");
+}
+
+#[test]
+fn test_prototype_chain_length_cap() {
+    // The default cap is 128 links; `{}` already has one, to Object.prototype
+    let code = r#"
+        var base = { x: 1 };
+        var o = base;
+        for (var i = 0; i < 127; i++) o = Object.create(o);
+        var r = [o.x, o.missing];
+        try { Object.create(o); } catch (e) { r.push(e.name); }
+        try { Object.setPrototypeOf({}, o); } catch (e) { r.push(e.name); }
+        Object.setPrototypeOf(base, null);
+        r.push(Object.create(o).x);
+        r.join()
+    "#;
+    assert_js_eq(code, "1,,RangeError,RangeError,1");
+}
+
+#[test]
+fn test_optional_chaining() {
+    assert_js_eq("var o = null; o?.a?.b", "undefined");
+    assert_js_eq("var o = { a: { b: 3 } }; o?.a?.b", "3");
+    assert_js_eq("var o = { a: null }; o.a?.b.c", "undefined");
+    assert_js_eq("var a = [1, 2]; var none; a?.[1] + ',' + none?.[1]", "2,undefined");
+}
+
+#[test]
+fn test_optional_call() {
+    let code = r#"
+        var o = { twice: function (x) { return x * 2; } };
+        var f = null;
+        var parts = [o.twice?.(4), o.missing?.(4), f?.(1), o?.twice(1)];
+        parts.join()
+    "#;
+    assert_js_eq(code, "8,,,2");
+}
+
+#[test]
+fn test_optional_chain_short_circuits() {
+    let code = r#"
+        var calls = 0;
+        function next() { calls++; return "a"; }
+        var o = null;
+        o?.a[next()];
+        o?.[next()](next());
+        calls
+    "#;
+    assert_js_eq(code, "0");
+}
+
+#[test]
+fn test_logical_assignment_members() {
+    let code = r#"
+        var o = { p: 0, q: 2 };
+        o.p ||= 6;
+        o.q &&= 5;
+        o.r ??= 7;
+        o.r ??= 8;
+        var a = [0, 1];
+        a[0] ||= 9;
+        a[1] ??= 9;
+        [o.p, o.q, o.r, a[0], a[1]].join()
+    "#;
+    assert_js_eq(code, "6,5,7,9,1");
+}
+
+#[test]
+fn test_object_keys_of_null_throws() {
+    assert_js_error("Object.keys(null)");
+    assert_js_error("Object.entries(undefined)");
+}
+
+#[test]
+fn test_property_access_on_nullish_throws_type_error() {
+    assert_js_error("var o; o.x");
+    assert_js_error("null[0]");
+    assert_js_error("var o = null; o.x = 1");
+    let code = r#"
+        var r;
+        try { undefined.x; } catch (e) { r = [e.name, e.message, e.stack].join('|'); }
+        r
+    "#;
+    assert_js_eq(code, "TypeError|cannot read property of undefined|TypeError: cannot read property of undefined");
+}
+
+#[test]
+fn test_error_prototype_chain() {
+    let code = r#"
+        var e;
+        try { null.x = 1; } catch (err) { e = err; }
+        var proto = Object.getPrototypeOf(e);
+        var base = Object.getPrototypeOf(new Error("x"));
+        [
+            proto === Object.getPrototypeOf(new TypeError("y")),
+            Object.getPrototypeOf(proto) === base,
+            Object.getPrototypeOf(base) === Object.prototype
+        ].join()
+    "#;
+    assert_js_eq(code, "true,true,true");
+}
+
+#[test]
+fn test_delete_property() {
+    assert_js_eq("var o = {a: 1}; delete o.a; 'a' in o", "false");
+    assert_js_eq("var o = {a: 1, b: 2}; [delete o['a'], delete o.zzz, o.a, o.b, 'b' in o].join()", "true,true,,2,true");
+    assert_js_eq("var o = {a: 1, b: 2, c: 3}; delete o.b; var k = ''; for (var p in o) k += p; k", "ac");
+    // Deleting from a table big enough to be hashed keeps the other keys reachable
+    let code = r#"
+        var o = {};
+        for (var i = 0; i < 20; i++) o['k' + i] = i;
+        delete o.k3;
+        delete o['k10'];
+        var n = 0;
+        for (var p in o) n++;
+        [n, o.k3, o.k4, o.k11, o.k19, 'k10' in o].join()
+    "#;
+    assert_js_eq(code, "18,,4,11,19,false");
+    // Variables and array lengths stay
+    assert_js_eq("var x = 1; [delete x, x, delete [1].length, delete 5].join()", "false,1,false,true");
+    assert_js_eq("var q = null; delete q?.a", "true");
+    assert_js_error("delete null.a");
+}
+
+#[test]
+fn test_in_operator() {
+    assert_js_eq("['a' in {a: undefined}, 'toString' in {}, 'b' in {a: 1}].join()", "true,true,false");
+    assert_js_eq("[0 in [7], 1 in [7], 'length' in [7]].join()", "true,false,true");
+    assert_js_error("'a' in 5");
+}
+
+#[test]
+fn test_object_assign_accessors() {
+    // Source getters run once per copy; target setters see the copied value
+    let code = r#"
+        var n = 0, seen = [];
+        var src = { get a() { n++; return 'A'; } };
+        var dst = { set a(v) { seen.push(v); } };
+        Object.assign(dst, src);
+        [n, seen.join()].join()
+    "#;
+    assert_js_eq(code, "1,A");
+    assert_js_eq(
+        "var log = ''; var p = { set x(v) { log += v; } }; var t = Object.create(p); Object.assign(t, {x: 7}); [log, t.hasOwnProperty('x')].join()",
+        "7,false",
+    );
+    assert_js_error("Object.assign({ get a() { return 1; } }, {a: 2})");
+    assert_js_error("var t = {}; Object.defineProperty(t, 'a', {value: 1, writable: false}); Object.assign(t, {a: 2})");
+    // Later sources win; nullish and primitive sources are skipped
+    assert_js_eq("var t = Object.assign({}, {a: 1, c: 1}, null, {b: 2}, undefined, 5, {a: 3, c: 3}); [t.a, t.b, t.c].join()", "3,2,3");
+}
+
+#[test]
+fn test_object_spread() {
+    assert_js_eq("var p = {inherited: 1}; var o = Object.create(p); o.own = 2; var c = {...o}; ['own' in c, 'inherited' in c].join()", "true,false");
+    assert_js_eq("var c = {a: 1, ...{a: 2, b: 2}, b: 3}; [c.a, c.b].join()", "2,3");
+    assert_js_eq("var c = {...null, ...undefined, ...5, x: 1}; Object.keys(c).join()", "x");
+    assert_js_eq("var c = {...[1, 2]}; [c[0], c[1], 'length' in c].join()", "1,2,false");
+    // A getter is read each time it's spread and copied as a plain value
+    let code = r#"
+        var n = 0;
+        var src = { get g() { return ++n; } };
+        var a = {...src}, b = {...src};
+        [a.g, b.g, a.g, n].join()
+    "#;
+    assert_js_eq(code, "1,2,1,2");
+    assert_js_eq("function f(o) { return o.x + o.y; } f({...{x: 1}, y: 2})", "3");
+}
+
+#[test]
+fn test_objects_convert_to_primitives() {
+    assert_js_eq("'x=' + {a: 1}", "x=[object Object]");
+    assert_js_eq("[1, 2] + ''", "1,2");
+    assert_js_eq("[[1, 2], [], [null, 3]] + '!'", "1,2,,,3!");
+    assert_js_eq("`list: ${[1, 2]} obj: ${{}}`", "list: 1,2 obj: [object Object]");
+    assert_js_eq("[] + []", "");
+    assert_js_eq("[5] + 1", "51");
+    assert_js_eq("var t = Object.prototype.toString; [t.call([]), t.call(Math.max), t.call({})].join()", "[object Array],[object Function],[object Object]");
+
+    // `+` and `==` try valueOf first, then toString
+    assert_js_eq("var o = {valueOf: function () { return 41 }, toString: function () { return 'o' }}; [o + 1, 'v' + o, o == 41].join()", "42,v41,true");
+    assert_js_eq("var o = {toString: function () { return 'T' }}; [o + '!', o == 'T', [o, o].join()].join()", "T!,true,T,T");
+    assert_js_eq("var calls = 0; var o = {toString: function () { return ++calls }}; [o == null, o == o, calls].join()", "false,true,0");
+
+    // Methods that throw, or give no primitive, make the conversion throw
+    assert_js_eq("var r; try { '' + {toString: function () { throw new Error('boom') }} } catch (e) { r = e.message } r", "boom");
+    assert_js_eq(
+        "var r; try { ({toString: function () { return {} }}) + 1 } catch (e) { r = e.name + ': ' + e.message } r",
+        "TypeError: Cannot convert object to primitive value",
+    );
+}
@@ -0,0 +1,84 @@
+//! Integration tests for 32-bit register manipulation
+//!
+//! Device scripts treat numbers as u32 registers: masking, shifting,
+//! packing bytes and printing in hex. Expected values are computed in Rust.
+
+#![cfg(test)]
+
+use crate::harness::*;
+
+const STATUS: u32 = 0xDEAD_BEEF;
+
+#[test]
+fn test_register_mask() {
+    assert_js_eq("0xDEADBEEF & 0xFF000000", &((STATUS & 0xFF00_0000) as i32).to_string());
+    assert_js_eq("(0xDEADBEEF & 0xFF000000) >>> 0", &(STATUS & 0xFF00_0000).to_string());
+    assert_js_eq("0xDEADBEEF & 0xFFFF", &(STATUS & 0xFFFF).to_string());
+    assert_js_eq("(0xDEADBEEF | 0x10) >>> 0", &(STATUS | 0x10).to_string());
+    assert_js_eq("(0xDEADBEEF ^ 0xFFFFFFFF) >>> 0", &(!STATUS).to_string());
+    assert_js_eq("var r = 0xFFFFFFFF; r &= ~(1 << 4); r >>> 0", &(u32::MAX & !(1 << 4)).to_string());
+    assert_js_eq("(0xDEADBEEF & (1 << 31)) !== 0", "true");
+}
+
+#[test]
+fn test_register_shift() {
+    assert_js_eq("1 << 31", &(1i32 << 31).to_string());
+    assert_js_eq("(1 << 31) >>> 0", &(1u32 << 31).to_string());
+    assert_js_eq("0xDEADBEEF >> 24", &((STATUS as i32) >> 24).to_string());
+    assert_js_eq("0xDEADBEEF >>> 24", &(STATUS >> 24).to_string());
+    assert_js_eq("-1 >>> 0", &u32::MAX.to_string());
+    assert_js_eq("~0 >>> 0", &u32::MAX.to_string());
+    assert_js_eq("0x80000000 | 0", &i32::MIN.to_string());
+    assert_js_eq("4294967296 | 0", "0");
+    // Shift counts are taken modulo 32
+    assert_js_eq("1 << 32", "1");
+    assert_js_eq("0xDEADBEEF >>> 36", &(STATUS >> 4).to_string());
+}
+
+#[test]
+fn test_register_pack() {
+    let packed = (0xDEu32 << 24) | (0xAD << 16) | (0xBE << 8) | 0xEF;
+    assert_js_eq("(0xDE << 24) | (0xAD << 16) | (0xBE << 8) | 0xEF", &(packed as i32).to_string());
+    assert_js_eq("((0xDE << 24) | (0xAD << 16) | (0xBE << 8) | 0xEF) >>> 0", &packed.to_string());
+
+    let code = r#"
+        function pack(a, b, c, d) {
+            return ((a << 24) | (b << 16) | (c << 8) | d) >>> 0;
+        }
+        pack(0xCA, 0xFE, 0xBA, 0xBE)
+    "#;
+    assert_js_eq(code, &0xCAFE_BABEu32.to_string());
+}
+
+#[test]
+fn test_register_unpack() {
+    let code = r#"
+        function unpack(value) {
+            return [(value >>> 24) & 0xFF, (value >>> 16) & 0xFF, (value >>> 8) & 0xFF, value & 0xFF];
+        }
+        unpack(0xDEADBEEF).join(",")
+    "#;
+    let bytes = STATUS.to_be_bytes().map(|b| b.to_string()).join(",");
+    assert_js_eq(code, &bytes);
+
+    // Bit fields: bits 4..11 of the status register
+    assert_js_eq("(0xDEADBEEF >>> 4) & 0xFF", &((STATUS >> 4) & 0xFF).to_string());
+}
+
+#[test]
+fn test_register_hex_format() {
+    assert_js_eq("(0xDEADBEEF >>> 0).toString(16)", &format!("{:x}", STATUS));
+    assert_js_eq("(-1 >>> 0).toString(16)", &format!("{:x}", u32::MAX));
+    assert_js_eq("((0xDE << 24) >>> 0).toString(16)", &format!("{:x}", 0xDEu32 << 24));
+    assert_js_eq("(0xDEADBEEF & 0xF0F0F0F0).toString(16)", &format!("-{:x}", (STATUS & 0xF0F0_F0F0).wrapping_neg()));
+    assert_js_eq("(0xA5 >>> 0).toString(2)", &format!("{:b}", 0xA5));
+}
+
+#[test]
+fn test_register_parse_hex() {
+    assert_js_eq("parseInt('deadbeef', 16)", &STATUS.to_string());
+    assert_js_eq("parseInt('0xDEADBEEF')", &STATUS.to_string());
+    assert_js_eq("'0xff' | 0", "255");
+    assert_js_eq("'0xDEADBEEF' >>> 0", &STATUS.to_string());
+    assert_js_eq("parseInt((0xCAFEBABE >>> 0).toString(16), 16) === 0xCAFEBABE", "true");
+}
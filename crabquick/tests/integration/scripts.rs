@@ -0,0 +1,114 @@
+//! Integration tests for running loaded scripts
+
+#![cfg(test)]
+
+use crabquick::{Engine, LoadedScript};
+use std::sync::OnceLock;
+use std::thread;
+
+/// One compiled script shared by every test engine, as a ROM image would be
+fn shared_script() -> &'static [u8] {
+    static BYTECODE: OnceLock<Vec<u8>> = OnceLock::new();
+    BYTECODE.get_or_init(|| {
+        let mut source = String::new();
+        for i in 0..16 {
+            source.push_str(&format!("function step{i}(x) {{\n    var y = x;\n"));
+            for j in 0..15 {
+                let limit = j * 100;
+                source.push_str(&format!("    if (y > {limit}) {{ y = y - {i}; }} else {{ y = y * 2 + {j}; }}\n"));
+            }
+            source.push_str("    return y;\n}\n");
+        }
+        source.push_str("var total = 0;\n");
+        for i in 0..16 {
+            source.push_str(&format!("total = total + step{i}({i});\n"));
+        }
+        source.push_str("total");
+        LoadedScript::compile(&source).unwrap().bytecode().to_vec()
+    })
+}
+
+#[test]
+fn test_engines_share_borrowed_script() {
+    let bytes = shared_script();
+    assert!(bytes.len() >= 8 * 1024, "script is only {} bytes", bytes.len());
+
+    let expected = {
+        let mut engine = Engine::new(262144);
+        engine.run_as_string(&LoadedScript::owned(bytes.to_vec())).unwrap()
+    };
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            thread::spawn(move || {
+                let mut engine = Engine::new(262144);
+                let result = engine.run_as_string(&LoadedScript::borrowed(bytes)).unwrap();
+                (result, engine.memory_stats().heap_used)
+            })
+        })
+        .collect();
+
+    let mut owned = Engine::new(262144);
+    owned.run(&LoadedScript::owned(bytes.to_vec())).unwrap();
+    let owned_used = owned.memory_stats().heap_used;
+
+    for handle in handles {
+        let (result, borrowed_used) = handle.join().unwrap();
+        assert_eq!(result, expected);
+        // Each engine saves at least the script's size over copying it in
+        assert!(owned_used - borrowed_used >= bytes.len());
+    }
+}
+
+#[test]
+fn test_borrowed_closures_outlive_run() {
+    let mut engine = Engine::new(262144);
+    engine.run(&LoadedScript::borrowed(shared_script())).unwrap();
+
+    // Functions defined by the script still run from the borrowed bytes
+    let expected = {
+        let mut engine = Engine::new(262144);
+        engine.run(&LoadedScript::owned(shared_script().to_vec())).unwrap();
+        engine.eval_as_string("step3(2)").unwrap()
+    };
+    assert_eq!(engine.eval_as_string("step3(2)").unwrap(), expected);
+}
+
+#[test]
+fn test_runtime_errors_report_their_line() {
+    let source = "var total = 0;\n\
+                  function check(n) {\n\
+                  \x20   if (n > 2) {\n\
+                  \x20       throw new RangeError('too big: ' + n);\n\
+                  \x20   }\n\
+                  \x20   return n;\n\
+                  }\n\
+                  for (var i = 0; i < 5; i++) {\n\
+                  \x20   total += check(i);\n\
+                  }\n";
+
+    let mut engine = Engine::new(65536);
+    let err = engine.eval(source).unwrap_err();
+    assert_eq!(err.to_string(), "RangeError: too big: 3 at line 4");
+    assert!(matches!(err, crabquick::EngineError::Runtime { line: 4, .. }));
+
+    // Borrowed bytecode carries the same table
+    let script = LoadedScript::compile(source).unwrap();
+    let bytes: &'static [u8] = Box::leak(script.bytecode().into());
+    let err = Engine::new(65536).run(&LoadedScript::borrowed(bytes)).unwrap_err();
+    assert_eq!(err.to_string(), "RangeError: too big: 3 at line 4");
+
+    // Errors raised by the VM, caught, keep the line in lineNumber
+    let text = engine.eval_as_string(
+        "var o = {};\n\nvar line;\ntry {\n  o.missing.x;\n} catch (e) {\n  line = e.lineNumber;\n}\nline"
+    ).unwrap();
+    assert_eq!(text, "5");
+
+    // A rethrow keeps the line of the original throw
+    let err = engine.eval("try {\n  null.x;\n} catch (e) {\n\n  throw e;\n}").unwrap_err();
+    assert!(err.to_string().ends_with(" at line 2"), "{}", err);
+
+    // Thrown values other than errors have no line
+    let err = engine.eval("\n\nthrow 'plain';").unwrap_err();
+    assert_eq!(err.to_string(), "plain");
+}
@@ -5,13 +5,11 @@
 use crate::harness::*;
 
 #[test]
-#[ignore]
 fn test_string_literal() {
     assert_js_eq("\"hello\"", "hello");
 }
 
 #[test]
-#[ignore]
 fn test_string_length() {
     let code = r#"
         var s = "hello";
@@ -21,13 +19,20 @@ fn test_string_length() {
 }
 
 #[test]
-#[ignore]
 fn test_string_concat() {
     assert_js_eq("\"hello\" + \" \" + \"world\"", "hello world");
 }
 
 #[test]
-#[ignore]
+fn test_string_function() {
+    assert_js_eq("[String(), String(null), String(undefined), String(true), String(1.5)].join('|')", "|null|undefined|true|1.5");
+    assert_js_eq("String([1, [2, 3]]) + String({ toString: function () { return 'T' } })", "1,2,3T");
+    assert_js_eq("typeof String(42)", "string");
+    assert_js_true("String('abc') === 'abc'");
+    assert_js_error("new String('abc')");
+}
+
+#[test]
 fn test_string_char_at() {
     let code = r#"
         var s = "hello";
@@ -37,7 +42,6 @@ fn test_string_char_at() {
 }
 
 #[test]
-#[ignore]
 fn test_string_char_code_at() {
     let code = r#"
         var s = "A";
@@ -47,7 +51,6 @@ fn test_string_char_code_at() {
 }
 
 #[test]
-#[ignore]
 fn test_string_slice() {
     let code = r#"
         var s = "Hello, World!";
@@ -57,7 +60,6 @@ fn test_string_slice() {
 }
 
 #[test]
-#[ignore]
 fn test_string_substring() {
     let code = r#"
         var s = "JavaScript";
@@ -67,7 +69,6 @@ fn test_string_substring() {
 }
 
 #[test]
-#[ignore]
 fn test_string_to_upper_case() {
     let code = r#"
         var s = "hello";
@@ -77,7 +78,6 @@ fn test_string_to_upper_case() {
 }
 
 #[test]
-#[ignore]
 fn test_string_to_lower_case() {
     let code = r#"
         var s = "WORLD";
@@ -87,7 +87,6 @@ fn test_string_to_lower_case() {
 }
 
 #[test]
-#[ignore]
 fn test_string_index_of() {
     let code = r#"
         var s = "Hello, World!";
@@ -97,7 +96,6 @@ fn test_string_index_of() {
 }
 
 #[test]
-#[ignore]
 fn test_string_index_of_not_found() {
     let code = r#"
         var s = "hello";
@@ -107,7 +105,6 @@ fn test_string_index_of_not_found() {
 }
 
 #[test]
-#[ignore]
 fn test_string_split() {
     let code = r#"
         var s = "a,b,c";
@@ -118,7 +115,6 @@ fn test_string_split() {
 }
 
 #[test]
-#[ignore]
 fn test_string_trim() {
     let code = r#"
         var s = "  hello  ";
@@ -128,7 +124,6 @@ fn test_string_trim() {
 }
 
 #[test]
-#[ignore]
 fn test_string_replace() {
     let code = r#"
         var s = "hello world";
@@ -138,7 +133,6 @@ fn test_string_replace() {
 }
 
 #[test]
-#[ignore]
 fn test_empty_string() {
     let code = r#"
         var s = "";
@@ -148,8 +142,163 @@ fn test_empty_string() {
 }
 
 #[test]
-#[ignore]
 fn test_string_comparison() {
     assert_js_true("\"hello\" === \"hello\"");
     assert_js_false("\"hello\" === \"world\"");
 }
+
+#[test]
+fn test_string_slice_negative() {
+    assert_js_eq(r#""hello".slice(-3)"#, "llo");
+    assert_js_eq(r#""hello".slice(1, -1)"#, "ell");
+    assert_js_eq(r#""hello".slice(-1, 1)"#, "");
+}
+
+#[test]
+fn test_string_multibyte_indices() {
+    // Indices count characters, never splitting a multi-byte one
+    assert_js_eq(r#""éa".slice(1)"#, "a");
+    assert_js_eq(r#""héllo".length"#, "5");
+    assert_js_eq(r#""aé€".charAt(2)"#, "€");
+    assert_js_eq(r#""ééx".indexOf("x")"#, "2");
+    assert_js_eq(r#""x€y€".substring(1, 3)"#, "€y");
+}
+
+#[test]
+fn test_string_index_access() {
+    assert_js_eq("'abc'[1]", "b");
+    assert_js_eq("var s = 'abc', i = 2; s[i] + s['0']", "ca");
+    assert_js_true("'abc'[3] === undefined && 'abc'[-1] === undefined");
+    // Like charAt, indices count characters rather than bytes
+    assert_js_eq(r#""aé€x"[2]"#, "€");
+    assert_js_eq(r#""aé€x"[3]"#, "x");
+    assert_js_eq(r#"var s = "ëx", out = ''; for (var i = 0; i < s.length; i++) out += s[i] + '|'; out"#, "ë|x|");
+}
+
+#[test]
+fn test_string_split_to_array() {
+    let code = r#"
+        var parts = "a,b,c".split(",");
+        parts.length + ":" + parts[2]
+    "#;
+    assert_js_eq(code, "3:c");
+    assert_js_eq(r#""abc".split("").length"#, "3");
+    assert_js_eq(r#""abc".split().length"#, "1");
+}
+
+#[test]
+fn test_template_literal() {
+    assert_js_eq("`plain`", "plain");
+    assert_js_eq("var name = 'world'; `hello ${name}!`", "hello world!");
+    assert_js_eq("`${1}${2}`", "12");
+    assert_js_eq("var a = 2, b = 3; `${a} + ${b} = ${a + b}`", "2 + 3 = 5");
+}
+
+#[test]
+fn test_template_literal_expressions() {
+    let code = r#"
+        function greet(who) { return 'hi ' + who; }
+        var o = { n: 4 };
+        `${greet('bob')}, ${o.n > 3 ? `big ${o.n}` : 'small'}, ${ {k: 'v'}.k }`
+    "#;
+    assert_js_eq(code, "hi bob, big 4, v");
+}
+
+#[test]
+fn test_template_literal_multiline_and_escapes() {
+    assert_js_eq("`a\nb`.length", "3");
+    assert_js_eq("`tab\\tdone \\` \\${x}`", "tab\tdone ` ${x}");
+}
+
+#[test]
+fn test_integer_argument_edge_cases() {
+    assert_js_eq(r#""abc".slice(undefined, NaN)"#, "");
+    assert_js_eq(r#""abc".slice(undefined, undefined)"#, "abc");
+    assert_js_eq(r#""abc".slice(-1.5)"#, "c");
+    assert_js_eq(r#""abc".substring(1, Infinity)"#, "bc");
+    assert_js_eq(r#""abc".charAt(Infinity)"#, "");
+    assert_js_eq(r#""abc".charAt(1.9)"#, "b");
+    assert_js_eq(r#""abc".lastIndexOf("c", NaN)"#, "2");
+    assert_js_eq(r#""abc".at(-1.5)"#, "c");
+    assert_js_eq(r#""abc".at(3)"#, "undefined");
+}
+
+#[test]
+fn test_pad() {
+    assert_js_eq(r#""x".padStart(5, "ab")"#, "ababx");
+    assert_js_eq(r#""x".padEnd(3) + "|""#, "x  |");
+    assert_js_eq(r#""abc".padStart(-Infinity)"#, "abc");
+    assert_js_eq(r#""abc".padEnd(10, "")"#, "abc");
+    // Lengths beyond the heap throw instead of hanging
+    assert_js_error(r#""x".padStart(Infinity)"#);
+}
+
+#[test]
+fn test_string_methods_reject_null_receivers() {
+    assert_js_error("String.prototype.at.call(null, 0)");
+    assert_js_error("String.prototype.slice.call(undefined)");
+    assert_js_eq(r#""".at.call(123, 1)"#, "2");
+}
+
+#[test]
+fn test_string_concat_in_loop_collects_garbage() {
+    // Every intermediate string adds up to more than the heap; only the
+    // last one is live
+    let mut ctx = crabquick::Context::new(16 * 1024);
+    let code = r#"
+        var s = "";
+        for (var i = 0; i < 200; i++) s = s + "x";
+        s.length
+    "#;
+    let result = ctx.eval(code, "concat.js", 0).expect("loop should not run out of memory");
+    assert_eq!(result.to_int(), Some(200));
+
+    let stats = ctx.memory_stats();
+    assert!(stats.gc_count > 1, "expected several collections, got {}", stats.gc_count);
+    assert!(
+        stats.live_bytes < stats.heap_size / 2,
+        "live bytes {} should stay well below the {} byte heap",
+        stats.live_bytes,
+        stats.heap_size
+    );
+}
+
+#[test]
+fn test_failed_allocation_collects_and_retries() {
+    // Each 2 KB string is allocated in one step, past the collection
+    // threshold; it only fits once the previous one has been collected
+    let code = r#"
+        var big = "x";
+        for (var i = 0; i < 11; i++) big = big + big;
+        var n = 0;
+        for (var j = 0; j < 50; j++) { var s = big + j; n = n + s.length; }
+        n
+    "#;
+    let mut ctx = crabquick::Context::new(10 * 1024);
+    let result = ctx.eval(code, "retry.js", 0).expect("collection should make room");
+    assert_eq!(result.to_int(), Some(50 * 2048 + 10 + 40 * 2));
+    assert!(ctx.memory_stats().gc_count > 1);
+
+    // Too small even when everything dead is collected
+    let mut ctx = crabquick::Context::new(6 * 1024);
+    let err = ctx.eval(code, "retry.js", 0).unwrap_err();
+    assert!(err.to_string().contains("Out of memory"), "{}", err);
+}
+
+#[test]
+fn test_string_escapes() {
+    assert_js_eq(r#""a\tb".length"#, "3");
+    assert_js_eq(r#""\u0041\x42\u{43}""#, "ABC");
+    assert_js_eq(r#""\u{1F600}" === "\uD83D\uDE00""#, "true");
+    assert_js_eq(r#""\b\f\v\0".length"#, "4");
+    assert_js_eq("'one \\\ntwo'", "one two");
+    assert_js_eq(r#"`\u{48}i ${1 + 1}`"#, "Hi 2");
+
+    let mut engine = crabquick::Engine::new(65536);
+    let err = engine.eval_as_string("var s = 1;\nvar t = \"\\u{110000}\";").unwrap_err();
+    assert!(err.contains("code point above U+10FFFF") && err.contains("line 2, column 9"), "{}", err);
+    assert_js_error(r#""\x4g""#);
+    assert_js_error(r#""\12""#);
+    assert_js_error("\"unterminated");
+    assert_js_error("'broken\nline'");
+}
@@ -0,0 +1,74 @@
+//! Uint8Array
+//!
+//! Byte buffers with a fixed length. Elements read as numbers, stores wrap
+//! modulo 256, and indices past the end read as undefined and ignore
+//! writes. `subarray` returns a copy.
+
+#![cfg(test)]
+
+use crate::harness::*;
+use crabquick::Engine;
+
+#[test]
+fn test_uint8array_construction() {
+    assert_js_eq("new Uint8Array(4).length", "4");
+    assert_js_eq("new Uint8Array(3)[2]", "0");
+    assert_js_eq("new Uint8Array().length", "0");
+    assert_js_eq("new Uint8Array([1, 2, 3])[1]", "2");
+    assert_js_eq("new Uint8Array(new Uint8Array([7, 8]))[1]", "8");
+    assert_js_eq("var a = new Uint8Array({ length: 2, 0: 5 }); a[0] + ',' + a[1]", "5,0");
+    assert_js_eq("typeof new Uint8Array(1)", "object");
+    assert_js_error("new Uint8Array(-1)");
+}
+
+#[test]
+fn test_uint8array_element_access() {
+    let code = r#"
+        var a = new Uint8Array(4);
+        a[0] = 255;
+        a[1] = 256;
+        a[2] = -1;
+        a[3] = 3.7;
+        a[4] = 9;
+        a.length = 10;
+        a[0] + ',' + a[1] + ',' + a[2] + ',' + a[3] + ',' + a[4] + ',' + a.length
+    "#;
+    assert_js_eq(code, "255,0,255,3,undefined,4");
+    assert_js_eq("var a = new Uint8Array(2); a[1] = '7'; a[1] += 1; a[1]", "8");
+    assert_js_eq("var a = new Uint8Array(2); a[0] = 'x'; a[0]", "0");
+    assert_js_true("var a = new Uint8Array(2); (1 in a) && !(2 in a)");
+    assert_js_eq("var a = new Uint8Array([4, 5]); var s = 0; for (var k in a) s += a[k]; s", "9");
+    assert_js_eq("Object.keys(new Uint8Array(3)).length", "3");
+}
+
+#[test]
+fn test_uint8array_set() {
+    assert_js_eq("var a = new Uint8Array(4); a.set([1, 2], 1); a[0] + ',' + a[1] + ',' + a[2]", "0,1,2");
+    assert_js_eq("var a = new Uint8Array(3); a.set(new Uint8Array([9, 8])); a[1]", "8");
+    assert_js_eq("var a = new Uint8Array(2); a.set([300]); a[0]", "44");
+    assert_js_error("new Uint8Array(2).set([1, 2, 3])");
+    assert_js_error("new Uint8Array(2).set([1], 2)");
+    assert_js_error("new Uint8Array(2).set([1], -1)");
+}
+
+#[test]
+fn test_uint8array_subarray() {
+    let code = r#"
+        var a = new Uint8Array([1, 2, 3, 4, 5]);
+        var s = a.subarray(1, -1);
+        s[0] = 99;
+        s.length + ':' + s[0] + ':' + s[2] + ':' + a[1]
+    "#;
+    assert_js_eq(code, "3:99:4:2");
+    assert_js_eq("new Uint8Array([1, 2, 3]).subarray().length", "3");
+    assert_js_eq("new Uint8Array([1, 2, 3]).subarray(-2)[0]", "2");
+    assert_js_eq("new Uint8Array([1, 2, 3]).subarray(2, 1).length", "0");
+}
+
+#[cfg(feature = "builtin-console")]
+#[test]
+fn test_uint8array_console_log() {
+    let mut engine = Engine::new(65536);
+    let report = engine.eval_captured("console.log(new Uint8Array([1, 2, 3, 4]))");
+    assert_eq!(report.output, ["Uint8Array(4) [ 1, 2, 3, 4 ]"]);
+}
@@ -62,11 +62,50 @@ fn test_reassignment() {
 }
 
 #[test]
-#[ignore]
+fn test_chained_assignment() {
+    assert_js_eq("var x, y; var r = x = y = 5; [r, x, y].join()", "5,5,5");
+    assert_js_eq("var x, y, z; x = y = z = 0; [x, y, z].join()", "0,0,0");
+    assert_js_eq("var x, y; (x = y = 5) + ',' + x + ',' + y", "5,5,5");
+    assert_js_eq("let a; const b = a = 'v'; a + b", "vv");
+
+    // Locals, including ones a closure captured, and members
+    assert_js_eq("function f() { var a, b; a = b = 7; return a + b } f()", "14");
+    assert_js_eq(
+        "function f() { var c = 0, d; var get = function () { return c }; d = c = 3; return [d, c, get()].join() } f()",
+        "3,3,3",
+    );
+    assert_js_eq(
+        "function f() { var a = 0, b = 0; return function () { var r = a = b = 4; return [r, a, b].join() } } f()()",
+        "4,4,4",
+    );
+    assert_js_eq("var o = {}, p = []; var r = o.a = p[0] = o['b'] = 9; [r, o.a, p[0], o.b].join()", "9,9,9,9");
+    assert_js_eq("var p = [1, 2], i = 0; p[i] = p[i + 1] = 6; p.join()", "6,6");
+
+    // Compound operators chain too, each reading its target before the right side runs
+    assert_js_eq("var x = 1, y = 2; x += y *= 3; [x, y].join()", "7,6");
+    assert_js_eq("var s = 'a'; var t = s += s += 'b'; [s, t].join()", "aab,aab");
+
+    // Comma expressions evaluate left to right and give the last value
+    assert_js_eq("var x, y; var r = (x = 1, y = 2, x + y); [r, x, y].join()", "3,1,2");
+    assert_js_eq("var n = 0; n++, n++, n", "2");
+}
+
+#[test]
 fn test_undefined_variable() {
-    // Accessing undefined variable should return undefined or throw
-    // For now we test that it doesn't crash
-    let _ = run_js("x");
+    assert_js_error("x");
+    assert_js_eq("var r; try { x; } catch (e) { r = e.name + ': ' + e.message; } r", "ReferenceError: x is not defined");
+    assert_js_eq("typeof x", "undefined");
+}
+
+#[test]
+fn test_undefined_variable_suggestion() {
+    let message = |body: &str| format!("var r; try {{ {} }} catch (e) {{ r = e.message; }} r", body);
+    assert_js_eq(&message("var deviceId = 1; deviceID;"), "deviceID is not defined (did you mean 'deviceId'?)");
+    assert_js_eq(
+        &message("function f() { var count = 1; return cout; } f();"),
+        "cout is not defined (did you mean 'count'?)",
+    );
+    assert_js_eq(&message("var deviceId = 1; serialNumber;"), "serialNumber is not defined");
 }
 
 #[test]
@@ -95,3 +134,110 @@ fn test_global_scope() {
     "#;
     assert_js_eq(code, "100");
 }
+
+#[test]
+fn test_var_is_function_scoped() {
+    assert_js_eq("function f() { for (var i = 0; i < 3; i++) {} return i } f()", "3");
+    assert_js_eq("function g() { if (true) { var i = 3 } return i } g()", "3");
+    assert_js_eq("var k = 9; for (var k = 0; k < 3; k++) {} k", "3");
+    assert_js_eq("for (var key in { p: 1 }) {} key", "p");
+    assert_js_eq("function h() { for (var x of [1, 2]) {} try { var y = 'y' } catch (e) {} return x + y } h()", "2y");
+    // A var in a loop body keeps its value from one iteration to the next
+    assert_js_eq("function w() { for (var n = 0; n < 3; n++) { var m; m = (m || 0) + 1 } return m } w()", "3");
+    // let and const stay block scoped
+    assert_js_eq("function b() { { let z = 1; const c = 2 } return typeof z + typeof c } b()", "undefinedundefined");
+    assert_js_error("function d() { let q = 1; { var q = 2 } }");
+}
+
+#[test]
+fn test_array_destructuring() {
+    let code = r#"
+        var [a, , b, c] = [1, 2, 3];
+        function swap(pair) {
+            const [first, second] = pair;
+            return [second, first];
+        }
+        var [x, y] = swap([a, b]);
+        x + "," + y + "," + c
+    "#;
+    assert_js_eq(code, "3,1,undefined");
+}
+
+#[test]
+fn test_logical_assignment() {
+    assert_js_eq("var x = 0; x ||= 5; x", "5");
+    assert_js_eq("var x = 3; x ||= 5; x", "3");
+    assert_js_eq("var x = 3; x &&= 5; x", "5");
+    assert_js_eq("var x = null; var y = 0; x ??= 4; y ??= 9; x + ',' + y", "4,0");
+}
+
+#[test]
+fn test_logical_assignment_short_circuits() {
+    let code = r#"
+        var evaluated = 0;
+        function bump() { evaluated++; return 2; }
+        function run() {
+            let a = 1;
+            let b = null;
+            a ||= bump();
+            b &&= bump();
+            function inner() { b ??= bump(); return b; }
+            return inner();
+        }
+        run() + ',' + evaluated
+    "#;
+    assert_js_eq(code, "2,1");
+}
+
+#[test]
+fn test_compound_assignment() {
+    assert_js_eq("var x=10; x+=5; x", "15");
+    assert_js_eq("var o={n:1}; o.n*=3; o.n", "3");
+    assert_js_eq("var a=[1,2]; var i=1; a[i]+=5; a[1]", "7");
+    assert_js_eq("var x=2; x**=3; x-=1; x%=4; x", "3");
+    assert_js_eq("var x=5; x<<=2; x>>=1; x|=1; x&=7; x^=2; x", "1");
+    assert_js_eq("var x=-8; x>>>=28; x", "15");
+    assert_js_eq("function f() { var s='a'; s+='b'; return s += 1; } f()", "ab1");
+}
+
+#[test]
+fn test_compound_assignment_evaluates_target_once() {
+    let code = r#"
+        var calls = 0;
+        var o = { v: 1 };
+        function get() { calls++; return o; }
+        var k = 0;
+        var a = [10, 20];
+        var r = (get().v += 2) + (a[k++] -= 3);
+        test('result', function () { assertEquals(r, 10); });
+        test('member target', function () { assertEquals(o.v, 3); assertEquals(calls, 1, 'get() calls'); });
+        test('index target', function () { assertEquals(k, 1, 'k++ runs'); assertEquals(a[0], 7); });
+    "#;
+    assert_js_tests(code);
+}
+
+#[test]
+fn test_many_globals_in_small_heap() {
+    let mut ctx = crabquick::Context::new(65536);
+    crabquick::runtime::init_runtime(&mut ctx).unwrap();
+    let global = ctx.global_object();
+    let capacity = ctx.own_property_capacity(global);
+
+    let names: Vec<String> = (0..200).map(|i| format!("g{i}")).collect();
+    for (i, name) in names.iter().enumerate() {
+        ctx.eval(&format!("var {name} = {i};"), "g.js", 0).unwrap();
+    }
+    assert!(ctx.own_property_capacity(global) > capacity, "the global table never grew");
+    assert!(ctx.own_property_capacity(global) >= ctx.own_property_count(global));
+
+    let sum: i32 = names.chunks(10)
+        .map(|chunk| ctx.eval(&chunk.join(" + "), "sum.js", 0).unwrap().to_int().unwrap())
+        .sum();
+    assert_eq!(sum, 19900);
+    for (i, name) in names.iter().enumerate() {
+        let key = ctx.intern_atom(name);
+        assert_eq!(ctx.get_global_property(key).and_then(crabquick::JSValue::to_int), Some(i as i32), "{name}");
+    }
+    let after = ctx.eval("g6 = 'six'; typeof g200 + ',' + g6.length + ',' + (g199 - g0)", "more.js", 0).unwrap();
+    assert_eq!(ctx.get_string(after), Some("undefined,3,199"));
+}
@@ -6,7 +6,7 @@ extern crate alloc;
 use alloc::vec::Vec;
 
 use crabquick::{
-    Context,
+    Context, ErrorKind,
     bytecode::{BytecodeWriter, Instruction, Opcode},
 };
 
@@ -18,14 +18,20 @@ fn execute_bytecode(ctx: &mut Context, instructions: &[Instruction]) -> Result<c
         writer.emit(inst);
     }
     let code = writer.finish();
+    execute_code(ctx, &code)
+}
 
+/// Helper function to execute raw code bytes, which needn't decode
+fn execute_code(ctx: &mut Context, code: &[u8]) -> Result<crabquick::JSValue, crabquick::JSValue> {
     // Add headers
-    // Format: [const_count: u16][constants...][atom_count: u16][atoms...][func_count: u16][funcs...][bytecode...]
+    // Format: [const_count: u16][constants...][atom_count: u16][atoms...][func_count: u16][funcs...][lines_len: u16][lines...][hint_count: u16][hints...][bytecode...]
     let mut bytecode = Vec::new();
     bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 constants
     bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 atoms
     bytecode.extend_from_slice(&0u16.to_le_bytes()); // 0 functions
-    bytecode.extend_from_slice(&code);
+    bytecode.extend_from_slice(&0u16.to_le_bytes()); // no line table
+    bytecode.extend_from_slice(&0u16.to_le_bytes()); // no name hints
+    bytecode.extend_from_slice(code);
 
     // Allocate bytecode array
     let bc_index = ctx.alloc_byte_array(bytecode.len()).unwrap();
@@ -427,3 +433,54 @@ fn test_nop() {
     let result = execute_bytecode(&mut ctx, &instructions).unwrap();
     assert_eq!(result.to_int(), Some(42));
 }
+
+#[test]
+fn test_bad_jumps_and_truncated_code_throw() {
+    let mut ctx = Context::new(65536);
+    crabquick::runtime::init_runtime(&mut ctx).unwrap();
+
+    // A jump back past the start used to wrap around and end the script
+    let err = execute_bytecode(&mut ctx, &[
+        Instruction::new(Opcode::Push2),
+        Instruction::with_label(Opcode::Goto, -100),
+    ]).unwrap_err();
+    assert_eq!(ctx.error_kind(err), Some(ErrorKind::Internal));
+
+    let err = execute_bytecode(&mut ctx, &[Instruction::with_label(Opcode::Goto, 100)]).unwrap_err();
+    assert_eq!(ctx.error_kind(err), Some(ErrorKind::Internal));
+
+    // push_2, then if_false missing its offset
+    let err = execute_code(&mut ctx, &[Opcode::Push2 as u8, Opcode::IfFalse as u8, 0]).unwrap_err();
+    assert_eq!(ctx.error_kind(err), Some(ErrorKind::Internal));
+}
+
+#[test]
+fn test_locals_outside_the_frame_throw() {
+    let mut ctx = Context::new(65536);
+    crabquick::runtime::init_runtime(&mut ctx).unwrap();
+
+    // The script reserved no slots, so slot 3 would land among temporaries
+    // or past the top of the stack; writes used to pad the stack out to it
+    let err = execute_bytecode(&mut ctx, &[
+        Instruction::new(Opcode::Push1),
+        Instruction::with_u8(Opcode::PutLoc, 3),
+        Instruction::new(Opcode::ReturnUndef),
+    ]).unwrap_err();
+    assert_eq!(ctx.error_kind(err), Some(ErrorKind::Reference));
+
+    let err = execute_bytecode(&mut ctx, &[
+        Instruction::with_u8(Opcode::GetLoc, 0),
+        Instruction::new(Opcode::Return),
+    ]).unwrap_err();
+    assert_eq!(ctx.error_kind(err), Some(ErrorKind::Reference));
+
+    // Reserved slots still work
+    let result = execute_bytecode(&mut ctx, &[
+        Instruction::new(Opcode::Undefined),
+        Instruction::new(Opcode::Push2),
+        Instruction::with_u8(Opcode::PutLoc, 0),
+        Instruction::with_u8(Opcode::GetLoc, 0),
+        Instruction::new(Opcode::Return),
+    ]).unwrap();
+    assert_eq!(result.to_int(), Some(2));
+}
@@ -30,11 +30,10 @@ fn test_value_special() {
 }
 
 #[test]
-fn test_eval_stub() {
+fn test_eval() {
     let mut ctx = Context::new(8192);
-    let result = ctx.eval("2 + 2", "test.js", 0);
-    // For now, eval just returns undefined
-    assert!(result.is_undefined());
+    let result = ctx.eval("2 + 2", "test.js", 0).unwrap();
+    assert_eq!(result.to_int(), Some(4));
 }
 
 #[test]
@@ -59,7 +58,7 @@ fn test_gc_basic() {
     assert!(usage_before > 0);
 
     // Run GC (nothing is rooted, but compaction is not fully implemented yet)
-    ctx.gc();
+    ctx.gc().unwrap();
 
     // Memory is still allocated
     let usage_after = ctx.memory_usage();
@@ -81,7 +80,7 @@ fn test_gc_roots() {
     ctx.add_root(val);
 
     // Run GC
-    ctx.gc();
+    ctx.gc().unwrap();
 
     // Remove root
     ctx.remove_root(val);
@@ -0,0 +1,34 @@
+#!/bin/sh
+# Reports how much the optional builtins add to a release binary.
+#
+# Builds the CLI (without the REPL) twice: once with every `builtin-*`
+# feature compiled out and once with `full`, then prints both sizes and
+# the delta. Extra arguments are passed to cargo, e.g. `--target ...`.
+#
+#   tests/size_report.sh
+set -eu
+
+cd "$(dirname "$0")/.."
+
+target_dir=target/size-report
+
+build() {
+    name=$1
+    shift
+    cargo build --release --quiet --package crabquick-cli --no-default-features \
+        --target-dir "$target_dir/$name" "$@" >&2
+    find "$target_dir/$name" -path '*/release/crabquick' -type f | head -n 1
+}
+
+size_of() {
+    wc -c < "$1" | tr -d ' '
+}
+
+minimal_bin=$(build minimal "$@")
+full_bin=$(build full --features full "$@")
+
+minimal=$(size_of "$minimal_bin")
+full=$(size_of "$full_bin")
+
+printf '%-8s %10s bytes\n' minimal "$minimal" full "$full"
+printf '%-8s %10s bytes\n' delta "$((full - minimal))"